@@ -0,0 +1,185 @@
+use std::{cell::RefCell, rc::Weak};
+
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::{Element, Event, HtmlCanvasElement, HtmlElement};
+use wmc_core::marker::Marker;
+use wmc_render::log;
+
+use crate::{
+    error::{self, ComponentError},
+    events::{self, ClickTarget},
+    state::MapState,
+};
+
+/// Attribute holding the marker id on its button
+const MARKER_ATTRIBUTE: &str = "data-marker";
+
+/// Event name and callback of a listener on the list
+type Listener = (&'static str, Closure<dyn FnMut(Event)>);
+
+/// Marker buttons and announcements for assistive technology
+///
+/// The list lives in the canvas' fallback content, which isn't rendered but
+/// is exposed to screen readers and reachable with Tab. Each marker gets a
+/// button named by [`label`]. Focusing a button rings the marker on the map,
+/// and activating it with Enter or Space emits the same events as clicking
+/// the marker. Selections are announced through an `aria-live` region.
+/// Elements and listeners are removed on drop.
+pub struct MarkerList {
+    list: Element,
+    live: Element,
+    listeners: Vec<Listener>,
+}
+
+impl MarkerList {
+    /// Adds the list and live region to `canvas`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the elements can't be created or the
+    /// listeners can't be added
+    pub fn attach(
+        canvas: &HtmlCanvasElement,
+        state: &Weak<RefCell<MapState>>,
+    ) -> Result<Self, JsValue> {
+        let document = canvas
+            .owner_document()
+            .ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+        let list = document.create_element("ul")?;
+        list.set_attribute("aria-label", "Map markers")?;
+        let live = document.create_element("p")?;
+        live.set_attribute("aria-live", "polite")?;
+        canvas.append_child(&list)?;
+        canvas.append_child(&live)?;
+
+        // Focus changes while the map is borrowed, e.g. when a rebuild
+        // refocuses the same marker, are dropped
+        let listeners = vec![
+            (
+                "focusin",
+                listener(state, |state, id| {
+                    if let Ok(mut map) = state.try_borrow_mut() {
+                        map.set_focus(id.map(str::to_string));
+                    }
+                    Ok(())
+                }),
+            ),
+            (
+                "focusout",
+                listener(state, |state, _| {
+                    if let Ok(mut map) = state.try_borrow_mut() {
+                        map.set_focus(None);
+                    }
+                    Ok(())
+                }),
+            ),
+            (
+                "click",
+                listener(state, |state, id| {
+                    id.map_or(Ok(()), |id| events::click(state, ClickTarget::Marker(id)))
+                }),
+            ),
+        ];
+        for (event, callback) in &listeners {
+            list.add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())?;
+        }
+
+        Ok(Self {
+            list,
+            live,
+            listeners,
+        })
+    }
+
+    /// Rebuilds the buttons for `markers`, keeping keyboard focus on the
+    /// marker that had it
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the elements can't be created
+    pub fn sync<'a>(&self, markers: impl Iterator<Item = &'a Marker>) -> Result<(), JsValue> {
+        let document = self
+            .list
+            .owner_document()
+            .ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+        let focused = document
+            .active_element()
+            .filter(|element| self.list.contains(Some(element)))
+            .and_then(|element| element.get_attribute(MARKER_ATTRIBUTE));
+
+        self.list.set_text_content(None);
+        for marker in markers {
+            let id = marker.id.to_string();
+            let button = document.create_element("button")?;
+            button.set_attribute("type", "button")?;
+            button.set_attribute(MARKER_ATTRIBUTE, &id)?;
+            button.set_text_content(Some(&label(marker)));
+            let item = document.create_element("li")?;
+            item.append_child(&button)?;
+            self.list.append_child(&item)?;
+
+            if focused.as_deref() == Some(id.as_str()) {
+                button.dyn_into::<HtmlElement>()?.focus()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Has screen readers read `text` out when idle
+    pub fn announce(&self, text: &str) {
+        self.live.set_text_content(Some(text));
+    }
+}
+
+impl Drop for MarkerList {
+    fn drop(&mut self) {
+        for (event, callback) in &self.listeners {
+            let _ = self
+                .list
+                .remove_event_listener_with_callback(event, callback.as_ref().unchecked_ref());
+        }
+        self.list.remove();
+        self.live.remove();
+    }
+}
+
+/// Returns the accessible name of `marker`: its `meta.label` or `meta.name`,
+/// or its id, followed by its coordinates, e.g. `Paris, 48.86° N, 2.35° E`
+pub fn label(marker: &Marker) -> String {
+    let name = marker
+        .meta
+        .as_ref()
+        .and_then(|meta| {
+            ["label", "name"]
+                .iter()
+                .find_map(|key| meta.get(key)?.as_str())
+        })
+        .map_or_else(|| format!("Marker {}", marker.id), str::to_string);
+
+    format!("{name}, {}", marker.coord.format_hemisphere(2))
+}
+
+/// Wraps `handle` as a listener receiving the marker id of the event target
+fn listener(
+    state: &Weak<RefCell<MapState>>,
+    handle: impl Fn(&RefCell<MapState>, Option<&str>) -> Result<(), JsValue> + 'static,
+) -> Closure<dyn FnMut(Event)> {
+    let state = Weak::clone(state);
+    Closure::new(move |event: Event| {
+        // Fallback content events bubble to the canvas, whose listener would
+        // take a button click for a pointer click at its top left
+        if event.type_() == "click" {
+            event.stop_propagation();
+        }
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let id = event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok())
+            .and_then(|element| element.get_attribute(MARKER_ATTRIBUTE));
+        if let Err(e) = handle(&state, id.as_deref()) {
+            log::error("a11y", "Marker list event not handled", &[("error", e)]);
+        }
+    })
+}
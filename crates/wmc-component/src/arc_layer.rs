@@ -0,0 +1,123 @@
+use wmc_core::{
+    arc::Arc,
+    marker::Color,
+    projection::Projection,
+    svg::SvgDocument,
+    theme::{LineStyle, Theme},
+};
+use wmc_render::{ArcRenderer, Gl, RenderContext, RenderError};
+
+use crate::layer::{Layer, LayerFrame};
+
+/// Look of an [`ArcLayer`]
+#[derive(Debug, Clone, Copy)]
+pub struct ArcPaint {
+    /// Color of arcs without their own
+    pub color: Color,
+    /// Line width in CSS pixels
+    pub width: f32,
+    /// Dash pattern in CSS pixels
+    pub style: LineStyle,
+    /// Lift of the arc midpoints as a fraction of the projected arc length
+    pub elevation: f32,
+    /// Speed the dashes flow from origin to destination, in CSS pixels per
+    /// second
+    pub speed: f32,
+}
+
+/// Great-circle arcs between origin and destination pairs, e.g. flight
+/// routes, with dashes flowing towards the destinations
+pub struct ArcLayer {
+    arcs: Vec<Arc>,
+    paint: ArcPaint,
+    renderer: ArcRenderer,
+}
+
+impl ArcLayer {
+    /// Projects `arcs` and uploads their meshes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        arcs: Vec<Arc>,
+        projection: &dyn Projection,
+        paint: ArcPaint,
+    ) -> Result<Self, RenderError> {
+        let renderer = Self::build(ctx, &arcs, projection, paint)?;
+
+        Ok(Self {
+            arcs,
+            paint,
+            renderer,
+        })
+    }
+
+    fn build(
+        ctx: &RenderContext,
+        arcs: &[Arc],
+        projection: &dyn Projection,
+        paint: ArcPaint,
+    ) -> Result<ArcRenderer, RenderError> {
+        let color = paint.color;
+        ArcRenderer::new(
+            ctx,
+            arcs,
+            projection,
+            [color.r, color.g, color.b, color.a],
+            paint.elevation,
+        )
+    }
+}
+
+impl Layer for ArcLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        let renderer = Self::build(ctx, &self.arcs, projection, self.paint)?;
+        std::mem::replace(&mut self.renderer, renderer).delete(ctx.gl());
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let ctx = frame.ctx;
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let width = self.paint.width * pixel_ratio;
+        let style = self.paint.style.scaled(pixel_ratio);
+
+        // Wrapped to one dash period so the offset keeps its precision
+        let period = match style {
+            LineStyle::Solid => 0.0,
+            LineStyle::Dashed { dash, gap } => dash + gap,
+            LineStyle::Dotted { gap } => width + gap,
+        };
+        let flow = if period > 0.0 {
+            (ctx.camera().time * self.paint.speed * pixel_ratio).rem_euclid(period)
+        } else {
+            0.0
+        };
+
+        self.renderer.draw(ctx, width, style, flow);
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn is_animated(&self) -> bool {
+        self.paint.speed != 0.0 && self.paint.style != LineStyle::Solid && !self.arcs.is_empty()
+    }
+
+    fn export_svg(&self, svg: &mut SvgDocument, projection: &dyn Projection, _theme: &Theme) {
+        for arc in &self.arcs {
+            let color = arc.color.unwrap_or(self.paint.color);
+            for piece in arc.project(projection, f64::from(self.paint.elevation)) {
+                svg.polyline(&piece, color, self.paint.width, self.paint.style);
+            }
+        }
+    }
+}
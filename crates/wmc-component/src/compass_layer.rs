@@ -0,0 +1,167 @@
+use std::f32::consts::TAU;
+
+use wmc_core::{marker::Color, projection::Projection};
+use wmc_render::{Gl, RenderContext, RenderError, TextAnchor, TextRenderer, log};
+
+use crate::{
+    label_layer::LabelPaint,
+    layer::{Layer, LayerFrame, color_array},
+    options::ScaleBarCorner,
+};
+
+/// Straight segments approximating the dial
+const DIAL_SEGMENTS: usize = 24;
+
+/// Dial opacity, letting the map show through a little
+const DIAL_OPACITY: f32 = 0.85;
+
+/// Needle half length as a fraction of the dial radius
+const NEEDLE_LENGTH: f32 = 0.55;
+
+/// Needle half width as a fraction of the dial radius
+const NEEDLE_WIDTH: f32 = 0.18;
+
+/// Distance of the north letter from the center as a fraction of the dial
+/// radius
+const LETTER_DISTANCE: f32 = 0.78;
+
+/// Needle pointing to map north in a corner of the map, turning with the
+/// view's bearing
+///
+/// Clicking the dial turns the map back north up, see [`Layer::hit`].
+pub struct CompassLayer {
+    corner: ScaleBarCorner,
+    /// Dial radius in CSS pixels
+    radius: f32,
+    /// Letter and south needle color, with the dial in the halo color
+    paint: LabelPaint,
+    north_color: Color,
+    /// Dial center and radius in drawing buffer pixels
+    dial: ([f32; 2], f32),
+    renderer: TextRenderer,
+}
+
+impl CompassLayer {
+    /// Creates the text renderer for the CSS font family `font` and lays out
+    /// the compass
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        corner: ScaleBarCorner,
+        radius: f32,
+        paint: LabelPaint,
+        north_color: Color,
+        font: &str,
+    ) -> Result<Self, RenderError> {
+        let mut layer = Self {
+            corner,
+            radius,
+            paint,
+            north_color,
+            dial: ([0.0, 0.0], 0.0),
+            renderer: TextRenderer::new(ctx, font)?,
+        };
+        layer.place(ctx);
+        Ok(layer)
+    }
+
+    /// Lays out the dial `padding` pixels in from the corner, with the
+    /// needle along the view's north
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn place(&mut self, ctx: &RenderContext) {
+        self.renderer.clear();
+
+        let (width, height) = (ctx.width() as f32, ctx.height() as f32);
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let radius = self.radius * pixel_ratio;
+        let inset = self.paint.padding.mul_add(pixel_ratio, radius);
+        let x = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::BottomLeft => inset,
+            ScaleBarCorner::TopRight | ScaleBarCorner::BottomRight => width - inset,
+        };
+        let y = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::TopRight => inset,
+            ScaleBarCorner::BottomLeft | ScaleBarCorner::BottomRight => height - inset,
+        };
+        self.dial = ([x, y], radius);
+
+        let mut dial = color_array(self.paint.halo_color);
+        dial[3] *= DIAL_OPACITY;
+        let rim = |step: usize| {
+            let angle = TAU * step as f32 / DIAL_SEGMENTS as f32;
+            [
+                angle.cos().mul_add(radius, x),
+                angle.sin().mul_add(radius, y),
+            ]
+        };
+        for step in 0..DIAL_SEGMENTS {
+            self.renderer
+                .add_triangle([[x, y], rim(step), rim(step + 1)], dial);
+        }
+
+        // Map north turns clockwise with the view
+        let rotation = ctx.view().rotation;
+        let north = [rotation.sin(), -rotation.cos()];
+        let across = [-north[1], north[0]];
+        let along = |forward: f32, side: f32| {
+            [
+                (north[0] * forward).mul_add(radius, (across[0] * side).mul_add(radius, x)),
+                (north[1] * forward).mul_add(radius, (across[1] * side).mul_add(radius, y)),
+            ]
+        };
+        let (left, right) = (along(0.0, -NEEDLE_WIDTH), along(0.0, NEEDLE_WIDTH));
+        self.renderer.add_triangle(
+            [along(NEEDLE_LENGTH, 0.0), left, right],
+            color_array(self.north_color),
+        );
+        self.renderer.add_triangle(
+            [along(-NEEDLE_LENGTH, 0.0), right, left],
+            color_array(self.paint.color),
+        );
+
+        let style = self.paint.style(pixel_ratio, TextAnchor::Center);
+        self.renderer.add("N", along(LETTER_DISTANCE, 0.0), &style);
+    }
+}
+
+impl Layer for CompassLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx);
+        Ok(())
+    }
+
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Compass skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn hit(&self, point: [f32; 2]) -> bool {
+        let ([x, y], radius) = self.dial;
+        (point[0] - x).hypot(point[1] - y) <= radius
+    }
+}
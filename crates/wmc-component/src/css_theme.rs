@@ -0,0 +1,148 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::{Element, MediaQueryList, MutationObserver, MutationObserverInit};
+use wmc_core::theme::Theme;
+use wmc_render::log;
+
+use crate::{
+    error::{self, MapError},
+    state::MapState,
+};
+
+/// Theme colors taken from CSS custom properties on the canvas, e.g.
+/// `--wmc-background`
+///
+/// Custom properties inherit, so they can be set on the canvas or any
+/// ancestor, typically next to an application's own dark or light mode
+/// variables. The properties are read again whenever a `class`, `style` or
+/// `data-theme` attribute changes anywhere in the document or the preferred
+/// color scheme flips, and the theme is rebuilt if any value changed.
+pub struct CssTheme {
+    /// Theme the properties override
+    base: Theme,
+    element: Element,
+    /// Property values last applied, in [`Theme::CSS_PROPERTIES`] order
+    values: Vec<String>,
+    observer: MutationObserver,
+    media: Option<MediaQueryList>,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl CssTheme {
+    /// Starts watching `element` for changes of the properties overriding
+    /// `base`
+    ///
+    /// The theme isn't applied until the first [`MapState::refresh_css_theme`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the observers can't be registered
+    pub fn watch(
+        state: &Rc<RefCell<MapState>>,
+        element: Element,
+        base: Theme,
+    ) -> Result<Self, JsValue> {
+        let weak: Weak<RefCell<MapState>> = Rc::downgrade(state);
+        let callback = Closure::<dyn FnMut()>::new(move || {
+            let Some(state) = weak.upgrade() else {
+                return;
+            };
+            // Attribute changes made while the map itself is borrowed are
+            // caught up with on the next change
+            let Ok(mut state) = state.try_borrow_mut() else {
+                return;
+            };
+            if let Err(e) = state.refresh_css_theme() {
+                log::error("theme", "CSS theme not applied", &[("error", e)]);
+            }
+        });
+
+        let observer = MutationObserver::new(callback.as_ref().unchecked_ref())?;
+        if let Some(root) = element
+            .owner_document()
+            .and_then(|doc| doc.document_element())
+        {
+            let init = MutationObserverInit::new();
+            init.set_attributes(true);
+            init.set_subtree(true);
+            init.set_attribute_filter(&js_sys::Array::of3(
+                &"class".into(),
+                &"style".into(),
+                &"data-theme".into(),
+            ));
+            observer.observe_with_options(&root, &init)?;
+        }
+
+        let media = web_sys::window()
+            .map(|window| window.match_media("(prefers-color-scheme: dark)"))
+            .transpose()?
+            .flatten();
+        if let Some(media) = &media {
+            media.add_event_listener_with_callback("change", callback.as_ref().unchecked_ref())?;
+        }
+
+        Ok(Self {
+            base,
+            element,
+            values: Vec::new(),
+            observer,
+            media,
+            callback,
+        })
+    }
+
+    /// Replaces the theme the properties override
+    pub fn set_base(&mut self, base: Theme) {
+        self.base = base;
+        self.values.clear();
+    }
+
+    /// Returns the base theme with the current property values applied, or
+    /// `None` if no value changed since the last call
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if a property holds an unsupported color
+    pub fn resolve(&mut self) -> Result<Option<Theme>, JsValue> {
+        let Some(style) = web_sys::window()
+            .map(|window| window.get_computed_style(&self.element))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        let values: Vec<String> = Theme::CSS_PROPERTIES
+            .iter()
+            .map(|name| style.get_property_value(name))
+            .collect::<Result<_, _>>()?;
+        if values == self.values {
+            return Ok(None);
+        }
+
+        let mut theme = self.base.clone();
+        for (name, value) in Theme::CSS_PROPERTIES.iter().zip(&values) {
+            theme.set_css_property(name, value).map_err(|e| {
+                error::to_js(MapError::from(e).context(&format!("Invalid {name}")))
+            })?;
+        }
+        self.values = values;
+        Ok(Some(theme))
+    }
+}
+
+impl Drop for CssTheme {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        if let Some(media) = &self.media {
+            let _ = media.remove_event_listener_with_callback(
+                "change",
+                self.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
@@ -0,0 +1,38 @@
+/// Reasons the next loop frame has to be drawn
+///
+/// The render loop skips draw calls while every flag is clear and no
+/// time-based animation is active, so an idle map costs no GPU time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirtyFlags {
+    /// Canvas size, pixel ratio or projection changed
+    pub viewport: bool,
+    /// Layer geometry, visibility or styling changed
+    pub scene: bool,
+    /// Marker set changed
+    pub markers: bool,
+}
+
+impl DirtyFlags {
+    /// Returns flags with everything marked dirty
+    pub const fn all() -> Self {
+        Self {
+            viewport: true,
+            scene: true,
+            markers: true,
+        }
+    }
+
+    /// Returns true if any flag is set
+    pub const fn any(self) -> bool {
+        self.viewport || self.scene || self.markers
+    }
+
+    /// Clears all flags after a frame has been drawn
+    pub const fn clear(&mut self) {
+        *self = Self {
+            viewport: false,
+            scene: false,
+            markers: false,
+        };
+    }
+}
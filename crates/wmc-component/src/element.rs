@@ -0,0 +1,346 @@
+use std::{
+    cell::{Cell, RefCell},
+    num::ParseFloatError,
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{JsCast, prelude::*};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Element, HtmlCanvasElement, ResizeObserver, Response};
+use wmc_core::theme::Theme;
+use wmc_render::log;
+
+use crate::{
+    WorldMap,
+    error::{self, ComponentError},
+    events::{self, EventEmitter, MapEvent},
+    global, parse_markers,
+    state::MapState,
+};
+
+/// Tag name the element is registered under by default
+const DEFAULT_TAG: &str = "world-map";
+
+/// Attributes the element reacts to after it's connected
+const OBSERVED_ATTRIBUTES: [&str; 4] = ["theme", "markers", "markers-url", "zoom-snap"];
+
+#[wasm_bindgen(inline_js = r"
+export function defineElement(name, observed, connect) {
+    if (typeof customElements === 'undefined' || customElements.get(name)) {
+        return;
+    }
+    customElements.define(name, class extends HTMLElement {
+        static get observedAttributes() {
+            return observed;
+        }
+
+        connectedCallback() {
+            if (!this.style.display) {
+                this.style.display = 'block';
+            }
+            if (!this.wmc) {
+                this.wmc = connect(this);
+            }
+        }
+
+        disconnectedCallback() {
+            // Moving the element disconnects and reconnects it in one task
+            queueMicrotask(() => {
+                if (!this.isConnected && this.wmc) {
+                    this.wmc.disconnect();
+                    this.wmc = null;
+                }
+            });
+        }
+
+        attributeChangedCallback(name, _previous, value) {
+            this.wmc?.attributeChanged(name, value);
+        }
+
+        ready() {
+            return this.wmc
+                ? this.wmc.ready()
+                : Promise.reject(new Error('Element is not connected'));
+        }
+    });
+}
+")]
+extern "C" {
+    #[wasm_bindgen(js_name = defineElement)]
+    fn define_element(name: &str, observed: js_sys::Array, connect: &JsValue);
+}
+
+/// Registers the `<world-map>` custom element, or one named `tag`
+///
+/// ```html
+/// <world-map theme="dark-minimal" markers-url="/api/markers"></world-map>
+/// ```
+///
+/// Each connected element gets its own canvas and map, sized to the element
+/// and resized with it; the map is destroyed when the element leaves the
+/// document. Supported attributes:
+///
+/// - `theme`: a preset name from [`Theme::PRESETS`] or a theme as JSON
+/// - `markers`: a JSON array of markers
+/// - `markers-url`: a URL to fetch a JSON array of markers from
+/// - `css-theme`: present to follow `--wmc-*` custom properties, see
+///   [`crate::MapOptions::css_theme`]; read once on connect
+/// - `options`: [`crate::MapOptions`] as JSON; read once on connect
+/// - `zoom-snap`: the zoom step views settle on, e.g. `1` for integer
+///   zooms, see [`WorldMap::set_zoom_snap`]; continuous when absent
+///
+/// The element's `ready()` method returns a promise resolving once the map
+/// and its initial `markers-url` fetch are loaded. Attribute errors are
+/// logged to the console and emitted as `error` events. Registering a tag
+/// twice, or outside a window, does nothing.
+#[wasm_bindgen(js_name = defineWorldMapElement)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn define_world_map_element(tag: Option<String>) {
+    let observed: js_sys::Array = OBSERVED_ATTRIBUTES
+        .iter()
+        .map(|&name| JsValue::from_str(name))
+        .collect();
+    let connect = Closure::<dyn FnMut(Element) -> JsValue>::new(|element: Element| {
+        match WorldMapElement::connect(element) {
+            Ok(handle) => handle.into(),
+            Err(e) => {
+                log::error("element", "Element connect failed", &[("error", e)]);
+                JsValue::NULL
+            },
+        }
+    });
+
+    define_element(
+        tag.as_deref().unwrap_or(DEFAULT_TAG),
+        observed,
+        connect.as_ref(),
+    );
+    // The element class calls back for every instance, for the lifetime of
+    // the page
+    connect.forget();
+}
+
+/// Map behind one connected `<world-map>` element
+#[wasm_bindgen]
+pub struct WorldMapElement {
+    element: Element,
+    canvas: HtmlCanvasElement,
+    map: WorldMap,
+    observer: ResizeObserver,
+    _on_resize: Closure<dyn FnMut()>,
+    /// Incremented per markers request, so only the latest response lands
+    markers_request: Rc<Cell<u32>>,
+}
+
+#[wasm_bindgen]
+impl WorldMapElement {
+    /// Applies a changed attribute, `None` meaning it was removed
+    #[wasm_bindgen(js_name = attributeChanged)]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn attribute_changed(&self, name: &str, value: Option<String>) {
+        if let Err(e) = self.apply(name, value.as_deref()) {
+            log::error(
+                "element",
+                "Attribute not applied",
+                &[("attribute", name.into()), ("error", e.clone())],
+            );
+            let events = Rc::clone(&self.map.state.borrow().events);
+            events.emit_error(&e);
+        }
+    }
+
+    /// Returns a promise resolving once the map is ready, see
+    /// [`WorldMap::ready`]
+    pub fn ready(&self) -> js_sys::Promise {
+        self.map.ready()
+    }
+
+    /// Stops observing the element and destroys the map and its canvas
+    pub fn disconnect(self) {
+        self.observer.disconnect();
+        self.markers_request
+            .set(self.markers_request.get().wrapping_add(1));
+        self.map.destroy();
+        self.canvas.remove();
+    }
+}
+
+impl WorldMapElement {
+    /// Creates the canvas and map inside `element` and applies its
+    /// attributes
+    fn connect(element: Element) -> Result<Self, JsValue> {
+        let document = element
+            .owner_document()
+            .ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+        let canvas: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+        canvas.set_attribute("style", "display: block; width: 100%; height: 100%")?;
+        element.append_child(&canvas)?;
+
+        let mut options: serde_json::Value = match element.get_attribute("options") {
+            Some(json) => serde_json::from_str(&json).map_err(|e| {
+                error::to_js(ComponentError::AttributeParseError {
+                    attribute: "options".to_string(),
+                    value: e.to_string(),
+                })
+            })?,
+            None => serde_json::Value::Object(serde_json::Map::new()),
+        };
+        if element.has_attribute("css-theme")
+            && let Some(object) = options.as_object_mut()
+        {
+            object.insert("cssTheme".to_string(), true.into());
+        }
+
+        let map = match WorldMap::new(&canvas, Some(options.to_string())) {
+            Ok(map) => map,
+            Err(e) => {
+                canvas.remove();
+                return Err(e);
+            },
+        };
+
+        let on_resize = resize_callback(element.clone(), Rc::downgrade(&map.state));
+        let observer = ResizeObserver::new(on_resize.as_ref().unchecked_ref())?;
+        observer.observe(&element);
+
+        let handle = Self {
+            element,
+            canvas,
+            map,
+            observer,
+            _on_resize: on_resize,
+            markers_request: Rc::new(Cell::new(0)),
+        };
+        for name in OBSERVED_ATTRIBUTES {
+            if let Some(value) = handle.element.get_attribute(name) {
+                handle.attribute_changed(name, Some(value));
+            }
+        }
+        handle.map.start()?;
+
+        Ok(handle)
+    }
+
+    fn apply(&self, name: &str, value: Option<&str>) -> Result<(), JsValue> {
+        match (name, value) {
+            ("theme", Some(value)) => Theme::from_name(value.trim()).map_or_else(
+                || self.map.set_theme(value),
+                |theme| self.map.state.borrow_mut().set_theme(theme),
+            ),
+            ("theme", None) => self.map.state.borrow_mut().set_theme(Theme::dark_minimal()),
+            ("markers", Some(json)) => self.map.set_markers(json),
+            ("markers-url", Some(url)) => {
+                self.fetch_markers(url.to_string());
+                Ok(())
+            },
+            ("markers" | "markers-url", None) => {
+                self.map.clear_markers();
+                Ok(())
+            },
+            ("zoom-snap", Some(value)) => {
+                let step = value.trim().parse().map_err(|e: ParseFloatError| {
+                    error::to_js(ComponentError::AttributeParseError {
+                        attribute: name.to_string(),
+                        value: format!("{value}: {e}"),
+                    })
+                })?;
+                self.map.set_zoom_snap(step)
+            },
+            ("zoom-snap", None) => self.map.set_zoom_snap(0.0),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fetches markers from `url` and shows them unless a newer request was
+    /// made in the meantime
+    fn fetch_markers(&self, url: String) {
+        let request = self.markers_request.get().wrapping_add(1);
+        self.markers_request.set(request);
+        let latest = Rc::clone(&self.markers_request);
+        let state = Rc::downgrade(&self.map.state);
+        let readiness = Rc::clone(&self.map.state.borrow().readiness);
+        let events = Rc::clone(&self.map.state.borrow().events);
+        readiness.begin();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            let markers = fetch_text(&url, &events)
+                .await
+                .and_then(|json| parse_markers(&json));
+            let state = state.upgrade().filter(|_| latest.get() == request);
+            let Some(state) = state else {
+                // A superseded request doesn't hold up readiness
+                readiness.finish(Ok(()));
+                return;
+            };
+
+            let shown = markers.and_then(|markers| {
+                #[allow(clippy::cast_precision_loss)]
+                let count = markers.len() as f64;
+                state.borrow_mut().set_markers(markers)?;
+                events.emit_progress("upload", count, Some(count));
+                Ok(())
+            });
+            readiness.finish(shown.as_ref().copied());
+            if let Err(e) = shown {
+                log::error(
+                    "fetch",
+                    "Markers not loaded",
+                    &[("url", url.as_str().into()), ("error", e.clone())],
+                );
+                events.emit_error(&e);
+            }
+        });
+    }
+}
+
+/// Resizes the map to the element's CSS size on every observed change
+fn resize_callback(element: Element, state: Weak<RefCell<MapState>>) -> Closure<dyn FnMut()> {
+    Closure::new(move || {
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let width = u32::try_from(element.client_width()).unwrap_or(0);
+        let height = u32::try_from(element.client_height()).unwrap_or(0);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        // A resize while the map is borrowed is caught up with on the next one
+        let Ok(mut map) = state.try_borrow_mut() else {
+            return;
+        };
+        let resized = map.resize(width, height);
+        let events = Rc::clone(&map.events);
+        drop(map);
+
+        let emitted = resized.and_then(|()| {
+            let detail = events::detail(&[("width", width.into()), ("height", height.into())])?;
+            events.emit(MapEvent::ViewChange, &detail);
+            Ok(())
+        });
+        if let Err(e) = emitted {
+            log::error("frame", "Resize failed", &[("error", e)]);
+        }
+    })
+}
+
+/// Fetches `url` as text, emitting `download` progress to `events`
+#[allow(clippy::future_not_send)]
+async fn fetch_text(url: &str, events: &EventEmitter) -> Result<String, JsValue> {
+    let window =
+        web_sys::window().ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+    let response: Response = JsFuture::from(window.fetch_with_str(url))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(error::to_js(ComponentError::MarkerUrlFetchFailed {
+            url: url.to_string(),
+            status: response.status(),
+        }));
+    }
+
+    global::read_text(&response, |loaded, total| {
+        events.emit_progress("download", loaded, total);
+    })
+    .await
+}
@@ -1,9 +1,21 @@
 use std::fmt;
 
+use js_sys::{JSON, Reflect};
 use masterror::AppError;
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+use wmc_core::error::CoreError;
+use wmc_render::RenderError;
+
+/// Code of errors raised by the browser or without a typed source
+const INTERNAL: &str = "internal";
 
 /// Component-level errors
-#[derive(Debug, Clone)]
+///
+/// Serializes as `{code, details}`, `code` being the variant name in
+/// snake case and `details` its fields
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
 pub enum ComponentError {
     /// Failed to parse attribute
     AttributeParseError {
@@ -19,6 +31,20 @@ pub enum ComponentError {
         /// HTTP status code
         status: u16,
     },
+    /// Input passed to the component is malformed
+    ParseFailed {
+        /// What was parsed, e.g. `Markers`
+        input: String,
+        /// Parser error description
+        reason: String,
+    },
+    /// A name doesn't match any known value
+    UnknownName {
+        /// What was named, e.g. `event`
+        kind: String,
+        /// The unknown name
+        name: String,
+    },
     /// Component not mounted
     ComponentNotMounted,
     /// Rendering error
@@ -37,6 +63,8 @@ impl fmt::Display for ComponentError {
             Self::MarkerUrlFetchFailed { url, status } => {
                 write!(f, "Failed to fetch markers from {url}: HTTP {status}")
             },
+            Self::ParseFailed { input, reason } => write!(f, "{input} parse failed: {reason}"),
+            Self::UnknownName { kind, name } => write!(f, "Unknown {kind}: {name}"),
             Self::ComponentNotMounted => write!(f, "Component not mounted"),
             Self::RenderError { details } => write!(f, "Render error: {details}"),
         }
@@ -50,3 +78,131 @@ impl From<ComponentError> for AppError {
         Self::internal(err.to_string())
     }
 }
+
+/// Error shape surfaced to JavaScript
+///
+/// Every error the component returns or emits converts into a JS `Error`
+/// named `MapError` with extra `code` and `details` properties.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapError {
+    /// Stable snake case identifier, e.g. `theme_validation_failed`, or
+    /// `internal` for errors raised by the browser
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+    /// The error's fields, or `null`
+    pub details: serde_json::Value,
+}
+
+impl MapError {
+    /// Creates an error without a typed source
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self {
+            code: INTERNAL.to_string(),
+            message: message.into(),
+            details: serde_json::Value::Null,
+        }
+    }
+
+    /// Prefixes the message with `context`
+    #[must_use]
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{context}: {}", self.message);
+        self
+    }
+
+    /// Builds the shape from an error serialized as `{code, details}`
+    fn typed<E: Serialize + fmt::Display>(error: &E) -> Self {
+        let message = error.to_string();
+        let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(error) else {
+            return Self::internal(message);
+        };
+        let code = fields
+            .remove("code")
+            .and_then(|code| code.as_str().map(str::to_string))
+            .unwrap_or_else(|| INTERNAL.to_string());
+
+        Self {
+            code,
+            message,
+            details: fields.remove("details").unwrap_or_default(),
+        }
+    }
+
+    /// Returns `error` as a `MapError` object, wrapping errors raised by the
+    /// browser and plain strings as `internal`
+    #[must_use]
+    pub fn normalize(error: &JsValue) -> JsValue {
+        let structured =
+            Reflect::get(error, &JsValue::from_str("code")).is_ok_and(|code| code.is_string());
+        if structured {
+            return error.clone();
+        }
+
+        let message = error.as_string().unwrap_or_else(|| {
+            error
+                .dyn_ref::<js_sys::Error>()
+                .map_or_else(|| format!("{error:?}"), |e| String::from(e.message()))
+        });
+        Self::internal(message).into()
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<ComponentError> for MapError {
+    fn from(err: ComponentError) -> Self {
+        Self::typed(&err)
+    }
+}
+
+impl From<CoreError> for MapError {
+    fn from(err: CoreError) -> Self {
+        Self::typed(&err)
+    }
+}
+
+impl From<RenderError> for MapError {
+    fn from(err: RenderError) -> Self {
+        Self::typed(&err)
+    }
+}
+
+/// Serialization failures are internal: the component only serializes its
+/// own types
+impl From<serde_json::Error> for MapError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::internal(err.to_string())
+    }
+}
+
+impl From<MapError> for JsValue {
+    fn from(err: MapError) -> Self {
+        let error = js_sys::Error::new(&err.message);
+        error.set_name("MapError");
+        let details = serde_json::to_string(&err.details)
+            .ok()
+            .and_then(|json| JSON::parse(&json).ok())
+            .unwrap_or(Self::NULL);
+        // Setting a property on a fresh `Error` can't fail
+        let _ = Reflect::set(&error, &Self::from_str("code"), &Self::from_str(&err.code));
+        let _ = Reflect::set(&error, &Self::from_str("details"), &details);
+        error.into()
+    }
+}
+
+/// Converts a typed error into a JS `MapError` object
+pub fn to_js(err: impl Into<MapError>) -> JsValue {
+    err.into().into()
+}
+
+/// Like [`to_js`], prefixing the message with `context`
+pub fn to_js_in<E: Into<MapError>>(context: &str) -> impl FnOnce(E) -> JsValue + '_ {
+    move |err| err.into().context(context).into()
+}
@@ -0,0 +1,744 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::{HtmlCanvasElement, MouseEvent, PointerEvent, WheelEvent};
+use wmc_core::{
+    marker::Marker,
+    projection::{ProjectedCoord, Projection},
+};
+use wmc_render::{View, log};
+
+use crate::{
+    accessibility,
+    error::{self, MapError},
+    gestures::Gestures,
+    global, layer,
+    options::Interactions,
+    state::MapState,
+};
+
+/// Event a host can subscribe to with [`crate::WorldMap::on`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapEvent {
+    /// The canvas was clicked; detail `{lat, lon, x, y}` with `x` and `y` in
+    /// CSS pixels from the canvas' top left
+    Click,
+    /// A marker was clicked; detail `{marker, lat, lon, x, y}`
+    MarkerClick,
+    /// The canvas was right-clicked or long-pressed on a touch screen;
+    /// detail `{lat, lon, x, y, marker, country}`, `marker` being the marker
+    /// under the pointer and `country` the properties of the world feature
+    /// there, each `null` if there is none
+    ContextMenu,
+    /// The pointer moved over the canvas; detail `{lat, lon, x, y}`, or
+    /// `null` once the pointer leaves the canvas
+    PointerMove,
+    /// The viewport was resized; detail `{width, height}` in CSS pixels
+    ViewChange,
+    /// A world topology finished loading; no detail
+    Load,
+    /// The selected marker changed through a marker click or the URL hash;
+    /// detail `{id, marker}`, `marker` being `null` if no shown marker has
+    /// the id and both `null` once the selection is cleared
+    Select,
+    /// The selected marker changed in any way, including through
+    /// [`crate::WorldMap::select_marker`] and
+    /// [`crate::WorldMap::clear_selection`]; detail as for
+    /// [`MapEvent::Select`]
+    SelectionChange,
+    /// A load made progress; detail `{stage, loaded, total}`, see
+    /// [`EventEmitter::emit_progress`]
+    Progress,
+    /// An asynchronous operation failed; detail the
+    /// [`crate::error::MapError`] object with `code`, `message` and `details`
+    Error,
+}
+
+impl MapEvent {
+    /// Parses an event name as passed to [`crate::WorldMap::on`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "click" => Some(Self::Click),
+            "markerclick" => Some(Self::MarkerClick),
+            "mapcontextmenu" => Some(Self::ContextMenu),
+            "pointermove" => Some(Self::PointerMove),
+            "viewchange" => Some(Self::ViewChange),
+            "load" => Some(Self::Load),
+            "select" => Some(Self::Select),
+            "selectionchange" => Some(Self::SelectionChange),
+            "progress" => Some(Self::Progress),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Host callbacks by event, called in registration order
+#[derive(Default)]
+pub struct EventEmitter {
+    listeners: RefCell<Vec<(MapEvent, Function)>>,
+}
+
+impl EventEmitter {
+    /// Registers `callback` for `event`
+    pub fn on(&self, event: MapEvent, callback: Function) {
+        self.listeners.borrow_mut().push((event, callback));
+    }
+
+    /// Removes `callback` from `event`, or every callback of `event` with
+    /// `None`
+    pub fn off(&self, event: MapEvent, callback: Option<&Function>) {
+        self.listeners.borrow_mut().retain(|(other, registered)| {
+            *other != event || callback.is_some_and(|callback| callback != registered)
+        });
+    }
+
+    /// Returns true if a callback is registered for `event`
+    pub fn has_listeners(&self, event: MapEvent) -> bool {
+        self.listeners
+            .borrow()
+            .iter()
+            .any(|(other, _)| *other == event)
+    }
+
+    /// Removes every callback
+    pub fn clear(&self) {
+        self.listeners.borrow_mut().clear();
+    }
+
+    /// Calls every callback of `event` with `detail`
+    ///
+    /// Callbacks may register or remove others and call back into the map,
+    /// so the caller must not hold a borrow of the map state. Errors thrown
+    /// by a callback are logged and don't stop the others.
+    pub fn emit(&self, event: MapEvent, detail: &JsValue) {
+        let callbacks: Vec<Function> = self
+            .listeners
+            .borrow()
+            .iter()
+            .filter(|(other, _)| *other == event)
+            .map(|(_, callback)| callback.clone())
+            .collect();
+
+        for callback in callbacks {
+            if let Err(e) = callback.call1(&JsValue::NULL, detail) {
+                log::error("event", "Event callback threw", &[("error", e)]);
+            }
+        }
+    }
+
+    /// Emits [`MapEvent::Progress`] for a load `stage`:
+    ///
+    /// - `download`: bytes of a marker source received, `total` being
+    ///   `null` without a `Content-Length`
+    /// - `parse`: bytes of a world topology parsed
+    /// - `upload`: features or markers uploaded to the GPU
+    /// - `tiles`: tiles of the current batch loaded
+    pub fn emit_progress(&self, stage: &str, loaded: f64, total: Option<f64>) {
+        let fields = [
+            ("stage", JsValue::from_str(stage)),
+            ("loaded", loaded.into()),
+            ("total", total.map_or(JsValue::NULL, JsValue::from)),
+        ];
+        if let Ok(detail) = detail(&fields) {
+            self.emit(MapEvent::Progress, &detail);
+        }
+    }
+
+    /// Emits [`MapEvent::Error`] with `error` as a structured error
+    pub fn emit_error(&self, error: &JsValue) {
+        self.emit(MapEvent::Error, &MapError::normalize(error));
+    }
+}
+
+/// Builds an event detail object from `fields`
+///
+/// # Errors
+///
+/// Returns `JsValue` error if a field can't be set
+pub fn detail(fields: &[(&str, JsValue)]) -> Result<JsValue, JsValue> {
+    let object = Object::new();
+    for (key, value) in fields {
+        Reflect::set(&object, &JsValue::from_str(key), value)?;
+    }
+    Ok(object.into())
+}
+
+/// Emits [`MapEvent::Select`] and [`MapEvent::SelectionChange`] for a
+/// selection the user made, see [`emit_selection_change`]
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the marker can't be serialized or the detail
+/// can't be built
+pub fn emit_select(state: &RefCell<MapState>) -> Result<(), JsValue> {
+    let (events, detail) = selection_detail(state)?;
+    events.emit(MapEvent::Select, &detail);
+    events.emit(MapEvent::SelectionChange, &detail);
+    Ok(())
+}
+
+/// Emits [`MapEvent::SelectionChange`] for the current selection,
+/// announcing it to screen readers while the accessible marker list is on
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the marker can't be serialized or the detail
+/// can't be built
+pub fn emit_selection_change(state: &RefCell<MapState>) -> Result<(), JsValue> {
+    let (events, detail) = selection_detail(state)?;
+    events.emit(MapEvent::SelectionChange, &detail);
+    Ok(())
+}
+
+/// Builds the `{id, marker}` detail of the current selection, announcing it
+/// to screen readers while the accessible marker list is on
+fn selection_detail(state: &RefCell<MapState>) -> Result<(Rc<EventEmitter>, JsValue), JsValue> {
+    let (events, id, marker) = {
+        let state = state.borrow();
+        let marker = state
+            .selected
+            .as_deref()
+            .and_then(|id| state.layers.find_marker(id));
+        if let Some(list) = &state.marker_list {
+            list.announce(&marker.map_or_else(
+                || "Selection cleared".to_string(),
+                |marker| format!("Selected {}", accessibility::label(marker)),
+            ));
+        }
+        let marker = marker
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(error::to_js_in("Marker serialization failed"))?;
+        (Rc::clone(&state.events), state.selected.clone(), marker)
+    };
+
+    let marker = marker.map_or(Ok(JsValue::NULL), |json| js_sys::JSON::parse(&json))?;
+    let id = id.map_or(JsValue::NULL, |id| JsValue::from_str(&id));
+    Ok((events, detail(&[("id", id), ("marker", marker)])?))
+}
+
+/// Time a touch must be held to count as a long press, in milliseconds
+const LONG_PRESS_MS: i32 = 500;
+
+/// Distance a held touch may drift before it stops counting as a long
+/// press, in CSS pixels
+const LONG_PRESS_SLOP: f64 = 10.0;
+
+/// DOM mouse or pointer event callback
+type MouseCallback = Closure<dyn FnMut(MouseEvent)>;
+
+/// Pointer listeners on the canvas emitting [`MapEvent::Click`],
+/// [`MapEvent::MarkerClick`], [`MapEvent::ContextMenu`] and
+/// [`MapEvent::PointerMove`], moving the coordinate readout and driving
+/// [`Gestures`]
+///
+/// Pressed pointers are captured by the canvas, so a drag keeps moving the
+/// map outside it, and a press that dragged doesn't click.
+///
+/// Touch screens have no secondary click, so a primary touch held still for
+/// half a second emits [`MapEvent::ContextMenu`] too, and the click it ends
+/// with is dropped. Touches are only timed while a context menu callback is
+/// registered, and moving or lifting the finger earlier, or the browser
+/// taking the touch over for scrolling, cancels the press.
+///
+/// The listeners hold a weak reference to the map and are removed on drop.
+pub struct PointerListener {
+    canvas: HtmlCanvasElement,
+    /// Callbacks by DOM event name
+    callbacks: Vec<(&'static str, MouseCallback)>,
+    long_press: Rc<LongPress>,
+    _on_long_press: Closure<dyn FnMut()>,
+}
+
+/// Touch press timed to detect a long press
+#[derive(Default)]
+struct LongPress {
+    /// Timer of the pending press and where it started, in CSS pixels
+    pending: Cell<Option<(i32, [f64; 2])>>,
+    /// The last press emitted a context menu, so its click is dropped
+    fired: Cell<bool>,
+}
+
+impl LongPress {
+    /// Cancels the pending press, returning true if there was one
+    fn cancel(&self) -> bool {
+        self.pending.take().is_some_and(|(timer, _)| {
+            global::clear_timeout(timer);
+            true
+        })
+    }
+}
+
+impl PointerListener {
+    /// Starts listening for clicks, secondary clicks and pointer moves on
+    /// `canvas`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if a listener can't be added
+    pub fn attach(
+        canvas: HtmlCanvasElement,
+        state: &Weak<RefCell<MapState>>,
+    ) -> Result<Self, JsValue> {
+        let long_press = Rc::new(LongPress::default());
+        let on_long_press = {
+            let (press, state) = (Rc::clone(&long_press), state.clone());
+            Closure::<dyn FnMut()>::new(move || {
+                let (Some((_, [x, y])), Some(state)) = (press.pending.take(), state.upgrade())
+                else {
+                    return;
+                };
+                match context_menu(&state, x, y) {
+                    Ok(fired) => press.fired.set(fired),
+                    Err(e) => log::error("event", "Long press not handled", &[("error", e)]),
+                }
+            })
+        };
+        let timeout: Function = on_long_press.as_ref().unchecked_ref::<Function>().clone();
+        let gestures = Rc::new(Gestures::default());
+        let mut listener = Self {
+            canvas,
+            callbacks: Vec::new(),
+            long_press: Rc::clone(&long_press),
+            _on_long_press: on_long_press,
+        };
+
+        let (press, gesture) = (Rc::clone(&long_press), Rc::clone(&gestures));
+        listener.listen(state, "click", move |state, event| {
+            let dragged = gesture.take_dragged();
+            if press.fired.take() || dragged {
+                return;
+            }
+            let target = ClickTarget::Point(event.offset_x(), event.offset_y());
+            if let Err(e) = click(state, target) {
+                log::error("event", "Click not handled", &[("error", e)]);
+            }
+        })?;
+        let press = Rc::clone(&long_press);
+        listener.listen(state, "contextmenu", move |state, event| {
+            // Some mobile browsers open their own menu on long presses too
+            if press.fired.get() {
+                event.prevent_default();
+                return;
+            }
+            let touch = press.cancel();
+            match context_menu(state, event.offset_x(), event.offset_y()) {
+                // The host shows its own menu instead of the browser's
+                Ok(true) => {
+                    event.prevent_default();
+                    press.fired.set(touch);
+                },
+                Ok(false) => {},
+                Err(e) => log::error("event", "Context menu not handled", &[("error", e)]),
+            }
+        })?;
+        let press = Rc::clone(&long_press);
+        listener.listen(state, "pointerdown", move |state, event| {
+            press.cancel();
+            press.fired.set(false);
+            let touch = event
+                .dyn_ref::<PointerEvent>()
+                .is_some_and(|event| event.pointer_type() == "touch" && event.is_primary());
+            if !touch || !wants_context_menu(state) {
+                return;
+            }
+            match global::set_timeout_after(&timeout, LONG_PRESS_MS) {
+                Ok(timer) => press
+                    .pending
+                    .set(Some((timer, [event.offset_x(), event.offset_y()]))),
+                Err(e) => log::error("event", "Long press not timed", &[("error", e)]),
+            }
+        })?;
+        listener.listen_gestures(state, gestures)?;
+        let press = Rc::clone(&long_press);
+        listener.listen(state, "pointermove", move |_, event| {
+            if let Some((_, [x, y])) = press.pending.get()
+                && (event.offset_x() - x).hypot(event.offset_y() - y) > LONG_PRESS_SLOP
+            {
+                press.cancel();
+            }
+        })?;
+        for name in ["pointerup", "pointercancel"] {
+            let press = Rc::clone(&long_press);
+            listener.listen(state, name, move |_, _| {
+                press.cancel();
+            })?;
+        }
+        listener.listen(state, "pointermove", |state, event| {
+            if let Err(e) = pointer_move(state, Some((event.offset_x(), event.offset_y()))) {
+                log::error("event", "Pointer move not handled", &[("error", e)]);
+            }
+        })?;
+        let press = long_press;
+        listener.listen(state, "pointerleave", move |state, _| {
+            press.cancel();
+            if let Err(e) = pointer_move(state, None) {
+                log::error("event", "Pointer move not handled", &[("error", e)]);
+            }
+        })?;
+
+        Ok(listener)
+    }
+
+    /// Returns the canvas listened on
+    pub const fn canvas(&self) -> &HtmlCanvasElement {
+        &self.canvas
+    }
+
+    /// Leaves touches on the canvas to the browser's scrolling and zooming
+    /// while no gesture is enabled, and takes them over otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the canvas style can't be set
+    pub fn follow(&self, interactions: Interactions) -> Result<(), JsValue> {
+        let style = self.canvas.style();
+        if interactions.gestures() {
+            style.set_property("touch-action", "none")
+        } else {
+            style.remove_property("touch-action").map(drop)
+        }
+    }
+
+    /// Adds the listeners driving `gestures`
+    fn listen_gestures(
+        &mut self,
+        state: &Weak<RefCell<MapState>>,
+        gestures: Rc<Gestures>,
+    ) -> Result<(), JsValue> {
+        let (gesture, canvas) = (Rc::clone(&gestures), self.canvas.clone());
+        self.listen(state, "pointerdown", move |state, event| {
+            if let Some(event) = event.dyn_ref::<PointerEvent>()
+                && gesture.down(state, event)
+            {
+                let _ = canvas.set_pointer_capture(event.pointer_id());
+            }
+        })?;
+        let gesture = Rc::clone(&gestures);
+        self.listen(state, "pointermove", move |state, event| {
+            let Some(event) = event.dyn_ref::<PointerEvent>() else {
+                return;
+            };
+            if let Err(e) = gesture.moved(state, event) {
+                log::error("event", "Gesture not handled", &[("error", e)]);
+            }
+        })?;
+        for name in ["pointerup", "pointercancel"] {
+            let gesture = Rc::clone(&gestures);
+            self.listen(state, name, move |state, event| {
+                let Some(event) = event.dyn_ref::<PointerEvent>() else {
+                    return;
+                };
+                if let Err(e) = gesture.up(state, event) {
+                    log::error("event", "Gesture not handled", &[("error", e)]);
+                }
+            })?;
+        }
+        self.listen(state, "wheel", move |state, event| {
+            let Some(event) = event.dyn_ref::<WheelEvent>() else {
+                return;
+            };
+            match gestures.wheel(state, event) {
+                Ok(true) => event.prevent_default(),
+                Ok(false) => {},
+                Err(e) => log::error("event", "Wheel not handled", &[("error", e)]),
+            }
+        })
+    }
+
+    /// Adds a listener for the DOM event `name` calling `handle` while the
+    /// map is alive
+    fn listen(
+        &mut self,
+        state: &Weak<RefCell<MapState>>,
+        name: &'static str,
+        handle: impl Fn(&RefCell<MapState>, &MouseEvent) + 'static,
+    ) -> Result<(), JsValue> {
+        let state = state.clone();
+        let callback = MouseCallback::new(move |event: MouseEvent| {
+            if let Some(state) = state.upgrade() {
+                handle(&state, &event);
+            }
+        });
+        self.canvas
+            .add_event_listener_with_callback(name, callback.as_ref().unchecked_ref())?;
+        self.callbacks.push((name, callback));
+        Ok(())
+    }
+}
+
+impl Drop for PointerListener {
+    fn drop(&mut self) {
+        self.long_press.cancel();
+        for (name, callback) in &self.callbacks {
+            let _ = self
+                .canvas
+                .remove_event_listener_with_callback(name, callback.as_ref().unchecked_ref());
+        }
+    }
+}
+
+/// What a click landed on
+#[derive(Debug, Clone, Copy)]
+pub enum ClickTarget<'a> {
+    /// Pointer position in CSS pixels from the canvas' top left
+    Point(f64, f64),
+    /// Marker with this id, activated from the keyboard and clicked at its
+    /// center
+    Marker(&'a str),
+}
+
+/// Emits the click, and a marker click if it hit a marker, as far as they
+/// are enabled
+///
+/// With marker clicks enabled the click also selects the marker hit, or
+/// clears the selection.
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the marker can't be serialized or an event
+/// detail can't be built
+#[allow(clippy::cast_possible_truncation)]
+pub fn click(state: &RefCell<MapState>, target: ClickTarget<'_>) -> Result<(), JsValue> {
+    if let ClickTarget::Point(x, y) = target
+        && compass_click(state, x, y)?
+    {
+        return Ok(());
+    }
+
+    let (events, coord, [x, y], marker, interactions) = {
+        // A click while the map is borrowed can't be resolved and is dropped
+        let Ok(state) = state.try_borrow() else {
+            return Ok(());
+        };
+        let interactions = state.interactions;
+        if !interactions.any() {
+            return Ok(());
+        }
+        let projection = state.projection();
+        let (point, position, marker) = match target {
+            ClickTarget::Point(x, y) => {
+                let (point, marker) = pick(&state, &projection, x, y);
+                (point, [x, y], marker)
+            },
+            ClickTarget::Marker(id) => {
+                let Some(marker) = state.layers.find_marker(id) else {
+                    return Ok(());
+                };
+                let point = projection.project(marker.coord);
+                let pixel_ratio = state.ctx.pixel_ratio();
+                let [x, y] = state.ctx.to_screen([point.x as f32, point.y as f32]);
+                let position = [f64::from(x) / pixel_ratio, f64::from(y) / pixel_ratio];
+                (point, position, Some(marker))
+            },
+        };
+        let marker = marker
+            .filter(|_| interactions.marker_click)
+            .map(|marker| serde_json::to_string(marker).map(|json| (marker.id.to_string(), json)))
+            .transpose()
+            .map_err(error::to_js_in("Marker serialization failed"))?;
+
+        (
+            Rc::clone(&state.events),
+            projection.unproject(point),
+            position,
+            marker,
+            interactions,
+        )
+    };
+
+    let position = [
+        ("lat", JsValue::from(coord.lat)),
+        ("lon", JsValue::from(coord.lon)),
+        ("x", JsValue::from(x)),
+        ("y", JsValue::from(y)),
+    ];
+    if interactions.click {
+        events.emit(MapEvent::Click, &detail(&position)?);
+    }
+    let selected = match marker {
+        Some((id, marker)) => {
+            let marker = js_sys::JSON::parse(&marker)?;
+            let mut fields = vec![("marker", marker)];
+            fields.extend(position);
+            events.emit(MapEvent::MarkerClick, &detail(&fields)?);
+            Some(id)
+        },
+        None => None,
+    };
+
+    if interactions.marker_click && state.borrow_mut().select(selected)? {
+        emit_select(state)?;
+    }
+    Ok(())
+}
+
+/// Turns the map back north up if `x`, `y` in CSS pixels is on the
+/// compass, returning true if it was
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the layers can't follow the view
+#[allow(clippy::cast_possible_truncation)]
+fn compass_click(state: &RefCell<MapState>, x: f64, y: f64) -> Result<bool, JsValue> {
+    let Ok(mut state) = state.try_borrow_mut() else {
+        return Ok(false);
+    };
+    let pixel_ratio = state.ctx.pixel_ratio();
+    let point = [(x * pixel_ratio) as f32, (y * pixel_ratio) as f32];
+    if state.layers.hit(layer::COMPASS, point) != Some(true) {
+        return Ok(false);
+    }
+
+    let view = View {
+        rotation: 0.0,
+        ..state.ctx.view()
+    };
+    state.set_view(view)?;
+    Ok(true)
+}
+
+/// Emits [`MapEvent::ContextMenu`] for a secondary click or long press at
+/// `x`, `y` in CSS pixels from the canvas' top left
+///
+/// Returns false without emitting if context menus are disabled or nothing
+/// listens for them, so the browser's menu is left alone.
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the marker or country can't be serialized or
+/// the detail can't be built
+pub fn context_menu(state: &RefCell<MapState>, x: f64, y: f64) -> Result<bool, JsValue> {
+    let (events, coord, marker, country) = {
+        let Ok(state) = state.try_borrow() else {
+            return Ok(false);
+        };
+        if !wants_context_menu_in(&state) {
+            return Ok(false);
+        }
+        let projection = state.projection();
+        let (point, marker) = pick(&state, &projection, x, y);
+        let coord = projection.unproject(point);
+        let marker = marker
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(error::to_js_in("Marker serialization failed"))?;
+        let country = state
+            .layers
+            .feature_at(layer::WORLD, coord)
+            .map(|feature| serde_json::to_string(&feature.properties))
+            .transpose()
+            .map_err(error::to_js_in("Feature serialization failed"))?;
+        (Rc::clone(&state.events), coord, marker, country)
+    };
+
+    let parse =
+        |json: Option<String>| json.map_or(Ok(JsValue::NULL), |json| js_sys::JSON::parse(&json));
+    let fields = [
+        ("lat", JsValue::from(coord.lat)),
+        ("lon", JsValue::from(coord.lon)),
+        ("x", JsValue::from(x)),
+        ("y", JsValue::from(y)),
+        ("marker", parse(marker)?),
+        ("country", parse(country)?),
+    ];
+    events.emit(MapEvent::ContextMenu, &detail(&fields)?);
+    Ok(true)
+}
+
+/// Returns true if context menus are enabled and listened for, so presses
+/// should be timed
+fn wants_context_menu(state: &RefCell<MapState>) -> bool {
+    state
+        .try_borrow()
+        .is_ok_and(|state| wants_context_menu_in(&state))
+}
+
+fn wants_context_menu_in(state: &MapState) -> bool {
+    state.interactions.context_menu && state.events.has_listeners(MapEvent::ContextMenu)
+}
+
+/// Moves the coordinate readout to the pointer at `x`, `y` in CSS pixels
+/// from the canvas' top left, or clears it with `None`, and emits
+/// [`MapEvent::PointerMove`]
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the detail can't be built
+pub fn pointer_move(
+    state: &RefCell<MapState>,
+    position: Option<(f64, f64)>,
+) -> Result<(), JsValue> {
+    let (events, position) = {
+        // A move while the map is borrowed is dropped, the next one catches up
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return Ok(());
+        };
+        let state = &mut *state;
+        let position = position.map(|(x, y)| {
+            let coord = state.projection().unproject(map_point(state, x, y));
+            (coord, x, y)
+        });
+        let coord = position.map(|(coord, _, _)| coord);
+        if state
+            .layers
+            .set_pointer(layer::COORDINATE_READOUT, &state.ctx, coord)
+            == Some(true)
+        {
+            state.dirty.scene = true;
+        }
+        if !state.events.has_listeners(MapEvent::PointerMove) {
+            return Ok(());
+        }
+        (Rc::clone(&state.events), position)
+    };
+
+    let detail = match position {
+        Some((coord, x, y)) => detail(&[
+            ("lat", JsValue::from(coord.lat)),
+            ("lon", JsValue::from(coord.lon)),
+            ("x", JsValue::from(x)),
+            ("y", JsValue::from(y)),
+        ])?,
+        None => JsValue::NULL,
+    };
+    events.emit(MapEvent::PointerMove, &detail);
+    Ok(())
+}
+
+/// Returns the map point at `x`, `y` in CSS pixels and the marker drawn
+/// there
+#[allow(clippy::cast_possible_truncation)]
+fn pick<'a>(
+    state: &'a MapState,
+    projection: &dyn Projection,
+    x: f64,
+    y: f64,
+) -> (ProjectedCoord, Option<&'a Marker>) {
+    let point = map_point(state, x, y);
+    let marker = state.layers.pick_marker(
+        projection,
+        [point.x as f32, point.y as f32],
+        state.ctx.pixel_ratio() as f32,
+    );
+    (point, marker)
+}
+
+/// Returns the point in map pixels shown at `x`, `y` in CSS pixels from the
+/// canvas' top left, through the view
+#[allow(clippy::cast_possible_truncation)]
+fn map_point(state: &MapState, x: f64, y: f64) -> ProjectedCoord {
+    let pixel_ratio = state.ctx.pixel_ratio();
+    let [x, y] = state
+        .ctx
+        .to_map([(x * pixel_ratio) as f32, (y * pixel_ratio) as f32]);
+    ProjectedCoord {
+        x: f64::from(x),
+        y: f64::from(y),
+    }
+}
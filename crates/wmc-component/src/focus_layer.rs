@@ -0,0 +1,124 @@
+use wmc_core::{
+    marker::{Color, Marker},
+    projection::Projection,
+    theme::Theme,
+};
+use wmc_render::{Gl, RenderContext, RenderError, TextRenderer, log};
+
+use crate::layer::{Layer, LayerFrame, color_array};
+
+/// Ring thickness in CSS pixels
+const RING_WIDTH: f32 = 2.0;
+
+/// Space between the marker and the ring in CSS pixels
+const RING_GAP: f32 = 4.0;
+
+/// Halo width around the ring in CSS pixels
+const HALO_WIDTH: f32 = 1.5;
+
+/// Square focus ring around the marker focused from the keyboard
+///
+/// The ring contrasts with the theme background and has a halo in the
+/// background color, so it stays visible over any basemap.
+pub struct FocusLayer {
+    marker: Option<Marker>,
+    color: Color,
+    halo_color: Color,
+    renderer: TextRenderer,
+}
+
+impl FocusLayer {
+    /// Creates the layer without a focused marker
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(ctx: &RenderContext, theme: &Theme) -> Result<Self, RenderError> {
+        Ok(Self {
+            marker: None,
+            color: theme.background.contrasting(),
+            halo_color: theme.background,
+            renderer: TextRenderer::new(ctx, "sans-serif")?,
+        })
+    }
+
+    /// Lays out the ring around the focused marker, if any
+    #[allow(clippy::cast_possible_truncation)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        self.renderer.clear();
+        let Some(marker) = &self.marker else {
+            return;
+        };
+
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let center = projection.project(marker.coord);
+        let (x, y) = (center.x as f32, center.y as f32);
+        let inner = (marker.radius + RING_GAP) * pixel_ratio;
+        let outer = RING_WIDTH.mul_add(pixel_ratio, inner);
+        let sides = [
+            [x - outer, y - outer, x + outer, y - inner],
+            [x - outer, y + inner, x + outer, y + outer],
+            [x - outer, y - inner, x - inner, y + inner],
+            [x + inner, y - inner, x + outer, y + inner],
+        ];
+
+        let halo = HALO_WIDTH * pixel_ratio;
+        for [x0, y0, x1, y1] in sides {
+            self.renderer.add_box(
+                [x0 - halo, y0 - halo, x1 + halo, y1 + halo],
+                color_array(self.halo_color),
+            );
+        }
+        for bounds in sides {
+            self.renderer.add_box(bounds, color_array(self.color));
+        }
+    }
+}
+
+impl Layer for FocusLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Focus ring skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn set_theme(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        theme: &Theme,
+    ) -> Result<(), RenderError> {
+        self.color = theme.background.contrasting();
+        self.halo_color = theme.background;
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn focus_marker(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        marker: Option<&Marker>,
+    ) -> bool {
+        self.marker = marker.cloned();
+        self.place(ctx, projection);
+        true
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use js_sys::{
+    Array, Float32Array, Float64Array, Function, Int32Array, Object, Reflect, Uint32Array,
+};
+use wasm_bindgen::{JsCast, closure::Closure, prelude::*};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker};
+use wmc_core::{
+    projection::MercatorProjection,
+    simplify::LineDetail,
+    topology::{PackedLines, WorldTopology},
+};
+use wmc_render::{LineMesh, log};
+
+use crate::{
+    error::{self, ComponentError, MapError},
+    layer,
+};
+
+/// `type` of geometry requests and replies, telling them apart from the
+/// worker script's own messages
+const MESSAGE_TYPE: &str = "wmcGeometry";
+
+/// Builds the world line mesh a map with a geometry worker asks for, and
+/// posts it back with its buffers transferred
+///
+/// Call it from the message handler of a module worker that loads this
+/// package, see [`crate::WorldMap::set_geometry_worker`]. Messages that
+/// aren't geometry requests are left to the script and return false.
+///
+/// ```js
+/// import init, { handleGeometryMessage } from "./wmc_component.js";
+///
+/// const ready = init();
+/// self.onmessage = async ({ data }) => {
+///   await ready;
+///   handleGeometryMessage(data);
+/// };
+/// ```
+///
+/// A request failing to parse is answered with the error, so the map's
+/// `loadWorld` rejects with it.
+///
+/// # Errors
+///
+/// Returns `JsValue` error outside a dedicated worker or if the reply can't
+/// be posted
+#[wasm_bindgen(js_name = handleGeometryMessage)]
+pub fn handle_geometry_message(data: &JsValue) -> Result<bool, JsValue> {
+    if get(data, "type")?.as_string().as_deref() != Some(MESSAGE_TYPE) {
+        return Ok(false);
+    }
+    let scope = js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .map_err(|_| MapError::internal("Geometry requests are handled in a dedicated worker"))?;
+    let id = get(data, "id")?;
+
+    let reply = Object::new();
+    set(&reply, "type", &MESSAGE_TYPE.into())?;
+    set(&reply, "id", &id)?;
+    let transfer = Array::new();
+    match build_world(data) {
+        Ok(world) => world.write(&reply, &transfer)?,
+        Err(e) => set(&reply, "error", &e)?,
+    }
+
+    scope.post_message_with_transfer(&reply, &transfer)?;
+    Ok(true)
+}
+
+/// Parses, simplifies and projects the topology of a request
+fn build_world(request: &JsValue) -> Result<WorldLines, JsValue> {
+    let geojson = get(request, "geojson")?.as_string().unwrap_or_default();
+    let size = |key| {
+        get(request, key)?
+            .as_f64()
+            .filter(|value| *value >= 1.0)
+            .ok_or_else(|| {
+                error::to_js(ComponentError::AttributeParseError {
+                    attribute: key.to_string(),
+                    value: "not a drawing buffer size".to_string(),
+                })
+            })
+    };
+    let (width, height) = (size("width")?, size("height")?);
+
+    let topology = WorldTopology::from_geojson(&geojson).map_err(|e| {
+        error::to_js(ComponentError::ParseFailed {
+            input: "Topology".to_string(),
+            reason: e.to_string(),
+        })
+    })?;
+    let detail = LineDetail::new(&topology);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (width, height) = (width as u32, height as u32);
+    let mesh = LineMesh::build(
+        &topology,
+        &MercatorProjection::new(f64::from(width), f64::from(height)),
+        Some((&detail, layer::line_tolerance(width, height))),
+    );
+
+    Ok(WorldLines {
+        topology,
+        detail,
+        mesh,
+        width,
+        height,
+    })
+}
+
+/// World topology with its line mesh, built for a drawing buffer size
+pub struct WorldLines {
+    pub topology: WorldTopology,
+    pub detail: LineDetail,
+    pub mesh: LineMesh,
+    /// Drawing buffer width the mesh was projected for
+    pub width: u32,
+    /// Drawing buffer height the mesh was projected for
+    pub height: u32,
+}
+
+impl WorldLines {
+    /// Writes the lines into a reply as typed arrays, adding their buffers
+    /// to `transfer`
+    fn write(&self, reply: &Object, transfer: &Array) -> Result<(), JsValue> {
+        let packed = PackedLines::pack(&self.topology);
+        let arrays: [(&str, JsValue); 8] = [
+            (
+                "coords",
+                Float64Array::from(packed.coords.as_slice()).into(),
+            ),
+            ("lines", Uint32Array::from(packed.lines.as_slice()).into()),
+            (
+                "features",
+                Uint32Array::from(packed.features.as_slice()).into(),
+            ),
+            (
+                "importance",
+                Float32Array::from(self.detail.to_flat().as_slice()).into(),
+            ),
+            (
+                "vertices",
+                Float32Array::from(self.mesh.vertices.as_slice()).into(),
+            ),
+            (
+                "ranges",
+                Int32Array::from(self.mesh.ranges.as_flattened()).into(),
+            ),
+            (
+                "bounds",
+                Float32Array::from(self.mesh.bounds.as_flattened()).into(),
+            ),
+            (
+                "size",
+                Uint32Array::from([self.width, self.height].as_slice()).into(),
+            ),
+        ];
+        for (key, array) in arrays {
+            transfer.push(&get(&array, "buffer")?);
+            set(reply, key, &array)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the lines from a reply
+    fn read(reply: &JsValue) -> Result<Self, JsValue> {
+        let f64s = |key| Ok::<_, JsValue>(get(reply, key)?.dyn_into::<Float64Array>()?.to_vec());
+        let f32s = |key| Ok::<_, JsValue>(get(reply, key)?.dyn_into::<Float32Array>()?.to_vec());
+        let u32s = |key| Ok::<_, JsValue>(get(reply, key)?.dyn_into::<Uint32Array>()?.to_vec());
+        let i32s = |key| Ok::<_, JsValue>(get(reply, key)?.dyn_into::<Int32Array>()?.to_vec());
+
+        let packed = PackedLines {
+            coords: f64s("coords")?,
+            lines: u32s("lines")?,
+            features: u32s("features")?,
+        };
+        let topology = packed.unpack().map_err(error::to_js)?;
+        let detail = LineDetail::from_flat(&f32s("importance")?, &packed.lines);
+        let ranges = i32s("ranges")?.as_chunks::<2>().0.to_vec();
+        let bounds = f32s("bounds")?.as_chunks::<4>().0.to_vec();
+        let size = u32s("size")?;
+        let [width, height] = size
+            .as_slice()
+            .try_into()
+            .map_err(|_| MapError::internal("Geometry reply without a drawing buffer size"))?;
+
+        Ok(Self {
+            topology,
+            detail,
+            mesh: LineMesh {
+                vertices: f32s("vertices")?,
+                ranges,
+                bounds,
+            },
+            width,
+            height,
+        })
+    }
+}
+
+/// Resolve and reject callbacks of a request awaiting its reply
+type Pending = (u32, Function, Function);
+
+/// Main thread side of a geometry worker, see [`handle_geometry_message`]
+///
+/// Replies are matched to requests by id. The message listener is removed
+/// on drop; the worker itself belongs to the host.
+pub struct GeometryWorker {
+    worker: Worker,
+    next_id: Cell<u32>,
+    pending: Rc<RefCell<Vec<Pending>>>,
+    on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl GeometryWorker {
+    /// Starts listening to replies from `worker`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the listener can't be added
+    pub fn attach(worker: Worker) -> Result<Self, JsValue> {
+        let pending: Rc<RefCell<Vec<Pending>>> = Rc::default();
+        let replies = Rc::clone(&pending);
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let data = event.data();
+            if get(&data, "type")
+                .ok()
+                .and_then(|kind| kind.as_string())
+                .as_deref()
+                != Some(MESSAGE_TYPE)
+            {
+                return;
+            }
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let id = get(&data, "id")
+                .ok()
+                .and_then(|id| id.as_f64())
+                .map(|id| id as u32);
+            let request = {
+                let mut pending = replies.borrow_mut();
+                let position = pending
+                    .iter()
+                    .position(|(request, ..)| Some(*request) == id);
+                position.map(|position| pending.swap_remove(position))
+            };
+            let Some((_, resolve, reject)) = request else {
+                return;
+            };
+
+            let result = match get(&data, "error") {
+                Ok(error) if !error.is_undefined() => reject.call1(&JsValue::NULL, &error),
+                _ => resolve.call1(&JsValue::NULL, &data),
+            };
+            if let Err(e) = result {
+                log::error("topology", "Geometry reply not delivered", &[("error", e)]);
+            }
+        });
+        worker.add_event_listener_with_callback("message", on_message.as_ref().unchecked_ref())?;
+
+        Ok(Self {
+            worker,
+            next_id: Cell::new(0),
+            pending,
+            on_message,
+        })
+    }
+
+    /// Has the worker parse, simplify and project `geojson` for a `width` x
+    /// `height` drawing buffer
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the request can't be posted, the worker
+    /// fails to parse the topology, or the reply is malformed
+    #[allow(clippy::future_not_send)]
+    pub async fn build_world(
+        &self,
+        geojson: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<WorldLines, JsValue> {
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1));
+
+        let request = Object::new();
+        set(&request, "type", &MESSAGE_TYPE.into())?;
+        set(&request, "id", &id.into())?;
+        set(&request, "geojson", &geojson.into())?;
+        set(&request, "width", &width.into())?;
+        set(&request, "height", &height.into())?;
+
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            self.pending.borrow_mut().push((id, resolve, reject));
+        });
+        if let Err(e) = self.worker.post_message(&request) {
+            self.pending
+                .borrow_mut()
+                .retain(|(request, ..)| *request != id);
+            return Err(e);
+        }
+
+        let reply = JsFuture::from(promise).await?;
+        WorldLines::read(&reply)
+    }
+}
+
+impl Drop for GeometryWorker {
+    fn drop(&mut self) {
+        let _ = self.worker.remove_event_listener_with_callback(
+            "message",
+            self.on_message.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+fn get(target: &JsValue, key: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(target, &key.into())
+}
+
+fn set(target: &Object, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    Reflect::set(target, &key.into(), value).map(|_| ())
+}
@@ -0,0 +1,301 @@
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::JsValue;
+use web_sys::{PointerEvent, WheelEvent};
+use wmc_render::View;
+
+use crate::{options::Interactions, state::MapState};
+
+/// Distance a pressed pointer may move before it drags the map, in CSS
+/// pixels
+const DRAG_SLOP: f64 = 4.0;
+
+/// Zoom steps per pixel the wheel scrolls
+const WHEEL_ZOOM_PER_PIXEL: f64 = 1.0 / 300.0;
+
+/// Pixels a wheel line scrolls, for wheels reporting lines
+const WHEEL_LINE: f64 = 40.0;
+
+/// Pixels a wheel page scrolls, for wheels reporting pages
+const WHEEL_PAGE: f64 = 800.0;
+
+/// Highest zoom gestures reach
+const MAX_ZOOM: f32 = 24.0;
+
+/// Pan, pinch zoom, twist rotation and wheel zoom of the map, as far as
+/// [`Interactions`] enables them
+///
+/// One pointer pans, or rotates about the center of the canvas with Shift
+/// held. Two pointers pan with their midpoint, zoom with their distance
+/// and rotate with their angle. The view follows unsnapped while pointers
+/// are down and settles on the zoom snap once the last one is lifted.
+#[derive(Default)]
+pub struct Gestures {
+    /// Pressed pointers by id, at their last position in CSS pixels
+    pointers: RefCell<Vec<(i32, [f64; 2])>>,
+    /// The press moved past [`DRAG_SLOP`], so its click is dropped
+    dragged: Cell<bool>,
+    /// Where the view settles once the gesture ends, in CSS pixels
+    anchor: Cell<[f64; 2]>,
+    /// Wheel zoom not applied yet because the zoom snaps
+    wheel_rest: Cell<f32>,
+}
+
+impl Gestures {
+    /// Starts tracking a pressed pointer, returning false if no gesture is
+    /// enabled or the mouse button isn't the primary one
+    pub fn down(&self, state: &RefCell<MapState>, event: &PointerEvent) -> bool {
+        let enabled = state
+            .try_borrow()
+            .is_ok_and(|state| state.interactions.gestures());
+        if !enabled || (event.pointer_type() == "mouse" && event.button() != 0) {
+            return false;
+        }
+
+        let mut pointers = self.pointers.borrow_mut();
+        if pointers.is_empty() {
+            self.dragged.set(false);
+        }
+        let position = [event.offset_x(), event.offset_y()];
+        pointers.retain(|&(id, _)| id != event.pointer_id());
+        pointers.push((event.pointer_id(), position));
+        true
+    }
+
+    /// Moves the view with a tracked pointer
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view
+    pub fn moved(&self, state: &RefCell<MapState>, event: &PointerEvent) -> Result<(), JsValue> {
+        let mut pointers = self.pointers.borrow_mut();
+        let Some(index) = pointers
+            .iter()
+            .position(|&(id, _)| id == event.pointer_id())
+        else {
+            return Ok(());
+        };
+        let position = [event.offset_x(), event.offset_y()];
+        let previous = pointers[index].1;
+        if !self.dragged.get() {
+            if pointers.len() == 1 && distance(previous, position) <= DRAG_SLOP {
+                return Ok(());
+            }
+            self.dragged.set(true);
+        }
+        let before = pointers
+            .iter()
+            .map(|&(_, point)| point)
+            .take(2)
+            .collect::<Vec<_>>();
+        pointers[index].1 = position;
+        // Pointers past the second don't steer
+        if index > 1 {
+            return Ok(());
+        }
+        let after = pointers
+            .iter()
+            .map(|&(_, point)| point)
+            .take(2)
+            .collect::<Vec<_>>();
+        drop(pointers);
+
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return Ok(());
+        };
+        let gesture = match (before.as_slice(), after.as_slice()) {
+            (&[from], &[to]) => drag(&state, state.interactions, from, to, event.shift_key()),
+            (&[a, b], &[c, d]) => Some(pinch(state.interactions, [a, b], [c, d])),
+            _ => None,
+        };
+        let Some(gesture) = gesture else {
+            return Ok(());
+        };
+        self.anchor.set(gesture.to);
+        let view = gesture.apply(&state);
+        state.move_view(view)
+    }
+
+    /// Stops tracking a lifted or cancelled pointer, settling the view once
+    /// the last one is
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view or the
+    /// hash write can't be timed
+    pub fn up(&self, state: &RefCell<MapState>, event: &PointerEvent) -> Result<(), JsValue> {
+        let mut pointers = self.pointers.borrow_mut();
+        let count = pointers.len();
+        pointers.retain(|&(id, _)| id != event.pointer_id());
+        if pointers.len() == count || !pointers.is_empty() || !self.dragged.get() {
+            return Ok(());
+        }
+        drop(pointers);
+
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return Ok(());
+        };
+        let anchor = buffer_point(&state, self.anchor.get());
+        let view = state.ctx.view();
+        state.settle_view(view, anchor)
+    }
+
+    /// Returns true once after a press that dragged, whose click is then
+    /// dropped
+    pub fn take_dragged(&self) -> bool {
+        self.dragged.take()
+    }
+
+    /// Zooms about the pointer with the wheel, returning false if wheel
+    /// zoom is off and the page should scroll instead
+    ///
+    /// With the zoom snapping, scrolling adds up until it reaches the next
+    /// snapped zoom.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view or the
+    /// hash write can't be timed
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn wheel(&self, state: &RefCell<MapState>, event: &WheelEvent) -> Result<bool, JsValue> {
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return Ok(false);
+        };
+        if !state.interactions.zoom {
+            return Ok(false);
+        }
+
+        let pixels = match event.delta_mode() {
+            WheelEvent::DOM_DELTA_LINE => WHEEL_LINE,
+            WheelEvent::DOM_DELTA_PAGE => WHEEL_PAGE,
+            _ => 1.0,
+        } * event.delta_y();
+        let at = [event.offset_x(), event.offset_y()];
+        let gesture = Gesture {
+            from: at,
+            to: at,
+            zoom: self.wheel_rest.take() - (pixels * WHEEL_ZOOM_PER_PIXEL) as f32,
+            rotation: 0.0,
+        };
+        let (view, anchor) = (gesture.apply(&state), buffer_point(&state, at));
+        let wanted = view.zoom;
+        state.settle_view(view, anchor)?;
+        self.wheel_rest.set(wanted - state.ctx.view().zoom);
+        Ok(true)
+    }
+}
+
+/// View change of one pointer move or wheel step
+struct Gesture {
+    /// Where the map point that follows the pointers was, in CSS pixels
+    from: [f64; 2],
+    /// Where that point moves to, in CSS pixels
+    to: [f64; 2],
+    /// Zoom steps added
+    zoom: f32,
+    /// Clockwise rotation added in radians
+    rotation: f32,
+}
+
+impl Gesture {
+    /// Returns the view of `state` changed by the gesture, kept on the map
+    /// and within [`MAX_ZOOM`]
+    #[allow(clippy::cast_precision_loss)]
+    fn apply(&self, state: &MapState) -> View {
+        let (width, height) = (state.ctx.width() as f32, state.ctx.height() as f32);
+        let view = state.ctx.view();
+        let zoom = (view.zoom + self.zoom).clamp(0.0, MAX_ZOOM) - view.zoom;
+        let view = view.transformed(
+            width,
+            height,
+            buffer_point(state, self.from),
+            buffer_point(state, self.to),
+            zoom,
+            self.rotation,
+        );
+        View {
+            center: view.center.map(|fraction| fraction.clamp(0.0, 1.0)),
+            ..view
+        }
+    }
+}
+
+/// Returns the gesture of a single pointer moving from `from` to `to`: a
+/// pan, or a rotation about the center of the canvas with `shift` held
+#[allow(clippy::cast_possible_truncation)]
+fn drag(
+    state: &MapState,
+    interactions: Interactions,
+    from: [f64; 2],
+    to: [f64; 2],
+    shift: bool,
+) -> Option<Gesture> {
+    if shift && interactions.rotate {
+        let center = [
+            f64::from(state.ctx.css_width()) / 2.0,
+            f64::from(state.ctx.css_height()) / 2.0,
+        ];
+        return Some(Gesture {
+            from: center,
+            to: center,
+            zoom: 0.0,
+            rotation: (angle(center, to) - angle(center, from)) as f32,
+        });
+    }
+    interactions.pan.then_some(Gesture {
+        from,
+        to,
+        zoom: 0.0,
+        rotation: 0.0,
+    })
+}
+
+/// Returns the gesture of two pointers moving from `before` to `after`
+#[allow(clippy::cast_possible_truncation)]
+fn pinch(interactions: Interactions, before: [[f64; 2]; 2], after: [[f64; 2]; 2]) -> Gesture {
+    let midpoint = |[a, b]: [[f64; 2]; 2]| [f64::midpoint(a[0], b[0]), f64::midpoint(a[1], b[1])];
+    let from = midpoint(before);
+    let to = if interactions.pan {
+        midpoint(after)
+    } else {
+        from
+    };
+    let zoom = if interactions.zoom {
+        let (was, is) = (distance(before[0], before[1]), distance(after[0], after[1]));
+        if was > 0.0 && is > 0.0 {
+            (is / was).log2() as f32
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+    let rotation = if interactions.rotate {
+        (angle(after[0], after[1]) - angle(before[0], before[1])) as f32
+    } else {
+        0.0
+    };
+    Gesture {
+        from,
+        to,
+        zoom,
+        rotation,
+    }
+}
+
+/// Returns a point in CSS pixels in drawing buffer pixels
+#[allow(clippy::cast_possible_truncation)]
+fn buffer_point(state: &MapState, [x, y]: [f64; 2]) -> [f32; 2] {
+    let pixel_ratio = state.ctx.pixel_ratio();
+    [(x * pixel_ratio) as f32, (y * pixel_ratio) as f32]
+}
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// Returns the clockwise angle of `b` seen from `a` in radians, y growing
+/// downwards
+fn angle(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (b[1] - a[1]).atan2(b[0] - a[0])
+}
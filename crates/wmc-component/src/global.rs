@@ -0,0 +1,194 @@
+use js_sys::{Function, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, DedicatedWorkerGlobalScope, ImageBitmap, ReadableStreamDefaultReader, Request, Response,
+};
+
+use crate::error::MapError;
+
+/// Global scope the component runs in
+///
+/// The map runs either on the main thread or, with an `OffscreenCanvas`, in a
+/// dedicated worker, which has no `window`.
+enum Scope {
+    Window(web_sys::Window),
+    Worker(DedicatedWorkerGlobalScope),
+}
+
+fn scope() -> Result<Scope, JsValue> {
+    if let Some(window) = web_sys::window() {
+        return Ok(Scope::Window(window));
+    }
+
+    js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .map(Scope::Worker)
+        .map_err(|_| MapError::internal("Unsupported global scope").into())
+}
+
+/// Schedules `callback` for the next animation frame
+pub fn request_animation_frame(callback: &Function) -> Result<i32, JsValue> {
+    match scope()? {
+        Scope::Window(window) => window.request_animation_frame(callback),
+        Scope::Worker(worker) => worker.request_animation_frame(callback),
+    }
+}
+
+/// Cancels a pending animation frame request
+pub fn cancel_animation_frame(id: i32) {
+    // Cancelling an already-fired request is a no-op
+    let _ = match scope() {
+        Ok(Scope::Window(window)) => window.cancel_animation_frame(id),
+        Ok(Scope::Worker(worker)) => worker.cancel_animation_frame(id),
+        Err(e) => Err(e),
+    };
+}
+
+/// Schedules `callback` on the next macrotask
+pub fn set_timeout(callback: &Function) -> Result<i32, JsValue> {
+    match scope()? {
+        Scope::Window(window) => window.set_timeout_with_callback(callback),
+        Scope::Worker(worker) => worker.set_timeout_with_callback(callback),
+    }
+}
+
+/// Calls `callback` once after `delay_ms` milliseconds unless cleared
+pub fn set_timeout_after(callback: &Function, delay_ms: i32) -> Result<i32, JsValue> {
+    match scope()? {
+        Scope::Window(window) => {
+            window.set_timeout_with_callback_and_timeout_and_arguments_0(callback, delay_ms)
+        },
+        Scope::Worker(worker) => {
+            worker.set_timeout_with_callback_and_timeout_and_arguments_0(callback, delay_ms)
+        },
+    }
+}
+
+/// Cancels a timer started with [`set_timeout_after`]
+pub fn clear_timeout(id: i32) {
+    match scope() {
+        Ok(Scope::Window(window)) => window.clear_timeout_with_handle(id),
+        Ok(Scope::Worker(worker)) => worker.clear_timeout_with_handle(id),
+        Err(_) => {},
+    }
+}
+
+/// Calls `callback` every `interval_ms` milliseconds until cleared
+pub fn set_interval(callback: &Function, interval_ms: i32) -> Result<i32, JsValue> {
+    match scope()? {
+        Scope::Window(window) => {
+            window.set_interval_with_callback_and_timeout_and_arguments_0(callback, interval_ms)
+        },
+        Scope::Worker(worker) => {
+            worker.set_interval_with_callback_and_timeout_and_arguments_0(callback, interval_ms)
+        },
+    }
+}
+
+/// Cancels a timer started with [`set_interval`]
+pub fn clear_interval(id: i32) {
+    match scope() {
+        Ok(Scope::Window(window)) => window.clear_interval_with_handle(id),
+        Ok(Scope::Worker(worker)) => worker.clear_interval_with_handle(id),
+        Err(_) => {},
+    }
+}
+
+/// Returns a high-resolution timestamp in milliseconds
+///
+/// Falls back to `Date.now()` where `performance` is missing.
+pub fn now() -> f64 {
+    let performance = match scope() {
+        Ok(Scope::Window(window)) => window.performance(),
+        Ok(Scope::Worker(worker)) => worker.performance(),
+        Err(_) => None,
+    };
+
+    performance.map_or_else(js_sys::Date::now, |performance| performance.now())
+}
+
+/// Sends `request` and waits for the response headers
+#[allow(clippy::future_not_send)]
+pub async fn fetch(request: &Request) -> Result<Response, JsValue> {
+    let response = match scope()? {
+        Scope::Window(window) => window.fetch_with_request(request),
+        Scope::Worker(worker) => worker.fetch_with_request(request),
+    };
+    JsFuture::from(response).await?.dyn_into()
+}
+
+/// Reads the body of `response` as UTF-8 text
+///
+/// The body is streamed, calling `progress` with the bytes received so far
+/// and the `Content-Length`, if any, after every chunk.
+#[allow(clippy::future_not_send, clippy::cast_precision_loss)]
+pub async fn read_text(
+    response: &Response,
+    progress: impl Fn(f64, Option<f64>),
+) -> Result<String, JsValue> {
+    let total = response
+        .headers()
+        .get("Content-Length")?
+        .and_then(|length| length.parse::<f64>().ok());
+    let Some(body) = response.body() else {
+        return JsFuture::from(response.text()?)
+            .await?
+            .as_string()
+            .ok_or_else(|| MapError::internal("Response body is not text").into());
+    };
+
+    let reader: ReadableStreamDefaultReader = body.get_reader().dyn_into()?;
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&chunk, &JsValue::from_str("done"))?.is_truthy() {
+            break;
+        }
+        let value: Uint8Array = Reflect::get(&chunk, &JsValue::from_str("value"))?.dyn_into()?;
+        bytes.extend(value.to_vec());
+        progress(bytes.len() as f64, total);
+    }
+
+    String::from_utf8(bytes).map_err(|_| MapError::internal("Response body is not UTF-8").into())
+}
+
+/// Fetches an image and decodes it into an `ImageBitmap`
+///
+/// Decoding happens off the main thread where the browser supports it.
+#[allow(clippy::future_not_send)]
+pub async fn fetch_image(url: &str) -> Result<ImageBitmap, JsValue> {
+    let response = match scope()? {
+        Scope::Window(window) => window.fetch_with_str(url),
+        Scope::Worker(worker) => worker.fetch_with_str(url),
+    };
+    let response: Response = JsFuture::from(response).await?.dyn_into()?;
+    if !response.ok() {
+        return Err(MapError::internal(format!("HTTP {} for {url}", response.status())).into());
+    }
+
+    let blob: Blob = JsFuture::from(response.blob()?).await?.dyn_into()?;
+    let bitmap = match scope()? {
+        Scope::Window(window) => window.create_image_bitmap_with_blob(&blob)?,
+        Scope::Worker(worker) => worker.create_image_bitmap_with_blob(&blob)?,
+    };
+
+    JsFuture::from(bitmap).await?.dyn_into()
+}
+
+/// Resolves on the next macrotask, letting the browser handle pending events
+#[allow(clippy::future_not_send)]
+pub async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let mut schedule_error = None;
+    let promise = Promise::new(&mut |resolve, _reject| {
+        if let Err(e) = set_timeout(&resolve) {
+            schedule_error = Some(e);
+        }
+    });
+
+    if let Some(e) = schedule_error {
+        return Err(e);
+    }
+
+    JsFuture::from(promise).await.map(|_| ())
+}
@@ -0,0 +1,324 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use js_sys::Function;
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::{UrlSearchParams, Window};
+use wmc_core::projection::GeoCoord;
+use wmc_render::log;
+
+use crate::{
+    error::{self, ComponentError},
+    events, global,
+    state::MapState,
+};
+
+/// Hash parameter holding the selected marker id
+const KEY: &str = "marker";
+
+/// Hash parameter holding the view, see [`HashView`]
+const VIEW_KEY: &str = "map";
+
+/// Shortest time between two view writes, in milliseconds
+///
+/// Browsers limit how often history can be replaced, and gestures change
+/// the view every frame.
+const VIEW_WRITE_MS: i32 = 250;
+
+/// View encoded in the hash as `zoom/lat/lon`, followed by `/bearing` when
+/// the map is rotated
+///
+/// The bearing is in degrees clockwise. Zoom keeps two decimals, the center
+/// five, about a meter, and the bearing one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashView {
+    /// Zoom of the map, see [`crate::WorldMap::zoom`]
+    pub zoom: f32,
+    /// Coordinate at the center of the map
+    pub center: GeoCoord,
+    /// Clockwise rotation in degrees
+    pub bearing: f32,
+}
+
+impl HashView {
+    /// Parses `zoom/lat/lon` or `zoom/lat/lon/bearing`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ComponentError::ParseFailed` if a part is missing or isn't a
+    /// finite number, or the center is out of range
+    pub fn parse(value: &str) -> Result<Self, ComponentError> {
+        let failed = |reason: String| ComponentError::ParseFailed {
+            input: "Hash view".to_string(),
+            reason,
+        };
+        let parts = value.split('/').collect::<Vec<_>>();
+        let [zoom, lat, lon, bearing @ ..] = parts.as_slice() else {
+            return Err(failed(format!("expected zoom/lat/lon, got '{value}'")));
+        };
+        if bearing.len() > 1 {
+            return Err(failed(format!(
+                "expected zoom/lat/lon/bearing, got '{value}'"
+            )));
+        }
+        let number = |part: &str| {
+            part.parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| failed(format!("'{part}' isn't a number")))
+        };
+
+        let center =
+            GeoCoord::new(number(lat)?, number(lon)?).map_err(|e| failed(e.to_string()))?;
+        #[allow(clippy::cast_possible_truncation)]
+        let zoom = number(zoom)? as f32;
+        #[allow(clippy::cast_possible_truncation)]
+        let bearing = bearing.first().map_or(Ok(0.0), |b| number(b))? as f32;
+        Ok(Self {
+            zoom,
+            center,
+            bearing,
+        })
+    }
+}
+
+impl fmt::Display for HashView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2}/{:.5}/{:.5}",
+            self.zoom, self.center.lat, self.center.lon
+        )?;
+        let bearing = self.bearing.rem_euclid(360.0);
+        if (bearing * 10.0).round() % 3600.0 != 0.0 {
+            write!(f, "/{bearing:.1}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Mirrors the selected marker and the view into `location.hash`
+///
+/// The hash is read as URL search parameters, so
+/// `#map=3.00/48.85660/2.35220&marker=hq&tab=2` shows Paris at zoom 3,
+/// selects marker `hq` and leaves `tab` to the host. Every selection pushes
+/// a history entry, while view changes replace the current one, so back and
+/// forward step through selections and return to the view each was left
+/// at. View writes are throttled to one per [`VIEW_WRITE_MS`], the last
+/// view always being written. Going back or forward, or editing the hash,
+/// fires `hashchange`, which drops a pending view write and restores the
+/// view and the marker the hash names. The listener is removed and the
+/// pending write dropped on drop.
+pub struct HashSync {
+    window: Window,
+    on_change: Closure<dyn FnMut()>,
+    view: Rc<PendingView>,
+    on_view_timer: Closure<dyn FnMut()>,
+}
+
+/// View waiting for its throttled write
+#[derive(Default)]
+struct PendingView {
+    /// View to write once the timer fires
+    view: Cell<Option<HashView>>,
+    /// Timer of the pending write
+    timer: Cell<Option<i32>>,
+}
+
+impl PendingView {
+    /// Drops the pending write, returning its view
+    fn take(&self) -> Option<HashView> {
+        if let Some(timer) = self.timer.take() {
+            global::clear_timeout(timer);
+        }
+        self.view.take()
+    }
+}
+
+impl HashSync {
+    /// Starts following hash changes for the map behind `state`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error outside a window or if the listener can't be
+    /// added
+    pub fn attach(state: Weak<RefCell<MapState>>) -> Result<Self, JsValue> {
+        let window =
+            web_sys::window().ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+        let view = Rc::new(PendingView::default());
+        let on_change = {
+            let view = Rc::clone(&view);
+            Closure::<dyn FnMut()>::new(move || {
+                // The view was left behind with the old entry
+                view.take();
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                if let Err(e) = restore(&state) {
+                    log::error("hash", "Hash not restored", &[("error", e)]);
+                }
+            })
+        };
+        let on_view_timer = {
+            let (view, window) = (Rc::clone(&view), window.clone());
+            Closure::<dyn FnMut()>::new(move || {
+                view.timer.set(None);
+                let Some(pending) = view.view.take() else {
+                    return;
+                };
+                let written = params(&window).and_then(|params| {
+                    params.set(VIEW_KEY, &pending.to_string());
+                    commit(&window, &params, false)
+                });
+                if let Err(e) = written {
+                    log::error("hash", "View not written", &[("error", e)]);
+                }
+            })
+        };
+        window
+            .add_event_listener_with_callback("hashchange", on_change.as_ref().unchecked_ref())?;
+
+        Ok(Self {
+            window,
+            on_change,
+            view,
+            on_view_timer,
+        })
+    }
+
+    /// Returns the marker id the hash names, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the location can't be read
+    pub fn read(&self) -> Result<Option<String>, JsValue> {
+        Ok(params(&self.window)?.get(KEY))
+    }
+
+    /// Returns the view the hash names, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the location can't be read or the view is
+    /// malformed
+    pub fn read_view(&self) -> Result<Option<HashView>, JsValue> {
+        params(&self.window)?
+            .get(VIEW_KEY)
+            .map(|value| HashView::parse(&value).map_err(error::to_js))
+            .transpose()
+    }
+
+    /// Pushes a history entry naming `id` in the hash, or without the
+    /// parameter for `None`
+    ///
+    /// A pending view write goes into the new entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the location or history can't be accessed
+    pub fn write(&self, id: Option<&str>) -> Result<(), JsValue> {
+        let params = params(&self.window)?;
+        match id {
+            Some(id) => params.set(KEY, id),
+            None => params.delete(KEY),
+        }
+        if let Some(view) = self.view.take() {
+            params.set(VIEW_KEY, &view.to_string());
+        }
+        commit(&self.window, &params, true)
+    }
+
+    /// Replaces the view in the current history entry's hash once
+    /// [`VIEW_WRITE_MS`] passed since the last write
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the write can't be timed
+    pub fn write_view(&self, view: &HashView) -> Result<(), JsValue> {
+        self.view.view.set(Some(*view));
+        if self.view.timer.get().is_none() {
+            let callback: &Function = self.on_view_timer.as_ref().unchecked_ref();
+            let timer = global::set_timeout_after(callback, VIEW_WRITE_MS)?;
+            self.view.timer.set(Some(timer));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for HashSync {
+    fn drop(&mut self) {
+        self.view.take();
+        let _ = self.window.remove_event_listener_with_callback(
+            "hashchange",
+            self.on_change.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// Returns the parameters of the window's hash
+fn params(window: &Window) -> Result<UrlSearchParams, JsValue> {
+    let hash = window.location().hash()?;
+    UrlSearchParams::new_with_str(hash.trim_start_matches('#'))
+}
+
+/// Writes `params` as the window's hash, pushing a history entry or
+/// replacing the current one
+fn commit(window: &Window, params: &UrlSearchParams, push: bool) -> Result<(), JsValue> {
+    // Slashes are valid in a fragment and keep `map=3.00/48.85660/2.35220`
+    // readable; parsing decodes both forms alike
+    let hash = String::from(params.to_string()).replace("%2F", "/");
+    let location = window.location();
+    if !push && location.hash()?.trim_start_matches('#') == hash {
+        return Ok(());
+    }
+
+    // An empty fragment would leave a bare `#` in the URL
+    let url = if hash.is_empty() {
+        format!("{}{}", location.pathname()?, location.search()?)
+    } else {
+        format!("#{hash}")
+    };
+    let history = window.history()?;
+    if push {
+        history.push_state_with_url(&JsValue::NULL, "", Some(&url))
+    } else {
+        history.replace_state_with_url(&JsValue::NULL, "", Some(&url))
+    }
+}
+
+/// Selects the marker and shows the view the hash names, emitting `select`
+/// if the selection changed
+///
+/// A hash without a view gets the current one, so the URL can be shared
+/// right away.
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the hash can't be read or written, its view is
+/// malformed, or the event detail can't be built
+pub fn restore(state: &RefCell<MapState>) -> Result<(), JsValue> {
+    let (changed, view) = {
+        let mut map = state.borrow_mut();
+        let Some(sync) = &map.hash_sync else {
+            return Ok(());
+        };
+        let id = sync.read()?;
+        let view = sync.read_view();
+        let changed = map.selected != id;
+        map.selected = id;
+        map.refresh_selection();
+        (changed, view)
+    };
+
+    if changed {
+        events::emit_select(state)?;
+    }
+    let mut map = state.borrow_mut();
+    match view? {
+        Some(view) => map.show_hash_view(&view),
+        None => map.write_hash_view(),
+    }
+}
@@ -0,0 +1,69 @@
+use wmc_core::{
+    heatmap::{Gradient, HeatPoint},
+    projection::Projection,
+};
+use wmc_render::{Gl, HeatmapRenderer, HeatmapSettings, RenderContext, RenderError, log};
+
+use crate::layer::{Layer, LayerFrame};
+
+/// Density heatmap of weighted points, for datasets too dense for markers
+pub struct HeatmapLayer {
+    points: Vec<HeatPoint>,
+    settings: HeatmapSettings,
+    renderer: HeatmapRenderer,
+}
+
+impl HeatmapLayer {
+    /// Projects `points` and uploads them with the color ramp `gradient`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if instancing is unavailable or renderer
+    /// initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        points: Vec<HeatPoint>,
+        projection: &dyn Projection,
+        gradient: &Gradient,
+        settings: HeatmapSettings,
+    ) -> Result<Self, RenderError> {
+        let mut renderer = HeatmapRenderer::new(ctx, gradient)?;
+        renderer.upload(ctx, &points, projection);
+
+        Ok(Self {
+            points,
+            settings,
+            renderer,
+        })
+    }
+}
+
+impl Layer for HeatmapLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.renderer.upload(ctx, &self.points, projection);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx, frame.post, self.settings) {
+            log::warn(
+                "layer",
+                "Heatmap skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn set_opacity(&mut self, opacity: f32) -> bool {
+        self.settings.opacity = opacity;
+        true
+    }
+}
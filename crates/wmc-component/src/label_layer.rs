@@ -0,0 +1,269 @@
+use wmc_core::{
+    collision::Collider,
+    label::{FeatureLabel, GridAxis, GridLabel, zoom_level},
+    marker::Color,
+    projection::{GeoCoord, Projection},
+};
+use wmc_render::{Gl, RenderContext, RenderError, TextAnchor, TextRenderer, TextStyle, log};
+
+use crate::layer::{Layer, LayerFrame, color_array};
+
+/// Look of a [`LabelLayer`]
+#[derive(Debug, Clone, Copy)]
+pub struct LabelPaint {
+    /// Font size in CSS pixels
+    pub size: f32,
+    /// Text color
+    pub color: Color,
+    /// Halo color
+    pub halo_color: Color,
+    /// Halo width in CSS pixels
+    pub halo_width: f32,
+    /// Minimum gap between two labels in CSS pixels
+    pub padding: f32,
+}
+
+impl LabelPaint {
+    /// Returns the text style in drawing buffer pixels for `pixel_ratio`
+    pub fn style(&self, pixel_ratio: f32, anchor: TextAnchor) -> TextStyle {
+        TextStyle {
+            size: self.size * pixel_ratio,
+            color: color_array(self.color),
+            halo_color: color_array(self.halo_color),
+            halo_width: self.halo_width * pixel_ratio,
+            anchor,
+        }
+    }
+}
+
+/// Feature names placed inside their polygons, e.g. country names
+///
+/// Labels appear once the view reaches their minimum zoom. Larger features
+/// are placed first, and a label that would overlap one already placed is
+/// left out, so the biggest countries keep their names in crowded regions.
+pub struct LabelLayer {
+    /// Labels from the largest feature to the smallest
+    labels: Vec<FeatureLabel>,
+    paint: LabelPaint,
+    renderer: TextRenderer,
+}
+
+impl LabelLayer {
+    /// Creates the text renderer for the CSS font family `font` and places
+    /// `labels`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        mut labels: Vec<FeatureLabel>,
+        projection: &dyn Projection,
+        paint: LabelPaint,
+        font: &str,
+    ) -> Result<Self, RenderError> {
+        labels.sort_by(|a, b| b.size.total_cmp(&a.size));
+
+        let mut layer = Self {
+            labels,
+            paint,
+            renderer: TextRenderer::new(ctx, font)?,
+        };
+        layer.place(ctx, projection);
+        Ok(layer)
+    }
+
+    /// Lays out the labels visible at the current zoom, the view's
+    /// included, that don't collide on screen
+    #[allow(clippy::cast_possible_truncation)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        let zoom = zoom_level(f64::from(ctx.css_width())) + f64::from(ctx.view().zoom);
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let padding = self.paint.padding * pixel_ratio;
+        let style = self.paint.style(pixel_ratio, TextAnchor::Center);
+
+        self.renderer.clear();
+        let mut placement = Placement::new(ctx, padding);
+        for label in self.labels.iter().filter(|label| label.is_visible(zoom)) {
+            let point = projection.project(label.anchor);
+            let position = ctx.to_screen([point.x as f32, point.y as f32]);
+            if position.iter().all(|value| value.is_finite()) {
+                placement.add(&mut self.renderer, &label.text, position, &style);
+            }
+        }
+    }
+}
+
+impl Layer for LabelLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Labels skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+}
+
+/// Degree labels of the graticule lines along the map edges, e.g. `30°N`
+///
+/// Parallels are labeled along the left edge and meridians along the bottom
+/// edge, `padding` pixels in. Labels that would overlap one placed before or
+/// stick out of the canvas are left out, and so are all of them while the
+/// view is rotated, when lines no longer meet the edges square.
+pub struct GridLabelLayer {
+    labels: Vec<GridLabel>,
+    paint: LabelPaint,
+    renderer: TextRenderer,
+}
+
+impl GridLabelLayer {
+    /// Creates the text renderer for the CSS font family `font` and places
+    /// the labels of graticule lines every `step` degrees
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        step: f64,
+        projection: &dyn Projection,
+        paint: LabelPaint,
+        font: &str,
+    ) -> Result<Self, RenderError> {
+        let mut layer = Self {
+            labels: GridLabel::graticule(step),
+            paint,
+            renderer: TextRenderer::new(ctx, font)?,
+        };
+        layer.place(ctx, projection);
+        Ok(layer)
+    }
+
+    /// Lays out the labels of lines that reach the edges without colliding
+    #[allow(clippy::cast_possible_truncation)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let padding = self.paint.padding * pixel_ratio;
+        let style = |anchor| self.paint.style(pixel_ratio, anchor);
+
+        self.renderer.clear();
+        if ctx.view().rotation != 0.0 {
+            return;
+        }
+        let mut placement = Placement::new(ctx, padding);
+        for label in &self.labels {
+            let (position, style) = match label.axis {
+                GridAxis::Meridian => {
+                    let point = projection.project(GeoCoord {
+                        lat: 0.0,
+                        lon: label.degrees,
+                    });
+                    let [x, _] = ctx.to_screen([point.x as f32, 0.0]);
+                    ([x, placement.height - padding], style(TextAnchor::Bottom))
+                },
+                GridAxis::Parallel => {
+                    let point = projection.project(GeoCoord {
+                        lat: label.degrees,
+                        lon: 0.0,
+                    });
+                    let [_, y] = ctx.to_screen([0.0, point.y as f32]);
+                    ([padding, y], style(TextAnchor::Left))
+                },
+            };
+            if position.iter().all(|value| value.is_finite()) {
+                placement.add(&mut self.renderer, &label.text, position, &style);
+            }
+        }
+    }
+}
+
+impl Layer for GridLabelLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Grid labels skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+}
+
+/// Greedy placement of labels that neither overlap nor leave the drawing
+/// buffer
+struct Placement {
+    /// Boxes of the labels placed so far
+    collider: Collider,
+    height: f32,
+}
+
+impl Placement {
+    #[allow(clippy::cast_precision_loss)]
+    const fn new(ctx: &RenderContext, padding: f32) -> Self {
+        let (width, height) = (ctx.width() as f32, ctx.height() as f32);
+        Self {
+            collider: Collider::new([0.0, 0.0, width, height], padding),
+            height,
+        }
+    }
+
+    /// Adds `text` to `renderer` unless it would stick out of the drawing
+    /// buffer or come closer than the padding to a placed label
+    fn add(
+        &mut self,
+        renderer: &mut TextRenderer,
+        text: &str,
+        position: [f32; 2],
+        style: &TextStyle,
+    ) {
+        if self.collider.place(renderer.bounds(text, position, style)) {
+            renderer.add(text, position, style);
+        }
+    }
+}
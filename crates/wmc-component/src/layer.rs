@@ -0,0 +1,1761 @@
+use std::{
+    collections::HashSet,
+    ops::Range,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use wmc_core::{
+    index::SpatialIndex,
+    label::zoom_level,
+    marker::{Color, Marker},
+    marker_buffer::{InstanceBuilder, MarkerBuffer},
+    projection::{GeoBounds, GeoCoord, Projection},
+    simplify::LineDetail,
+    svg::SvgDocument,
+    theme::{LayerStyle, LineStyle, MarkerStyle, Theme},
+    topology::{Feature, WorldTopology},
+};
+use wmc_render::{
+    BatchStyle, BlendMode, Bloom, BloomSettings, FillMode, FillRenderer, Gl, GpuTexture, GpuTimer,
+    LineBatch, LineMesh, MarkerRenderer, OverlayRenderer, PostProcessor, RenderContext,
+    RenderError, ScissorRect, WorldRenderer, line_batch::MAX_BATCH_MEMBERS, log,
+};
+
+use crate::options::MarkerOrder;
+
+/// Id of the built-in world coastline layer
+pub const WORLD: &str = "world";
+/// Id of the built-in lakes layer
+pub const LAKES: &str = "lakes";
+/// Id of the built-in rivers layer
+pub const RIVERS: &str = "rivers";
+/// Id of the built-in graticule layer
+pub const GRATICULE: &str = "graticule";
+/// Id of the built-in graticule labels layer
+pub const GRATICULE_LABELS: &str = "graticule-labels";
+/// Id of the built-in scale bar layer
+pub const SCALE_BAR: &str = "scale-bar";
+/// Id of the coordinate readout layer
+pub const COORDINATE_READOUT: &str = "coordinate-readout";
+/// Id of the compass layer
+pub const COMPASS: &str = "compass";
+/// Id of the built-in markers layer
+pub const MARKERS: &str = "markers";
+/// Id of the built-in marker ripple layer
+pub const RIPPLES: &str = "ripples";
+/// Id of the built-in keyboard focus ring layer
+pub const FOCUS: &str = "focus";
+/// Id of the built-in selected marker layer
+pub const SELECTION: &str = "selection";
+
+/// Graticule spacing in degrees
+pub const GRATICULE_STEP: f64 = 15.0;
+
+/// Line details smaller than this many drawing buffer pixels are dropped
+const LINE_TOLERANCE: f64 = 0.5;
+
+/// Per-frame inputs shared by every layer
+///
+/// Animation time and the viewport reach shaders through the camera
+/// uniforms of `ctx`.
+pub struct LayerFrame<'a> {
+    /// Rendering context to draw with
+    pub ctx: &'a RenderContext,
+    /// Active theme
+    pub theme: &'a Theme,
+    /// Post processor whose scene target layers draw into
+    pub post: &'a PostProcessor,
+    /// Animation time is fixed, so wall-clock transitions such as tile
+    /// fades are drawn finished
+    pub deterministic: bool,
+}
+
+/// Drawable map layer
+pub trait Layer {
+    /// Rebuilds projected geometry after the viewport changed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if GPU resources can't be recreated
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError>;
+
+    /// Draws the layer into the current render target
+    fn draw(&mut self, frame: &LayerFrame<'_>);
+
+    /// Frees the layer's GPU resources
+    ///
+    /// The layer must not be drawn afterwards.
+    fn delete(&self, gl: &Gl);
+
+    /// Returns true if the layer changes over time and needs continuous
+    /// frames
+    fn is_animated(&self) -> bool {
+        false
+    }
+
+    /// Returns the part of the drawing buffer an animated layer changes from
+    /// one frame to the next
+    ///
+    /// `None` means it may change anywhere, forcing a full redraw.
+    fn damage(&self, _frame: &LayerFrame<'_>) -> Option<ScissorRect> {
+        None
+    }
+
+    /// Sets the layer opacity in `0.0..=1.0`
+    ///
+    /// Returns false if the layer has no opacity control.
+    fn set_opacity(&mut self, _opacity: f32) -> bool {
+        false
+    }
+
+    /// Adds the layer's vector geometry to an SVG export
+    ///
+    /// Raster layers have none and add nothing.
+    fn export_svg(&self, _svg: &mut SvgDocument, _projection: &dyn Projection, _theme: &Theme) {}
+
+    /// Applies a new theme to styling the layer baked in when it was built
+    ///
+    /// Layers reading the theme every frame need nothing here.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if GPU resources can't be rebuilt
+    fn set_theme(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _theme: &Theme,
+    ) -> Result<(), RenderError> {
+        Ok(())
+    }
+
+    /// Follows a new pan, zoom or rotation of [`RenderContext::view`]
+    ///
+    /// Geometry drawn through the camera needs nothing here; layers culled
+    /// to the drawing buffer or laid out in screen pixels update.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if GPU resources can't be updated
+    fn set_view(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        Ok(())
+    }
+
+    /// Returns the marker drawn at `point` in map pixels, if the layer draws
+    /// markers
+    fn pick_marker(
+        &self,
+        _projection: &dyn Projection,
+        _point: [f32; 2],
+        _pixel_ratio: f32,
+    ) -> Option<&Marker> {
+        None
+    }
+
+    /// Returns the markers the layer draws
+    fn markers(&self) -> &[Marker] {
+        &[]
+    }
+
+    /// Replaces the markers of a layer drawing markers in place
+    ///
+    /// Returns false if the layer draws no markers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the instances can't be uploaded
+    fn update_markers(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _markers: &[Marker],
+    ) -> Result<bool, RenderError> {
+        Ok(false)
+    }
+
+    /// Hides the markers whose group is in `groups` and shows the others
+    ///
+    /// Hidden markers aren't drawn, picked or exported. Returns false if the
+    /// layer draws no markers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the instances can't be uploaded
+    fn set_hidden_groups(
+        &mut self,
+        _ctx: &RenderContext,
+        _groups: &HashSet<String>,
+    ) -> Result<bool, RenderError> {
+        Ok(false)
+    }
+
+    /// Sets the order markers are drawn in and how many are drawn
+    ///
+    /// Returns false if the layer draws no markers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the instances can't be uploaded
+    fn set_marker_order(
+        &mut self,
+        _ctx: &RenderContext,
+        _order: MarkerOrder,
+    ) -> Result<bool, RenderError> {
+        Ok(false)
+    }
+
+    /// Moves a focus ring to `marker`, or hides it with `None`
+    ///
+    /// Returns false if the layer draws no focus ring.
+    fn focus_marker(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _marker: Option<&Marker>,
+    ) -> bool {
+        false
+    }
+
+    /// Highlights `marker` as selected, or clears the highlight with `None`
+    ///
+    /// Returns false if the layer draws no selection.
+    fn select_marker(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _marker: Option<&Marker>,
+    ) -> bool {
+        false
+    }
+
+    /// Starts a ripple from each of `markers` at animation time `time`
+    ///
+    /// Returns false if the layer draws no ripples.
+    fn add_ripples(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _markers: &[Marker],
+        _time: f32,
+    ) -> bool {
+        false
+    }
+
+    /// Follows the position under the pointer, `None` once the pointer
+    /// leaves the canvas
+    ///
+    /// Returns false if the layer doesn't follow the pointer.
+    fn set_pointer(&mut self, _ctx: &RenderContext, _coord: Option<GeoCoord>) -> bool {
+        false
+    }
+
+    /// Returns true if the layer draws a control at `point` in drawing
+    /// buffer pixels that handles clicks itself, which then don't reach the
+    /// map
+    fn hit(&self, _point: [f32; 2]) -> bool {
+        false
+    }
+
+    /// Returns the first feature whose polygons contain `coord`, if the
+    /// layer draws a topology
+    fn feature_at(&self, _coord: GeoCoord) -> Option<&Feature> {
+        None
+    }
+
+    /// Replaces the fragment shader with custom GLSL, or restores the
+    /// built-in one with `None`
+    ///
+    /// Returns false if the layer has no replaceable shader.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the shader fails to compile or link; the
+    /// layer then keeps its previous shader
+    fn set_fragment_shader(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+        _source: Option<&str>,
+    ) -> Result<bool, RenderError> {
+        Ok(false)
+    }
+
+    /// Returns the line mesh of a layer that may share a [`LineBatch`] with
+    /// the line layers next to it
+    ///
+    /// Layers drawing anything else, or lines with a custom shader, return
+    /// `None` and are drawn on their own.
+    fn batch_lines(&self) -> Option<BatchLines<'_>> {
+        None
+    }
+}
+
+/// Line mesh of a layer drawn in a [`LineBatch`], see [`Layer::batch_lines`]
+pub struct BatchLines<'a> {
+    pub mesh: &'a LineMesh,
+    /// Drawing buffer size the mesh was projected for
+    pub size: [u32; 2],
+    /// Tells meshes apart: a mesh keeps its revision while it's unchanged
+    pub revision: u64,
+    pub paint: Paint,
+}
+
+/// Where a layer takes its color and line width from
+#[derive(Debug, Clone, Copy)]
+pub enum Paint {
+    /// Theme coastline style
+    Coastlines,
+    /// Theme river style
+    Rivers,
+    /// Theme lake style
+    Lakes,
+    /// Theme graticule style
+    Graticule,
+    /// Fixed color, line width and dash pattern in CSS pixels
+    Fixed {
+        /// Line or fill color
+        color: Color,
+        /// Line width in CSS pixels
+        width: f32,
+        /// Line dash pattern in CSS pixels
+        style: LineStyle,
+    },
+}
+
+impl Paint {
+    /// Returns the style block for `theme`, with lengths in CSS pixels
+    pub const fn layer_style(self, theme: &Theme) -> LayerStyle {
+        match self {
+            Self::Coastlines => theme.coastlines,
+            Self::Rivers => theme.rivers,
+            Self::Lakes => theme.lakes,
+            Self::Graticule => theme.graticule,
+            Self::Fixed {
+                color,
+                width,
+                style,
+            } => LayerStyle {
+                color,
+                width,
+                opacity: 1.0,
+                style,
+            },
+        }
+    }
+
+    /// Returns the color for `theme`, opacity applied
+    pub fn color(self, theme: &Theme) -> Color {
+        self.layer_style(theme).effective_color()
+    }
+
+    /// Returns the line width in CSS pixels for `theme`
+    pub const fn width(self, theme: &Theme) -> f32 {
+        self.layer_style(theme).width
+    }
+
+    /// Returns the line dash pattern in CSS pixels for `theme`
+    pub const fn style(self, theme: &Theme) -> LineStyle {
+        self.layer_style(theme).style
+    }
+
+    /// Returns the line paint of a frame, in drawing buffer pixels
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn batch_style(self, frame: &LayerFrame<'_>) -> BatchStyle {
+        let pixel_ratio = frame.ctx.pixel_ratio() as f32;
+        BatchStyle {
+            color: color_array(self.color(frame.theme)),
+            width: self.width(frame.theme) * pixel_ratio,
+            style: self.style(frame.theme).scaled(pixel_ratio),
+        }
+    }
+}
+
+/// Topology drawn as anti-aliased lines
+///
+/// Lines are simplified to the canvas size, so small maps upload and draw
+/// fewer vertices; the SVG export keeps every vertex. Resizing only
+/// stretches the mesh, until the canvas outgrows the detail it was
+/// simplified for or shrinks below half of it. Meshes of the last few
+/// detail sizes are kept, so resizing back, or swapping the fragment
+/// shader, uploads them again without reprojecting.
+pub struct LineLayer {
+    topology: WorldTopology,
+    detail: LineDetail,
+    renderer: WorldRenderer,
+    /// Mesh the renderer was uploaded from
+    mesh: ProjectedMesh,
+    /// Meshes of other detail sizes, least recently used first
+    cache: Vec<ProjectedMesh>,
+    paint: Paint,
+    /// Custom fragment shader source
+    fragment: Option<String>,
+}
+
+/// Most meshes a [`LineLayer`] keeps besides the one it draws
+const MESH_CACHE_SIZE: usize = 2;
+
+/// Line mesh projected for a drawing buffer size
+///
+/// The map's projection only varies with the buffer size, and a mesh
+/// stretches over every size of its detail, so meshes are told apart by
+/// their [`detail_size`] alone.
+struct ProjectedMesh {
+    /// Drawing buffer side the mesh's detail resolves
+    detail_size: u32,
+    /// Drawing buffer size the mesh was projected for
+    size: [u32; 2],
+    mesh: LineMesh,
+    /// Unique to the mesh, see [`BatchLines::revision`]
+    revision: u64,
+}
+
+/// Source of [`ProjectedMesh::revision`]s
+static MESH_REVISION: AtomicU64 = AtomicU64::new(0);
+
+impl ProjectedMesh {
+    /// Projects the lines of `topology` with the detail the context's
+    /// drawing buffer resolves
+    fn new(
+        ctx: &RenderContext,
+        topology: &WorldTopology,
+        detail: &LineDetail,
+        projection: &dyn Projection,
+    ) -> Self {
+        let (width, height) = (ctx.width(), ctx.height());
+
+        Self {
+            detail_size: detail_size(width, height),
+            size: [width, height],
+            mesh: LineMesh::build(
+                topology,
+                projection,
+                Some((detail, line_tolerance(width, height))),
+            ),
+            revision: MESH_REVISION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Uploads the mesh, stretched to the context's drawing buffer
+    fn upload(
+        &self,
+        ctx: &RenderContext,
+        fragment: Option<&str>,
+    ) -> Result<WorldRenderer, RenderError> {
+        WorldRenderer::from_mesh(ctx, &self.mesh, self.size, fragment)
+    }
+}
+
+impl LineLayer {
+    /// Projects `topology` and uploads its line mesh
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        topology: WorldTopology,
+        projection: &dyn Projection,
+        paint: Paint,
+    ) -> Result<Self, RenderError> {
+        Self::with_fragment(ctx, topology, projection, paint, None)
+    }
+
+    /// Projects `topology` and uploads its line mesh, drawn with a custom
+    /// fragment shader unless `fragment` is `None`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the shader fails to compile or renderer
+    /// initialization fails
+    pub fn with_fragment(
+        ctx: &RenderContext,
+        topology: WorldTopology,
+        projection: &dyn Projection,
+        paint: Paint,
+        fragment: Option<String>,
+    ) -> Result<Self, RenderError> {
+        let detail = LineDetail::new(&topology);
+        let mesh = ProjectedMesh::new(ctx, &topology, &detail, projection);
+        let renderer = mesh.upload(ctx, fragment.as_deref())?;
+
+        Ok(Self {
+            topology,
+            detail,
+            renderer,
+            mesh,
+            cache: Vec::new(),
+            paint,
+            fragment,
+        })
+    }
+
+    /// Uploads a line mesh of `topology` built beforehand, e.g. in a
+    /// geometry worker, for a `size` drawing buffer
+    ///
+    /// The mesh is stretched to the context's size, see
+    /// [`Layer::reproject`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the shader fails to compile or renderer
+    /// initialization fails
+    pub fn with_mesh(
+        ctx: &RenderContext,
+        topology: WorldTopology,
+        detail: LineDetail,
+        mesh: LineMesh,
+        size: [u32; 2],
+        paint: Paint,
+        fragment: Option<String>,
+    ) -> Result<Self, RenderError> {
+        let mesh = ProjectedMesh {
+            detail_size: detail_size(size[0], size[1]),
+            size,
+            mesh,
+            revision: MESH_REVISION.fetch_add(1, Ordering::Relaxed),
+        };
+        let renderer = mesh.upload(ctx, fragment.as_deref())?;
+
+        Ok(Self {
+            topology,
+            detail,
+            renderer,
+            mesh,
+            cache: Vec::new(),
+            paint,
+            fragment,
+        })
+    }
+
+    /// Makes the mesh of `detail_size` current, taking it from the cache or
+    /// projecting it, and caches the previous one
+    fn select_mesh(&mut self, ctx: &RenderContext, projection: &dyn Projection, detail_size: u32) {
+        let mesh = match self
+            .cache
+            .iter()
+            .position(|mesh| mesh.detail_size == detail_size)
+        {
+            Some(position) => self.cache.remove(position),
+            None => ProjectedMesh::new(ctx, &self.topology, &self.detail, projection),
+        };
+
+        self.cache.push(std::mem::replace(&mut self.mesh, mesh));
+        if self.cache.len() > MESH_CACHE_SIZE {
+            self.cache.remove(0);
+        }
+    }
+}
+
+/// Returns the [`LineDetail`] tolerance of line layers in a `width` x
+/// `height` drawing buffer
+///
+/// The world spans the larger side of the buffer when it isn't square,
+/// which keeps the tolerance conservative along the other axis. Sides are
+/// rounded up to [`detail_size`], so a mesh stays detailed enough while
+/// the buffer grows up to it.
+pub fn line_tolerance(width: u32, height: u32) -> f64 {
+    LineDetail::tolerance(
+        LINE_TOLERANCE,
+        zoom_level(f64::from(detail_size(width, height))),
+    )
+}
+
+/// Returns the larger side of a `width` x `height` drawing buffer rounded
+/// up to a power of two
+fn detail_size(width: u32, height: u32) -> u32 {
+    width.max(height).max(1).next_power_of_two()
+}
+
+impl Layer for LineLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        let size = detail_size(ctx.width(), ctx.height());
+        if size == self.mesh.detail_size {
+            self.renderer.resize(ctx.width(), ctx.height());
+            return Ok(());
+        }
+
+        self.select_mesh(ctx, projection, size);
+        let renderer = self.mesh.upload(ctx, self.fragment.as_deref())?;
+        std::mem::replace(&mut self.renderer, renderer).delete(ctx.gl());
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let style = self.paint.batch_style(frame);
+        self.renderer
+            .draw(frame.ctx, style.color, style.width, style.style);
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn feature_at(&self, coord: GeoCoord) -> Option<&Feature> {
+        self.topology
+            .features
+            .iter()
+            .find(|feature| feature.geometry.contains(coord))
+    }
+
+    fn export_svg(&self, svg: &mut SvgDocument, projection: &dyn Projection, theme: &Theme) {
+        svg.lines(
+            &self.topology,
+            projection,
+            self.paint.color(theme),
+            self.paint.width(theme),
+            self.paint.style(theme),
+        );
+    }
+
+    fn set_fragment_shader(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+        source: Option<&str>,
+    ) -> Result<bool, RenderError> {
+        let renderer = self.mesh.upload(ctx, source)?;
+        std::mem::replace(&mut self.renderer, renderer).delete(ctx.gl());
+        self.fragment = source.map(str::to_string);
+        Ok(true)
+    }
+
+    fn batch_lines(&self) -> Option<BatchLines<'_>> {
+        self.fragment.is_none().then_some(BatchLines {
+            mesh: &self.mesh.mesh,
+            size: self.mesh.size,
+            revision: self.mesh.revision,
+            paint: self.paint,
+        })
+    }
+}
+
+/// Polygon topology drawn as solid fills
+pub struct FillLayer {
+    topology: WorldTopology,
+    renderer: FillRenderer,
+    paint: Paint,
+    mode: FillMode,
+}
+
+impl FillLayer {
+    /// Projects the polygons of `topology` and builds their fill geometry
+    /// for `mode`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        topology: WorldTopology,
+        projection: &dyn Projection,
+        paint: Paint,
+        mode: FillMode,
+    ) -> Result<Self, RenderError> {
+        let renderer = FillRenderer::with_mode(ctx, &topology, projection, mode)?;
+
+        Ok(Self {
+            topology,
+            renderer,
+            paint,
+            mode,
+        })
+    }
+}
+
+impl Layer for FillLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        let renderer = FillRenderer::with_mode(ctx, &self.topology, projection, self.mode)?;
+        std::mem::replace(&mut self.renderer, renderer).delete(ctx.gl());
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        self.renderer
+            .draw(frame.ctx, color_array(self.paint.color(frame.theme)));
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn export_svg(&self, svg: &mut SvgDocument, projection: &dyn Projection, theme: &Theme) {
+        svg.fills(&self.topology, projection, self.paint.color(theme));
+    }
+
+    fn feature_at(&self, coord: GeoCoord) -> Option<&Feature> {
+        self.topology
+            .features
+            .iter()
+            .find(|feature| feature.geometry.contains(coord))
+    }
+}
+
+/// Image draped over a geographic bounding box
+pub struct ImageLayer {
+    renderer: OverlayRenderer,
+    opacity: f32,
+}
+
+impl ImageLayer {
+    /// Creates an overlay of an uploaded image
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        bounds: GeoBounds,
+        texture: GpuTexture,
+        opacity: f32,
+    ) -> Result<Self, RenderError> {
+        Ok(Self {
+            renderer: OverlayRenderer::new(ctx, bounds, texture)?,
+            opacity,
+        })
+    }
+}
+
+impl Layer for ImageLayer {
+    /// Projection happens in the vertex shader, so there is nothing to rebuild
+    fn reproject(
+        &mut self,
+        _ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        self.renderer.draw(frame.ctx, self.opacity);
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn set_opacity(&mut self, opacity: f32) -> bool {
+        self.opacity = opacity;
+        true
+    }
+}
+
+/// Pulsing markers drawn in one instanced call, glowing by
+/// [`MarkerStyle::glow`]
+///
+/// Only markers reaching into the drawing buffer, glow included, are
+/// uploaded and drawn, e.g. not those past the Web Mercator latitude limit,
+/// in the [`MarkerOrder`] and up to its limit.
+pub struct MarkerLayer {
+    markers: Vec<Marker>,
+    builder: InstanceBuilder,
+    /// Instances as last built, one per marker
+    instances: MarkerBuffer,
+    /// Slot of each marker's instance in `uploaded`, `None` if it's culled
+    slots: Vec<Option<usize>>,
+    /// Instances as last uploaded, those of the markers on screen
+    uploaded: MarkerBuffer,
+    radius: f32,
+    /// Style of markers without a color, whose glow widens the area markers
+    /// are kept in and whose zoom rules apply to every marker
+    style: MarkerStyle,
+    /// Zoom the style's zoom rules are applied for
+    zoom: f32,
+    /// Projected marker centers as `[min_x, min_y, max_x, max_y]`
+    bounds: Option<[f32; 4]>,
+    /// Positions in `markers` by projected center, for picking
+    index: SpatialIndex<usize>,
+    /// Groups whose markers are hidden
+    hidden_groups: HashSet<String>,
+    order: MarkerOrder,
+    renderer: MarkerRenderer,
+    bloom: Bloom,
+}
+
+impl MarkerLayer {
+    /// Projects `markers` and uploads them as instances
+    ///
+    /// Markers without a color are colored by `style`, markers in
+    /// `hidden_groups` aren't drawn and the others are drawn in `order`. All
+    /// markers are drawn with the largest marker radius.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if instancing is unavailable or renderer
+    /// initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        markers: Vec<Marker>,
+        projection: &dyn Projection,
+        style: MarkerStyle,
+        fragment: Option<&str>,
+        hidden_groups: HashSet<String>,
+        order: MarkerOrder,
+    ) -> Result<Self, RenderError> {
+        let mut layer = Self {
+            radius: max_radius(&markers),
+            markers,
+            builder: InstanceBuilder::new(style),
+            instances: MarkerBuffer::new(0),
+            slots: Vec::new(),
+            uploaded: MarkerBuffer::new(0),
+            style,
+            zoom: 0.0,
+            bounds: None,
+            index: SpatialIndex::new(),
+            hidden_groups,
+            order,
+            renderer: MarkerRenderer::with_fragment(ctx, fragment)?,
+            bloom: Bloom::new(ctx)?,
+        };
+        layer.reproject(ctx, projection)?;
+        Ok(layer)
+    }
+
+    /// Returns the area marker centers are kept in, as `[min_x, min_y,
+    /// max_x, max_y]` in map pixels around the part of the map on screen
+    #[allow(clippy::cast_possible_truncation)]
+    fn screen(&self, ctx: &RenderContext) -> [f32; 4] {
+        // One extra pixel for filtering at the glow edge, as in `damage`
+        let margin = (self.drawn_radius() + self.style.glow.max(0.0))
+            .mul_add(ctx.pixel_ratio() as f32, 1.0);
+        let [min_x, min_y, max_x, max_y] = ctx.visible_bounds();
+        [
+            min_x - margin,
+            min_y - margin,
+            max_x + margin,
+            max_y + margin,
+        ]
+    }
+
+    /// Returns the radius in CSS pixels every marker is drawn with at the
+    /// current zoom
+    fn drawn_radius(&self) -> f32 {
+        self.radius * self.style.radius_at(self.zoom)
+    }
+
+    /// Returns true if the marker at `position` is in a hidden group
+    fn is_hidden(&self, position: usize) -> bool {
+        self.markers
+            .get(position)
+            .and_then(|marker| marker.group.as_ref())
+            .is_some_and(|group| self.hidden_groups.contains(group))
+    }
+
+    /// Returns true if the marker at `position` was uploaded by the last
+    /// cull
+    fn is_drawn(&self, position: usize) -> bool {
+        self.slots.get(position).copied().flatten().is_some()
+    }
+
+    /// Uploads the instances of the visible markers on screen in the marker
+    /// order, assigning their slots
+    fn cull(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let screen = self.screen(ctx);
+        let mut drawn: Vec<usize> = (0..self.instances.len())
+            .filter(|&index| {
+                self.instances.get(index).is_some_and(|instance| {
+                    on_screen(screen, instance.x, instance.y) && !self.is_hidden(index)
+                })
+            })
+            .collect();
+
+        let intensity = |index: &usize| self.instances.get(*index).map_or(0.0, |i| i.intensity);
+        let by_intensity = |a: &usize, b: &usize| intensity(a).total_cmp(&intensity(b));
+        if let Some(limit) = self.order.limit(self.zoom)
+            && drawn.len() > limit
+        {
+            // Stable, so the earlier of equally intense markers is dropped
+            drawn.sort_by(by_intensity);
+            drawn.drain(..drawn.len() - limit);
+            if !self.order.by_intensity {
+                drawn.sort_unstable();
+            }
+        } else if self.order.by_intensity {
+            drawn.sort_by(by_intensity);
+        }
+
+        self.uploaded.reset(drawn.len());
+        self.slots.clear();
+        self.slots.resize(self.instances.len(), None);
+        for (slot, index) in drawn.into_iter().enumerate() {
+            let Some(&instance) = self.instances.get(index) else {
+                continue;
+            };
+            self.uploaded
+                .push(instance)
+                .map_err(|_| RenderError::BufferAllocationFailed {
+                    size: std::mem::size_of_val(&instance) * self.instances.len(),
+                })?;
+            self.slots[index] = Some(slot);
+        }
+
+        self.renderer.upload(ctx, &self.uploaded);
+        Ok(())
+    }
+}
+
+impl Layer for MarkerLayer {
+    /// Also applies the style's zoom rules for the zoom the canvas width
+    /// sets
+    #[allow(clippy::cast_possible_truncation)]
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.zoom = zoom_level(f64::from(ctx.css_width())) as f32;
+        self.builder.set_opacity(self.style.opacity_at(self.zoom));
+
+        let mut bounds: Option<[f32; 4]> = None;
+        let mut index = SpatialIndex::new();
+        self.instances.reset(self.markers.len());
+
+        for (position, marker) in self.markers.iter().enumerate() {
+            let [x, y] = project_marker(projection, marker);
+            bounds = Some(extend_bounds(bounds, x, y));
+            index.insert(point_bounds(x, y), position);
+
+            let instance = self.builder.build(marker, x, y);
+            self.instances
+                .push(instance)
+                .map_err(|_| RenderError::BufferAllocationFailed {
+                    size: std::mem::size_of_val(&instance) * self.markers.len(),
+                })?;
+        }
+
+        self.bounds = bounds;
+        self.index = index;
+        self.cull(ctx)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let ctx = frame.ctx;
+        let size = self.drawn_radius() * ctx.pixel_ratio() as f32;
+
+        if frame.theme.markers.glow <= 0.0 {
+            self.renderer.draw(ctx, size);
+            return;
+        }
+
+        if let Err(e) = self.bloom.begin_layer(ctx) {
+            log::warn(
+                "layer",
+                "Marker glow disabled for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+        self.renderer.draw(ctx, size);
+        self.bloom.finish_layer(
+            ctx,
+            frame.post,
+            BloomSettings {
+                radius: frame.theme.markers.glow,
+                ..BloomSettings::default()
+            },
+        );
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+        self.bloom.delete(gl);
+    }
+
+    fn is_animated(&self) -> bool {
+        self.renderer.instance_count() > 0
+    }
+
+    /// Rebuilds the instances so markers without a color take the new
+    /// theme's marker color or scale
+    fn set_theme(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        theme: &Theme,
+    ) -> Result<(), RenderError> {
+        self.builder = InstanceBuilder::new(theme.markers);
+        self.style = theme.markers;
+        self.reproject(ctx, projection)
+    }
+
+    /// Culls the markers to the part of the map now on screen
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.cull(ctx)
+    }
+
+    /// Draws each marker drawn on the canvas at its own radius, faded by its
+    /// intensity, both following the zoom rules
+    fn export_svg(&self, svg: &mut SvgDocument, projection: &dyn Projection, theme: &Theme) {
+        let (scale, opacity) = (
+            theme.markers.radius_at(self.zoom),
+            theme.markers.opacity_at(self.zoom),
+        );
+        let mut drawn: Vec<usize> = (0..self.markers.len())
+            .filter(|&position| self.is_drawn(position))
+            .collect();
+        drawn.sort_by_key(|&position| self.slots[position]);
+        for marker in drawn
+            .into_iter()
+            .filter_map(|position| self.markers.get(position))
+        {
+            let color = marker
+                .color
+                .unwrap_or_else(|| theme.markers.color_at(marker.intensity));
+            svg.circle(
+                projection.project(marker.coord),
+                marker.radius * scale,
+                Color {
+                    a: (color.a * marker.intensity * opacity).min(1.0),
+                    ..color
+                },
+            );
+        }
+    }
+
+    /// Covers every marker at full pulse size plus its glow
+    #[allow(clippy::cast_possible_truncation)]
+    fn damage(&self, frame: &LayerFrame<'_>) -> Option<ScissorRect> {
+        let [min_x, min_y, max_x, max_y] = self.bounds?;
+        let glow = frame.theme.markers.glow.max(0.0);
+        // One extra pixel for filtering at the glow edge
+        let margin = (self.drawn_radius() + glow).mul_add(frame.ctx.pixel_ratio() as f32, 1.0);
+
+        frame.ctx.scissor_for([
+            min_x - margin,
+            min_y - margin,
+            max_x + margin,
+            max_y + margin,
+        ])
+    }
+
+    /// Picks the drawn marker nearest to `point` within the drawn radius,
+    /// the one drawn on top on ties
+    #[allow(clippy::cast_possible_truncation)]
+    fn pick_marker(
+        &self,
+        projection: &dyn Projection,
+        point: [f32; 2],
+        pixel_ratio: f32,
+    ) -> Option<&Marker> {
+        let reach = self.drawn_radius() * pixel_ratio;
+        let [x, y, r] = [point[0], point[1], reach].map(f64::from);
+
+        self.index
+            .query([x - r, y - r, x + r, y + r])
+            .filter_map(|&position| {
+                let slot = self.slots.get(position).copied().flatten()?;
+                let marker = self.markers.get(position)?;
+                let center = projection.project(marker.coord);
+                let distance =
+                    (center.x as f32 - point[0]).hypot(center.y as f32 - point[1]);
+                (distance <= reach).then_some((distance, slot, marker))
+            })
+            // The last drawn of equally near markers wins
+            .min_by(|(a, i, _), (b, j, _)| a.total_cmp(b).then(j.cmp(i)))
+            .map(|(_, _, marker)| marker)
+    }
+
+    fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    /// Re-uploads only the span of instances that changed when the marker
+    /// ids are the same and in the same order, and everything otherwise
+    fn update_markers(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        markers: &[Marker],
+    ) -> Result<bool, RenderError> {
+        let same_ids = markers.len() == self.markers.len()
+            && markers
+                .iter()
+                .zip(&self.markers)
+                .all(|(new, old)| new.id == old.id);
+        let radius = max_radius(markers);
+        // A new radius moves the screen margin, which may cull any marker
+        let resized = (radius - self.radius).abs() > 0.0;
+        markers.clone_into(&mut self.markers);
+        self.radius = radius;
+        if !same_ids {
+            self.reproject(ctx, projection)?;
+            return Ok(true);
+        }
+
+        let screen = self.screen(ctx);
+        let mut bounds: Option<[f32; 4]> = None;
+        let mut changed: Option<Range<usize>> = None;
+        // A changed marker may move in the order or in or out of the limit
+        let mut recull = resized || self.order.reorders(self.zoom);
+        for (index, marker) in self.markers.iter().enumerate() {
+            let [x, y] = project_marker(projection, marker);
+            bounds = Some(extend_bounds(bounds, x, y));
+
+            let instance = self.builder.build(marker, x, y);
+            let Some(old) = self.instances.get(index).copied() else {
+                continue;
+            };
+            if old == instance {
+                continue;
+            }
+
+            self.index.remove(point_bounds(old.x, old.y), &index);
+            self.index.insert(point_bounds(x, y), index);
+            self.instances.update(index, instance).map_err(|_| {
+                RenderError::BufferAllocationFailed {
+                    size: std::mem::size_of_val(&instance) * self.markers.len(),
+                }
+            })?;
+
+            // Markers staying on screen are updated in their slot, markers
+            // entering or leaving it reassign every slot
+            match (
+                self.slots.get(index).copied().flatten(),
+                on_screen(screen, x, y) && !self.is_hidden(index),
+            ) {
+                (Some(slot), true) if !recull => {
+                    self.uploaded.update(slot, instance).map_err(|_| {
+                        RenderError::BufferAllocationFailed {
+                            size: std::mem::size_of_val(&instance) * self.uploaded.len(),
+                        }
+                    })?;
+                    changed = Some(changed.map_or(slot..slot + 1, |range| range.start..slot + 1));
+                },
+                (None, false) => {},
+                _ => recull = true,
+            }
+        }
+
+        if recull {
+            self.cull(ctx)?;
+        } else if let Some(range) = changed {
+            self.renderer.update(ctx, &self.uploaded, range);
+        }
+        self.bounds = bounds;
+        Ok(true)
+    }
+
+    fn set_hidden_groups(
+        &mut self,
+        ctx: &RenderContext,
+        groups: &HashSet<String>,
+    ) -> Result<bool, RenderError> {
+        groups.clone_into(&mut self.hidden_groups);
+        self.cull(ctx)?;
+        Ok(true)
+    }
+
+    fn set_marker_order(
+        &mut self,
+        ctx: &RenderContext,
+        order: MarkerOrder,
+    ) -> Result<bool, RenderError> {
+        self.order = order;
+        self.cull(ctx)?;
+        Ok(true)
+    }
+
+    fn set_fragment_shader(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        source: Option<&str>,
+    ) -> Result<bool, RenderError> {
+        let renderer = MarkerRenderer::with_fragment(ctx, source)?;
+        std::mem::replace(&mut self.renderer, renderer).delete(ctx.gl());
+        self.reproject(ctx, projection)?;
+        Ok(true)
+    }
+}
+
+/// Largest marker radius, which every marker is drawn with
+fn max_radius(markers: &[Marker]) -> f32 {
+    markers
+        .iter()
+        .map(|marker| marker.radius)
+        .fold(0.0, f32::max)
+}
+
+/// Projects a marker center to drawing buffer pixels
+#[allow(clippy::cast_possible_truncation)]
+fn project_marker(projection: &dyn Projection, marker: &Marker) -> [f32; 2] {
+    let point = projection.project(marker.coord);
+    [point.x as f32, point.y as f32]
+}
+
+/// Returns true if `x`, `y` lies in `screen`, edges included
+fn on_screen(screen: [f32; 4], x: f32, y: f32) -> bool {
+    (screen[0]..=screen[2]).contains(&x) && (screen[1]..=screen[3]).contains(&y)
+}
+
+/// Returns the spatial index box of a projected point
+fn point_bounds(x: f32, y: f32) -> [f64; 4] {
+    let (x, y) = (f64::from(x), f64::from(y));
+    [x, y, x, y]
+}
+
+/// Grows `[min_x, min_y, max_x, max_y]` bounds to include a point
+fn extend_bounds(bounds: Option<[f32; 4]>, x: f32, y: f32) -> [f32; 4] {
+    bounds.map_or([x, y, x, y], |[min_x, min_y, max_x, max_y]| {
+        [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+    })
+}
+
+struct Entry {
+    id: String,
+    layer: Box<dyn Layer>,
+    visible: bool,
+    blend: BlendMode,
+}
+
+/// Line layers drawn together, see [`LayerStack::draw`]
+struct CachedBatch {
+    /// [`BatchLines::revision`] of each member, bottom to top
+    revisions: Vec<u64>,
+    batch: LineBatch,
+    /// Drawn this frame
+    used: bool,
+}
+
+/// Ordered set of named layers, drawn bottom to top
+#[derive(Default)]
+pub struct LayerStack {
+    entries: Vec<Entry>,
+    batches: Vec<CachedBatch>,
+    /// Paint of the members of the batch being drawn, kept to reuse its
+    /// allocation
+    styles: Vec<BatchStyle>,
+}
+
+impl LayerStack {
+    /// Creates an empty stack
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the layer ids from bottom to top
+    pub fn ids(&self) -> Vec<String> {
+        self.entries.iter().map(|entry| entry.id.clone()).collect()
+    }
+
+    /// Returns the position of a layer, 0 being the bottom
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    /// Adds a visible layer, returning the one it replaced
+    ///
+    /// An existing layer with the same id is replaced in place, keeping its
+    /// blend mode; otherwise the layer is inserted at `index` (clamped), or
+    /// on top when `None`, with normal blending.
+    pub fn set(
+        &mut self,
+        id: &str,
+        layer: Box<dyn Layer>,
+        index: Option<usize>,
+    ) -> Option<Box<dyn Layer>> {
+        if let Some(position) = self.position(id) {
+            self.entries[position].visible = true;
+            return Some(std::mem::replace(&mut self.entries[position].layer, layer));
+        }
+
+        let index = index.map_or(self.entries.len(), |i| i.min(self.entries.len()));
+        self.entries.insert(
+            index,
+            Entry {
+                id: id.to_string(),
+                layer,
+                visible: true,
+                blend: BlendMode::Normal,
+            },
+        );
+        None
+    }
+
+    /// Removes a layer, returning it if it existed
+    pub fn remove(&mut self, id: &str) -> Option<Box<dyn Layer>> {
+        self.position(id)
+            .map(|position| self.entries.remove(position).layer)
+    }
+
+    /// Removes every layer, returning them from bottom to top
+    pub fn clear(&mut self) -> Vec<Box<dyn Layer>> {
+        self.entries.drain(..).map(|entry| entry.layer).collect()
+    }
+
+    /// Moves a layer to `index` (clamped), returning true if it existed
+    pub fn move_to(&mut self, id: &str, index: usize) -> bool {
+        let Some(position) = self.position(id) else {
+            return false;
+        };
+
+        let entry = self.entries.remove(position);
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, entry);
+        true
+    }
+
+    /// Sets the opacity of a layer
+    ///
+    /// Returns `None` for an unknown id, and otherwise whether the layer has
+    /// an opacity control.
+    pub fn set_opacity(&mut self, id: &str, opacity: f32) -> Option<bool> {
+        let position = self.position(id)?;
+        Some(self.entries[position].layer.set_opacity(opacity))
+    }
+
+    /// Replaces the fragment shader of a layer, see
+    /// [`Layer::set_fragment_shader`]
+    ///
+    /// Returns `None` if no layer has this id.
+    pub fn set_fragment_shader(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        source: Option<&str>,
+    ) -> Option<Result<bool, RenderError>> {
+        let position = self.position(id)?;
+        Some(
+            self.entries[position]
+                .layer
+                .set_fragment_shader(ctx, projection, source),
+        )
+    }
+
+    /// Sets the blend mode of a layer, returning `None` for an unknown id and
+    /// otherwise whether the mode changed
+    pub fn set_blend(&mut self, id: &str, blend: BlendMode) -> Option<bool> {
+        let position = self.position(id)?;
+        let entry = &mut self.entries[position];
+        let changed = entry.blend != blend;
+        entry.blend = blend;
+        Some(changed)
+    }
+
+    /// Returns the visibility of a layer
+    pub fn is_visible(&self, id: &str) -> Option<bool> {
+        self.position(id)
+            .map(|position| self.entries[position].visible)
+    }
+
+    /// Shows or hides a layer
+    ///
+    /// Returns `None` for an unknown id, and otherwise whether the visibility
+    /// changed.
+    pub fn set_visible(&mut self, id: &str, visible: bool) -> Option<bool> {
+        let position = self.position(id)?;
+        let entry = &mut self.entries[position];
+        let changed = entry.visible != visible;
+        entry.visible = visible;
+        Some(changed)
+    }
+
+    /// Replaces the markers of a layer in place, see
+    /// [`Layer::update_markers`]
+    ///
+    /// Returns `None` if no layer has this id.
+    pub fn update_markers(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        markers: &[Marker],
+    ) -> Option<Result<bool, RenderError>> {
+        let position = self.position(id)?;
+        Some(
+            self.entries[position]
+                .layer
+                .update_markers(ctx, projection, markers),
+        )
+    }
+
+    /// Reprojects every layer, hidden ones included
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`RenderError`] a layer reports
+    pub fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|entry| entry.layer.reproject(ctx, projection))
+    }
+
+    /// Applies a new theme to every layer, hidden ones included
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`RenderError`] a layer reports
+    pub fn set_theme(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        theme: &Theme,
+    ) -> Result<(), RenderError> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|entry| entry.layer.set_theme(ctx, projection, theme))
+    }
+
+    /// Applies a new view to every layer, hidden ones included
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`RenderError`] a layer reports
+    pub fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|entry| entry.layer.set_view(ctx, projection))
+    }
+
+    /// Draws the visible layers from bottom to top, each with its blend mode
+    ///
+    /// Runs of up to [`MAX_BATCH_MEMBERS`] adjacent line layers with the same
+    /// blend mode, see [`Layer::batch_lines`], share one draw call. Their
+    /// [`LineBatch`] is kept while its meshes stay the same and freed the
+    /// first frame it isn't drawn.
+    ///
+    /// With a `timer`, each layer is timed as a pass named by its id, and
+    /// each batch as a pass named by the ids of its layers joined with `+`.
+    pub fn draw(&mut self, frame: &LayerFrame<'_>, timer: Option<&GpuTimer>) {
+        for cached in &mut self.batches {
+            cached.used = false;
+        }
+
+        let mut start = 0;
+        while start < self.entries.len() {
+            let group = batch_group(&self.entries[start..]);
+            if group > 1
+                && draw_batch(
+                    &self.entries[start..start + group],
+                    &mut self.batches,
+                    &mut self.styles,
+                    frame,
+                    timer,
+                )
+            {
+                start += group;
+                continue;
+            }
+
+            let entry = &mut self.entries[start];
+            start += 1;
+            if !entry.visible {
+                continue;
+            }
+            if let Some(timer) = timer {
+                timer.begin(frame.ctx.gl(), &entry.id);
+            }
+            frame.ctx.set_blend_mode(entry.blend);
+            entry.layer.draw(frame);
+        }
+        if let Some(timer) = timer {
+            timer.end(frame.ctx.gl());
+        }
+        frame.ctx.set_blend_mode(BlendMode::Normal);
+
+        self.batches.retain(|cached| {
+            if !cached.used {
+                cached.batch.delete(frame.ctx.gl());
+            }
+            cached.used
+        });
+    }
+
+    /// Frees the GPU resources of the line batches
+    pub fn delete_batches(&mut self, gl: &Gl) {
+        for cached in self.batches.drain(..) {
+            cached.batch.delete(gl);
+        }
+    }
+
+    /// Returns the marker at `point` on the topmost visible layer that has
+    /// one there, see [`Layer::pick_marker`]
+    pub fn pick_marker(
+        &self,
+        projection: &dyn Projection,
+        point: [f32; 2],
+        pixel_ratio: f32,
+    ) -> Option<&Marker> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.visible)
+            .find_map(|entry| entry.layer.pick_marker(projection, point, pixel_ratio))
+    }
+
+    /// Returns the markers of all layers from bottom to top, hidden ones
+    /// included
+    pub fn markers(&self) -> impl Iterator<Item = &Marker> {
+        self.entries.iter().flat_map(|entry| entry.layer.markers())
+    }
+
+    /// Returns the number of markers across all layers, hidden ones included
+    pub fn marker_count(&self) -> usize {
+        self.markers().count()
+    }
+
+    /// Hides the markers of every layer whose group is in `groups`
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`RenderError`] a layer reports
+    pub fn set_hidden_groups(
+        &mut self,
+        ctx: &RenderContext,
+        groups: &HashSet<String>,
+    ) -> Result<(), RenderError> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|entry| entry.layer.set_hidden_groups(ctx, groups).map(drop))
+    }
+
+    /// Sets the marker order of every layer drawing markers
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if a layer's instances can't be uploaded
+    pub fn set_marker_order(
+        &mut self,
+        ctx: &RenderContext,
+        order: MarkerOrder,
+    ) -> Result<(), RenderError> {
+        self.entries
+            .iter_mut()
+            .try_for_each(|entry| entry.layer.set_marker_order(ctx, order).map(drop))
+    }
+
+    /// Returns the first marker whose id reads `id`, hidden layers included
+    pub fn find_marker(&self, id: &str) -> Option<&Marker> {
+        self.markers().find(|marker| marker.id.to_string() == id)
+    }
+
+    /// Moves the focus ring of a layer to `marker`
+    ///
+    /// Returns `None` if no layer has this id, and `Some(false)` if the
+    /// layer draws no focus ring.
+    pub fn focus_marker(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        marker: Option<&Marker>,
+    ) -> Option<bool> {
+        let position = self.position(id)?;
+        Some(
+            self.entries[position]
+                .layer
+                .focus_marker(ctx, projection, marker),
+        )
+    }
+
+    /// Moves the selection highlight of a layer to `marker`
+    ///
+    /// Returns `None` if no layer has this id, and `Some(false)` if the
+    /// layer draws no selection.
+    pub fn select_marker(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        marker: Option<&Marker>,
+    ) -> Option<bool> {
+        let position = self.position(id)?;
+        Some(
+            self.entries[position]
+                .layer
+                .select_marker(ctx, projection, marker),
+        )
+    }
+
+    /// Starts ripples on the layer with `id`, see [`Layer::add_ripples`]
+    ///
+    /// Returns `None` if no layer has this id, and `Some(false)` if the
+    /// layer draws no ripples.
+    pub fn add_ripples(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        markers: &[Marker],
+        time: f32,
+    ) -> Option<bool> {
+        let position = self.position(id)?;
+        Some(
+            self.entries[position]
+                .layer
+                .add_ripples(ctx, projection, markers, time),
+        )
+    }
+
+    /// Moves the pointer position of the layer with `id`, see
+    /// [`Layer::set_pointer`]
+    ///
+    /// Returns `None` if no layer has this id, and `Some(false)` if the
+    /// layer doesn't follow the pointer.
+    pub fn set_pointer(
+        &mut self,
+        id: &str,
+        ctx: &RenderContext,
+        coord: Option<GeoCoord>,
+    ) -> Option<bool> {
+        let position = self.position(id)?;
+        Some(self.entries[position].layer.set_pointer(ctx, coord))
+    }
+
+    /// Returns true if the layer with `id` is visible and has a control at
+    /// `point`, see [`Layer::hit`]
+    ///
+    /// Returns `None` if no layer has this id.
+    pub fn hit(&self, id: &str, point: [f32; 2]) -> Option<bool> {
+        let entry = &self.entries[self.position(id)?];
+        Some(entry.visible && entry.layer.hit(point))
+    }
+
+    /// Returns the feature of the layer with `id` containing `coord`, see
+    /// [`Layer::feature_at`]
+    pub fn feature_at(&self, id: &str, coord: GeoCoord) -> Option<&Feature> {
+        let position = self.position(id)?;
+        self.entries[position].layer.feature_at(coord)
+    }
+
+    /// Adds the visible layers to an SVG export from bottom to top
+    pub fn export_svg(&self, svg: &mut SvgDocument, projection: &dyn Projection, theme: &Theme) {
+        for entry in self.entries.iter().filter(|entry| entry.visible) {
+            entry.layer.export_svg(svg, projection, theme);
+        }
+    }
+
+    /// Returns true if any visible layer is animated
+    pub fn is_animated(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.visible && entry.layer.is_animated())
+    }
+
+    /// Returns the region covering what the visible animated layers change
+    /// between frames
+    ///
+    /// `None` if a layer may change anywhere or nothing is animated.
+    pub fn damage(&self, frame: &LayerFrame<'_>) -> Option<ScissorRect> {
+        let mut region: Option<ScissorRect> = None;
+
+        for entry in self
+            .entries
+            .iter()
+            .filter(|entry| entry.visible && entry.layer.is_animated())
+        {
+            let rect = entry.layer.damage(frame)?;
+            region = Some(region.map_or(rect, |region| region.union(rect)));
+        }
+
+        region
+    }
+}
+
+/// Returns how many of `entries`, from the first, can share a line batch
+fn batch_group(entries: &[Entry]) -> usize {
+    let Some(first) = entries.first() else {
+        return 0;
+    };
+    entries
+        .iter()
+        .take(MAX_BATCH_MEMBERS)
+        .take_while(|entry| {
+            entry.visible && entry.blend == first.blend && entry.layer.batch_lines().is_some()
+        })
+        .count()
+}
+
+/// Draws `entries` as one line batch, building it unless cached, with
+/// `styles` as scratch space for their paint
+///
+/// Returns false if the batch can't be built, leaving the layers to be drawn
+/// on their own.
+fn draw_batch(
+    entries: &[Entry],
+    batches: &mut Vec<CachedBatch>,
+    styles: &mut Vec<BatchStyle>,
+    frame: &LayerFrame<'_>,
+    timer: Option<&GpuTimer>,
+) -> bool {
+    let lines = || entries.iter().filter_map(|entry| entry.layer.batch_lines());
+
+    let cached = batches.iter().position(|cached| {
+        cached
+            .revisions
+            .iter()
+            .copied()
+            .eq(lines().map(|lines| lines.revision))
+    });
+    let position = if let Some(position) = cached {
+        position
+    } else {
+        let meshes: Vec<(&LineMesh, [u32; 2])> =
+            lines().map(|lines| (lines.mesh, lines.size)).collect();
+        match LineBatch::new(frame.ctx, &meshes) {
+            Ok(batch) => {
+                batches.push(CachedBatch {
+                    revisions: lines().map(|lines| lines.revision).collect(),
+                    batch,
+                    used: false,
+                });
+                batches.len() - 1
+            },
+            Err(e) => {
+                log::warn(
+                    "layer",
+                    "Line batch not built",
+                    &[("error", e.to_string().into())],
+                );
+                return false;
+            },
+        }
+    };
+    let cached = &mut batches[position];
+    cached.used = true;
+    cached.batch.resize(frame.ctx.width(), frame.ctx.height());
+
+    styles.clear();
+    styles.extend(lines().map(|lines| lines.paint.batch_style(frame)));
+    if let Some(timer) = timer {
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        timer.begin(frame.ctx.gl(), &ids.join("+"));
+    }
+    frame.ctx.set_blend_mode(entries[0].blend);
+    cached.batch.draw(frame.ctx, styles);
+    true
+}
+
+/// Returns the channels of `color` as a shader uniform value
+pub const fn color_array(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
@@ -2,93 +2,1882 @@
 //!
 //! WebAssembly component for rendering interactive world maps in the browser.
 
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Function, Promise};
 use wasm_bindgen::prelude::*;
-use web_sys::HtmlCanvasElement;
-use wmc_core::{projection::MercatorProjection, theme::Theme, topology::WorldTopology};
-use wmc_render::{RenderContext, WorldRenderer};
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{HtmlCanvasElement, OffscreenCanvas};
+use wmc_core::{
+    arc::Arc,
+    geodesy,
+    heatmap::HeatPoint,
+    history::MarkerEdit,
+    label::FeatureLabel,
+    legend::Legend,
+    marker::Marker,
+    projection::{GeoBounds, GeoCoord},
+    scale_bar::ScaleUnit,
+    theme::Theme,
+    topology::{Feature, Geometry, WorldTopology},
+};
+use wmc_render::{
+    BUILTIN_PROGRAMS, BlendMode, GpuTexture, RenderBackend, RenderContext, ShaderProgram, View,
+    log,
+};
 
+mod accessibility;
+mod arc_layer;
+mod compass_layer;
+mod css_theme;
+mod dirty;
+mod element;
 /// Component error types
 pub mod error;
+mod events;
+mod focus_layer;
+mod geometry_worker;
+mod gestures;
+mod global;
+mod hash_sync;
+mod heatmap_layer;
+mod label_layer;
+mod layer;
+mod loader;
+mod logging;
+mod marker_poll;
+/// Component construction options
+pub mod options;
+mod panic;
+mod pixel_ratio;
+mod readout_layer;
+mod ready;
+mod render_loop;
+mod ripple_layer;
+mod scale_bar_layer;
+mod selection_layer;
+mod state;
+mod theme_builder;
+mod tile_layer;
+mod visibility;
+pub mod worker;
 
+pub use element::{WorldMapElement, define_world_map_element};
 pub use error::ComponentError;
+pub use geometry_worker::handle_geometry_message;
+pub use logging::{set_log_level, set_logger};
+pub use options::{
+    ArcLayerOptions, CompassOptions, CoordinateFormat, CoordinateReadoutOptions,
+    GraticuleLabelOptions, HeatmapLayerOptions, Interactions, LabelLayerOptions, LayerKind,
+    LayerOptions, LegendOptions, LegendScale, MapOptions, MarkerOrder, ScaleBarCorner,
+    ScaleBarOptions, TileLayerOptions,
+};
+pub use theme_builder::JsThemeBuilder;
+pub use worker::WorkerMessage;
+
+use crate::{
+    accessibility::MarkerList,
+    arc_layer::{ArcLayer, ArcPaint},
+    css_theme::CssTheme,
+    error::MapError,
+    events::{MapEvent, PointerListener},
+    focus_layer::FocusLayer,
+    geometry_worker::GeometryWorker,
+    hash_sync::HashSync,
+    heatmap_layer::HeatmapLayer,
+    label_layer::{GridLabelLayer, LabelLayer, LabelPaint},
+    layer::{ImageLayer, Paint},
+    marker_poll::MarkerPoll,
+    render_loop::RenderLoop,
+    state::MapState,
+    tile_layer::{TileLayer, TileSource},
+    visibility::VisibilityWatch,
+};
+
+/// Counts allocations for [`WorldMap::stats`]
+///
+/// Only with the `alloc-stats` feature, as the allocator is global to the
+/// final binary and a crate depending on this one may bring its own.
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: wmc_render::CountingAllocator<std::alloc::System> =
+    wmc_render::CountingAllocator(std::alloc::System);
 
 const WORLD_GEOJSON: &str = include_str!("../../../assets/world-110m.geojson");
 
+/// Straight segments approximating the ring of a circle layer
+const CIRCLE_SEGMENTS: usize = 128;
+
 /// World map component for WebAssembly
 #[wasm_bindgen]
 pub struct WorldMap {
-    ctx: RenderContext,
-    world_renderer: WorldRenderer,
-    theme: Theme,
-    topology: WorldTopology,
+    state: Rc<RefCell<MapState>>,
+    render_loop: RenderLoop,
+    /// Suspends the loop while the canvas isn't visible, on the main thread
+    visibility: Option<VisibilityWatch>,
 }
 
 #[wasm_bindgen]
 impl WorldMap {
     /// Creates a new world map component
     ///
+    /// `options` is an optional JSON string deserialized into [`MapOptions`],
+    /// e.g. `{"context": {"antialias": false}}`.
+    ///
     /// # Errors
     ///
-    /// Returns `JsValue` error if WebGL initialization or topology parsing fails
+    /// Returns `JsValue` error if the options are invalid, or WebGL
+    /// initialization or topology parsing fails
     #[wasm_bindgen(constructor)]
-    pub fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
-        console_error_panic_hook::set_once();
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(canvas: &HtmlCanvasElement, options: Option<String>) -> Result<Self, JsValue> {
+        panic::install();
+
+        let options = MapOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let theme = options.theme().map_err(error::to_js)?;
+
+        let ctx = RenderContext::with_options(canvas, options.context)
+            .map_err(error::to_js_in("WebGL init failed"))?;
 
-        let ctx = RenderContext::new(canvas)
-            .map_err(|e| JsValue::from_str(&format!("WebGL init failed: {e}")))?;
+        let mut map = Self::with_context(ctx, theme.clone())?;
+        pixel_ratio::watch(&map.state)?;
+        let pause = map.render_loop.pause_handle(&map.state);
+        map.visibility = Some(VisibilityWatch::watch(canvas, &pause)?);
+        let pointer = PointerListener::attach(canvas.clone(), &Rc::downgrade(&map.state))?;
+        {
+            let mut state = map.state.borrow_mut();
+            state.pointer = Some(pointer);
+            state.set_interactions(options.interactions)?;
+        }
+        map.set_zoom_snap(options.zoom_snap)?;
+        if options.accessible {
+            map.set_accessible(true)?;
+        }
+        if options.css_theme {
+            let css = CssTheme::watch(&map.state, canvas.clone().into(), theme)?;
+            let mut state = map.state.borrow_mut();
+            state.css_theme = Some(css);
+            state.refresh_css_theme()?;
+        }
 
-        let topology = WorldTopology::from_geojson(WORLD_GEOJSON)
-            .map_err(|e| JsValue::from_str(&format!("Topology parse failed: {e}")))?;
+        Ok(map)
+    }
 
-        let projection = MercatorProjection::new(f64::from(ctx.width()), f64::from(ctx.height()));
+    /// Creates a world map rendering into an `OffscreenCanvas`
+    ///
+    /// Intended for Web Workers: the main thread transfers the canvas with
+    /// `transferControlToOffscreen()` after sizing it to CSS size ×
+    /// `devicePixelRatio`, passes that ratio here, and forwards resizes and
+    /// other events through [`WorldMap::handle_message`] (see the
+    /// [`worker`] module for the protocol).
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid, or WebGL
+    /// initialization or topology parsing fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn from_offscreen(
+        canvas: &OffscreenCanvas,
+        pixel_ratio: f64,
+        options: Option<String>,
+    ) -> Result<Self, JsValue> {
+        panic::install();
 
-        let world_renderer = WorldRenderer::new(&ctx, &topology, &projection)
-            .map_err(|e| JsValue::from_str(&format!("Renderer init failed: {e}")))?;
+        let options = MapOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let theme = options.theme().map_err(error::to_js)?;
 
-        let theme = Theme::dark_minimal();
+        let ctx = RenderContext::with_offscreen(canvas, options.context, pixel_ratio)
+            .map_err(error::to_js_in("WebGL init failed"))?;
 
-        Ok(Self {
-            ctx,
-            world_renderer,
-            theme,
-            topology,
-        })
+        let map = Self::with_context(ctx, theme)?;
+        map.set_zoom_snap(options.zoom_snap)?;
+        Ok(map)
     }
 
-    /// Resizes the map viewport
+    /// Applies a message forwarded from the main thread to a worker-hosted map
+    ///
+    /// `message` is the JSON form of a [`WorkerMessage`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the message is invalid or applying it fails
+    pub fn handle_message(&self, message: &str) -> Result<(), JsValue> {
+        let message = WorkerMessage::parse(message).map_err(error::to_js)?;
+
+        match message {
+            WorkerMessage::Resize {
+                width,
+                height,
+                pixel_ratio,
+            } => {
+                {
+                    let mut state = self.state.borrow_mut();
+                    if let Some(pixel_ratio) = pixel_ratio {
+                        state.set_pixel_ratio(pixel_ratio)?;
+                    }
+                    state.resize(width, height)?;
+                }
+                self.emit_view_change(width, height)
+            },
+            WorkerMessage::Start => self.start(),
+            WorkerMessage::Stop => {
+                self.stop();
+                Ok(())
+            },
+            WorkerMessage::Render => {
+                self.render();
+                Ok(())
+            },
+            WorkerMessage::SetLakesVisible { visible } => {
+                self.set_lakes_visible(visible);
+                Ok(())
+            },
+            WorkerMessage::SetRiversVisible { visible } => {
+                self.set_rivers_visible(visible);
+                Ok(())
+            },
+            WorkerMessage::SetLayerVisible { id, visible } => self.set_layer_visible(&id, visible),
+        }
+    }
+
+    /// Moves the parsing, simplification and projection of topologies
+    /// passed to [`WorldMap::load_world`] to `worker`, or back to the main
+    /// thread with `null`
+    ///
+    /// `worker` is a module worker that loads this package and hands its
+    /// messages to `handleGeometryMessage`, which posts the line mesh back
+    /// as transferred typed arrays, so the page only uploads it. Marker
+    /// feeds are still parsed on the main thread. Loads already sent to a
+    /// replaced worker complete with it.
+    ///
+    /// ```js
+    /// const worker = new Worker(new URL("./geometry.js", import.meta.url), {
+    ///   type: "module",
+    /// });
+    /// map.setGeometryWorker(worker);
+    /// await map.loadWorld(await (await fetch("world-10m.geojson")).text());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the worker can't be listened to
+    pub fn set_geometry_worker(&self, worker: Option<web_sys::Worker>) -> Result<(), JsValue> {
+        let worker = worker.map(GeometryWorker::attach).transpose()?;
+        self.state.borrow_mut().geometry_worker = worker.map(Rc::new);
+        Ok(())
+    }
+
+    /// Resizes the map viewport to the given CSS size
+    ///
+    /// The drawing buffer is sized at the CSS size multiplied by the device
+    /// pixel ratio, which is tracked automatically.
     ///
     /// # Errors
     ///
     /// Returns `JsValue` error if renderer reinitialization fails
-    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
-        self.ctx.resize(width, height);
+    pub fn resize(&self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.state.borrow_mut().resize(width, height)?;
+        self.emit_view_change(width, height)
+    }
+
+    /// Centers the map on `lat`, `lon`, keeping the zoom and bearing
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the coordinates are out of range or the
+    /// layers can't follow the view
+    pub fn set_center(&self, lat: f64, lon: f64) -> Result<(), JsValue> {
+        let coord = GeoCoord::new(lat, lon).map_err(error::to_js_in("Invalid center"))?;
+        let mut state = self.state.borrow_mut();
+        let view = View {
+            center: state.center_at(coord),
+            ..state.ctx.view()
+        };
+        state.set_view(view)
+    }
+
+    /// Returns the zoom of the map, 0 showing the whole world and each step
+    /// in doubling its scale
+    #[must_use]
+    pub fn zoom(&self) -> f32 {
+        self.state.borrow().ctx.view().zoom
+    }
+
+    /// Zooms the map about its center; see [`WorldMap::zoom`]
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view
+    pub fn set_zoom(&self, zoom: f32) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        let view = View {
+            zoom: if zoom.is_finite() { zoom } else { 0.0 },
+            ..state.ctx.view()
+        };
+        state.set_view(view)
+    }
+
+    /// Returns the zoom step the view settles on, 0 when zoom is continuous
+    #[must_use]
+    pub fn zoom_snap(&self) -> f32 {
+        self.state.borrow().zoom_snap
+    }
+
+    /// Makes every zoom settle on a multiple of `step`, rounding the
+    /// current one right away
+    ///
+    /// A `step` of 1 keeps to integer zooms, where raster tiles show at
+    /// their native scale; 0, or any step that isn't positive and finite,
+    /// keeps zoom continuous. Zooms are rounded about the center of the
+    /// map, see [`View::snap_zoom`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view
+    pub fn set_zoom_snap(&self, step: f32) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state.zoom_snap = if step > 0.0 && step.is_finite() {
+            step
+        } else {
+            0.0
+        };
+        let view = state.ctx.view();
+        state.set_view(view)
+    }
+
+    /// Returns the clockwise rotation of the map in degrees, 0 with north
+    /// up
+    #[must_use]
+    pub fn bearing(&self) -> f32 {
+        self.state.borrow().ctx.view().rotation.to_degrees()
+    }
+
+    /// Rotates the map clockwise about its center to `degrees`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view
+    pub fn set_bearing(&self, degrees: f32) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        let rotation = if degrees.is_finite() {
+            degrees.rem_euclid(360.0).to_radians()
+        } else {
+            0.0
+        };
+        let view = View {
+            rotation,
+            ..state.ctx.view()
+        };
+        state.set_view(view)
+    }
+
+    /// Replaces the world topology with a `GeoJSON` `FeatureCollection`
+    ///
+    /// The document is parsed incrementally, yielding to the event loop
+    /// between chunks of features, so large datasets don't freeze the page;
+    /// `progress` events report the `parse` and `upload` stages. With a
+    /// [geometry worker](WorldMap::set_geometry_worker), the worker parses
+    /// and projects it instead, and `parse` is reported once it's done. The
+    /// returned promise resolves once the new world is in place.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn load_world(&self, geojson: String) -> Promise {
+        let state = Rc::clone(&self.state);
+        let readiness = Rc::clone(&self.state.borrow().readiness);
+        let events = Rc::clone(&self.state.borrow().events);
+        let worker = self.state.borrow().geometry_worker.clone();
+        readiness.begin();
+
+        future_to_promise(async move {
+            let loaded = if let Some(worker) = worker {
+                let (width, height) = {
+                    let state = state.borrow();
+                    (state.ctx.width(), state.ctx.height())
+                };
+                let built = worker.build_world(&geojson, width, height).await;
+                built.and_then(|lines| {
+                    let bytes = geojson.len() as f64;
+                    events.emit_progress("parse", bytes, Some(bytes));
+                    let features = lines.topology.features.len() as f64;
+                    state.borrow_mut().set_world_lines(lines)?;
+                    events.emit_progress("upload", features, Some(features));
+                    Ok(())
+                })
+            } else {
+                let parsed = loader::parse_topology_streaming(&geojson, |loaded, total| {
+                    events.emit_progress("parse", loaded, Some(total));
+                })
+                .await;
+                parsed.and_then(|topology| {
+                    let features = topology.features.len() as f64;
+                    state.borrow_mut().set_world(topology)?;
+                    events.emit_progress("upload", features, Some(features));
+                    Ok(())
+                })
+            };
+            readiness.finish(loaded.as_ref().copied());
+            match loaded {
+                Ok(()) => {
+                    events.emit(MapEvent::Load, &JsValue::UNDEFINED);
+                    Ok(JsValue::UNDEFINED)
+                },
+                Err(e) => {
+                    log::error("topology", "World not loaded", &[("error", e.clone())]);
+                    events.emit_error(&e);
+                    Err(e)
+                },
+            }
+        })
+    }
+
+    /// Returns a promise resolving once the map is ready to show
+    ///
+    /// The built-in topology is parsed and uploaded when the map is created;
+    /// the promise additionally waits for the built-in shaders, which compile
+    /// in the background where the browser supports it, pending
+    /// [`WorldMap::load_world`] calls and, for a `<world-map>` element, the
+    /// initial `markers-url` fetch. It resolves immediately when nothing is pending and rejects
+    /// with the error of the first load that fails.
+    pub fn ready(&self) -> Promise {
+        self.state.borrow().readiness.promise()
+    }
+
+    /// Subscribes `callback` to a map event
+    ///
+    /// `event` is one of:
+    ///
+    /// - `click`: the canvas was clicked, with `{lat, lon, x, y}`, `x` and
+    ///   `y` in CSS pixels from the canvas' top left
+    /// - `markerclick`: a marker was clicked, with `{marker, lat, lon, x, y}`
+    /// - `mapcontextmenu`: the canvas was right-clicked or long-pressed on a
+    ///   touch screen, with `{lat, lon, x, y, marker, country}`, `marker`
+    ///   and `country` being the marker and the world feature's properties
+    ///   under the pointer, or `null`; the browser's menu is suppressed
+    ///   while a callback is registered
+    /// - `pointermove`: the pointer moved over the canvas, with `{lat, lon,
+    ///   x, y}`, or `null` once it leaves the canvas
+    /// - `viewchange`: the map was resized, with `{width, height}` in CSS
+    ///   pixels
+    /// - `load`: a world topology from [`WorldMap::load_world`] is in place
+    /// - `select`: the user selected a marker or cleared the selection,
+    ///   with `{id, marker}`, see [`WorldMap::set_hash_sync`]
+    /// - `selectionchange`: the selected marker changed in any way,
+    ///   including through [`WorldMap::select_marker`], with `{id, marker}`
+    /// - `progress`: a load advanced, with `{stage, loaded, total}`; stages
+    ///   are `download`, `parse`, `upload` and `tiles`, and `total` is
+    ///   `null` when unknown
+    /// - `error`: an asynchronous operation failed, with the error object;
+    ///   a panic is reported with code `panic` and a state snapshot in its
+    ///   details, after which the module must be reloaded
+    ///
+    /// Callbacks receive the detail object and may call back into the map.
+    /// Pointer events are only reported for maps created on a canvas
+    /// element, and clicks while enabled with [`WorldMap::set_interactions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error for an unknown event
+    pub fn on(&self, event: &str, callback: Function) -> Result<(), JsValue> {
+        let event = parse_event(event)?;
+        self.state.borrow().events.on(event, callback);
+        Ok(())
+    }
+
+    /// Enables or disables pointer interactions given as JSON, e.g.
+    /// `{"zoom": false}` to leave the wheel to page scrolling,
+    /// `{"click": false, "markerClick": true}` to report only marker clicks
+    /// or `{"pan": false, "zoom": false, "rotate": false, "click": false,
+    /// "markerClick": false, "contextMenu": false}` for a static "poster"
+    /// map
+    ///
+    /// Omitted fields are enabled, see [`Interactions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid or the canvas style
+    /// can't be set
+    pub fn set_interactions(&self, json: &str) -> Result<(), JsValue> {
+        let interactions = Interactions::parse(json).map_err(error::to_js)?;
+        self.state.borrow_mut().set_interactions(interactions)
+    }
+
+    /// Synchronizes the selected marker and the view with `location.hash`,
+    /// making both shareable as links
+    ///
+    /// Clicking a marker selects it and clicking elsewhere clears the
+    /// selection, emitting `select` events. With synchronization on, every
+    /// selection pushes a history entry with `#marker=<id>`, and the view is
+    /// kept in `#map=<zoom>/<lat>/<lon>`, followed by `/<bearing>` when the
+    /// map is rotated; view changes replace the current entry rather than
+    /// pushing one. Other hash parameters are kept, and the hash is followed
+    /// through edits and back and forward navigation. Enabling shows the
+    /// view and selects the marker the current hash names, so a shared link
+    /// restores both on load; the `select` event's `marker` is `null` until
+    /// markers with that id are shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error outside a window, e.g. in a worker, or if the
+    /// hash's `map` parameter is malformed
+    pub fn set_hash_sync(&self, enabled: bool) -> Result<(), JsValue> {
+        if !enabled {
+            self.state.borrow_mut().hash_sync = None;
+            return Ok(());
+        }
+        if self.state.borrow().hash_sync.is_some() {
+            return Ok(());
+        }
+
+        let sync = HashSync::attach(Rc::downgrade(&self.state))?;
+        self.state.borrow_mut().hash_sync = Some(sync);
+        hash_sync::restore(&self.state)
+    }
+
+    /// Exposes the markers to screen readers and keyboard users
+    ///
+    /// Every marker gets a button in the canvas' fallback content, named by
+    /// its `meta.label` or `meta.name`, or its id, and its coordinates, so
+    /// screen readers list the markers and Tab moves between them. The
+    /// focused marker is ringed on the map, Enter or Space on it emits the
+    /// same `click`, `markerclick` and `select` events as clicking it, and
+    /// selections are announced through an `aria-live` region. The ring is
+    /// drawn by a `focus` layer on top of the stack.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error for maps without a canvas element, e.g. in a
+    /// worker, or if the list or the focus ring can't be created
+    pub fn set_accessible(&self, enabled: bool) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        if !enabled {
+            state.marker_list = None;
+            state.remove_layer(layer::FOCUS);
+            state.set_focus(None);
+            return Ok(());
+        }
+        if state.marker_list.is_some() {
+            return Ok(());
+        }
+
+        let canvas = state
+            .pointer
+            .as_ref()
+            .map(|pointer| pointer.canvas().clone())
+            .ok_or_else(|| error::to_js(ComponentError::ComponentNotMounted))?;
+        let list = MarkerList::attach(&canvas, &Rc::downgrade(&self.state))?;
+        let ring = FocusLayer::new(&state.ctx, &state.theme)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        state.set_layer(layer::FOCUS, Box::new(ring), None);
+        state.marker_list = Some(list);
+        state.markers_changed();
+        Ok(())
+    }
+
+    /// Returns the id of the selected marker, see
+    /// [`WorldMap::set_hash_sync`]
+    #[must_use]
+    pub fn selected_marker(&self) -> Option<String> {
+        self.state.borrow().selected.clone()
+    }
+
+    /// Selects the marker with `id` as a click on it would, without
+    /// emitting click events
+    ///
+    /// The selected marker is drawn over the others in the theme's
+    /// `selection` style: recolored, scaled up and ringed. The selection
+    /// emits `selectionchange` and is mirrored into the URL hash when
+    /// synchronized. A marker with the id needn't be shown yet; it is
+    /// highlighted once it is. Returns false if it was selected already.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the hash can't be written or the event
+    /// detail can't be built
+    pub fn select_marker(&self, id: &str) -> Result<bool, JsValue> {
+        self.set_selection(Some(id.to_string()))
+    }
+
+    /// Clears the selection, emitting `selectionchange`
+    ///
+    /// Returns false if no marker was selected.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the hash can't be written or the event
+    /// detail can't be built
+    pub fn clear_selection(&self) -> Result<bool, JsValue> {
+        self.set_selection(None)
+    }
 
-        let projection = MercatorProjection::new(f64::from(width), f64::from(height));
+    /// Unsubscribes `callback` from a map event, or every callback of the
+    /// event when omitted
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error for an unknown event
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn off(&self, event: &str, callback: Option<Function>) -> Result<(), JsValue> {
+        let event = parse_event(event)?;
+        self.state.borrow().events.off(event, callback.as_ref());
+        Ok(())
+    }
+
+    /// Loads a lakes layer from a `GeoJSON` `FeatureCollection` of polygons
+    ///
+    /// Replaces any previously loaded lakes. The layer is visible after loading.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if parsing or renderer initialization fails
+    pub fn load_lakes(&self, geojson: &str) -> Result<(), JsValue> {
+        let topology = WorldTopology::from_geojson(geojson).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Lakes".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        self.state.borrow_mut().set_lakes(topology)
+    }
+
+    /// Loads a rivers layer from a `GeoJSON` `FeatureCollection` of lines
+    ///
+    /// Replaces any previously loaded rivers. The layer is visible after loading.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if parsing or renderer initialization fails
+    pub fn load_rivers(&self, geojson: &str) -> Result<(), JsValue> {
+        let topology = WorldTopology::from_geojson(geojson).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Rivers".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        self.state.borrow_mut().set_rivers(topology)
+    }
+
+    /// Shows or hides the lakes layer, if loaded
+    pub fn set_lakes_visible(&self, visible: bool) {
+        self.state
+            .borrow_mut()
+            .set_layer_visible(layer::LAKES, visible);
+    }
 
-        self.world_renderer = WorldRenderer::new(&self.ctx, &self.topology, &projection)
-            .map_err(|e| JsValue::from_str(&format!("Renderer reinit failed: {e}")))?;
+    /// Shows or hides the rivers layer, if loaded
+    pub fn set_rivers_visible(&self, visible: bool) {
+        self.state
+            .borrow_mut()
+            .set_layer_visible(layer::RIVERS, visible);
+    }
+
+    /// Replaces all markers with a JSON array of markers
+    ///
+    /// Markers are drawn above all other layers in a single instanced call
+    /// and glow according to the theme. The new markers start a new edit
+    /// history, see [`WorldMap::undo`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON or a marker is invalid, or if
+    /// instanced drawing is unavailable
+    pub fn set_markers(&self, json: &str) -> Result<(), JsValue> {
+        let markers = parse_markers(json)?;
+        self.state.borrow_mut().set_markers(markers)
+    }
 
+    /// Refetches markers from `url` now and then every `interval_ms`
+    /// milliseconds, replacing a previous poll
+    ///
+    /// `url` must return a JSON array of markers. The last response's `ETag`
+    /// is sent as `If-None-Match`, so an unchanged source isn't parsed or
+    /// uploaded again, and while marker ids stay the same only the markers
+    /// that changed are re-uploaded. Failed requests are emitted as `error`
+    /// events and polling continues.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the timer can't be started
+    pub fn poll_markers(&self, url: String, interval_ms: u32) -> Result<(), JsValue> {
+        let interval_ms = i32::try_from(interval_ms).unwrap_or(i32::MAX);
+        // Stop the previous poll first so its first response can't land
+        // after the new one's
+        self.state.borrow_mut().marker_poll = None;
+        let poll = MarkerPoll::start(url, interval_ms, Rc::downgrade(&self.state))?;
+        self.state.borrow_mut().marker_poll = Some(poll);
         Ok(())
     }
 
+    /// Stops polling started with [`WorldMap::poll_markers`], keeping the
+    /// current markers
+    pub fn stop_polling_markers(&self) {
+        self.state.borrow_mut().marker_poll = None;
+    }
+
+    /// Removes all markers, undoably
+    pub fn clear_markers(&self) {
+        self.state.borrow_mut().clear_markers();
+    }
+
+    /// Adds a JSON array of markers after the current ones, undoably
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON or a marker is invalid, or if
+    /// the markers can't be uploaded
+    pub fn add_markers(&self, json: &str) -> Result<(), JsValue> {
+        let added = parse_markers(json)?;
+        self.state.borrow_mut().edit_markers(|markers| {
+            let start = markers.len();
+            MarkerEdit::Insert((start..).zip(added).collect())
+        })
+    }
+
+    /// Moves the markers with `id` to `lat`, `lon`, undoably
+    ///
+    /// Returns false if no marker has the id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the coordinates are out of range or the
+    /// markers can't be uploaded
+    pub fn move_marker(&self, id: &str, lat: f64, lon: f64) -> Result<bool, JsValue> {
+        let to = GeoCoord::new(lat, lon).map_err(error::to_js_in("Invalid marker"))?;
+        let mut moved = false;
+        self.state.borrow_mut().edit_markers(|markers| {
+            let edits: Vec<_> = markers
+                .iter()
+                .enumerate()
+                .filter(|(_, marker)| marker.id.to_string() == id)
+                .map(|(position, marker)| (position, marker.coord, to))
+                .collect();
+            moved = !edits.is_empty();
+            MarkerEdit::Move(edits)
+        })?;
+        Ok(moved)
+    }
+
+    /// Removes the markers with `id`, undoably
+    ///
+    /// Returns false if no marker has the id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the remaining markers can't be uploaded
+    pub fn remove_marker(&self, id: &str) -> Result<bool, JsValue> {
+        let mut removed = false;
+        self.state.borrow_mut().edit_markers(|markers| {
+            let edit = MarkerEdit::remove_where(markers, |marker| marker.id.to_string() == id);
+            removed = !edit.is_empty();
+            edit
+        })?;
+        Ok(removed)
+    }
+
+    /// Undoes the last marker edit, returning false if there is none
+    ///
+    /// Adding, moving and removing markers, [`WorldMap::clear_markers`] and
+    /// [`WorldMap::clear_group`] are undoable, up to the last 100 edits.
+    /// Replacing the markers with [`WorldMap::set_markers`] or a poll starts
+    /// a new history.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn undo(&self) -> Result<bool, JsValue> {
+        self.state.borrow_mut().undo()
+    }
+
+    /// Redoes the last undone marker edit, returning false if there is none
+    ///
+    /// Any new edit drops the edits left to redo.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn redo(&self) -> Result<bool, JsValue> {
+        self.state.borrow_mut().redo()
+    }
+
+    /// Returns true if there is a marker edit to undo
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.state.borrow().history.can_undo()
+    }
+
+    /// Returns true if there is an undone marker edit to redo
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.state.borrow().history.can_redo()
+    }
+
+    /// Sets the order markers are drawn in given as JSON, e.g.
+    /// `{"byIntensity": true, "top": 500, "topMaxZoom": 2}` to draw the most
+    /// intense markers on top and, zoomed out, only the 500 most intense on
+    /// screen
+    ///
+    /// Markers left out aren't clicked or exported either. The order is
+    /// kept for markers set later; see [`MarkerOrder`] for the defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid or the markers can't be
+    /// uploaded
+    pub fn set_marker_order(&self, json: &str) -> Result<(), JsValue> {
+        let order = MarkerOrder::parse(json).map_err(error::to_js)?;
+        self.state.borrow_mut().set_marker_order(order)
+    }
+
+    /// Shows the markers whose `group` is `group` again
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn show_group(&self, group: &str) -> Result<(), JsValue> {
+        self.state
+            .borrow_mut()
+            .set_group_visible(group, true)
+            .map(drop)
+    }
+
+    /// Hides the markers whose `group` is `group`, e.g. to toggle a category
+    /// of a dashboard in one call
+    ///
+    /// Hidden markers aren't drawn, clicked, exported or listed for
+    /// assistive technology. The group stays hidden for markers set later,
+    /// until [`WorldMap::show_group`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn hide_group(&self, group: &str) -> Result<(), JsValue> {
+        self.state
+            .borrow_mut()
+            .set_group_visible(group, false)
+            .map(drop)
+    }
+
+    /// Returns false while `group` is hidden with [`WorldMap::hide_group`]
+    #[must_use]
+    pub fn group_visible(&self, group: &str) -> bool {
+        !self.state.borrow().hidden_groups.contains(group)
+    }
+
+    /// Removes the markers whose `group` is `group`, undoably, returning
+    /// how many were removed
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the remaining markers can't be uploaded
+    pub fn clear_group(&self, group: &str) -> Result<usize, JsValue> {
+        self.state.borrow_mut().clear_group(group)
+    }
+
+    /// Switches to a theme given as JSON, e.g. one returned by
+    /// [`WorldMap::get_theme`] with some colors changed
+    ///
+    /// The background, built-in lines, default marker color, marker glow and
+    /// post effects change on the next frame. Layers added with explicit
+    /// colors, and labels styled from the previous theme, keep their look.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON or the theme is invalid, or if
+    /// the new styling can't be applied; the current theme stays then
+    pub fn set_theme(&self, theme_json: &str) -> Result<(), JsValue> {
+        let theme: Theme = serde_json::from_str(theme_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Theme".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+        theme.validate().map_err(error::to_js_in("Invalid theme"))?;
+
+        self.state.borrow_mut().set_theme(theme)
+    }
+
+    /// Returns the current theme as JSON, in the form
+    /// [`WorldMap::set_theme`] accepts
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the theme can't be serialized
+    pub fn get_theme(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.borrow().theme)
+            .map_err(error::to_js_in("Theme serialization failed"))
+    }
+
+    /// Returns the WCAG contrast ratios of the current theme's colors
+    /// against its background as JSON, with one ratio per style block, e.g.
+    /// `{"coastlines": 4.5, "rivers": 3.1, "lakes": 1.4, ..., "markers": 9.8}`
+    ///
+    /// Ratios range from 1 to 21; WCAG 2.1 asks for at least 3 for
+    /// graphical objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the report can't be serialized
+    pub fn contrast_report(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.state.borrow().theme.contrast_report())
+            .map_err(error::to_js_in("Contrast report serialization failed"))
+    }
+
+    /// Returns the layer ids from bottom to top
+    ///
+    /// Built-in layers are `"graticule"` (hidden by default),
+    /// `"graticule-labels"` once added, `"lakes"` and `"rivers"` once loaded,
+    /// `"world"`, `"markers"` once set, and `"scale-bar"` once added.
+    #[must_use]
+    pub fn layers(&self) -> Vec<String> {
+        self.state.borrow().layers.ids()
+    }
+
+    /// Adds a layer from a `GeoJSON` `FeatureCollection`
+    ///
+    /// `options` is an optional JSON string, see [`LayerOptions`]. A layer
+    /// with the same id is replaced in place and shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if parsing fails, the line style has invalid
+    /// lengths, or renderer initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_layer(
+        &self,
+        id: &str,
+        geojson: &str,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = LayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let topology = WorldTopology::from_geojson(geojson).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Layer".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+        self.add_topology_layer(id, topology, &options)
+    }
+
+    /// Adds a circle of `radius` meters over the ground around a point, e.g.
+    /// a range ring or coverage radius
+    ///
+    /// `options` is an optional JSON string, see [`LayerOptions`]; with
+    /// `"kind": "fill"` the circle is filled, otherwise outlined. Add two
+    /// layers for both. A layer with the same id is replaced in place and
+    /// shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the center, radius or options are invalid,
+    /// or renderer initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_circle_layer(
+        &self,
+        id: &str,
+        lat: f64,
+        lon: f64,
+        radius: f64,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = LayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let center = GeoCoord::new(lat, lon).map_err(error::to_js_in("Invalid circle center"))?;
+        if !radius.is_finite() || radius <= 0.0 {
+            return Err(MapError::internal(format!("Invalid circle radius: {radius}")).into());
+        }
+
+        let ring = geodesy::geodesic_circle(center, radius, CIRCLE_SEGMENTS);
+        let geometry = match options.kind {
+            LayerKind::Fill => Geometry::Polygon(vec![ring]),
+            LayerKind::Line => Geometry::MultiLineString(geodesy::split_antimeridian(&ring)),
+        };
+        let topology = WorldTopology {
+            features: vec![Feature {
+                geometry,
+                properties: serde_json::Map::new(),
+            }],
+        };
+        self.add_topology_layer(id, topology, &options)
+    }
+
+    /// Builds a line or fill layer of `topology` and adds it as `id`
+    fn add_topology_layer(
+        &self,
+        id: &str,
+        topology: WorldTopology,
+        options: &LayerOptions,
+    ) -> Result<(), JsValue> {
+        if let Some(style) = options.style.filter(|style| !style.is_valid()) {
+            return Err(MapError::internal(format!("Invalid line style: {style:?}")).into());
+        }
+
+        let mut state = self.state.borrow_mut();
+        let default = match options.kind {
+            LayerKind::Line => state.theme.borders,
+            LayerKind::Fill => state.theme.fills,
+        };
+        let paint = Paint::Fixed {
+            color: options.color.unwrap_or_else(|| default.effective_color()),
+            width: options.width.unwrap_or(default.width),
+            style: options.style.unwrap_or(default.style),
+        };
+        let layer = state
+            .build_layer(
+                topology,
+                paint,
+                (options.kind == LayerKind::Fill).then_some(options.fill_mode),
+            )
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        state.set_layer(id, layer, options.index);
+        state.set_layer_blend(id, options.blend);
+        Ok(())
+    }
+
+    /// Adds a raster basemap of XYZ tiles, e.g.
+    /// `"https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png"`
+    ///
+    /// `options` is an optional JSON string, see [`TileLayerOptions`]. New
+    /// tile layers go to the bottom of the stack, under the coastlines,
+    /// unless `index` says otherwise. Tiles load in the background and fade
+    /// in while the render loop runs. A layer with the same id is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid or renderer
+    /// initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_tile_layer(
+        &self,
+        id: &str,
+        url_template: &str,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = TileLayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+
+        let mut state = self.state.borrow_mut();
+        let layer = TileLayer::new(
+            &state.ctx,
+            TileSource {
+                template: url_template.to_string(),
+                subdomains: options.subdomains,
+                max_zoom: options.max_zoom,
+                tile_size: options.tile_size,
+                opacity: options.opacity,
+            },
+            Rc::downgrade(&state.events),
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        state.set_layer(id, Box::new(layer), Some(options.index));
+        Ok(())
+    }
+
+    /// Adds great-circle arcs from a JSON array of
+    /// `{"from": {"lat", "lon"}, "to": {"lat", "lon"}, "color"?}` objects
+    ///
+    /// `options` is an optional JSON string, see [`ArcLayerOptions`]. Arcs
+    /// bow towards the top of the map by their elevation, and their dashes
+    /// flow from `from` to `to` while the render loop runs. New arc layers go
+    /// below the markers, or on top without them, unless `index` says
+    /// otherwise. A layer with the same id is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON, an arc or the options are
+    /// invalid, or if renderer initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_arc_layer(
+        &self,
+        id: &str,
+        json: &str,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = ArcLayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let arcs: Vec<Arc> = serde_json::from_str(json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Arcs".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+        for arc in &arcs {
+            arc.validate().map_err(error::to_js_in("Invalid arc"))?;
+        }
+
+        let mut state = self.state.borrow_mut();
+        let paint = ArcPaint {
+            color: options
+                .color
+                .unwrap_or_else(|| state.theme.markers.effective_color()),
+            width: options.width,
+            style: options.style,
+            elevation: options.elevation,
+            speed: options.speed,
+        };
+        let layer = ArcLayer::new(&state.ctx, arcs, &state.projection(), paint)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = options
+            .index
+            .or_else(|| state.layers.position(layer::MARKERS));
+        state.set_layer(id, Box::new(layer), index);
+        Ok(())
+    }
+
+    /// Adds a density heatmap from a JSON array of
+    /// `{"coord": {"lat", "lon"}, "weight"?}` points
+    ///
+    /// `options` is an optional JSON string, see [`HeatmapLayerOptions`].
+    /// Points blend into a smooth density colored by a gradient ramp, which
+    /// reads better than markers for very dense datasets. New heatmaps go
+    /// below the markers, or on top without them, unless `index` says
+    /// otherwise. A layer with the same id is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON, a point or the options are
+    /// invalid, or if instanced drawing is unavailable
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_heatmap_layer(
+        &self,
+        id: &str,
+        json: &str,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = HeatmapLayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let points: Vec<HeatPoint> = serde_json::from_str(json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Heatmap".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+        for point in &points {
+            point
+                .validate()
+                .map_err(error::to_js_in("Invalid heat point"))?;
+        }
+
+        let mut state = self.state.borrow_mut();
+        let layer = HeatmapLayer::new(
+            &state.ctx,
+            points,
+            &state.projection(),
+            &options.gradient,
+            options.settings(),
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = options
+            .index
+            .or_else(|| state.layers.position(layer::MARKERS));
+        state.set_layer(id, Box::new(layer), index);
+        Ok(())
+    }
+
+    /// Adds feature name labels, e.g. country names, from a `GeoJSON`
+    /// `FeatureCollection` of polygons, or from the built-in countries when
+    /// `geojson` is omitted
+    ///
+    /// `options` is an optional JSON string, see [`LabelLayerOptions`]. Each
+    /// label sits inside its feature's largest polygon, farthest from its
+    /// edges, and appears once the zoom, which follows the canvas width and
+    /// the view's zoom, reaches the feature's `MIN_LABEL` property or its size allows. Larger
+    /// features win where labels would overlap. New label layers go below
+    /// the markers, or on top without them, unless `index` says otherwise. A
+    /// layer with the same id is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if parsing fails, the options are invalid, or
+    /// renderer initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_label_layer(
+        &self,
+        id: &str,
+        geojson: Option<String>,
+        options: Option<String>,
+    ) -> Result<(), JsValue> {
+        let options = LabelLayerOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        let topology = WorldTopology::from_geojson(geojson.as_deref().unwrap_or(WORLD_GEOJSON))
+            .map_err(|e| {
+                error::to_js(ComponentError::ParseFailed {
+                    input: "Layer".to_string(),
+                    reason: e.to_string(),
+                })
+            })?;
+        let labels = topology
+            .features
+            .iter()
+            .filter_map(|feature| {
+                FeatureLabel::from_feature(
+                    feature,
+                    &options.property,
+                    options.min_zoom_property.as_deref(),
+                    f64::from(options.min_size),
+                )
+            })
+            .collect();
+
+        let mut state = self.state.borrow_mut();
+        let paint = LabelPaint {
+            size: options.size,
+            color: options
+                .color
+                .unwrap_or_else(|| state.theme.background.contrasting()),
+            halo_color: options.halo_color.unwrap_or(state.theme.background),
+            halo_width: options.halo_width,
+            padding: options.padding,
+        };
+        let layer = LabelLayer::new(
+            &state.ctx,
+            labels,
+            &state.projection(),
+            paint,
+            &options.font,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = options
+            .index
+            .or_else(|| state.layers.position(layer::MARKERS));
+        state.set_layer(id, Box::new(layer), index);
+        Ok(())
+    }
+
+    /// Shows degree labels of the graticule lines along the map edges, e.g.
+    /// `30°N` on the left and `60°E` at the bottom
+    ///
+    /// `options` is an optional JSON string, see [`GraticuleLabelOptions`].
+    /// The labels form the `"graticule-labels"` layer, placed above the
+    /// graticule or replaced in place, and follow every resize. Labels that
+    /// would overlap are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid or renderer
+    /// initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_graticule_labels(&self, options: Option<String>) -> Result<(), JsValue> {
+        let options = GraticuleLabelOptions::parse(options.as_deref()).map_err(error::to_js)?;
+
+        let mut state = self.state.borrow_mut();
+        let paint = LabelPaint {
+            size: options.size,
+            color: options
+                .color
+                .unwrap_or_else(|| state.theme.background.contrasting()),
+            halo_color: options.halo_color.unwrap_or(state.theme.background),
+            halo_width: options.halo_width,
+            padding: options.padding,
+        };
+        let layer = GridLabelLayer::new(
+            &state.ctx,
+            options.step,
+            &state.projection(),
+            paint,
+            &options.font,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = state
+            .layers
+            .position(layer::GRATICULE)
+            .map(|position| position + 1);
+        state.set_layer(layer::GRATICULE_LABELS, Box::new(layer), index);
+        Ok(())
+    }
+
+    /// Shows a bar of a round ground distance in a map corner, e.g. `500 km`
+    ///
+    /// `options` is an optional JSON string, see [`ScaleBarOptions`]. The bar
+    /// forms the `"scale-bar"` layer, added on top or replaced in place. Its
+    /// length is refitted to the scale at the view center on every view
+    /// change.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid or renderer
+    /// initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_scale_bar(&self, options: Option<String>) -> Result<(), JsValue> {
+        let options = ScaleBarOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        self.state.borrow_mut().set_scale_bar(options)
+    }
+
+    /// Switches the scale bar between `"metric"` and `"imperial"` units,
+    /// keeping its other options
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the unit is unknown or no scale bar has
+    /// been added
+    pub fn set_scale_unit(&self, unit: &str) -> Result<(), JsValue> {
+        let unit = ScaleUnit::from_name(unit).ok_or_else(|| {
+            error::to_js(ComponentError::UnknownName {
+                kind: "scale unit".to_string(),
+                name: unit.to_string(),
+            })
+        })?;
+
+        let mut state = self.state.borrow_mut();
+        let options = state
+            .scale_bar
+            .clone()
+            .filter(|_| state.layers.position(layer::SCALE_BAR).is_some())
+            .ok_or_else(|| unknown_layer(layer::SCALE_BAR))?;
+        state.set_scale_bar(ScaleBarOptions { unit, ..options })
+    }
+
+    /// Shows the latitude and longitude under the pointer in a map corner,
+    /// e.g. `48.8566° N, 2.3522° E`
+    ///
+    /// `options` is an optional JSON string, see [`CoordinateReadoutOptions`].
+    /// The readout forms the `"coordinate-readout"` layer, added on top or
+    /// replaced in place, and stays empty while the pointer is off the
+    /// canvas. Subscribe to `pointermove` events to show the coordinates in
+    /// a custom element instead. Only maps created on a canvas element follow
+    /// the pointer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid or renderer
+    /// initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_coordinate_readout(&self, options: Option<String>) -> Result<(), JsValue> {
+        let options = CoordinateReadoutOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        self.state.borrow_mut().set_coordinate_readout(&options)
+    }
+
+    /// Shows a compass in a map corner, its needle pointing to map north as
+    /// the view turns
+    ///
+    /// `options` is an optional JSON string, see [`CompassOptions`]. The
+    /// compass forms the `"compass"` layer, added on top or replaced in
+    /// place. Clicking it turns the map back north up, see
+    /// [`WorldMap::set_bearing`], without emitting `click`. Only maps
+    /// created on a canvas element take clicks.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the options are invalid or renderer
+    /// initialization fails
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn add_compass(&self, options: Option<String>) -> Result<(), JsValue> {
+        let options = CompassOptions::parse(options.as_deref()).map_err(error::to_js)?;
+        self.state.borrow_mut().set_compass(&options)
+    }
+
+    /// Drapes an image over a geographic bounding box
+    ///
+    /// `bounds` is `[west, south, east, north]` in degrees, west of the
+    /// antimeridian; the image is expected to span it linearly in longitude and latitude, like weather
+    /// radar composites or scanned historical maps. The overlay goes below
+    /// the markers, or on top without them. Cross-origin images need CORS
+    /// headers. A layer with the same id is replaced.
+    ///
+    /// The returned promise resolves once the image is shown and rejects if
+    /// the bounds are invalid or the image fails to load.
+    pub fn add_image_overlay(
+        &self,
+        id: String,
+        url: String,
+        bounds: Vec<f64>,
+        opacity: Option<f32>,
+    ) -> Promise {
+        let state = Rc::clone(&self.state);
+
+        future_to_promise(async move {
+            let bounds = match bounds[..] {
+                [west, south, east, north] => GeoBounds::new(west, south, east, north)
+                    .map_err(error::to_js_in("Invalid overlay bounds"))?,
+                _ => {
+                    return Err(MapError::internal(
+                        "Overlay bounds must be [west, south, east, north]",
+                    )
+                    .into());
+                },
+            };
+            if bounds.crosses_antimeridian() {
+                return Err(
+                    MapError::internal("Overlay bounds must not cross the antimeridian").into(),
+                );
+            }
+            let opacity = opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+
+            let bitmap = global::fetch_image(&url).await?;
+
+            let mut state = state.borrow_mut();
+            let gl = state.ctx.gl();
+            let mut texture =
+                GpuTexture::new(gl).map_err(error::to_js_in("Renderer init failed"))?;
+            let uploaded = texture.upload_image_bitmap(gl, &bitmap);
+            bitmap.close();
+            uploaded.map_err(error::to_js_in("Overlay upload failed"))?;
+
+            let layer = ImageLayer::new(&state.ctx, bounds, texture, opacity)
+                .map_err(error::to_js_in("Renderer init failed"))?;
+            let index = state.layers.position(layer::MARKERS);
+            state.set_layer(&id, Box::new(layer), index);
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Sets the opacity of a tile, image or heatmap layer, clamped to `0.0..=1.0`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if no layer has this id or the layer has no
+    /// opacity control
+    pub fn set_layer_opacity(&self, id: &str, opacity: f32) -> Result<(), JsValue> {
+        match self
+            .state
+            .borrow_mut()
+            .set_layer_opacity(id, opacity.clamp(0.0, 1.0))
+        {
+            Some(true) => Ok(()),
+            Some(false) => {
+                Err(MapError::internal(format!("Layer has no opacity control: {id}")).into())
+            },
+            None => Err(unknown_layer(id)),
+        }
+    }
+
+    /// Removes a layer, returning false if no layer has this id
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove_layer(&self, id: &str) -> bool {
+        self.state.borrow_mut().remove_layer(id)
+    }
+
+    /// Moves a layer to `index` in the stack, 0 being the bottom
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if no layer has this id
+    pub fn move_layer(&self, id: &str, index: usize) -> Result<(), JsValue> {
+        if self.state.borrow_mut().move_layer(id, index) {
+            Ok(())
+        } else {
+            Err(unknown_layer(id))
+        }
+    }
+
+    /// Shows or hides a layer
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if no layer has this id
+    pub fn set_layer_visible(&self, id: &str, visible: bool) -> Result<(), JsValue> {
+        if self.state.borrow_mut().set_layer_visible(id, visible) {
+            Ok(())
+        } else {
+            Err(unknown_layer(id))
+        }
+    }
+
+    /// Sets how a layer blends with the layers below it: `"normal"`,
+    /// `"additive"` or `"multiply"`
+    ///
+    /// Additive blending makes overlapping glowing markers brighten each
+    /// other, e.g. `map.set_layer_blend_mode("markers", "additive")`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the mode is unknown or no layer has this id
+    pub fn set_layer_blend_mode(&self, id: &str, mode: &str) -> Result<(), JsValue> {
+        let blend = BlendMode::from_name(mode).ok_or_else(|| {
+            error::to_js(ComponentError::UnknownName {
+                kind: "blend mode".to_string(),
+                name: mode.to_string(),
+            })
+        })?;
+
+        if self.state.borrow_mut().set_layer_blend(id, blend) {
+            Ok(())
+        } else {
+            Err(unknown_layer(id))
+        }
+    }
+
+    /// Replaces the fragment shader of a line or markers layer with custom
+    /// GLSL, or restores the built-in shader when `source` is omitted
+    ///
+    /// The source must match [`WorldMap::backend`]: GLSL ES 3.00 for
+    /// `"webgl2"`, GLSL ES 1.00 for `"webgl1"`. Line shaders receive
+    /// `v_distance`, the signed distance from the line center in pixels;
+    /// marker shaders receive `v_color`, `v_intensity` and `v_uv`, which
+    /// spans the marker from -1 to 1. Both may read the built-in uniforms,
+    /// e.g. `u_color` for lines and `u_time` on WebGL1 or from the `Camera`
+    /// uniform block on WebGL2.
+    ///
+    /// Shaders of `"world"` and `"markers"` stay in effect when the world or
+    /// markers are reloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error with the compiler log if the shader fails to
+    /// compile, or if no layer has this id or the layer draws no replaceable
+    /// shader; the layer then keeps its previous shader
+    pub fn set_fragment_shader(&self, id: &str, source: Option<String>) -> Result<(), JsValue> {
+        match self
+            .state
+            .borrow_mut()
+            .set_fragment_shader(id, source)
+            .map_err(error::to_js)?
+        {
+            Some(true) => Ok(()),
+            Some(false) => {
+                Err(MapError::internal(format!("Layer has no replaceable shader: {id}")).into())
+            },
+            None => Err(unknown_layer(id)),
+        }
+    }
+
+    /// Returns the visibility of a layer, or `undefined` for an unknown id
+    #[must_use]
+    pub fn layer_visible(&self, id: &str) -> Option<bool> {
+        self.state.borrow().layers.is_visible(id)
+    }
+
     /// Renders the map to the canvas
     pub fn render(&self) {
-        self.ctx.clear(
-            self.theme.background.r,
-            self.theme.background.g,
-            self.theme.background.b,
-            self.theme.background.a,
-        );
-
-        self.world_renderer.draw(
-            &self.ctx,
-            [
-                self.theme.contour_color.r,
-                self.theme.contour_color.g,
-                self.theme.contour_color.b,
-                self.theme.contour_color.a,
-            ],
-            self.theme.contour_width,
-        );
+        self.state.borrow_mut().render();
+    }
+
+    /// Renders the current view and encodes it as a PNG `Blob`
+    ///
+    /// The frame is drawn and captured in the same task, so this works
+    /// without `preserveDrawingBuffer`. The promise rejects if the browser
+    /// refuses to encode the canvas, e.g. when a cross-origin image without
+    /// CORS headers tainted it.
+    pub fn to_png(&self) -> Promise {
+        let mut state = self.state.borrow_mut();
+        state.render();
+
+        state
+            .ctx
+            .surface()
+            .to_png()
+            .unwrap_or_else(|e| Promise::reject(&error::to_js(e)))
+    }
+
+    /// Exports the visible world lines, fills, graticule and markers as an
+    /// SVG document
+    ///
+    /// The document is sized at the canvas CSS size and uses the theme
+    /// colors. Tile and image layers and post effects are raster-only and
+    /// left out.
+    #[must_use]
+    pub fn export_svg(&self) -> String {
+        self.state.borrow().export_svg()
     }
+
+    /// Renders a legend as standalone SVG markup, for hosts to place in the
+    /// DOM next to the canvas
+    ///
+    /// `legend` is an optional JSON string, see [`LegendOptions`]: explicit
+    /// entries such as marker categories and sizes, and the classes of a
+    /// choropleth scale. Without entries or scale it describes the visible
+    /// built-in layers. The legend is drawn on the theme background.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON, an entry or the scale is invalid
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn legend_svg(&self, legend: Option<String>) -> Result<String, JsValue> {
+        let options = LegendOptions::parse(legend.as_deref()).map_err(error::to_js)?;
+
+        let state = self.state.borrow();
+        let legend = match options.legend() {
+            Ok(Some(legend)) => legend,
+            Ok(None) => Legend {
+                title: options.title,
+                ..state.legend()
+            },
+            Err(e) => return Err(error::to_js(e)),
+        };
+        Ok(legend.to_svg(state.theme.background))
+    }
+
+    /// Starts the built-in `requestAnimationFrame` render loop
+    ///
+    /// Every frame advances the animation time; drawing is skipped on frames
+    /// where nothing changed and no animation is active. Calling `start` while
+    /// running does nothing.
+    ///
+    /// For maps on a canvas element, frames are suspended while the canvas
+    /// is scrolled out of view or the tab is hidden, see
+    /// [`WorldMap::suspended`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the first frame can't be requested
+    pub fn start(&self) -> Result<(), JsValue> {
+        self.render_loop.start(&self.state)
+    }
+
+    /// Stops the render loop; the last frame stays on the canvas
+    pub fn stop(&self) {
+        self.render_loop.stop();
+    }
+
+    /// Returns true while the render loop is running
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn running(&self) -> bool {
+        self.render_loop.is_running()
+    }
+
+    /// Returns true while the loop is running but skips frames because the
+    /// canvas is scrolled out of view or the tab is hidden
+    ///
+    /// Rendering resumes by itself once the canvas is visible again.
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn suspended(&self) -> bool {
+        self.render_loop.is_suspended()
+    }
+
+    /// Returns the graphics API the map renders with, e.g. `"webgl2"`
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn backend(&self) -> String {
+        self.state.borrow().ctx.kind().as_str().to_string()
+    }
+
+    /// Returns true if the browser exposes WebGPU and the component was built
+    /// with the `webgpu` feature
+    #[must_use]
+    // Constant without the feature, but wasm-bindgen can't export a const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn webgpu_supported() -> bool {
+        #[cfg(feature = "webgpu")]
+        {
+            wmc_render::webgpu::is_available()
+        }
+        #[cfg(not(feature = "webgpu"))]
+        {
+            false
+        }
+    }
+
+    /// Returns statistics of the last drawn frame
+    ///
+    /// The object has `drawCalls`, `vertices` (counting every marker
+    /// instance), `bufferUploads`, `uploadBytes`, `frameMs`, the CPU time
+    /// spent issuing the frame, and `allocations`, the heap allocations made
+    /// meanwhile, which stay at 0 while an unchanged scene animates.
+    /// Allocations are only counted in builds with the `alloc-stats`
+    /// feature and are `null` otherwise.
+    ///
+    /// With GPU timing enabled it also has `gpuMs`, the GPU time of the
+    /// newest measured frame, and `gpuPasses`, that frame's `{name, ms}`
+    /// passes in drawing order: one per visible layer, named by its id, and
+    /// `"post"` for post-processing. GPU results lag a few frames behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the object can't be built
+    pub fn stats(&self) -> Result<JsValue, JsValue> {
+        let map = self.state.borrow();
+        let stats = map.stats;
+        let object = js_sys::Object::new();
+
+        if let Some(timer) = &map.gpu_timer {
+            let timings = timer.timings();
+            let passes = js_sys::Array::new();
+            for timing in &timings {
+                let pass = js_sys::Object::new();
+                js_sys::Reflect::set(&pass, &"name".into(), &JsValue::from_str(&timing.name))?;
+                js_sys::Reflect::set(&pass, &"ms".into(), &JsValue::from(timing.ms))?;
+                passes.push(&pass);
+            }
+
+            let total: f64 = timings.iter().map(|timing| timing.ms).sum();
+            js_sys::Reflect::set(&object, &"gpuMs".into(), &JsValue::from(total))?;
+            js_sys::Reflect::set(&object, &"gpuPasses".into(), &passes)?;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        for (key, value) in [
+            ("drawCalls", f64::from(stats.draw_calls)),
+            ("vertices", stats.vertices as f64),
+            ("bufferUploads", f64::from(stats.buffer_uploads)),
+            ("uploadBytes", stats.upload_bytes as f64),
+            ("frameMs", stats.frame_ms),
+        ] {
+            js_sys::Reflect::set(&object, &JsValue::from_str(key), &JsValue::from(value))?;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let allocations = stats
+            .allocations
+            .map_or(JsValue::NULL, |count| JsValue::from(count as f64));
+        js_sys::Reflect::set(&object, &"allocations".into(), &allocations)?;
+
+        Ok(object.into())
+    }
+
+    /// Turns per-pass GPU timing on or off, see [`WorldMap::stats`]
+    ///
+    /// Timing uses `EXT_disjoint_timer_query_webgl2` and costs a little GPU
+    /// time per pass, so it is off by default.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error when enabling if the browser doesn't support
+    /// timer queries
+    pub fn set_gpu_timing(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.state.borrow_mut().set_gpu_timing(enabled) {
+            Ok(())
+        } else {
+            Err(MapError::internal("GPU timer queries are not supported").into())
+        }
+    }
+
+    /// Sets a callback invoked after every loop frame
+    ///
+    /// The callback receives the animation time in seconds and the frame
+    /// delta in milliseconds. Pass `undefined` to remove it.
+    pub fn set_frame_callback(&self, callback: Option<Function>) {
+        self.state.borrow_mut().frame_callback = callback;
+    }
+
+    /// Fixes the animation time at `time` seconds, or lets it run again with
+    /// `undefined`
+    ///
+    /// While fixed, marker pulses, arc flows and grain are drawn at that
+    /// time and tiles without their fade-in, so two renders of the same
+    /// state are pixel-identical, e.g. for snapshot tests and exports. Marker
+    /// phases come from their `phase` or ID, and grain from
+    /// [`WorldMap::set_noise_seed`]. The running clock resumes from `time`.
+    pub fn set_fixed_time(&self, time: Option<f64>) {
+        let time = time.filter(|time| time.is_finite());
+        let mut state = self.state.borrow_mut();
+        if let Some(time) = time {
+            state.time = time;
+        }
+        state.fixed_time = time;
+        state.dirty.scene = true;
+    }
+
+    /// Returns the fixed animation time in seconds, or `undefined` while the
+    /// clock runs
+    #[wasm_bindgen(getter)]
+    #[must_use]
+    pub fn fixed_time(&self) -> Option<f64> {
+        self.state.borrow().fixed_time
+    }
+
+    /// Sets the seed of the film grain noise, 0 by default
+    ///
+    /// Grain is the same for the same seed and animation time, so
+    /// reproducible renders pair a seed with [`WorldMap::set_fixed_time`].
+    pub fn set_noise_seed(&self, seed: f32) {
+        let mut state = self.state.borrow_mut();
+        state.post.set_seed(seed);
+        state.dirty.scene = true;
+    }
+
+    /// Stops the render loop and frees every GPU resource of the map
+    ///
+    /// Call this when the canvas is unmounted, e.g. by a single-page app; the
+    /// JS object can't be used afterwards.
+    pub fn destroy(self) {
+        self.render_loop.stop();
+
+        let mut state = self.state.borrow_mut();
+        state.frame_callback = None;
+        state.destroy();
+    }
+}
+
+impl WorldMap {
+    /// Selects the marker with `id`, or clears the selection with `None`,
+    /// emitting `selectionchange` if it changed
+    fn set_selection(&self, id: Option<String>) -> Result<bool, JsValue> {
+        let changed = self.state.borrow_mut().select(id)?;
+        if changed {
+            events::emit_selection_change(&self.state)?;
+        }
+        Ok(changed)
+    }
+
+    /// Emits [`MapEvent::ViewChange`] for a viewport of `width` × `height`
+    /// CSS pixels
+    fn emit_view_change(&self, width: u32, height: u32) -> Result<(), JsValue> {
+        let events = Rc::clone(&self.state.borrow().events);
+        let detail = events::detail(&[("width", width.into()), ("height", height.into())])?;
+        events.emit(MapEvent::ViewChange, &detail);
+        Ok(())
+    }
+
+    fn with_context(ctx: RenderContext, theme: Theme) -> Result<Self, JsValue> {
+        let topology = WorldTopology::from_geojson(WORLD_GEOJSON).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Topology".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        ShaderProgram::precompile(ctx.gl(), BUILTIN_PROGRAMS);
+        let state = Rc::new(RefCell::new(MapState::new(ctx, topology, theme)?));
+        panic::watch(&state);
+        await_precompiled(&state);
+
+        Ok(Self {
+            state,
+            render_loop: RenderLoop::new(),
+            visibility: None,
+        })
+    }
+}
+
+/// Holds up [`WorldMap::ready`] until the programs precompiled for the map
+/// finish compiling, polling them once per macrotask
+fn await_precompiled(state: &Rc<RefCell<MapState>>) {
+    if ShaderProgram::compiling(state.borrow().ctx.gl()) == 0 {
+        return;
+    }
+
+    let readiness = Rc::clone(&state.borrow().readiness);
+    let state = Rc::downgrade(state);
+    readiness.begin();
+    wasm_bindgen_futures::spawn_local(async move {
+        // A destroyed map has deleted its precompiled programs
+        while state
+            .upgrade()
+            .is_some_and(|state| ShaderProgram::compiling(state.borrow().ctx.gl()) > 0)
+        {
+            if let Err(e) = global::yield_to_event_loop().await {
+                log::warn("shader", "Shader compilation not awaited", &[("error", e)]);
+                break;
+            }
+        }
+        readiness.finish(Ok(()));
+    });
+}
+
+/// Parses and validates a JSON array of markers
+fn parse_markers(json: &str) -> Result<Vec<Marker>, JsValue> {
+    let markers: Vec<Marker> = serde_json::from_str(json).map_err(|e| {
+        error::to_js(ComponentError::ParseFailed {
+            input: "Markers".to_string(),
+            reason: e.to_string(),
+        })
+    })?;
+    for marker in &markers {
+        marker
+            .validate()
+            .map_err(error::to_js_in("Invalid marker"))?;
+    }
+
+    Ok(markers)
+}
+
+fn parse_event(name: &str) -> Result<MapEvent, JsValue> {
+    MapEvent::from_name(name).ok_or_else(|| {
+        error::to_js(ComponentError::UnknownName {
+            kind: "event".to_string(),
+            name: name.to_string(),
+        })
+    })
+}
+
+fn unknown_layer(id: &str) -> JsValue {
+    error::to_js(ComponentError::UnknownName {
+        kind: "layer".to_string(),
+        name: id.to_string(),
+    })
 }
@@ -0,0 +1,52 @@
+use wasm_bindgen::JsValue;
+use wmc_core::topology::{TopologyStream, WorldTopology};
+use wmc_render::log;
+
+use crate::{
+    error::{self, ComponentError},
+    global,
+};
+
+/// Number of features parsed between yields to the event loop
+const FEATURES_PER_CHUNK: usize = 64;
+
+/// Parses a `GeoJSON` `FeatureCollection` incrementally
+///
+/// Yields to the browser event loop after every chunk of features so that
+/// large (e.g. 10m-resolution) documents don't block input and painting,
+/// calling `progress` with the bytes parsed and the total before each yield.
+#[allow(clippy::future_not_send, clippy::cast_precision_loss)]
+pub async fn parse_topology_streaming(
+    geojson: &str,
+    progress: impl Fn(f64, f64),
+) -> Result<WorldTopology, JsValue> {
+    let mut stream = TopologyStream::new(geojson).map_err(|e| {
+        error::to_js(ComponentError::ParseFailed {
+            input: "Topology".to_string(),
+            reason: e.to_string(),
+        })
+    })?;
+
+    while !stream.parse_chunk(FEATURES_PER_CHUNK).map_err(|e| {
+        error::to_js(ComponentError::ParseFailed {
+            input: "Topology".to_string(),
+            reason: e.to_string(),
+        })
+    })? {
+        progress(stream.consumed() as f64, geojson.len() as f64);
+        global::yield_to_event_loop().await?;
+    }
+    progress(geojson.len() as f64, geojson.len() as f64);
+
+    if stream.skipped_count() > 0 {
+        log::warn(
+            "topology",
+            "Features with unsupported geometry dropped",
+            &[
+                ("dropped", stream.skipped_count().into()),
+                ("parsed", stream.parsed_count().into()),
+            ],
+        );
+    }
+    Ok(stream.finish())
+}
@@ -0,0 +1,40 @@
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use wmc_render::{LogLevel, log};
+
+use crate::error::{self, ComponentError};
+
+/// Sets the least severe diagnostic level logged by every map on the page
+///
+/// `level` is `error`, `warn` (the default), `info`, `debug` or `off`. At
+/// `info`, slow frames are reported too; `debug` is the level to ask for when
+/// a map shows up blank.
+///
+/// # Errors
+///
+/// Returns `JsValue` error for an unknown level
+#[wasm_bindgen(js_name = setLogLevel)]
+pub fn set_log_level(level: &str) -> Result<(), JsValue> {
+    let level = match level {
+        "off" => None,
+        name => Some(LogLevel::from_name(name).ok_or_else(|| {
+            error::to_js(ComponentError::UnknownName {
+                kind: "log level".to_string(),
+                name: name.to_string(),
+            })
+        })?),
+    };
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Sends diagnostics to `logger` instead of the console, or back to the
+/// console with `null`
+///
+/// `logger` is called with `{level, target, message, fields}`, `target`
+/// naming the subsystem: `frame`, `fetch`, `topology`, `shader`, `layer`,
+/// `tile`, `glyph`, `theme`, `element` or `event`.
+#[wasm_bindgen(js_name = setLogger)]
+pub fn set_logger(logger: Option<Function>) {
+    log::set_logger(logger);
+}
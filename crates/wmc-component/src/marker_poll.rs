@@ -0,0 +1,160 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::Request;
+use wmc_render::log;
+
+use crate::{
+    error::{self, ComponentError},
+    global, parse_markers,
+    state::MapState,
+};
+
+/// HTTP status of a conditional request whose resource is unchanged
+const NOT_MODIFIED: u16 = 304;
+
+/// Refetches markers from a URL on a timer
+///
+/// Every request after the first sends the last response's `ETag` as
+/// `If-None-Match`, so an unchanged source costs a `304` and no parsing or
+/// upload; cross-origin sources must expose the header with
+/// `Access-Control-Expose-Headers: ETag`. Changed marker sets go through
+/// [`MapState::update_markers`], which re-uploads only the instances that
+/// differ while the marker ids stay the same. A tick is skipped while the
+/// previous request is in flight, and the timer is cleared on drop.
+pub struct MarkerPoll {
+    interval: i32,
+    poll: Rc<Poll>,
+    _on_tick: Closure<dyn FnMut()>,
+}
+
+struct Poll {
+    url: String,
+    state: Weak<RefCell<MapState>>,
+    etag: RefCell<Option<String>>,
+    in_flight: Cell<bool>,
+    stopped: Cell<bool>,
+}
+
+impl MarkerPoll {
+    /// Fetches markers from `url` now and then every `interval_ms`
+    /// milliseconds
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the timer can't be started
+    pub fn start(
+        url: String,
+        interval_ms: i32,
+        state: Weak<RefCell<MapState>>,
+    ) -> Result<Self, JsValue> {
+        let poll = Rc::new(Poll {
+            url,
+            state,
+            etag: RefCell::new(None),
+            in_flight: Cell::new(false),
+            stopped: Cell::new(false),
+        });
+
+        let ticking = Rc::clone(&poll);
+        let on_tick = Closure::<dyn FnMut()>::new(move || Poll::tick(&ticking));
+        let interval = global::set_interval(on_tick.as_ref().unchecked_ref(), interval_ms)?;
+        Poll::tick(&poll);
+
+        Ok(Self {
+            interval,
+            poll,
+            _on_tick: on_tick,
+        })
+    }
+}
+
+impl Drop for MarkerPoll {
+    fn drop(&mut self) {
+        global::clear_interval(self.interval);
+        // A response still in flight is dropped when it lands
+        self.poll.stopped.set(true);
+    }
+}
+
+impl Poll {
+    fn tick(poll: &Rc<Self>) {
+        if poll.in_flight.replace(true) {
+            return;
+        }
+
+        let poll = Rc::clone(poll);
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = poll.fetch().await;
+            poll.in_flight.set(false);
+            if poll.stopped.get() {
+                return;
+            }
+            let Some(state) = poll.state.upgrade() else {
+                return;
+            };
+
+            let applied = result.and_then(|markers| match markers {
+                Some(json) => {
+                    let markers = parse_markers(&json)?;
+                    #[allow(clippy::cast_precision_loss)]
+                    let count = markers.len() as f64;
+                    state.borrow_mut().update_markers(markers)?;
+                    Ok(Some(count))
+                },
+                None => Ok(None),
+            });
+            let events = Rc::clone(&state.borrow().events);
+            match applied {
+                Ok(Some(count)) => events.emit_progress("upload", count, Some(count)),
+                Ok(None) => {},
+                Err(e) => {
+                    log::warn(
+                        "fetch",
+                        "Markers poll failed",
+                        &[("url", poll.url.as_str().into()), ("error", e.clone())],
+                    );
+                    events.emit_error(&e);
+                },
+            }
+        });
+    }
+
+    /// Fetches the markers JSON, or `None` if it's unchanged since the last
+    /// fetch
+    #[allow(clippy::future_not_send)]
+    async fn fetch(&self) -> Result<Option<String>, JsValue> {
+        let request = Request::new_with_str(&self.url)?;
+        if let Some(etag) = self.etag.borrow().as_deref() {
+            request.headers().set("If-None-Match", etag)?;
+        }
+
+        let response = global::fetch(&request).await?;
+        if response.status() == NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !response.ok() {
+            return Err(error::to_js(ComponentError::MarkerUrlFetchFailed {
+                url: self.url.clone(),
+                status: response.status(),
+            }));
+        }
+
+        let etag = response.headers().get("ETag")?;
+        let events = self
+            .state
+            .upgrade()
+            .map(|state| Rc::clone(&state.borrow().events));
+        let json = global::read_text(&response, |loaded, total| {
+            if let Some(events) = &events {
+                events.emit_progress("download", loaded, total);
+            }
+        })
+        .await?;
+        *self.etag.borrow_mut() = etag;
+        Ok(Some(json))
+    }
+}
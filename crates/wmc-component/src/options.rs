@@ -0,0 +1,1089 @@
+use serde::Deserialize;
+use wmc_core::{
+    color_scale::ColorScale,
+    heatmap::Gradient,
+    legend::{Legend, LegendEntry},
+    marker::Color,
+    projection::GeoCoord,
+    scale_bar::ScaleUnit,
+    theme::{LineStyle, Theme},
+};
+use wmc_render::{BlendMode, ContextOptions, FillMode, HeatmapSettings};
+
+use crate::error::ComponentError;
+
+/// Construction options for [`crate::WorldMap`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"theme": "light-minimal", "cssTheme": true, "context": {"antialias": false, "powerPreference": "low-power"}}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MapOptions {
+    /// Name of the built-in theme preset, one of [`Theme::PRESETS`];
+    /// `"dark-minimal"` when omitted
+    pub theme: Option<String>,
+    /// Overrides theme colors with CSS custom properties on the canvas or
+    /// its ancestors, such as `--wmc-background`, following their changes;
+    /// see [`Theme::CSS_PROPERTIES`]. Ignored for offscreen canvases.
+    pub css_theme: bool,
+    /// WebGL context creation attributes
+    pub context: ContextOptions,
+    /// Pointer interactions enabled from the start
+    pub interactions: Interactions,
+    /// Exposes markers to assistive technology from the start, see
+    /// [`crate::WorldMap::set_accessible`]
+    pub accessible: bool,
+    /// Zoom step the view settles on, e.g. 1 for integer zooms; 0 keeps
+    /// zoom continuous. See [`crate::WorldMap::set_zoom_snap`].
+    pub zoom_snap: f32,
+}
+
+impl MapOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or names an unknown theme
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        options.theme()?;
+        Ok(options)
+    }
+
+    /// Returns the selected theme preset
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if no preset has the
+    /// name
+    pub fn theme(&self) -> Result<Theme, ComponentError> {
+        self.theme.as_deref().map_or_else(
+            || Ok(Theme::dark_minimal()),
+            |name| {
+                Theme::from_name(name).ok_or_else(|| ComponentError::AttributeParseError {
+                    attribute: "theme".to_string(),
+                    value: format!("{name} is not one of {}", Theme::PRESETS.join(", ")),
+                })
+            },
+        )
+    }
+}
+
+/// Pointer interactions of a map, see [`crate::WorldMap::set_interactions`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"zoom": false, "markerClick": true}`. Omitted fields stay enabled.
+///
+/// Gestures move the view like [`crate::WorldMap::set_center`],
+/// [`crate::WorldMap::set_zoom`] and [`crate::WorldMap::set_bearing`] do,
+/// the zoom settling on [`crate::WorldMap::zoom_snap`] once they end. With
+/// `zoom` off the wheel scrolls the page, and with every gesture off
+/// touches scroll and zoom the page too; a drag past a few pixels isn't a
+/// click.
+///
+/// ```
+/// use wmc_component::Interactions;
+///
+/// let interactions = Interactions::parse(r#"{"zoom": false, "contextMenu": false}"#).unwrap();
+/// assert!(interactions.pan && !interactions.zoom);
+/// assert!(interactions.click && !interactions.context_menu);
+/// assert!(interactions.gestures());
+/// assert!(!Interactions::NONE.any());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Interactions {
+    /// Pans the map by dragging it with the mouse or a finger
+    pub pan: bool,
+    /// Zooms the map about the pointer with the wheel and by pinching
+    pub zoom: bool,
+    /// Rotates the map by twisting two fingers, or by dragging with Shift
+    /// held
+    pub rotate: bool,
+    /// Emits `click` events for clicks on the canvas
+    pub click: bool,
+    /// Picks markers under clicks and emits `markerclick` events
+    pub marker_click: bool,
+    /// Emits `mapcontextmenu` events for secondary clicks and touch long
+    /// presses on the canvas, replacing the browser's menu while a callback
+    /// is registered
+    pub context_menu: bool,
+}
+
+impl Default for Interactions {
+    fn default() -> Self {
+        Self {
+            pan: true,
+            zoom: true,
+            rotate: true,
+            click: true,
+            marker_click: true,
+            context_menu: true,
+        }
+    }
+}
+
+impl Interactions {
+    /// No interaction, for static "poster" maps
+    pub const NONE: Self = Self {
+        pan: false,
+        zoom: false,
+        rotate: false,
+        click: false,
+        marker_click: false,
+        context_menu: false,
+    };
+
+    /// Parses interactions from a JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    pub fn parse(json: &str) -> Result<Self, ComponentError> {
+        serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+            attribute: "interactions".to_string(),
+            value: e.to_string(),
+        })
+    }
+
+    /// Returns true if any interaction is enabled
+    #[must_use]
+    pub const fn any(self) -> bool {
+        self.gestures() || self.click || self.marker_click || self.context_menu
+    }
+
+    /// Returns true if any gesture moving the view is enabled
+    #[must_use]
+    pub const fn gestures(self) -> bool {
+        self.pan || self.zoom || self.rotate
+    }
+}
+
+/// Order markers are drawn in, and how many, so the most important stay
+/// on top of huge datasets
+///
+/// Passed from JavaScript as a JSON string, e.g. `{"byIntensity": true,
+/// "top": 500}`. Omitted fields keep their defaults, which draw every
+/// marker in the order given.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MarkerOrder {
+    /// Draws markers by ascending intensity, so the most intense end up on
+    /// top
+    pub by_intensity: bool,
+    /// Draws only this many of the most intense markers on screen while
+    /// zoomed out to `top_max_zoom` or below; ties keep the later marker
+    pub top: Option<usize>,
+    /// Zoom up to which `top` applies, see [`wmc_core::label::zoom_level`]
+    pub top_max_zoom: f32,
+}
+
+impl Default for MarkerOrder {
+    fn default() -> Self {
+        Self {
+            by_intensity: false,
+            top: None,
+            top_max_zoom: 2.0,
+        }
+    }
+}
+
+impl MarkerOrder {
+    /// Parses a marker order from a JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    pub fn parse(json: &str) -> Result<Self, ComponentError> {
+        serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+            attribute: "markerOrder".to_string(),
+            value: e.to_string(),
+        })
+    }
+
+    /// Returns how many markers are drawn at `zoom`, `None` for all
+    #[must_use]
+    pub fn limit(self, zoom: f32) -> Option<usize> {
+        self.top.filter(|_| zoom <= self.top_max_zoom)
+    }
+
+    /// Returns true if markers aren't all drawn in their own order at
+    /// `zoom`
+    #[must_use]
+    pub fn reorders(self, zoom: f32) -> bool {
+        self.by_intensity || self.limit(zoom).is_some()
+    }
+}
+
+/// How a custom layer draws its geometry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LayerKind {
+    /// Anti-aliased lines along every ring and line string
+    #[default]
+    Line,
+    /// Solid polygon fills
+    Fill,
+}
+
+/// Options for layers added with [`crate::WorldMap::add_layer`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"kind": "fill", "color": {"r": 0.2, "g": 0.4, "b": 0.6, "a": 1.0}, "blend": "multiply"}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LayerOptions {
+    /// Line or fill rendering
+    pub kind: LayerKind,
+    /// Layer color; the theme borders color for lines and fills color for
+    /// fills when omitted
+    pub color: Option<Color>,
+    /// Line width in CSS pixels; the theme borders width when omitted
+    pub width: Option<f32>,
+    /// Line dash pattern in CSS pixels, e.g. `{"type": "dashed", "dash": 6,
+    /// "gap": 3}`; the theme borders style when omitted
+    pub style: Option<LineStyle>,
+    /// Stack position for a new layer, 0 being the bottom; on top when omitted
+    pub index: Option<usize>,
+    /// How the layer blends with the layers below it
+    pub blend: BlendMode,
+    /// How fill layers cover polygon interiors; `"stencil"` cuts holes and
+    /// copes with self-intersecting rings
+    pub fill_mode: FillMode,
+}
+
+impl LayerOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "layer options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )
+    }
+}
+
+/// Options for layers added with [`crate::WorldMap::add_tile_layer`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"subdomains": ["a", "b"], "maxZoom": 17, "opacity": 0.6}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TileLayerOptions {
+    /// Values substituted for `{s}` in the URL template
+    pub subdomains: Vec<String>,
+    /// Highest zoom level the tile server provides
+    pub max_zoom: u8,
+    /// Tile size in pixels
+    pub tile_size: u32,
+    /// Layer opacity in `0.0..=1.0`
+    pub opacity: f32,
+    /// Stack position for a new layer, 0 being the bottom
+    pub index: usize,
+}
+
+impl Default for TileLayerOptions {
+    fn default() -> Self {
+        Self {
+            subdomains: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            max_zoom: 19,
+            tile_size: 256,
+            opacity: 1.0,
+            index: 0,
+        }
+    }
+}
+
+impl TileLayerOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "tile layer options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        if options.tile_size == 0 || !(0.0..=1.0).contains(&options.opacity) {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "tile layer options".to_string(),
+                value: format!(
+                    "tile size {} and opacity {} must be positive and at most 1",
+                    options.tile_size, options.opacity
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Options for layers added with [`crate::WorldMap::add_arc_layer`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"width": 2, "elevation": 0.3, "speed": 40, "style": {"type": "dotted", "gap": 4}}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArcLayerOptions {
+    /// Color of arcs without their own; the theme marker color when omitted
+    pub color: Option<Color>,
+    /// Line width in CSS pixels
+    pub width: f32,
+    /// Dash pattern in CSS pixels; a solid style stops the flow
+    pub style: LineStyle,
+    /// Lift of the arc midpoints as a fraction of the projected arc length;
+    /// 0 draws the arcs flat
+    pub elevation: f32,
+    /// Speed the dashes flow towards the destinations, in CSS pixels per
+    /// second
+    pub speed: f32,
+    /// Stack position for a new layer, 0 being the bottom; below the markers
+    /// when omitted
+    pub index: Option<usize>,
+}
+
+impl Default for ArcLayerOptions {
+    fn default() -> Self {
+        Self {
+            color: None,
+            width: 1.5,
+            style: LineStyle::Dashed {
+                dash: 8.0,
+                gap: 6.0,
+            },
+            elevation: 0.2,
+            speed: 30.0,
+            index: None,
+        }
+    }
+}
+
+impl ArcLayerOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "arc layer options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.width > 0.0
+            && options.elevation.is_finite()
+            && options.speed.is_finite()
+            && options.style.is_valid();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "arc layer options".to_string(),
+                value: format!(
+                    "width {} must be positive, elevation {} and speed {} finite, and style {:?} valid",
+                    options.width, options.elevation, options.speed, options.style
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Stops a heatmap [`ColorScale`] is sampled at
+const SCALE_STOPS: usize = 16;
+
+/// Options for layers added with [`crate::WorldMap::add_heatmap_layer`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"radius": 30, "intensity": 0.1, "gradient": [{"offset": 0, "color": {...}}, ...]}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HeatmapLayerOptions {
+    /// Kernel radius in CSS pixels
+    pub radius: f32,
+    /// Density a point of weight 1 adds at its center; density 1 takes the
+    /// last gradient color
+    pub intensity: f32,
+    /// Layer opacity in `0.0..=1.0`
+    pub opacity: f32,
+    /// Color ramp from density 0 to 1
+    pub gradient: Gradient,
+    /// Palette scale over densities replacing `gradient`, e.g.
+    /// `{"palette": "magma"}`; density 0 stays transparent
+    pub scale: Option<ColorScale>,
+    /// Stack position for a new layer, 0 being the bottom; below the markers
+    /// when omitted
+    pub index: Option<usize>,
+}
+
+impl Default for HeatmapLayerOptions {
+    fn default() -> Self {
+        let settings = HeatmapSettings::default();
+
+        Self {
+            radius: settings.radius,
+            intensity: settings.intensity,
+            opacity: settings.opacity,
+            gradient: Gradient::default(),
+            scale: None,
+            index: None,
+        }
+    }
+}
+
+impl HeatmapLayerOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let mut options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "heatmap layer options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let invalid = |e: wmc_core::CoreError| ComponentError::AttributeParseError {
+            attribute: "heatmap layer options".to_string(),
+            value: e.to_string(),
+        };
+        if let Some(scale) = options.scale {
+            scale.validate().map_err(invalid)?;
+            options.gradient = scale.gradient(SCALE_STOPS);
+            if let Some(first) = options.gradient.stops.first_mut() {
+                first.color.a = 0.0;
+            }
+        }
+        options.gradient.validate().map_err(invalid)?;
+        let valid = options.radius > 0.0
+            && options.radius.is_finite()
+            && options.intensity >= 0.0
+            && options.intensity.is_finite()
+            && (0.0..=1.0).contains(&options.opacity);
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "heatmap layer options".to_string(),
+                value: format!(
+                    "radius {} must be positive, intensity {} non-negative and opacity {} at most 1",
+                    options.radius, options.intensity, options.opacity
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+
+    /// Returns the render settings
+    #[must_use]
+    pub const fn settings(&self) -> HeatmapSettings {
+        HeatmapSettings {
+            radius: self.radius,
+            intensity: self.intensity,
+            opacity: self.opacity,
+        }
+    }
+}
+
+/// Options for layers added with [`crate::WorldMap::add_label_layer`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"property": "NAME_EN", "size": 11, "haloWidth": 2, "minSize": 64}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LabelLayerOptions {
+    /// Feature property holding the label text
+    pub property: String,
+    /// Numeric feature property holding the zoom a label appears at, like
+    /// Natural Earth's `MIN_LABEL`; features without it, or all features
+    /// when `null`, appear by size
+    pub min_zoom_property: Option<String>,
+    /// Size in CSS pixels a feature must reach, as the square root of its
+    /// area, before its label appears
+    pub min_size: f32,
+    /// CSS font family
+    pub font: String,
+    /// Font size in CSS pixels
+    pub size: f32,
+    /// Text color; near-white or near-black, whichever contrasts with the
+    /// theme background, when omitted
+    pub color: Option<Color>,
+    /// Halo color; the theme background when omitted
+    pub halo_color: Option<Color>,
+    /// Halo width in CSS pixels; 0 draws no halo
+    pub halo_width: f32,
+    /// Minimum gap between two labels in CSS pixels
+    pub padding: f32,
+    /// Stack position for a new layer, 0 being the bottom; below the markers
+    /// when omitted
+    pub index: Option<usize>,
+}
+
+impl Default for LabelLayerOptions {
+    fn default() -> Self {
+        Self {
+            property: "NAME".to_string(),
+            min_zoom_property: Some("MIN_LABEL".to_string()),
+            min_size: 48.0,
+            font: "sans-serif".to_string(),
+            size: 12.0,
+            color: None,
+            halo_color: None,
+            halo_width: 1.5,
+            padding: 4.0,
+            index: None,
+        }
+    }
+}
+
+impl LabelLayerOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "label layer options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.size > 0.0
+            && options.size.is_finite()
+            && options.min_size > 0.0
+            && options.min_size.is_finite()
+            && options.halo_width >= 0.0
+            && options.halo_width.is_finite()
+            && options.padding.is_finite();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "label layer options".to_string(),
+                value: format!(
+                    "size {} and min size {} must be positive, halo width {} non-negative and padding {} finite",
+                    options.size, options.min_size, options.halo_width, options.padding
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Options for [`crate::WorldMap::add_graticule_labels`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"step": 15, "size": 10, "color": {"r": 1, "g": 1, "b": 1, "a": 0.6}}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GraticuleLabelOptions {
+    /// Spacing of the labeled lines in degrees
+    pub step: f64,
+    /// CSS font family
+    pub font: String,
+    /// Font size in CSS pixels
+    pub size: f32,
+    /// Text color; near-white or near-black, whichever contrasts with the
+    /// theme background, when omitted
+    pub color: Option<Color>,
+    /// Halo color; the theme background when omitted
+    pub halo_color: Option<Color>,
+    /// Halo width in CSS pixels; 0 draws no halo
+    pub halo_width: f32,
+    /// Distance from the map edges and minimum gap between two labels in
+    /// CSS pixels
+    pub padding: f32,
+}
+
+impl Default for GraticuleLabelOptions {
+    fn default() -> Self {
+        Self {
+            step: 30.0,
+            font: "sans-serif".to_string(),
+            size: 10.0,
+            color: None,
+            halo_color: None,
+            halo_width: 1.5,
+            padding: 4.0,
+        }
+    }
+}
+
+impl GraticuleLabelOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "graticule label options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.step > 0.0
+            && options.step.is_finite()
+            && options.size > 0.0
+            && options.size.is_finite()
+            && options.halo_width >= 0.0
+            && options.halo_width.is_finite()
+            && options.padding >= 0.0
+            && options.padding.is_finite();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "graticule label options".to_string(),
+                value: format!(
+                    "step {} and size {} must be positive, halo width {} and padding {} non-negative",
+                    options.step, options.size, options.halo_width, options.padding
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Corner of the map a scale bar, coordinate readout or compass sits in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScaleBarCorner {
+    /// Top left corner
+    TopLeft,
+    /// Top right corner
+    TopRight,
+    /// Bottom left corner
+    BottomLeft,
+    /// Bottom right corner
+    #[default]
+    BottomRight,
+}
+
+/// Options for the scale bar shown with [`crate::WorldMap::add_scale_bar`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"unit": "imperial", "corner": "bottomLeft", "maxWidth": 120}`.
+/// Omitted fields keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScaleBarOptions {
+    /// Unit system of the distance, `"metric"` or `"imperial"`
+    pub unit: ScaleUnit,
+    /// Longest bar length in CSS pixels
+    pub max_width: f32,
+    /// Corner of the map the bar sits in
+    pub corner: ScaleBarCorner,
+    /// CSS font family of the label
+    pub font: String,
+    /// Label font size in CSS pixels
+    pub size: f32,
+    /// Bar and label color; near-white or near-black, whichever contrasts
+    /// with the theme background, when omitted
+    pub color: Option<Color>,
+    /// Halo color; the theme background when omitted
+    pub halo_color: Option<Color>,
+    /// Halo width in CSS pixels; 0 draws no halo
+    pub halo_width: f32,
+    /// Distance from the map edges in CSS pixels
+    pub padding: f32,
+}
+
+impl Default for ScaleBarOptions {
+    fn default() -> Self {
+        Self {
+            unit: ScaleUnit::Metric,
+            max_width: 100.0,
+            corner: ScaleBarCorner::BottomRight,
+            font: "sans-serif".to_string(),
+            size: 10.0,
+            color: None,
+            halo_color: None,
+            halo_width: 1.5,
+            padding: 8.0,
+        }
+    }
+}
+
+impl ScaleBarOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "scale bar options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.max_width > 0.0
+            && options.max_width.is_finite()
+            && options.size > 0.0
+            && options.size.is_finite()
+            && options.halo_width >= 0.0
+            && options.halo_width.is_finite()
+            && options.padding >= 0.0
+            && options.padding.is_finite();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "scale bar options".to_string(),
+                value: format!(
+                    "max width {} and size {} must be positive, halo width {} and padding {} non-negative",
+                    options.max_width, options.size, options.halo_width, options.padding
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Notation of the coordinates in a coordinate readout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CoordinateFormat {
+    /// Signed decimal degrees, e.g. `-22.9068, -43.1729`
+    Decimal,
+    /// Unsigned decimal degrees with hemisphere letters, e.g.
+    /// `22.9068° S, 43.1729° W`
+    #[default]
+    Hemisphere,
+    /// Degrees, minutes and seconds, e.g. `22°54′25″ S, 43°10′22″ W`
+    Dms,
+}
+
+impl CoordinateFormat {
+    /// Formats `coord`, with `precision` digits after the point in the
+    /// decimal notations
+    ///
+    /// ```
+    /// use wmc_component::CoordinateFormat;
+    /// use wmc_core::projection::GeoCoord;
+    ///
+    /// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+    /// assert_eq!(CoordinateFormat::Decimal.format(paris, 1), "48.9, 2.4");
+    /// assert_eq!(CoordinateFormat::Dms.format(paris, 1), "48°51′24″ N, 2°21′08″ E");
+    /// ```
+    #[must_use]
+    pub fn format(self, coord: GeoCoord, precision: usize) -> String {
+        match self {
+            Self::Decimal => coord.format_decimal(precision),
+            Self::Hemisphere => coord.format_hemisphere(precision),
+            Self::Dms => coord.format_dms(),
+        }
+    }
+}
+
+/// Options for the coordinate readout shown with
+/// [`crate::WorldMap::add_coordinate_readout`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"format": "dms", "corner": "topLeft"}`. Omitted fields keep their
+/// defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CoordinateReadoutOptions {
+    /// Notation of the coordinates, `"decimal"`, `"hemisphere"` or `"dms"`
+    pub format: CoordinateFormat,
+    /// Digits after the point in the decimal notations, at most 8
+    pub precision: usize,
+    /// Corner of the map the readout sits in
+    pub corner: ScaleBarCorner,
+    /// CSS font family of the text
+    pub font: String,
+    /// Font size in CSS pixels
+    pub size: f32,
+    /// Text color; near-white or near-black, whichever contrasts with the
+    /// theme background, when omitted
+    pub color: Option<Color>,
+    /// Halo color; the theme background when omitted
+    pub halo_color: Option<Color>,
+    /// Halo width in CSS pixels; 0 draws no halo
+    pub halo_width: f32,
+    /// Distance from the map edges in CSS pixels
+    pub padding: f32,
+}
+
+impl Default for CoordinateReadoutOptions {
+    fn default() -> Self {
+        Self {
+            format: CoordinateFormat::Hemisphere,
+            precision: 4,
+            corner: ScaleBarCorner::BottomLeft,
+            font: "monospace".to_string(),
+            size: 11.0,
+            color: None,
+            halo_color: None,
+            halo_width: 1.5,
+            padding: 8.0,
+        }
+    }
+}
+
+impl CoordinateReadoutOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "coordinate readout options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.precision <= 8
+            && options.size > 0.0
+            && options.size.is_finite()
+            && options.halo_width >= 0.0
+            && options.halo_width.is_finite()
+            && options.padding >= 0.0
+            && options.padding.is_finite();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "coordinate readout options".to_string(),
+                value: format!(
+                    "precision {} must be at most 8, size {} positive, halo width {} and padding {} non-negative",
+                    options.precision, options.size, options.halo_width, options.padding
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Options for the compass shown with [`crate::WorldMap::add_compass`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"corner": "topLeft", "radius": 24}`. Omitted fields keep their
+/// defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompassOptions {
+    /// Corner of the map the compass sits in
+    pub corner: ScaleBarCorner,
+    /// Dial radius in CSS pixels
+    pub radius: f32,
+    /// CSS font family of the north letter
+    pub font: String,
+    /// North letter font size in CSS pixels
+    pub size: f32,
+    /// Color of the south half of the needle and the north letter;
+    /// near-white or near-black, whichever contrasts with the theme
+    /// background, when omitted
+    pub color: Option<Color>,
+    /// Color of the north half of the needle
+    pub north_color: Color,
+    /// Dial color; the theme background when omitted
+    pub halo_color: Option<Color>,
+    /// Distance from the map edges in CSS pixels
+    pub padding: f32,
+}
+
+impl Default for CompassOptions {
+    fn default() -> Self {
+        Self {
+            corner: ScaleBarCorner::TopRight,
+            radius: 20.0,
+            font: "sans-serif".to_string(),
+            size: 9.0,
+            color: None,
+            north_color: Color::rgba(0.86, 0.2, 0.18, 1.0),
+            halo_color: None,
+            padding: 8.0,
+        }
+    }
+}
+
+impl CompassOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    /// or a value is out of range
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        let options: Self = json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "compass options".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )?;
+
+        let valid = options.radius > 0.0
+            && options.radius.is_finite()
+            && options.size > 0.0
+            && options.size.is_finite()
+            && options.padding >= 0.0
+            && options.padding.is_finite();
+        if !valid {
+            return Err(ComponentError::AttributeParseError {
+                attribute: "compass options".to_string(),
+                value: format!(
+                    "radius {} and size {} must be positive, padding {} non-negative",
+                    options.radius, options.size, options.padding
+                ),
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+/// Classed color scale described by a legend
+#[derive(Debug, Clone, Deserialize)]
+pub struct LegendScale {
+    /// Class boundaries in ascending order
+    pub breaks: Vec<f64>,
+    /// Colors the classes are picked from, first to last
+    #[serde(default)]
+    pub gradient: Gradient,
+    /// Palette scale coloring each class by its middle value, replacing
+    /// `gradient`, e.g. `{"palette": "viridis", "domain": [0, 1000]}`
+    #[serde(default)]
+    pub colors: Option<ColorScale>,
+}
+
+/// Contents of a legend rendered with [`crate::WorldMap::legend_svg`]
+///
+/// Passed from JavaScript as a JSON string, e.g.
+/// `{"title": "Alerts", "entries": [{"label": "Critical", "color": {...}, "symbol": {"type": "circle", "radius": 6}}]}`
+/// or `{"title": "Density", "scale": {"breaks": [0, 10, 100, 1000]}}`.
+/// Scale classes follow the entries. Without either, the legend describes
+/// the visible built-in layers in the theme's styles.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LegendOptions {
+    /// Heading above the entries
+    pub title: Option<String>,
+    /// Explicit rows, e.g. marker categories or sizes
+    pub entries: Vec<LegendEntry>,
+    /// Classed scale appended after the entries
+    pub scale: Option<LegendScale>,
+}
+
+impl LegendOptions {
+    /// Parses options from an optional JSON string
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is invalid
+    pub fn parse(json: Option<&str>) -> Result<Self, ComponentError> {
+        json.map_or_else(
+            || Ok(Self::default()),
+            |json| {
+                serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+                    attribute: "legend".to_string(),
+                    value: e.to_string(),
+                })
+            },
+        )
+    }
+
+    /// Builds the legend from the entries and scale, or returns `None` if
+    /// there are neither
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if an entry or the
+    /// scale is invalid
+    pub fn legend(&self) -> Result<Option<Legend>, ComponentError> {
+        let invalid = |e: wmc_core::CoreError| ComponentError::AttributeParseError {
+            attribute: "legend".to_string(),
+            value: e.to_string(),
+        };
+
+        if self.entries.is_empty() && self.scale.is_none() {
+            return Ok(None);
+        }
+
+        let mut legend = Legend {
+            title: self.title.clone(),
+            entries: self.entries.clone(),
+        };
+        if let Some(scale) = &self.scale {
+            let classes = scale
+                .colors
+                .as_ref()
+                .map_or_else(
+                    || Legend::classes(None, &scale.breaks, &scale.gradient),
+                    |colors| Legend::scale_classes(None, &scale.breaks, colors),
+                )
+                .map_err(invalid)?;
+            legend.entries.extend(classes.entries);
+        }
+        legend.validate().map_err(invalid)?;
+
+        Ok(Some(legend))
+    }
+}
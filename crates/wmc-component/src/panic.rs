@@ -0,0 +1,108 @@
+use std::{
+    cell::RefCell,
+    panic::PanicHookInfo,
+    rc::{Rc, Weak},
+    sync::Once,
+};
+
+use serde_json::json;
+
+use crate::{error::MapError, events::EventEmitter, state::MapState};
+
+/// Code of errors reporting a panic
+const PANIC: &str = "panic";
+
+thread_local! {
+    /// Maps alive on this thread, told about panics
+    static MAPS: RefCell<Vec<Watched>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A map's state and events, the events held apart so a panic raised while
+/// the state is borrowed still reaches the host
+#[derive(Clone)]
+struct Watched {
+    state: Weak<RefCell<MapState>>,
+    events: Weak<EventEmitter>,
+}
+
+/// Installs the panic hook, once per module instance
+///
+/// Panics are still written to the console by `console_error_panic_hook`,
+/// then emitted as a `MapError` with code `panic` to the `error` listeners
+/// of every live map. Its `details` hold the panic `message`, its
+/// `location`, and a `state` snapshot of the map, `null` if the map was
+/// borrowed when the panic occurred. The module is unusable afterwards, so
+/// listeners should report the error and recreate the map.
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        std::panic::set_hook(Box::new(|info| {
+            console_error_panic_hook::hook(info);
+            report(info);
+        }));
+    });
+}
+
+/// Registers `state` for panic reports until it's dropped
+pub fn watch(state: &Rc<RefCell<MapState>>) {
+    let watched = Watched {
+        state: Rc::downgrade(state),
+        events: Rc::downgrade(&state.borrow().events),
+    };
+    MAPS.with(|maps| {
+        if let Ok(mut maps) = maps.try_borrow_mut() {
+            maps.retain(|map| map.state.strong_count() > 0);
+            maps.push(watched);
+        }
+    });
+}
+
+fn report(info: &PanicHookInfo<'_>) {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = info.location().map(ToString::to_string);
+
+    // A panic while the registry is borrowed has no maps to report to
+    let maps = MAPS.with(|maps| {
+        maps.try_borrow()
+            .map(|maps| maps.clone())
+            .unwrap_or_default()
+    });
+
+    for map in maps {
+        let Some(events) = map.events.upgrade() else {
+            continue;
+        };
+        let state = map.state.upgrade();
+        let snapshot = state
+            .as_deref()
+            .and_then(|state| state.try_borrow().ok())
+            .map_or(serde_json::Value::Null, |state| snapshot(&state));
+        let error = MapError {
+            code: PANIC.to_string(),
+            message: format!("Panic: {message}"),
+            details: json!({
+                "message": message,
+                "location": location,
+                "state": snapshot,
+            }),
+        };
+        events.emit_error(&error.into());
+    }
+}
+
+/// Returns the view and content of `state` at the time of a panic
+fn snapshot(state: &MapState) -> serde_json::Value {
+    json!({
+        "width": state.ctx.css_width(),
+        "height": state.ctx.css_height(),
+        "pixelRatio": state.ctx.pixel_ratio(),
+        "markers": state.layers.marker_count(),
+        "layers": state.layers.ids(),
+        "time": state.time,
+    })
+}
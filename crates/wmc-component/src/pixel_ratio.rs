@@ -0,0 +1,58 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::AddEventListenerOptions;
+use wmc_render::log;
+
+use crate::state::MapState;
+
+/// Watches `devicePixelRatio` and resizes the drawing buffer when it changes
+///
+/// A `(resolution: Ndppx)` media query only fires once the ratio moves away
+/// from `N`, so a fresh one-shot listener is armed for the new ratio after
+/// every change (browser zoom, moving the window to another display). The
+/// listener holds a weak reference and stops re-arming once the map is gone.
+///
+/// # Errors
+///
+/// Returns `JsValue` error if the media query listener can't be registered
+pub fn watch(state: &Rc<RefCell<MapState>>) -> Result<(), JsValue> {
+    arm(Rc::downgrade(state))
+}
+
+fn arm(state: Weak<RefCell<MapState>>) -> Result<(), JsValue> {
+    let Some(window) = web_sys::window() else {
+        return Ok(());
+    };
+
+    let query = format!("(resolution: {}dppx)", window.device_pixel_ratio());
+    let Some(media) = window.match_media(&query)? else {
+        return Ok(());
+    };
+
+    let callback = Closure::once_into_js(move || {
+        let Some(strong) = state.upgrade() else {
+            return;
+        };
+
+        let ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+        if let Err(e) = strong.borrow_mut().set_pixel_ratio(ratio) {
+            log::error("frame", "Pixel ratio change failed", &[("error", e)]);
+        }
+
+        if let Err(e) = arm(state) {
+            log::error("frame", "Pixel ratio watch failed", &[("error", e)]);
+        }
+    });
+
+    let options = AddEventListenerOptions::new();
+    options.set_once(true);
+    media.add_event_listener_with_callback_and_add_event_listener_options(
+        "change",
+        callback.unchecked_ref(),
+        &options,
+    )
+}
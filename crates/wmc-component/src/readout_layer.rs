@@ -0,0 +1,111 @@
+use wmc_core::projection::{GeoCoord, Projection};
+use wmc_render::{Gl, RenderContext, RenderError, TextAnchor, TextRenderer, log};
+
+use crate::{
+    label_layer::LabelPaint,
+    layer::{Layer, LayerFrame},
+    options::{CoordinateFormat, ScaleBarCorner},
+};
+
+/// Latitude and longitude under the pointer in a corner of the map, e.g.
+/// `48.8566° N, 2.3522° E`
+///
+/// The readout is empty until the pointer moves over the canvas and once
+/// it leaves it.
+pub struct ReadoutLayer {
+    format: CoordinateFormat,
+    precision: usize,
+    corner: ScaleBarCorner,
+    paint: LabelPaint,
+    /// Position under the pointer
+    coord: Option<GeoCoord>,
+    renderer: TextRenderer,
+}
+
+impl ReadoutLayer {
+    /// Creates the text renderer for the CSS font family `font`, with an
+    /// empty readout
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        format: CoordinateFormat,
+        precision: usize,
+        corner: ScaleBarCorner,
+        paint: LabelPaint,
+        font: &str,
+    ) -> Result<Self, RenderError> {
+        Ok(Self {
+            format,
+            precision,
+            corner,
+            paint,
+            coord: None,
+            renderer: TextRenderer::new(ctx, font)?,
+        })
+    }
+
+    /// Lays out the coordinates `padding` pixels in from the corner
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn place(&mut self, ctx: &RenderContext) {
+        self.renderer.clear();
+        let Some(coord) = self.coord else {
+            return;
+        };
+
+        let text = self.format.format(coord, self.precision);
+        let (width, height) = (ctx.width() as f32, ctx.height() as f32);
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        let padding = self.paint.padding * pixel_ratio;
+
+        let (x, anchor) = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::BottomLeft => (padding, TextAnchor::Left),
+            ScaleBarCorner::TopRight | ScaleBarCorner::BottomRight => {
+                (width - padding, TextAnchor::Right)
+            },
+        };
+        let style = self.paint.style(pixel_ratio, anchor);
+        // The anchor centers the text vertically, so it's measured first
+        let [_, top, _, bottom] = self.renderer.bounds(&text, [x, 0.0], &style);
+        let half = (bottom - top) / 2.0;
+        let y = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::TopRight => padding + half,
+            ScaleBarCorner::BottomLeft | ScaleBarCorner::BottomRight => height - padding - half,
+        };
+
+        self.renderer.add(&text, [x, y], &style);
+    }
+}
+
+impl Layer for ReadoutLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Coordinate readout skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn set_pointer(&mut self, ctx: &RenderContext, coord: Option<GeoCoord>) -> bool {
+        self.coord = coord;
+        self.place(ctx);
+        true
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::{Cell, RefCell};
+
+use js_sys::{Function, Promise};
+use wasm_bindgen::JsValue;
+
+/// Pending asynchronous loads a [`crate::WorldMap::ready`] promise waits for
+///
+/// Every tracked load calls [`Readiness::begin`] when it starts and
+/// [`Readiness::finish`] when it ends. Waiting promises resolve once no load
+/// is pending and reject with the error of the first failed load.
+#[derive(Default)]
+pub struct Readiness {
+    pending: Cell<u32>,
+    /// `resolve` and `reject` of every promise still waiting
+    waiters: RefCell<Vec<(Function, Function)>>,
+}
+
+impl Readiness {
+    /// Records that a load started
+    pub fn begin(&self) {
+        self.pending.set(self.pending.get() + 1);
+    }
+
+    /// Records that a load ended, settling the waiting promises if it failed
+    /// or was the last one pending
+    pub fn finish(&self, result: Result<(), &JsValue>) {
+        self.pending.set(self.pending.get().saturating_sub(1));
+
+        if result.is_ok() && self.pending.get() > 0 {
+            return;
+        }
+
+        let waiters = std::mem::take(&mut *self.waiters.borrow_mut());
+        for (resolve, reject) in waiters {
+            // Settling an already settled promise is a no-op
+            let _ = match result {
+                Ok(()) => resolve.call0(&JsValue::NULL),
+                Err(e) => reject.call1(&JsValue::NULL, e),
+            };
+        }
+    }
+
+    /// Returns a promise resolving once no load is pending, immediately if
+    /// none is
+    pub fn promise(&self) -> Promise {
+        if self.pending.get() == 0 {
+            return Promise::resolve(&JsValue::UNDEFINED);
+        }
+
+        Promise::new(&mut |resolve, reject| {
+            self.waiters.borrow_mut().push((resolve, reject));
+        })
+    }
+}
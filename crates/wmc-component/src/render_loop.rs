@@ -0,0 +1,211 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use wmc_render::log;
+
+use crate::{global, state::MapState};
+
+type FrameClosure = Closure<dyn FnMut(f64)>;
+
+/// `requestAnimationFrame` driven render loop
+///
+/// The frame closure is created once and reused across `start`/`stop`
+/// cycles, so stopping from inside a frame callback never drops a closure
+/// that is still executing. It only holds weak references, to the loop and to
+/// the map state, so dropping the [`RenderLoop`] releases everything.
+pub struct RenderLoop {
+    shared: Rc<LoopShared>,
+}
+
+struct LoopShared {
+    running: Cell<bool>,
+    /// The canvas is scrolled out of view
+    offscreen: Cell<bool>,
+    /// The page is in a background tab or minimized
+    page_hidden: Cell<bool>,
+    request_id: Cell<Option<i32>>,
+    frame: RefCell<Option<FrameClosure>>,
+}
+
+impl RenderLoop {
+    /// Creates a stopped render loop
+    pub fn new() -> Self {
+        Self {
+            shared: Rc::new(LoopShared {
+                running: Cell::new(false),
+                offscreen: Cell::new(false),
+                page_hidden: Cell::new(false),
+                request_id: Cell::new(None),
+                frame: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Returns true while the loop is started, even if suspended
+    pub fn is_running(&self) -> bool {
+        self.shared.running.get()
+    }
+
+    /// Returns true while the loop is started but skips frames because the
+    /// canvas isn't visible
+    pub fn is_suspended(&self) -> bool {
+        self.shared.running.get() && self.shared.is_suspended()
+    }
+
+    /// Returns a handle suspending the loop while the canvas isn't visible
+    pub fn pause_handle(&self, state: &Rc<RefCell<MapState>>) -> PauseHandle {
+        PauseHandle {
+            shared: Rc::downgrade(&self.shared),
+            state: Rc::downgrade(state),
+        }
+    }
+
+    /// Starts rendering on every animation frame
+    ///
+    /// Does nothing if the loop is already running.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the first frame can't be requested
+    pub fn start(&self, state: &Rc<RefCell<MapState>>) -> Result<(), JsValue> {
+        if self.shared.running.get() {
+            return Ok(());
+        }
+
+        if self.shared.frame.borrow().is_none() {
+            let frame = Self::frame_closure(Rc::downgrade(&self.shared), Rc::downgrade(state));
+            *self.shared.frame.borrow_mut() = Some(frame);
+        }
+
+        state.borrow_mut().reset_clock();
+        self.shared.running.set(true);
+
+        if !self.shared.is_suspended() && self.shared.request_id.get().is_none() {
+            self.shared.request()?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops scheduling frames
+    pub fn stop(&self) {
+        self.shared.running.set(false);
+        self.shared.cancel();
+    }
+
+    fn frame_closure(shared: Weak<LoopShared>, state: Weak<RefCell<MapState>>) -> FrameClosure {
+        Closure::new(move |timestamp: f64| {
+            let Some(shared) = shared.upgrade() else {
+                return;
+            };
+            shared.request_id.set(None);
+
+            let Some(state) = state.upgrade() else {
+                shared.running.set(false);
+                return;
+            };
+            if !shared.running.get() || shared.is_suspended() {
+                return;
+            }
+
+            // The state borrow ends before the JS callback, which may call back
+            // into the map
+            let callback = state.borrow_mut().frame(timestamp);
+            if let Some((callback, time, delta)) = callback
+                && let Err(e) =
+                    callback.call2(&JsValue::NULL, &JsValue::from(time), &JsValue::from(delta))
+            {
+                log::error("frame", "Frame callback threw", &[("error", e)]);
+            }
+
+            if shared.running.get()
+                && shared.request_id.get().is_none()
+                && let Err(e) = shared.request()
+            {
+                shared.running.set(false);
+                log::error("frame", "Render loop stopped", &[("error", e)]);
+            }
+        })
+    }
+}
+
+impl Drop for RenderLoop {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Suspends a [`RenderLoop`] while its canvas is off-screen or the page
+/// is hidden, and resumes it when both are visible again
+///
+/// Suspending keeps the loop started, so it resumes where the host left it;
+/// a stopped loop stays stopped. The handle holds weak references only.
+#[derive(Clone)]
+pub struct PauseHandle {
+    shared: Weak<LoopShared>,
+    state: Weak<RefCell<MapState>>,
+}
+
+impl PauseHandle {
+    /// Records whether the canvas is scrolled out of view
+    pub fn set_offscreen(&self, offscreen: bool) {
+        self.update(|shared| shared.offscreen.set(offscreen));
+    }
+
+    /// Records whether the page is hidden
+    pub fn set_page_hidden(&self, hidden: bool) {
+        self.update(|shared| shared.page_hidden.set(hidden));
+    }
+
+    fn update(&self, change: impl FnOnce(&LoopShared)) {
+        let Some(shared) = self.shared.upgrade() else {
+            return;
+        };
+
+        change(&shared);
+        if shared.is_suspended() {
+            shared.cancel();
+            return;
+        }
+        if !shared.running.get() || shared.request_id.get().is_some() {
+            return;
+        }
+
+        // Animations resume without jumping over the suspended time
+        if let Some(state) = self.state.upgrade()
+            && let Ok(mut state) = state.try_borrow_mut()
+        {
+            state.reset_clock();
+        }
+        if let Err(e) = shared.request() {
+            shared.running.set(false);
+            log::error("frame", "Render loop stopped", &[("error", e)]);
+        }
+    }
+}
+
+impl LoopShared {
+    const fn is_suspended(&self) -> bool {
+        self.offscreen.get() || self.page_hidden.get()
+    }
+
+    fn request(&self) -> Result<(), JsValue> {
+        let frame = self.frame.borrow();
+        let Some(frame) = frame.as_ref() else {
+            return Ok(());
+        };
+
+        let id = global::request_animation_frame(frame.as_ref().unchecked_ref())?;
+        self.request_id.set(Some(id));
+        Ok(())
+    }
+
+    fn cancel(&self) {
+        if let Some(id) = self.request_id.take() {
+            global::cancel_animation_frame(id);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use wmc_core::{
+    label::zoom_level,
+    marker::{Color, Marker},
+    marker_buffer::{MarkerBuffer, MarkerInstance},
+    projection::{GeoCoord, Projection},
+    theme::{MarkerStyle, RippleStyle, Theme},
+};
+use wmc_render::{Gl, RenderContext, RenderError, RippleRenderer, ScissorRect};
+
+use crate::layer::{Layer, LayerFrame};
+
+/// Most ripples running at once; older ones are dropped for newer ones
+const MAX_RIPPLES: usize = 32;
+
+/// Ripple running from a marker
+struct Ripple {
+    coord: GeoCoord,
+    /// Projected center
+    center: [f32; 2],
+    /// Radius of the marker in CSS pixels before zoom rules
+    radius: f32,
+    color: Color,
+    /// Animation time in seconds the ripple started at
+    start: f32,
+}
+
+/// Rings spreading from markers as they're selected or added, in the
+/// theme's [`RippleStyle`], drawn over the markers layer
+///
+/// Ripples run on the camera clock, so they only cost an upload when one
+/// starts or ends.
+pub struct RippleLayer {
+    style: RippleStyle,
+    markers_style: MarkerStyle,
+    ripples: Vec<Ripple>,
+    buffer: MarkerBuffer,
+    /// Zoom the markers style's zoom rules are applied for
+    zoom: f32,
+    renderer: RippleRenderer,
+}
+
+impl RippleLayer {
+    /// Creates the layer without ripples
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if instancing is unavailable or renderer
+    /// initialization fails
+    pub fn new(ctx: &RenderContext, theme: &Theme) -> Result<Self, RenderError> {
+        Ok(Self {
+            style: theme.ripple,
+            markers_style: theme.markers,
+            ripples: Vec::new(),
+            buffer: MarkerBuffer::new(MAX_RIPPLES),
+            zoom: 0.0,
+            renderer: RippleRenderer::new(ctx)?,
+        })
+    }
+
+    /// Returns the radius in CSS pixels rings start at, the largest of the
+    /// rippling markers as drawn
+    fn start_radius(&self) -> f32 {
+        self.ripples
+            .iter()
+            .map(|ripple| ripple.radius)
+            .fold(0.0, f32::max)
+            * self.markers_style.radius_at(self.zoom)
+    }
+
+    /// Uploads the instances of the running ripples
+    fn upload(&mut self, ctx: &RenderContext) {
+        self.buffer.clear();
+        for ripple in &self.ripples {
+            let Color { r, g, b, a } = ripple.color;
+            // Capacity covers every running ripple
+            let _ = self.buffer.push(MarkerInstance {
+                x: ripple.center[0],
+                y: ripple.center[1],
+                intensity: 1.0,
+                phase: ripple.start,
+                r,
+                g,
+                b,
+                a,
+            });
+        }
+        self.renderer.upload(ctx, &self.buffer);
+    }
+
+    /// Projects the ripple centers and applies the zoom rules for the
+    /// current canvas width
+    #[allow(clippy::cast_possible_truncation)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        self.zoom = zoom_level(f64::from(ctx.css_width())) as f32;
+        for ripple in &mut self.ripples {
+            let point = projection.project(ripple.coord);
+            ripple.center = [point.x as f32, point.y as f32];
+        }
+        self.upload(ctx);
+    }
+}
+
+impl Layer for RippleLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let time = frame.ctx.camera().time;
+        let count = self.ripples.len();
+        self.ripples
+            .retain(|ripple| time - ripple.start < self.style.duration);
+        if self.ripples.len() != count {
+            self.upload(frame.ctx);
+        }
+
+        self.renderer.draw(
+            frame.ctx,
+            self.start_radius() * frame.ctx.pixel_ratio() as f32,
+            self.style.duration,
+            self.style.scale,
+        );
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+
+    fn is_animated(&self) -> bool {
+        !self.ripples.is_empty()
+    }
+
+    /// Covers every ring at full spread
+    #[allow(clippy::cast_possible_truncation)]
+    fn damage(&self, frame: &LayerFrame<'_>) -> Option<ScissorRect> {
+        // One extra pixel for filtering at the ring edge
+        let margin =
+            (self.start_radius() * self.style.scale).mul_add(frame.ctx.pixel_ratio() as f32, 1.0);
+
+        self.ripples
+            .iter()
+            .filter_map(|ripple| {
+                let [x, y] = ripple.center;
+                frame
+                    .ctx
+                    .scissor_for([x - margin, y - margin, x + margin, y + margin])
+            })
+            .reduce(ScissorRect::union)
+    }
+
+    fn set_theme(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+        theme: &Theme,
+    ) -> Result<(), RenderError> {
+        self.style = theme.ripple;
+        self.markers_style = theme.markers;
+        if !self.style.is_enabled() {
+            self.ripples.clear();
+            self.upload(ctx);
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn add_ripples(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        markers: &[Marker],
+        time: f32,
+    ) -> bool {
+        if !self.style.is_enabled() {
+            return false;
+        }
+
+        self.zoom = zoom_level(f64::from(ctx.css_width())) as f32;
+        for marker in markers {
+            let point = projection.project(marker.coord);
+            let own = marker
+                .color
+                .unwrap_or_else(|| self.markers_style.color_at(marker.intensity));
+            self.ripples.push(Ripple {
+                coord: marker.coord,
+                center: [point.x as f32, point.y as f32],
+                radius: marker.radius,
+                color: self.style.color_for(own),
+                start: time,
+            });
+        }
+        let excess = self.ripples.len().saturating_sub(MAX_RIPPLES);
+        self.ripples.drain(..excess);
+
+        self.upload(ctx);
+        true
+    }
+}
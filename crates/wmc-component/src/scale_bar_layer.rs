@@ -0,0 +1,166 @@
+use wmc_core::{
+    projection::{ProjectedCoord, Projection},
+    scale_bar::{ScaleBar, ScaleUnit},
+};
+use wmc_render::{Gl, RenderContext, RenderError, TextAnchor, TextRenderer, log};
+
+use crate::{
+    label_layer::LabelPaint,
+    layer::{Layer, LayerFrame, color_array},
+    options::ScaleBarCorner,
+};
+
+/// Bar thickness in CSS pixels
+const BAR_WIDTH: f32 = 2.0;
+
+/// Height of the end ticks in CSS pixels
+const TICK_HEIGHT: f32 = 6.0;
+
+/// Gap between the label and the ticks in CSS pixels
+const LABEL_GAP: f32 = 2.0;
+
+/// Bar of a round ground distance in a corner of the map, e.g. `500 km`
+///
+/// The distance is measured east-west at the view center and refitted on
+/// every view change, so the bar stays true to the projection's scale there.
+pub struct ScaleBarLayer {
+    unit: ScaleUnit,
+    /// Longest bar length in CSS pixels
+    max_width: f32,
+    corner: ScaleBarCorner,
+    paint: LabelPaint,
+    renderer: TextRenderer,
+}
+
+impl ScaleBarLayer {
+    /// Creates the text renderer for the CSS font family `font` and lays out
+    /// the bar
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        unit: ScaleUnit,
+        max_width: f32,
+        corner: ScaleBarCorner,
+        projection: &dyn Projection,
+        paint: LabelPaint,
+        font: &str,
+    ) -> Result<Self, RenderError> {
+        let mut layer = Self {
+            unit,
+            max_width,
+            corner,
+            paint,
+            renderer: TextRenderer::new(ctx, font)?,
+        };
+        layer.place(ctx, projection);
+        Ok(layer)
+    }
+
+    /// Fits the bar to the scale at the view center and lays it out with its
+    /// label above, `padding` pixels in from the corner
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        self.renderer.clear();
+
+        let (width, height) = (ctx.width() as f32, ctx.height() as f32);
+        let [x, y] = ctx.to_map([width / 2.0, height / 2.0]);
+        let center = projection.unproject(ProjectedCoord {
+            x: f64::from(x),
+            y: f64::from(y),
+        });
+        let pixel_ratio = ctx.pixel_ratio() as f32;
+        // Zooming in by one shows every map pixel on two screen pixels
+        let Some(bar) = ScaleBar::fit(
+            projection.meters_per_pixel(center) / f64::from(ctx.view().zoom).exp2(),
+            f64::from(self.max_width * pixel_ratio),
+            self.unit,
+        ) else {
+            return;
+        };
+
+        let padding = self.paint.padding * pixel_ratio;
+        let thickness = BAR_WIDTH * pixel_ratio;
+        let tick = TICK_HEIGHT * pixel_ratio;
+        let gap = LABEL_GAP * pixel_ratio;
+        let length = bar.width as f32;
+
+        let left = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::BottomLeft => padding,
+            ScaleBarCorner::TopRight | ScaleBarCorner::BottomRight => width - padding - length,
+        };
+        let label_x = length.mul_add(0.5, left);
+        let (bottom, label) = match self.corner {
+            ScaleBarCorner::TopLeft | ScaleBarCorner::TopRight => {
+                let style = self.paint.style(pixel_ratio, TextAnchor::Top);
+                let label = ([label_x, padding], style);
+                let bounds = self.renderer.bounds(&bar.label, label.0, &style);
+                (bounds[3] + gap + tick, label)
+            },
+            ScaleBarCorner::BottomLeft | ScaleBarCorner::BottomRight => {
+                let bottom = height - padding;
+                let style = self.paint.style(pixel_ratio, TextAnchor::Bottom);
+                (bottom, ([label_x, bottom - tick - gap], style))
+            },
+        };
+
+        let right = left + length;
+        let boxes = [
+            [left, bottom - thickness, right, bottom],
+            [left, bottom - tick, left + thickness, bottom],
+            [right - thickness, bottom - tick, right, bottom],
+        ];
+        // Halos go below every box so they don't cut into the joints
+        let halo = self.paint.halo_width * pixel_ratio;
+        if halo > 0.0 {
+            for [x0, y0, x1, y1] in boxes {
+                self.renderer.add_box(
+                    [x0 - halo, y0 - halo, x1 + halo, y1 + halo],
+                    color_array(self.paint.halo_color),
+                );
+            }
+        }
+        for bounds in boxes {
+            self.renderer.add_box(bounds, color_array(self.paint.color));
+        }
+
+        let (position, style) = label;
+        self.renderer.add(&bar.label, position, &style);
+    }
+}
+
+impl Layer for ScaleBarLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        if let Err(e) = self.renderer.draw(frame.ctx) {
+            log::warn(
+                "layer",
+                "Scale bar skipped for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+    }
+}
@@ -0,0 +1,163 @@
+use wmc_core::{
+    label::zoom_level,
+    marker::{Color, Marker},
+    marker_buffer::{MarkerBuffer, MarkerInstance},
+    projection::Projection,
+    theme::{MarkerStyle, SelectionStyle, Theme},
+};
+use wmc_render::{Gl, MarkerRenderer, RenderContext, RenderError, ScissorRect};
+
+use crate::layer::{Layer, LayerFrame};
+
+/// Ring radius as a multiple of the selected marker's radius; the ring
+/// covers the outer fifth of it, leaving a gap around the marker
+const RING_SCALE: f32 = 1.4;
+
+/// Selected marker drawn again over the markers layer, in the theme's
+/// [`SelectionStyle`]: recolored, scaled up and ringed
+///
+/// The marker and its ring pulse with the marker below, which they cover.
+pub struct SelectionLayer {
+    marker: Option<Marker>,
+    markers_style: MarkerStyle,
+    style: SelectionStyle,
+    ring_color: Color,
+    /// Instances of the marker and of its ring
+    fill: MarkerBuffer,
+    ring: MarkerBuffer,
+    /// Zoom the markers style's zoom rules are applied for
+    zoom: f32,
+    /// Projected marker center
+    center: Option<[f32; 2]>,
+    fill_renderer: MarkerRenderer,
+    ring_renderer: MarkerRenderer,
+}
+
+impl SelectionLayer {
+    /// Creates the layer without a selected marker
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if instancing is unavailable or renderer
+    /// initialization fails
+    pub fn new(ctx: &RenderContext, theme: &Theme) -> Result<Self, RenderError> {
+        Ok(Self {
+            marker: None,
+            markers_style: theme.markers,
+            style: theme.selection,
+            ring_color: theme.selection.ring_color(theme.background),
+            fill: MarkerBuffer::new(1),
+            ring: MarkerBuffer::new(1),
+            zoom: 0.0,
+            center: None,
+            fill_renderer: MarkerRenderer::new(ctx)?,
+            ring_renderer: MarkerRenderer::ring(ctx)?,
+        })
+    }
+
+    /// Returns the radius in CSS pixels the selected marker is drawn with
+    fn drawn_radius(&self) -> f32 {
+        self.marker.as_ref().map_or(0.0, |marker| {
+            marker.radius * self.markers_style.radius_at(self.zoom) * self.style.scale
+        })
+    }
+
+    /// Uploads the instances of the selected marker, if any
+    #[allow(clippy::cast_possible_truncation)]
+    fn place(&mut self, ctx: &RenderContext, projection: &dyn Projection) {
+        self.fill.clear();
+        self.ring.clear();
+        self.zoom = zoom_level(f64::from(ctx.css_width())) as f32;
+        self.center = self.marker.as_ref().map(|marker| {
+            let point = projection.project(marker.coord);
+            [point.x as f32, point.y as f32]
+        });
+
+        if let (Some(marker), Some([x, y])) = (&self.marker, self.center) {
+            let own = marker
+                .color
+                .unwrap_or_else(|| self.markers_style.color_at(marker.intensity));
+            // Full strength whatever the marker's intensity and zoom opacity
+            let instance = |Color { r, g, b, a }| MarkerInstance {
+                x,
+                y,
+                intensity: 1.0,
+                phase: marker.phase(),
+                r,
+                g,
+                b,
+                a,
+            };
+            // Capacity covers the one instance
+            let _ = self.fill.push(instance(self.style.fill(own)));
+            let _ = self.ring.push(instance(self.ring_color));
+        }
+
+        self.fill_renderer.upload(ctx, &self.fill);
+        self.ring_renderer.upload(ctx, &self.ring);
+    }
+}
+
+impl Layer for SelectionLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let size = self.drawn_radius() * frame.ctx.pixel_ratio() as f32;
+        self.ring_renderer.draw(frame.ctx, size * RING_SCALE);
+        self.fill_renderer.draw(frame.ctx, size);
+    }
+
+    fn delete(&self, gl: &Gl) {
+        self.fill_renderer.delete(gl);
+        self.ring_renderer.delete(gl);
+    }
+
+    fn is_animated(&self) -> bool {
+        self.center.is_some()
+    }
+
+    /// Covers the ring at full pulse size
+    #[allow(clippy::cast_possible_truncation)]
+    fn damage(&self, frame: &LayerFrame<'_>) -> Option<ScissorRect> {
+        let [x, y] = self.center?;
+        // One extra pixel for filtering at the ring edge
+        let margin =
+            (self.drawn_radius() * RING_SCALE).mul_add(frame.ctx.pixel_ratio() as f32, 1.0);
+
+        frame
+            .ctx
+            .scissor_for([x - margin, y - margin, x + margin, y + margin])
+    }
+
+    fn set_theme(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        theme: &Theme,
+    ) -> Result<(), RenderError> {
+        self.markers_style = theme.markers;
+        self.style = theme.selection;
+        self.ring_color = theme.selection.ring_color(theme.background);
+        self.place(ctx, projection);
+        Ok(())
+    }
+
+    fn select_marker(
+        &mut self,
+        ctx: &RenderContext,
+        projection: &dyn Projection,
+        marker: Option<&Marker>,
+    ) -> bool {
+        self.marker = marker.cloned();
+        self.place(ctx, projection);
+        true
+    }
+}
@@ -0,0 +1,1218 @@
+use std::{collections::HashSet, rc::Rc};
+
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+use wmc_core::{
+    history::{EditHistory, MarkerEdit},
+    legend::Legend,
+    marker::{Marker, MarkerId},
+    projection::{GeoCoord, MercatorProjection, ProjectedCoord, Projection},
+    svg::SvgDocument,
+    theme::Theme,
+    topology::WorldTopology,
+};
+use wmc_render::{
+    BlendMode, ColorGradeEffect, FillMode, Gl, GpuTimer, GrainEffect, MarkerRenderer, PostEffect,
+    PostProcessor, RenderContext, RenderError, RenderStats, ScissorRect, View, VignetteEffect,
+    log, stats,
+};
+
+use crate::{
+    accessibility::MarkerList,
+    compass_layer::CompassLayer,
+    css_theme::CssTheme,
+    dirty::DirtyFlags,
+    error,
+    events::{EventEmitter, PointerListener},
+    geometry_worker::{GeometryWorker, WorldLines},
+    global,
+    hash_sync::{HashSync, HashView},
+    label_layer::LabelPaint,
+    layer::{self, FillLayer, Layer, LayerFrame, LayerStack, LineLayer, MarkerLayer, Paint},
+    marker_poll::MarkerPoll,
+    options::{
+        CompassOptions, CoordinateReadoutOptions, Interactions, MarkerOrder, ScaleBarOptions,
+    },
+    readout_layer::ReadoutLayer,
+    ready::Readiness,
+    ripple_layer::RippleLayer,
+    scale_bar_layer::ScaleBarLayer,
+    selection_layer::SelectionLayer,
+};
+
+/// CPU milliseconds above which a frame is logged as slow, two frames at
+/// 60 Hz
+const SLOW_FRAME_MS: f64 = 33.0;
+
+/// Mutable map state shared between the JS handle and async tasks
+pub struct MapState {
+    pub ctx: RenderContext,
+    pub theme: Theme,
+    /// Map layers, drawn bottom to top
+    pub layers: LayerStack,
+    /// Post-processing effects applied to every frame
+    pub post: PostProcessor,
+    /// Animation time in seconds, advanced by the render loop
+    pub time: f64,
+    /// Timestamp of the previous animation frame in milliseconds
+    pub last_frame: Option<f64>,
+    /// Animation time in seconds every frame is drawn at, for pixel-identical
+    /// renders of the same state
+    pub fixed_time: Option<f64>,
+    /// JS callback invoked after every loop frame with `(time, deltaMs)`
+    pub frame_callback: Option<Function>,
+    /// Pending reasons to redraw on the next loop frame
+    pub dirty: DirtyFlags,
+    /// Statistics of the last drawn frame
+    pub stats: RenderStats,
+    /// Custom fragment shader of the world layer, kept across reloads
+    pub world_shader: Option<String>,
+    /// Custom fragment shader of the markers layer, kept across reloads
+    pub marker_shader: Option<String>,
+    /// Per-pass GPU timing, when enabled
+    pub gpu_timer: Option<GpuTimer>,
+    /// Options of the scale bar layer, kept to switch its unit
+    pub scale_bar: Option<ScaleBarOptions>,
+    /// Theme colors followed from CSS custom properties, when enabled
+    pub css_theme: Option<CssTheme>,
+    /// Host callbacks, shared with listeners that emit outside a borrow
+    pub events: Rc<EventEmitter>,
+    /// Canvas pointer listener, on the main thread
+    pub pointer: Option<PointerListener>,
+    /// Marker source polled by [`crate::WorldMap::poll_markers`]
+    pub marker_poll: Option<MarkerPoll>,
+    /// Loads [`crate::WorldMap::ready`] waits for
+    pub readiness: Rc<Readiness>,
+    /// Pointer interactions the pointer listener reports
+    pub interactions: Interactions,
+    /// Zoom step views settle on, see [`crate::WorldMap::set_zoom_snap`]
+    pub zoom_snap: f32,
+    /// Id of the selected marker, set by a click, the URL hash or
+    /// [`crate::WorldMap::select_marker`]
+    pub selected: Option<String>,
+    /// `location.hash` mirror of the selection, when enabled
+    pub hash_sync: Option<HashSync>,
+    /// Markers exposed to assistive technology, when enabled
+    pub marker_list: Option<MarkerList>,
+    /// Id of the marker focused from the keyboard
+    pub focused: Option<String>,
+    /// Marker groups hidden by [`crate::WorldMap::hide_group`]
+    pub hidden_groups: HashSet<String>,
+    /// Undoable marker edits since the markers were last replaced
+    pub history: EditHistory,
+    /// Order and limit markers are drawn in
+    pub marker_order: MarkerOrder,
+    /// Worker building loaded topologies off the main thread, when set
+    pub geometry_worker: Option<Rc<GeometryWorker>>,
+}
+
+impl MapState {
+    /// Creates the state for a world topology, building its layers
+    ///
+    /// The stack starts with a hidden graticule below the world layer.
+    pub fn new(
+        ctx: RenderContext,
+        topology: WorldTopology,
+        theme: Theme,
+    ) -> Result<Self, JsValue> {
+        let projection = MercatorProjection::new(f64::from(ctx.width()), f64::from(ctx.height()));
+
+        let mut layers = LayerStack::new();
+        let graticule = LineLayer::new(
+            &ctx,
+            WorldTopology::graticule(layer::GRATICULE_STEP),
+            &projection,
+            Paint::Graticule,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        layers.set(layer::GRATICULE, Box::new(graticule), None);
+        layers.set_visible(layer::GRATICULE, false);
+        let world = LineLayer::new(&ctx, topology, &projection, Paint::Coastlines)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        layers.set(layer::WORLD, Box::new(world), None);
+
+        let mut post =
+            PostProcessor::new(ctx.gl()).map_err(error::to_js_in("Renderer init failed"))?;
+        for effect in
+            theme_effects(ctx.gl(), &theme).map_err(error::to_js_in("Renderer init failed"))?
+        {
+            post.push(effect);
+        }
+
+        Ok(Self {
+            ctx,
+            theme,
+            layers,
+            post,
+            time: 0.0,
+            last_frame: None,
+            fixed_time: None,
+            frame_callback: None,
+            dirty: DirtyFlags::all(),
+            stats: RenderStats::default(),
+            world_shader: None,
+            marker_shader: None,
+            gpu_timer: None,
+            scale_bar: None,
+            css_theme: None,
+            events: Rc::default(),
+            pointer: None,
+            marker_poll: None,
+            readiness: Rc::default(),
+            interactions: Interactions::default(),
+            zoom_snap: 0.0,
+            selected: None,
+            hash_sync: None,
+            marker_list: None,
+            focused: None,
+            hidden_groups: HashSet::new(),
+            history: EditHistory::default(),
+            marker_order: MarkerOrder::default(),
+            geometry_worker: None,
+        })
+    }
+
+    /// Returns the projection matching the current viewport
+    pub fn projection(&self) -> MercatorProjection {
+        MercatorProjection::new(f64::from(self.ctx.width()), f64::from(self.ctx.height()))
+    }
+
+    /// Exports the visible vector layers as an SVG document
+    ///
+    /// Geometry is projected at the canvas CSS size, so line widths and
+    /// marker radii keep their CSS pixel values.
+    pub fn export_svg(&self) -> String {
+        let projection = MercatorProjection::new(
+            f64::from(self.ctx.css_width()),
+            f64::from(self.ctx.css_height()),
+        );
+        let mut svg = SvgDocument::new(
+            self.ctx.css_width(),
+            self.ctx.css_height(),
+            self.theme.background,
+        );
+
+        self.layers.export_svg(&mut svg, &projection, &self.theme);
+        svg.finish()
+    }
+
+    /// Describes the visible built-in layers in the theme's styles
+    pub fn legend(&self) -> Legend {
+        let mut legend = Legend::from_theme(&self.theme);
+        let layers = [
+            layer::WORLD,
+            layer::RIVERS,
+            layer::LAKES,
+            layer::GRATICULE,
+            layer::MARKERS,
+        ];
+        let mut visible = layers
+            .iter()
+            .map(|id| self.layers.is_visible(id).unwrap_or(false));
+        legend.entries.retain(|_| visible.next().unwrap_or(false));
+        legend
+    }
+
+    /// Resizes the viewport to a CSS size and fits all loaded geometry to it
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.ctx.resize(width, height);
+        self.dirty.viewport = true;
+        self.reproject()
+    }
+
+    /// Applies a new device pixel ratio, reprojecting geometry if it changed
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) -> Result<(), JsValue> {
+        if self.ctx.set_pixel_ratio(pixel_ratio) {
+            self.dirty.viewport = true;
+            self.reproject()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Enables or disables pointer interactions, letting the browser handle
+    /// touches on the canvas while no gesture is enabled
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the canvas style can't be set
+    pub fn set_interactions(&mut self, interactions: Interactions) -> Result<(), JsValue> {
+        self.interactions = interactions;
+        self.pointer
+            .as_ref()
+            .map_or(Ok(()), |pointer| pointer.follow(interactions))
+    }
+
+    /// Pans, zooms and rotates the map to `view`, its zoom snapped to
+    /// [`Self::zoom_snap`] about the center of the map
+    ///
+    /// Geometry keeps its projection; only markers culled to the screen and
+    /// text laid out in screen pixels follow. The view is mirrored into the
+    /// URL hash when synchronized.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn set_view(&mut self, view: View) -> Result<(), JsValue> {
+        let (width, height) = (self.ctx.width() as f32, self.ctx.height() as f32);
+        self.settle_view(view, [width / 2.0, height / 2.0])
+    }
+
+    /// Moves the map to `view` as [`Self::set_view`] does, snapping its zoom
+    /// about `anchor` in drawing buffer pixels, e.g. where a gesture ended
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view or the
+    /// hash write can't be timed
+    #[allow(clippy::cast_precision_loss)]
+    pub fn settle_view(&mut self, view: View, anchor: [f32; 2]) -> Result<(), JsValue> {
+        let (width, height) = (self.ctx.width() as f32, self.ctx.height() as f32);
+        self.move_view(view.snap_zoom(self.zoom_snap, width, height, anchor))?;
+        self.write_hash_view()
+    }
+
+    /// Moves the map to `view` as is, for gestures still in progress
+    ///
+    /// Neither the zoom is snapped nor the hash written; the gesture
+    /// settles the view once it ends, see [`Self::settle_view`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view
+    pub fn move_view(&mut self, view: View) -> Result<(), JsValue> {
+        if view == self.ctx.view() {
+            return Ok(());
+        }
+        self.ctx.set_view(view);
+        self.dirty.scene = true;
+
+        let projection = self.projection();
+        self.layers
+            .set_view(&self.ctx, &projection)
+            .map_err(error::to_js_in("View update failed"))
+    }
+
+    /// Returns the view center, as fractions of the map, that puts `coord`
+    /// in the middle of the canvas
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn center_at(&self, coord: GeoCoord) -> [f32; 2] {
+        let point = self.projection().project(coord);
+        [
+            (point.x / f64::from(self.ctx.width().max(1))) as f32,
+            (point.y / f64::from(self.ctx.height().max(1))) as f32,
+        ]
+    }
+
+    /// Returns the coordinate in the middle of the canvas
+    pub fn center_coord(&self) -> GeoCoord {
+        let [x, y] = self.ctx.view().center;
+        self.projection().unproject(ProjectedCoord {
+            x: f64::from(x) * f64::from(self.ctx.width()),
+            y: f64::from(y) * f64::from(self.ctx.height()),
+        })
+    }
+
+    /// Shows the view named in the URL hash
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the layers can't follow the view or the
+    /// hash can't be written
+    pub fn show_hash_view(&mut self, hash: &HashView) -> Result<(), JsValue> {
+        let view = View {
+            center: self.center_at(hash.center),
+            zoom: hash.zoom,
+            rotation: hash.bearing.rem_euclid(360.0).to_radians(),
+        };
+        self.set_view(view)
+    }
+
+    /// Replaces the view in the URL hash with the current one when
+    /// synchronized
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the hash write can't be timed
+    pub fn write_hash_view(&self) -> Result<(), JsValue> {
+        let Some(sync) = &self.hash_sync else {
+            return Ok(());
+        };
+        let view = self.ctx.view();
+        sync.write_view(&HashView {
+            zoom: view.zoom,
+            center: self.center_coord(),
+            bearing: view.rotation.to_degrees(),
+        })
+    }
+
+    /// Fits all layers to the current drawing buffer size
+    fn reproject(&mut self) -> Result<(), JsValue> {
+        let projection = self.projection();
+
+        self.layers
+            .reproject(&self.ctx, &projection)
+            .map_err(error::to_js_in("Renderer reinit failed"))
+    }
+
+    /// Builds a layer for `topology` in the current projection, filled with
+    /// `fill` or drawn as lines when `None`
+    pub fn build_layer(
+        &self,
+        topology: WorldTopology,
+        paint: Paint,
+        fill: Option<FillMode>,
+    ) -> Result<Box<dyn Layer>, RenderError> {
+        let projection = self.projection();
+
+        Ok(match fill {
+            Some(mode) => Box::new(FillLayer::new(
+                &self.ctx,
+                topology,
+                &projection,
+                paint,
+                mode,
+            )?),
+            None => Box::new(LineLayer::new(&self.ctx, topology, &projection, paint)?),
+        })
+    }
+
+    /// Adds or replaces a layer; new layers go to `index`, or on top
+    pub fn set_layer(&mut self, id: &str, layer: Box<dyn Layer>, index: Option<usize>) {
+        if let Some(old) = self.layers.set(id, layer, index) {
+            old.delete(self.ctx.gl());
+        }
+        self.dirty.scene = true;
+    }
+
+    /// Builds the scale bar layer from `options`, on top of the stack or
+    /// replacing the current one in place
+    pub fn set_scale_bar(&mut self, options: ScaleBarOptions) -> Result<(), JsValue> {
+        let paint = LabelPaint {
+            size: options.size,
+            color: options
+                .color
+                .unwrap_or_else(|| self.theme.background.contrasting()),
+            halo_color: options.halo_color.unwrap_or(self.theme.background),
+            halo_width: options.halo_width,
+            padding: options.padding,
+        };
+        let layer = ScaleBarLayer::new(
+            &self.ctx,
+            options.unit,
+            options.max_width,
+            options.corner,
+            &self.projection(),
+            paint,
+            &options.font,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        self.set_layer(layer::SCALE_BAR, Box::new(layer), None);
+        self.scale_bar = Some(options);
+        Ok(())
+    }
+
+    /// Builds the coordinate readout layer from `options`, on top of the
+    /// stack or replacing the current one in place
+    pub fn set_coordinate_readout(
+        &mut self,
+        options: &CoordinateReadoutOptions,
+    ) -> Result<(), JsValue> {
+        let paint = LabelPaint {
+            size: options.size,
+            color: options
+                .color
+                .unwrap_or_else(|| self.theme.background.contrasting()),
+            halo_color: options.halo_color.unwrap_or(self.theme.background),
+            halo_width: options.halo_width,
+            padding: options.padding,
+        };
+        let layer = ReadoutLayer::new(
+            &self.ctx,
+            options.format,
+            options.precision,
+            options.corner,
+            paint,
+            &options.font,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        self.set_layer(layer::COORDINATE_READOUT, Box::new(layer), None);
+        Ok(())
+    }
+
+    /// Builds the compass layer from `options`, on top of the stack or
+    /// replacing the current one in place
+    pub fn set_compass(&mut self, options: &CompassOptions) -> Result<(), JsValue> {
+        let paint = LabelPaint {
+            size: options.size,
+            color: options
+                .color
+                .unwrap_or_else(|| self.theme.background.contrasting()),
+            halo_color: options.halo_color.unwrap_or(self.theme.background),
+            halo_width: 0.0,
+            padding: options.padding,
+        };
+        let layer = CompassLayer::new(
+            &self.ctx,
+            options.corner,
+            options.radius,
+            paint,
+            options.north_color,
+            &options.font,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        self.set_layer(layer::COMPASS, Box::new(layer), None);
+        Ok(())
+    }
+
+    /// Switches to `theme`, restyling the frame, layers and post effects
+    ///
+    /// With CSS theming on, `theme` becomes the base the custom properties
+    /// override. Layers with explicit colors keep them.
+    pub fn set_theme(&mut self, theme: Theme) -> Result<(), JsValue> {
+        match &mut self.css_theme {
+            Some(css) => {
+                css.set_base(theme);
+                self.refresh_css_theme()
+            },
+            None => self.apply_theme(theme),
+        }
+    }
+
+    /// Re-reads the theme CSS custom properties and applies them if they
+    /// changed
+    pub fn refresh_css_theme(&mut self) -> Result<(), JsValue> {
+        let Some(css) = &mut self.css_theme else {
+            return Ok(());
+        };
+        let Some(theme) = css.resolve()? else {
+            return Ok(());
+        };
+        theme.validate().map_err(error::to_js_in("Invalid theme"))?;
+        self.apply_theme(theme)
+    }
+
+    /// Restyles the frame, layers and post effects for `theme`
+    fn apply_theme(&mut self, theme: Theme) -> Result<(), JsValue> {
+        let effects = theme_effects(self.ctx.gl(), &theme)
+            .map_err(error::to_js_in("Renderer reinit failed"))?;
+        if let Err(e) = self.layers.set_theme(&self.ctx, &self.projection(), &theme) {
+            for effect in &effects {
+                effect.program().delete(self.ctx.gl());
+            }
+            return Err(JsValue::from_str(&format!("Renderer reinit failed: {e}")));
+        }
+
+        while let Some(old) = self.post.remove(0) {
+            old.program().delete(self.ctx.gl());
+        }
+        for effect in effects {
+            self.post.push(effect);
+        }
+        self.theme = theme;
+        self.dirty = DirtyFlags::all();
+        Ok(())
+    }
+
+    /// Replaces the world topology and rebuilds its layer
+    pub fn set_world(&mut self, topology: WorldTopology) -> Result<(), JsValue> {
+        let world = LineLayer::with_fragment(
+            &self.ctx,
+            topology,
+            &self.projection(),
+            Paint::Coastlines,
+            self.world_shader.clone(),
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        self.set_layer(layer::WORLD, Box::new(world), None);
+        Ok(())
+    }
+
+    /// Replaces the world layer with lines built by the geometry worker
+    ///
+    /// Lines built for another drawing buffer size, as when the canvas was
+    /// resized while the worker ran, are stretched or reprojected.
+    pub fn set_world_lines(&mut self, lines: WorldLines) -> Result<(), JsValue> {
+        let mut world = LineLayer::with_mesh(
+            &self.ctx,
+            lines.topology,
+            lines.detail,
+            lines.mesh,
+            [lines.width, lines.height],
+            Paint::Coastlines,
+            self.world_shader.clone(),
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+        world
+            .reproject(&self.ctx, &self.projection())
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        self.set_layer(layer::WORLD, Box::new(world), None);
+        Ok(())
+    }
+
+    /// Replaces the lakes layer
+    ///
+    /// New lakes go below the rivers, or below the world layer.
+    pub fn set_lakes(&mut self, topology: WorldTopology) -> Result<(), JsValue> {
+        let lakes = self
+            .build_layer(topology, Paint::Lakes, Some(FillMode::Triangulate))
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = self
+            .layers
+            .position(layer::RIVERS)
+            .or_else(|| self.layers.position(layer::WORLD));
+        self.set_layer(layer::LAKES, lakes, index);
+        Ok(())
+    }
+
+    /// Replaces the rivers layer
+    ///
+    /// New rivers go below the world layer.
+    pub fn set_rivers(&mut self, topology: WorldTopology) -> Result<(), JsValue> {
+        let rivers = self
+            .build_layer(topology, Paint::Rivers, None)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        let index = self.layers.position(layer::WORLD);
+        self.set_layer(layer::RIVERS, rivers, index);
+        Ok(())
+    }
+
+    /// Replaces the markers layer, keeping it on top, and forgets the edit
+    /// history
+    pub fn set_markers(&mut self, markers: Vec<Marker>) -> Result<(), JsValue> {
+        self.history.clear();
+        self.build_markers(markers)
+    }
+
+    /// Replaces the markers layer, keeping it on top
+    fn build_markers(&mut self, markers: Vec<Marker>) -> Result<(), JsValue> {
+        let projection = self.projection();
+        let layer = MarkerLayer::new(
+            &self.ctx,
+            markers,
+            &projection,
+            self.theme.markers,
+            self.marker_shader.as_deref(),
+            self.hidden_groups.clone(),
+            self.marker_order,
+        )
+        .map_err(error::to_js_in("Renderer init failed"))?;
+
+        if let Some(old) = self.layers.set(layer::MARKERS, Box::new(layer), None) {
+            old.delete(self.ctx.gl());
+        }
+        self.dirty.markers = true;
+        self.markers_changed();
+        Ok(())
+    }
+
+    /// Replaces the markers, re-uploading only the instances that changed
+    /// when the marker ids are unchanged, and forgets the edit history
+    ///
+    /// Creates the markers layer if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if instanced drawing is unavailable or
+    /// renderer initialization fails
+    pub fn update_markers(&mut self, markers: Vec<Marker>) -> Result<(), JsValue> {
+        self.history.clear();
+        self.upload_markers(markers)
+    }
+
+    /// Replaces the markers, re-uploading only the instances that changed
+    /// when the marker ids are unchanged, and ripples the markers with new
+    /// ids
+    fn upload_markers(&mut self, markers: Vec<Marker>) -> Result<(), JsValue> {
+        let added = self.added_markers(&markers);
+        self.upload_changed_markers(markers)?;
+        self.ripple(&added);
+        Ok(())
+    }
+
+    /// Returns the shown markers of `markers` whose ids no current marker
+    /// has, or none while ripples are off or there are no markers yet, as
+    /// the first markers aren't news
+    fn added_markers(&self, markers: &[Marker]) -> Vec<Marker> {
+        if !self.theme.ripple.is_enabled() || self.layers.marker_count() == 0 {
+            return Vec::new();
+        }
+        let current: HashSet<&MarkerId> = self.layers.markers().map(|marker| &marker.id).collect();
+        markers
+            .iter()
+            .filter(|marker| !current.contains(&marker.id) && self.is_marker_visible(marker))
+            .cloned()
+            .collect()
+    }
+
+    fn upload_changed_markers(&mut self, markers: Vec<Marker>) -> Result<(), JsValue> {
+        let projection = self.projection();
+        let updated = self
+            .layers
+            .update_markers(layer::MARKERS, &self.ctx, &projection, &markers)
+            .transpose()
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        if updated != Some(true) {
+            return self.build_markers(markers);
+        }
+
+        self.dirty.markers = true;
+        self.markers_changed();
+        Ok(())
+    }
+
+    /// Applies an undoable edit to the markers
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn edit_markers(
+        &mut self,
+        edit: impl FnOnce(&[Marker]) -> MarkerEdit,
+    ) -> Result<(), JsValue> {
+        let mut markers: Vec<Marker> = self.layers.markers().cloned().collect();
+        let edit = edit(&markers);
+        if edit.is_empty() {
+            return Ok(());
+        }
+        self.history.apply(edit, &mut markers);
+        self.upload_markers(markers)
+    }
+
+    /// Undoes the last marker edit, returning false if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn undo(&mut self) -> Result<bool, JsValue> {
+        let mut markers: Vec<Marker> = self.layers.markers().cloned().collect();
+        if !self.history.undo(&mut markers) {
+            return Ok(false);
+        }
+        self.upload_markers(markers)?;
+        Ok(true)
+    }
+
+    /// Redoes the last undone marker edit, returning false if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the markers can't be uploaded
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        let mut markers: Vec<Marker> = self.layers.markers().cloned().collect();
+        if !self.history.redo(&mut markers) {
+            return Ok(false);
+        }
+        self.upload_markers(markers)?;
+        Ok(true)
+    }
+
+    /// Removes every marker as an undoable edit, dropping the markers layer
+    pub fn clear_markers(&mut self) {
+        let mut markers: Vec<Marker> = self.layers.markers().cloned().collect();
+        let edit = MarkerEdit::remove_where(&markers, |_| true);
+        self.history.apply(edit, &mut markers);
+        self.remove_layer(layer::MARKERS);
+    }
+
+    /// Moves the keyboard focus ring to the marker with `id`, or hides it
+    /// with `None`
+    pub fn set_focus(&mut self, id: Option<String>) {
+        self.focused = id;
+        self.refresh_focus();
+    }
+
+    /// Places the focus ring on the focused marker, dropping the focus if
+    /// the marker is gone
+    fn refresh_focus(&mut self) {
+        let marker = self
+            .focused
+            .as_deref()
+            .and_then(|id| self.layers.find_marker(id))
+            .filter(|marker| self.is_marker_visible(marker))
+            .cloned();
+        if marker.is_none() {
+            self.focused = None;
+        }
+
+        let projection = self.projection();
+        let focused =
+            self.layers
+                .focus_marker(layer::FOCUS, &self.ctx, &projection, marker.as_ref());
+        self.dirty.scene |= focused == Some(true);
+    }
+
+    /// Rebuilds the accessible marker list and focus ring after the markers
+    /// changed
+    pub fn markers_changed(&mut self) {
+        if let Some(list) = &self.marker_list
+            && let Err(e) = list.sync(
+                self.layers
+                    .markers()
+                    .filter(|marker| self.is_marker_visible(marker)),
+            )
+        {
+            log::warn("a11y", "Marker list not updated", &[("error", e)]);
+        }
+        self.refresh_focus();
+        self.refresh_selection();
+    }
+
+    /// Highlights the selected marker while it's shown, creating the
+    /// selection layer over the markers on first use
+    ///
+    /// Unlike the focus, the selection outlives its marker, so a selection
+    /// restored from the URL hash shows once the marker arrives.
+    pub fn refresh_selection(&mut self) {
+        let marker = self
+            .selected
+            .as_deref()
+            .and_then(|id| self.layers.find_marker(id))
+            .filter(|marker| self.is_marker_visible(marker))
+            .cloned();
+        if marker.is_some() && self.layers.position(layer::SELECTION).is_none() {
+            match SelectionLayer::new(&self.ctx, &self.theme) {
+                Ok(selection) => {
+                    let index = self.layers.position(layer::MARKERS).map(|index| index + 1);
+                    self.set_layer(layer::SELECTION, Box::new(selection), index);
+                },
+                Err(e) => log::warn(
+                    "layer",
+                    "Selection highlight unavailable",
+                    &[("error", e.to_string().into())],
+                ),
+            }
+        }
+
+        let projection = self.projection();
+        let selected =
+            self.layers
+                .select_marker(layer::SELECTION, &self.ctx, &projection, marker.as_ref());
+        self.dirty.scene |= selected == Some(true);
+    }
+
+    /// Starts a ripple from each of `markers`, creating the ripple layer
+    /// over the markers on first use
+    fn ripple(&mut self, markers: &[Marker]) {
+        if markers.is_empty() || !self.theme.ripple.is_enabled() {
+            return;
+        }
+        if self.layers.position(layer::RIPPLES).is_none() {
+            match RippleLayer::new(&self.ctx, &self.theme) {
+                Ok(ripples) => {
+                    let index = self.layers.position(layer::MARKERS).map(|index| index + 1);
+                    self.set_layer(layer::RIPPLES, Box::new(ripples), index);
+                },
+                Err(e) => {
+                    log::warn(
+                        "layer",
+                        "Marker ripples unavailable",
+                        &[("error", e.to_string().into())],
+                    );
+                    return;
+                },
+            }
+        }
+
+        let projection = self.projection();
+        #[allow(clippy::cast_possible_truncation)]
+        let rippled = self.layers.add_ripples(
+            layer::RIPPLES,
+            &self.ctx,
+            &projection,
+            markers,
+            self.time as f32,
+        );
+        self.dirty.scene |= rippled == Some(true);
+    }
+
+    /// Returns false if the marker is in a hidden group
+    pub fn is_marker_visible(&self, marker: &Marker) -> bool {
+        marker
+            .group
+            .as_ref()
+            .is_none_or(|group| !self.hidden_groups.contains(group))
+    }
+
+    /// Shows or hides the markers of `group`
+    ///
+    /// Returns false if the group's visibility is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the instances can't be uploaded
+    pub fn set_group_visible(&mut self, group: &str, visible: bool) -> Result<bool, JsValue> {
+        let changed = if visible {
+            self.hidden_groups.remove(group)
+        } else {
+            self.hidden_groups.insert(group.to_string())
+        };
+        if !changed {
+            return Ok(false);
+        }
+
+        self.layers
+            .set_hidden_groups(&self.ctx, &self.hidden_groups)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        self.dirty.markers = true;
+        self.markers_changed();
+        Ok(true)
+    }
+
+    /// Sets the order and limit markers are drawn in, kept for markers set
+    /// later
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the instances can't be uploaded
+    pub fn set_marker_order(&mut self, order: MarkerOrder) -> Result<(), JsValue> {
+        self.marker_order = order;
+        self.layers
+            .set_marker_order(&self.ctx, order)
+            .map_err(error::to_js_in("Renderer init failed"))?;
+        self.dirty.markers = true;
+        Ok(())
+    }
+
+    /// Removes the markers of `group` as an undoable edit, returning how
+    /// many were removed
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the instances can't be uploaded
+    pub fn clear_group(&mut self, group: &str) -> Result<usize, JsValue> {
+        let mut removed = 0;
+        self.edit_markers(|markers| {
+            let edit =
+                MarkerEdit::remove_where(markers, |marker| marker.group.as_deref() == Some(group));
+            if let MarkerEdit::Remove(markers) = &edit {
+                removed = markers.len();
+            }
+            edit
+        })?;
+        Ok(removed)
+    }
+
+    /// Selects the marker with `id`, or clears the selection with `None`,
+    /// pushing the change to the URL hash when synchronized
+    ///
+    /// Returns false if the selection is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the hash can't be written
+    pub fn select(&mut self, id: Option<String>) -> Result<bool, JsValue> {
+        if self.selected == id {
+            return Ok(false);
+        }
+        if let Some(sync) = &self.hash_sync {
+            sync.write(id.as_deref())?;
+        }
+        self.selected = id;
+        self.refresh_selection();
+        let selected = self
+            .selected
+            .as_deref()
+            .and_then(|id| self.layers.find_marker(id))
+            .filter(|marker| self.is_marker_visible(marker))
+            .cloned();
+        self.ripple(selected.as_slice());
+        Ok(true)
+    }
+
+    /// Replaces the fragment shader of a layer, or restores the built-in one
+    /// with `None`
+    ///
+    /// Shaders of the world and markers layers are kept for the layers that
+    /// replace them, and a markers shader may be set before any markers.
+    /// Returns `None` if no layer has this id, and `Some(false)` if the layer
+    /// has no replaceable shader.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the shader fails to compile or link
+    pub fn set_fragment_shader(
+        &mut self,
+        id: &str,
+        source: Option<String>,
+    ) -> Result<Option<bool>, RenderError> {
+        let projection = self.projection();
+        let replaced =
+            match self
+                .layers
+                .set_fragment_shader(id, &self.ctx, &projection, source.as_deref())
+            {
+                Some(result) => result?,
+                None if id == layer::MARKERS => {
+                    // Compile now so errors surface here rather than on
+                    // `set_markers`
+                    MarkerRenderer::with_fragment(&self.ctx, source.as_deref())?
+                        .delete(self.ctx.gl());
+                    true
+                },
+                None => return Ok(None),
+            };
+
+        if replaced {
+            match id {
+                layer::WORLD => self.world_shader = source,
+                layer::MARKERS => self.marker_shader = source,
+                _ => {},
+            }
+            self.dirty.scene = true;
+        }
+        Ok(Some(replaced))
+    }
+
+    /// Shows or hides a layer
+    ///
+    /// Returns false if no layer has this id.
+    pub fn set_layer_visible(&mut self, id: &str, visible: bool) -> bool {
+        match self.layers.set_visible(id, visible) {
+            Some(changed) => {
+                self.dirty.scene |= changed;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Sets the opacity of a layer
+    ///
+    /// Returns `None` for an unknown id and false for a layer without an
+    /// opacity control.
+    pub fn set_layer_opacity(&mut self, id: &str, opacity: f32) -> Option<bool> {
+        let changed = self.layers.set_opacity(id, opacity)?;
+        self.dirty.scene |= changed;
+        Some(changed)
+    }
+
+    /// Sets the blend mode of a layer
+    ///
+    /// Returns false if no layer has this id.
+    pub fn set_layer_blend(&mut self, id: &str, blend: BlendMode) -> bool {
+        match self.layers.set_blend(id, blend) {
+            Some(changed) => {
+                self.dirty.scene |= changed;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Moves a layer to `index` in the stack
+    ///
+    /// Returns false if no layer has this id.
+    pub fn move_layer(&mut self, id: &str, index: usize) -> bool {
+        let moved = self.layers.move_to(id, index);
+        self.dirty.scene |= moved;
+        moved
+    }
+
+    /// Removes a layer
+    ///
+    /// Returns false if no layer has this id.
+    pub fn remove_layer(&mut self, id: &str) -> bool {
+        let Some(removed) = self.layers.remove(id) else {
+            return false;
+        };
+        removed.delete(self.ctx.gl());
+        self.dirty.scene = true;
+        if id == layer::MARKERS {
+            self.markers_changed();
+        }
+        true
+    }
+
+    /// Turns per-pass GPU timing on or off
+    ///
+    /// Returns false if timing was requested but the context has no timer
+    /// query support.
+    pub fn set_gpu_timing(&mut self, enabled: bool) -> bool {
+        if !enabled {
+            if let Some(timer) = self.gpu_timer.take() {
+                timer.delete(self.ctx.gl());
+            }
+            return true;
+        }
+
+        if self.gpu_timer.is_none() {
+            self.gpu_timer = GpuTimer::new(self.ctx.gl());
+        }
+        self.gpu_timer.is_some()
+    }
+
+    /// Frees every GPU resource of the map
+    ///
+    /// The state must not be drawn afterwards.
+    pub fn destroy(&mut self) {
+        for layer in self.layers.clear() {
+            layer.delete(self.ctx.gl());
+        }
+        self.layers.delete_batches(self.ctx.gl());
+        self.post.delete(self.ctx.gl());
+        self.css_theme = None;
+        self.pointer = None;
+        self.marker_poll = None;
+        self.hash_sync = None;
+        self.marker_list = None;
+        self.geometry_worker = None;
+        self.events.clear();
+        if let Some(timer) = self.gpu_timer.take() {
+            timer.delete(self.ctx.gl());
+        }
+        self.ctx.delete();
+    }
+
+    /// Forgets the previous frame timestamp so a paused loop resumes without a
+    /// time jump
+    pub const fn reset_clock(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// Returns true while a time-based animation needs continuous frames
+    ///
+    /// Visible animated layers and animated post effects need continuous
+    /// frames.
+    pub fn is_animating(&self) -> bool {
+        self.layers.is_animated() || self.post.is_animated()
+    }
+
+    /// Advances the animation clock to `timestamp` (ms) and draws a frame if
+    /// anything changed or an animation is running
+    ///
+    /// Returns the frame callback with the current time in seconds and the
+    /// frame delta in milliseconds, to be invoked once the state is released.
+    pub fn frame(&mut self, timestamp: f64) -> Option<(Function, f64, f64)> {
+        let delta = self
+            .last_frame
+            .map_or(0.0, |last| (timestamp - last).max(0.0));
+        self.last_frame = Some(timestamp);
+        self.time = self
+            .fixed_time
+            .unwrap_or_else(|| self.time + delta / 1000.0);
+
+        if self.dirty.any() {
+            self.render();
+        } else if self.is_animating() {
+            let region = self.animated_region();
+            self.draw(region);
+        }
+
+        self.frame_callback
+            .clone()
+            .map(|callback| (callback, self.time, delta))
+    }
+
+    /// Returns the region animation alone changes, if it is limited to one
+    ///
+    /// Animated post effects change the whole frame.
+    fn animated_region(&self) -> Option<ScissorRect> {
+        if self.post.is_animated() {
+            return None;
+        }
+
+        self.layers
+            .damage(&self.layer_frame())
+            .filter(|rect| !rect.covers(self.ctx.width(), self.ctx.height()))
+    }
+
+    const fn layer_frame(&self) -> LayerFrame<'_> {
+        LayerFrame {
+            ctx: &self.ctx,
+            theme: &self.theme,
+            post: &self.post,
+            deterministic: self.fixed_time.is_some(),
+        }
+    }
+
+    /// Draws a frame unconditionally and clears the dirty flags
+    pub fn render(&mut self) {
+        self.draw(None);
+    }
+
+    /// Draws a frame, clearing and drawing the scene only inside `region`
+    ///
+    /// The rest of the scene is kept from the previous frame, which requires
+    /// a destination that keeps its contents: the post-processing scene
+    /// target or a canvas with `preserveDrawingBuffer`. Otherwise the whole
+    /// scene is drawn. Post effects always run over the full frame.
+    ///
+    /// Buffer uploads since the previous frame count towards this frame's
+    /// statistics.
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, region: Option<ScissorRect>) {
+        let start = global::now();
+        let allocations = stats::allocation_count();
+        self.dirty.clear();
+
+        if let Err(e) = self.post.begin(&self.ctx) {
+            log::warn(
+                "frame",
+                "Post-processing disabled for this frame",
+                &[("error", e.to_string().into())],
+            );
+        }
+
+        self.ctx.update_camera(self.time as f32);
+        let timer = self.gpu_timer.as_ref();
+        if let Some(timer) = timer {
+            timer.begin_frame(self.ctx.gl());
+        }
+
+        let persistent = self.post.is_capturing() || self.ctx.preserves_drawing_buffer();
+        self.ctx.set_scissor(region.filter(|_| persistent));
+
+        self.ctx.clear(
+            self.theme.background.r,
+            self.theme.background.g,
+            self.theme.background.b,
+            self.theme.background.a,
+        );
+
+        self.layers.draw(
+            &LayerFrame {
+                ctx: &self.ctx,
+                theme: &self.theme,
+                post: &self.post,
+                deterministic: self.fixed_time.is_some(),
+            },
+            timer,
+        );
+
+        self.ctx.set_scissor(None);
+        if let Some(timer) = timer {
+            timer.begin(self.ctx.gl(), "post");
+        }
+        self.post.finish(&self.ctx, self.time as f32);
+        if let Some(timer) = timer {
+            timer.end_frame(self.ctx.gl());
+        }
+
+        self.stats = RenderStats {
+            frame_ms: global::now() - start,
+            allocations: stats::allocation_count()
+                .zip(allocations)
+                .map(|(after, before)| after - before),
+            ..self.ctx.gl().take_stats()
+        };
+        if self.stats.frame_ms > SLOW_FRAME_MS {
+            log::info(
+                "frame",
+                "Slow frame",
+                &[
+                    ("frameMs", self.stats.frame_ms.into()),
+                    ("drawCalls", self.stats.draw_calls.into()),
+                ],
+            );
+        }
+    }
+}
+
+/// Builds the post effects `theme` configures, in draw order
+///
+/// Grain goes last so its noise isn't graded or vignetted.
+fn theme_effects(gl: &Gl, theme: &Theme) -> Result<Vec<Box<dyn PostEffect>>, RenderError> {
+    Ok(vec![
+        Box::new(ColorGradeEffect::new(gl, theme.grading)?),
+        Box::new(VignetteEffect::new(gl, theme.vignette)?),
+        Box::new(GrainEffect::new(gl, theme.grain_intensity)?),
+    ])
+}
@@ -0,0 +1,200 @@
+use wasm_bindgen::prelude::*;
+use wmc_core::{
+    marker::Color,
+    theme::{
+        ColorGrading, LayerStyle, MarkerStyle, RippleStyle, SelectionStyle, Theme, ThemeBuilder,
+    },
+};
+
+use crate::error::{self, ComponentError};
+
+/// Chainable theme construction from JavaScript
+///
+/// Exported as `ThemeBuilder`. Every setter returns the builder, and
+/// [`JsThemeBuilder::build`] validates the theme and returns the JSON
+/// [`crate::WorldMap::set_theme`] takes, e.g.
+/// `map.set_theme(new ThemeBuilder("ocean", "light-minimal").background("#e8f1f8").vignette(0.1).build())`.
+#[wasm_bindgen(js_name = ThemeBuilder)]
+pub struct JsThemeBuilder {
+    inner: ThemeBuilder,
+}
+
+#[wasm_bindgen(js_class = ThemeBuilder)]
+impl JsThemeBuilder {
+    /// Starts a theme named `name` from the preset `preset`, one of
+    /// [`Theme::PRESETS`], or `"dark-minimal"` when omitted
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if no preset has the name
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(name: String, preset: Option<String>) -> Result<Self, JsValue> {
+        let base = match preset.as_deref() {
+            Some(preset) => Theme::from_name(preset).ok_or_else(|| {
+                error::to_js(ComponentError::UnknownName {
+                    kind: "theme preset".to_string(),
+                    name: preset.to_string(),
+                })
+            })?,
+            None => Theme::dark_minimal(),
+        };
+
+        Ok(Self {
+            inner: ThemeBuilder::from(base).name(name),
+        })
+    }
+
+    /// Sets the background to a CSS color, e.g. `"#0b0f10"`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the color isn't supported
+    pub fn background(self, color: &str) -> Result<Self, JsValue> {
+        let color = Color::from_css(color).map_err(error::to_js_in("Invalid color"))?;
+
+        Ok(Self {
+            inner: self.inner.background(color),
+        })
+    }
+
+    /// Sets a style block from JSON, e.g. `style("coastlines", '{"color":
+    /// {...}, "width": 1.5, "opacity": 0.8}')`
+    ///
+    /// `block` is one of `coastlines`, `borders`, `rivers`, `lakes`, `fills`
+    /// and `graticule`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the block is unknown or the JSON is invalid
+    pub fn style(self, block: &str, style_json: &str) -> Result<Self, JsValue> {
+        let style: LayerStyle = serde_json::from_str(style_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Style".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        let inner = match block {
+            "coastlines" => self.inner.coastlines(style),
+            "borders" => self.inner.borders(style),
+            "rivers" => self.inner.rivers(style),
+            "lakes" => self.inner.lakes(style),
+            "fills" => self.inner.fills(style),
+            "graticule" => self.inner.graticule(style),
+            _ => {
+                return Err(error::to_js(ComponentError::UnknownName {
+                    kind: "style block".to_string(),
+                    name: block.to_string(),
+                }));
+            },
+        };
+        Ok(Self { inner })
+    }
+
+    /// Sets the marker style from JSON, e.g. `'{"color": {...}, "glow": 8}'`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid
+    pub fn markers(self, style_json: &str) -> Result<Self, JsValue> {
+        let style: MarkerStyle = serde_json::from_str(style_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Marker style".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        Ok(Self {
+            inner: self.inner.markers(style),
+        })
+    }
+
+    /// Sets the selected marker style from JSON, e.g. `'{"color": "#ffcc00",
+    /// "scale": 2}'`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid
+    pub fn selection(self, style_json: &str) -> Result<Self, JsValue> {
+        let style: SelectionStyle = serde_json::from_str(style_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Selection style".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        Ok(Self {
+            inner: self.inner.selection(style),
+        })
+    }
+
+    /// Sets the marker ripple style from JSON, e.g. `'{"duration": 2,
+    /// "scale": 4}'`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid
+    pub fn ripple(self, style_json: &str) -> Result<Self, JsValue> {
+        let style: RippleStyle = serde_json::from_str(style_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Ripple style".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        Ok(Self {
+            inner: self.inner.ripple(style),
+        })
+    }
+
+    /// Sets the film grain amplitude; zero disables grain
+    #[wasm_bindgen(js_name = grainIntensity)]
+    #[must_use]
+    pub fn grain_intensity(self, intensity: f32) -> Self {
+        Self {
+            inner: self.inner.grain_intensity(intensity),
+        }
+    }
+
+    /// Sets the vignette strength in `0.0..=1.0`; zero disables it
+    #[must_use]
+    pub fn vignette(self, vignette: f32) -> Self {
+        Self {
+            inner: self.inner.vignette(vignette),
+        }
+    }
+
+    /// Sets the color grading from JSON, e.g. `'{"exposure": 0.2,
+    /// "saturation": 0.8}'`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if the JSON is invalid
+    pub fn grading(self, grading_json: &str) -> Result<Self, JsValue> {
+        let grading: ColorGrading = serde_json::from_str(grading_json).map_err(|e| {
+            error::to_js(ComponentError::ParseFailed {
+                input: "Grading".to_string(),
+                reason: e.to_string(),
+            })
+        })?;
+
+        Ok(Self {
+            inner: self.inner.grading(grading),
+        })
+    }
+
+    /// Validates the theme and returns it as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error describing the first invalid value
+    pub fn build(self) -> Result<String, JsValue> {
+        let theme = self
+            .inner
+            .build()
+            .map_err(error::to_js_in("Invalid theme"))?;
+
+        serde_json::to_string(&theme).map_err(error::to_js_in("Theme serialization failed"))
+    }
+}
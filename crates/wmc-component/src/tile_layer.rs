@@ -0,0 +1,330 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use web_sys::ImageBitmap;
+use wmc_core::{projection::Projection, tile::TileId};
+use wmc_render::{Gl, GpuTexture, RenderContext, RenderError, TileRenderer, log};
+
+use crate::{
+    events::EventEmitter,
+    global,
+    layer::{Layer, LayerFrame},
+};
+
+/// Time a newly loaded tile takes to fade in, in milliseconds
+const FADE_MS: f64 = 250.0;
+
+/// Number of tile textures kept around, current ones included
+const CACHE_SIZE: usize = 256;
+
+/// Settings of a [`TileLayer`]
+#[derive(Debug, Clone)]
+pub struct TileSource {
+    /// URL template with `{z}`, `{x}`, `{y}` and optional `{s}` placeholders
+    pub template: String,
+    /// Values substituted for `{s}`
+    pub subdomains: Vec<String>,
+    /// Highest zoom level the server provides
+    pub max_zoom: u8,
+    /// Tile size in pixels
+    pub tile_size: u32,
+    /// Layer opacity in `0.0..=1.0`
+    pub opacity: f32,
+}
+
+struct Tile {
+    texture: GpuTexture,
+    /// `performance.now()` when the tile arrived
+    loaded_at: f64,
+    /// Frame the tile was last drawn in
+    last_used: u64,
+}
+
+/// Tiles being fetched, shared with the fetch tasks
+#[derive(Default)]
+struct Inbox {
+    requested: HashSet<TileId>,
+    arrived: Vec<(TileId, ImageBitmap)>,
+    /// Tiles loaded or failed since nothing was last in flight
+    settled: usize,
+}
+
+/// Raster basemap of XYZ tiles covering the world
+///
+/// The tile zoom level follows the canvas size and the zoom of the view, and
+/// only tiles the view shows are requested, again whenever the view moves.
+/// Tiles are fetched asynchronously, uploaded on the next frame and faded
+/// in; until then the closest cached ancestor tile is drawn in their place.
+/// Each settled fetch emits `tiles` progress over the batch in flight.
+pub struct TileLayer {
+    source: TileSource,
+    zoom: u8,
+    tiles: HashMap<TileId, Tile>,
+    inbox: Rc<RefCell<Inbox>>,
+    events: Weak<EventEmitter>,
+    renderer: TileRenderer,
+    frame: u64,
+    /// Ancestors drawn in place of missing tiles this frame, kept to reuse
+    /// the allocation
+    fallbacks: HashSet<TileId>,
+    /// Loaded tiles of the current level this frame, kept likewise
+    current: Vec<TileId>,
+    /// Tiles of the current level the view shows
+    visible: Vec<TileId>,
+}
+
+impl TileLayer {
+    /// Creates the layer and requests the tiles the view shows
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if renderer initialization fails
+    pub fn new(
+        ctx: &RenderContext,
+        source: TileSource,
+        events: Weak<EventEmitter>,
+    ) -> Result<Self, RenderError> {
+        let mut layer = Self {
+            source,
+            zoom: 0,
+            tiles: HashMap::new(),
+            inbox: Rc::default(),
+            events,
+            renderer: TileRenderer::new(ctx)?,
+            frame: 0,
+            fallbacks: HashSet::new(),
+            current: Vec::new(),
+            visible: Vec::new(),
+        };
+        layer.request_visible(ctx);
+        Ok(layer)
+    }
+
+    /// Picks the zoom level for the view and requests the tiles it shows
+    /// that are neither loaded nor in flight
+    fn request_visible(&mut self, ctx: &RenderContext) {
+        // The world spans the canvas at view zoom 0 and doubles per step
+        let scale = f64::from(ctx.view().zoom).exp2();
+        self.zoom = TileId::zoom_for(
+            f64::from(ctx.css_width()) * scale,
+            f64::from(ctx.css_height()) * scale,
+            f64::from(self.source.tile_size),
+            self.source.max_zoom,
+        );
+
+        let (width, height) = (f64::from(ctx.width()), f64::from(ctx.height()));
+        self.visible.clear();
+        self.visible.extend(TileId::covering(
+            self.zoom,
+            ctx.visible_bounds().map(f64::from),
+            width,
+            height,
+        ));
+
+        for &tile in &self.visible {
+            if !self.tiles.contains_key(&tile) && self.inbox.borrow_mut().requested.insert(tile) {
+                spawn_fetch(
+                    tile,
+                    tile.url(&self.source.template, &self.source.subdomains),
+                    Rc::downgrade(&self.inbox),
+                    Weak::clone(&self.events),
+                );
+            }
+        }
+    }
+
+    /// Uploads tiles that arrived since the last frame
+    fn receive(&mut self, ctx: &RenderContext) {
+        let arrived = std::mem::take(&mut self.inbox.borrow_mut().arrived);
+
+        for (id, bitmap) in arrived {
+            self.inbox.borrow_mut().requested.remove(&id);
+
+            let texture = GpuTexture::new(ctx.gl()).and_then(|mut texture| {
+                texture.upload_image_bitmap(ctx.gl(), &bitmap)?;
+                Ok(texture)
+            });
+            bitmap.close();
+
+            match texture {
+                Ok(texture) => {
+                    self.tiles.insert(
+                        id,
+                        Tile {
+                            texture,
+                            loaded_at: global::now(),
+                            last_used: self.frame,
+                        },
+                    );
+                },
+                Err(e) => log::error(
+                    "tile",
+                    "Tile upload failed",
+                    &[
+                        ("tile", format!("{}/{}/{}", id.z, id.x, id.y).into()),
+                        ("error", e.to_string().into()),
+                    ],
+                ),
+            }
+        }
+    }
+
+    /// Drops the least recently drawn tiles beyond [`CACHE_SIZE`]
+    fn evict(&mut self, ctx: &RenderContext) {
+        if self.tiles.len() <= CACHE_SIZE {
+            return;
+        }
+
+        let mut ages: Vec<(u64, TileId)> = self
+            .tiles
+            .iter()
+            .map(|(id, tile)| (tile.last_used, *id))
+            .collect();
+        ages.sort_unstable_by_key(|(last_used, _)| *last_used);
+
+        for (_, id) in ages.into_iter().take(self.tiles.len() - CACHE_SIZE) {
+            if let Some(tile) = self.tiles.remove(&id) {
+                tile.texture.delete(ctx.gl());
+            }
+        }
+    }
+}
+
+impl Layer for TileLayer {
+    fn reproject(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.request_visible(ctx);
+        Ok(())
+    }
+
+    fn set_view(
+        &mut self,
+        ctx: &RenderContext,
+        _projection: &dyn Projection,
+    ) -> Result<(), RenderError> {
+        self.request_visible(ctx);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw(&mut self, frame: &LayerFrame<'_>) {
+        let ctx = frame.ctx;
+        let (width, height) = (f64::from(ctx.width()), f64::from(ctx.height()));
+        let now = global::now();
+
+        self.receive(ctx);
+        self.frame += 1;
+
+        // Ancestors stand in for missing tiles and are drawn first
+        self.fallbacks.clear();
+        self.current.clear();
+        for &id in &self.visible {
+            if self.tiles.contains_key(&id) {
+                self.current.push(id);
+            } else if let Some(ancestor) = std::iter::successors(id.parent(), |id| id.parent())
+                .find(|ancestor| self.tiles.contains_key(ancestor))
+            {
+                self.fallbacks.insert(ancestor);
+            }
+        }
+
+        for id in self.fallbacks.iter().chain(&self.current) {
+            if let Some(tile) = self.tiles.get_mut(id) {
+                tile.last_used = self.frame;
+            }
+        }
+
+        let opacity = self.source.opacity;
+        let draws = self
+            .fallbacks
+            .iter()
+            .map(|id| (*id, 1.0))
+            .chain(self.current.iter().map(|id| {
+                if frame.deterministic {
+                    return (*id, 1.0);
+                }
+                let fade = self.tiles[id].loaded_at;
+                (*id, ((now - fade) / FADE_MS).clamp(0.0, 1.0) as f32)
+            }))
+            .map(|(id, fade)| {
+                let [left, top, right, bottom] = id.bounds(width, height);
+                (
+                    &self.tiles[&id].texture,
+                    [left as f32, top as f32, right as f32, bottom as f32],
+                    opacity * fade,
+                )
+            });
+        self.renderer.draw(ctx, draws);
+
+        self.evict(ctx);
+    }
+
+    /// Also closes tiles that arrived but were never uploaded
+    fn delete(&self, gl: &Gl) {
+        self.renderer.delete(gl);
+        for tile in self.tiles.values() {
+            tile.texture.delete(gl);
+        }
+        for (_, bitmap) in self.inbox.borrow_mut().arrived.drain(..) {
+            bitmap.close();
+        }
+    }
+
+    fn set_opacity(&mut self, opacity: f32) -> bool {
+        self.source.opacity = opacity;
+        true
+    }
+
+    fn is_animated(&self) -> bool {
+        let now = global::now();
+
+        !self.inbox.borrow().requested.is_empty()
+            || self
+                .tiles
+                .values()
+                .any(|tile| now - tile.loaded_at < FADE_MS)
+    }
+}
+
+fn spawn_fetch(id: TileId, url: String, inbox: Weak<RefCell<Inbox>>, events: Weak<EventEmitter>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = global::fetch_image(&url).await;
+
+        // The layer was removed while the tile was in flight
+        let Some(inbox) = inbox.upgrade() else {
+            return;
+        };
+        let mut inbox = inbox.borrow_mut();
+
+        match result {
+            Ok(bitmap) => inbox.arrived.push((id, bitmap)),
+            Err(e) => {
+                inbox.requested.remove(&id);
+                log::warn(
+                    "fetch",
+                    "Tile failed to load",
+                    &[("url", url.as_str().into()), ("error", e)],
+                );
+            },
+        }
+
+        inbox.settled += 1;
+        let settled = inbox.settled;
+        let in_flight = inbox.requested.len() - inbox.arrived.len();
+        if in_flight == 0 {
+            inbox.settled = 0;
+        }
+        drop(inbox);
+
+        if let Some(events) = events.upgrade() {
+            #[allow(clippy::cast_precision_loss)]
+            events.emit_progress("tiles", settled as f64, Some((settled + in_flight) as f64));
+        }
+    });
+}
@@ -0,0 +1,77 @@
+use js_sys::Array;
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::{Document, Element, IntersectionObserver, IntersectionObserverEntry};
+
+use crate::render_loop::PauseHandle;
+
+/// Suspends the render loop while the canvas is scrolled out of view or the
+/// tab is hidden
+///
+/// An `IntersectionObserver` tracks the canvas against the viewport and the
+/// Page Visibility API tracks the tab, so dashboards with many maps only
+/// spend frames on the ones the user can see. Observers are disconnected on
+/// drop.
+pub struct VisibilityWatch {
+    observer: IntersectionObserver,
+    _on_intersect: Closure<dyn FnMut(Array)>,
+    document: Option<Document>,
+    on_visibility: Closure<dyn FnMut()>,
+}
+
+impl VisibilityWatch {
+    /// Starts watching `element`, pausing the loop behind `pause`
+    ///
+    /// # Errors
+    ///
+    /// Returns `JsValue` error if an observer can't be registered
+    pub fn watch(element: &Element, pause: &PauseHandle) -> Result<Self, JsValue> {
+        let handle = pause.clone();
+        let on_intersect = Closure::<dyn FnMut(Array)>::new(move |entries: Array| {
+            // Entries come in time order, so the last one is current
+            if let Some(entry) = entries
+                .iter()
+                .last()
+                .and_then(|entry| entry.dyn_into::<IntersectionObserverEntry>().ok())
+            {
+                handle.set_offscreen(!entry.is_intersecting());
+            }
+        });
+        let observer = IntersectionObserver::new(on_intersect.as_ref().unchecked_ref())?;
+        observer.observe(element);
+
+        let document = element.owner_document();
+        let handle = pause.clone();
+        let watched = document.clone();
+        let on_visibility = Closure::<dyn FnMut()>::new(move || {
+            if let Some(document) = &watched {
+                handle.set_page_hidden(document.hidden());
+            }
+        });
+        if let Some(document) = &document {
+            pause.set_page_hidden(document.hidden());
+            document.add_event_listener_with_callback(
+                "visibilitychange",
+                on_visibility.as_ref().unchecked_ref(),
+            )?;
+        }
+
+        Ok(Self {
+            observer,
+            _on_intersect: on_intersect,
+            document,
+            on_visibility,
+        })
+    }
+}
+
+impl Drop for VisibilityWatch {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        if let Some(document) = &self.document {
+            let _ = document.remove_event_listener_with_callback(
+                "visibilitychange",
+                self.on_visibility.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Message protocol for running the map in a Web Worker
+//!
+//! With [`crate::WorldMap::from_offscreen`] the whole map (parsing,
+//! projection, rendering) lives in a dedicated worker that owns an
+//! `OffscreenCanvas`. The main thread keeps the `<canvas>` element and
+//! forwards what only it can observe as JSON messages, which the worker passes
+//! to [`crate::WorldMap::handle_message`]:
+//!
+//! | `type`             | Fields                                   | Effect                        |
+//! |--------------------|------------------------------------------|-------------------------------|
+//! | `resize`           | `width`, `height` (CSS px), `pixelRatio` | Resizes the drawing buffer    |
+//! | `start`            |                                          | Starts the render loop        |
+//! | `stop`             |                                          | Stops the render loop         |
+//! | `render`           |                                          | Draws a single frame          |
+//! | `setLakesVisible`  | `visible`                                | Toggles the lakes layer       |
+//! | `setRiversVisible` | `visible`                                | Toggles the rivers layer      |
+//! | `setLayerVisible`  | `id`, `visible`                          | Toggles a layer by id         |
+//!
+//! For example, a `ResizeObserver` on the main thread would post
+//! `{"type":"resize","width":800,"height":450,"pixelRatio":2}`.
+
+use serde::Deserialize;
+
+use crate::error::ComponentError;
+
+/// Message forwarded from the main thread to a worker-hosted map
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WorkerMessage {
+    /// Canvas element was resized or the pixel ratio changed
+    #[serde(rename_all = "camelCase")]
+    Resize {
+        /// Width in CSS pixels
+        width: u32,
+        /// Height in CSS pixels
+        height: u32,
+        /// Main thread `devicePixelRatio`; unchanged when omitted
+        #[serde(default)]
+        pixel_ratio: Option<f64>,
+    },
+    /// Start the render loop
+    Start,
+    /// Stop the render loop
+    Stop,
+    /// Draw a single frame
+    Render,
+    /// Show or hide the lakes layer
+    SetLakesVisible {
+        /// New visibility
+        visible: bool,
+    },
+    /// Show or hide the rivers layer
+    SetRiversVisible {
+        /// New visibility
+        visible: bool,
+    },
+    /// Show or hide a layer by id
+    SetLayerVisible {
+        /// Layer id, see [`crate::WorldMap::layers`]
+        id: String,
+        /// New visibility
+        visible: bool,
+    },
+}
+
+impl WorkerMessage {
+    /// Parses a message from its JSON form
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ComponentError::AttributeParseError`] if the JSON is not a
+    /// known message
+    pub fn parse(json: &str) -> Result<Self, ComponentError> {
+        serde_json::from_str(json).map_err(|e| ComponentError::AttributeParseError {
+            attribute: "message".to_string(),
+            value: e.to_string(),
+        })
+    }
+}
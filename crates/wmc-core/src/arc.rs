@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::CoreError,
+    geodesy::{central_angle, great_circle, split_antimeridian},
+    marker::Color,
+    projection::{GeoCoord, ProjectedCoord, Projection},
+};
+
+/// Degrees of great circle per straight segment of a projected arc
+const DEGREES_PER_SEGMENT: f64 = 2.0;
+
+/// Great-circle connection between two places, e.g. a flight route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arc {
+    /// Where the arc starts; dashes flow away from it
+    pub from: GeoCoord,
+    /// Where the arc ends
+    pub to: GeoCoord,
+    /// Optional custom color
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+impl Arc {
+    /// Validates both end points
+    ///
+    /// ```
+    /// use wmc_core::arc::Arc;
+    ///
+    /// let arc: Arc = serde_json::from_str(
+    ///     r#"{"from": {"lat": 51.47, "lon": -0.45}, "to": {"lat": 95.0, "lon": 0.0}}"#,
+    /// )
+    /// .unwrap();
+    /// assert!(arc.validate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidCoordinates`] if an end point is out of
+    /// range
+    pub fn validate(&self) -> Result<(), CoreError> {
+        GeoCoord::new(self.from.lat, self.from.lon)?;
+        GeoCoord::new(self.to.lat, self.to.lon)?;
+        Ok(())
+    }
+
+    /// Projects the arc as polylines, split where it crosses the antimeridian
+    ///
+    /// Points are lifted towards the top of the screen by `elevation`
+    /// times the projected arc length times `sin(πt)`, `t` running from 0
+    /// at `from` to 1 at `to`, so every arc bulges in the same proportion.
+    /// Points projecting to infinity are kept as they are.
+    ///
+    /// ```
+    /// use wmc_core::{arc::Arc, projection::{GeoCoord, MercatorProjection}};
+    ///
+    /// let arc = Arc {
+    ///     from: GeoCoord::new(0.0, -90.0).unwrap(),
+    ///     to: GeoCoord::new(0.0, 90.0).unwrap(),
+    ///     color: None,
+    /// };
+    /// let projection = MercatorProjection::new(360.0, 360.0);
+    ///
+    /// let flat = arc.project(&projection, 0.0);
+    /// let lifted = arc.project(&projection, 0.25);
+    /// let mid = flat[0].len() / 2;
+    /// assert!((flat[0][mid].y - lifted[0][mid].y - 45.0).abs() < 1e-6);
+    /// ```
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::suboptimal_flops
+    )]
+    pub fn project(
+        &self,
+        projection: &dyn Projection,
+        elevation: f64,
+    ) -> Vec<Vec<ProjectedCoord>> {
+        let angle = central_angle(self.from, self.to);
+        let segments = (angle.to_degrees() / DEGREES_PER_SEGMENT).ceil() as usize;
+        let pieces = split_antimeridian(&great_circle(self.from, self.to, segments));
+
+        let mut projected: Vec<Vec<ProjectedCoord>> = pieces
+            .iter()
+            .map(|piece| {
+                piece
+                    .iter()
+                    .map(|&coord| projection.project(coord))
+                    .collect()
+            })
+            .collect();
+        if elevation == 0.0 || angle <= 0.0 {
+            return projected;
+        }
+
+        let length: f64 = projected
+            .iter()
+            .flat_map(|piece| piece.windows(2))
+            .map(|pair| (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y))
+            .filter(|length| length.is_finite())
+            .sum();
+
+        for (piece, points) in pieces.iter().zip(&mut projected) {
+            for (&coord, point) in piece.iter().zip(points) {
+                let t = central_angle(self.from, coord) / angle;
+                point.y -= elevation * length * (std::f64::consts::PI * t).sin();
+            }
+        }
+        projected
+    }
+}
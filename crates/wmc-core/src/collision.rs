@@ -0,0 +1,160 @@
+use crate::index::SpatialIndex;
+
+/// Screen-space detector keeping labels from overlapping
+///
+/// Boxes are `[min_x, min_y, max_x, max_y]` in pixels. Placed boxes go into
+/// an R-tree, so each new label is tested against its neighbors only.
+/// Labels are placed greedily: whatever is placed first wins, so callers
+/// add the most important labels first, or hand them to
+/// [`Collider::resolve`] with priorities. Country names, marker labels and
+/// cluster badges can share one collider to stay clear of each other.
+///
+/// ```
+/// use wmc_core::collision::Collider;
+///
+/// let mut collider = Collider::new([0.0, 0.0, 100.0, 100.0], 2.0);
+/// assert!(collider.place([10.0, 10.0, 40.0, 20.0]));
+///
+/// // Too close to the first label, or out of the area
+/// assert!(!collider.place([41.0, 10.0, 60.0, 20.0]));
+/// assert!(!collider.place([90.0, 90.0, 110.0, 95.0]));
+///
+/// // Moved below the first label instead
+/// let offsets = [[0.0, 0.0], [0.0, 15.0]];
+/// assert_eq!(collider.place_near([20.0, 10.0, 50.0, 20.0], &offsets), Some([0.0, 15.0]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Collider {
+    placed: SpatialIndex<[f32; 4]>,
+    area: [f32; 4],
+    padding: f32,
+}
+
+/// Label competing for space in [`Collider::resolve`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelCandidate {
+    /// Box at the label's preferred position
+    pub bounds: [f32; 4],
+    /// Higher priorities are placed first
+    pub priority: f32,
+}
+
+impl Collider {
+    /// Creates a collider keeping labels inside `area` and at least
+    /// `padding` pixels apart
+    #[must_use]
+    pub const fn new(area: [f32; 4], padding: f32) -> Self {
+        Self {
+            placed: SpatialIndex::new(),
+            area,
+            padding,
+        }
+    }
+
+    /// Returns the number of boxes placed or reserved
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.placed.len()
+    }
+
+    /// Returns true if nothing is placed yet
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.placed.is_empty()
+    }
+
+    /// Returns true if `bounds` lies inside the area and keeps the padding
+    /// to every placed box
+    #[must_use]
+    pub fn fits(&self, bounds: [f32; 4]) -> bool {
+        let [min_x, min_y, max_x, max_y] = bounds;
+        let [area_min_x, area_min_y, area_max_x, area_max_y] = self.area;
+        if min_x < area_min_x || min_y < area_min_y || max_x > area_max_x || max_y > area_max_y {
+            return false;
+        }
+
+        let padded = [
+            min_x - self.padding,
+            min_y - self.padding,
+            max_x + self.padding,
+            max_y + self.padding,
+        ];
+        let mut clear = true;
+        self.placed.query_each(padded.map(f64::from), |&other| {
+            clear &= !overlaps(padded, other);
+        });
+        clear
+    }
+
+    /// Reserves `bounds` whether it fits or not, e.g. for a marker labels
+    /// must keep clear of
+    pub fn reserve(&mut self, bounds: [f32; 4]) {
+        self.placed.insert(bounds.map(f64::from), bounds);
+    }
+
+    /// Places `bounds` if it fits, returning false otherwise
+    pub fn place(&mut self, bounds: [f32; 4]) -> bool {
+        let fits = self.fits(bounds);
+        if fits {
+            self.reserve(bounds);
+        }
+        fits
+    }
+
+    /// Places `bounds` moved by the first of `offsets` where it fits,
+    /// returning that offset, or `None` if it fits nowhere
+    pub fn place_near(&mut self, bounds: [f32; 4], offsets: &[[f32; 2]]) -> Option<[f32; 2]> {
+        offsets
+            .iter()
+            .copied()
+            .find(|&offset| self.place(shift(bounds, offset)))
+    }
+
+    /// Places `candidates` from the highest priority down, the earlier one
+    /// on ties, each at the first of `offsets` where it fits
+    ///
+    /// Returns the offset of every candidate in input order, `None` for
+    /// those left out.
+    ///
+    /// ```
+    /// use wmc_core::collision::{Collider, LabelCandidate};
+    ///
+    /// let mut collider = Collider::new([0.0, 0.0, 100.0, 100.0], 0.0);
+    /// let label = |x, priority| LabelCandidate {
+    ///     bounds: [x, 10.0, x + 20.0, 20.0],
+    ///     priority,
+    /// };
+    ///
+    /// let placed = collider.resolve(&[label(0.0, 1.0), label(10.0, 5.0)], &[[0.0, 0.0]]);
+    /// assert_eq!(placed, [None, Some([0.0, 0.0])]);
+    /// ```
+    pub fn resolve(
+        &mut self,
+        candidates: &[LabelCandidate],
+        offsets: &[[f32; 2]],
+    ) -> Vec<Option<[f32; 2]>> {
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| candidates[b].priority.total_cmp(&candidates[a].priority));
+
+        let mut placed = vec![None; candidates.len()];
+        for index in order {
+            placed[index] = self.place_near(candidates[index].bounds, offsets);
+        }
+        placed
+    }
+}
+
+/// Returns `bounds` moved by `offset`
+const fn shift(bounds: [f32; 4], [dx, dy]: [f32; 2]) -> [f32; 4] {
+    [
+        bounds[0] + dx,
+        bounds[1] + dy,
+        bounds[2] + dx,
+        bounds[3] + dy,
+    ]
+}
+
+/// Returns true if two boxes intersect, touching edges excluded
+fn overlaps(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
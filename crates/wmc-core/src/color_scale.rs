@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::CoreError,
+    heatmap::{Gradient, GradientStop},
+    marker::Color,
+};
+
+/// Viridis anchors, evenly spaced from low to high
+const VIRIDIS: [[u8; 3]; 9] = [
+    [0x44, 0x01, 0x54],
+    [0x47, 0x2d, 0x7b],
+    [0x3b, 0x52, 0x8b],
+    [0x2c, 0x72, 0x8e],
+    [0x21, 0x91, 0x8c],
+    [0x28, 0xae, 0x80],
+    [0x5e, 0xc9, 0x62],
+    [0xad, 0xdc, 0x30],
+    [0xfd, 0xe7, 0x25],
+];
+
+/// Magma anchors, evenly spaced from low to high
+const MAGMA: [[u8; 3]; 9] = [
+    [0x00, 0x00, 0x04],
+    [0x1c, 0x10, 0x44],
+    [0x4f, 0x12, 0x7b],
+    [0x81, 0x25, 0x81],
+    [0xb5, 0x36, 0x7a],
+    [0xe5, 0x50, 0x64],
+    [0xfb, 0x87, 0x61],
+    [0xfe, 0xc2, 0x87],
+    [0xfc, 0xfd, 0xbf],
+];
+
+/// `ColorBrewer` red to blue anchors, evenly spaced from low to high
+const RD_BU: [[u8; 3]; 11] = [
+    [0x67, 0x00, 0x1f],
+    [0xb2, 0x18, 0x2b],
+    [0xd6, 0x60, 0x4d],
+    [0xf4, 0xa5, 0x82],
+    [0xfd, 0xdb, 0xc7],
+    [0xf7, 0xf7, 0xf7],
+    [0xd1, 0xe5, 0xf0],
+    [0x92, 0xc5, 0xde],
+    [0x43, 0x93, 0xc3],
+    [0x21, 0x66, 0xac],
+    [0x05, 0x30, 0x61],
+];
+
+/// Built-in color ramp of a [`ColorScale`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Perceptually uniform dark purple through teal to yellow
+    #[default]
+    Viridis,
+    /// Perceptually uniform black through purple and orange to pale yellow
+    Magma,
+    /// Diverging dark red through white to dark blue
+    #[serde(alias = "RdBu")]
+    RdBu,
+}
+
+impl Palette {
+    /// Parses a palette name as used in scale options
+    ///
+    /// ```
+    /// use wmc_core::color_scale::Palette;
+    ///
+    /// assert_eq!(Palette::from_name("RdBu"), Some(Palette::RdBu));
+    /// assert_eq!(Palette::from_name("jet"), None);
+    /// ```
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "viridis" => Some(Self::Viridis),
+            "magma" => Some(Self::Magma),
+            "rdbu" | "RdBu" => Some(Self::RdBu),
+            _ => None,
+        }
+    }
+
+    /// Returns true for palettes meant to diverge from a neutral midpoint
+    #[must_use]
+    pub const fn is_diverging(self) -> bool {
+        matches!(self, Self::RdBu)
+    }
+
+    /// Returns the anchors as RGB bytes, evenly spaced from low to high
+    const fn anchors(self) -> &'static [[u8; 3]] {
+        match self {
+            Self::Viridis => &VIRIDIS,
+            Self::Magma => &MAGMA,
+            Self::RdBu => &RD_BU,
+        }
+    }
+
+    /// Returns the opaque color at `t` in `0.0..=1.0`, blending the two
+    /// nearest anchors linearly
+    ///
+    /// ```
+    /// use wmc_core::color_scale::Palette;
+    ///
+    /// let white = Palette::RdBu.sample(0.5);
+    /// assert!(white.r > 0.96 && white.b > 0.96);
+    /// ```
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn sample(self, t: f32) -> Color {
+        let anchors = self.anchors();
+        let last = anchors.len() - 1;
+        let position = t.clamp(0.0, 1.0) * last as f32;
+        let index = (position.floor() as usize).min(last - 1);
+        let t = position - index as f32;
+
+        let (from, to) = (rgb(anchors[index]), rgb(anchors[index + 1]));
+        let mix = |i: usize| (to[i] - from[i]).mul_add(t, from[i]);
+        Color::rgba(mix(0), mix(1), mix(2), 1.0)
+    }
+}
+
+/// Scales byte channels to `0.0..=1.0`
+fn rgb(bytes: [u8; 3]) -> [f32; 3] {
+    bytes.map(|channel| f32::from(channel) / 255.0)
+}
+
+const fn default_domain() -> [f64; 2] {
+    [0.0, 1.0]
+}
+
+/// Mapping of data values onto a [`Palette`], e.g. for choropleth classes,
+/// heatmap densities or marker intensities
+///
+/// Values are placed linearly between the ends of the domain and clamped to
+/// it. A diverging scale can set a midpoint, e.g. zero for gains and losses,
+/// which then takes the palette's center color even if the domain isn't
+/// symmetric around it. Deserialized from e.g.
+/// `{"palette": "RdBu", "domain": [-5, 20], "midpoint": 0}`.
+///
+/// ```
+/// use wmc_core::color_scale::{ColorScale, Palette};
+///
+/// let scale = ColorScale::new(Palette::Viridis, [0.0, 100.0]);
+/// assert_eq!(scale.position(25.0), 0.25);
+/// assert_eq!(scale.position(250.0), 1.0);
+///
+/// let scale = ColorScale::new(Palette::RdBu, [-5.0, 20.0]).with_midpoint(0.0);
+/// assert_eq!(scale.position(0.0), 0.5);
+/// assert_eq!(scale.position(10.0), 0.75);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorScale {
+    /// Colors from the low to the high end of the domain
+    #[serde(default)]
+    pub palette: Palette,
+    /// Values mapped to the first and last palette color; a descending
+    /// domain runs the palette backwards
+    #[serde(default = "default_domain")]
+    pub domain: [f64; 2],
+    /// Value mapped to the palette's center color
+    #[serde(default)]
+    pub midpoint: Option<f64>,
+    /// Runs the palette from high to low
+    #[serde(default)]
+    pub reverse: bool,
+}
+
+impl Default for ColorScale {
+    fn default() -> Self {
+        Self::new(Palette::default(), default_domain())
+    }
+}
+
+impl ColorScale {
+    /// Creates a scale mapping `domain` onto `palette`
+    #[must_use]
+    pub const fn new(palette: Palette, domain: [f64; 2]) -> Self {
+        Self {
+            palette,
+            domain,
+            midpoint: None,
+            reverse: false,
+        }
+    }
+
+    /// Maps `midpoint` to the palette's center color
+    #[must_use]
+    pub const fn with_midpoint(mut self, midpoint: f64) -> Self {
+        self.midpoint = Some(midpoint);
+        self
+    }
+
+    /// Runs the palette from high to low
+    #[must_use]
+    pub const fn reversed(mut self) -> Self {
+        self.reverse = !self.reverse;
+        self
+    }
+
+    /// Validates that the domain ends are finite and distinct and the
+    /// midpoint lies strictly between them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] describing the invalid
+    /// value
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let [start, end] = self.domain;
+        let reason = if !start.is_finite() || !end.is_finite() || !(end - start).is_normal() {
+            format!("invalid color scale domain {:?}", self.domain)
+        } else if self.midpoint.is_some_and(|midpoint| {
+            !(midpoint.is_finite() && start.min(end) < midpoint && midpoint < start.max(end))
+        }) {
+            format!(
+                "color scale midpoint {:?} outside domain {:?}",
+                self.midpoint, self.domain
+            )
+        } else {
+            return Ok(());
+        };
+
+        Err(CoreError::ThemeValidationFailed { reason })
+    }
+
+    /// Returns where `value` falls along the palette, from 0 at the start
+    /// of the domain to 1 at its end
+    ///
+    /// Values outside the domain are clamped and `NaN` maps to 0.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn position(&self, value: f64) -> f32 {
+        let [start, end] = self.domain;
+        let fraction = |from: f64, to: f64| (value - from) / (to - from);
+
+        let t = match self.midpoint {
+            Some(midpoint) if fraction(start, midpoint) < 1.0 => 0.5 * fraction(start, midpoint),
+            Some(midpoint) => fraction(midpoint, end).mul_add(0.5, 0.5),
+            None => fraction(start, end),
+        };
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let t = if self.reverse { 1.0 - t } else { t };
+        t as f32
+    }
+
+    /// Returns the opaque color of `value`
+    ///
+    /// ```
+    /// use wmc_core::color_scale::{ColorScale, Palette};
+    ///
+    /// let scale = ColorScale::new(Palette::Magma, [0.0, 10.0]);
+    /// assert_eq!(scale.color(-3.0).b, Palette::Magma.sample(0.0).b);
+    /// ```
+    #[must_use]
+    pub fn color(&self, value: f64) -> Color {
+        self.palette.sample(self.position(value))
+    }
+
+    /// Samples the scale at `stops` evenly spaced values across the domain,
+    /// as a gradient from offset 0 at the domain start to 1 at its end
+    ///
+    /// ```
+    /// use wmc_core::color_scale::{ColorScale, Palette};
+    ///
+    /// let gradient = ColorScale::new(Palette::Viridis, [0.0, 1.0]).gradient(16);
+    /// assert_eq!(gradient.stops.len(), 16);
+    /// assert!(gradient.validate().is_ok());
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn gradient(&self, stops: usize) -> Gradient {
+        let [start, end] = self.domain;
+        let last = stops.saturating_sub(1).max(1) as f64;
+
+        Gradient {
+            stops: (0..stops.max(2))
+                .map(|i| {
+                    let t = i as f64 / last;
+                    GradientStop {
+                        offset: t as f32,
+                        color: self.color((end - start).mul_add(t, start)),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,9 +1,23 @@
 use std::fmt;
 
 use masterror::AppError;
+use serde::Serialize;
 
 /// Core library errors
-#[derive(Debug, Clone)]
+///
+/// Serializes as `{code, details}`, `code` being the variant name in
+/// snake case and `details` its fields
+///
+/// ```
+/// use wmc_core::error::CoreError;
+///
+/// let error = CoreError::InvalidMarkerId { id: "7".to_string() };
+/// let json = serde_json::to_value(&error).unwrap();
+/// assert_eq!(json["code"], "invalid_marker_id");
+/// assert_eq!(json["details"]["id"], "7");
+/// ```
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
 pub enum CoreError {
     /// Invalid geographic coordinates
     InvalidCoordinates {
@@ -12,6 +26,17 @@ pub enum CoreError {
         /// Longitude value
         lon: f64,
     },
+    /// Bounding box whose edges enclose no area
+    InvalidBounds {
+        /// Western longitude
+        west: f64,
+        /// Southern latitude
+        south: f64,
+        /// Eastern longitude
+        east: f64,
+        /// Northern latitude
+        north: f64,
+    },
     /// Invalid marker identifier
     InvalidMarkerId {
         /// Marker ID or error description
@@ -42,6 +67,18 @@ impl fmt::Display for CoreError {
             Self::InvalidCoordinates { lat, lon } => {
                 write!(f, "Invalid coordinates: lat={lat}, lon={lon}")
             },
+            Self::InvalidBounds {
+                west,
+                south,
+                east,
+                north,
+            } => {
+                write!(
+                    f,
+                    "Invalid bounds: south={south} must be below north={north} and \
+                     west={west} differ from east={east}"
+                )
+            },
             Self::InvalidMarkerId { id } => write!(f, "Invalid marker ID: {id}"),
             Self::ThemeValidationFailed { reason } => {
                 write!(f, "Theme validation failed: {reason}")
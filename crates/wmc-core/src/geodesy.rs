@@ -0,0 +1,527 @@
+use crate::projection::GeoCoord;
+
+/// Mean Earth radius in meters
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Equatorial radius of the WGS 84 ellipsoid in meters
+pub const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+
+/// Flattening of the WGS 84 ellipsoid
+pub const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Most iterations [`distance_vincenty`] runs before giving up
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+
+/// Returns the angle between two points as seen from the Earth's center, in
+/// radians
+///
+/// Uses the haversine formula, which stays accurate for nearby points.
+///
+/// ```
+/// use wmc_core::{geodesy::central_angle, projection::GeoCoord};
+///
+/// let a = GeoCoord::new(0.0, 0.0).unwrap();
+/// let b = GeoCoord::new(0.0, 90.0).unwrap();
+/// assert!((central_angle(a, b) - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+/// ```
+#[must_use]
+pub fn central_angle(a: GeoCoord, b: GeoCoord) -> f64 {
+    let (lat_a, lat_b) = (a.lat.to_radians(), b.lat.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.lon - a.lon).to_radians();
+
+    let h = (d_lon / 2.0)
+        .sin()
+        .powi(2)
+        .mul_add(lat_a.cos() * lat_b.cos(), (d_lat / 2.0).sin().powi(2));
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+/// Returns the great-circle distance between two points in meters, on a
+/// sphere of [`EARTH_RADIUS`]
+///
+/// Off by up to about 0.5% from the distance on the ellipsoid, see
+/// [`distance_vincenty`] where that matters.
+///
+/// ```
+/// use wmc_core::{geodesy::distance_haversine, projection::GeoCoord};
+///
+/// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+/// let london = GeoCoord::new(51.5074, -0.1278).unwrap();
+/// let km = distance_haversine(paris, london) / 1000.0;
+/// assert!((km - 343.6).abs() < 0.5);
+/// ```
+#[must_use]
+pub fn distance_haversine(a: GeoCoord, b: GeoCoord) -> f64 {
+    central_angle(a, b) * EARTH_RADIUS
+}
+
+/// Returns the distance between two points in meters along the WGS 84
+/// ellipsoid, accurate to a millimeter
+///
+/// Uses Vincenty's inverse formula, which converges slowly or not at all
+/// for nearly antipodal points; it returns `None` for those, and callers
+/// may fall back to [`distance_haversine`].
+///
+/// ```
+/// use wmc_core::{geodesy::distance_vincenty, projection::GeoCoord};
+///
+/// // Flinders Peak to Buninyong, Vincenty's own test line
+/// let flinders = GeoCoord::new(-37.951_033_417, 144.424_867_889).unwrap();
+/// let buninyong = GeoCoord::new(-37.652_821_139, 143.926_495_528).unwrap();
+/// let meters = distance_vincenty(flinders, buninyong).unwrap();
+/// assert!((meters - 54_972.271).abs() < 0.01);
+///
+/// let antipode = GeoCoord::new(0.5, -179.7).unwrap();
+/// assert!(distance_vincenty(GeoCoord::new(0.0, 0.0).unwrap(), antipode).is_none());
+/// ```
+#[must_use]
+pub fn distance_vincenty(a: GeoCoord, b: GeoCoord) -> Option<f64> {
+    let flattening = WGS84_FLATTENING;
+    let semi_minor = WGS84_SEMI_MAJOR_AXIS * (1.0 - flattening);
+    let lon_delta = (b.lon - a.lon).to_radians();
+    // Reduced latitudes, on the auxiliary sphere
+    let (sin_u1, cos_u1) = ((1.0 - flattening) * a.lat.to_radians().tan())
+        .atan()
+        .sin_cos();
+    let (sin_u2, cos_u2) = ((1.0 - flattening) * b.lat.to_radians().tan())
+        .atan()
+        .sin_cos();
+
+    let mut lambda = lon_delta;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma =
+            (cos_u2 * sin_lambda).hypot((sin_u1 * cos_u2).mul_add(-cos_lambda, cos_u1 * sin_u2));
+        if sin_sigma <= 0.0 {
+            return Some(0.0);
+        }
+        let cos_sigma = (cos_u1 * cos_u2).mul_add(cos_lambda, sin_u1 * sin_u2);
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = sin_alpha.mul_add(-sin_alpha, 1.0);
+        // Lines along the equator have no midpoint latitude
+        let cos_2sigma_m = if cos2_alpha > 0.0 {
+            (2.0 * sin_u1 * sin_u2).mul_add(-1.0 / cos2_alpha, cos_sigma)
+        } else {
+            0.0
+        };
+        let c = flattening / 16.0
+            * cos2_alpha
+            * flattening.mul_add(3.0f64.mul_add(-cos2_alpha, 4.0), 4.0);
+
+        let previous = lambda;
+        lambda = ((1.0 - c) * flattening * sin_alpha).mul_add(
+            (c * sin_sigma).mul_add(
+                (c * cos_sigma).mul_add(
+                    (2.0 * cos_2sigma_m).mul_add(cos_2sigma_m, -1.0),
+                    cos_2sigma_m,
+                ),
+                sigma,
+            ),
+            lon_delta,
+        );
+        if (lambda - previous).abs() > 1e-12 {
+            continue;
+        }
+
+        let axis_ratio = WGS84_SEMI_MAJOR_AXIS / semi_minor;
+        let second_eccentricity2 = axis_ratio.mul_add(axis_ratio, -1.0);
+        let u2 = cos2_alpha * second_eccentricity2;
+        let big_a = (u2 / 16_384.0).mul_add(
+            u2.mul_add(u2.mul_add(175.0f64.mul_add(-u2, 320.0), -768.0), 4096.0),
+            1.0,
+        );
+        let big_b =
+            u2 / 1024.0 * u2.mul_add(u2.mul_add(47.0f64.mul_add(-u2, 74.0), -128.0), 256.0);
+        let correction = (big_b / 6.0 * cos_2sigma_m * (4.0 * sin_sigma).mul_add(sin_sigma, -3.0))
+            .mul_add(
+                -(4.0 * cos_2sigma_m).mul_add(cos_2sigma_m, -3.0),
+                cos_sigma * (2.0 * cos_2sigma_m).mul_add(cos_2sigma_m, -1.0),
+            );
+        let delta_sigma = big_b * sin_sigma * (big_b / 4.0).mul_add(correction, cos_2sigma_m);
+        return Some(semi_minor * big_a * (sigma - delta_sigma));
+    }
+
+    None
+}
+
+/// Returns the length of a polyline in meters, following the ellipsoid
+/// between consecutive points
+///
+/// Each segment is measured with [`distance_vincenty`], falling back to
+/// [`distance_haversine`] for nearly antipodal ones. Fewer than two points
+/// have no length.
+///
+/// ```
+/// use wmc_core::{geodesy::line_length_m, projection::GeoCoord};
+///
+/// let point = |lat, lon| GeoCoord::new(lat, lon).unwrap();
+/// // Along the equator, where a degree is 111.32 km
+/// let route = [point(0.0, 0.0), point(0.0, 1.0), point(0.0, 3.0)];
+/// assert!((line_length_m(&route) / 1000.0 - 333.96).abs() < 0.01);
+/// assert_eq!(line_length_m(&route[..1]), 0.0);
+/// ```
+#[must_use]
+pub fn line_length_m(points: &[GeoCoord]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| {
+            distance_vincenty(pair[0], pair[1])
+                .unwrap_or_else(|| distance_haversine(pair[0], pair[1]))
+        })
+        .sum()
+}
+
+/// Returns the initial bearing of the great circle from `a` to `b`, in
+/// degrees clockwise from north in `[0, 360)`
+///
+/// The bearing changes along the way for all but meridian and equator
+/// lines, see [`final_bearing`] for the heading on arrival.
+///
+/// ```
+/// use wmc_core::{geodesy::bearing, projection::GeoCoord};
+///
+/// let a = GeoCoord::new(0.0, 0.0).unwrap();
+/// assert!((bearing(a, GeoCoord::new(0.0, 10.0).unwrap()) - 90.0).abs() < 1e-9);
+/// assert!((bearing(a, GeoCoord::new(-10.0, 0.0).unwrap()) - 180.0).abs() < 1e-9);
+///
+/// // Paris to New York sets off north of west
+/// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+/// let new_york = GeoCoord::new(40.7128, -74.006).unwrap();
+/// assert!((bearing(paris, new_york) - 291.8).abs() < 0.1);
+/// ```
+#[must_use]
+pub fn bearing(a: GeoCoord, b: GeoCoord) -> f64 {
+    let (lat_a, lat_b) = (a.lat.to_radians(), b.lat.to_radians());
+    let (sin_d_lon, cos_d_lon) = (b.lon - a.lon).to_radians().sin_cos();
+
+    let y = sin_d_lon * lat_b.cos();
+    let x = lat_a
+        .cos()
+        .mul_add(lat_b.sin(), -(lat_a.sin() * lat_b.cos() * cos_d_lon));
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Returns the bearing of the great circle from `a` to `b` on arrival at
+/// `b`, in degrees clockwise from north in `[0, 360)`
+///
+/// ```
+/// use wmc_core::{geodesy::final_bearing, projection::GeoCoord};
+///
+/// // Paris to New York arrives heading south of west
+/// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+/// let new_york = GeoCoord::new(40.7128, -74.006).unwrap();
+/// let heading = final_bearing(paris, new_york);
+/// assert!(heading > 180.0 && heading < 270.0);
+/// ```
+#[must_use]
+pub fn final_bearing(a: GeoCoord, b: GeoCoord) -> f64 {
+    (bearing(b, a) + 180.0).rem_euclid(360.0)
+}
+
+/// Returns the point `distance` meters from `origin` along the great circle
+/// leaving it at `bearing` degrees clockwise from north
+///
+/// Works on a sphere of [`EARTH_RADIUS`], like [`distance_haversine`]. The
+/// longitude of the result is wrapped into `[-180, 180)`.
+///
+/// ```
+/// use wmc_core::{
+///     geodesy::{destination, distance_haversine},
+///     projection::GeoCoord,
+/// };
+///
+/// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+/// let point = destination(paris, 45.0, 100_000.0);
+/// assert!(point.lat > paris.lat && point.lon > paris.lon);
+/// assert!((distance_haversine(paris, point) - 100_000.0).abs() < 1e-6);
+///
+/// // Heading east across the antimeridian wraps around
+/// let fiji = GeoCoord::new(0.0, 179.0).unwrap();
+/// assert!(destination(fiji, 90.0, 250_000.0).lon < -178.0);
+/// ```
+#[must_use]
+pub fn destination(origin: GeoCoord, bearing: f64, distance: f64) -> GeoCoord {
+    let angle = distance / EARTH_RADIUS;
+    let (sin_angle, cos_angle) = angle.sin_cos();
+    let (sin_bearing, cos_bearing) = bearing.to_radians().sin_cos();
+    let (sin_lat, cos_lat) = origin.lat.to_radians().sin_cos();
+
+    let sin_lat_to = sin_lat.mul_add(cos_angle, cos_lat * sin_angle * cos_bearing);
+    let lat = sin_lat_to.clamp(-1.0, 1.0).asin();
+    let d_lon = (sin_bearing * sin_angle * cos_lat).atan2(sin_lat.mul_add(-sin_lat_to, cos_angle));
+
+    GeoCoord {
+        lat: lat.to_degrees(),
+        lon: (origin.lon + d_lon.to_degrees() + 180.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Returns the point a fraction `t` of the way from `a` to `b` along the
+/// great circle through them
+///
+/// Antipodal points have no unique great circle; between them the point is
+/// interpolated linearly in latitude and longitude instead.
+///
+/// ```
+/// use wmc_core::{geodesy::interpolate, projection::GeoCoord};
+///
+/// let a = GeoCoord::new(0.0, -10.0).unwrap();
+/// let b = GeoCoord::new(0.0, 10.0).unwrap();
+/// let mid = interpolate(a, b, 0.5);
+/// assert!(mid.lat.abs() < 1e-9 && mid.lon.abs() < 1e-9);
+/// ```
+#[must_use]
+#[allow(clippy::many_single_char_names)]
+pub fn interpolate(a: GeoCoord, b: GeoCoord, t: f64) -> GeoCoord {
+    let angle = central_angle(a, b);
+    let sin_angle = angle.sin();
+    if sin_angle.abs() < 1e-12 {
+        return GeoCoord {
+            lat: (b.lat - a.lat).mul_add(t, a.lat),
+            lon: (b.lon - a.lon).mul_add(t, a.lon),
+        };
+    }
+
+    let (wa, wb) = (
+        ((1.0 - t) * angle).sin() / sin_angle,
+        (t * angle).sin() / sin_angle,
+    );
+    let [xa, ya, za] = to_unit(a);
+    let [xb, yb, zb] = to_unit(b);
+    let (x, y, z) = (
+        wa.mul_add(xa, wb * xb),
+        wa.mul_add(ya, wb * yb),
+        wa.mul_add(za, wb * zb),
+    );
+
+    GeoCoord {
+        lat: z.atan2(x.hypot(y)).to_degrees(),
+        lon: y.atan2(x).to_degrees(),
+    }
+}
+
+/// Returns `segments + 1` evenly spaced points along the great circle from
+/// `a` to `b`, both included
+///
+/// ```
+/// use wmc_core::{geodesy::great_circle, projection::GeoCoord};
+///
+/// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+/// let new_york = GeoCoord::new(40.7128, -74.006).unwrap();
+/// let path = great_circle(paris, new_york, 32);
+///
+/// assert_eq!(path.len(), 33);
+/// // The route bows north of both cities
+/// assert!(path[16].lat > 50.0);
+/// ```
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn great_circle(a: GeoCoord, b: GeoCoord, segments: usize) -> Vec<GeoCoord> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| match i {
+            0 => a,
+            i if i == segments => b,
+            i => interpolate(a, b, i as f64 / segments as f64),
+        })
+        .collect()
+}
+
+/// Returns a closed ring of `segments + 1` points lying `radius` meters
+/// from `center` over the ground, e.g. a range ring
+///
+/// The ring runs counterclockwise, as `GeoJSON` expects of outer rings, and
+/// its last point repeats the first. Far from the equator it looks
+/// stretched on flat projections, as the true circle does. Rings crossing
+/// the antimeridian or enclosing a pole keep their wrapped longitudes, so
+/// only their outline draws correctly.
+///
+/// ```
+/// use wmc_core::{
+///     geodesy::{distance_haversine, geodesic_circle},
+///     projection::GeoCoord,
+/// };
+///
+/// let oslo = GeoCoord::new(59.91, 10.75).unwrap();
+/// let ring = geodesic_circle(oslo, 50_000.0, 64);
+///
+/// assert_eq!(ring.len(), 65);
+/// assert_eq!(ring[0], ring[64]);
+/// assert!(ring
+///     .iter()
+///     .all(|&point| (distance_haversine(oslo, point) - 50_000.0).abs() < 1e-6));
+/// ```
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn geodesic_circle(center: GeoCoord, radius: f64, segments: usize) -> Vec<GeoCoord> {
+    let segments = segments.max(3);
+    let mut ring: Vec<GeoCoord> = (0..segments)
+        .map(|i| destination(center, 360.0 - 360.0 * i as f64 / segments as f64, radius))
+        .collect();
+    ring.push(ring[0]);
+    ring
+}
+
+/// Returns the point of the great-circle segment from `a` to `b` closest to
+/// `point`
+///
+/// ```
+/// use wmc_core::{geodesy::nearest_on_segment, projection::GeoCoord};
+///
+/// let point = |lat, lon| GeoCoord::new(lat, lon).unwrap();
+/// let (a, b) = (point(0.0, 0.0), point(0.0, 10.0));
+///
+/// let foot = nearest_on_segment(point(3.0, 4.0), a, b);
+/// assert!(foot.lat.abs() < 1e-9 && (foot.lon - 4.0).abs() < 1e-9);
+/// // Beyond the ends the nearest end is closest
+/// assert_eq!(nearest_on_segment(point(1.0, 12.0), a, b), b);
+/// ```
+#[must_use]
+pub fn nearest_on_segment(point: GeoCoord, a: GeoCoord, b: GeoCoord) -> GeoCoord {
+    let (unit, ua, ub) = (to_unit(point), to_unit(a), to_unit(b));
+    let normal = cross(ua, ub);
+    let length = dot(normal, normal).sqrt();
+    if length < 1e-12 {
+        return nearer(point, a, b);
+    }
+
+    // Drop the point onto the plane of the great circle
+    let normal = normal.map(|c| c / length);
+    let height = dot(unit, normal);
+    let foot = [0, 1, 2].map(|i| height.mul_add(-normal[i], unit[i]));
+    if dot(foot, foot) < 1e-24 {
+        return nearer(point, a, b);
+    }
+
+    if dot(cross(ua, foot), normal) >= 0.0 && dot(cross(foot, ub), normal) >= 0.0 {
+        from_unit(foot)
+    } else {
+        nearer(point, a, b)
+    }
+}
+
+/// Returns the area enclosed by a ring of points joined by great circles,
+/// in square meters, on a sphere of [`EARTH_RADIUS`]
+///
+/// Sums the spherical excess of the triangles each edge makes with the
+/// pole, so the result doesn't depend on any projection. Rings may be
+/// open or closed and run either way; of the two regions a ring splits the
+/// globe into, the smaller one is measured.
+///
+/// ```
+/// use wmc_core::{geodesy::ring_area, projection::GeoCoord};
+///
+/// let point = |lat, lon| GeoCoord::new(lat, lon).unwrap();
+/// // One degree square on the equator
+/// let square = [point(0.0, 0.0), point(0.0, 1.0), point(1.0, 1.0), point(1.0, 0.0)];
+/// assert!((ring_area(&square) / 1e6 - 12_364.0).abs() < 1.0);
+///
+/// // The same square in the Arctic covers a fraction of it
+/// let arctic = [point(70.0, 0.0), point(70.0, 1.0), point(71.0, 1.0), point(71.0, 0.0)];
+/// assert!(ring_area(&arctic) < ring_area(&square) / 2.0);
+/// ```
+#[must_use]
+pub fn ring_area(ring: &[GeoCoord]) -> f64 {
+    let excess: f64 = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let d_lon = ((b.lon - a.lon + 180.0).rem_euclid(360.0) - 180.0).to_radians();
+            let (tan_a, tan_b) = (
+                (a.lat.to_radians() / 2.0).tan(),
+                (b.lat.to_radians() / 2.0).tan(),
+            );
+            2.0 * ((d_lon / 2.0).tan() * (tan_a + tan_b)).atan2(tan_a.mul_add(tan_b, 1.0))
+        })
+        .sum();
+
+    let excess = excess.abs();
+    excess.min(std::f64::consts::TAU.mul_add(2.0, -excess)) * EARTH_RADIUS * EARTH_RADIUS
+}
+
+/// Splits a path where it crosses the antimeridian
+///
+/// A step between consecutive points is taken to cross when it spans more
+/// than 180° of longitude. The pieces end and start on the ±180° meridian,
+/// at the latitude where the step crosses it.
+///
+/// ```
+/// use wmc_core::{geodesy::split_antimeridian, projection::GeoCoord};
+///
+/// let path = [
+///     GeoCoord::new(10.0, 170.0).unwrap(),
+///     GeoCoord::new(20.0, -170.0).unwrap(),
+/// ];
+/// let pieces = split_antimeridian(&path);
+///
+/// assert_eq!(pieces.len(), 2);
+/// assert_eq!(pieces[0].last().unwrap().lon, 180.0);
+/// assert_eq!(pieces[1][0].lon, -180.0);
+/// assert!((pieces[1][0].lat - 15.0).abs() < 1e-9);
+/// ```
+#[must_use]
+pub fn split_antimeridian(points: &[GeoCoord]) -> Vec<Vec<GeoCoord>> {
+    let mut pieces = Vec::new();
+    let mut piece: Vec<GeoCoord> = Vec::new();
+
+    for &point in points {
+        if let Some(&last) = piece.last() {
+            let step = point.lon - last.lon;
+            if step.abs() > 180.0 {
+                // Longitude of the crossing as seen from `last`
+                let edge: f64 = if step < 0.0 { 180.0 } else { -180.0 };
+                let unwrapped = edge.mul_add(2.0, point.lon);
+                let t = (edge - last.lon) / (unwrapped - last.lon);
+                let lat = (point.lat - last.lat).mul_add(t, last.lat);
+
+                piece.push(GeoCoord { lat, lon: edge });
+                pieces.push(std::mem::take(&mut piece));
+                piece.push(GeoCoord { lat, lon: -edge });
+            }
+        }
+        piece.push(point);
+    }
+
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Returns the unit vector pointing at `coord` from the Earth's center
+fn to_unit(coord: GeoCoord) -> [f64; 3] {
+    let (lat, lon) = (coord.lat.to_radians(), coord.lon.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Returns the point a vector from the Earth's center points at, the
+/// inverse of [`to_unit`] for vectors of any length
+fn from_unit([x, y, z]: [f64; 3]) -> GeoCoord {
+    GeoCoord {
+        lat: z.atan2(x.hypot(y)).to_degrees(),
+        lon: y.atan2(x).to_degrees(),
+    }
+}
+
+/// Returns whichever of `a` and `b` is closer to `point`
+fn nearer(point: GeoCoord, a: GeoCoord, b: GeoCoord) -> GeoCoord {
+    if central_angle(point, a) <= central_angle(point, b) {
+        a
+    } else {
+        b
+    }
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1].mul_add(b[2], -(a[2] * b[1])),
+        a[2].mul_add(b[0], -(a[0] * b[2])),
+        a[0].mul_add(b[1], -(a[1] * b[0])),
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0].mul_add(b[0], a[1].mul_add(b[1], a[2] * b[2]))
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{error::CoreError, marker::Color, projection::GeoCoord};
+
+/// Weighted point contributing to a heatmap's density
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HeatPoint {
+    /// Geographic coordinates
+    pub coord: GeoCoord,
+    /// Contribution to the density, 1 by default
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+const fn default_weight() -> f32 {
+    1.0
+}
+
+impl HeatPoint {
+    /// Validates the coordinates and weight
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidCoordinates`] if the coordinates are out of
+    /// range or [`CoreError::InvalidMarkerId`] if the weight is negative or
+    /// not finite
+    pub fn validate(&self) -> Result<(), CoreError> {
+        GeoCoord::new(self.coord.lat, self.coord.lon)?;
+
+        if self.weight < 0.0 || !self.weight.is_finite() {
+            return Err(CoreError::InvalidMarkerId {
+                id: format!("invalid heat point weight {}", self.weight),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Color of a gradient at one density
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Density in `0.0..=1.0` the color applies at
+    pub offset: f32,
+    /// Color at this density
+    pub color: Color,
+}
+
+/// Color ramp mapping heatmap density to color
+///
+/// Stops are sorted by offset; densities between two stops blend their
+/// colors linearly, densities outside the stops take the nearest one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Gradient {
+    /// Color stops by ascending offset
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    /// Validates that there is at least one stop, offsets ascend within
+    /// `0.0..=1.0` and color components are in range
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] describing the first
+    /// invalid stop
+    pub fn validate(&self) -> Result<(), CoreError> {
+        if self.stops.is_empty() {
+            return Err(CoreError::ThemeValidationFailed {
+                reason: "gradient has no stops".to_string(),
+            });
+        }
+
+        let mut previous = 0.0;
+        for stop in &self.stops {
+            let color = stop.color;
+            let in_range = [stop.offset, color.r, color.g, color.b, color.a]
+                .iter()
+                .all(|value| (0.0..=1.0).contains(value));
+            if !in_range || stop.offset < previous {
+                return Err(CoreError::ThemeValidationFailed {
+                    reason: format!("invalid gradient stop {stop:?}"),
+                });
+            }
+            previous = stop.offset;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the color at `density`
+    ///
+    /// ```
+    /// use wmc_core::heatmap::Gradient;
+    ///
+    /// let gradient = Gradient::default();
+    /// assert_eq!(gradient.sample(0.0).a, 0.0);
+    /// assert_eq!(gradient.sample(1.0).r, 1.0);
+    /// ```
+    #[must_use]
+    pub fn sample(&self, density: f32) -> Color {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color::rgba(0.0, 0.0, 0.0, 0.0);
+        };
+        if density <= first.offset {
+            return first.color;
+        }
+
+        self.stops
+            .windows(2)
+            .find(|pair| density <= pair[1].offset)
+            .map_or(last.color, |pair| {
+                let (from, to) = (pair[0], pair[1]);
+                let span = to.offset - from.offset;
+                let t = if span > 0.0 {
+                    (density - from.offset) / span
+                } else {
+                    1.0
+                };
+                let mix = |a: f32, b: f32| (b - a).mul_add(t, a);
+                Color::rgba(
+                    mix(from.color.r, to.color.r),
+                    mix(from.color.g, to.color.g),
+                    mix(from.color.b, to.color.b),
+                    mix(from.color.a, to.color.a),
+                )
+            })
+    }
+
+    /// Samples the gradient at `width` evenly spaced densities from 0 to 1
+    /// as tightly packed RGBA bytes, for upload as a lookup texture
+    ///
+    /// ```
+    /// use wmc_core::heatmap::Gradient;
+    ///
+    /// let ramp = Gradient::default().ramp(256);
+    /// assert_eq!(ramp.len(), 256 * 4);
+    /// assert_eq!(&ramp[255 * 4..], &[255, 0, 0, 255]);
+    /// ```
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn ramp(&self, width: usize) -> Vec<u8> {
+        let last = width.saturating_sub(1).max(1) as f32;
+
+        (0..width)
+            .flat_map(|i| {
+                let color = self.sample(i as f32 / last);
+                [color.r, color.g, color.b, color.a]
+                    .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect()
+    }
+}
+
+impl Default for Gradient {
+    /// Transparent blue through cyan, lime and yellow to red
+    fn default() -> Self {
+        let stop = |offset, r, g, b, a| GradientStop {
+            offset,
+            color: Color::rgba(r, g, b, a),
+        };
+
+        Self {
+            stops: vec![
+                stop(0.0, 0.0, 0.0, 1.0, 0.0),
+                stop(0.2, 0.0, 0.0, 1.0, 0.6),
+                stop(0.4, 0.0, 1.0, 1.0, 0.8),
+                stop(0.6, 0.0, 1.0, 0.0, 0.9),
+                stop(0.8, 1.0, 1.0, 0.0, 1.0),
+                stop(1.0, 1.0, 0.0, 0.0, 1.0),
+            ],
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+
+use geojson::{JsonObject, JsonValue};
+
+use crate::{
+    geodesy::EARTH_RADIUS,
+    heatmap::HeatPoint,
+    projection::GeoCoord,
+    topology::{Feature, Geometry, WorldTopology},
+};
+
+/// Circumradius of resolution 0 cells on the unit sphere's equal-area plane
+const BASE_SIZE: f64 = 0.5;
+
+/// Cosine of the 30° standard parallel of the equal-area plane
+const STANDARD_COS: f64 = 0.866_025_403_784_438_6;
+
+/// Mask of the 30 bits each axial coordinate takes in a cell id
+const AXIS_MASK: u64 = (1 << 30) - 1;
+
+/// Hexagonal cell of a hierarchical grid covering the globe, for hexbin
+/// aggregation and cell-based choropleths
+///
+/// Cells tile a Behrmann equal-area projection, so every cell of a
+/// resolution covers the same ground area and counts per cell compare
+/// fairly. Their shapes stretch east-west towards the poles. Each
+/// resolution halves the cell size of the one before, from about 6000 km
+/// across at 0 to under 200 m at [`HexCell::MAX_RESOLUTION`].
+///
+/// # Examples
+///
+/// ```
+/// use wmc_core::{hex::HexCell, projection::GeoCoord};
+///
+/// let berlin = GeoCoord::new(52.52, 13.405).unwrap();
+/// let cell = HexCell::at(berlin, 6);
+///
+/// assert!(cell.contains(berlin));
+/// assert_eq!(HexCell::from_id(cell.id()), Some(cell));
+/// assert_eq!(cell.parent(), Some(HexCell::at(cell.center(), 5)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HexCell {
+    /// Resolution, 0 being the coarsest
+    pub resolution: u8,
+    /// Axial column, growing eastwards
+    pub q: i32,
+    /// Axial row, growing northwards
+    pub r: i32,
+}
+
+impl HexCell {
+    /// Finest supported resolution
+    pub const MAX_RESOLUTION: u8 = 15;
+
+    /// Returns the cell of `resolution` containing `coord`
+    ///
+    /// Resolutions above [`Self::MAX_RESOLUTION`] are clamped to it.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn at(coord: GeoCoord, resolution: u8) -> Self {
+        let resolution = resolution.min(Self::MAX_RESOLUTION);
+        let (x, y) = to_plane(coord);
+        let size = size(resolution);
+
+        let q = (3.0f64.sqrt() / 3.0).mul_add(x, -y / 3.0) / size;
+        let r = 2.0 / 3.0 * y / size;
+        let (q, r) = round_axial(q, r);
+
+        Self {
+            resolution,
+            q: q as i32,
+            r: r as i32,
+        }
+    }
+
+    /// Returns the center of the cell
+    #[must_use]
+    pub fn center(self) -> GeoCoord {
+        let size = size(self.resolution);
+        let (q, r) = (f64::from(self.q), f64::from(self.r));
+
+        from_plane(size * 3.0f64.sqrt() * r.mul_add(0.5, q), size * 1.5 * r)
+    }
+
+    /// Returns the outline of the cell as a closed counterclockwise ring of
+    /// seven points
+    ///
+    /// Rings of cells straddling the antimeridian keep their wrapped
+    /// longitudes, like [`crate::geodesy::geodesic_circle`].
+    ///
+    /// ```
+    /// use wmc_core::{hex::HexCell, projection::GeoCoord};
+    ///
+    /// let cell = HexCell::at(GeoCoord::new(0.0, 0.0).unwrap(), 3);
+    /// let ring = cell.boundary();
+    /// assert_eq!(ring.len(), 7);
+    /// assert_eq!(ring[0], ring[6]);
+    /// ```
+    #[must_use]
+    pub fn boundary(self) -> Vec<GeoCoord> {
+        let size = size(self.resolution);
+        let (q, r) = (f64::from(self.q), f64::from(self.r));
+        let (cx, cy) = (size * 3.0f64.sqrt() * r.mul_add(0.5, q), size * 1.5 * r);
+
+        let mut ring: Vec<GeoCoord> = (0..6)
+            .map(|corner| {
+                let (sin, cos) = f64::from(60 * corner - 30).to_radians().sin_cos();
+                from_plane(size.mul_add(cos, cx), size.mul_add(sin, cy))
+            })
+            .collect();
+        ring.push(ring[0]);
+        ring
+    }
+
+    /// Returns true if `coord` falls in the cell
+    #[must_use]
+    pub fn contains(self, coord: GeoCoord) -> bool {
+        Self::at(coord, self.resolution) == self
+    }
+
+    /// Returns the six cells sharing an edge with this one, counterclockwise
+    /// from the east
+    #[must_use]
+    pub const fn neighbors(self) -> [Self; 6] {
+        const STEPS: [(i32, i32); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
+
+        let mut cells = [self; 6];
+        let mut i = 0;
+        while i < 6 {
+            cells[i].q += STEPS[i].0;
+            cells[i].r += STEPS[i].1;
+            i += 1;
+        }
+        cells
+    }
+
+    /// Returns the cell one resolution coarser containing this one's center
+    ///
+    /// Hexagons don't nest exactly, so a parent covers its children only
+    /// approximately, as in H3.
+    #[must_use]
+    pub fn parent(self) -> Option<Self> {
+        match self.resolution {
+            0 => None,
+            resolution => Some(Self::at(self.center(), resolution - 1)),
+        }
+    }
+
+    /// Returns the ground area of every cell of `resolution` in square
+    /// meters
+    ///
+    /// ```
+    /// use wmc_core::hex::HexCell;
+    ///
+    /// assert!((HexCell::area(1) / HexCell::area(2) - 4.0).abs() < 1e-9);
+    /// ```
+    #[must_use]
+    pub fn area(resolution: u8) -> f64 {
+        let size = size(resolution.min(Self::MAX_RESOLUTION)) * EARTH_RADIUS;
+        1.5 * 3.0f64.sqrt() * size * size
+    }
+
+    /// Returns the cell packed into a 64-bit id: the resolution in the top
+    /// four bits, then `q` and `r` in 30 bits each
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub const fn id(self) -> u64 {
+        ((self.resolution as u64) << 60)
+            | ((self.q as u64 & AXIS_MASK) << 30)
+            | (self.r as u64 & AXIS_MASK)
+    }
+
+    /// Unpacks a cell from [`Self::id`], or `None` if the resolution is out
+    /// of range
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub const fn from_id(id: u64) -> Option<Self> {
+        let resolution = (id >> 60) as u8;
+        if resolution > Self::MAX_RESOLUTION {
+            return None;
+        }
+
+        // Shift the 30-bit fields to the top to sign-extend them back
+        Some(Self {
+            resolution,
+            q: (((id >> 30) as u32) << 2) as i32 >> 2,
+            r: ((id as u32) << 2) as i32 >> 2,
+        })
+    }
+}
+
+/// Sums the weights of `points` per cell of `resolution`
+///
+/// ```
+/// use wmc_core::{heatmap::HeatPoint, hex::{self, HexCell}, projection::GeoCoord};
+///
+/// let point = |lat, lon, weight| HeatPoint {
+///     coord: GeoCoord::new(lat, lon).unwrap(),
+///     weight,
+/// };
+/// let bins = hex::hexbin(
+///     &[point(48.85, 2.35, 1.0), point(48.86, 2.36, 2.0), point(-33.9, 151.2, 1.0)],
+///     5,
+/// );
+///
+/// assert_eq!(bins.len(), 2);
+/// let paris = HexCell::at(GeoCoord::new(48.85, 2.35).unwrap(), 5);
+/// assert_eq!(bins[&paris], 3.0);
+/// ```
+#[must_use]
+pub fn hexbin(points: &[HeatPoint], resolution: u8) -> BTreeMap<HexCell, f64> {
+    let mut bins = BTreeMap::new();
+    for point in points {
+        *bins
+            .entry(HexCell::at(point.coord, resolution))
+            .or_insert(0.0) += f64::from(point.weight);
+    }
+    bins
+}
+
+/// Turns binned values into polygon features, one per cell, for fill layers
+/// and choropleth styling
+///
+/// Each feature carries its cell id as a hexadecimal `"cell"` property and
+/// its value as `"value"`.
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use wmc_core::{hex::{self, HexCell}, projection::GeoCoord};
+///
+/// let cell = HexCell::at(GeoCoord::new(0.0, 0.0).unwrap(), 4);
+/// let topology = hex::to_topology(&BTreeMap::from([(cell, 7.0)]));
+///
+/// assert_eq!(topology.features[0].property_f64("value"), Some(7.0));
+/// assert!(topology.features[0].geometry.contains(cell.center()));
+/// ```
+#[must_use]
+pub fn to_topology(bins: &BTreeMap<HexCell, f64>) -> WorldTopology {
+    WorldTopology {
+        features: bins
+            .iter()
+            .map(|(cell, &value)| {
+                let mut properties = JsonObject::new();
+                properties.insert(
+                    "cell".to_string(),
+                    JsonValue::from(format!("{:016x}", cell.id())),
+                );
+                properties.insert("value".to_string(), JsonValue::from(value));
+
+                Feature {
+                    geometry: Geometry::Polygon(vec![cell.boundary()]),
+                    properties,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Returns the cell circumradius of `resolution` on the unit equal-area
+/// plane
+fn size(resolution: u8) -> f64 {
+    BASE_SIZE / f64::from(1_u32 << resolution)
+}
+
+/// Projects `coord` onto the unit Behrmann equal-area plane
+fn to_plane(coord: GeoCoord) -> (f64, f64) {
+    (
+        coord.lon.to_radians() * STANDARD_COS,
+        coord.lat.to_radians().sin() / STANDARD_COS,
+    )
+}
+
+/// Inverse of [`to_plane`], wrapping longitudes into `[-180, 180)` and
+/// clamping latitudes to the poles
+fn from_plane(x: f64, y: f64) -> GeoCoord {
+    let lon = (x / STANDARD_COS).to_degrees();
+
+    GeoCoord {
+        lat: (y * STANDARD_COS).clamp(-1.0, 1.0).asin().to_degrees(),
+        lon: (lon + 180.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Rounds fractional axial coordinates to the hexagon containing them
+fn round_axial(q: f64, r: f64) -> (f64, f64) {
+    let s = -q - r;
+    let (rq, rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+    if dq > dr && dq > ds {
+        (-rr - rs, rr)
+    } else if dr > ds {
+        (rq, -rq - rs)
+    } else {
+        (rq, rr)
+    }
+}
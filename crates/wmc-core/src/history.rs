@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use crate::{marker::Marker, projection::GeoCoord};
+
+/// Undoable change to a list of markers
+///
+/// Edits address markers by position, so they only undo correctly on the
+/// list they were made on.
+#[derive(Debug, Clone)]
+pub enum MarkerEdit {
+    /// Inserts each marker at its position, in ascending order
+    Insert(Vec<(usize, Marker)>),
+    /// Removes the markers at the positions, in ascending order, keeping
+    /// them for undo
+    Remove(Vec<(usize, Marker)>),
+    /// Moves the markers at the positions from the first coordinate to the
+    /// second
+    Move(Vec<(usize, GeoCoord, GeoCoord)>),
+}
+
+impl MarkerEdit {
+    /// Returns the edit removing the markers of `markers` that match
+    /// `remove`
+    pub fn remove_where(markers: &[Marker], mut remove: impl FnMut(&Marker) -> bool) -> Self {
+        Self::Remove(
+            markers
+                .iter()
+                .enumerate()
+                .filter(|(_, marker)| remove(marker))
+                .map(|(position, marker)| (position, marker.clone()))
+                .collect(),
+        )
+    }
+
+    /// Returns true if the edit changes nothing
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        match self {
+            Self::Insert(markers) | Self::Remove(markers) => markers.is_empty(),
+            Self::Move(moves) => moves.is_empty(),
+        }
+    }
+
+    /// Applies the edit to `markers`
+    ///
+    /// Positions past the end insert at the end, and are skipped by
+    /// removals and moves.
+    pub fn apply(&self, markers: &mut Vec<Marker>) {
+        match self {
+            Self::Insert(inserted) => {
+                for (position, marker) in inserted {
+                    markers.insert((*position).min(markers.len()), marker.clone());
+                }
+            },
+            Self::Remove(removed) => {
+                // From the back, so earlier positions stay valid
+                for &(position, _) in removed.iter().rev() {
+                    if position < markers.len() {
+                        markers.remove(position);
+                    }
+                }
+            },
+            Self::Move(moves) => {
+                for &(position, _, to) in moves {
+                    if let Some(marker) = markers.get_mut(position) {
+                        marker.coord = to;
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns the edit undoing this one
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        match self {
+            Self::Insert(markers) => Self::Remove(markers.clone()),
+            Self::Remove(markers) => Self::Insert(markers.clone()),
+            Self::Move(moves) => Self::Move(
+                moves
+                    .iter()
+                    .map(|&(position, from, to)| (position, to, from))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Undo and redo stacks of [`MarkerEdit`]s, for editor-style maps
+///
+/// Applying an edit drops the redo stack, and only the last `limit` edits
+/// can be undone.
+///
+/// ```
+/// use wmc_core::{
+///     history::{EditHistory, MarkerEdit},
+///     marker::{Marker, MarkerId},
+///     projection::GeoCoord,
+/// };
+///
+/// let marker = |id| Marker::new(MarkerId::Number(id), 0.0, 0.0).unwrap();
+/// let mut markers = vec![marker(1)];
+/// let mut history = EditHistory::new(10);
+///
+/// history.apply(MarkerEdit::Insert(vec![(1, marker(2))]), &mut markers);
+/// let to = GeoCoord::new(10.0, 20.0).unwrap();
+/// history.apply(MarkerEdit::Move(vec![(0, markers[0].coord, to)]), &mut markers);
+/// assert_eq!((markers.len(), markers[0].coord), (2, to));
+///
+/// assert!(history.undo(&mut markers));
+/// assert!(history.undo(&mut markers));
+/// assert!(!history.undo(&mut markers));
+/// assert_eq!((markers.len(), markers[0].coord.lat), (1, 0.0));
+///
+/// assert!(history.redo(&mut markers));
+/// assert_eq!(markers.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    done: VecDeque<MarkerEdit>,
+    undone: Vec<MarkerEdit>,
+    limit: usize,
+}
+
+impl Default for EditHistory {
+    /// Keeps the last 100 edits
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl EditHistory {
+    /// Creates an empty history keeping the last `limit` edits
+    #[must_use]
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Returns true if there is an edit to undo
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    /// Returns true if there is an undone edit to redo
+    #[must_use]
+    pub const fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Applies `edit` to `markers` and records it, unless it changes nothing
+    pub fn apply(&mut self, edit: MarkerEdit, markers: &mut Vec<Marker>) {
+        if edit.is_empty() {
+            return;
+        }
+        edit.apply(markers);
+        self.undone.clear();
+        self.done.push_back(edit);
+        while self.done.len() > self.limit {
+            self.done.pop_front();
+        }
+    }
+
+    /// Undoes the last edit on `markers`, returning false if there is none
+    pub fn undo(&mut self, markers: &mut Vec<Marker>) -> bool {
+        let Some(edit) = self.done.pop_back() else {
+            return false;
+        };
+        edit.inverse().apply(markers);
+        self.undone.push(edit);
+        true
+    }
+
+    /// Redoes the last undone edit on `markers`, returning false if there
+    /// is none
+    pub fn redo(&mut self, markers: &mut Vec<Marker>) -> bool {
+        let Some(edit) = self.undone.pop() else {
+            return false;
+        };
+        edit.apply(markers);
+        self.done.push_back(edit);
+        true
+    }
+
+    /// Forgets every edit, e.g. after the markers were replaced
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
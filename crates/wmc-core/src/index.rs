@@ -0,0 +1,384 @@
+/// Axis-aligned box as `[min_x, min_y, max_x, max_y]`
+///
+/// Boxes may be geographic, as `[west, south, east, north]` in degrees, or
+/// projected in pixels; one index must hold a single kind. Boxes are
+/// planar, so a [`GeoBounds`](crate::projection::GeoBounds) crossing the
+/// antimeridian is inserted and queried as its two halves, see
+/// [`GeoBounds::boxes`](crate::projection::GeoBounds::boxes).
+pub type Bounds = [f64; 4];
+
+/// Most entries a node holds before it's split
+const MAX_ENTRIES: usize = 8;
+
+/// Fewest entries a node keeps after a removal, or its entries are
+/// reinserted
+const MIN_ENTRIES: usize = 3;
+
+/// R-tree of items by their bounding boxes
+///
+/// Finding the items whose boxes intersect a query box takes logarithmic
+/// time in the number of items, instead of testing every one. Nodes are
+/// split along the two entries that would waste the most area together.
+///
+/// ```
+/// use wmc_core::index::SpatialIndex;
+///
+/// let mut index = SpatialIndex::new();
+/// index.insert([0.0, 0.0, 10.0, 10.0], "square");
+/// index.insert([20.0, 20.0, 20.0, 20.0], "point");
+///
+/// let hits: Vec<_> = index.query([5.0, 5.0, 25.0, 6.0]).collect();
+/// assert_eq!(hits, [&"square"]);
+///
+/// assert!(index.remove([0.0, 0.0, 10.0, 10.0], &"square"));
+/// assert_eq!(index.query([5.0, 5.0, 25.0, 6.0]).count(), 0);
+/// assert_eq!(index.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Node<T> {
+    Leaf(Vec<(Bounds, T)>),
+    Branch(Vec<(Bounds, Self)>),
+}
+
+impl<T> SpatialIndex<T> {
+    /// Creates an empty index
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            root: Node::Leaf(Vec::new()),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of items in the index
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the index holds no items
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds `item` covering `bounds`
+    pub fn insert(&mut self, bounds: Bounds, item: T) {
+        if let Some(sibling) = self.root.insert(bounds, item) {
+            let root = std::mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            self.root = Node::Branch(vec![(root.bounds(), root), (sibling.bounds(), sibling)]);
+        }
+        self.len += 1;
+    }
+
+    /// Returns the items whose bounds intersect `bounds`, edges included,
+    /// in no particular order
+    #[must_use]
+    pub fn query(&self, bounds: Bounds) -> Query<'_, T> {
+        Query {
+            bounds,
+            nodes: vec![&self.root],
+            entries: [].iter(),
+        }
+    }
+
+    /// Calls `visit` with the items whose bounds intersect `bounds`, as
+    /// [`SpatialIndex::query`] returns them but without allocating
+    pub fn query_each(&self, bounds: Bounds, mut visit: impl FnMut(&T)) {
+        self.root.query_each(bounds, &mut visit);
+    }
+}
+
+impl<T: PartialEq> SpatialIndex<T> {
+    /// Removes one `item` inserted with exactly `bounds`
+    ///
+    /// Returns false if there's no such item.
+    pub fn remove(&mut self, bounds: Bounds, item: &T) -> bool {
+        let mut orphans = Vec::new();
+        if !self.root.remove(bounds, item, &mut orphans) {
+            return false;
+        }
+
+        // A root left with a single child is replaced by it
+        loop {
+            match &mut self.root {
+                Node::Branch(children) if children.len() <= 1 => {
+                    self.root = children
+                        .pop()
+                        .map_or(Node::Leaf(Vec::new()), |(_, child)| child);
+                },
+                _ => break,
+            }
+        }
+
+        self.len -= 1 + orphans.len();
+        for (bounds, item) in orphans {
+            self.insert(bounds, item);
+        }
+        true
+    }
+}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(Bounds, T)> for SpatialIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (Bounds, T)>>(items: I) -> Self {
+        let mut index = Self::new();
+        for (bounds, item) in items {
+            index.insert(bounds, item);
+        }
+        index
+    }
+}
+
+/// Iterator over the items of a [`SpatialIndex`] intersecting a box, see
+/// [`SpatialIndex::query`]
+#[derive(Debug)]
+pub struct Query<'a, T> {
+    bounds: Bounds,
+    nodes: Vec<&'a Node<T>>,
+    entries: std::slice::Iter<'a, (Bounds, T)>,
+}
+
+impl<'a, T> Iterator for Query<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let bounds = self.bounds;
+            if let Some((_, item)) = self.entries.find(|(entry, _)| intersects(*entry, bounds)) {
+                return Some(item);
+            }
+
+            match self.nodes.pop()? {
+                Node::Leaf(entries) => self.entries = entries.iter(),
+                Node::Branch(children) => self.nodes.extend(
+                    children
+                        .iter()
+                        .filter(|(child, _)| intersects(*child, bounds))
+                        .map(|(_, node)| node),
+                ),
+            }
+        }
+    }
+}
+
+impl<T> Node<T> {
+    /// Returns the number of entries or children of the node
+    const fn len(&self) -> usize {
+        match self {
+            Self::Leaf(entries) => entries.len(),
+            Self::Branch(children) => children.len(),
+        }
+    }
+
+    /// Returns the box covering every entry of the node
+    fn bounds(&self) -> Bounds {
+        match self {
+            Self::Leaf(entries) => cover(entries),
+            Self::Branch(children) => cover(children),
+        }
+    }
+
+    /// Calls `visit` with the items below the node intersecting `bounds`
+    fn query_each(&self, bounds: Bounds, visit: &mut impl FnMut(&T)) {
+        match self {
+            Self::Leaf(entries) => entries
+                .iter()
+                .filter(|(entry, _)| intersects(*entry, bounds))
+                .for_each(|(_, item)| visit(item)),
+            Self::Branch(children) => {
+                for (_, child) in children
+                    .iter()
+                    .filter(|(child, _)| intersects(*child, bounds))
+                {
+                    child.query_each(bounds, visit);
+                }
+            },
+        }
+    }
+
+    /// Adds `item` below the node, returning the new sibling if the node
+    /// overflowed and was split
+    fn insert(&mut self, bounds: Bounds, item: T) -> Option<Self> {
+        match self {
+            Self::Leaf(entries) => {
+                entries.push((bounds, item));
+                (entries.len() > MAX_ENTRIES).then(|| Self::Leaf(split(entries)))
+            },
+            Self::Branch(children) => {
+                let Some((child_bounds, child)) = choose_child(children, bounds) else {
+                    children.push((bounds, Self::Leaf(vec![(bounds, item)])));
+                    return None;
+                };
+                match child.insert(bounds, item) {
+                    Some(sibling) => {
+                        *child_bounds = child.bounds();
+                        children.push((sibling.bounds(), sibling));
+                    },
+                    None => *child_bounds = union(*child_bounds, bounds),
+                }
+                (children.len() > MAX_ENTRIES).then(|| Self::Branch(split(children)))
+            },
+        }
+    }
+
+    /// Moves every item below the node into `items`
+    fn drain_into(self, items: &mut Vec<(Bounds, T)>) {
+        match self {
+            Self::Leaf(entries) => items.extend(entries),
+            Self::Branch(children) => {
+                for (_, child) in children {
+                    child.drain_into(items);
+                }
+            },
+        }
+    }
+}
+
+impl<T: PartialEq> Node<T> {
+    /// Removes `item` with `bounds` below the node, moving the items of
+    /// children left underfull into `orphans`
+    fn remove(&mut self, bounds: Bounds, item: &T, orphans: &mut Vec<(Bounds, T)>) -> bool {
+        match self {
+            Self::Leaf(entries) => {
+                let found = entries
+                    .iter()
+                    .position(|(entry, other)| same(*entry, bounds) && other == item);
+                found.map(|index| entries.swap_remove(index)).is_some()
+            },
+            Self::Branch(children) => {
+                let found = children.iter_mut().position(|(child_bounds, child)| {
+                    contains(*child_bounds, bounds) && child.remove(bounds, item, orphans)
+                });
+                let Some(index) = found else {
+                    return false;
+                };
+
+                if children[index].1.len() < MIN_ENTRIES {
+                    children.swap_remove(index).1.drain_into(orphans);
+                } else {
+                    children[index].0 = children[index].1.bounds();
+                }
+                true
+            },
+        }
+    }
+}
+
+/// Returns the child of a branch whose box grows least to take `bounds`,
+/// the smaller one on ties
+fn choose_child<T>(
+    children: &mut [(Bounds, Node<T>)],
+    bounds: Bounds,
+) -> Option<(&mut Bounds, &mut Node<T>)> {
+    children
+        .iter_mut()
+        .min_by(|(a, _), (b, _)| {
+            let growth = |cover| area(union(cover, bounds)) - area(cover);
+            growth(*a)
+                .total_cmp(&growth(*b))
+                .then_with(|| area(*a).total_cmp(&area(*b)))
+        })
+        .map(|(cover, child)| (cover, child))
+}
+
+/// Splits an overfull node's entries in two, leaving one group in
+/// `entries` and returning the other
+///
+/// The seeds are the two entries wasting the most area in a common box;
+/// the rest join the group they enlarge least, while both groups can still
+/// reach [`MIN_ENTRIES`].
+fn split<E>(entries: &mut Vec<(Bounds, E)>) -> Vec<(Bounds, E)> {
+    let mut seeds = (0, 1);
+    let mut worst = f64::NEG_INFINITY;
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            let (a, b) = (entries[i].0, entries[j].0);
+            let waste = area(union(a, b)) - area(a) - area(b);
+            if waste > worst {
+                worst = waste;
+                seeds = (i, j);
+            }
+        }
+    }
+
+    let mut pending = std::mem::take(entries);
+    // The later seed goes first so the earlier one keeps its index
+    let second = pending.swap_remove(seeds.1);
+    let first = pending.swap_remove(seeds.0);
+    let mut covers = [first.0, second.0];
+    let mut groups = [vec![first], vec![second]];
+
+    while let Some(entry) = pending.pop() {
+        let remaining = pending.len() + 1;
+        let target = if groups[0].len() + remaining <= MIN_ENTRIES {
+            0
+        } else if groups[1].len() + remaining <= MIN_ENTRIES {
+            1
+        } else {
+            let growth = |cover| area(union(cover, entry.0)) - area(cover);
+            let prefer_first = growth(covers[0])
+                .total_cmp(&growth(covers[1]))
+                .then_with(|| area(covers[0]).total_cmp(&area(covers[1])))
+                .then_with(|| groups[0].len().cmp(&groups[1].len()))
+                .is_le();
+            usize::from(!prefer_first)
+        };
+        covers[target] = union(covers[target], entry.0);
+        groups[target].push(entry);
+    }
+
+    let [kept, moved] = groups;
+    *entries = kept;
+    moved
+}
+
+/// Returns the box covering `entries`, inverted if there are none
+fn cover<E>(entries: &[(Bounds, E)]) -> Bounds {
+    entries.iter().fold(
+        [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ],
+        |cover, (bounds, _)| union(cover, *bounds),
+    )
+}
+
+const fn union(a: Bounds, b: Bounds) -> Bounds {
+    [
+        a[0].min(b[0]),
+        a[1].min(b[1]),
+        a[2].max(b[2]),
+        a[3].max(b[3]),
+    ]
+}
+
+fn area(bounds: Bounds) -> f64 {
+    (bounds[2] - bounds[0]).max(0.0) * (bounds[3] - bounds[1]).max(0.0)
+}
+
+fn intersects(a: Bounds, b: Bounds) -> bool {
+    a[0] <= b[2] && b[0] <= a[2] && a[1] <= b[3] && b[1] <= a[3]
+}
+
+fn contains(outer: Bounds, inner: Bounds) -> bool {
+    outer[0] <= inner[0] && outer[1] <= inner[1] && outer[2] >= inner[2] && outer[3] >= inner[3]
+}
+
+/// Returns true if `a` and `b` are the same box, bit for bit
+fn same(a: Bounds, b: Bounds) -> bool {
+    a.iter().zip(&b).all(|(a, b)| a.to_bits() == b.to_bits())
+}
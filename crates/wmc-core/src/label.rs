@@ -0,0 +1,215 @@
+use crate::{
+    projection::{GeoCoord, MercatorProjection, ProjectedCoord, Projection},
+    topology::{self, Feature, GRATICULE_MAX_LAT},
+};
+
+/// Width in pixels of the whole Web Mercator world at zoom 0
+pub const WORLD_SIZE: f64 = 256.0;
+
+/// Degrees to which [`FeatureLabel`] anchors are placed
+const ANCHOR_PRECISION: f64 = 0.01;
+
+/// Returns the fractional zoom level of a view showing the whole Web
+/// Mercator world `width` pixels wide
+///
+/// Zoom 0 is [`WORLD_SIZE`] pixels, and each level doubles it.
+///
+/// ```
+/// use wmc_core::label::zoom_level;
+///
+/// assert_eq!(zoom_level(256.0), 0.0);
+/// assert_eq!(zoom_level(1024.0), 2.0);
+/// ```
+#[must_use]
+pub fn zoom_level(width: f64) -> f64 {
+    (width / WORLD_SIZE).log2()
+}
+
+/// Text label of a polygon feature
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureLabel {
+    /// Label text
+    pub text: String,
+    /// Pole of inaccessibility of the feature's largest polygon
+    pub anchor: GeoCoord,
+    /// Square root of the largest polygon's area at zoom 0, in pixels
+    pub size: f64,
+    /// Lowest zoom level at which the label is shown
+    pub min_zoom: f64,
+}
+
+impl FeatureLabel {
+    /// Builds the label of `feature` from its string property `text_key`
+    ///
+    /// The label is anchored inside the largest polygon in Web Mercator, at
+    /// its pole of inaccessibility, so multi-part countries are labeled on
+    /// their main landmass and concave ones within their borders. It is shown from the zoom in the numeric property
+    /// `min_zoom_key`, like `MIN_LABEL` in Natural Earth data, or otherwise
+    /// from the zoom at which the polygon's [`FeatureLabel::size`] reaches
+    /// `min_size` pixels.
+    ///
+    /// Returns `None` for features without the text property or without
+    /// area.
+    ///
+    /// ```
+    /// use wmc_core::{label::FeatureLabel, topology::WorldTopology};
+    ///
+    /// let topology = WorldTopology::from_geojson(r#"{"type":"FeatureCollection","features":[
+    ///     {"type":"Feature","properties":{"NAME":"Square"},"geometry":{"type":"Polygon",
+    ///      "coordinates":[[[-10,-10],[10,-10],[10,10],[-10,10],[-10,-10]]]}}
+    /// ]}"#).unwrap();
+    ///
+    /// let label = FeatureLabel::from_feature(&topology.features[0], "NAME", None, 32.0).unwrap();
+    /// assert_eq!(label.text, "Square");
+    /// assert!(label.anchor.lat.abs() < 1e-9 && label.anchor.lon.abs() < 1e-9);
+    /// // About 14 pixels wide at zoom 0, so 32 pixels are reached past zoom 1
+    /// assert!(label.min_zoom > 1.0 && label.min_zoom < 1.5);
+    /// ```
+    #[must_use]
+    pub fn from_feature(
+        feature: &Feature,
+        text_key: &str,
+        min_zoom_key: Option<&str>,
+        min_size: f64,
+    ) -> Option<Self> {
+        let text = feature.property_str(text_key)?.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let projection = MercatorProjection::new(WORLD_SIZE, WORLD_SIZE);
+        let (rings, area) = feature
+            .geometry
+            .polygons()
+            .into_iter()
+            .filter_map(|rings| {
+                let outer: Vec<ProjectedCoord> = rings
+                    .first()?
+                    .iter()
+                    .map(|&coord| projection.project(coord))
+                    .collect();
+                Some((rings, ring_area(&outer)?))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        let size = area.sqrt();
+        let min_zoom = min_zoom_key
+            .and_then(|key| feature.property_f64(key))
+            .filter(|zoom| zoom.is_finite())
+            .unwrap_or_else(|| (min_size / size).log2());
+
+        Some(Self {
+            text: text.to_string(),
+            anchor: topology::polygon_pole(rings, ANCHOR_PRECISION)?,
+            size,
+            min_zoom,
+        })
+    }
+
+    /// Returns true if the label is shown at `zoom`
+    #[must_use]
+    pub fn is_visible(&self, zoom: f64) -> bool {
+        zoom >= self.min_zoom
+    }
+}
+
+/// Kind of graticule line a [`GridLabel`] names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridAxis {
+    /// Line of constant longitude
+    Meridian,
+    /// Line of constant latitude
+    Parallel,
+}
+
+/// Degree label of one graticule line
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridLabel {
+    /// Kind of line
+    pub axis: GridAxis,
+    /// Longitude of a meridian or latitude of a parallel, in degrees
+    pub degrees: f64,
+    /// Formatted degrees, e.g. `30°N`
+    pub text: String,
+}
+
+impl GridLabel {
+    /// Returns the labels of the lines [`crate::topology::WorldTopology::graticule`]
+    /// builds for `step`: meridians west to east, then parallels south to
+    /// north
+    ///
+    /// ```
+    /// use wmc_core::label::{GridAxis, GridLabel};
+    ///
+    /// let labels = GridLabel::graticule(30.0);
+    /// assert_eq!(labels.len(), 18);
+    /// assert_eq!(labels[0].text, "180°");
+    /// assert_eq!(labels[8].text, "60°E");
+    /// assert_eq!(labels[13].axis, GridAxis::Parallel);
+    /// assert_eq!(labels[13].text, "60°S");
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn graticule(step: f64) -> Vec<Self> {
+        if !step.is_finite() || step <= 0.0 {
+            return Vec::new();
+        }
+
+        let steps = |limit: i32| (f64::from(limit) / step).floor() as i32;
+        let label = |axis, i: i32| {
+            let degrees = f64::from(i) * step;
+            Self {
+                axis,
+                degrees,
+                text: format_degrees(degrees, axis),
+            }
+        };
+
+        (-steps(180)..=steps(180))
+            .map(|i| label(GridAxis::Meridian, i))
+            .chain(
+                (-steps(GRATICULE_MAX_LAT)..=steps(GRATICULE_MAX_LAT))
+                    .map(|i| label(GridAxis::Parallel, i)),
+            )
+            .collect()
+    }
+}
+
+/// Formats a longitude or latitude as whole or fractional degrees with a
+/// hemisphere letter
+///
+/// The equator, the prime meridian and the antimeridian have none.
+///
+/// ```
+/// use wmc_core::label::{GridAxis, format_degrees};
+///
+/// assert_eq!(format_degrees(30.0, GridAxis::Parallel), "30°N");
+/// assert_eq!(format_degrees(-7.5, GridAxis::Meridian), "7.5°W");
+/// assert_eq!(format_degrees(0.0, GridAxis::Parallel), "0°");
+/// assert_eq!(format_degrees(-180.0, GridAxis::Meridian), "180°");
+/// ```
+#[must_use]
+pub fn format_degrees(degrees: f64, axis: GridAxis) -> String {
+    let magnitude = degrees.abs();
+    let hemisphere = match axis {
+        _ if magnitude == 0.0 => "",
+        GridAxis::Meridian if magnitude >= 180.0 => "",
+        GridAxis::Meridian if degrees > 0.0 => "E",
+        GridAxis::Meridian => "W",
+        GridAxis::Parallel if degrees > 0.0 => "N",
+        GridAxis::Parallel => "S",
+    };
+
+    format!("{magnitude}°{hemisphere}")
+}
+
+/// Returns the unsigned area of a closed ring, or `None` if it has no area
+fn ring_area(ring: &[ProjectedCoord]) -> Option<f64> {
+    let twice_area: f64 = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.x.mul_add(b.y, -(b.x * a.y)))
+        .sum();
+
+    twice_area.is_normal().then(|| twice_area.abs() / 2.0)
+}
@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color_scale::ColorScale,
+    error::CoreError,
+    heatmap::Gradient,
+    marker::Color,
+    projection::ProjectedCoord,
+    svg::SvgDocument,
+    theme::{LayerStyle, LineStyle, Theme},
+};
+
+/// Space around the legend content and between its columns, in pixels
+const PADDING: f64 = 10.0;
+
+/// Width of the symbol column and smallest row height, in pixels
+const SYMBOL_SIZE: f64 = 16.0;
+
+/// Entry label font size in pixels
+const FONT_SIZE: f32 = 12.0;
+
+/// Title font size in pixels
+const TITLE_SIZE: f32 = 13.0;
+
+/// Approximate advance of one character as a fraction of the font size,
+/// used to size the legend without text measurement
+const CHAR_WIDTH: f64 = 0.6;
+
+/// Swatch drawn next to a legend entry
+///
+/// Deserialized from e.g. `{"type": "circle", "radius": 6}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LegendSymbol {
+    /// Filled circle, e.g. for a marker color or size
+    Circle {
+        /// Radius in pixels
+        radius: f32,
+    },
+    /// Filled square, e.g. for a fill color or scale class
+    Swatch,
+    /// Line sample, e.g. for coastlines or rivers
+    Line {
+        /// Line width in pixels
+        width: f32,
+        /// Dash pattern in pixels
+        #[serde(default)]
+        style: LineStyle,
+    },
+}
+
+impl LegendSymbol {
+    /// Returns the height the symbol needs, in pixels
+    fn extent(self) -> f64 {
+        match self {
+            Self::Circle { radius } => 2.0 * f64::from(radius),
+            Self::Swatch => SYMBOL_SIZE * 0.75,
+            Self::Line { width, .. } => f64::from(width),
+        }
+    }
+
+    /// Returns true if the symbol's sizes can be drawn
+    fn is_valid(self) -> bool {
+        match self {
+            Self::Circle { radius } => radius > 0.0 && radius.is_finite(),
+            Self::Swatch => true,
+            Self::Line { width, style } => width > 0.0 && width.is_finite() && style.is_valid(),
+        }
+    }
+}
+
+/// One row of a [`Legend`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegendEntry {
+    /// Text next to the symbol
+    pub label: String,
+    /// Symbol color
+    pub color: Color,
+    /// Symbol shape
+    pub symbol: LegendSymbol,
+}
+
+/// Key explaining map colors and sizes, e.g. marker categories or the
+/// classes of a choropleth scale
+///
+/// Legends are rendered as standalone SVG markup, so hosts can place them in
+/// the DOM next to the canvas and style or position them freely.
+///
+/// ```
+/// use wmc_core::{legend::Legend, theme::Theme};
+///
+/// let svg = Legend::from_theme(&Theme::dark_minimal()).to_svg(Theme::dark_minimal().background);
+/// assert!(svg.contains("Markers"));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Legend {
+    /// Heading above the entries
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Rows from top to bottom
+    #[serde(default)]
+    pub entries: Vec<LegendEntry>,
+}
+
+impl Legend {
+    /// Describes every line, fill and marker style of `theme`
+    ///
+    /// Entries come in the order coastlines, rivers, lakes, graticule,
+    /// markers.
+    #[must_use]
+    pub fn from_theme(theme: &Theme) -> Self {
+        let line = |label: &str, style: &LayerStyle| LegendEntry {
+            label: label.to_string(),
+            color: style.effective_color(),
+            symbol: LegendSymbol::Line {
+                width: style.width,
+                style: style.style,
+            },
+        };
+
+        Self {
+            title: None,
+            entries: vec![
+                line("Coastlines", &theme.coastlines),
+                line("Rivers", &theme.rivers),
+                LegendEntry {
+                    label: "Lakes".to_string(),
+                    color: theme.lakes.effective_color(),
+                    symbol: LegendSymbol::Swatch,
+                },
+                line("Graticule", &theme.graticule),
+                LegendEntry {
+                    label: "Markers".to_string(),
+                    color: theme.markers.effective_color(),
+                    symbol: LegendSymbol::Circle { radius: 5.0 },
+                },
+            ],
+        }
+    }
+
+    /// Describes a classed scale: one swatch per interval between
+    /// consecutive `breaks`, colored evenly along `gradient` from its first
+    /// to its last stop
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] if there are fewer than
+    /// two breaks, they are not finite and strictly ascending, or the
+    /// gradient is invalid
+    ///
+    /// ```
+    /// use wmc_core::{heatmap::Gradient, legend::Legend};
+    ///
+    /// let legend = Legend::classes(Some("Population"), &[0.0, 10.0, 100.0], &Gradient::default())
+    ///     .unwrap();
+    /// assert_eq!(legend.entries.len(), 2);
+    /// assert_eq!(legend.entries[1].label, "10 – 100");
+    /// ```
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn classes(
+        title: Option<&str>,
+        breaks: &[f64],
+        gradient: &Gradient,
+    ) -> Result<Self, CoreError> {
+        gradient.validate()?;
+        let last = (breaks.len().saturating_sub(2)).max(1) as f64;
+        Self::classed(title, breaks, |i, _| {
+            gradient.sample((i as f64 / last) as f32)
+        })
+    }
+
+    /// Describes a classed scale: one swatch per interval between
+    /// consecutive `breaks`, colored by `scale` at the interval's middle
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] if there are fewer than
+    /// two breaks, they are not finite and strictly ascending, or the scale
+    /// is invalid
+    ///
+    /// ```
+    /// use wmc_core::{
+    ///     color_scale::{ColorScale, Palette},
+    ///     legend::Legend,
+    /// };
+    ///
+    /// let scale = ColorScale::new(Palette::RdBu, [-10.0, 10.0]).with_midpoint(0.0);
+    /// let legend = Legend::scale_classes(None, &[-10.0, -5.0, 5.0, 10.0], &scale).unwrap();
+    /// assert_eq!(legend.entries[1].color.g, Palette::RdBu.sample(0.5).g);
+    /// ```
+    pub fn scale_classes(
+        title: Option<&str>,
+        breaks: &[f64],
+        scale: &ColorScale,
+    ) -> Result<Self, CoreError> {
+        scale.validate()?;
+        Self::classed(title, breaks, |_, [low, high]| {
+            scale.color(low.midpoint(high))
+        })
+    }
+
+    /// Builds one swatch per interval between `breaks`, colored by
+    /// `color(index, [low, high])`
+    fn classed(
+        title: Option<&str>,
+        breaks: &[f64],
+        color: impl Fn(usize, [f64; 2]) -> Color,
+    ) -> Result<Self, CoreError> {
+        let ascending = breaks.windows(2).all(|pair| pair[0] < pair[1]);
+        if breaks.len() < 2 || !ascending || !breaks.iter().all(|value| value.is_finite()) {
+            return Err(CoreError::ThemeValidationFailed {
+                reason: format!("invalid scale breaks {breaks:?}"),
+            });
+        }
+
+        let entries = breaks
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| LegendEntry {
+                label: format!("{} – {}", pair[0], pair[1]),
+                color: color(i, [pair[0], pair[1]]),
+                symbol: LegendSymbol::Swatch,
+            })
+            .collect();
+
+        Ok(Self {
+            title: title.map(str::to_string),
+            entries,
+        })
+    }
+
+    /// Validates that every symbol can be drawn and colors are in range
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] describing the first
+    /// invalid entry
+    pub fn validate(&self) -> Result<(), CoreError> {
+        for entry in &self.entries {
+            let color = entry.color;
+            let in_range = [color.r, color.g, color.b, color.a]
+                .iter()
+                .all(|value| (0.0..=1.0).contains(value));
+            if !in_range || !entry.symbol.is_valid() {
+                return Err(CoreError::ThemeValidationFailed {
+                    reason: format!("invalid legend entry {entry:?}"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the legend as an SVG document on `background`
+    ///
+    /// Text is near-black or near-white, whichever contrasts more with the
+    /// background. The document is sized to fit its content, estimating
+    /// text widths from the character count.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn to_svg(&self, background: Color) -> String {
+        let text_color = background.contrasting();
+        let text_width =
+            |text: &str, size: f32| text.chars().count() as f64 * CHAR_WIDTH * f64::from(size);
+
+        let symbol_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.symbol.extent())
+            .fold(SYMBOL_SIZE, f64::max);
+        let row_height = |entry: &LegendEntry| entry.symbol.extent().max(SYMBOL_SIZE) + 4.0;
+        let title_height = if self.title.is_some() {
+            f64::from(TITLE_SIZE) + 8.0
+        } else {
+            0.0
+        };
+
+        let labels_width = self
+            .entries
+            .iter()
+            .map(|entry| text_width(&entry.label, FONT_SIZE))
+            .fold(0.0, f64::max);
+        let title_width = self
+            .title
+            .as_deref()
+            .map_or(0.0, |title| text_width(title, TITLE_SIZE));
+        let content_width = (symbol_width + PADDING + labels_width).max(title_width);
+        let content_height = self.entries.iter().map(row_height).sum::<f64>() + title_height;
+        let width = PADDING.mul_add(2.0, content_width);
+        let height = PADDING.mul_add(2.0, content_height);
+
+        let mut svg = SvgDocument::new(width.ceil() as u32, height.ceil() as u32, background);
+        if let Some(title) = &self.title {
+            svg.text(
+                ProjectedCoord {
+                    x: PADDING,
+                    y: PADDING + f64::from(TITLE_SIZE),
+                },
+                title,
+                TITLE_SIZE,
+                text_color,
+            );
+        }
+
+        let mut top = PADDING + title_height;
+        for entry in &self.entries {
+            let row = row_height(entry);
+            let center = ProjectedCoord {
+                x: PADDING + symbol_width / 2.0,
+                y: top + row / 2.0,
+            };
+
+            match entry.symbol {
+                LegendSymbol::Circle { radius } => svg.circle(center, radius, entry.color),
+                LegendSymbol::Swatch => {
+                    let side = LegendSymbol::Swatch.extent();
+                    svg.rect(
+                        ProjectedCoord {
+                            x: center.x - side / 2.0,
+                            y: center.y - side / 2.0,
+                        },
+                        side,
+                        side,
+                        entry.color,
+                    );
+                },
+                LegendSymbol::Line { width, style } => svg.polyline(
+                    &[
+                        ProjectedCoord {
+                            x: PADDING,
+                            y: center.y,
+                        },
+                        ProjectedCoord {
+                            x: PADDING + symbol_width,
+                            y: center.y,
+                        },
+                    ],
+                    entry.color,
+                    width,
+                    style,
+                ),
+            }
+
+            svg.text(
+                ProjectedCoord {
+                    x: 2.0f64.mul_add(PADDING, symbol_width),
+                    y: f64::from(FONT_SIZE).mul_add(0.35, center.y),
+                },
+                &entry.label,
+                FONT_SIZE,
+                text_color,
+            );
+            top += row;
+        }
+
+        svg.finish()
+    }
+}
@@ -3,17 +3,51 @@
 //! This crate provides core data structures and utilities for rendering
 //! interactive world maps with markers.
 
+/// Great-circle connections
+pub mod arc;
+/// Screen-space label collision avoidance
+pub mod collision;
+/// Palettes and value-to-color mapping
+pub mod color_scale;
 /// Error types
 pub mod error;
+/// Great-circle math
+pub mod geodesy;
+/// Weighted points and color ramps for heatmaps
+pub mod heatmap;
+/// Hexagonal grid cells and hexbin aggregation
+pub mod hex;
+/// Undo and redo of marker edits
+pub mod history;
+/// R-tree spatial index
+pub mod index;
+/// Label placement for map features
+pub mod label;
+/// Map legends rendered as SVG
+pub mod legend;
 /// Marker types and utilities
 pub mod marker;
 /// GPU marker buffer management
 pub mod marker_buffer;
+/// CSS named color keywords
+mod named_colors;
 /// Map projection implementations
 pub mod projection;
+/// Ground distance scale bars
+pub mod scale_bar;
+/// Signed distance fields for glyph rendering
+pub mod sdf;
+/// Line simplification for levels of detail
+pub mod simplify;
+/// SVG export of projected geometry
+pub mod svg;
 /// Visual theme configuration
 pub mod theme;
+/// Web Mercator tile addressing
+pub mod tile;
 /// World topology data structures
 pub mod topology;
+/// Polygon triangulation
+pub mod triangulate;
 
 pub use error::CoreError;
@@ -1,6 +1,8 @@
+use std::{f32::consts::PI, fmt};
+
 use serde::{Deserialize, Serialize};
 
-use crate::{error::CoreError, projection::GeoCoord};
+use crate::{error::CoreError, named_colors, projection::GeoCoord};
 
 /// Unique identifier for a marker
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -26,6 +28,23 @@ impl MarkerId {
     }
 }
 
+/// Formats the identifier as plain text, numbers in decimal
+///
+/// ```
+/// use wmc_core::marker::MarkerId;
+///
+/// assert_eq!(MarkerId::Number(42).to_string(), "42");
+/// assert_eq!(MarkerId::String("hq".to_string()).to_string(), "hq");
+/// ```
+impl fmt::Display for MarkerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(s) => f.write_str(s),
+            Self::Number(n) => write!(f, "{n}"),
+        }
+    }
+}
+
 /// A map marker with position, appearance, and metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Marker {
@@ -45,6 +64,13 @@ pub struct Marker {
     /// Optional metadata
     #[serde(default)]
     pub meta: Option<serde_json::Value>,
+    /// Category shown and hidden together with the markers sharing it,
+    /// e.g. `alerts`
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Pulse animation phase in radians, derived from the ID when unset
+    #[serde(default)]
+    pub phase: Option<f32>,
 }
 
 const fn default_intensity() -> f32 {
@@ -73,6 +99,8 @@ impl Marker {
             color: None,
             radius: default_radius(),
             meta: None,
+            group: None,
+            phase: None,
         })
     }
 
@@ -96,28 +124,68 @@ impl Marker {
             });
         }
 
+        if let Some(phase) = self.phase.filter(|phase| !phase.is_finite()) {
+            return Err(CoreError::InvalidMarkerId {
+                id: format!("invalid phase {phase}"),
+            });
+        }
+
         Ok(())
     }
 
-    /// Computes a deterministic animation phase based on marker ID
+    /// Returns the pulse animation phase in radians
+    ///
+    /// Without an explicit phase it's derived from the marker ID with FNV-1a,
+    /// so it stays the same across runs, builds and platforms.
+    ///
+    /// ```
+    /// use wmc_core::marker::{Marker, MarkerId};
+    ///
+    /// let mut marker = Marker::new(MarkerId::Number(7), 0.0, 0.0).unwrap();
+    /// assert!((0.0..std::f32::consts::TAU).contains(&marker.phase()));
+    ///
+    /// marker.phase = Some(1.5);
+    /// assert_eq!(marker.phase(), 1.5);
+    /// ```
     #[must_use]
     #[allow(clippy::cast_precision_loss)]
     pub fn phase(&self) -> f32 {
-        use std::{
-            collections::hash_map::DefaultHasher,
-            hash::{Hash, Hasher},
-        };
+        if let Some(phase) = self.phase {
+            return phase;
+        }
 
-        let mut hasher = DefaultHasher::new();
-        self.id.hash(&mut hasher);
-        let hash = hasher.finish();
+        let number;
+        let bytes = match &self.id {
+            MarkerId::String(id) => id.as_bytes(),
+            MarkerId::Number(id) => {
+                number = id.to_le_bytes();
+                &number
+            },
+        };
+        let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
         let normalized = (hash % 10000) as f32 / 10000.0;
         normalized * std::f32::consts::TAU
     }
 }
 
 /// RGBA color with components in range [0.0, 1.0]
+///
+/// Deserializes from `{"r": 1, "g": 0.4, "b": 0.3, "a": 1}` or any CSS color
+/// string [`Color::from_css`] accepts, e.g. `"tomato"` or
+/// `"hsl(210 60% 40%)"`; serializes as components.
+///
+/// ```
+/// use wmc_core::marker::Color;
+///
+/// let color: Color = serde_json::from_str(r#""rebeccapurple""#).unwrap();
+/// assert_eq!(color.to_hex(), "#663399");
+/// assert!(serde_json::from_str::<Color>(r#""not a color""#).is_err());
+/// assert!(serde_json::from_str::<Color>(r##""#abcéf""##).is_err());
+/// ```
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "ColorValue")]
 pub struct Color {
     /// Red component
     pub r: f32,
@@ -142,41 +210,315 @@ impl Color {
     /// # Errors
     ///
     /// Returns [`CoreError::ThemeValidationFailed`] if the hex string is invalid
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// assert_eq!(Color::from_hex("#ff8000").unwrap().to_hex(), "#ff8000");
+    /// assert!(Color::from_hex("#abcéf").is_err());
+    /// assert!(Color::from_hex("+f+f+f").is_err());
+    /// ```
     pub fn from_hex(hex: &str) -> Result<Self, CoreError> {
         let hex = hex.trim_start_matches('#');
 
-        if hex.len() != 6 && hex.len() != 8 {
+        // Checked up front so the byte slicing below stays on char boundaries
+        if (hex.len() != 6 && hex.len() != 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
             return Err(CoreError::ThemeValidationFailed {
                 reason: format!("invalid hex color: {hex}"),
             });
         }
 
-        let r =
-            u8::from_str_radix(&hex[0..2], 16).map_err(|_| CoreError::ThemeValidationFailed {
-                reason: format!("invalid hex color: {hex}"),
-            })?;
-        let g =
-            u8::from_str_radix(&hex[2..4], 16).map_err(|_| CoreError::ThemeValidationFailed {
-                reason: format!("invalid hex color: {hex}"),
-            })?;
-        let b =
-            u8::from_str_radix(&hex[4..6], 16).map_err(|_| CoreError::ThemeValidationFailed {
-                reason: format!("invalid hex color: {hex}"),
-            })?;
-
-        let a = if hex.len() == 8 {
-            u8::from_str_radix(&hex[6..8], 16).map_err(|_| CoreError::ThemeValidationFailed {
-                reason: format!("invalid hex color: {hex}"),
-            })?
-        } else {
-            255
+        let channel = |at: usize| {
+            u8::from_str_radix(&hex[at..at + 2], 16).map_or(0.0, |value| f32::from(value) / 255.0)
         };
 
         Ok(Self {
-            r: f32::from(r) / 255.0,
-            g: f32::from(g) / 255.0,
-            b: f32::from(b) / 255.0,
-            a: f32::from(a) / 255.0,
+            r: channel(0),
+            g: channel(2),
+            b: channel(4),
+            a: if hex.len() == 8 { channel(6) } else { 1.0 },
         })
     }
+
+    /// Creates a color from a hue in degrees and saturation, lightness and
+    /// alpha in `0.0..=1.0`
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// let color = Color::from_hsl(120.0, 1.0, 0.25, 1.0);
+    /// assert_eq!(color.to_hex(), "#008000");
+    /// ```
+    #[must_use]
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let chroma = (1.0 - 2.0f32.mul_add(lightness, -1.0).abs()) * saturation;
+        Self::from_chroma(hue, chroma, chroma.mul_add(-0.5, lightness), alpha)
+    }
+
+    /// Creates a color from a hue in degrees and saturation, value and alpha
+    /// in `0.0..=1.0`
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// assert_eq!(Color::from_hsv(240.0, 1.0, 1.0, 1.0).to_hex(), "#0000ff");
+    /// ```
+    #[must_use]
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation.clamp(0.0, 1.0);
+        Self::from_chroma(hue, chroma, value - chroma, alpha)
+    }
+
+    /// Places `chroma` on the RGB hue hexagon at `hue` degrees and lifts all
+    /// channels by `offset`
+    fn from_chroma(hue: f32, chroma: f32, offset: f32, alpha: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let second = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match sector {
+            s if s < 1.0 => (chroma, second, 0.0),
+            s if s < 2.0 => (second, chroma, 0.0),
+            s if s < 3.0 => (0.0, chroma, second),
+            s if s < 4.0 => (0.0, second, chroma),
+            s if s < 5.0 => (second, 0.0, chroma),
+            _ => (chroma, 0.0, second),
+        };
+
+        Self::rgba(r + offset, g + offset, b + offset, alpha.clamp(0.0, 1.0))
+    }
+
+    /// Parses a CSS color value: hex (`#rgb`, `#rgba`, `#rrggbb`,
+    /// `#rrggbbaa`), `rgb()`/`rgba()` and `hsl()`/`hsla()` with comma or
+    /// space separated channels, a named color such as `tomato`, or
+    /// `transparent`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] for other values
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// let color = Color::from_css(" rgb(255 128 0 / 50%) ").unwrap();
+    /// assert!((color.g - 128.0 / 255.0).abs() < 1e-6 && (color.a - 0.5).abs() < 1e-6);
+    /// assert_eq!(Color::from_css("#0f0").unwrap().g, 1.0);
+    /// assert_eq!(Color::from_css("hsl(0.5turn, 100%, 25%)").unwrap().to_hex(), "#008080");
+    /// assert_eq!(Color::from_css("Tomato").unwrap().to_hex(), "#ff6347");
+    /// assert!(Color::from_css("color(display-p3 1 0 0)").is_err());
+    /// assert!(Color::from_css("#abcéf").is_err());
+    /// ```
+    pub fn from_css(value: &str) -> Result<Self, CoreError> {
+        let value = value.trim().to_ascii_lowercase();
+        let invalid = || CoreError::ThemeValidationFailed {
+            reason: format!("unsupported CSS color: {value}"),
+        };
+
+        if value == "transparent" {
+            return Ok(Self::rgba(0.0, 0.0, 0.0, 0.0));
+        }
+        if let Some(bytes) = named_colors::lookup(&value) {
+            let [r, g, b] = bytes.map(|channel| f32::from(channel) / 255.0);
+            return Ok(Self::rgba(r, g, b, 1.0));
+        }
+
+        if let Some(hex) = value.strip_prefix('#') {
+            let hex = if hex.len() == 3 || hex.len() == 4 {
+                hex.chars().flat_map(|digit| [digit, digit]).collect()
+            } else {
+                hex.to_string()
+            };
+            return Self::from_hex(&hex);
+        }
+
+        let (function, channels) = value
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once('('))
+            .ok_or_else(invalid)?;
+        let parts: Vec<&str> = channels
+            .split(|ch: char| ch == ',' || ch == '/' || ch.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.len() != 3 && parts.len() != 4 {
+            return Err(invalid());
+        }
+
+        let parse = |part: &str, scale: f32| -> Result<f32, CoreError> {
+            let (number, scale) = part
+                .strip_suffix('%')
+                .map_or((part, scale), |number| (number, 100.0));
+            let number: f32 = number.parse().map_err(|_| invalid())?;
+            Ok((number / scale).clamp(0.0, 1.0))
+        };
+        let alpha = parts.get(3).map_or(Ok(1.0), |alpha| parse(alpha, 1.0))?;
+
+        match function {
+            "rgb" | "rgba" => Ok(Self::rgba(
+                parse(parts[0], 255.0)?,
+                parse(parts[1], 255.0)?,
+                parse(parts[2], 255.0)?,
+                alpha,
+            )),
+            "hsl" | "hsla" => {
+                let hue = parts[0];
+                let (number, degrees) = [
+                    ("deg", 1.0),
+                    ("grad", 0.9),
+                    ("rad", 180.0 / PI),
+                    ("turn", 360.0),
+                ]
+                .into_iter()
+                .find_map(|(unit, degrees)| Some((hue.strip_suffix(unit)?, degrees)))
+                .unwrap_or((hue, 1.0));
+                let hue: f32 = number.parse().map_err(|_| invalid())?;
+                // Saturation and lightness are percentages with or without
+                // the sign
+                Ok(Self::from_hsl(
+                    hue * degrees,
+                    parse(parts[1].trim_end_matches('%'), 100.0)?,
+                    parse(parts[2].trim_end_matches('%'), 100.0)?,
+                    alpha,
+                ))
+            },
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Returns the channels as bytes, clamped to `0..=255`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn to_bytes(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// Formats the color as `#rrggbb`, or `#rrggbbaa` if it's translucent
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// assert_eq!(Color::rgba(1.0, 0.5, 0.0, 1.0).to_hex(), "#ff8000");
+    /// assert_eq!(Color::rgba(0.0, 0.0, 0.0, 0.0).to_hex(), "#00000000");
+    /// ```
+    #[must_use]
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self.to_bytes();
+        if a == u8::MAX {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        }
+    }
+
+    /// Formats the color for CSS: `#rrggbb` if it's opaque, otherwise
+    /// `rgba()` with the alpha to three decimals
+    ///
+    /// The result parses back with [`Color::from_css`].
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// assert_eq!(Color::rgba(1.0, 0.0, 0.0, 0.25).to_css(), "rgba(255, 0, 0, 0.25)");
+    /// assert_eq!(Color::from_css("navy").unwrap().to_css(), "#000080");
+    /// ```
+    #[must_use]
+    pub fn to_css(self) -> String {
+        let [r, g, b, a] = self.to_bytes();
+        if a == u8::MAX {
+            return self.to_hex();
+        }
+
+        let alpha = (self.a.clamp(0.0, 1.0) * 1000.0).round() / 1000.0;
+        format!("rgba({r}, {g}, {b}, {alpha})")
+    }
+
+    /// Returns the WCAG relative luminance of the color, ignoring alpha
+    ///
+    /// Channels are linearized from sRGB, so black is 0 and white is 1.
+    #[must_use]
+    pub fn relative_luminance(self) -> f32 {
+        let linear = |channel: f32| {
+            if channel <= 0.040_45 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.0722f32.mul_add(
+            linear(self.b),
+            0.2126f32.mul_add(linear(self.r), 0.7152 * linear(self.g)),
+        )
+    }
+
+    /// Returns the opaque color of this one blended over `background`
+    #[must_use]
+    pub fn over(self, background: Self) -> Self {
+        let blend = |fg: f32, bg: f32| (fg - bg).mul_add(self.a, bg);
+
+        Self::rgba(
+            blend(self.r, background.r),
+            blend(self.g, background.g),
+            blend(self.b, background.b),
+            1.0,
+        )
+    }
+
+    /// Returns the WCAG contrast ratio between two colors, from 1 for equal
+    /// luminance to 21 for black on white
+    ///
+    /// Alpha is ignored; blend translucent colors with [`Color::over`]
+    /// first.
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// let black = Color::rgba(0.0, 0.0, 0.0, 1.0);
+    /// let white = Color::rgba(1.0, 1.0, 1.0, 1.0);
+    /// assert!((black.contrast_ratio(white) - 21.0).abs() < 1e-4);
+    /// assert!((white.contrast_ratio(white) - 1.0).abs() < 1e-6);
+    /// ```
+    #[must_use]
+    pub fn contrast_ratio(self, other: Self) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Returns near-black for light colors and near-white for dark ones,
+    /// e.g. for text drawn on this color
+    ///
+    /// ```
+    /// use wmc_core::marker::Color;
+    ///
+    /// assert!(Color::rgba(0.0, 0.0, 0.0, 1.0).contrasting().r > 0.5);
+    /// assert!(Color::rgba(1.0, 1.0, 0.9, 1.0).contrasting().r < 0.5);
+    /// ```
+    #[must_use]
+    pub fn contrasting(self) -> Self {
+        let luminance = 0.0722f32.mul_add(self.b, 0.2126f32.mul_add(self.r, 0.7152 * self.g));
+
+        if luminance > 0.5 {
+            Self::rgba(0.1, 0.1, 0.1, 1.0)
+        } else {
+            Self::rgba(0.9, 0.9, 0.9, 1.0)
+        }
+    }
+}
+
+/// Serialized forms a [`Color`] is read from
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Css(String),
+    Channels { r: f32, g: f32, b: f32, a: f32 },
+}
+
+impl TryFrom<ColorValue> for Color {
+    type Error = CoreError;
+
+    fn try_from(value: ColorValue) -> Result<Self, Self::Error> {
+        match value {
+            ColorValue::Css(css) => Self::from_css(&css),
+            ColorValue::Channels { r, g, b, a } => Ok(Self::rgba(r, g, b, a)),
+        }
+    }
 }
@@ -1,8 +1,14 @@
-use crate::{error::CoreError, marker::Marker};
+use std::ops::Range;
+
+use crate::{
+    error::CoreError,
+    marker::{Color, Marker},
+    theme::MarkerStyle,
+};
 
 /// GPU instance data for a single marker
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct MarkerInstance {
     /// X position in screen space
     pub x: f32,
@@ -86,16 +92,42 @@ impl MarkerBuffer {
         self.count = 0;
     }
 
+    /// Clears all markers and sets the capacity to `capacity`, keeping the
+    /// allocation when it's large enough
+    pub fn reset(&mut self, capacity: usize) {
+        self.data.clear();
+        self.data.reserve(capacity);
+        self.capacity = capacity;
+        self.count = 0;
+    }
+
     /// Returns the buffer data as a byte slice for GPU upload
     #[must_use]
-    #[allow(unsafe_code)]
     pub fn as_bytes(&self) -> &[u8] {
-        let data_slice = &self.data[0..self.count];
+        self.range_bytes(0..self.count)
+    }
+
+    /// Returns the instances in `range` as a byte slice for a partial GPU
+    /// upload
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` extends past the buffered markers
+    #[must_use]
+    #[allow(unsafe_code)]
+    pub fn range_bytes(&self, range: Range<usize>) -> &[u8] {
+        let data_slice = &self.data[..self.count][range];
         let ptr = data_slice.as_ptr().cast::<u8>();
-        let len = self.count * std::mem::size_of::<MarkerInstance>();
+        let len = std::mem::size_of_val(data_slice);
         unsafe { std::slice::from_raw_parts(ptr, len) }
     }
 
+    /// Returns the instance at `index`
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&MarkerInstance> {
+        self.data[..self.count].get(index)
+    }
+
     /// Updates a marker instance at the specified index
     ///
     /// # Errors
@@ -116,31 +148,34 @@ impl MarkerBuffer {
 
 /// Builder for creating marker instances from markers
 pub struct InstanceBuilder {
-    default_color: [f32; 4],
+    style: MarkerStyle,
+    opacity: f32,
 }
 
 impl InstanceBuilder {
-    /// Creates a new instance builder with the specified default color
+    /// Creates a new instance builder coloring markers without their own
+    /// color by `style`
     #[must_use]
-    pub const fn new(default_color: [f32; 4]) -> Self {
-        Self { default_color }
+    pub const fn new(style: MarkerStyle) -> Self {
+        Self {
+            style,
+            opacity: 1.0,
+        }
+    }
+
+    /// Multiplies the alpha of every built instance by `opacity`, e.g. the
+    /// style's [`MarkerStyle::opacity_at`] the current zoom
+    pub const fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
     }
 
     /// Builds a marker instance from a marker and screen coordinates
     #[must_use]
     #[allow(clippy::many_single_char_names)]
     pub fn build(&self, marker: &Marker, x: f32, y: f32) -> MarkerInstance {
-        let (r, g, b, a) = marker.color.map_or_else(
-            || {
-                (
-                    self.default_color[0],
-                    self.default_color[1],
-                    self.default_color[2],
-                    self.default_color[3],
-                )
-            },
-            |color| (color.r, color.g, color.b, color.a),
-        );
+        let Color { r, g, b, a } = marker
+            .color
+            .unwrap_or_else(|| self.style.color_at(marker.intensity));
 
         MarkerInstance {
             x,
@@ -150,7 +185,7 @@ impl InstanceBuilder {
             r,
             g,
             b,
-            a,
+            a: (a * self.opacity).clamp(0.0, 1.0),
         }
     }
 }
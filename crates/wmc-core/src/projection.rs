@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::error::CoreError;
+use crate::{
+    error::CoreError,
+    geodesy::{self, EARTH_RADIUS},
+    index::Bounds,
+};
 
 /// Geographic coordinate in WGS84 (latitude, longitude)
 ///
@@ -52,6 +56,331 @@ impl GeoCoord {
 
         Ok(Self { lat, lon })
     }
+
+    /// Formats the coordinate as signed decimal degrees, latitude first,
+    /// with `precision` digits after the point
+    ///
+    /// Suits data export and copying into other tools; four digits resolve
+    /// about 10 meters.
+    ///
+    /// ```
+    /// use wmc_core::projection::GeoCoord;
+    ///
+    /// let rio = GeoCoord::new(-22.906_847, -43.172_896).unwrap();
+    /// assert_eq!(rio.format_decimal(4), "-22.9068, -43.1729");
+    /// ```
+    #[must_use]
+    pub fn format_decimal(self, precision: usize) -> String {
+        format!("{:.precision$}, {:.precision$}", self.lat, self.lon)
+    }
+
+    /// Formats the coordinate as unsigned decimal degrees with hemisphere
+    /// letters, with `precision` digits after the point
+    ///
+    /// ```
+    /// use wmc_core::projection::GeoCoord;
+    ///
+    /// let rio = GeoCoord::new(-22.906_847, -43.172_896).unwrap();
+    /// assert_eq!(rio.format_hemisphere(2), "22.91° S, 43.17° W");
+    /// ```
+    #[must_use]
+    pub fn format_hemisphere(self, precision: usize) -> String {
+        let (north_south, east_west) = self.hemispheres();
+        format!(
+            "{:.precision$}° {north_south}, {:.precision$}° {east_west}",
+            self.lat.abs(),
+            self.lon.abs()
+        )
+    }
+
+    /// Formats the coordinate as degrees, minutes and whole seconds with
+    /// hemisphere letters
+    ///
+    /// A second of latitude is about 30 meters.
+    ///
+    /// ```
+    /// use wmc_core::projection::GeoCoord;
+    ///
+    /// let paris = GeoCoord::new(48.8566, 2.3522).unwrap();
+    /// assert_eq!(paris.format_dms(), "48°51′24″ N, 2°21′08″ E");
+    ///
+    /// // Seconds rounding up carry into the minutes and degrees
+    /// let near = GeoCoord::new(-10.999_99, 0.0).unwrap();
+    /// assert_eq!(near.format_dms(), "11°00′00″ S, 0°00′00″ E");
+    /// ```
+    #[must_use]
+    pub fn format_dms(self) -> String {
+        let (north_south, east_west) = self.hemispheres();
+        format!(
+            "{} {north_south}, {} {east_west}",
+            dms(self.lat),
+            dms(self.lon)
+        )
+    }
+
+    /// Returns the hemisphere letters of the latitude and longitude, north
+    /// and east on the equator and prime meridian
+    fn hemispheres(self) -> (char, char) {
+        (
+            if self.lat < 0.0 { 'S' } else { 'N' },
+            if self.lon < 0.0 { 'W' } else { 'E' },
+        )
+    }
+}
+
+/// Formats the magnitude of `degrees` as degrees, minutes and seconds, e.g.
+/// `48°51′24″`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn dms(degrees: f64) -> String {
+    let seconds = (degrees.abs() * 3600.0).round() as u64;
+    format!(
+        "{}°{:02}′{:02}″",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Geographic bounding box in degrees
+///
+/// A box whose west edge lies east of its east edge crosses the
+/// antimeridian, spanning from `west` eastward to 180° and on from -180° to
+/// `east`.
+///
+/// # Examples
+///
+/// ```
+/// use wmc_core::projection::{GeoBounds, GeoCoord};
+///
+/// let europe = GeoBounds::new(-10.0, 35.0, 40.0, 70.0).unwrap();
+/// assert_eq!(europe.north, 70.0);
+///
+/// // The Pacific, across the antimeridian
+/// let pacific = GeoBounds::new(150.0, -40.0, -120.0, 40.0).unwrap();
+/// assert!(pacific.crosses_antimeridian());
+/// assert!(pacific.contains(GeoCoord::new(0.0, -170.0).unwrap()));
+/// assert!(!pacific.intersects(&europe));
+///
+/// // South must lie south of north, also when deserialized
+/// assert!(GeoBounds::new(-10.0, 70.0, 40.0, 35.0).is_err());
+/// let flipped = r#"{"west": -10, "south": 70, "east": 40, "north": 35}"#;
+/// assert!(serde_json::from_str::<GeoBounds>(flipped).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BoundsEdges")]
+pub struct GeoBounds {
+    /// Western longitude
+    pub west: f64,
+    /// Southern latitude
+    pub south: f64,
+    /// Eastern longitude
+    pub east: f64,
+    /// Northern latitude
+    pub north: f64,
+}
+
+impl GeoBounds {
+    /// Creates a bounding box from its edges
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidCoordinates`] if a corner is not a valid
+    /// [`GeoCoord`], or [`CoreError::InvalidBounds`] if the box is empty
+    ///
+    /// ```
+    /// use wmc_core::{error::CoreError, projection::GeoBounds};
+    ///
+    /// let error = GeoBounds::new(-10.0, 70.0, 40.0, 35.0).unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     CoreError::InvalidBounds { south: 70.0, north: 35.0, .. }
+    /// ));
+    /// ```
+    #[allow(clippy::float_cmp)]
+    pub fn new(west: f64, south: f64, east: f64, north: f64) -> Result<Self, CoreError> {
+        GeoCoord::new(south, west)?;
+        GeoCoord::new(north, east)?;
+
+        if west == east || south >= north {
+            return Err(CoreError::InvalidBounds {
+                west,
+                south,
+                east,
+                north,
+            });
+        }
+
+        Ok(Self {
+            west,
+            south,
+            east,
+            north,
+        })
+    }
+
+    /// Returns the south-west corner
+    #[must_use]
+    pub const fn south_west(&self) -> GeoCoord {
+        GeoCoord {
+            lat: self.south,
+            lon: self.west,
+        }
+    }
+
+    /// Returns the north-east corner
+    #[must_use]
+    pub const fn north_east(&self) -> GeoCoord {
+        GeoCoord {
+            lat: self.north,
+            lon: self.east,
+        }
+    }
+
+    /// Returns true if the box spans the antimeridian
+    #[must_use]
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.west > self.east
+    }
+
+    /// Returns the degrees of longitude the box spans eastward from `west`
+    #[must_use]
+    pub fn width(&self) -> f64 {
+        if self.crosses_antimeridian() {
+            self.east - self.west + 360.0
+        } else {
+            self.east - self.west
+        }
+    }
+
+    /// Returns true if `coord` lies inside the box or on its edges
+    #[must_use]
+    pub fn contains(&self, coord: GeoCoord) -> bool {
+        (self.south..=self.north).contains(&coord.lat) && self.contains_lon(coord.lon)
+    }
+
+    /// Returns true if the boxes share at least a point
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.south <= other.north
+            && other.south <= self.north
+            && (self.contains_lon(other.west) || other.contains_lon(self.west))
+    }
+
+    /// Grows the box just enough to cover `coord`
+    ///
+    /// Longitude grows toward whichever side is nearer, so extending past
+    /// the antimeridian makes the box cross it.
+    ///
+    /// ```
+    /// use wmc_core::projection::{GeoBounds, GeoCoord};
+    ///
+    /// let mut bounds = GeoBounds::new(160.0, -10.0, 170.0, 10.0).unwrap();
+    /// bounds.extend(GeoCoord::new(20.0, -175.0).unwrap());
+    /// assert_eq!((bounds.west, bounds.east, bounds.north), (160.0, -175.0, 20.0));
+    /// ```
+    pub fn extend(&mut self, coord: GeoCoord) {
+        let point = Self {
+            west: coord.lon,
+            south: coord.lat,
+            east: coord.lon,
+            north: coord.lat,
+        };
+        *self = self.union(&point);
+    }
+
+    /// Returns the smallest box covering both boxes
+    ///
+    /// Of the ways to join two longitude spans around the globe, the
+    /// narrowest is kept.
+    ///
+    /// ```
+    /// use wmc_core::projection::GeoBounds;
+    ///
+    /// let japan = GeoBounds::new(129.0, 31.0, 146.0, 46.0).unwrap();
+    /// let alaska = GeoBounds::new(-170.0, 54.0, -130.0, 72.0).unwrap();
+    /// let both = japan.union(&alaska);
+    /// assert_eq!((both.west, both.east), (129.0, -130.0));
+    /// assert_eq!((both.south, both.north), (31.0, 72.0));
+    /// ```
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        let candidates = [
+            (self.west, self.east),
+            (other.west, other.east),
+            (self.west, other.east),
+            (other.west, self.east),
+        ];
+        let (west, east) = candidates
+            .into_iter()
+            .map(|(west, east)| Self {
+                west,
+                east,
+                ..*self
+            })
+            .filter(|span| span.covers_lon(self) && span.covers_lon(other))
+            .min_by(|a, b| a.width().total_cmp(&b.width()))
+            .map_or((-180.0, 180.0), |span| (span.west, span.east));
+
+        Self {
+            west,
+            south: self.south.min(other.south),
+            east,
+            north: self.north.max(other.north),
+        }
+    }
+
+    /// Returns the box as plain `[west, south, east, north]` boxes for a
+    /// [`SpatialIndex`](crate::index::SpatialIndex), split in two at the
+    /// antimeridian if it crosses it
+    ///
+    /// ```
+    /// use wmc_core::projection::GeoBounds;
+    ///
+    /// let pacific = GeoBounds::new(150.0, -40.0, -120.0, 40.0).unwrap();
+    /// let boxes: Vec<_> = pacific.boxes().collect();
+    /// assert_eq!(boxes, [[150.0, -40.0, 180.0, 40.0], [-180.0, -40.0, -120.0, 40.0]]);
+    /// ```
+    pub fn boxes(&self) -> impl Iterator<Item = Bounds> + use<> {
+        let (south, north) = (self.south, self.north);
+        let parts = if self.crosses_antimeridian() {
+            [
+                Some([self.west, south, 180.0, north]),
+                Some([-180.0, south, self.east, north]),
+            ]
+        } else {
+            [Some([self.west, south, self.east, north]), None]
+        };
+        parts.into_iter().flatten()
+    }
+
+    /// Returns true if `lon` lies within the longitude span of the box
+    fn contains_lon(&self, lon: f64) -> bool {
+        (lon - self.west).rem_euclid(360.0) <= self.width()
+    }
+
+    /// Returns true if the longitude span of `other` lies within that of the
+    /// box
+    fn covers_lon(&self, other: &Self) -> bool {
+        (other.west - self.west).rem_euclid(360.0) + other.width() <= self.width()
+    }
+}
+
+/// Serialized form a [`GeoBounds`] is read from, validated by
+/// [`GeoBounds::new`]
+#[derive(Deserialize)]
+struct BoundsEdges {
+    west: f64,
+    south: f64,
+    east: f64,
+    north: f64,
+}
+
+impl TryFrom<BoundsEdges> for GeoBounds {
+    type Error = CoreError;
+
+    fn try_from(edges: BoundsEdges) -> Result<Self, Self::Error> {
+        Self::new(edges.west, edges.south, edges.east, edges.north)
+    }
 }
 
 /// Projected coordinate in screen space
@@ -69,6 +398,31 @@ pub trait Projection {
     fn project(&self, coord: GeoCoord) -> ProjectedCoord;
     /// Unprojects screen coordinates to geographic space
     fn unproject(&self, coord: ProjectedCoord) -> GeoCoord;
+
+    /// Returns the ground distance in meters one pixel spans east-west at
+    /// `coord`
+    ///
+    /// The default measures the great-circle distance across the pixel
+    /// around the projected point; projections with a closed form override
+    /// it.
+    fn meters_per_pixel(&self, coord: GeoCoord) -> f64 {
+        let center = self.project(coord);
+        let west = self.unproject(ProjectedCoord {
+            x: center.x - 0.5,
+            y: center.y,
+        });
+        let east = self.unproject(ProjectedCoord {
+            x: center.x + 0.5,
+            y: center.y,
+        });
+        geodesy::distance_haversine(west, east)
+    }
+}
+
+/// Returns the length in meters of the parallel through `lat` divided over
+/// `width` pixels
+fn parallel_meters_per_pixel(lat: f64, width: f64) -> f64 {
+    2.0 * std::f64::consts::PI * EARTH_RADIUS * lat.to_radians().cos() / width
 }
 
 /// Web Mercator projection
@@ -112,6 +466,22 @@ impl Projection for MercatorProjection {
             lon: lon.to_degrees(),
         }
     }
+
+    /// Parallels are stretched by `1 / cos(lat)`, so a pixel spans the
+    /// parallel's length over the map width
+    ///
+    /// ```
+    /// use wmc_core::projection::{GeoCoord, MercatorProjection, Projection};
+    ///
+    /// let projection = MercatorProjection::new(1024.0, 1024.0);
+    /// let equator = projection.meters_per_pixel(GeoCoord::new(0.0, 0.0).unwrap());
+    /// let sixty = projection.meters_per_pixel(GeoCoord::new(60.0, 0.0).unwrap());
+    /// assert!((equator - 39_092.0).abs() < 0.1);
+    /// assert!((sixty - equator / 2.0).abs() < 1e-6);
+    /// ```
+    fn meters_per_pixel(&self, coord: GeoCoord) -> f64 {
+        parallel_meters_per_pixel(coord.lat, self.width)
+    }
 }
 
 /// Equirectangular (Plate Carrée) projection
@@ -143,4 +513,10 @@ impl Projection for EquirectangularProjection {
 
         GeoCoord { lat, lon }
     }
+
+    /// Meridians are evenly spaced, so a pixel spans the parallel's length
+    /// over the map width
+    fn meters_per_pixel(&self, coord: GeoCoord) -> f64 {
+        parallel_meters_per_pixel(coord.lat, self.width)
+    }
 }
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+/// Meters per foot
+const FOOT: f64 = 0.3048;
+
+/// Meters per statute mile
+const MILE: f64 = 1609.344;
+
+/// Unit system a [`ScaleBar`] is labeled in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScaleUnit {
+    /// Meters below a kilometer, kilometers above
+    #[default]
+    Metric,
+    /// Feet below a mile, miles above
+    Imperial,
+}
+
+impl ScaleUnit {
+    /// Parses a unit name as used in scale bar options
+    ///
+    /// ```
+    /// use wmc_core::scale_bar::ScaleUnit;
+    ///
+    /// assert_eq!(ScaleUnit::from_name("imperial"), Some(ScaleUnit::Imperial));
+    /// assert_eq!(ScaleUnit::from_name("nautical"), None);
+    /// ```
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "metric" => Some(Self::Metric),
+            "imperial" => Some(Self::Imperial),
+            _ => None,
+        }
+    }
+
+    /// Returns the small and large unit as `(meters, symbol)` pairs
+    const fn units(self) -> [(f64, &'static str); 2] {
+        match self {
+            Self::Metric => [(1.0, "m"), (1000.0, "km")],
+            Self::Imperial => [(FOOT, "ft"), (MILE, "mi")],
+        }
+    }
+}
+
+/// Bar of a round ground distance, e.g. `500 km`, and its length on screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaleBar {
+    /// Bar length in pixels
+    pub width: f64,
+    /// Ground distance the bar spans, in meters
+    pub meters: f64,
+    /// Distance in the bar's unit, e.g. `500 km`
+    pub label: String,
+}
+
+impl ScaleBar {
+    /// Returns the longest bar of 1, 2 or 5 times a power of ten in `unit`
+    /// that fits `max_width` pixels at `meters_per_pixel`
+    ///
+    /// Distances of a kilometer or mile and more switch to the large unit.
+    /// Returns `None` if the inputs aren't positive and finite.
+    ///
+    /// ```
+    /// use wmc_core::scale_bar::{ScaleBar, ScaleUnit};
+    ///
+    /// let bar = ScaleBar::fit(39_092.0, 100.0, ScaleUnit::Metric).unwrap();
+    /// assert_eq!(bar.label, "2000 km");
+    /// assert!((bar.width - 51.16).abs() < 0.01);
+    ///
+    /// let bar = ScaleBar::fit(3.0, 100.0, ScaleUnit::Imperial).unwrap();
+    /// assert_eq!(bar.label, "500 ft");
+    /// ```
+    #[must_use]
+    pub fn fit(meters_per_pixel: f64, max_width: f64, unit: ScaleUnit) -> Option<Self> {
+        let max_meters = meters_per_pixel * max_width;
+        if !max_meters.is_normal() || max_meters < 0.0 || max_width <= 0.0 {
+            return None;
+        }
+
+        let [small, large] = unit.units();
+        let (unit_meters, symbol) = if max_meters >= large.0 { large } else { small };
+        let value = round_down(max_meters / unit_meters);
+        let meters = value * unit_meters;
+
+        Some(Self {
+            width: meters / meters_per_pixel,
+            meters,
+            label: format!("{value} {symbol}"),
+        })
+    }
+}
+
+/// Returns the largest 1, 2 or 5 times a power of ten not above `value`
+fn round_down(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    let leading = value / magnitude;
+    let step = if leading >= 5.0 {
+        5.0
+    } else if leading >= 2.0 {
+        2.0
+    } else {
+        1.0
+    };
+    step * magnitude
+}
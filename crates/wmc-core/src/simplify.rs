@@ -0,0 +1,176 @@
+use crate::{
+    label::WORLD_SIZE,
+    projection::{GeoCoord, MercatorProjection, Projection},
+    topology::WorldTopology,
+};
+
+/// Latitude limit of the square Web Mercator world, beyond which
+/// projected coordinates grow without bound
+const MERCATOR_MAX_LAT: f64 = 85.051_129;
+
+/// Importance of every vertex of a topology's lines, for drawing them at a
+/// level of detail matching the zoom
+///
+/// A vertex's importance is the Douglas-Peucker tolerance at which it's
+/// dropped, in Web Mercator pixels at zoom 0; line ends are always kept.
+/// Keeping the vertices whose importance reaches a tolerance gives the
+/// Douglas-Peucker simplification at that tolerance, so one pass over the
+/// topology serves every zoom.
+///
+/// ```
+/// use wmc_core::{simplify::LineDetail, topology::WorldTopology};
+///
+/// let topology = WorldTopology::graticule(30.0);
+/// let detail = LineDetail::new(&topology);
+/// let lines = topology.features[0].geometry.lines();
+///
+/// // Meridians are straight, so only their ends matter at any zoom
+/// let kept = detail.simplify(0, lines[0], LineDetail::tolerance(0.5, 2.0));
+/// assert_eq!(kept.count(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LineDetail {
+    lines: Vec<Vec<f32>>,
+}
+
+impl LineDetail {
+    /// Computes the importance of the vertices of every line of `topology`
+    #[must_use]
+    pub fn new(topology: &WorldTopology) -> Self {
+        let projection = MercatorProjection::new(WORLD_SIZE, WORLD_SIZE);
+        let lines = topology
+            .features
+            .iter()
+            .flat_map(|feature| feature.geometry.lines())
+            .map(|line| {
+                let points: Vec<[f64; 2]> = line
+                    .iter()
+                    .map(|&coord| {
+                        let point = projection.project(GeoCoord {
+                            lat: coord.lat.clamp(-MERCATOR_MAX_LAT, MERCATOR_MAX_LAT),
+                            lon: coord.lon,
+                        });
+                        [point.x, point.y]
+                    })
+                    .collect();
+                importance(&points)
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Rebuilds the detail from the importance of every point, line after
+    /// line, and the number of points of each line, as in
+    /// [`PackedLines`](crate::topology::PackedLines)
+    ///
+    /// Lines past the end of `importance` get no detail and are kept whole.
+    #[must_use]
+    pub fn from_flat(importance: &[f32], lines: &[u32]) -> Self {
+        let mut rest = importance;
+        let lines = lines
+            .iter()
+            .map(|&length| {
+                let (line, tail) = rest.split_at(rest.len().min(length as usize));
+                rest = tail;
+                line.to_vec()
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Returns the importance of every point, line after line
+    #[must_use]
+    pub fn to_flat(&self) -> Vec<f32> {
+        self.lines.concat()
+    }
+
+    /// Returns the tolerance in zoom 0 pixels that drops details smaller
+    /// than `pixels` at `zoom`
+    #[must_use]
+    pub fn tolerance(pixels: f64, zoom: f64) -> f64 {
+        pixels / zoom.exp2()
+    }
+
+    /// Returns the points of `line` kept at `tolerance`, `index` being the
+    /// position of the line among the lines of every feature, in order
+    ///
+    /// Lines the detail wasn't computed for are kept whole.
+    pub fn simplify<'a>(
+        &'a self,
+        index: usize,
+        line: &'a [GeoCoord],
+        tolerance: f64,
+    ) -> impl Iterator<Item = GeoCoord> + 'a {
+        let importance = self
+            .lines
+            .get(index)
+            .filter(|importance| importance.len() == line.len());
+
+        line.iter()
+            .enumerate()
+            .filter(move |&(i, _)| {
+                importance.is_none_or(|importance| f64::from(importance[i]) >= tolerance)
+            })
+            .map(|(_, &point)| point)
+    }
+}
+
+/// Returns the Douglas-Peucker importance of each of `points`, the ends
+/// being infinitely important
+///
+/// A vertex is as important as its distance from the chord it splits,
+/// capped by the importance of the vertex that split the chord before it, so
+/// importance never grows on the way down and every threshold keeps a valid
+/// simplification.
+///
+/// ```
+/// use wmc_core::simplify::importance;
+///
+/// let points = [[0.0, 0.0], [1.0, 0.1], [2.0, 3.0], [4.0, 0.0]];
+/// let importance = importance(&points);
+///
+/// assert_eq!(importance[0], f32::INFINITY);
+/// assert_eq!(importance[2], 3.0);
+/// assert!(importance[1] < 1.0);
+/// ```
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn importance(points: &[[f64; 2]]) -> Vec<f32> {
+    let mut importance = vec![f32::INFINITY; points.len()];
+    if points.len() < 3 {
+        return importance;
+    }
+
+    let mut chords = vec![(0, points.len() - 1, f64::INFINITY)];
+    while let Some((start, end, cap)) = chords.pop() {
+        let farthest = (start + 1..end)
+            .map(|i| (i, distance_to_chord(points[i], points[start], points[end])))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((split, distance)) = farthest else {
+            continue;
+        };
+
+        let value = distance.min(cap);
+        importance[split] = value as f32;
+        chords.push((start, split, value));
+        chords.push((split, end, value));
+    }
+
+    importance
+}
+
+/// Returns the distance from `point` to the segment from `start` to `end`
+fn distance_to_chord(point: [f64; 2], start: [f64; 2], end: [f64; 2]) -> f64 {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    let length_squared = dx.mul_add(dx, dy * dy);
+    let t = if length_squared > 0.0 {
+        ((point[0] - start[0]).mul_add(dx, (point[1] - start[1]) * dy) / length_squared)
+            .clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (point[0] - t.mul_add(dx, start[0])).hypot(point[1] - t.mul_add(dy, start[1]))
+}
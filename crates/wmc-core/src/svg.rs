@@ -0,0 +1,249 @@
+use std::fmt::Write;
+
+use crate::{
+    marker::Color,
+    projection::{GeoCoord, ProjectedCoord, Projection},
+    theme::LineStyle,
+    topology::WorldTopology,
+};
+
+/// SVG document built from projected map geometry
+///
+/// Geometry is projected with the same [`Projection`] the GPU path uses, so
+/// an export matches the rendered view. Coordinates are written in the
+/// document's user units, one per projected pixel.
+///
+/// # Examples
+///
+/// ```
+/// use wmc_core::{
+///     marker::Color, projection::MercatorProjection, svg::SvgDocument, theme::LineStyle,
+///     topology::WorldTopology,
+/// };
+///
+/// let projection = MercatorProjection::new(800.0, 600.0);
+/// let mut svg = SvgDocument::new(800, 600, Color::rgba(0.0, 0.0, 0.0, 1.0));
+/// svg.lines(
+///     &WorldTopology::graticule(30.0),
+///     &projection,
+///     Color::rgba(1.0, 1.0, 1.0, 0.5),
+///     1.0,
+///     LineStyle::Dashed { dash: 4.0, gap: 2.0 },
+/// );
+///
+/// let document = svg.finish();
+/// assert!(document.starts_with("<svg"));
+/// assert!(document.ends_with("</svg>\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    out: String,
+}
+
+impl SvgDocument {
+    /// Starts a `width` × `height` document filled with `background`
+    #[must_use]
+    pub fn new(width: u32, height: u32, background: Color) -> Self {
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        let _ = writeln!(
+            out,
+            r#"<rect width="100%" height="100%" fill="{}" fill-opacity="{}"/>"#,
+            rgb(background),
+            background.a
+        );
+
+        Self { out }
+    }
+
+    /// Adds every line string and polygon ring of `topology` as one stroked
+    /// path, `width` units wide and dashed by `style`
+    pub fn lines(
+        &mut self,
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+        color: Color,
+        width: f32,
+        style: LineStyle,
+    ) {
+        let mut data = String::new();
+        for feature in &topology.features {
+            for line in feature.geometry.lines() {
+                push_line(&mut data, line, projection, false);
+            }
+        }
+
+        self.stroke(&data, color, width, style);
+    }
+
+    /// Adds a stroked line through `points`, `width` units wide and dashed
+    /// by `style`
+    pub fn polyline(
+        &mut self,
+        points: &[ProjectedCoord],
+        color: Color,
+        width: f32,
+        style: LineStyle,
+    ) {
+        let mut data = String::new();
+        push_points(&mut data, points, false);
+        self.stroke(&data, color, width, style);
+    }
+
+    /// Adds the polygons of `topology` as one filled path
+    ///
+    /// Holes are cut out with the even-odd fill rule.
+    pub fn fills(&mut self, topology: &WorldTopology, projection: &dyn Projection, color: Color) {
+        let mut data = String::new();
+        for feature in &topology.features {
+            for rings in feature.geometry.polygons() {
+                for ring in rings {
+                    push_line(&mut data, ring, projection, true);
+                }
+            }
+        }
+
+        if !data.is_empty() {
+            let _ = writeln!(
+                self.out,
+                r#"<path d="{data}" fill="{}" fill-opacity="{}" fill-rule="evenodd"/>"#,
+                rgb(color),
+                color.a
+            );
+        }
+    }
+
+    /// Adds a filled circle
+    pub fn circle(&mut self, center: ProjectedCoord, radius: f32, color: Color) {
+        let _ = writeln!(
+            self.out,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{radius}" fill="{}" fill-opacity="{}"/>"#,
+            center.x,
+            center.y,
+            rgb(color),
+            color.a
+        );
+    }
+
+    /// Adds a filled rectangle with its top left corner at `origin`
+    pub fn rect(&mut self, origin: ProjectedCoord, width: f64, height: f64, color: Color) {
+        let _ = writeln!(
+            self.out,
+            r#"<rect x="{:.2}" y="{:.2}" width="{width:.2}" height="{height:.2}" fill="{}" fill-opacity="{}"/>"#,
+            origin.x,
+            origin.y,
+            rgb(color),
+            color.a
+        );
+    }
+
+    /// Adds a line of sans-serif `text`, `size` units high, starting at
+    /// `origin` on its baseline
+    pub fn text(&mut self, origin: ProjectedCoord, text: &str, size: f32, color: Color) {
+        let _ = writeln!(
+            self.out,
+            r#"<text x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{size}" fill="{}" fill-opacity="{}">{}</text>"#,
+            origin.x,
+            origin.y,
+            rgb(color),
+            color.a,
+            escape(text)
+        );
+    }
+
+    /// Adds path `data` as a stroke, unless it is empty
+    fn stroke(&mut self, data: &str, color: Color, width: f32, style: LineStyle) {
+        if data.is_empty() {
+            return;
+        }
+
+        // Dots are zero-length dashes with round caps
+        let (cap, dashes) = match style {
+            LineStyle::Solid => ("round", String::new()),
+            LineStyle::Dashed { dash, gap } => {
+                ("butt", format!(r#" stroke-dasharray="{dash} {gap}""#))
+            },
+            LineStyle::Dotted { gap } => {
+                ("round", format!(r#" stroke-dasharray="0 {}""#, gap + width))
+            },
+        };
+        let _ = writeln!(
+            self.out,
+            r#"<path d="{data}" fill="none" stroke="{}" stroke-opacity="{}" stroke-width="{width}" stroke-linejoin="round" stroke-linecap="{cap}"{dashes}/>"#,
+            rgb(color),
+            color.a
+        );
+    }
+
+    /// Closes the document and returns its source
+    #[must_use]
+    pub fn finish(mut self) -> String {
+        self.out.push_str("</svg>\n");
+        self.out
+    }
+}
+
+/// Appends `points` as a subpath
+///
+/// Points projecting to infinity (the poles under Mercator) are dropped, and
+/// lines with fewer than two remaining points are skipped.
+fn push_line(data: &mut String, points: &[GeoCoord], projection: &dyn Projection, close: bool) {
+    let points: Vec<ProjectedCoord> = points
+        .iter()
+        .map(|&coord| projection.project(coord))
+        .collect();
+    push_points(data, &points, close);
+}
+
+/// Appends projected `points` as a subpath
+///
+/// Non-finite points are dropped, and lines with fewer than two remaining
+/// points are skipped.
+fn push_points(data: &mut String, points: &[ProjectedCoord], close: bool) {
+    let points: Vec<&ProjectedCoord> = points
+        .iter()
+        .filter(|point| point.x.is_finite() && point.y.is_finite())
+        .collect();
+    if points.len() < 2 {
+        return;
+    }
+
+    for (i, point) in points.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        let _ = write!(data, "{command}{:.2} {:.2}", point.x, point.y);
+    }
+    if close {
+        data.push('Z');
+    }
+}
+
+/// Escapes the XML markup characters of `text`
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Formats the color channels as an SVG `rgb()` value
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn rgb(color: Color) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "rgb({},{},{})",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
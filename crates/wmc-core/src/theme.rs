@@ -1,41 +1,941 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{error::CoreError, marker::Color};
+use crate::{color_scale::ColorScale, error::CoreError, marker::Color};
 
 /// Visual theme for map rendering
+///
+/// Every map element has its own style block, e.g.
+/// `{"coastlines": {"color": {...}, "width": 1.5, "opacity": 0.8}}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Theme {
     /// Theme name
     pub name: String,
     /// Background color
     pub background: Color,
-    /// Contour line color
-    pub contour_color: Color,
-    /// Contour line width in pixels
-    pub contour_width: f32,
-    /// Default marker color
-    pub marker_color: Color,
-    /// Marker glow radius
-    pub marker_glow: f32,
-    /// Film grain intensity
+    /// Country outlines and coastlines of the world layer
+    pub coastlines: LayerStyle,
+    /// Default look of line layers added at runtime, e.g. administrative
+    /// borders
+    #[serde(default = "default_borders")]
+    pub borders: LayerStyle,
+    /// Rivers layer
+    #[serde(default = "default_rivers")]
+    pub rivers: LayerStyle,
+    /// Lakes layer
+    #[serde(default = "default_lakes")]
+    pub lakes: LayerStyle,
+    /// Default look of fill layers added at runtime
+    #[serde(default = "default_fills")]
+    pub fills: LayerStyle,
+    /// Graticule layer
+    #[serde(default = "default_graticule")]
+    pub graticule: LayerStyle,
+    /// Markers without their own color
+    pub markers: MarkerStyle,
+    /// Selected marker, drawn over the others
+    #[serde(default)]
+    pub selection: SelectionStyle,
+    /// Ripples spreading from selected and newly added markers
+    #[serde(default)]
+    pub ripple: RippleStyle,
+    /// Film grain noise amplitude in color units; zero disables grain
     pub grain_intensity: f32,
+    /// Vignette darkening at the corners in `0.0..=1.0`; zero disables it
+    #[serde(default)]
+    pub vignette: f32,
+    /// Color grading applied to the rendered frame
+    #[serde(default)]
+    pub grading: ColorGrading,
+}
+
+/// Look of one line or fill layer
+///
+/// Fills ignore the width and dash pattern.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LayerStyle {
+    /// Line or fill color
+    pub color: Color,
+    /// Line width in pixels
+    #[serde(default = "default_width")]
+    pub width: f32,
+    /// Opacity in `0.0..=1.0`, multiplied into the color's alpha
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Line dash pattern
+    #[serde(default)]
+    pub style: LineStyle,
+}
+
+impl LayerStyle {
+    /// Creates an opaque solid line style
+    #[must_use]
+    pub const fn line(color: Color, width: f32) -> Self {
+        Self {
+            color,
+            width,
+            opacity: 1.0,
+            style: LineStyle::Solid,
+        }
+    }
+
+    /// Returns the color with the opacity applied, as drawn
+    ///
+    /// ```
+    /// use wmc_core::{marker::Color, theme::LayerStyle};
+    ///
+    /// let style = LayerStyle {
+    ///     opacity: 0.5,
+    ///     ..LayerStyle::line(Color::rgba(1.0, 1.0, 1.0, 0.8), 1.0)
+    /// };
+    /// assert!((style.effective_color().a - 0.4).abs() < 1e-6);
+    /// ```
+    #[must_use]
+    pub fn effective_color(&self) -> Color {
+        Color {
+            a: self.color.a * self.opacity,
+            ..self.color
+        }
+    }
+
+    /// Validates the style of the element `name`
+    fn validate(&self, name: &str) -> Result<(), CoreError> {
+        let reason = if !is_valid_color(self.color) {
+            format!("invalid {name} color: {:?}", self.color)
+        } else if self.width <= 0.0 || !self.width.is_finite() {
+            format!("invalid {name} width: {}", self.width)
+        } else if !(0.0..=1.0).contains(&self.opacity) {
+            format!("invalid {name} opacity: {}", self.opacity)
+        } else if !self.style.is_valid() {
+            format!("invalid {name} style: {:?}", self.style)
+        } else {
+            return Ok(());
+        };
+
+        Err(CoreError::ThemeValidationFailed { reason })
+    }
+}
+
+/// Look of markers without their own color
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MarkerStyle {
+    /// Marker color
+    pub color: Color,
+    /// Opacity in `0.0..=1.0`, multiplied into the color's alpha
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Glow radius in pixels; zero disables the glow
+    #[serde(default)]
+    pub glow: f32,
+    /// Colors markers by intensity instead of `color`, e.g.
+    /// `{"palette": "magma"}`
+    #[serde(default)]
+    pub scale: Option<ColorScale>,
+    /// Multiplies marker radii by the zoom, e.g. `{"zoom": [0, 4], "value":
+    /// [0.5, 1.5]}` to grow markers from half to one and a half times their
+    /// radius
+    #[serde(default)]
+    pub zoom_radius: Option<ZoomRamp>,
+    /// Multiplies the marker opacity by the zoom, e.g. to fade dense markers
+    /// at world zoom
+    #[serde(default)]
+    pub zoom_opacity: Option<ZoomRamp>,
+}
+
+impl MarkerStyle {
+    /// Returns the color with the opacity applied, as drawn
+    #[must_use]
+    pub fn effective_color(&self) -> Color {
+        Color {
+            a: self.color.a * self.opacity,
+            ..self.color
+        }
+    }
+
+    /// Returns the color of a marker of `intensity` without its own color,
+    /// with the opacity applied
+    ///
+    /// ```
+    /// use wmc_core::{
+    ///     color_scale::{ColorScale, Palette},
+    ///     theme::Theme,
+    /// };
+    ///
+    /// let mut style = Theme::dark_minimal().markers;
+    /// assert_eq!(style.color_at(0.2).g, style.effective_color().g);
+    ///
+    /// style.scale = Some(ColorScale::new(Palette::Magma, [0.0, 1.0]));
+    /// assert_eq!(style.color_at(1.0).r, Palette::Magma.sample(1.0).r);
+    /// ```
+    #[must_use]
+    pub fn color_at(&self, intensity: f32) -> Color {
+        let color = self
+            .scale
+            .map_or(self.color, |scale| scale.color(f64::from(intensity)));
+        Color {
+            a: color.a * self.opacity,
+            ..color
+        }
+    }
+
+    /// Returns the factor marker radii are multiplied by at `zoom`
+    #[must_use]
+    pub fn radius_at(&self, zoom: f32) -> f32 {
+        self.zoom_radius.map_or(1.0, |ramp| ramp.at(zoom))
+    }
+
+    /// Returns the factor the marker opacity is multiplied by at `zoom`
+    #[must_use]
+    pub fn opacity_at(&self, zoom: f32) -> f32 {
+        self.zoom_opacity.map_or(1.0, |ramp| ramp.at(zoom))
+    }
+
+    /// Validates the marker style
+    fn validate(&self) -> Result<(), CoreError> {
+        if let Some(scale) = &self.scale {
+            scale.validate()?;
+        }
+
+        let reason = if !is_valid_color(self.color) {
+            format!("invalid marker color: {:?}", self.color)
+        } else if !(0.0..=1.0).contains(&self.opacity) {
+            format!("invalid marker opacity: {}", self.opacity)
+        } else if self.glow < 0.0 || !self.glow.is_finite() {
+            format!("invalid marker glow: {}", self.glow)
+        } else if let Some(ramp) = self.zoom_radius.filter(|ramp| !ramp.is_valid()) {
+            format!("invalid marker zoom radius: {ramp:?}")
+        } else if let Some(ramp) = self.zoom_opacity.filter(|ramp| !ramp.is_valid()) {
+            format!("invalid marker zoom opacity: {ramp:?}")
+        } else {
+            return Ok(());
+        };
+
+        Err(CoreError::ThemeValidationFailed { reason })
+    }
+}
+
+/// Look of the selected marker: recolored, scaled up and ringed
+///
+/// Deserialized from e.g. `{"color": {...}, "scale": 1.5}`, omitted fields
+/// taking their defaults.
+///
+/// ```
+/// use wmc_core::{marker::Color, theme::{SelectionStyle, Theme}};
+///
+/// let theme = Theme::dark_minimal();
+/// let marker = Color::rgba(0.2, 0.4, 0.6, 1.0);
+/// assert_eq!(theme.selection.fill(marker).r, 1.0);
+///
+/// let style: SelectionStyle = serde_json::from_str(r#"{"scale": 2}"#).unwrap();
+/// assert_eq!(style.fill(marker).r, 0.2);
+/// assert_eq!(style.ring_color(theme.background).r, 0.9);
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectionStyle {
+    /// Fill of the selected marker, its own color when `None`
+    pub color: Option<Color>,
+    /// Ring color, contrasting with the background when `None`
+    pub ring: Option<Color>,
+    /// Factor the selected marker's radius is multiplied by
+    pub scale: f32,
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        Self {
+            color: None,
+            ring: None,
+            scale: 1.5,
+        }
+    }
+}
+
+impl SelectionStyle {
+    /// Returns the fill of a selected marker drawn in `color` otherwise
+    #[must_use]
+    pub fn fill(&self, color: Color) -> Color {
+        self.color.unwrap_or(color)
+    }
+
+    /// Returns the ring color over `background`
+    #[must_use]
+    pub fn ring_color(&self, background: Color) -> Color {
+        self.ring.unwrap_or_else(|| background.contrasting())
+    }
+
+    /// Validates the selection style
+    fn validate(&self) -> Result<(), CoreError> {
+        let reason = if let Some(color) = self.color.filter(|&color| !is_valid_color(color)) {
+            format!("invalid selection color: {color:?}")
+        } else if let Some(color) = self.ring.filter(|&color| !is_valid_color(color)) {
+            format!("invalid selection ring: {color:?}")
+        } else if self.scale <= 0.0 || !self.scale.is_finite() {
+            format!("invalid selection scale: {}", self.scale)
+        } else {
+            return Ok(());
+        };
+
+        Err(CoreError::ThemeValidationFailed { reason })
+    }
+}
+
+/// Look of the ripple rings spreading from a marker as it's selected or
+/// added, drawing the eye to it
+///
+/// Deserialized from e.g. `{"color": {...}, "duration": 2}`, omitted
+/// fields taking their defaults.
+///
+/// ```
+/// use wmc_core::{marker::Color, theme::RippleStyle};
+///
+/// let marker = Color::rgba(0.2, 0.4, 0.6, 1.0);
+/// let style = RippleStyle::default();
+/// assert_eq!(style.color_for(marker).b, 0.6);
+/// assert!(style.is_enabled());
+///
+/// let style: RippleStyle = serde_json::from_str(r#"{"duration": 0}"#).unwrap();
+/// assert!(!style.is_enabled());
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RippleStyle {
+    /// Ring color, the marker's own color when `None`
+    pub color: Option<Color>,
+    /// Seconds a ripple takes to spread and fade out; zero disables ripples
+    pub duration: f32,
+    /// Factor of the marker radius the ring spreads to
+    pub scale: f32,
+}
+
+impl Default for RippleStyle {
+    fn default() -> Self {
+        Self {
+            color: None,
+            duration: 1.2,
+            scale: 3.0,
+        }
+    }
+}
+
+impl RippleStyle {
+    /// Returns the ring color of a ripple from a marker drawn in `color`
+    #[must_use]
+    pub fn color_for(&self, color: Color) -> Color {
+        self.color.unwrap_or(color)
+    }
+
+    /// Returns true if ripples are drawn at all
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.duration > 0.0
+    }
+
+    /// Validates the ripple style
+    fn validate(&self) -> Result<(), CoreError> {
+        let reason = if let Some(color) = self.color.filter(|&color| !is_valid_color(color)) {
+            format!("invalid ripple color: {color:?}")
+        } else if self.duration < 0.0 || !self.duration.is_finite() {
+            format!("invalid ripple duration: {}", self.duration)
+        } else if self.scale < 1.0 || !self.scale.is_finite() {
+            format!("invalid ripple scale: {}", self.scale)
+        } else {
+            return Ok(());
+        };
+
+        Err(CoreError::ThemeValidationFailed { reason })
+    }
+}
+
+/// Value ramping linearly between two zoom levels and held past them
+///
+/// Deserialized from e.g. `{"zoom": [0, 4], "value": [0.5, 1.5]}`.
+///
+/// ```
+/// use wmc_core::theme::ZoomRamp;
+///
+/// let ramp = ZoomRamp {
+///     zoom: [0.0, 4.0],
+///     value: [0.5, 1.5],
+/// };
+/// assert_eq!(ramp.at(-1.0), 0.5);
+/// assert_eq!(ramp.at(2.0), 1.0);
+/// assert_eq!(ramp.at(6.0), 1.5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ZoomRamp {
+    /// Zoom levels the ramp starts and ends at
+    pub zoom: [f32; 2],
+    /// Values at the start and end zoom levels
+    pub value: [f32; 2],
+}
+
+impl ZoomRamp {
+    /// Returns the value at `zoom`
+    #[must_use]
+    pub fn at(self, zoom: f32) -> f32 {
+        let [start, end] = self.zoom;
+        let t = if end > start {
+            ((zoom - start) / (end - start)).clamp(0.0, 1.0)
+        } else {
+            f32::from(u8::from(zoom >= end))
+        };
+        (self.value[1] - self.value[0]).mul_add(t, self.value[0])
+    }
+
+    /// Returns true if the zoom levels are ordered and the values are
+    /// finite and not negative
+    #[must_use]
+    pub fn is_valid(self) -> bool {
+        self.zoom.iter().all(|zoom| zoom.is_finite())
+            && self.zoom[0] <= self.zoom[1]
+            && self
+                .value
+                .iter()
+                .all(|value| value.is_finite() && *value >= 0.0)
+    }
+}
+
+/// Dash pattern of a line, with lengths in pixels
+///
+/// Deserialized from e.g. `{"type": "dashed", "dash": 6, "gap": 3}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LineStyle {
+    /// Unbroken line
+    #[default]
+    Solid,
+    /// Dashes with square ends
+    Dashed {
+        /// Dash length
+        dash: f32,
+        /// Gap between dashes
+        gap: f32,
+    },
+    /// Round dots one line width across
+    Dotted {
+        /// Gap between dots
+        gap: f32,
+    },
+}
+
+impl LineStyle {
+    /// Returns true if the lengths can be drawn
+    ///
+    /// ```
+    /// use wmc_core::theme::LineStyle;
+    ///
+    /// assert!(LineStyle::Dashed { dash: 4.0, gap: 2.0 }.is_valid());
+    /// assert!(!LineStyle::Dashed { dash: 0.0, gap: 2.0 }.is_valid());
+    /// assert!(!LineStyle::Dotted { gap: -1.0 }.is_valid());
+    /// ```
+    #[must_use]
+    pub fn is_valid(self) -> bool {
+        match self {
+            Self::Solid => true,
+            Self::Dashed { dash, gap } => {
+                dash > 0.0 && dash.is_finite() && gap >= 0.0 && gap.is_finite()
+            },
+            Self::Dotted { gap } => gap >= 0.0 && gap.is_finite(),
+        }
+    }
+
+    /// Returns the style with every length multiplied by `factor`, e.g. to
+    /// convert CSS pixels to device pixels
+    #[must_use]
+    pub fn scaled(self, factor: f32) -> Self {
+        match self {
+            Self::Solid => Self::Solid,
+            Self::Dashed { dash, gap } => Self::Dashed {
+                dash: dash * factor,
+                gap: gap * factor,
+            },
+            Self::Dotted { gap } => Self::Dotted { gap: gap * factor },
+        }
+    }
+}
+
+/// WCAG contrast ratios of theme colors against the background, from 1 to
+/// 21
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ContrastReport {
+    /// Coastline and country outline
+    pub coastlines: f32,
+    /// Default line layer
+    pub borders: f32,
+    /// River lines
+    pub rivers: f32,
+    /// Lake fills
+    pub lakes: f32,
+    /// Default fill layer
+    pub fills: f32,
+    /// Graticule lines
+    pub graticule: f32,
+    /// Markers without their own color
+    pub markers: f32,
+}
+
+impl ContrastReport {
+    /// WCAG 2.1 minimum contrast of graphical objects (success criterion
+    /// 1.4.11)
+    pub const NON_TEXT_MINIMUM: f32 = 3.0;
+
+    /// Returns the elements below `minimum` with their ratios
+    #[must_use]
+    pub fn failures(&self, minimum: f32) -> Vec<(&'static str, f32)> {
+        [
+            ("coastlines", self.coastlines),
+            ("borders", self.borders),
+            ("rivers", self.rivers),
+            ("lakes", self.lakes),
+            ("fills", self.fills),
+            ("graticule", self.graticule),
+            ("markers", self.markers),
+        ]
+        .into_iter()
+        .filter(|&(_, ratio)| ratio < minimum)
+        .collect()
+    }
+}
+
+/// Simple color grading applied as a post-processing pass
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorGrading {
+    /// Exposure adjustment in stops
+    pub exposure: f32,
+    /// Saturation multiplier; zero is grayscale
+    pub saturation: f32,
+    /// Tint multiplied into the colors, blended in by its alpha
+    pub tint: Color,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            saturation: 1.0,
+            tint: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        }
+    }
+}
+
+impl ColorGrading {
+    /// Returns true if grading leaves colors unchanged
+    #[must_use]
+    #[allow(clippy::float_cmp)]
+    pub fn is_identity(&self) -> bool {
+        self.exposure == 0.0 && self.saturation == 1.0 && self.tint.a == 0.0
+    }
+}
+
+const fn default_width() -> f32 {
+    1.0
+}
+
+const fn default_opacity() -> f32 {
+    1.0
+}
+
+const fn default_borders() -> LayerStyle {
+    LayerStyle {
+        color: Color::rgba(0.12, 0.15, 0.16, 0.6),
+        width: 0.75,
+        opacity: 1.0,
+        style: LineStyle::Dashed {
+            dash: 4.0,
+            gap: 2.0,
+        },
+    }
+}
+
+const fn default_rivers() -> LayerStyle {
+    LayerStyle::line(Color::rgba(0.1, 0.16, 0.19, 0.5), 1.0)
+}
+
+const fn default_lakes() -> LayerStyle {
+    LayerStyle::line(Color::rgba(0.06, 0.09, 0.11, 1.0), 1.0)
+}
+
+const fn default_fills() -> LayerStyle {
+    LayerStyle::line(Color::rgba(0.12, 0.15, 0.16, 0.6), 1.0)
+}
+
+const fn default_graticule() -> LayerStyle {
+    LayerStyle::line(Color::rgba(0.12, 0.15, 0.16, 0.25), 0.5)
+}
+
+/// Returns true if every channel is in `0.0..=1.0`
+fn is_valid_color(color: Color) -> bool {
+    [color.r, color.g, color.b, color.a]
+        .iter()
+        .all(|channel| (0.0..=1.0).contains(channel))
 }
 
 impl Theme {
+    /// Names of the built-in presets
+    pub const PRESETS: [&str; 5] = [
+        "dark-minimal",
+        "light-minimal",
+        "midnight-blue",
+        "blueprint",
+        "high-contrast",
+    ];
+
+    /// CSS custom properties [`Theme::set_css_property`] understands
+    pub const CSS_PROPERTIES: [&str; 8] = [
+        "--wmc-background",
+        "--wmc-coastline-color",
+        "--wmc-border-color",
+        "--wmc-river-color",
+        "--wmc-lake-color",
+        "--wmc-fill-color",
+        "--wmc-graticule-color",
+        "--wmc-marker-color",
+    ];
+
     /// Creates a dark minimal theme preset
     #[must_use]
     pub fn dark_minimal() -> Self {
         Self {
             name: "dark-minimal".to_string(),
             background: Color::rgba(0.043, 0.059, 0.063, 1.0), // #0b0f10
-            contour_color: Color::rgba(0.12, 0.15, 0.16, 0.6),
-            contour_width: 1.0,
-            marker_color: Color::rgba(0.3, 0.7, 0.8, 1.0),
-            marker_glow: 8.0,
+            coastlines: LayerStyle::line(Color::rgba(0.12, 0.15, 0.16, 0.6), 1.0),
+            borders: default_borders(),
+            rivers: default_rivers(),
+            lakes: default_lakes(),
+            fills: default_fills(),
+            graticule: default_graticule(),
+            markers: MarkerStyle {
+                color: Color::rgba(0.3, 0.7, 0.8, 1.0),
+                opacity: 1.0,
+                glow: 8.0,
+                scale: None,
+                zoom_radius: None,
+                zoom_opacity: None,
+            },
+            selection: SelectionStyle {
+                color: Some(Color::rgba(1.0, 0.75, 0.3, 1.0)),
+                ..SelectionStyle::default()
+            },
+            ripple: RippleStyle::default(),
             grain_intensity: 0.015,
+            vignette: 0.0,
+            grading: ColorGrading::default(),
+        }
+    }
+
+    /// Creates a light minimal theme preset: dark gray coastlines on
+    /// off-white with muted blue water
+    #[must_use]
+    pub fn light_minimal() -> Self {
+        let ink = Color::rgba(0.35, 0.38, 0.4, 1.0);
+
+        Self {
+            name: "light-minimal".to_string(),
+            background: Color::rgba(0.961, 0.961, 0.949, 1.0), // #f5f5f2
+            coastlines: LayerStyle {
+                opacity: 0.8,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            borders: LayerStyle {
+                color: ink,
+                opacity: 0.6,
+                ..default_borders()
+            },
+            rivers: LayerStyle::line(Color::rgba(0.55, 0.7, 0.82, 0.8), 1.0),
+            lakes: LayerStyle::line(Color::rgba(0.8, 0.87, 0.92, 1.0), 1.0),
+            fills: LayerStyle {
+                opacity: 0.3,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            graticule: LayerStyle {
+                opacity: 0.25,
+                ..LayerStyle::line(ink, 0.5)
+            },
+            markers: MarkerStyle {
+                color: Color::rgba(0.85, 0.33, 0.2, 1.0),
+                opacity: 1.0,
+                glow: 6.0,
+                scale: None,
+                zoom_radius: None,
+                zoom_opacity: None,
+            },
+            selection: SelectionStyle {
+                color: Some(Color::rgba(0.15, 0.45, 0.85, 1.0)),
+                ..SelectionStyle::default()
+            },
+            ripple: RippleStyle::default(),
+            grain_intensity: 0.0,
+            vignette: 0.0,
+            grading: ColorGrading::default(),
+        }
+    }
+
+    /// Creates a midnight blue theme preset: pale blue coastlines and amber
+    /// markers on deep navy
+    #[must_use]
+    pub fn midnight_blue() -> Self {
+        let ink = Color::rgba(0.35, 0.5, 0.75, 1.0);
+
+        Self {
+            name: "midnight-blue".to_string(),
+            background: Color::rgba(0.039, 0.078, 0.2, 1.0), // #0a1433
+            coastlines: LayerStyle {
+                opacity: 0.7,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            borders: LayerStyle {
+                color: ink,
+                opacity: 0.5,
+                ..default_borders()
+            },
+            rivers: LayerStyle::line(Color::rgba(0.2, 0.35, 0.6, 0.6), 1.0),
+            lakes: LayerStyle::line(Color::rgba(0.05, 0.11, 0.26, 1.0), 1.0),
+            fills: LayerStyle {
+                opacity: 0.3,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            graticule: LayerStyle {
+                opacity: 0.2,
+                ..LayerStyle::line(ink, 0.5)
+            },
+            markers: MarkerStyle {
+                color: Color::rgba(1.0, 0.75, 0.3, 1.0),
+                opacity: 1.0,
+                glow: 10.0,
+                scale: None,
+                zoom_radius: None,
+                zoom_opacity: None,
+            },
+            selection: SelectionStyle {
+                color: Some(Color::rgba(0.95, 0.97, 1.0, 1.0)),
+                ..SelectionStyle::default()
+            },
+            ripple: RippleStyle::default(),
+            grain_intensity: 0.01,
+            vignette: 0.3,
+            grading: ColorGrading::default(),
+        }
+    }
+
+    /// Creates a blueprint theme preset: white linework with a dashed
+    /// graticule on drafting blue
+    #[must_use]
+    pub fn blueprint() -> Self {
+        let ink = Color::rgba(0.92, 0.95, 1.0, 1.0);
+
+        Self {
+            name: "blueprint".to_string(),
+            background: Color::rgba(0.122, 0.306, 0.549, 1.0), // #1f4e8c
+            coastlines: LayerStyle {
+                opacity: 0.9,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            borders: LayerStyle {
+                color: ink,
+                opacity: 0.7,
+                ..default_borders()
+            },
+            rivers: LayerStyle {
+                opacity: 0.6,
+                ..LayerStyle::line(ink, 0.75)
+            },
+            lakes: LayerStyle::line(Color::rgba(0.1, 0.26, 0.48, 1.0), 1.0),
+            fills: LayerStyle {
+                opacity: 0.2,
+                ..LayerStyle::line(ink, 1.0)
+            },
+            graticule: LayerStyle {
+                color: ink,
+                width: 0.5,
+                opacity: 0.35,
+                style: LineStyle::Dashed {
+                    dash: 4.0,
+                    gap: 4.0,
+                },
+            },
+            markers: MarkerStyle {
+                color: Color::rgba(1.0, 1.0, 1.0, 1.0),
+                opacity: 1.0,
+                glow: 4.0,
+                scale: None,
+                zoom_radius: None,
+                zoom_opacity: None,
+            },
+            selection: SelectionStyle {
+                color: Some(Color::rgba(1.0, 0.85, 0.0, 1.0)),
+                ..SelectionStyle::default()
+            },
+            ripple: RippleStyle::default(),
+            grain_intensity: 0.02,
+            vignette: 0.2,
+            grading: ColorGrading::default(),
+        }
+    }
+
+    /// Creates a high contrast theme preset: opaque white coastlines and
+    /// yellow markers on black, without glow, grain or vignette
+    ///
+    /// Every element reaches [`ContrastReport::NON_TEXT_MINIMUM`] against
+    /// the background, the WCAG 2.1 level for graphical objects.
+    ///
+    /// ```
+    /// use wmc_core::theme::{ContrastReport, Theme};
+    ///
+    /// let theme = Theme::high_contrast();
+    /// assert!(theme.validate_contrast(ContrastReport::NON_TEXT_MINIMUM).is_ok());
+    /// assert!(Theme::dark_minimal().validate_contrast(ContrastReport::NON_TEXT_MINIMUM).is_err());
+    /// ```
+    #[must_use]
+    pub fn high_contrast() -> Self {
+        let white = Color::rgba(1.0, 1.0, 1.0, 1.0);
+
+        Self {
+            name: "high-contrast".to_string(),
+            background: Color::rgba(0.0, 0.0, 0.0, 1.0),
+            coastlines: LayerStyle::line(white, 1.5),
+            borders: LayerStyle {
+                color: white,
+                width: 1.0,
+                opacity: 1.0,
+                style: LineStyle::Dashed {
+                    dash: 4.0,
+                    gap: 2.0,
+                },
+            },
+            rivers: LayerStyle::line(Color::rgba(0.35, 0.75, 1.0, 1.0), 1.5),
+            lakes: LayerStyle::line(Color::rgba(0.0, 0.35, 0.8, 1.0), 1.5),
+            fills: LayerStyle::line(Color::rgba(0.45, 0.45, 0.45, 1.0), 1.5),
+            graticule: LayerStyle::line(Color::rgba(0.7, 0.7, 0.7, 1.0), 1.0),
+            markers: MarkerStyle {
+                color: Color::rgba(1.0, 0.85, 0.0, 1.0),
+                opacity: 1.0,
+                glow: 0.0,
+                scale: None,
+                zoom_radius: None,
+                zoom_opacity: None,
+            },
+            selection: SelectionStyle {
+                color: Some(Color::rgba(0.0, 0.9, 1.0, 1.0)),
+                ..SelectionStyle::default()
+            },
+            ripple: RippleStyle::default(),
+            grain_intensity: 0.0,
+            vignette: 0.0,
+            grading: ColorGrading::default(),
+        }
+    }
+
+    /// Returns the built-in preset with the given name, as in
+    /// [`Theme::PRESETS`]
+    ///
+    /// ```
+    /// use wmc_core::theme::Theme;
+    ///
+    /// assert_eq!(Theme::from_name("blueprint").unwrap().name, "blueprint");
+    /// assert!(Theme::from_name("neon").is_none());
+    /// for name in Theme::PRESETS {
+    ///     assert!(Theme::from_name(name).unwrap().validate().is_ok());
+    /// }
+    /// ```
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark-minimal" => Some(Self::dark_minimal()),
+            "light-minimal" => Some(Self::light_minimal()),
+            "midnight-blue" => Some(Self::midnight_blue()),
+            "blueprint" => Some(Self::blueprint()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
         }
     }
 
+    /// Sets the color a CSS custom property from [`Theme::CSS_PROPERTIES`]
+    /// stands for, e.g. `--wmc-background`, to the CSS color `value`
+    ///
+    /// Returns false, leaving the theme unchanged, for other properties and
+    /// empty values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] if the value isn't a
+    /// color [`Color::from_css`] understands
+    ///
+    /// ```
+    /// use wmc_core::theme::Theme;
+    ///
+    /// let mut theme = Theme::dark_minimal();
+    /// assert!(theme.set_css_property("--wmc-background", "#ffffff").unwrap());
+    /// assert_eq!(theme.background.r, 1.0);
+    /// assert!(!theme.set_css_property("--wmc-background", " ").unwrap());
+    /// assert!(!theme.set_css_property("--other", "#000").unwrap());
+    ///
+    /// // Malformed page values are reported, never a panic
+    /// assert!(theme.set_css_property("--wmc-background", "#abcéf").is_err());
+    /// assert_eq!(theme.background.r, 1.0);
+    /// ```
+    pub fn set_css_property(&mut self, name: &str, value: &str) -> Result<bool, CoreError> {
+        let target = match name {
+            "--wmc-background" => &mut self.background,
+            "--wmc-coastline-color" => &mut self.coastlines.color,
+            "--wmc-border-color" => &mut self.borders.color,
+            "--wmc-river-color" => &mut self.rivers.color,
+            "--wmc-lake-color" => &mut self.lakes.color,
+            "--wmc-fill-color" => &mut self.fills.color,
+            "--wmc-graticule-color" => &mut self.graticule.color,
+            "--wmc-marker-color" => &mut self.markers.color,
+            _ => return Ok(false),
+        };
+        if value.trim().is_empty() {
+            return Ok(false);
+        }
+
+        *target = Color::from_css(value)?;
+        Ok(true)
+    }
+
+    /// Returns the contrast ratio of every line, fill and marker color
+    /// against the background
+    ///
+    /// Translucent colors are blended over the background first, as they
+    /// are drawn.
+    #[must_use]
+    pub fn contrast_report(&self) -> ContrastReport {
+        let ratio = |color: Color| color.over(self.background).contrast_ratio(self.background);
+
+        ContrastReport {
+            coastlines: ratio(self.coastlines.effective_color()),
+            borders: ratio(self.borders.effective_color()),
+            rivers: ratio(self.rivers.effective_color()),
+            lakes: ratio(self.lakes.effective_color()),
+            fills: ratio(self.fills.effective_color()),
+            graticule: ratio(self.graticule.effective_color()),
+            markers: ratio(self.markers.effective_color()),
+        }
+    }
+
+    /// Validates that every element of [`Theme::contrast_report`] reaches
+    /// `minimum`, e.g. [`ContrastReport::NON_TEXT_MINIMUM`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] listing the elements
+    /// below the minimum
+    pub fn validate_contrast(&self, minimum: f32) -> Result<(), CoreError> {
+        let failures = self.contrast_report().failures(minimum);
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        let failures: Vec<String> = failures
+            .iter()
+            .map(|(name, ratio)| format!("{name} {ratio:.2}:1"))
+            .collect();
+        Err(CoreError::ThemeValidationFailed {
+            reason: format!("contrast below {minimum}:1 for {}", failures.join(", ")),
+        })
+    }
+
     /// Validates the theme data
     ///
     /// # Errors
@@ -48,18 +948,199 @@ impl Theme {
             });
         }
 
-        if self.contour_width <= 0.0 || !self.contour_width.is_finite() {
+        if !is_valid_color(self.background) {
             return Err(CoreError::ThemeValidationFailed {
-                reason: format!("invalid contour width: {}", self.contour_width),
+                reason: format!("invalid background: {:?}", self.background),
             });
         }
 
-        if self.marker_glow < 0.0 || !self.marker_glow.is_finite() {
+        for (name, style) in [
+            ("coastlines", &self.coastlines),
+            ("borders", &self.borders),
+            ("rivers", &self.rivers),
+            ("lakes", &self.lakes),
+            ("fills", &self.fills),
+            ("graticule", &self.graticule),
+        ] {
+            style.validate(name)?;
+        }
+        self.markers.validate()?;
+        self.selection.validate()?;
+        self.ripple.validate()?;
+
+        if self.grain_intensity < 0.0 || !self.grain_intensity.is_finite() {
             return Err(CoreError::ThemeValidationFailed {
-                reason: format!("invalid marker glow: {}", self.marker_glow),
+                reason: format!("invalid grain intensity: {}", self.grain_intensity),
+            });
+        }
+
+        if !(0.0..=1.0).contains(&self.vignette) {
+            return Err(CoreError::ThemeValidationFailed {
+                reason: format!("invalid vignette: {}", self.vignette),
+            });
+        }
+
+        if !self.grading.exposure.is_finite() {
+            return Err(CoreError::ThemeValidationFailed {
+                reason: format!("invalid exposure: {}", self.grading.exposure),
+            });
+        }
+
+        if self.grading.saturation < 0.0 || !self.grading.saturation.is_finite() {
+            return Err(CoreError::ThemeValidationFailed {
+                reason: format!("invalid saturation: {}", self.grading.saturation),
             });
         }
 
         Ok(())
     }
 }
+
+/// Chainable construction of a validated [`Theme`]
+///
+/// Starts from a preset, so only the differences need setting, and checks
+/// the result once in [`ThemeBuilder::build`].
+///
+/// ```
+/// use wmc_core::{
+///     marker::Color,
+///     theme::{LayerStyle, Theme, ThemeBuilder},
+/// };
+///
+/// let theme = ThemeBuilder::new("ocean")
+///     .background(Color::rgba(0.0, 0.1, 0.2, 1.0))
+///     .coastlines(LayerStyle::line(Color::rgba(0.6, 0.8, 1.0, 1.0), 1.5))
+///     .vignette(0.2)
+///     .build()
+///     .unwrap();
+/// assert_eq!(theme.name, "ocean");
+///
+/// assert!(ThemeBuilder::from(Theme::blueprint()).vignette(2.0).build().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThemeBuilder {
+    theme: Theme,
+}
+
+impl ThemeBuilder {
+    /// Starts a theme named `name` from the [`Theme::dark_minimal`] preset
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self::from(Theme::dark_minimal()).name(name)
+    }
+
+    /// Sets the theme name
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.theme.name = name.into();
+        self
+    }
+
+    /// Sets the background color
+    #[must_use]
+    pub const fn background(mut self, color: Color) -> Self {
+        self.theme.background = color;
+        self
+    }
+
+    /// Sets the coastline style
+    #[must_use]
+    pub const fn coastlines(mut self, style: LayerStyle) -> Self {
+        self.theme.coastlines = style;
+        self
+    }
+
+    /// Sets the default style of line layers added at runtime
+    #[must_use]
+    pub const fn borders(mut self, style: LayerStyle) -> Self {
+        self.theme.borders = style;
+        self
+    }
+
+    /// Sets the river style
+    #[must_use]
+    pub const fn rivers(mut self, style: LayerStyle) -> Self {
+        self.theme.rivers = style;
+        self
+    }
+
+    /// Sets the lake style
+    #[must_use]
+    pub const fn lakes(mut self, style: LayerStyle) -> Self {
+        self.theme.lakes = style;
+        self
+    }
+
+    /// Sets the default style of fill layers added at runtime
+    #[must_use]
+    pub const fn fills(mut self, style: LayerStyle) -> Self {
+        self.theme.fills = style;
+        self
+    }
+
+    /// Sets the graticule style
+    #[must_use]
+    pub const fn graticule(mut self, style: LayerStyle) -> Self {
+        self.theme.graticule = style;
+        self
+    }
+
+    /// Sets the style of markers without their own color
+    #[must_use]
+    pub const fn markers(mut self, style: MarkerStyle) -> Self {
+        self.theme.markers = style;
+        self
+    }
+
+    /// Sets the selected marker style
+    #[must_use]
+    pub const fn selection(mut self, style: SelectionStyle) -> Self {
+        self.theme.selection = style;
+        self
+    }
+
+    /// Sets the style of marker ripples
+    #[must_use]
+    pub const fn ripple(mut self, style: RippleStyle) -> Self {
+        self.theme.ripple = style;
+        self
+    }
+
+    /// Sets the film grain amplitude; zero disables grain
+    #[must_use]
+    pub const fn grain_intensity(mut self, intensity: f32) -> Self {
+        self.theme.grain_intensity = intensity;
+        self
+    }
+
+    /// Sets the vignette strength in `0.0..=1.0`; zero disables it
+    #[must_use]
+    pub const fn vignette(mut self, vignette: f32) -> Self {
+        self.theme.vignette = vignette;
+        self
+    }
+
+    /// Sets the color grading
+    #[must_use]
+    pub const fn grading(mut self, grading: ColorGrading) -> Self {
+        self.theme.grading = grading;
+        self
+    }
+
+    /// Returns the theme if it passes [`Theme::validate`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::ThemeValidationFailed`] describing the first
+    /// invalid value
+    pub fn build(self) -> Result<Theme, CoreError> {
+        self.theme.validate()?;
+        Ok(self.theme)
+    }
+}
+
+impl From<Theme> for ThemeBuilder {
+    fn from(theme: Theme) -> Self {
+        Self { theme }
+    }
+}
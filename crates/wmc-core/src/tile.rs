@@ -0,0 +1,158 @@
+/// Address of a Web Mercator (XYZ) map tile
+///
+/// At zoom `z` the world is split into `2^z` by `2^z` tiles, numbered from
+/// the top left corner, as used by `OpenStreetMap`-style tile servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    /// Zoom level
+    pub z: u8,
+    /// Column, from the antimeridian eastwards
+    pub x: u32,
+    /// Row, from the north edge southwards
+    pub y: u32,
+}
+
+impl TileId {
+    /// Highest supported zoom level
+    pub const MAX_ZOOM: u8 = 24;
+
+    /// Creates a tile address
+    #[must_use]
+    pub const fn new(z: u8, x: u32, y: u32) -> Self {
+        Self { z, x, y }
+    }
+
+    /// Returns the lowest zoom at which a `width` x `height` view of the whole
+    /// world shows tiles at no less than `tile_size` pixels, capped at
+    /// `max_zoom`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wmc_core::tile::TileId;
+    ///
+    /// assert_eq!(TileId::zoom_for(256.0, 256.0, 256.0, 19), 0);
+    /// assert_eq!(TileId::zoom_for(1200.0, 600.0, 256.0, 19), 3);
+    /// assert_eq!(TileId::zoom_for(1200.0, 600.0, 256.0, 2), 2);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn zoom_for(width: f64, height: f64, tile_size: f64, max_zoom: u8) -> u8 {
+        let span = width.max(height) / tile_size;
+        if !span.is_finite() || span <= 1.0 {
+            return 0;
+        }
+
+        (span.log2().ceil() as u8).min(max_zoom.min(Self::MAX_ZOOM))
+    }
+
+    /// Returns every tile of zoom level `z`, row by row
+    pub fn level(z: u8) -> impl Iterator<Item = Self> {
+        let count = 1_u32 << z.min(Self::MAX_ZOOM);
+
+        (0..count).flat_map(move |y| (0..count).map(move |x| Self::new(z, x, y)))
+    }
+
+    /// Returns the tiles of zoom level `z` overlapping `[min_x, min_y, max_x,
+    /// max_y]`, row by row, when the whole world spans `width` x `height`
+    /// pixels
+    ///
+    /// Parts of the rectangle outside the world are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wmc_core::tile::TileId;
+    ///
+    /// // The top left quarter of a 512 pixel world at zoom 2
+    /// let tiles: Vec<_> = TileId::covering(2, [0.0, 0.0, 255.0, 255.0], 512.0, 512.0).collect();
+    /// assert_eq!(tiles.len(), 4);
+    /// assert!(tiles.contains(&TileId::new(2, 1, 1)));
+    ///
+    /// // Off the world entirely
+    /// assert_eq!(TileId::covering(2, [-90.0, 0.0, -10.0, 50.0], 512.0, 512.0).count(), 0);
+    /// ```
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn covering(
+        z: u8,
+        [min_x, min_y, max_x, max_y]: [f64; 4],
+        width: f64,
+        height: f64,
+    ) -> impl Iterator<Item = Self> {
+        let count = 1_u32 << z.min(Self::MAX_ZOOM);
+        // Tile columns or rows from the one holding `min` to the one holding
+        // `max`, empty if the span misses the world
+        let span = |min: f64, max: f64, size: f64| {
+            let scale = f64::from(count) / size;
+            let (first, last) = ((min * scale).floor(), (max * scale).floor());
+            if last < 0.0 || first >= f64::from(count) || first > last {
+                0..0
+            } else {
+                first.max(0.0) as u32..(last as u32).min(count - 1) + 1
+            }
+        };
+        let columns = span(min_x, max_x, width);
+        let rows = span(min_y, max_y, height);
+
+        rows.flat_map(move |y| columns.clone().map(move |x| Self::new(z, x, y)))
+    }
+
+    /// Returns the tile one zoom level up containing this one
+    #[must_use]
+    pub const fn parent(self) -> Option<Self> {
+        match self.z {
+            0 => None,
+            z => Some(Self::new(z - 1, self.x / 2, self.y / 2)),
+        }
+    }
+
+    /// Returns the tile's pixel rectangle `[left, top, right, bottom]` when the
+    /// whole world spans `width` x `height` pixels
+    #[must_use]
+    pub fn bounds(self, width: f64, height: f64) -> [f64; 4] {
+        let count = f64::from(1_u32 << self.z.min(Self::MAX_ZOOM));
+        let (tile_width, tile_height) = (width / count, height / count);
+        let (left, top) = (
+            f64::from(self.x) * tile_width,
+            f64::from(self.y) * tile_height,
+        );
+
+        [left, top, left + tile_width, top + tile_height]
+    }
+
+    /// Fills in a tile URL template
+    ///
+    /// `{z}`, `{x}` and `{y}` are replaced by the tile address and `{s}` by
+    /// one of `subdomains`, spreading tiles evenly across them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wmc_core::tile::TileId;
+    ///
+    /// let tile = TileId::new(3, 4, 2);
+    /// let url = tile.url(
+    ///     "https://{s}.tile.openstreetmap.org/{z}/{x}/{y}.png",
+    ///     &["a", "b", "c"],
+    /// );
+    /// assert_eq!(url, "https://a.tile.openstreetmap.org/3/4/2.png");
+    /// ```
+    #[must_use]
+    pub fn url<S: AsRef<str>>(self, template: &str, subdomains: &[S]) -> String {
+        let mut url = template
+            .replace("{z}", &self.z.to_string())
+            .replace("{x}", &self.x.to_string())
+            .replace("{y}", &self.y.to_string());
+
+        if !subdomains.is_empty() {
+            let index = (self.x + self.y) as usize % subdomains.len();
+            url = url.replace("{s}", subdomains[index].as_ref());
+        }
+
+        url
+    }
+}
@@ -1,4 +1,19 @@
-use crate::{error::CoreError, projection::GeoCoord};
+use geojson::{JsonObject, JsonValue};
+
+use crate::{
+    error::CoreError,
+    geodesy::{self, EARTH_RADIUS},
+    index::SpatialIndex,
+    projection::{GeoBounds, GeoCoord},
+};
+
+/// Highest latitude in degrees graticule parallels reach, the Web Mercator
+/// range
+pub const GRATICULE_MAX_LAT: i32 = 85;
+
+/// Radius in meters the nearest point search starts from, quadrupled until
+/// the nearest point is confirmed
+const NEAREST_START_RADIUS: f64 = 100_000.0;
 
 /// World map topology data
 #[derive(Debug, Clone)]
@@ -12,6 +27,132 @@ pub struct WorldTopology {
 pub struct Feature {
     /// Feature geometry
     pub geometry: Geometry,
+    /// `GeoJSON` properties, empty when the feature has none
+    pub properties: JsonObject,
+}
+
+impl Feature {
+    /// Returns the string property `key`, if present
+    #[must_use]
+    pub fn property_str(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).and_then(JsonValue::as_str)
+    }
+
+    /// Returns the numeric property `key`, if present
+    #[must_use]
+    pub fn property_f64(&self, key: &str) -> Option<f64> {
+        self.properties.get(key).and_then(JsonValue::as_f64)
+    }
+
+    /// Returns the area-weighted centroid of the feature's polygons, holes
+    /// taken out, or `None` if they have no area
+    ///
+    /// Computed in longitude and latitude, which suits centering the view
+    /// on the feature. The centroid of a concave or multi-part feature may
+    /// lie outside it, see [`Feature::pole_of_inaccessibility`].
+    ///
+    /// ```
+    /// use wmc_core::topology::WorldTopology;
+    ///
+    /// let topology = WorldTopology::from_geojson(r#"{"type":"FeatureCollection","features":[
+    ///     {"type":"Feature","properties":{},"geometry":{"type":"MultiPolygon","coordinates":[
+    ///         [[[0,0],[2,0],[2,2],[0,2],[0,0]]],
+    ///         [[[10,0],[12,0],[12,2],[10,2],[10,0]]]
+    ///     ]}}
+    /// ]}"#).unwrap();
+    ///
+    /// let centroid = topology.features[0].centroid().unwrap();
+    /// assert!((centroid.lon - 6.0).abs() < 1e-9 && (centroid.lat - 1.0).abs() < 1e-9);
+    /// ```
+    #[must_use]
+    pub fn centroid(&self) -> Option<GeoCoord> {
+        let (mut area, mut lon, mut lat) = (0.0, 0.0, 0.0);
+        for rings in self.geometry.polygons() {
+            for (index, ring) in rings.iter().enumerate() {
+                let Some((centroid, ring_area)) = ring_centroid(ring) else {
+                    continue;
+                };
+                let weight = if index == 0 { ring_area } else { -ring_area };
+                area += weight;
+                lon = centroid.lon.mul_add(weight, lon);
+                lat = centroid.lat.mul_add(weight, lat);
+            }
+        }
+
+        area.is_normal().then(|| GeoCoord {
+            lat: lat / area,
+            lon: lon / area,
+        })
+    }
+
+    /// Returns the ground area of the feature's polygons, holes taken out,
+    /// in square kilometers
+    ///
+    /// Measured on the sphere with [`geodesy::ring_area`], so it is true to
+    /// size whatever the map projection. Line geometries have no area.
+    ///
+    /// ```
+    /// use wmc_core::topology::WorldTopology;
+    ///
+    /// let topology = WorldTopology::from_geojson(r#"{"type":"FeatureCollection","features":[
+    ///     {"type":"Feature","properties":{},"geometry":{"type":"Polygon","coordinates":[
+    ///         [[0,0],[2,0],[2,2],[0,2],[0,0]],
+    ///         [[0.5,0.5],[0.5,1.5],[1.5,1.5],[1.5,0.5],[0.5,0.5]]
+    ///     ]}}
+    /// ]}"#).unwrap();
+    ///
+    /// // Three quarters of a 2° square of about 49 450 km²
+    /// let area = topology.features[0].area_km2();
+    /// assert!((area - 37_090.0).abs() < 10.0);
+    /// ```
+    #[must_use]
+    pub fn area_km2(&self) -> f64 {
+        let square_meters: f64 = self
+            .geometry
+            .polygons()
+            .into_iter()
+            .flat_map(|rings| {
+                rings.iter().enumerate().map(|(index, ring)| {
+                    let area = geodesy::ring_area(ring);
+                    if index == 0 { area } else { -area }
+                })
+            })
+            .sum();
+        square_meters / 1e6
+    }
+
+    /// Returns the point of the feature's largest polygon farthest from its
+    /// edges, found to within `precision` degrees
+    ///
+    /// Unlike the centroid it always lies inside the polygon, clear of its
+    /// holes, which makes it the spot for a label. Distances are measured
+    /// in longitude and latitude. Returns `None` for features without area.
+    ///
+    /// ```
+    /// use wmc_core::topology::WorldTopology;
+    ///
+    /// // A U shape, whose centroid falls in the gap between its arms
+    /// let topology = WorldTopology::from_geojson(r#"{"type":"FeatureCollection","features":[
+    ///     {"type":"Feature","properties":{},"geometry":{"type":"Polygon","coordinates":[
+    ///         [[0,0],[30,0],[30,30],[20,30],[20,10],[10,10],[10,30],[0,30],[0,0]]
+    ///     ]}}
+    /// ]}"#).unwrap();
+    /// let feature = &topology.features[0];
+    ///
+    /// assert!(!feature.geometry.contains(feature.centroid().unwrap()));
+    /// assert!(feature.geometry.contains(feature.pole_of_inaccessibility(0.01).unwrap()));
+    /// ```
+    #[must_use]
+    pub fn pole_of_inaccessibility(&self, precision: f64) -> Option<GeoCoord> {
+        let rings = self
+            .geometry
+            .polygons()
+            .into_iter()
+            .filter_map(|rings| Some((rings, ring_centroid(rings.first()?)?.1)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?
+            .0;
+        polygon_pole(rings, precision)
+    }
 }
 
 /// Geometric representation of geographic features
@@ -21,6 +162,358 @@ pub enum Geometry {
     LineString(Vec<GeoCoord>),
     /// Multiple line strings
     MultiLineString(Vec<Vec<GeoCoord>>),
+    /// Polygon as an outer ring followed by optional hole rings
+    Polygon(Vec<Vec<GeoCoord>>),
+    /// Multiple polygons
+    MultiPolygon(Vec<Vec<Vec<GeoCoord>>>),
+}
+
+impl Geometry {
+    /// Returns every line string and polygon ring of the geometry
+    #[must_use]
+    pub fn lines(&self) -> Vec<&[GeoCoord]> {
+        match self {
+            Self::LineString(points) => vec![points.as_slice()],
+            Self::MultiLineString(lines) | Self::Polygon(lines) => {
+                lines.iter().map(Vec::as_slice).collect()
+            },
+            Self::MultiPolygon(polygons) => polygons
+                .iter()
+                .flat_map(|rings| rings.iter().map(Vec::as_slice))
+                .collect(),
+        }
+    }
+
+    /// Returns the polygons of the geometry, each as a list of rings
+    ///
+    /// Line geometries have no area and yield an empty list.
+    #[must_use]
+    pub fn polygons(&self) -> Vec<&[Vec<GeoCoord>]> {
+        match self {
+            Self::LineString(_) | Self::MultiLineString(_) => Vec::new(),
+            Self::Polygon(rings) => vec![rings.as_slice()],
+            Self::MultiPolygon(polygons) => polygons.iter().map(Vec::as_slice).collect(),
+        }
+    }
+
+    /// Returns the geographic box covering the geometry, or `None` if it
+    /// has no points
+    ///
+    /// Each line is expected not to cross the antimeridian, as `GeoJSON`
+    /// splits shapes along it; the boxes of the lines are then joined the
+    /// narrowest way, so a country split at the antimeridian gets a box
+    /// crossing it rather than one spanning the globe.
+    ///
+    /// ```
+    /// use wmc_core::topology::WorldTopology;
+    ///
+    /// let topology = WorldTopology::from_geojson(
+    ///     r#"{"type": "FeatureCollection", "features": [{
+    ///         "type": "Feature", "properties": {},
+    ///         "geometry": {"type": "MultiLineString", "coordinates": [
+    ///             [[170, 60], [180, 65]], [[-180, 65], [-170, 70]]
+    ///         ]}
+    ///     }]}"#,
+    /// )?;
+    ///
+    /// let bounds = topology.features[0].geometry.bounds().unwrap();
+    /// assert!(bounds.crosses_antimeridian());
+    /// assert_eq!((bounds.west, bounds.east), (170.0, -170.0));
+    /// # Ok::<(), wmc_core::CoreError>(())
+    /// ```
+    #[must_use]
+    pub fn bounds(&self) -> Option<GeoBounds> {
+        self.lines()
+            .into_iter()
+            .filter_map(|line| {
+                line.iter()
+                    .map(|point| GeoBounds {
+                        west: point.lon,
+                        south: point.lat,
+                        east: point.lon,
+                        north: point.lat,
+                    })
+                    .reduce(|a, b| GeoBounds {
+                        west: a.west.min(b.west),
+                        south: a.south.min(b.south),
+                        east: a.east.max(b.east),
+                        north: a.north.max(b.north),
+                    })
+            })
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Returns true if `coord` lies inside one of the polygons of the
+    /// geometry, outside its holes
+    ///
+    /// Rings are tested in longitude and latitude with the even-odd rule, so
+    /// polygons crossing the antimeridian must be split along it. Line
+    /// geometries contain nothing.
+    #[must_use]
+    pub fn contains(&self, coord: GeoCoord) -> bool {
+        self.polygons().into_iter().any(|rings| {
+            rings
+                .iter()
+                .filter(|ring| ring_crosses(ring, coord))
+                .count()
+                % 2
+                == 1
+        })
+    }
+}
+
+/// Returns the positions of the features in `index` whose bounds intersect
+/// `bounds`, ascending
+fn positions_in(index: &SpatialIndex<usize>, bounds: GeoBounds) -> Vec<usize> {
+    let mut positions = Vec::new();
+    for part in bounds.boxes() {
+        index.query_each(part, |&position| positions.push(position));
+    }
+    // Features crossing the antimeridian may match both halves
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+/// Returns the points of `feature`'s lines nearest to `coord`, one per
+/// segment, with `position` and their distances in meters
+fn nearest_on_feature(
+    feature: &Feature,
+    coord: GeoCoord,
+    position: usize,
+) -> impl Iterator<Item = (GeoCoord, usize, f64)> {
+    feature.geometry.lines().into_iter().flat_map(move |line| {
+        let segments = line.windows(2).map(|pair| (pair[0], pair[1]));
+        // Single points count as segments of no length
+        let single = (line.len() == 1).then(|| (line[0], line[0]));
+        segments.chain(single).map(move |(a, b)| {
+            let point = geodesy::nearest_on_segment(coord, a, b);
+            (point, position, geodesy::distance_haversine(coord, point))
+        })
+    })
+}
+
+/// Returns a box covering every point within `radius` meters of `center`,
+/// and whether it covers the whole world
+fn cap_bounds(center: GeoCoord, radius: f64) -> (GeoBounds, bool) {
+    let angle = radius / EARTH_RADIUS;
+    let south = (center.lat - angle.to_degrees()).max(-90.0);
+    let north = (center.lat + angle.to_degrees()).min(90.0);
+
+    // The cap spans every longitude once it reaches a pole
+    let reach = angle.sin() / center.lat.to_radians().cos();
+    if angle >= std::f64::consts::FRAC_PI_2 || reach >= 1.0 {
+        let bounds = GeoBounds {
+            west: -180.0,
+            south,
+            east: 180.0,
+            north,
+        };
+        return (bounds, south <= -90.0 && north >= 90.0);
+    }
+
+    let span = reach.asin().to_degrees();
+    let wrap = |lon: f64| {
+        if lon < -180.0 {
+            lon + 360.0
+        } else if lon > 180.0 {
+            lon - 360.0
+        } else {
+            lon
+        }
+    };
+    let bounds = GeoBounds {
+        west: wrap(center.lon - span),
+        south,
+        east: wrap(center.lon + span),
+        north,
+    };
+    (bounds, false)
+}
+
+/// Returns true if a ray from `coord` towards the east crosses `ring` an
+/// odd number of times
+fn ring_crosses(ring: &[GeoCoord], coord: GeoCoord) -> bool {
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        if (a.lat > coord.lat) != (b.lat > coord.lat) {
+            let lon = ((coord.lat - a.lat) / (b.lat - a.lat)).mul_add(b.lon - a.lon, a.lon);
+            if coord.lon < lon {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Returns the centroid and unsigned area of a closed ring in degrees, or
+/// `None` if it has no area
+fn ring_centroid(ring: &[GeoCoord]) -> Option<(GeoCoord, f64)> {
+    let (mut twice_area, mut lon, mut lat) = (0.0, 0.0, 0.0);
+
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let cross = a.lon.mul_add(b.lat, -(b.lon * a.lat));
+        twice_area += cross;
+        lon = (a.lon + b.lon).mul_add(cross, lon);
+        lat = (a.lat + b.lat).mul_add(cross, lat);
+    }
+
+    if !twice_area.is_normal() {
+        return None;
+    }
+
+    Some((
+        GeoCoord {
+            lat: lat / (3.0 * twice_area),
+            lon: lon / (3.0 * twice_area),
+        },
+        twice_area.abs() / 2.0,
+    ))
+}
+
+/// Square of longitude and latitude searched by [`polygon_pole()`]
+struct PoleCell {
+    center: GeoCoord,
+    half: f64,
+    /// Signed distance from the center to the polygon edges, negative
+    /// outside
+    distance: f64,
+}
+
+impl PoleCell {
+    fn new(center: GeoCoord, half: f64, rings: &[Vec<GeoCoord>]) -> Self {
+        Self {
+            center,
+            half,
+            distance: edge_distance(center, rings),
+        }
+    }
+
+    /// Returns the farthest any point of the cell can be from the edges
+    const fn potential(&self) -> f64 {
+        self.half.mul_add(std::f64::consts::SQRT_2, self.distance)
+    }
+}
+
+impl PartialEq for PoleCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for PoleCell {}
+
+impl PartialOrd for PoleCell {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PoleCell {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.potential().total_cmp(&other.potential())
+    }
+}
+
+/// Returns the point of a polygon, given as its outer ring followed by hole
+/// rings, farthest from its edges, found to within `precision` degrees
+///
+/// Searches a quadtree of cells, most promising first, as the polylabel
+/// algorithm does. See [`Feature::pole_of_inaccessibility`].
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn polygon_pole(rings: &[Vec<GeoCoord>], precision: f64) -> Option<GeoCoord> {
+    let outer = rings.first()?;
+    let (west, south, east, north) = outer.iter().fold(
+        (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(west, south, east, north), point| {
+            (
+                west.min(point.lon),
+                south.min(point.lat),
+                east.max(point.lon),
+                north.max(point.lat),
+            )
+        },
+    );
+    let size = (east - west).min(north - south);
+    if !size.is_normal() {
+        return None;
+    }
+
+    let half = size / 2.0;
+    let mut queue = std::collections::BinaryHeap::new();
+    let cells = |span: f64| (span / size).ceil() as u32;
+    for column in 0..cells(east - west) {
+        for row in 0..cells(north - south) {
+            let center = GeoCoord {
+                lat: f64::from(row).mul_add(size, south + half),
+                lon: f64::from(column).mul_add(size, west + half),
+            };
+            queue.push(PoleCell::new(center, half, rings));
+        }
+    }
+
+    let mut best = PoleCell::new(ring_centroid(outer)?.0, 0.0, rings);
+    let middle = GeoCoord {
+        lat: f64::midpoint(south, north),
+        lon: f64::midpoint(west, east),
+    };
+    let middle = PoleCell::new(middle, 0.0, rings);
+    if middle.distance > best.distance {
+        best = middle;
+    }
+
+    let precision = precision.max(f64::EPSILON);
+    while let Some(cell) = queue.pop() {
+        let (center, potential) = (cell.center, cell.potential());
+        let half = cell.half / 2.0;
+        if cell.distance > best.distance {
+            best = cell;
+        }
+        if potential - best.distance <= precision {
+            continue;
+        }
+
+        for (d_lon, d_lat) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let center = GeoCoord {
+                lat: half.mul_add(d_lat, center.lat),
+                lon: half.mul_add(d_lon, center.lon),
+            };
+            queue.push(PoleCell::new(center, half, rings));
+        }
+    }
+
+    Some(best.center)
+}
+
+/// Returns the distance in degrees from `coord` to the nearest polygon
+/// edge, negative outside the polygon or inside a hole
+fn edge_distance(coord: GeoCoord, rings: &[Vec<GeoCoord>]) -> f64 {
+    let mut inside = false;
+    let mut nearest = f64::INFINITY;
+
+    for ring in rings {
+        inside ^= ring_crosses(ring, coord);
+        for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+            let (dx, dy) = (b.lon - a.lon, b.lat - a.lat);
+            let length = dx.mul_add(dx, dy * dy);
+            let t = if length > 0.0 {
+                ((coord.lon - a.lon).mul_add(dx, (coord.lat - a.lat) * dy) / length)
+                    .clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (x, y) = (t.mul_add(dx, a.lon), t.mul_add(dy, a.lat));
+            nearest = nearest.min((coord.lon - x).hypot(coord.lat - y));
+        }
+    }
+
+    if inside { nearest } else { -nearest }
 }
 
 impl WorldTopology {
@@ -40,8 +533,7 @@ impl WorldTopology {
             geojson::GeoJson::FeatureCollection(fc) => fc
                 .features
                 .into_iter()
-                .filter_map(|f| f.geometry)
-                .filter_map(|g| Self::parse_geometry(g).ok())
+                .filter_map(|f| Self::parse_feature(f).ok())
                 .collect(),
             _ => {
                 return Err(CoreError::TopologyParseError {
@@ -53,74 +545,34 @@ impl WorldTopology {
         Ok(Self { features })
     }
 
-    fn parse_geometry(geometry: geojson::Geometry) -> Result<Feature, CoreError> {
+    fn parse_feature(feature: geojson::Feature) -> Result<Feature, CoreError> {
+        let geometry = feature
+            .geometry
+            .ok_or_else(|| CoreError::TopologyParseError {
+                details: "Feature without geometry".to_string(),
+            })?;
+
+        Ok(Feature {
+            geometry: Self::parse_geometry(geometry)?,
+            properties: feature.properties.unwrap_or_default(),
+        })
+    }
+
+    fn parse_geometry(geometry: geojson::Geometry) -> Result<Geometry, CoreError> {
         let geom = match geometry.value {
-            geojson::Value::LineString(coords) => {
-                let points = coords
-                    .into_iter()
-                    .filter_map(|c| {
-                        if c.len() >= 2 {
-                            GeoCoord::new(c[1], c[0]).ok()
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                Geometry::LineString(points)
-            },
+            geojson::Value::LineString(coords) => Geometry::LineString(parse_line(coords)),
             geojson::Value::MultiLineString(lines) => {
-                let multi = lines
-                    .into_iter()
-                    .map(|line| {
-                        line.into_iter()
-                            .filter_map(|c| {
-                                if c.len() >= 2 {
-                                    GeoCoord::new(c[1], c[0]).ok()
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect()
-                    })
-                    .collect();
-                Geometry::MultiLineString(multi)
+                Geometry::MultiLineString(lines.into_iter().map(parse_line).collect())
             },
             geojson::Value::Polygon(rings) => {
-                let multi = rings
-                    .into_iter()
-                    .map(|ring| {
-                        ring.into_iter()
-                            .filter_map(|c| {
-                                if c.len() >= 2 {
-                                    GeoCoord::new(c[1], c[0]).ok()
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect()
-                    })
-                    .collect();
-                Geometry::MultiLineString(multi)
+                Geometry::Polygon(rings.into_iter().map(parse_line).collect())
             },
-            geojson::Value::MultiPolygon(polygons) => {
-                let multi = polygons
+            geojson::Value::MultiPolygon(polygons) => Geometry::MultiPolygon(
+                polygons
                     .into_iter()
-                    .flat_map(|poly| {
-                        poly.into_iter().map(|ring| {
-                            ring.into_iter()
-                                .filter_map(|c| {
-                                    if c.len() >= 2 {
-                                        GeoCoord::new(c[1], c[0]).ok()
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
-                        })
-                    })
-                    .collect();
-                Geometry::MultiLineString(multi)
-            },
+                    .map(|rings| rings.into_iter().map(parse_line).collect())
+                    .collect(),
+            ),
             _ => {
                 return Err(CoreError::TopologyParseError {
                     details: "Unsupported geometry type".to_string(),
@@ -128,19 +580,229 @@ impl WorldTopology {
             },
         };
 
-        Ok(Feature { geometry: geom })
+        Ok(geom)
     }
 
-    /// Returns the total number of line strings in the topology
+    /// Builds a graticule of meridians and parallels every `step` degrees
+    ///
+    /// Lines are sampled every degree so they bend correctly under curved
+    /// projections. Latitudes are limited to ±85°, the Web Mercator range.
+    /// A non-positive or non-finite `step` yields an empty topology.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wmc_core::topology::WorldTopology;
+    ///
+    /// let graticule = WorldTopology::graticule(30.0);
+    /// // 13 meridians from -180° to 180° and 5 parallels from -60° to 60°
+    /// assert_eq!(graticule.line_count(), 18);
+    /// ```
     #[must_use]
-    pub fn line_count(&self) -> usize {
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn graticule(step: f64) -> Self {
+        if !step.is_finite() || step <= 0.0 {
+            return Self {
+                features: Vec::new(),
+            };
+        }
+
+        let steps = |limit: i32| (f64::from(limit) / step).floor() as i32;
+        let line = |points: Vec<GeoCoord>| Feature {
+            geometry: Geometry::LineString(points),
+            properties: JsonObject::new(),
+        };
+
+        let meridians = (-steps(180)..=steps(180)).map(|i| {
+            let lon = f64::from(i) * step;
+            line(
+                (-GRATICULE_MAX_LAT..=GRATICULE_MAX_LAT)
+                    .map(|lat| GeoCoord {
+                        lat: f64::from(lat),
+                        lon,
+                    })
+                    .collect(),
+            )
+        });
+
+        let parallels = (-steps(GRATICULE_MAX_LAT)..=steps(GRATICULE_MAX_LAT)).map(|i| {
+            let lat = f64::from(i) * step;
+            line(
+                (-180..=180)
+                    .map(|lon| GeoCoord {
+                        lat,
+                        lon: f64::from(lon),
+                    })
+                    .collect(),
+            )
+        });
+
+        Self {
+            features: meridians.chain(parallels).collect(),
+        }
+    }
+
+    /// Indexes the features by their geographic bounds, see
+    /// [`Geometry::bounds`], storing their positions in
+    /// [`features`](Self::features)
+    ///
+    /// Bounds crossing the antimeridian are stored as their two halves, see
+    /// [`GeoBounds::boxes`].
+    #[must_use]
+    pub fn spatial_index(&self) -> SpatialIndex<usize> {
         self.features
             .iter()
-            .map(|f| match &f.geometry {
-                Geometry::LineString(_) => 1,
-                Geometry::MultiLineString(lines) => lines.len(),
-            })
-            .sum()
+            .enumerate()
+            .filter_map(|(index, feature)| Some((feature.geometry.bounds()?, index)))
+            .flat_map(|(bounds, index)| bounds.boxes().map(move |part| (part, index)))
+            .collect()
+    }
+
+    /// Returns the features whose bounds intersect `bounds`, in the order of
+    /// [`features`](Self::features), using `index` from
+    /// [`Self::spatial_index`]
+    ///
+    /// Boxes crossing the antimeridian find features on both sides of it.
+    ///
+    /// ```
+    /// use wmc_core::{projection::GeoBounds, topology::WorldTopology};
+    ///
+    /// let topology = WorldTopology::from_geojson(
+    ///     r#"{"type": "FeatureCollection", "features": [
+    ///         {"type": "Feature", "properties": {"name": "Fiji"},
+    ///          "geometry": {"type": "LineString", "coordinates": [[177, -18], [179, -17]]}},
+    ///         {"type": "Feature", "properties": {"name": "Samoa"},
+    ///          "geometry": {"type": "LineString", "coordinates": [[-172, -14], [-171, -13]]}},
+    ///         {"type": "Feature", "properties": {"name": "Kenya"},
+    ///          "geometry": {"type": "LineString", "coordinates": [[34, -4], [41, 4]]}}
+    ///     ]}"#,
+    /// )?;
+    /// let index = topology.spatial_index();
+    ///
+    /// let pacific = GeoBounds::new(170.0, -30.0, -160.0, 0.0)?;
+    /// let names: Vec<_> = topology
+    ///     .features_in(&index, pacific)
+    ///     .filter_map(|feature| feature.property_str("name"))
+    ///     .collect();
+    /// assert_eq!(names, ["Fiji", "Samoa"]);
+    /// # Ok::<(), wmc_core::CoreError>(())
+    /// ```
+    pub fn features_in<'a>(
+        &'a self,
+        index: &SpatialIndex<usize>,
+        bounds: GeoBounds,
+    ) -> impl Iterator<Item = &'a Feature> {
+        positions_in(index, bounds)
+            .into_iter()
+            .filter_map(|position| self.features.get(position))
+    }
+
+    /// Returns the features whose polygons contain `coord`, using `index`
+    /// from [`Self::spatial_index`] to test only those whose bounds do
+    ///
+    /// ```
+    /// use wmc_core::{projection::GeoCoord, topology::WorldTopology};
+    ///
+    /// let topology = WorldTopology::from_geojson(
+    ///     r#"{"type": "FeatureCollection", "features": [{
+    ///         "type": "Feature",
+    ///         "properties": {"name": "Square"},
+    ///         "geometry": {"type": "Polygon", "coordinates": [
+    ///             [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+    ///             [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]]
+    ///         ]}
+    ///     }]}"#,
+    /// )?;
+    /// let index = topology.spatial_index();
+    /// let names = |lat, lon| {
+    ///     topology
+    ///         .features_at(&index, GeoCoord { lat, lon })
+    ///         .filter_map(|feature| feature.property_str("name"))
+    ///         .collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(names(2.0, 2.0), ["Square"]);
+    /// // Holes and the outside contain nothing
+    /// assert!(names(5.0, 5.0).is_empty());
+    /// assert!(names(20.0, 5.0).is_empty());
+    /// # Ok::<(), wmc_core::CoreError>(())
+    /// ```
+    pub fn features_at<'a>(
+        &'a self,
+        index: &'a SpatialIndex<usize>,
+        coord: GeoCoord,
+    ) -> impl Iterator<Item = &'a Feature> {
+        index
+            .query([coord.lon, coord.lat, coord.lon, coord.lat])
+            .filter_map(|&position| self.features.get(position))
+            .filter(move |feature| feature.geometry.contains(coord))
+    }
+
+    /// Returns the point on the features' lines and polygon outlines nearest
+    /// to `coord`, with the position of its feature in
+    /// [`features`](Self::features) and its distance in meters
+    ///
+    /// Lines are followed as great circles between their points, so clicks
+    /// can be snapped to coastlines and borders. Only features near `coord`
+    /// are measured: `index` from [`Self::spatial_index`] yields those whose
+    /// bounds reach within a search radius, which widens until the nearest
+    /// point found lies within it, and no feature outside could be nearer.
+    /// Returns `None` if the topology has no points.
+    ///
+    /// ```
+    /// use wmc_core::{projection::GeoCoord, topology::WorldTopology};
+    ///
+    /// let topology = WorldTopology::from_geojson(
+    ///     r#"{"type": "FeatureCollection", "features": [
+    ///         {"type": "Feature", "properties": {},
+    ///          "geometry": {"type": "LineString", "coordinates": [[0, 0], [0, 10]]}},
+    ///         {"type": "Feature", "properties": {},
+    ///          "geometry": {"type": "LineString", "coordinates": [[20, 0], [20, 10]]}}
+    ///     ]}"#,
+    /// )?;
+    /// let index = topology.spatial_index();
+    ///
+    /// let (point, feature, meters) = topology
+    ///     .nearest_point_on_topology(&index, GeoCoord { lat: 5.0, lon: 17.0 })
+    ///     .unwrap();
+    /// assert_eq!(feature, 1);
+    /// assert!((point.lon - 20.0).abs() < 1e-9);
+    /// assert!((meters / 1000.0 - 332.0).abs() < 1.0);
+    ///
+    /// // Far from every line, the search widens until it reaches one
+    /// let (_, feature, _) = topology
+    ///     .nearest_point_on_topology(&index, GeoCoord { lat: -60.0, lon: -170.0 })
+    ///     .unwrap();
+    /// assert_eq!(feature, 0);
+    /// # Ok::<(), wmc_core::CoreError>(())
+    /// ```
+    #[must_use]
+    pub fn nearest_point_on_topology(
+        &self,
+        index: &SpatialIndex<usize>,
+        coord: GeoCoord,
+    ) -> Option<(GeoCoord, usize, f64)> {
+        let mut radius = NEAREST_START_RADIUS;
+        loop {
+            let (bounds, whole) = cap_bounds(coord, radius);
+            let nearest = positions_in(index, bounds)
+                .into_iter()
+                .filter_map(|position| Some((position, self.features.get(position)?)))
+                .flat_map(|(position, feature)| nearest_on_feature(feature, coord, position))
+                .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+            if whole || nearest.is_some_and(|(_, _, meters)| meters <= radius) {
+                return nearest;
+            }
+            radius *= 4.0;
+        }
+    }
+
+    /// Returns the total number of line strings in the topology
+    ///
+    /// Polygon rings are counted as line strings.
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.features.iter().map(|f| f.geometry.lines().len()).sum()
     }
 
     /// Returns the total number of coordinate points in the topology
@@ -148,10 +810,343 @@ impl WorldTopology {
     pub fn point_count(&self) -> usize {
         self.features
             .iter()
-            .map(|f| match &f.geometry {
-                Geometry::LineString(points) => points.len(),
-                Geometry::MultiLineString(lines) => lines.iter().map(std::vec::Vec::len).sum(),
+            .map(|f| {
+                f.geometry
+                    .lines()
+                    .iter()
+                    .map(|line| line.len())
+                    .sum::<usize>()
             })
             .sum()
     }
 }
+
+/// Lines of a topology in flat arrays, e.g. to move them between threads
+///
+/// Geometry types and properties are not kept: unpacking yields one
+/// `MultiLineString` per feature, which draws the same lines.
+///
+/// ```
+/// use wmc_core::topology::{PackedLines, WorldTopology};
+///
+/// let graticule = WorldTopology::graticule(30.0);
+/// let packed = PackedLines::pack(&graticule);
+/// assert_eq!(packed.lines.len(), graticule.line_count());
+///
+/// let unpacked = packed.unpack()?;
+/// assert_eq!(unpacked.point_count(), graticule.point_count());
+/// # Ok::<(), wmc_core::CoreError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PackedLines {
+    /// Longitude and latitude of every point, line after line
+    pub coords: Vec<f64>,
+    /// Number of points of every line, feature after feature
+    pub lines: Vec<u32>,
+    /// Number of lines of every feature
+    pub features: Vec<u32>,
+}
+
+impl PackedLines {
+    /// Flattens the lines and polygon rings of `topology`
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn pack(topology: &WorldTopology) -> Self {
+        let mut packed = Self {
+            coords: Vec::with_capacity(topology.point_count() * 2),
+            lines: Vec::with_capacity(topology.line_count()),
+            features: Vec::with_capacity(topology.features.len()),
+        };
+        for feature in &topology.features {
+            let lines = feature.geometry.lines();
+            packed.features.push(lines.len() as u32);
+            for line in lines {
+                packed.lines.push(line.len() as u32);
+                packed
+                    .coords
+                    .extend(line.iter().flat_map(|point| [point.lon, point.lat]));
+            }
+        }
+        packed
+    }
+
+    /// Rebuilds the topology, one `MultiLineString` feature per packed
+    /// feature
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::TopologyParseError`] if the counts don't add up
+    /// to the coordinates
+    pub fn unpack(&self) -> Result<WorldTopology, CoreError> {
+        let mismatch = || CoreError::TopologyParseError {
+            details: "Packed line counts don't match the coordinates".to_string(),
+        };
+        let (pairs, odd) = self.coords.as_chunks::<2>();
+        if !odd.is_empty() {
+            return Err(mismatch());
+        }
+        let mut points = pairs.iter().map(|&[lon, lat]| GeoCoord { lat, lon });
+        let mut lines = self.lines.iter();
+
+        let features = self
+            .features
+            .iter()
+            .map(|&line_count| {
+                let lines = (0..line_count)
+                    .map(|_| {
+                        let &length = lines.next().ok_or_else(mismatch)?;
+                        let line: Vec<GeoCoord> = points.by_ref().take(length as usize).collect();
+                        if line.len() == length as usize {
+                            Ok(line)
+                        } else {
+                            Err(mismatch())
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Feature {
+                    geometry: Geometry::MultiLineString(lines),
+                    properties: JsonObject::new(),
+                })
+            })
+            .collect::<Result<_, CoreError>>()?;
+
+        if lines.next().is_some() || points.next().is_some() {
+            return Err(mismatch());
+        }
+        Ok(WorldTopology { features })
+    }
+}
+
+fn parse_line(coords: Vec<Vec<f64>>) -> Vec<GeoCoord> {
+    coords
+        .into_iter()
+        .filter_map(|c| {
+            if c.len() >= 2 {
+                GeoCoord::new(c[1], c[0]).ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Incremental `GeoJSON` parser that processes features in bounded chunks
+///
+/// Unlike [`WorldTopology::from_geojson`], no intermediate document is built:
+/// each feature of the `FeatureCollection` is located by a lightweight scanner
+/// and parsed on its own, so callers can interleave parsing with other work.
+///
+/// # Examples
+///
+/// ```
+/// use wmc_core::topology::TopologyStream;
+///
+/// let source = r#"{"type":"FeatureCollection","features":[
+///     {"type":"Feature","properties":{},"geometry":{"type":"LineString","coordinates":[[0,0],[1,1]]}}
+/// ]}"#;
+///
+/// let mut stream = TopologyStream::new(source).unwrap();
+/// while !stream.parse_chunk(16).unwrap() {}
+/// assert_eq!(stream.finish().features.len(), 1);
+/// ```
+pub struct TopologyStream<'a> {
+    source: &'a str,
+    cursor: usize,
+    features: Vec<Feature>,
+    skipped: usize,
+    done: bool,
+}
+
+impl<'a> TopologyStream<'a> {
+    /// Creates a stream positioned at the start of the `features` array
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::TopologyParseError`] if the source is not a
+    /// `FeatureCollection` object with a `features` array
+    pub fn new(source: &'a str) -> Result<Self, CoreError> {
+        let cursor = find_features_array(source).ok_or_else(|| CoreError::TopologyParseError {
+            details: "Expected FeatureCollection".to_string(),
+        })?;
+
+        Ok(Self {
+            source,
+            cursor,
+            features: Vec::new(),
+            skipped: 0,
+            done: false,
+        })
+    }
+
+    /// Parses up to `max_features` features
+    ///
+    /// Returns `true` once the end of the `features` array has been reached.
+    /// Features with unsupported geometry are skipped, as in
+    /// [`WorldTopology::from_geojson`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::TopologyParseError`] if a feature is not valid
+    /// `GeoJSON` or the array is truncated
+    pub fn parse_chunk(&mut self, max_features: usize) -> Result<bool, CoreError> {
+        let bytes = self.source.as_bytes();
+
+        for _ in 0..max_features {
+            while self.cursor < bytes.len()
+                && (bytes[self.cursor].is_ascii_whitespace() || bytes[self.cursor] == b',')
+            {
+                self.cursor += 1;
+            }
+
+            match bytes.get(self.cursor) {
+                Some(b']') => {
+                    self.done = true;
+                    return Ok(true);
+                },
+                Some(_) => {},
+                None => {
+                    return Err(CoreError::TopologyParseError {
+                        details: "Unterminated features array".to_string(),
+                    });
+                },
+            }
+
+            let end =
+                value_end(bytes, self.cursor).ok_or_else(|| CoreError::TopologyParseError {
+                    details: "Unterminated feature".to_string(),
+                })?;
+
+            let feature: geojson::Feature = serde_json::from_str(&self.source[self.cursor..end])
+                .map_err(|e| CoreError::TopologyParseError {
+                details: e.to_string(),
+            })?;
+            self.cursor = end;
+
+            match WorldTopology::parse_feature(feature) {
+                Ok(parsed) => self.features.push(parsed),
+                Err(_) => self.skipped += 1,
+            }
+        }
+
+        Ok(self.done)
+    }
+
+    /// Returns the fraction of the source consumed so far, in range [0.0, 1.0]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn progress(&self) -> f64 {
+        if self.done || self.source.is_empty() {
+            1.0
+        } else {
+            self.cursor as f64 / self.source.len() as f64
+        }
+    }
+
+    /// Returns the number of source bytes consumed so far
+    #[must_use]
+    pub const fn consumed(&self) -> usize {
+        if self.done {
+            self.source.len()
+        } else {
+            self.cursor
+        }
+    }
+
+    /// Returns the number of features parsed so far
+    #[must_use]
+    pub const fn parsed_count(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Returns the number of features skipped for unsupported geometry
+    #[must_use]
+    pub const fn skipped_count(&self) -> usize {
+        self.skipped
+    }
+
+    /// Consumes the stream and returns the features parsed so far
+    #[must_use]
+    pub fn finish(self) -> WorldTopology {
+        WorldTopology {
+            features: self.features,
+        }
+    }
+}
+
+/// Returns the index just past the `[` opening the top-level `features` array
+fn find_features_array(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let end = string_end(bytes, i)?;
+                if depth == 1 && &source[i + 1..end - 1] == "features" {
+                    let mut j = end;
+                    while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                        j += 1;
+                    }
+                    if bytes.get(j) == Some(&b':') {
+                        j += 1;
+                        while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                            j += 1;
+                        }
+                        return (bytes.get(j) == Some(&b'[')).then_some(j + 1);
+                    }
+                }
+                i = end;
+                continue;
+            },
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.checked_sub(1)?,
+            _ => {},
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Returns the index just past the JSON object or array starting at `start`
+fn value_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut i = start;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i = string_end(bytes, i)?;
+                continue;
+            },
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            },
+            _ if depth == 0 => return None,
+            _ => {},
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Returns the index just past the closing quote of the string at `start`
+const fn string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+
+    None
+}
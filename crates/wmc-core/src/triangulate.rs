@@ -0,0 +1,106 @@
+use crate::projection::ProjectedCoord;
+
+/// Triangulates a simple polygon ring using ear clipping
+///
+/// The ring may be given in either winding order and may repeat its first
+/// point at the end, as `GeoJSON` rings do. Hole rings are not considered.
+/// Returns indices into `ring`, three per triangle; degenerate rings yield no
+/// triangles.
+///
+/// # Examples
+///
+/// ```
+/// use wmc_core::{projection::ProjectedCoord, triangulate::ear_clip};
+///
+/// let square = [
+///     ProjectedCoord { x: 0.0, y: 0.0 },
+///     ProjectedCoord { x: 1.0, y: 0.0 },
+///     ProjectedCoord { x: 1.0, y: 1.0 },
+///     ProjectedCoord { x: 0.0, y: 1.0 },
+/// ];
+///
+/// assert_eq!(ear_clip(&square).len(), 6);
+/// ```
+#[must_use]
+pub fn ear_clip(ring: &[ProjectedCoord]) -> Vec<usize> {
+    let mut len = ring.len();
+    if len > 1 && ring[0] == ring[len - 1] {
+        len -= 1;
+    }
+    if len < 3 {
+        return Vec::new();
+    }
+
+    let mut remaining: Vec<usize> = (0..len).collect();
+    if signed_area(ring, &remaining) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut indices = Vec::with_capacity((len - 2) * 3);
+    let mut cursor = 0;
+    let mut attempts = 0;
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let prev = remaining[(cursor + n - 1) % n];
+        let curr = remaining[cursor % n];
+        let next = remaining[(cursor + 1) % n];
+
+        if is_ear(ring, &remaining, prev, curr, next) {
+            indices.extend_from_slice(&[prev, curr, next]);
+            remaining.remove(cursor % n);
+            attempts = 0;
+        } else {
+            cursor = (cursor + 1) % n;
+            attempts += 1;
+            // Self-intersecting input has no ear left; stop instead of looping
+            if attempts > n {
+                break;
+            }
+        }
+    }
+
+    if remaining.len() == 3 {
+        indices.extend_from_slice(&remaining);
+    }
+
+    indices
+}
+
+fn signed_area(ring: &[ProjectedCoord], order: &[usize]) -> f64 {
+    let n = order.len();
+    (0..n)
+        .map(|i| {
+            let a = ring[order[i]];
+            let b = ring[order[(i + 1) % n]];
+            a.x.mul_add(b.y, -(b.x * a.y))
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn cross(a: ProjectedCoord, b: ProjectedCoord, c: ProjectedCoord) -> f64 {
+    (b.x - a.x).mul_add(c.y - a.y, -((b.y - a.y) * (c.x - a.x)))
+}
+
+fn is_ear(
+    ring: &[ProjectedCoord],
+    remaining: &[usize],
+    prev: usize,
+    curr: usize,
+    next: usize,
+) -> bool {
+    let (a, b, c) = (ring[prev], ring[curr], ring[next]);
+
+    if cross(a, b, c) <= 0.0 {
+        return false;
+    }
+
+    remaining
+        .iter()
+        .filter(|&&i| i != prev && i != curr && i != next)
+        .all(|&i| {
+            let p = ring[i];
+            !(cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0)
+        })
+}
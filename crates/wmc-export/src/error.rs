@@ -0,0 +1,46 @@
+use std::fmt;
+
+use masterror::AppError;
+use serde::Serialize;
+
+/// Export errors
+///
+/// Serializes as `{code, details}`, `code` being the variant name in
+/// snake case and `details` its fields
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
+pub enum ExportError {
+    /// Image size is zero or too large to allocate
+    InvalidSize {
+        /// Requested width in pixels
+        width: u32,
+        /// Requested height in pixels
+        height: u32,
+    },
+    /// Bundled world topology could not be parsed
+    TopologyParseFailed {
+        /// Parser error description
+        details: String,
+    },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSize { width, height } => {
+                write!(f, "Invalid image size: {width}x{height}")
+            },
+            Self::TopologyParseFailed { details } => {
+                write!(f, "Topology parse failed: {details}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<ExportError> for AppError {
+    fn from(err: ExportError) -> Self {
+        Self::internal(err.to_string())
+    }
+}
@@ -0,0 +1,258 @@
+//! World Map Component - Static export
+//!
+//! Renders maps to PNG natively, without a browser or GPU, e.g. on a server
+//! for emails, reports and Open Graph images. Projection, styling and line
+//! meshes are shared with the web component through `wmc-core` and
+//! `wmc-render`, and pixels are filled by the headless backend.
+
+/// Error types
+pub mod error;
+
+pub use error::ExportError;
+use wmc_core::{
+    label::zoom_level,
+    marker::Marker,
+    marker_buffer::{InstanceBuilder, MarkerBuffer},
+    projection::{GeoCoord, MercatorProjection, Projection},
+    theme::Theme,
+    topology::WorldTopology,
+};
+use wmc_render::{
+    HeadlessContext, LineMesh, RenderBackend, View, line_mesh::LINE_VERTEX_FLOATS, snapshot,
+};
+
+const WORLD_GEOJSON: &str = include_str!("../../../assets/world-110m.geojson");
+
+/// Largest supported image side in pixels
+pub const MAX_SIZE: u32 = 8192;
+
+/// Static map rendered to an image in one go
+///
+/// The world is laid out like the web component's: Web Mercator fitted to
+/// the image, then moved by the [`View`]. Coastlines and markers are drawn
+/// in the theme's colors, flat and without glow, grain or other post
+/// effects, and markers at their pulse for [`StaticMap::time`], so the same
+/// map always renders to the same pixels.
+///
+/// ```
+/// use wmc_core::{marker::{Marker, MarkerId}, projection::GeoCoord, theme::Theme};
+/// use wmc_export::StaticMap;
+///
+/// let mut marker = Marker::new(MarkerId::Number(1), 55.7558, 37.6173).unwrap();
+/// marker.radius = 8.0;
+///
+/// let map = StaticMap::new(600, 315)
+///     .unwrap()
+///     .theme(Theme::light_minimal())
+///     .center(GeoCoord::new(50.0, 30.0).unwrap(), 2.0)
+///     .markers(vec![marker]);
+///
+/// let png = map.render_png();
+/// assert!(png.starts_with(b"\x89PNG"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticMap {
+    width: u32,
+    height: u32,
+    pixel_ratio: f32,
+    theme: Theme,
+    view: View,
+    topology: WorldTopology,
+    markers: Vec<Marker>,
+    time: f32,
+}
+
+impl StaticMap {
+    /// Creates a `width` x `height` pixel map of the bundled world in the
+    /// default dark theme
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExportError::InvalidSize`] if a side is zero or above
+    /// [`MAX_SIZE`], or [`ExportError::TopologyParseFailed`] if the bundled
+    /// topology can't be parsed
+    pub fn new(width: u32, height: u32) -> Result<Self, ExportError> {
+        if !(1..=MAX_SIZE).contains(&width) || !(1..=MAX_SIZE).contains(&height) {
+            return Err(ExportError::InvalidSize { width, height });
+        }
+
+        let topology = WorldTopology::from_geojson(WORLD_GEOJSON).map_err(|e| {
+            ExportError::TopologyParseFailed {
+                details: e.to_string(),
+            }
+        })?;
+
+        Ok(Self {
+            width,
+            height,
+            pixel_ratio: 1.0,
+            theme: Theme::dark_minimal(),
+            view: View::default(),
+            topology,
+            markers: Vec::new(),
+            time: 0.0,
+        })
+    }
+
+    /// Sets the theme
+    #[must_use]
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Replaces the bundled world with `topology`, drawn as coastlines
+    #[must_use]
+    pub fn topology(mut self, topology: WorldTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Sets the markers
+    #[must_use]
+    pub fn markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Sets the view over the map, the whole world by default
+    #[must_use]
+    pub const fn view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Centers the view on `coord` at `zoom`, keeping its rotation
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn center(mut self, coord: GeoCoord, zoom: f32) -> Self {
+        let point = self.projection().project(coord);
+        self.view.center = [
+            (point.x / f64::from(self.width)) as f32,
+            (point.y / f64::from(self.height)) as f32,
+        ];
+        self.view.zoom = zoom;
+        self
+    }
+
+    /// Sets the pixels per theme pixel, scaling line widths and marker radii,
+    /// e.g. 2 for high-density screens
+    #[must_use]
+    pub const fn pixel_ratio(mut self, pixel_ratio: f32) -> Self {
+        self.pixel_ratio = pixel_ratio;
+        self
+    }
+
+    /// Sets the animation time in seconds markers pulse at, 0 by default
+    #[must_use]
+    pub const fn time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Draws the map into a new headless context
+    ///
+    /// ```
+    /// use wmc_core::{marker::{Color, Marker, MarkerId}, projection::GeoCoord};
+    /// use wmc_export::StaticMap;
+    ///
+    /// let mut marker = Marker::new(MarkerId::Number(1), -33.87, 151.21).unwrap();
+    /// marker.color = Some(Color::from_hex("#ff0000").unwrap());
+    ///
+    /// let ctx = StaticMap::new(200, 100)
+    ///     .unwrap()
+    ///     .center(marker.coord, 3.0)
+    ///     .markers(vec![marker])
+    ///     .render();
+    /// assert_eq!(ctx.pixel(100, 50), [255, 0, 0, 255]);
+    /// ```
+    #[must_use]
+    pub fn render(&self) -> HeadlessContext {
+        let ctx = HeadlessContext::new(self.width, self.height);
+        let background = self.theme.background;
+        ctx.clear([background.r, background.g, background.b, background.a]);
+
+        self.draw_coastlines(&ctx);
+        self.draw_markers(&ctx);
+        ctx
+    }
+
+    /// Draws the map and encodes it as a PNG file
+    #[must_use]
+    pub fn render_png(&self) -> Vec<u8> {
+        let ctx = self.render();
+        snapshot::encode_png(self.width, self.height, &ctx.pixels())
+    }
+
+    fn projection(&self) -> MercatorProjection {
+        MercatorProjection::new(f64::from(self.width), f64::from(self.height))
+    }
+
+    /// Returns the image size as floats for [`View`] transforms
+    #[allow(clippy::cast_precision_loss)]
+    const fn size(&self) -> (f32, f32) {
+        (self.width as f32, self.height as f32)
+    }
+
+    fn draw_coastlines(&self, ctx: &HeadlessContext) {
+        let (width, height) = self.size();
+        let mut mesh = LineMesh::build(&self.topology, &self.projection(), None);
+
+        // Extrusions only turn with the view, so lines keep their width
+        let (sin, cos) = self.view.rotation.sin_cos();
+        for vertex in mesh.vertices.as_chunks_mut::<LINE_VERTEX_FLOATS>().0 {
+            let [x, y] = self.view.to_screen(width, height, [vertex[0], vertex[1]]);
+            let [extrude_x, extrude_y] = [vertex[2], vertex[3]];
+            vertex[..4].copy_from_slice(&[
+                x,
+                y,
+                cos.mul_add(extrude_x, -sin * extrude_y),
+                sin.mul_add(extrude_x, cos * extrude_y),
+            ]);
+        }
+
+        let style = &self.theme.coastlines;
+        let color = style.effective_color();
+        ctx.draw_lines(
+            &mesh.vertices,
+            [color.r, color.g, color.b, color.a],
+            style.width * self.pixel_ratio,
+        );
+    }
+
+    /// Draws every marker with the largest marker radius, as the web
+    /// component does, following the theme's zoom rules for the image width
+    /// and view zoom
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_markers(&self, ctx: &HeadlessContext) {
+        let (width, height) = self.size();
+        let projection = self.projection();
+        let style = self.theme.markers;
+        let zoom = zoom_level(f64::from(self.width)) as f32 + self.view.zoom;
+        let mut builder = InstanceBuilder::new(style);
+        builder.set_opacity(style.opacity_at(zoom));
+        let mut buffer = MarkerBuffer::new(self.markers.len());
+
+        for marker in &self.markers {
+            let point = projection.project(marker.coord);
+            let [x, y] = self
+                .view
+                .to_screen(width, height, [point.x as f32, point.y as f32]);
+            if x.is_finite() && y.is_finite() {
+                // Capacity covers every marker
+                let _ = buffer.push(builder.build(marker, x, y));
+            }
+        }
+
+        let radius = self
+            .markers
+            .iter()
+            .map(|marker| marker.radius)
+            .fold(0.0, f32::max);
+        ctx.draw_markers(
+            &buffer,
+            radius * style.radius_at(zoom) * self.pixel_ratio,
+            self.time,
+        );
+    }
+}
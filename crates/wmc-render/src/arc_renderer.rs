@@ -0,0 +1,171 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::{arc::Arc, projection::Projection, theme::LineStyle};
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    line_mesh::{LINE_VERTEX_FLOATS, extrude_polyline},
+    program::ShaderProgram,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Bytes per arc vertex: the line mesh vertex followed by an RGBA8 color
+const ARC_VERTEX_BYTES: usize = LINE_VERTEX_FLOATS * 4 + 4;
+
+/// WebGL renderer for great-circle arcs
+///
+/// Arcs are extruded like [`crate::WorldRenderer`] lines, with a color per
+/// arc, and drawn in one call. Dash patterns run from each arc's origin and
+/// can be shifted along it to animate a flow.
+pub struct ArcRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    vertex_count: i32,
+    u_line_width: WebGlUniformLocation,
+    u_aa_width: WebGlUniformLocation,
+    u_dash: WebGlUniformLocation,
+    u_flow: WebGlUniformLocation,
+}
+
+impl ArcRenderer {
+    /// Projects `arcs` and uploads their meshes
+    ///
+    /// Arcs without a color use `color`. `elevation` lifts the arcs as
+    /// described for [`Arc::project`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(
+        ctx: &RenderContext,
+        arcs: &[Arc],
+        projection: &dyn Projection,
+        color: [f32; 4],
+        elevation: f32,
+    ) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::ARC_VERTEX, shaders::ARC_FRAGMENT)
+        } else {
+            (shaders::ARC_VERTEX_ES100, shaders::ARC_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::ARC_ATTRIBUTES,
+        )?;
+
+        let u_line_width = program.get_uniform_location(gl, "u_line_width")?;
+        let u_aa_width = program.get_uniform_location(gl, "u_aa_width")?;
+        let u_dash = program.get_uniform_location(gl, "u_dash")?;
+        let u_flow = program.get_uniform_location(gl, "u_flow")?;
+
+        let vertices = Self::build_vertices(arcs, projection, color, elevation);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let vertex_count = (vertices.len() / ARC_VERTEX_BYTES) as i32;
+
+        let vertex_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+        vertex_buffer.upload_data(gl, &vertices);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let stride = ARC_VERTEX_BYTES as i32;
+        let vertices = VertexArray::new(
+            gl,
+            vertex_buffer,
+            stride,
+            &[
+                VertexAttribute::float(0, 2, 0),
+                VertexAttribute::float(1, 2, 8),
+                VertexAttribute::float(2, 1, 16),
+                VertexAttribute::float(3, 1, 20),
+                VertexAttribute::unorm8(4, 4, 24),
+            ],
+        )?;
+
+        Ok(Self {
+            program,
+            vertices,
+            vertex_count,
+            u_line_width,
+            u_aa_width,
+            u_dash,
+            u_flow,
+        })
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+    }
+
+    /// Draws the arcs with the dash pattern shifted `flow` pixels towards
+    /// their ends
+    ///
+    /// `line_width`, the lengths of `style` and `flow` are in drawing buffer
+    /// pixels.
+    pub fn draw(&self, ctx: &RenderContext, line_width: f32, style: LineStyle, flow: f32) {
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+
+        gl.uniform1f(Some(&self.u_line_width), line_width);
+        #[allow(clippy::cast_possible_truncation)]
+        gl.uniform1f(Some(&self.u_aa_width), ctx.pixel_ratio() as f32);
+        let (dash, gap, round) = match style {
+            LineStyle::Solid => (0.0, 0.0, 0.0),
+            LineStyle::Dashed { dash, gap } => (dash, gap, 0.0),
+            LineStyle::Dotted { gap } => (0.0, gap, 1.0),
+        };
+        gl.uniform3f(Some(&self.u_dash), dash, gap, round);
+        gl.uniform1f(Some(&self.u_flow), flow);
+
+        self.vertices.bind(gl);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count);
+        self.vertices.unbind(gl);
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn build_vertices(
+        arcs: &[Arc],
+        projection: &dyn Projection,
+        color: [f32; 4],
+        elevation: f32,
+    ) -> Vec<u8> {
+        let mut vertices = Vec::new();
+        let mut mesh = Vec::new();
+        let mut scratch = Vec::new();
+
+        for arc in arcs {
+            let rgba = arc
+                .color
+                .map_or(color, |color| [color.r, color.g, color.b, color.a])
+                .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+            mesh.clear();
+            for piece in arc.project(projection, f64::from(elevation)) {
+                scratch.clear();
+                scratch.extend(piece.iter().map(|point| [point.x as f32, point.y as f32]));
+                extrude_polyline(&mut mesh, &scratch);
+            }
+
+            for vertex in mesh.as_chunks::<LINE_VERTEX_FLOATS>().0 {
+                for value in vertex {
+                    vertices.extend_from_slice(&value.to_ne_bytes());
+                }
+                vertices.extend_from_slice(&rgba);
+            }
+        }
+
+        vertices
+    }
+}
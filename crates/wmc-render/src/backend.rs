@@ -0,0 +1,148 @@
+use web_sys::WebGl2RenderingContext;
+
+use crate::{
+    buffer::GpuBuffer, context::RenderContext, error::RenderError, program::ShaderProgram,
+};
+
+/// Graphics API behind a [`RenderBackend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// WebGL2, available in every modern browser
+    WebGl2,
+    /// WebGL1 fallback for older browsers
+    WebGl1,
+    /// WebGPU, used where the browser exposes `navigator.gpu`
+    WebGpu,
+    /// CPU rasterizer for native tests, see [`crate::HeadlessContext`]
+    Headless,
+}
+
+impl BackendKind {
+    /// Returns the lowercase API name, e.g. `"webgl2"`
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::WebGl2 => "webgl2",
+            Self::WebGl1 => "webgl",
+            Self::WebGpu => "webgpu",
+            Self::Headless => "headless",
+        }
+    }
+}
+
+/// Shader sources for one program, in every language a backend may need
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderSources<'a> {
+    /// GLSL ES 3.00 vertex shader for WebGL2
+    pub glsl_vertex: &'a str,
+    /// GLSL ES 3.00 fragment shader for WebGL2
+    pub glsl_fragment: &'a str,
+    /// GLSL ES 1.00 vertex and fragment shaders for WebGL1
+    pub glsl100: Option<(&'a str, &'a str)>,
+    /// Vertex attribute names in location order, bound before linking
+    pub attributes: &'a [&'a str],
+    /// WGSL module with `vs_main`/`fs_main` entry points for WebGPU
+    pub wgsl: Option<&'a str>,
+}
+
+/// Graphics API abstraction over surface, buffer and program management
+///
+/// Implemented by [`RenderContext`] for WebGL2 and WebGL1, by
+/// [`crate::HeadlessContext`] for native tests and, with the `webgpu`
+/// feature, by [`crate::webgpu::WebGpuContext`].
+pub trait RenderBackend {
+    /// Vertex buffer handle
+    type Buffer;
+    /// Compiled shader program or pipeline handle
+    type Program;
+
+    /// Returns the graphics API this backend uses
+    fn kind(&self) -> BackendKind;
+
+    /// Returns the drawing buffer size in device pixels
+    fn size(&self) -> (u32, u32);
+
+    /// Resizes the drawing buffer to a CSS size
+    fn resize(&mut self, css_width: u32, css_height: u32);
+
+    /// Clears the drawing buffer with the specified color
+    fn clear(&self, color: [f32; 4]);
+
+    /// Creates a vertex buffer initialized with `data`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::BufferAllocationFailed`] if allocation fails
+    fn create_vertex_buffer(&self, data: &[u8]) -> Result<Self::Buffer, RenderError>;
+
+    /// Replaces the contents of a vertex buffer
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::BufferAllocationFailed`] if the buffer must grow
+    /// and reallocation fails
+    fn upload(&self, buffer: &mut Self::Buffer, data: &[u8]) -> Result<(), RenderError>;
+
+    /// Compiles a program from the sources this backend understands
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if compilation or linking fails, or the
+    /// backend's shader language is missing from `sources`
+    fn create_program(&self, sources: &ShaderSources<'_>) -> Result<Self::Program, RenderError>;
+}
+
+impl RenderBackend for RenderContext {
+    type Buffer = GpuBuffer;
+    type Program = ShaderProgram;
+
+    fn kind(&self) -> BackendKind {
+        if self.gl().is_webgl2() {
+            BackendKind::WebGl2
+        } else {
+            BackendKind::WebGl1
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width(), self.height())
+    }
+
+    fn resize(&mut self, css_width: u32, css_height: u32) {
+        Self::resize(self, css_width, css_height);
+    }
+
+    fn clear(&self, color: [f32; 4]) {
+        Self::clear(self, color[0], color[1], color[2], color[3]);
+    }
+
+    fn create_vertex_buffer(&self, data: &[u8]) -> Result<Self::Buffer, RenderError> {
+        let buffer = GpuBuffer::new(
+            self.gl(),
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+        buffer.upload_data(self.gl(), data);
+        Ok(buffer)
+    }
+
+    fn upload(&self, buffer: &mut Self::Buffer, data: &[u8]) -> Result<(), RenderError> {
+        buffer.upload_data(self.gl(), data);
+        Ok(())
+    }
+
+    fn create_program(&self, sources: &ShaderSources<'_>) -> Result<Self::Program, RenderError> {
+        let (vertex, fragment) = if self.gl().is_webgl2() {
+            (sources.glsl_vertex, sources.glsl_fragment)
+        } else {
+            sources
+                .glsl100
+                .ok_or_else(|| RenderError::ShaderCompilationFailed {
+                    shader_type: "glsl100".to_string(),
+                    log: "no GLSL ES 1.00 source for the WebGL1 backend".to_string(),
+                })?
+        };
+
+        ShaderProgram::with_attributes(self.gl(), vertex, fragment, sources.attributes)
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use web_sys::WebGl2RenderingContext;
+
+use crate::gl::Gl;
+
+/// How a layer's colors combine with what is already drawn
+///
+/// Additive blending brightens overlaps, which suits glowing markers on dark
+/// themes; multiply darkens, which suits shading over light basemaps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlendMode {
+    /// Alpha compositing
+    #[default]
+    Normal,
+    /// Colors are added, weighted by alpha
+    Additive,
+    /// Colors are multiplied; exact for opaque colors, approximate at
+    /// translucent edges
+    Multiply,
+}
+
+impl BlendMode {
+    /// Parses a mode name as used in layer options
+    ///
+    /// ```
+    /// use wmc_render::BlendMode;
+    ///
+    /// assert_eq!(BlendMode::from_name("additive"), Some(BlendMode::Additive));
+    /// assert_eq!(BlendMode::from_name("screen"), None);
+    /// ```
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(Self::Normal),
+            "additive" => Some(Self::Additive),
+            "multiply" => Some(Self::Multiply),
+            _ => None,
+        }
+    }
+
+    /// Sets the blend function for sources with straight or premultiplied
+    /// alpha
+    ///
+    /// Destination alpha always accumulates as in normal blending.
+    pub fn apply(self, gl: &Gl, premultiplied: bool) {
+        let src_alpha = if premultiplied {
+            WebGl2RenderingContext::ONE
+        } else {
+            WebGl2RenderingContext::SRC_ALPHA
+        };
+
+        match self {
+            Self::Normal => gl.blend_func(src_alpha, WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA),
+            Self::Additive => {
+                gl.blend_func_separate(
+                    src_alpha,
+                    WebGl2RenderingContext::ONE,
+                    src_alpha,
+                    WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                );
+            },
+            Self::Multiply => gl.blend_func_separate(
+                WebGl2RenderingContext::DST_COLOR,
+                WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                src_alpha,
+                WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+            ),
+        }
+    }
+}
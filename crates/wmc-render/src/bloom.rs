@@ -0,0 +1,233 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+
+use crate::{
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    post_process::{PostProcessor, effect_program, fullscreen_triangle},
+    program::ShaderProgram,
+    render_target::RenderTarget,
+    shaders,
+    vertex_array::VertexArray,
+};
+
+/// Bloom settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// Glow radius in CSS pixels
+    pub radius: f32,
+    /// Brightness above which colors start to glow, in `0.0..1.0`
+    pub threshold: f32,
+    /// Glow brightness multiplier
+    pub strength: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            radius: 8.0,
+            threshold: 0.5,
+            strength: 1.5,
+        }
+    }
+}
+
+/// Bloom pass for a single layer
+///
+/// Unlike a [`crate::PostEffect`], bloom only applies to what is drawn between
+/// [`Bloom::begin_layer`] and [`Bloom::finish_layer`], e.g. markers. The layer
+/// is rendered offscreen; its bright parts are extracted, blurred at half
+/// resolution with a separable Gaussian, and composited additively together
+/// with the layer onto the scene.
+pub struct Bloom {
+    triangle: VertexArray,
+    bright: ShaderProgram,
+    u_threshold: WebGlUniformLocation,
+    blur: ShaderProgram,
+    u_step: WebGlUniformLocation,
+    composite: ShaderProgram,
+    u_strength: WebGlUniformLocation,
+    layer: Option<RenderTarget>,
+    halves: Vec<RenderTarget>,
+    active: bool,
+}
+
+impl Bloom {
+    /// Creates the bloom programs
+    ///
+    /// Render targets are allocated on the first [`Bloom::begin_layer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(ctx: &RenderContext) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let bright = effect_program(
+            gl,
+            shaders::BLOOM_BRIGHT_FRAGMENT,
+            shaders::BLOOM_BRIGHT_FRAGMENT_ES100,
+        )?;
+        let u_threshold = bright.get_uniform_location(gl, "u_threshold")?;
+
+        let blur = effect_program(
+            gl,
+            shaders::BLOOM_BLUR_FRAGMENT,
+            shaders::BLOOM_BLUR_FRAGMENT_ES100,
+        )?;
+        let u_step = blur.get_uniform_location(gl, "u_step")?;
+
+        let composite = effect_program(
+            gl,
+            shaders::BLOOM_COMPOSITE_FRAGMENT,
+            shaders::BLOOM_COMPOSITE_FRAGMENT_ES100,
+        )?;
+        let u_strength = composite.get_uniform_location(gl, "u_strength")?;
+        let u_glow = composite.get_uniform_location(gl, "u_glow")?;
+        composite.use_program(gl);
+        gl.uniform1i(Some(&u_glow), 1);
+
+        Ok(Self {
+            triangle: fullscreen_triangle(gl)?,
+            bright,
+            u_threshold,
+            blur,
+            u_step,
+            composite,
+            u_strength,
+            layer: None,
+            halves: Vec::new(),
+            active: false,
+        })
+    }
+
+    /// Frees the pass' GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.triangle.delete(gl);
+        for program in [&self.bright, &self.blur, &self.composite] {
+            program.delete(gl);
+        }
+        for target in self.layer.iter().chain(&self.halves) {
+            target.delete(gl);
+        }
+    }
+
+    /// Redirects drawing into the cleared layer target
+    ///
+    /// Layer colors are accumulated premultiplied by alpha until
+    /// [`Bloom::finish_layer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if a render target can't be allocated
+    pub fn begin_layer(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let gl = ctx.gl();
+        let (width, height) = (ctx.width(), ctx.height());
+        let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+
+        self.active = false;
+        match &mut self.layer {
+            Some(layer) => layer.resize(gl, width, height)?,
+            None => self.layer = Some(RenderTarget::new(gl, width, height)?),
+        }
+        for half in &mut self.halves {
+            half.resize(gl, half_width, half_height)?;
+        }
+        while self.halves.len() < 2 {
+            self.halves
+                .push(RenderTarget::new(gl, half_width, half_height)?);
+        }
+
+        if let Some(layer) = &self.layer {
+            layer.bind(gl);
+        }
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        gl.blend_func_separate(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
+        self.active = true;
+        Ok(())
+    }
+
+    /// Blurs the layer's bright parts and composites layer and glow onto the
+    /// scene destination of `post`
+    ///
+    /// Does nothing if [`Bloom::begin_layer`] failed or wasn't called. The
+    /// composite uses the context's blend mode.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn finish_layer(
+        &mut self,
+        ctx: &RenderContext,
+        post: &PostProcessor,
+        settings: BloomSettings,
+    ) {
+        let gl = ctx.gl();
+        ctx.apply_blend(false);
+
+        if !std::mem::take(&mut self.active) {
+            post.bind_scene(ctx);
+            return;
+        }
+        let (Some(layer), [first, second]) = (&self.layer, self.halves.as_slice()) else {
+            post.bind_scene(ctx);
+            return;
+        };
+
+        // Tap spacing so the four taps each side span the radius at half size
+        let spread = settings.radius * ctx.pixel_ratio() as f32 / 2.0 / 4.0;
+        let step_x = spread / first.width() as f32;
+        let step_y = spread / first.height() as f32;
+
+        // The scissor box is in full-size pixels; the half-size passes blur
+        // the whole layer and only the composite honors it
+        let scissored = gl.is_enabled(WebGl2RenderingContext::SCISSOR_TEST);
+        if scissored {
+            gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+        }
+
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        self.triangle.bind(gl);
+
+        first.bind(gl);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(layer.texture()));
+        self.bright.use_program(gl);
+        gl.uniform1f(Some(&self.u_threshold), settings.threshold);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+
+        self.blur.use_program(gl);
+        second.bind(gl);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(first.texture()));
+        gl.uniform2f(Some(&self.u_step), step_x, 0.0);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+
+        first.bind(gl);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(second.texture()));
+        gl.uniform2f(Some(&self.u_step), 0.0, step_y);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+
+        post.bind_scene(ctx);
+        if scissored {
+            gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        }
+        gl.enable(WebGl2RenderingContext::BLEND);
+        ctx.apply_blend(true);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(layer.texture()));
+        gl.active_texture(WebGl2RenderingContext::TEXTURE1);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(first.texture()));
+        self.composite.use_program(gl);
+        gl.uniform1f(Some(&self.u_strength), settings.strength);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        self.triangle.unbind(gl);
+        ctx.apply_blend(false);
+    }
+}
@@ -1,6 +1,6 @@
-use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+use web_sys::WebGlBuffer;
 
-use crate::error::RenderError;
+use crate::{error::RenderError, gl::Gl};
 
 /// WebGL buffer wrapper
 pub struct GpuBuffer {
@@ -15,7 +15,7 @@ impl GpuBuffer {
     /// # Errors
     ///
     /// Returns [`RenderError::BufferAllocationFailed`] if buffer creation fails
-    pub fn new(gl: &WebGl2RenderingContext, target: u32, usage: u32) -> Result<Self, RenderError> {
+    pub fn new(gl: &Gl, target: u32, usage: u32) -> Result<Self, RenderError> {
         let buffer = gl
             .create_buffer()
             .ok_or(RenderError::BufferAllocationFailed { size: 0 })?;
@@ -27,24 +27,35 @@ impl GpuBuffer {
         })
     }
 
+    /// Frees the buffer's GPU memory; the buffer must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        gl.delete_buffer(Some(&self.buffer));
+    }
+
+    /// Returns the underlying WebGL buffer
+    #[must_use]
+    pub const fn buffer(&self) -> &WebGlBuffer {
+        &self.buffer
+    }
+
     /// Binds the buffer to its target
-    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+    pub fn bind(&self, gl: &Gl) {
         gl.bind_buffer(self.target, Some(&self.buffer));
     }
 
     /// Unbinds the buffer from its target
-    pub fn unbind(&self, gl: &WebGl2RenderingContext) {
+    pub fn unbind(&self, gl: &Gl) {
         gl.bind_buffer(self.target, None);
     }
 
     /// Uploads data to the buffer
-    pub fn upload_data(&self, gl: &WebGl2RenderingContext, data: &[u8]) {
+    pub fn upload_data(&self, gl: &Gl, data: &[u8]) {
         self.bind(gl);
         gl.buffer_data_with_u8_array(self.target, data, self.usage);
     }
 
     /// Updates a portion of the buffer data
-    pub fn update_data(&self, gl: &WebGl2RenderingContext, offset: i32, data: &[u8]) {
+    pub fn update_data(&self, gl: &Gl, offset: i32, data: &[u8]) {
         self.bind(gl);
         gl.buffer_sub_data_with_i32_and_u8_array(self.target, offset, data);
     }
@@ -0,0 +1,445 @@
+use web_sys::{WebGl2RenderingContext, WebGlProgram};
+
+use crate::{buffer::GpuBuffer, error::RenderError, gl::Gl};
+
+/// Name of the uniform block WebGL2 shaders declare for [`CameraUniforms`]
+///
+/// ```glsl
+/// layout(std140) uniform Camera {
+///     mat4 u_view_projection;
+///     vec2 u_resolution;
+///     float u_time;
+///     float u_pixel_ratio;
+/// };
+/// ```
+///
+/// WebGL1 has no uniform blocks; its shaders declare the members as plain
+/// uniforms of the same names instead.
+pub const CAMERA_BLOCK: &str = "Camera";
+
+/// Uniform buffer binding point the camera block is bound to
+pub const CAMERA_BINDING: u32 = 0;
+
+/// Pan, zoom and rotation of the map within the drawing buffer
+///
+/// The view maps map pixels, where the projection puts geometry, to screen
+/// pixels. It's baked into [`CameraUniforms::view_projection`], so changing
+/// it never touches vertex buffers.
+///
+/// ```
+/// use wmc_render::camera::View;
+///
+/// let view = View {
+///     center: [0.25, 0.5],
+///     zoom: 1.0,
+///     rotation: 0.0,
+/// };
+///
+/// // The center shows in the middle of the buffer at twice the scale
+/// assert_eq!(view.to_screen(800.0, 600.0, [200.0, 300.0]), [400.0, 300.0]);
+/// assert_eq!(view.to_screen(800.0, 600.0, [210.0, 300.0]), [420.0, 300.0]);
+/// assert_eq!(view.to_map(800.0, 600.0, [420.0, 300.0]), [210.0, 300.0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// Map point shown at the center of the buffer, as fractions of its
+    /// width and height
+    pub center: [f32; 2],
+    /// Magnification as a power of two; 0 shows the whole map
+    pub zoom: f32,
+    /// Clockwise rotation about the center in radians
+    pub rotation: f32,
+}
+
+impl View {
+    /// Returns the column-major 3x3 matrix mapping map pixels to screen
+    /// pixels of a `width` x `height` buffer
+    #[must_use]
+    pub fn matrix(&self, width: f32, height: f32) -> [f32; 9] {
+        let scale = self.zoom.exp2();
+        let (sin, cos) = (self.rotation.sin() * scale, self.rotation.cos() * scale);
+        let center = [self.center[0] * width, self.center[1] * height];
+
+        [
+            cos,
+            sin,
+            0.0,
+            -sin,
+            cos,
+            0.0,
+            sin.mul_add(center[1], cos.mul_add(-center[0], width * 0.5)),
+            cos.mul_add(-center[1], sin.mul_add(-center[0], height * 0.5)),
+            1.0,
+        ]
+    }
+
+    /// Maps a point in map pixels to screen pixels of a `width` x `height`
+    /// buffer
+    #[must_use]
+    pub fn to_screen(&self, width: f32, height: f32, [x, y]: [f32; 2]) -> [f32; 2] {
+        let m = self.matrix(width, height);
+        [
+            m[3].mul_add(y, m[0].mul_add(x, m[6])),
+            m[4].mul_add(y, m[1].mul_add(x, m[7])),
+        ]
+    }
+
+    /// Maps a point in screen pixels of a `width` x `height` buffer back to
+    /// map pixels
+    #[must_use]
+    pub fn to_map(&self, width: f32, height: f32, [x, y]: [f32; 2]) -> [f32; 2] {
+        let scale = (-self.zoom).exp2();
+        let (sin, cos) = (self.rotation.sin() * scale, self.rotation.cos() * scale);
+        let (dx, dy) = (width.mul_add(-0.5, x), height.mul_add(-0.5, y));
+
+        [
+            self.center[0].mul_add(width, sin.mul_add(dy, cos * dx)),
+            self.center[1].mul_add(height, cos.mul_add(dy, -sin * dx)),
+        ]
+    }
+
+    /// Returns the bounds `[min_x, min_y, max_x, max_y]` in screen pixels of
+    /// a `width` x `height` buffer covering `bounds` in map pixels
+    ///
+    /// ```
+    /// use wmc_render::camera::View;
+    ///
+    /// let view = View {
+    ///     rotation: std::f32::consts::FRAC_PI_2,
+    ///     ..View::default()
+    /// };
+    /// let [min_x, min_y, max_x, max_y] = view.to_screen_bounds(800.0, 600.0, [0.0, 0.0, 800.0, 600.0]);
+    /// assert!((min_x - 100.0).abs() < 1e-3 && (max_x - 700.0).abs() < 1e-3);
+    /// assert!((min_y + 100.0).abs() < 1e-3 && (max_y - 700.0).abs() < 1e-3);
+    /// ```
+    #[must_use]
+    pub fn to_screen_bounds(&self, width: f32, height: f32, bounds: [f32; 4]) -> [f32; 4] {
+        corner_bounds(bounds, |point| self.to_screen(width, height, point))
+    }
+
+    /// Returns the bounds `[min_x, min_y, max_x, max_y]` in map pixels
+    /// covering `bounds` in screen pixels of a `width` x `height` buffer,
+    /// e.g. the part of the map the whole buffer shows
+    ///
+    /// ```
+    /// use wmc_render::camera::View;
+    ///
+    /// let view = View {
+    ///     zoom: 1.0,
+    ///     ..View::default()
+    /// };
+    /// assert_eq!(
+    ///     view.to_map_bounds(800.0, 600.0, [0.0, 0.0, 800.0, 600.0]),
+    ///     [200.0, 150.0, 600.0, 450.0]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_map_bounds(&self, width: f32, height: f32, bounds: [f32; 4]) -> [f32; 4] {
+        corner_bounds(bounds, |point| self.to_map(width, height, point))
+    }
+
+    /// Rounds the zoom to the nearest multiple of `step`, keeping the map
+    /// point at `anchor` in screen pixels of a `width` x `height` buffer in
+    /// place
+    ///
+    /// A `step` of 1 settles on integer zooms, where raster tiles show at
+    /// their native scale, e.g. once a pinch or its inertia ends at the
+    /// pinch center. Zero or any other step that isn't positive and finite
+    /// keeps the zoom continuous.
+    ///
+    /// ```
+    /// use wmc_render::camera::View;
+    ///
+    /// let view = View {
+    ///     center: [0.25, 0.5],
+    ///     zoom: 1.4,
+    ///     rotation: 0.3,
+    /// };
+    /// let point = view.to_map(800.0, 600.0, [100.0, 200.0]);
+    ///
+    /// let snapped = view.snap_zoom(1.0, 800.0, 600.0, [100.0, 200.0]);
+    /// assert_eq!(snapped.zoom, 1.0);
+    /// let [x, y] = snapped.to_screen(800.0, 600.0, point);
+    /// assert!((x - 100.0).abs() < 1e-3 && (y - 200.0).abs() < 1e-3);
+    ///
+    /// assert_eq!(view.snap_zoom(0.0, 800.0, 600.0, [100.0, 200.0]), view);
+    /// ```
+    #[must_use]
+    pub fn snap_zoom(self, step: f32, width: f32, height: f32, anchor: [f32; 2]) -> Self {
+        if !(step > 0.0 && step.is_finite()) {
+            return self;
+        }
+
+        let before = self.to_map(width, height, anchor);
+        let mut snapped = Self {
+            zoom: (self.zoom / step).round() * step,
+            ..self
+        };
+        // Moving the center moves every map point under the screen alike
+        let after = snapped.to_map(width, height, anchor);
+        snapped.center = [
+            self.center[0] + (before[0] - after[0]) / width,
+            self.center[1] + (before[1] - after[1]) / height,
+        ];
+        snapped
+    }
+
+    /// Zooms by `zoom` steps and rotates clockwise by `rotation` radians,
+    /// moving the map point shown at `from` to `to`, both in screen pixels
+    /// of a `width` x `height` buffer
+    ///
+    /// This is the view after a drag or pinch: the map point under the
+    /// pointers follows them.
+    ///
+    /// ```
+    /// use wmc_render::camera::View;
+    ///
+    /// let view = View::default();
+    /// let point = view.to_map(800.0, 600.0, [100.0, 200.0]);
+    ///
+    /// let moved = view.transformed(800.0, 600.0, [100.0, 200.0], [300.0, 250.0], 1.0, 0.5);
+    /// assert_eq!((moved.zoom, moved.rotation), (1.0, 0.5));
+    /// let [x, y] = moved.to_screen(800.0, 600.0, point);
+    /// assert!((x - 300.0).abs() < 1e-3 && (y - 250.0).abs() < 1e-3);
+    /// ```
+    #[must_use]
+    pub fn transformed(
+        self,
+        width: f32,
+        height: f32,
+        from: [f32; 2],
+        to: [f32; 2],
+        zoom: f32,
+        rotation: f32,
+    ) -> Self {
+        let before = self.to_map(width, height, from);
+        let mut moved = Self {
+            zoom: self.zoom + zoom,
+            rotation: (self.rotation + rotation).rem_euclid(std::f32::consts::TAU),
+            ..self
+        };
+        let after = moved.to_map(width, height, to);
+        moved.center = [
+            self.center[0] + (before[0] - after[0]) / width,
+            self.center[1] + (before[1] - after[1]) / height,
+        ];
+        moved
+    }
+}
+
+/// Returns the bounds of the four corners of `bounds` mapped by `map`
+fn corner_bounds(bounds: [f32; 4], map: impl Fn([f32; 2]) -> [f32; 2]) -> [f32; 4] {
+    let [min_x, min_y, max_x, max_y] = bounds;
+    [
+        [min_x, min_y],
+        [max_x, min_y],
+        [min_x, max_y],
+        [max_x, max_y],
+    ]
+    .map(map)
+    .iter()
+    .fold(
+        [
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ],
+        |[min_x, min_y, max_x, max_y], &[x, y]| {
+            [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+        },
+    )
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            center: [0.5, 0.5],
+            zoom: 0.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+/// Per-frame values shared by every shader program
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraUniforms {
+    /// Column-major mapping from map pixels, y down, to clip space, through
+    /// the [`View`]
+    pub view_projection: [f32; 16],
+    /// Drawing buffer size in device pixels
+    pub resolution: [f32; 2],
+    /// Animation time in seconds
+    pub time: f32,
+    /// Device pixel ratio
+    pub pixel_ratio: f32,
+}
+
+impl CameraUniforms {
+    /// Builds the uniforms for a `width` × `height` drawing buffer showing
+    /// the whole map
+    ///
+    /// ```
+    /// use wmc_render::camera::CameraUniforms;
+    ///
+    /// let camera = CameraUniforms::new(800, 600, 0.0, 2.0);
+    /// let m = camera.view_projection;
+    ///
+    /// // The top-left pixel maps to the top-left clip corner
+    /// assert_eq!((m[12], m[13]), (-1.0, 1.0));
+    /// // and the bottom-right pixel to the bottom-right one
+    /// assert_eq!((800.0 * m[0] + m[12], 600.0 * m[5] + m[13]), (1.0, -1.0));
+    /// ```
+    #[must_use]
+    pub fn new(width: u32, height: u32, time: f32, pixel_ratio: f32) -> Self {
+        Self::with_view(width, height, time, pixel_ratio, &View::default())
+    }
+
+    /// Builds the uniforms for a `width` × `height` drawing buffer showing
+    /// the map through `view`
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn with_view(width: u32, height: u32, time: f32, pixel_ratio: f32, view: &View) -> Self {
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let m = view.matrix(width, height);
+        // Screen pixels to clip space, y up
+        let (sx, sy) = (2.0 / width, -2.0 / height);
+
+        Self {
+            view_projection: [
+                m[0] * sx,
+                m[1] * sy,
+                0.0,
+                0.0,
+                m[3] * sx,
+                m[4] * sy,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                1.0,
+                0.0,
+                m[6].mul_add(sx, -1.0),
+                m[7].mul_add(sy, 1.0),
+                0.0,
+                1.0,
+            ],
+            resolution: [width, height],
+            time,
+            pixel_ratio,
+        }
+    }
+
+    /// Returns the values in `std140` layout
+    fn to_std140(self) -> [f32; 20] {
+        let mut data = [0.0; 20];
+        data[..16].copy_from_slice(&self.view_projection);
+        data[16..18].copy_from_slice(&self.resolution);
+        data[18] = self.time;
+        data[19] = self.pixel_ratio;
+        data
+    }
+}
+
+impl Default for CameraUniforms {
+    fn default() -> Self {
+        Self::new(1, 1, 0.0, 1.0)
+    }
+}
+
+/// Uniform buffer holding [`CameraUniforms`] on WebGL2
+pub struct CameraBuffer {
+    buffer: GpuBuffer,
+}
+
+impl CameraBuffer {
+    /// Creates the camera uniform buffer, or `None` on WebGL1
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::BufferAllocationFailed`] if buffer creation fails
+    pub fn new(gl: &Gl) -> Result<Option<Self>, RenderError> {
+        if !gl.is_webgl2() {
+            return Ok(None);
+        }
+
+        let buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::UNIFORM_BUFFER,
+            WebGl2RenderingContext::DYNAMIC_DRAW,
+        )?;
+
+        Ok(Some(Self { buffer }))
+    }
+
+    /// Frees the buffer's GPU memory
+    pub fn delete(&self, gl: &Gl) {
+        self.buffer.delete(gl);
+    }
+
+    /// Uploads `camera` and binds the buffer to [`CAMERA_BINDING`]
+    pub fn upload(&self, gl: &Gl, camera: &CameraUniforms) {
+        let data = camera.to_std140();
+
+        #[allow(unsafe_code)]
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr().cast::<u8>(), std::mem::size_of_val(&data))
+        };
+        self.buffer.upload_data(gl, bytes);
+
+        if let Some(gl) = gl.as_webgl2() {
+            gl.bind_buffer_base(
+                WebGl2RenderingContext::UNIFORM_BUFFER,
+                CAMERA_BINDING,
+                Some(self.buffer.buffer()),
+            );
+        }
+    }
+}
+
+/// Locations of the camera uniforms a WebGL1 program declares
+///
+/// On WebGL2 the members live in the [`CAMERA_BLOCK`] block, which has no
+/// uniform locations, so every field stays `None`.
+pub(crate) struct CameraLocations {
+    view_projection: Option<web_sys::WebGlUniformLocation>,
+    resolution: Option<web_sys::WebGlUniformLocation>,
+    time: Option<web_sys::WebGlUniformLocation>,
+    pixel_ratio: Option<web_sys::WebGlUniformLocation>,
+}
+
+impl CameraLocations {
+    /// Looks up the camera uniforms of `program` and binds its camera block,
+    /// if it has one, to [`CAMERA_BINDING`]
+    pub(crate) fn new(gl: &Gl, program: &WebGlProgram) -> Self {
+        if let Some(gl) = gl.as_webgl2() {
+            let index = gl.get_uniform_block_index(program, CAMERA_BLOCK);
+            if index != WebGl2RenderingContext::INVALID_INDEX {
+                gl.uniform_block_binding(program, index, CAMERA_BINDING);
+            }
+        }
+
+        Self {
+            view_projection: gl.get_uniform_location(program, "u_view_projection"),
+            resolution: gl.get_uniform_location(program, "u_resolution"),
+            time: gl.get_uniform_location(program, "u_time"),
+            pixel_ratio: gl.get_uniform_location(program, "u_pixel_ratio"),
+        }
+    }
+
+    /// Sets the declared uniforms of the bound program to `camera`
+    pub(crate) fn apply(&self, gl: &Gl, camera: &CameraUniforms) {
+        if let Some(location) = &self.view_projection {
+            gl.uniform_matrix4fv_with_f32_array(Some(location), false, &camera.view_projection);
+        }
+        if let Some(location) = &self.resolution {
+            gl.uniform2f(Some(location), camera.resolution[0], camera.resolution[1]);
+        }
+        if let Some(location) = &self.time {
+            gl.uniform1f(Some(location), camera.time);
+        }
+        if let Some(location) = &self.pixel_ratio {
+            gl.uniform1f(Some(location), camera.pixel_ratio);
+        }
+    }
+}
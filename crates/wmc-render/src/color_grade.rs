@@ -0,0 +1,76 @@
+use web_sys::WebGlUniformLocation;
+use wmc_core::theme::ColorGrading;
+
+use crate::{
+    error::RenderError,
+    gl::Gl,
+    post_process::{PostEffect, PostFrame, effect_program},
+    program::ShaderProgram,
+    shaders,
+};
+
+/// Exposure, saturation and tint grading post effect
+///
+/// Disabled while the grading is the identity.
+pub struct ColorGradeEffect {
+    program: ShaderProgram,
+    u_exposure: WebGlUniformLocation,
+    u_saturation: WebGlUniformLocation,
+    u_tint: WebGlUniformLocation,
+    grading: ColorGrading,
+}
+
+impl ColorGradeEffect {
+    /// Creates a color grading effect
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation fails
+    pub fn new(gl: &Gl, grading: ColorGrading) -> Result<Self, RenderError> {
+        let program = effect_program(
+            gl,
+            shaders::COLOR_GRADE_FRAGMENT,
+            shaders::COLOR_GRADE_FRAGMENT_ES100,
+        )?;
+
+        let u_exposure = program.get_uniform_location(gl, "u_exposure")?;
+        let u_saturation = program.get_uniform_location(gl, "u_saturation")?;
+        let u_tint = program.get_uniform_location(gl, "u_tint")?;
+
+        Ok(Self {
+            program,
+            u_exposure,
+            u_saturation,
+            u_tint,
+            grading,
+        })
+    }
+
+    /// Returns the grading settings
+    #[must_use]
+    pub const fn grading(&self) -> ColorGrading {
+        self.grading
+    }
+
+    /// Replaces the grading settings
+    pub const fn set_grading(&mut self, grading: ColorGrading) {
+        self.grading = grading;
+    }
+}
+
+impl PostEffect for ColorGradeEffect {
+    fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    fn apply_uniforms(&self, gl: &Gl, _frame: &PostFrame) {
+        let tint = self.grading.tint;
+        gl.uniform1f(Some(&self.u_exposure), self.grading.exposure);
+        gl.uniform1f(Some(&self.u_saturation), self.grading.saturation);
+        gl.uniform4f(Some(&self.u_tint), tint.r, tint.g, tint.b, tint.a);
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.grading.is_identity()
+    }
+}
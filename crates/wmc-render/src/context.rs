@@ -1,13 +1,199 @@
-use wasm_bindgen::JsCast;
-use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
+use std::cell::Cell;
 
-use crate::error::RenderError;
+use js_sys::Promise;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    HtmlCanvasElement, OffscreenCanvas, WebGl2RenderingContext, WebGlContextAttributes,
+    WebGlPowerPreference, WebGlRenderingContext,
+};
+
+use crate::{
+    blend::BlendMode,
+    camera::{CameraBuffer, CameraUniforms, View},
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    scissor::ScissorRect,
+};
+
+/// GPU selection hint passed to the browser on context creation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerPreference {
+    /// Let the browser decide
+    #[default]
+    Default,
+    /// Prefer an integrated, power-saving GPU
+    LowPower,
+    /// Prefer a discrete, high-performance GPU
+    HighPerformance,
+}
+
+impl From<PowerPreference> for WebGlPowerPreference {
+    fn from(preference: PowerPreference) -> Self {
+        match preference {
+            PowerPreference::Default => Self::Default,
+            PowerPreference::LowPower => Self::LowPower,
+            PowerPreference::HighPerformance => Self::HighPerformance,
+        }
+    }
+}
+
+/// WebGL context creation attributes
+///
+/// Field names follow the `WebGLContextAttributes` dictionary when
+/// deserialized, e.g. `{"antialias": false, "powerPreference": "low-power"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ContextOptions {
+    /// Request a multisampled drawing buffer
+    pub antialias: bool,
+    /// Give the drawing buffer an alpha channel
+    pub alpha: bool,
+    /// Treat drawing buffer colors as premultiplied by alpha
+    pub premultiplied_alpha: bool,
+    /// Keep the drawing buffer contents after compositing
+    pub preserve_drawing_buffer: bool,
+    /// Request an 8-bit stencil buffer, needed for stencil polygon fills
+    pub stencil: bool,
+    /// GPU selection hint
+    pub power_preference: PowerPreference,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            antialias: true,
+            alpha: true,
+            premultiplied_alpha: true,
+            preserve_drawing_buffer: false,
+            stencil: true,
+            power_preference: PowerPreference::Default,
+        }
+    }
+}
+
+impl ContextOptions {
+    fn to_attributes(self) -> WebGlContextAttributes {
+        let attributes = WebGlContextAttributes::new();
+        attributes.set_antialias(self.antialias);
+        attributes.set_alpha(self.alpha);
+        attributes.set_premultiplied_alpha(self.premultiplied_alpha);
+        attributes.set_preserve_drawing_buffer(self.preserve_drawing_buffer);
+        attributes.set_stencil(self.stencil);
+        attributes.set_power_preference(self.power_preference.into());
+        attributes
+    }
+}
+
+/// Canvas a rendering context draws into
+#[derive(Debug, Clone)]
+pub enum Surface {
+    /// Canvas element in the document
+    Element(HtmlCanvasElement),
+    /// Offscreen canvas, typically transferred to a Web Worker
+    Offscreen(OffscreenCanvas),
+}
+
+impl Surface {
+    pub(crate) fn set_size(&self, width: u32, height: u32) {
+        match self {
+            Self::Element(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            },
+            Self::Offscreen(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            },
+        }
+    }
+
+    /// Encodes the current canvas contents as PNG
+    ///
+    /// The snapshot is taken synchronously, so call this in the same task
+    /// that drew the frame; without `preserveDrawingBuffer` the canvas is
+    /// cleared once the frame is presented. The promise resolves to a `Blob`,
+    /// or to `null` for an empty canvas.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::ExportFailed`] if the browser refuses to encode
+    /// the canvas
+    pub fn to_png(&self) -> Result<Promise, RenderError> {
+        let export_error = |e: JsValue| RenderError::ExportFailed {
+            reason: e.as_string().unwrap_or_else(|| format!("{e:?}")),
+        };
+
+        match self {
+            Self::Element(canvas) => {
+                let mut error = None;
+                let promise = Promise::new(&mut |resolve, _reject| {
+                    error = canvas.to_blob_with_type(&resolve, "image/png").err();
+                });
+
+                error.map_or(Ok(promise), |e| Err(export_error(e)))
+            },
+            Self::Offscreen(canvas) => canvas.convert_to_blob().map_err(export_error),
+        }
+    }
+
+    /// Creates a WebGL2 context, falling back to WebGL1
+    fn webgl(&self, options: ContextOptions) -> Result<Gl, RenderError> {
+        let attributes = options.to_attributes();
+
+        if let Some(context) = self.get_context("webgl2", &attributes)? {
+            return context
+                .dyn_into::<WebGl2RenderingContext>()
+                .map(Gl::webgl2)
+                .map_err(|_| RenderError::UnsupportedWebGLVersion);
+        }
+
+        self.get_context("webgl", &attributes)?
+            .ok_or(RenderError::UnsupportedWebGLVersion)?
+            .dyn_into::<WebGlRenderingContext>()
+            .map(Gl::webgl1)
+            .map_err(|_| RenderError::UnsupportedWebGLVersion)
+    }
+
+    fn get_context(
+        &self,
+        kind: &str,
+        attributes: &WebGlContextAttributes,
+    ) -> Result<Option<js_sys::Object>, RenderError> {
+        let context = match self {
+            Self::Element(canvas) => canvas.get_context_with_context_options(kind, attributes),
+            Self::Offscreen(canvas) => canvas.get_context_with_context_options(kind, attributes),
+        };
+
+        context.map_err(|_| RenderError::WebGLContextCreationFailed)
+    }
+}
 
 /// WebGL rendering context with canvas state
+///
+/// Uses WebGL2 where available and WebGL1 otherwise; see [`Gl`].
+///
+/// The drawing buffer is sized at the canvas CSS size multiplied by the
+/// device pixel ratio, so rendering stays sharp on high-density displays. All
+/// rendering happens in drawing-buffer pixels.
 pub struct RenderContext {
-    gl: WebGl2RenderingContext,
+    gl: Gl,
+    surface: Surface,
+    css_width: u32,
+    css_height: u32,
     width: u32,
     height: u32,
+    pixel_ratio: f64,
+    preserve_drawing_buffer: bool,
+    stencil: bool,
+    blend_mode: Cell<BlendMode>,
+    camera: Cell<CameraUniforms>,
+    view: Cell<View>,
+    camera_buffer: Option<CameraBuffer>,
+    scissor: Cell<Option<ScissorRect>>,
 }
 
 impl RenderContext {
@@ -15,54 +201,378 @@ impl RenderContext {
     ///
     /// # Errors
     ///
-    /// Returns [`RenderError`] if WebGL2 context creation fails
-    #[allow(clippy::cast_possible_wrap)]
+    /// Returns [`RenderError`] if neither a WebGL2 nor a WebGL1 context can be
+    /// created
     pub fn new(canvas: &HtmlCanvasElement) -> Result<Self, RenderError> {
-        let gl = canvas
-            .get_context("webgl2")
-            .map_err(|_| RenderError::WebGLContextCreationFailed)?
-            .ok_or(RenderError::WebGLContextCreationFailed)?
-            .dyn_into::<WebGl2RenderingContext>()
-            .map_err(|_| RenderError::UnsupportedWebGLVersion)?;
+        Self::with_options(canvas, ContextOptions::default())
+    }
+
+    /// Creates a new rendering context with explicit context attributes
+    ///
+    /// The CSS size is taken from the canvas layout box, or from its
+    /// `width`/`height` attributes when it is not laid out yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if neither a WebGL2 nor a WebGL1 context can be
+    /// created
+    pub fn with_options(
+        canvas: &HtmlCanvasElement,
+        options: ContextOptions,
+    ) -> Result<Self, RenderError> {
+        let pixel_ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+
+        let (css_width, css_height) = match (
+            u32::try_from(canvas.client_width()),
+            u32::try_from(canvas.client_height()),
+        ) {
+            (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+            _ => (canvas.width(), canvas.height()),
+        };
+
+        Self::from_surface(
+            Surface::Element(canvas.clone()),
+            options,
+            (css_width, css_height),
+            pixel_ratio,
+        )
+    }
+
+    /// Creates a new rendering context from an offscreen canvas
+    ///
+    /// Workers have no layout or `devicePixelRatio`, so the canvas' current
+    /// `width`/`height` are taken as its size in device pixels at
+    /// `pixel_ratio`, as set up by the main thread before transferring it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if neither a WebGL2 nor a WebGL1 context can be
+    /// created
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn with_offscreen(
+        canvas: &OffscreenCanvas,
+        options: ContextOptions,
+        pixel_ratio: f64,
+    ) -> Result<Self, RenderError> {
+        let pixel_ratio = if pixel_ratio.is_finite() && pixel_ratio > 0.0 {
+            pixel_ratio
+        } else {
+            1.0
+        };
 
-        let width = canvas.width();
-        let height = canvas.height();
+        let css_width = (f64::from(canvas.width()) / pixel_ratio).round() as u32;
+        let css_height = (f64::from(canvas.height()) / pixel_ratio).round() as u32;
 
-        gl.viewport(0, 0, width as i32, height as i32);
+        Self::from_surface(
+            Surface::Offscreen(canvas.clone()),
+            options,
+            (css_width, css_height),
+            pixel_ratio,
+        )
+    }
+
+    fn from_surface(
+        surface: Surface,
+        options: ContextOptions,
+        (css_width, css_height): (u32, u32),
+        pixel_ratio: f64,
+    ) -> Result<Self, RenderError> {
+        let gl = surface.webgl(options)?;
+
+        // Anti-aliased edges and translucent theme colors rely on alpha blending
+        gl.enable(WebGl2RenderingContext::BLEND);
+        BlendMode::Normal.apply(&gl, false);
+
+        let camera_buffer = CameraBuffer::new(&gl)?;
 
-        Ok(Self { gl, width, height })
+        let mut ctx = Self {
+            gl,
+            surface,
+            css_width,
+            css_height,
+            width: 0,
+            height: 0,
+            pixel_ratio,
+            preserve_drawing_buffer: options.preserve_drawing_buffer,
+            stencil: options.stencil,
+            blend_mode: Cell::new(BlendMode::Normal),
+            camera: Cell::new(CameraUniforms::default()),
+            view: Cell::new(View::default()),
+            camera_buffer,
+            scissor: Cell::new(None),
+        };
+        ctx.resize(css_width, css_height);
+
+        Ok(ctx)
+    }
+
+    /// Returns the canvas this context draws into
+    #[must_use]
+    pub const fn surface(&self) -> &Surface {
+        &self.surface
     }
 
-    /// Returns a reference to the WebGL2 context
+    /// Returns a reference to the WebGL context
     #[must_use]
-    pub const fn gl(&self) -> &WebGl2RenderingContext {
+    pub const fn gl(&self) -> &Gl {
         &self.gl
     }
 
-    /// Returns the canvas width in pixels
+    /// Returns the drawing buffer width in device pixels
     #[must_use]
     pub const fn width(&self) -> u32 {
         self.width
     }
 
-    /// Returns the canvas height in pixels
+    /// Returns the drawing buffer height in device pixels
     #[must_use]
     pub const fn height(&self) -> u32 {
         self.height
     }
 
-    /// Resizes the viewport
+    /// Returns the canvas width in CSS pixels
+    #[must_use]
+    pub const fn css_width(&self) -> u32 {
+        self.css_width
+    }
+
+    /// Returns the canvas height in CSS pixels
+    #[must_use]
+    pub const fn css_height(&self) -> u32 {
+        self.css_height
+    }
+
+    /// Returns the device pixel ratio the drawing buffer is sized for
+    ///
+    /// Multiply CSS pixel lengths (line widths, marker radii) by this value to
+    /// get drawing buffer pixels. Line edges are feathered over one CSS pixel.
+    #[must_use]
+    pub const fn pixel_ratio(&self) -> f64 {
+        self.pixel_ratio
+    }
+
+    /// Returns true if the canvas keeps its contents between frames
+    ///
+    /// Only then can a frame redraw part of the canvas directly.
+    #[must_use]
+    pub const fn preserves_drawing_buffer(&self) -> bool {
+        self.preserve_drawing_buffer
+    }
+
+    /// Returns true if a stencil buffer was requested for the canvas
+    #[must_use]
+    pub const fn has_stencil(&self) -> bool {
+        self.stencil
+    }
+
+    /// Returns the blend mode layers are currently drawn with
+    #[must_use]
+    pub const fn blend_mode(&self) -> BlendMode {
+        self.blend_mode.get()
+    }
+
+    /// Sets the blend mode for subsequent drawing and applies it for
+    /// straight-alpha sources
+    pub fn set_blend_mode(&self, mode: BlendMode) {
+        self.blend_mode.set(mode);
+        mode.apply(&self.gl, false);
+    }
+
+    /// Applies the current blend mode for straight or premultiplied-alpha
+    /// sources
+    ///
+    /// Renderers drawing premultiplied colors switch with `true` and restore
+    /// with `false` when done.
+    pub fn apply_blend(&self, premultiplied: bool) {
+        self.blend_mode.get().apply(&self.gl, premultiplied);
+    }
+
+    /// Returns the camera uniforms of the current frame
+    #[must_use]
+    pub const fn camera(&self) -> CameraUniforms {
+        self.camera.get()
+    }
+
+    /// Returns the pan, zoom and rotation of the map
+    #[must_use]
+    pub const fn view(&self) -> View {
+        self.view.get()
+    }
+
+    /// Sets the pan, zoom and rotation of the map from the next
+    /// [`Self::update_camera`] on
+    ///
+    /// Only the camera uniforms change, so geometry needs no reprojection.
+    /// Text is drawn in screen pixels, so it's placed through
+    /// [`Self::to_screen`] rather than the camera.
+    pub fn set_view(&self, view: View) {
+        self.view.set(view);
+    }
+
+    /// Maps a point in map pixels to drawing buffer pixels through the
+    /// [`View`]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_screen(&self, point: [f32; 2]) -> [f32; 2] {
+        self.view
+            .get()
+            .to_screen(self.width as f32, self.height as f32, point)
+    }
+
+    /// Maps a point in drawing buffer pixels back to map pixels through the
+    /// [`View`]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_map(&self, point: [f32; 2]) -> [f32; 2] {
+        self.view
+            .get()
+            .to_map(self.width as f32, self.height as f32, point)
+    }
+
+    /// Returns the part of the map the drawing buffer shows as `[min_x,
+    /// min_y, max_x, max_y]` in map pixels
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn visible_bounds(&self) -> [f32; 4] {
+        let (width, height) = (self.width as f32, self.height as f32);
+        self.view
+            .get()
+            .to_map_bounds(width, height, [0.0, 0.0, width, height])
+    }
+
+    /// Returns the region of the drawing buffer covering `bounds` in map
+    /// pixels, `None` if it's off screen
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn scissor_for(&self, bounds: [f32; 4]) -> Option<ScissorRect> {
+        let bounds =
+            self.view
+                .get()
+                .to_screen_bounds(self.width as f32, self.height as f32, bounds);
+        ScissorRect::from_bounds(bounds, self.width, self.height)
+    }
+
+    /// Sets the camera uniforms for a frame at `time` seconds
+    ///
+    /// Call once per frame before drawing. The uniforms cover the current
+    /// drawing buffer through the [`View`]; on WebGL2 they are uploaded to
+    /// the camera uniform buffer, which every program reads from.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn update_camera(&self, time: f32) {
+        let camera = CameraUniforms::with_view(
+            self.width,
+            self.height,
+            time,
+            self.pixel_ratio as f32,
+            &self.view.get(),
+        );
+        self.camera.set(camera);
+
+        if let Some(buffer) = &self.camera_buffer {
+            buffer.upload(&self.gl, &camera);
+        }
+    }
+
+    /// Frees the context's own GPU resources, precompiled programs nothing
+    /// took over included
+    ///
+    /// Resources created by renderers are theirs to delete. The context
+    /// still clears and resizes afterwards, but programs no longer receive
+    /// the camera uniforms on WebGL2.
+    pub fn delete(&mut self) {
+        if let Some(buffer) = self.camera_buffer.take() {
+            buffer.delete(&self.gl);
+        }
+        ShaderProgram::delete_precompiled(&self.gl);
+    }
+
+    /// Restricts clearing and drawing to `rect`, or lifts the restriction
+    /// with `None`
+    ///
+    /// The scissor box applies to whatever framebuffer is bound.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn set_scissor(&self, rect: Option<ScissorRect>) {
+        self.scissor.set(rect);
+        match rect {
+            Some(rect) => {
+                let bottom = self.height.saturating_sub(rect.y + rect.height);
+                self.gl.scissor(
+                    rect.x as i32,
+                    bottom as i32,
+                    rect.width as i32,
+                    rect.height as i32,
+                );
+                self.gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+            },
+            None => self.gl.disable(WebGl2RenderingContext::SCISSOR_TEST),
+        }
+    }
+
+    /// Returns the rectangle drawing is restricted to, if any
+    ///
+    /// Renderers may skip geometry outside it.
+    #[must_use]
+    pub const fn scissor(&self) -> Option<ScissorRect> {
+        self.scissor.get()
+    }
+
+    /// Resizes the canvas to the given CSS size
+    ///
+    /// The drawing buffer and viewport are set to the CSS size multiplied by
+    /// the device pixel ratio.
+    #[allow(
+        clippy::cast_possible_wrap,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn resize(&mut self, css_width: u32, css_height: u32) {
+        self.css_width = css_width;
+        self.css_height = css_height;
+        self.width = (f64::from(css_width) * self.pixel_ratio).round() as u32;
+        self.height = (f64::from(css_height) * self.pixel_ratio).round() as u32;
+
+        self.surface.set_size(self.width, self.height);
+        self.gl
+            .viewport(0, 0, self.width as i32, self.height as i32);
+    }
+
+    /// Updates the device pixel ratio and resizes the drawing buffer to match
+    ///
+    /// Returns `true` if the ratio changed.
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) -> bool {
+        if !pixel_ratio.is_finite() || pixel_ratio <= 0.0 {
+            return false;
+        }
+
+        #[allow(clippy::float_cmp)]
+        if pixel_ratio == self.pixel_ratio {
+            return false;
+        }
+
+        self.pixel_ratio = pixel_ratio;
+        self.resize(self.css_width, self.css_height);
+        true
+    }
+
+    /// Directs rendering to the canvas and restores the full viewport
+    ///
+    /// Undoes [`crate::RenderTarget::bind`].
     #[allow(clippy::cast_possible_wrap)]
-    pub fn resize(&mut self, width: u32, height: u32) {
-        self.width = width;
-        self.height = height;
-        self.gl.viewport(0, 0, width as i32, height as i32);
+    pub fn bind_canvas(&self) {
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        self.gl
+            .viewport(0, 0, self.width as i32, self.height as i32);
     }
 
-    /// Clears the canvas with the specified color
+    /// Clears the bound framebuffer with the specified color
+    ///
+    /// The stencil buffer, if any, is cleared to zero as well.
     #[allow(clippy::many_single_char_names)]
     pub fn clear(&self, r: f32, g: f32, b: f32, a: f32) {
         self.gl.clear_color(r, g, b, a);
-        self.gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        self.gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::STENCIL_BUFFER_BIT,
+        );
     }
 }
@@ -1,11 +1,17 @@
 use std::fmt;
 
 use masterror::AppError;
+use serde::Serialize;
 
 /// Rendering errors
-#[derive(Debug, Clone)]
+///
+/// Serializes as `{code, details}`, `code` being the variant name in
+/// snake case and `details` its fields
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
 pub enum RenderError {
-    /// Failed to create WebGL2 context
+    /// Failed to create a WebGL context
+    #[serde(rename = "webgl_context_creation_failed")]
     WebGLContextCreationFailed,
     /// Shader compilation failed
     ShaderCompilationFailed {
@@ -14,6 +20,11 @@ pub enum RenderError {
         /// Compilation error log
         log: String,
     },
+    /// Shader source includes a chunk that doesn't exist
+    UnknownShaderChunk {
+        /// Chunk name
+        name: String,
+    },
     /// Program linking failed
     ProgramLinkingFailed {
         /// Linking error log
@@ -24,7 +35,8 @@ pub enum RenderError {
         /// Requested size in bytes
         size: usize,
     },
-    /// WebGL2 not supported
+    /// Neither WebGL2 nor WebGL1 is supported
+    #[serde(rename = "unsupported_webgl_version")]
     UnsupportedWebGLVersion,
     /// Uniform location not found
     UniformLocationNotFound {
@@ -33,24 +45,81 @@ pub enum RenderError {
     },
     /// VAO creation failed
     VaoCreationFailed,
+    /// Instanced drawing is not supported (WebGL1 without
+    /// `ANGLE_instanced_arrays`)
+    InstancingUnsupported,
+    /// Texture creation or allocation failed
+    TextureCreationFailed,
+    /// Texture upload was rejected
+    TextureUploadFailed {
+        /// Browser error description
+        reason: String,
+    },
+    /// Framebuffer is incomplete
+    FramebufferIncomplete {
+        /// `checkFramebufferStatus` result
+        status: u32,
+    },
+    /// Canvas contents could not be exported
+    ExportFailed {
+        /// Browser error description
+        reason: String,
+    },
+    /// Glyphs could not be rasterized for the text atlas
+    GlyphRasterizationFailed {
+        /// Browser error description
+        reason: String,
+    },
+    /// WebGPU is not exposed or no adapter is available
+    #[serde(rename = "webgpu_unavailable")]
+    WebGpuUnavailable,
+    /// WebGPU device or canvas setup failed
+    #[serde(rename = "webgpu_device_failed")]
+    WebGpuDeviceFailed {
+        /// Browser error description
+        reason: String,
+    },
+    /// Golden image could not be decoded
+    SnapshotDecodeFailed {
+        /// What is wrong with the file
+        reason: String,
+    },
 }
 
 impl fmt::Display for RenderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::WebGLContextCreationFailed => write!(f, "Failed to create WebGL2 context"),
+            Self::WebGLContextCreationFailed => write!(f, "Failed to create WebGL context"),
             Self::ShaderCompilationFailed { shader_type, log } => {
                 write!(f, "Shader compilation failed ({shader_type}): {log}")
             },
+            Self::UnknownShaderChunk { name } => write!(f, "Unknown shader chunk: {name}"),
             Self::ProgramLinkingFailed { log } => write!(f, "Program linking failed: {log}"),
             Self::BufferAllocationFailed { size } => {
                 write!(f, "Buffer allocation failed: {size} bytes")
             },
-            Self::UnsupportedWebGLVersion => write!(f, "WebGL2 not supported"),
+            Self::UnsupportedWebGLVersion => write!(f, "WebGL not supported"),
             Self::UniformLocationNotFound { name } => {
                 write!(f, "Uniform location not found: {name}")
             },
             Self::VaoCreationFailed => write!(f, "Failed to create Vertex Array Object"),
+            Self::InstancingUnsupported => write!(f, "Instanced drawing not supported"),
+            Self::TextureCreationFailed => write!(f, "Failed to create texture"),
+            Self::TextureUploadFailed { reason } => write!(f, "Texture upload failed: {reason}"),
+            Self::FramebufferIncomplete { status } => {
+                write!(f, "Framebuffer incomplete: status {status:#06x}")
+            },
+            Self::ExportFailed { reason } => write!(f, "Canvas export failed: {reason}"),
+            Self::GlyphRasterizationFailed { reason } => {
+                write!(f, "Glyph rasterization failed: {reason}")
+            },
+            Self::WebGpuUnavailable => write!(f, "WebGPU not supported"),
+            Self::WebGpuDeviceFailed { reason } => {
+                write!(f, "WebGPU device setup failed: {reason}")
+            },
+            Self::SnapshotDecodeFailed { reason } => {
+                write!(f, "Snapshot decoding failed: {reason}")
+            },
         }
     }
 }
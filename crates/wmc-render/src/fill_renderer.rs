@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use web_sys::WebGl2RenderingContext;
+use wmc_core::{
+    projection::{GeoCoord, ProjectedCoord, Projection},
+    topology::WorldTopology,
+    triangulate::ear_clip,
+};
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// How a [`FillRenderer`] covers polygon interiors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FillMode {
+    /// Ear-clipped outer rings; holes are ignored
+    #[default]
+    Triangulate,
+    /// Even-odd stencil fill of every ring
+    ///
+    /// Each ring is drawn as a triangle fan that inverts the stencil buffer,
+    /// then a quad covering the layer colors the pixels left odd. Holes and
+    /// self-intersecting rings come out right without triangulation, but
+    /// overlapping polygons cancel out like holes.
+    Stencil,
+}
+
+/// WebGL renderer for filled topology polygons
+pub struct FillRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    mode: FillMode,
+    /// Triangle vertices, or fan vertices in stencil mode
+    vertex_count: i32,
+    /// Vertices of the covering quad after the fans in stencil mode
+    cover_count: i32,
+    u_color: web_sys::WebGlUniformLocation,
+}
+
+impl FillRenderer {
+    /// Creates a new fill renderer from the polygon features of a topology
+    ///
+    /// Line features are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(
+        ctx: &RenderContext,
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+    ) -> Result<Self, RenderError> {
+        Self::with_mode(ctx, topology, projection, FillMode::Triangulate)
+    }
+
+    /// Creates a fill renderer using `mode`
+    ///
+    /// Stencil mode falls back to triangulation if the context has no stencil
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn with_mode(
+        ctx: &RenderContext,
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+        mode: FillMode,
+    ) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+        let mode = if ctx.has_stencil() {
+            mode
+        } else {
+            FillMode::Triangulate
+        };
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::WORLD_VERTEX, shaders::WORLD_FRAGMENT)
+        } else {
+            (shaders::WORLD_VERTEX_ES100, shaders::WORLD_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::WORLD_ATTRIBUTES,
+        )?;
+
+        let u_color = program.get_uniform_location(gl, "u_color")?;
+
+        let (vertices, cover_count) = match mode {
+            FillMode::Triangulate => (Self::build_vertices(topology, projection), 0),
+            FillMode::Stencil => Self::build_stencil_vertices(topology, projection),
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let vertex_count = (vertices.len() / 2) as i32 - cover_count;
+
+        let vertex_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+
+        #[allow(unsafe_code)]
+        let vertices_bytes = unsafe {
+            std::slice::from_raw_parts(
+                vertices.as_ptr().cast::<u8>(),
+                vertices.len() * std::mem::size_of::<f32>(),
+            )
+        };
+
+        vertex_buffer.upload_data(gl, vertices_bytes);
+
+        let vertices = VertexArray::new(gl, vertex_buffer, 0, &[VertexAttribute::float(0, 2, 0)])?;
+
+        Ok(Self {
+            program,
+            vertices,
+            mode,
+            vertex_count,
+            cover_count,
+            u_color,
+        })
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+    }
+
+    /// Draws the filled polygons
+    pub fn draw(&self, ctx: &RenderContext, color: [f32; 4]) {
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+
+        gl.uniform4f(Some(&self.u_color), color[0], color[1], color[2], color[3]);
+
+        self.vertices.bind(gl);
+        match self.mode {
+            FillMode::Triangulate => {
+                gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count);
+            },
+            FillMode::Stencil => self.draw_stencil(ctx),
+        }
+        self.vertices.unbind(gl);
+    }
+
+    /// Inverts the stencil bit under every fan, then colors and resets the
+    /// pixels left odd
+    ///
+    /// Expects the stencil buffer to be zero, as [`RenderContext::clear`]
+    /// leaves it, and leaves it zero again.
+    fn draw_stencil(&self, ctx: &RenderContext) {
+        let gl = ctx.gl();
+        if self.cover_count == 0 {
+            return;
+        }
+
+        gl.enable(WebGl2RenderingContext::STENCIL_TEST);
+        gl.stencil_mask(1);
+
+        gl.color_mask(false, false, false, false);
+        gl.stencil_func(WebGl2RenderingContext::ALWAYS, 0, 1);
+        gl.stencil_op(
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::INVERT,
+        );
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count);
+
+        gl.color_mask(true, true, true, true);
+        gl.stencil_func(WebGl2RenderingContext::NOTEQUAL, 0, 1);
+        gl.stencil_op(
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::ZERO,
+        );
+        gl.draw_arrays(
+            WebGl2RenderingContext::TRIANGLES,
+            self.vertex_count,
+            self.cover_count,
+        );
+
+        gl.stencil_mask(0xff);
+        gl.disable(WebGl2RenderingContext::STENCIL_TEST);
+    }
+
+    /// Builds a triangle fan for every ring, holes included, followed by a
+    /// quad covering all of them
+    ///
+    /// Returns the vertices and the number of cover vertices, zero when there
+    /// is nothing to fill.
+    #[allow(clippy::cast_possible_truncation)]
+    fn build_stencil_vertices(
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+    ) -> (Vec<f32>, i32) {
+        let mut vertices = Vec::with_capacity(topology.point_count() * 2 * 3 + 12);
+        let mut bounds: Option<[f32; 4]> = None;
+
+        for feature in &topology.features {
+            for rings in feature.geometry.polygons() {
+                for ring in rings {
+                    // Mercator sends the poles to infinity
+                    let points: Vec<[f32; 2]> = ring
+                        .iter()
+                        .map(|&coord| projection.project(coord))
+                        .filter(|point| point.x.is_finite() && point.y.is_finite())
+                        .map(|point| [point.x as f32, point.y as f32])
+                        .collect();
+                    let Some((&first, rest)) = points.split_first() else {
+                        continue;
+                    };
+
+                    for pair in rest.windows(2) {
+                        vertices.extend_from_slice(&first);
+                        vertices.extend_from_slice(&pair[0]);
+                        vertices.extend_from_slice(&pair[1]);
+                    }
+                    for &[x, y] in &points {
+                        bounds =
+                            Some(bounds.map_or([x, y, x, y], |[min_x, min_y, max_x, max_y]| {
+                                [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                            }));
+                    }
+                }
+            }
+        }
+
+        let Some([min_x, min_y, max_x, max_y]) = bounds else {
+            return (vertices, 0);
+        };
+        vertices.extend_from_slice(&[
+            min_x, min_y, max_x, min_y, max_x, max_y, min_x, min_y, max_x, max_y, min_x, max_y,
+        ]);
+        (vertices, 6)
+    }
+
+    fn build_vertices(topology: &WorldTopology, projection: &dyn Projection) -> Vec<f32> {
+        let mut vertices = Vec::with_capacity(topology.point_count() * 2 * 3);
+
+        for feature in &topology.features {
+            for rings in feature.geometry.polygons() {
+                if let Some(outer) = rings.first() {
+                    Self::add_polygon(&mut vertices, outer, projection);
+                }
+            }
+        }
+
+        vertices
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn add_polygon(vertices: &mut Vec<f32>, ring: &[GeoCoord], projection: &dyn Projection) {
+        let projected: Vec<ProjectedCoord> = ring.iter().map(|&p| projection.project(p)).collect();
+
+        for index in ear_clip(&projected) {
+            vertices.push(projected[index].x as f32);
+            vertices.push(projected[index].y as f32);
+        }
+    }
+}
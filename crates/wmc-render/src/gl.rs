@@ -0,0 +1,433 @@
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    AngleInstancedArrays, HtmlImageElement, ImageBitmap, OesVertexArrayObject,
+    WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer,
+    WebGlRenderingContext, WebGlShader, WebGlTexture, WebGlUniformLocation,
+    WebGlVertexArrayObject,
+};
+
+use crate::{program::PendingProgram, stats::RenderStats};
+
+enum Api {
+    WebGl2(WebGl2RenderingContext),
+    WebGl1 {
+        gl: WebGlRenderingContext,
+        vertex_arrays: Option<OesVertexArrayObject>,
+        instancing: Option<AngleInstancedArrays>,
+    },
+}
+
+/// WebGL context of either version
+///
+/// Exposes the calls shared by WebGL2 and WebGL1. Vertex array objects and
+/// instancing map to the `OES_vertex_array_object` and `ANGLE_instanced_arrays`
+/// extensions on WebGL1, where they may be missing; check
+/// [`Gl::supports_vertex_arrays`] and [`Gl::supports_instancing`] first.
+/// WebGL1 constants share their values with the WebGL2 ones, so callers keep
+/// using `WebGl2RenderingContext` constants.
+///
+/// Draw calls and buffer uploads are counted into [`RenderStats`], read with
+/// [`Gl::take_stats`].
+pub struct Gl {
+    api: Api,
+    stats: Cell<RenderStats>,
+    parallel_compile: bool,
+    /// Programs started by [`crate::ShaderProgram::precompile`] that no
+    /// constructor took over yet
+    precompiled: RefCell<Vec<PendingProgram>>,
+}
+
+/// Defines methods calling the same named method on either context
+///
+/// Doc comments on an entry are appended to the generated one, e.g. an
+/// `# Errors` section for methods returning a `Result`.
+macro_rules! delegate {
+    ($($(#[$attr:meta])* fn $name:ident(&self $(, $arg:ident: $ty:ty)*) $(-> $ret:ty)?;)*) => {
+        $(
+            #[doc = concat!("Calls `", stringify!($name), "` on the underlying context")]
+            $(#[$attr])*
+            pub fn $name(&self $(, $arg: $ty)*) $(-> $ret)? {
+                match &self.api {
+                    Api::WebGl2(gl) => gl.$name($($arg),*),
+                    Api::WebGl1 { gl, .. } => gl.$name($($arg),*),
+                }
+            }
+        )*
+    };
+}
+
+#[allow(
+    clippy::many_single_char_names,
+    clippy::must_use_candidate,
+    clippy::too_many_arguments
+)]
+impl Gl {
+    /// Wraps a WebGL2 context
+    #[must_use]
+    pub fn webgl2(gl: WebGl2RenderingContext) -> Self {
+        Self::with_api(Api::WebGl2(gl))
+    }
+
+    /// Wraps a WebGL1 context and enables the extensions it can use
+    #[must_use]
+    pub fn webgl1(gl: WebGlRenderingContext) -> Self {
+        let vertex_arrays = extension(&gl, "OES_vertex_array_object");
+        let instancing = extension(&gl, "ANGLE_instanced_arrays");
+
+        Self::with_api(Api::WebGl1 {
+            gl,
+            vertex_arrays,
+            instancing,
+        })
+    }
+
+    fn with_api(api: Api) -> Self {
+        let mut gl = Self {
+            api,
+            stats: Cell::new(RenderStats::default()),
+            parallel_compile: false,
+            precompiled: RefCell::default(),
+        };
+        gl.parallel_compile = gl.enable_extension("KHR_parallel_shader_compile");
+        gl
+    }
+
+    /// Returns true for a WebGL2 context
+    #[must_use]
+    pub const fn is_webgl2(&self) -> bool {
+        matches!(self.api, Api::WebGl2(_))
+    }
+
+    /// Returns the WebGL2 context, if this is one
+    #[must_use]
+    pub const fn as_webgl2(&self) -> Option<&WebGl2RenderingContext> {
+        match &self.api {
+            Api::WebGl2(gl) => Some(gl),
+            Api::WebGl1 { .. } => None,
+        }
+    }
+
+    /// Returns true if vertex array objects are available
+    #[must_use]
+    pub const fn supports_vertex_arrays(&self) -> bool {
+        match &self.api {
+            Api::WebGl2(_) => true,
+            Api::WebGl1 { vertex_arrays, .. } => vertex_arrays.is_some(),
+        }
+    }
+
+    /// Returns true if instanced drawing is available
+    #[must_use]
+    pub const fn supports_instancing(&self) -> bool {
+        match &self.api {
+            Api::WebGl2(_) => true,
+            Api::WebGl1 { instancing, .. } => instancing.is_some(),
+        }
+    }
+
+    /// Returns true if `KHR_parallel_shader_compile` is available, so
+    /// programs can be polled for completion without blocking
+    #[must_use]
+    pub const fn supports_parallel_compile(&self) -> bool {
+        self.parallel_compile
+    }
+
+    pub(crate) const fn precompiled(&self) -> &RefCell<Vec<PendingProgram>> {
+        &self.precompiled
+    }
+
+    /// Enables the extension `name`, returning false if the browser doesn't
+    /// expose it
+    pub fn enable_extension(&self, name: &str) -> bool {
+        let extension = match &self.api {
+            Api::WebGl2(gl) => gl.get_extension(name),
+            Api::WebGl1 { gl, .. } => gl.get_extension(name),
+        };
+        matches!(extension, Ok(Some(_)))
+    }
+
+    /// Creates a vertex array object
+    ///
+    /// Returns `None` if creation fails or vertex arrays are unsupported.
+    #[must_use]
+    pub fn create_vertex_array(&self) -> Option<WebGlVertexArrayObject> {
+        match &self.api {
+            Api::WebGl2(gl) => gl.create_vertex_array(),
+            Api::WebGl1 { vertex_arrays, .. } => vertex_arrays
+                .as_ref()
+                .and_then(OesVertexArrayObject::create_vertex_array_oes),
+        }
+    }
+
+    /// Binds a vertex array object; does nothing if vertex arrays are
+    /// unsupported
+    pub fn bind_vertex_array(&self, vao: Option<&WebGlVertexArrayObject>) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.bind_vertex_array(vao),
+            Api::WebGl1 { vertex_arrays, .. } => {
+                if let Some(ext) = vertex_arrays {
+                    ext.bind_vertex_array_oes(vao);
+                }
+            },
+        }
+    }
+
+    /// Deletes a vertex array object; does nothing if vertex arrays are
+    /// unsupported
+    pub fn delete_vertex_array(&self, vao: Option<&WebGlVertexArrayObject>) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.delete_vertex_array(vao),
+            Api::WebGl1 { vertex_arrays, .. } => {
+                if let Some(ext) = vertex_arrays {
+                    ext.delete_vertex_array_oes(vao);
+                }
+            },
+        }
+    }
+
+    /// Sets the instance divisor of a vertex attribute; does nothing if
+    /// instancing is unsupported
+    pub fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.vertex_attrib_divisor(index, divisor),
+            Api::WebGl1 { instancing, .. } => {
+                if let Some(ext) = instancing {
+                    ext.vertex_attrib_divisor_angle(index, divisor);
+                }
+            },
+        }
+    }
+
+    /// Draws `instance_count` instances of a vertex range; does nothing if
+    /// instancing is unsupported
+    pub fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instance_count: i32) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.draw_arrays_instanced(mode, first, count, instance_count),
+            Api::WebGl1 { instancing, .. } => match instancing {
+                Some(ext) => ext.draw_arrays_instanced_angle(mode, first, count, instance_count),
+                None => return,
+            },
+        }
+        self.count(|stats| stats.record_draw(count.saturating_mul(instance_count)));
+    }
+
+    /// Calls `draw_arrays` on the underlying context
+    pub fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.draw_arrays(mode, first, count),
+            Api::WebGl1 { gl, .. } => gl.draw_arrays(mode, first, count),
+        }
+        self.count(|stats| stats.record_draw(count));
+    }
+
+    /// Calls `buffer_data_with_u8_array` on the underlying context
+    pub fn buffer_data_with_u8_array(&self, target: u32, data: &[u8], usage: u32) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.buffer_data_with_u8_array(target, data, usage),
+            Api::WebGl1 { gl, .. } => gl.buffer_data_with_u8_array(target, data, usage),
+        }
+        self.count(|stats| stats.record_upload(data.len()));
+    }
+
+    /// Calls `buffer_sub_data_with_i32_and_u8_array` on the underlying context
+    pub fn buffer_sub_data_with_i32_and_u8_array(&self, target: u32, offset: i32, data: &[u8]) {
+        match &self.api {
+            Api::WebGl2(gl) => gl.buffer_sub_data_with_i32_and_u8_array(target, offset, data),
+            Api::WebGl1 { gl, .. } => {
+                gl.buffer_sub_data_with_i32_and_u8_array(target, offset, data);
+            },
+        }
+        self.count(|stats| stats.record_upload(data.len()));
+    }
+
+    /// Calls `tex_image_2d_with_u32_and_u32_and_html_image_element` on the
+    /// underlying context
+    ///
+    /// # Errors
+    ///
+    /// Returns the exception the context throws
+    pub fn tex_image_2d_with_u32_and_u32_and_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        kind: u32,
+        source: &HtmlImageElement,
+    ) -> Result<(), JsValue> {
+        match &self.api {
+            Api::WebGl2(gl) => gl.tex_image_2d_with_u32_and_u32_and_html_image_element(
+                target,
+                level,
+                internal_format,
+                format,
+                kind,
+                source,
+            ),
+            Api::WebGl1 { gl, .. } => gl.tex_image_2d_with_u32_and_u32_and_image(
+                target,
+                level,
+                internal_format,
+                format,
+                kind,
+                source,
+            ),
+        }
+    }
+
+    /// Returns the work counted since the last call and restarts counting
+    ///
+    /// [`RenderStats::frame_ms`] is always zero here.
+    pub fn take_stats(&self) -> RenderStats {
+        self.stats.take()
+    }
+
+    fn count(&self, record: impl FnOnce(&mut RenderStats)) {
+        let mut stats = self.stats.get();
+        record(&mut stats);
+        self.stats.set(stats);
+    }
+
+    delegate! {
+        fn create_buffer(&self) -> Option<WebGlBuffer>;
+        fn bind_buffer(&self, target: u32, buffer: Option<&WebGlBuffer>);
+        fn delete_buffer(&self, buffer: Option<&WebGlBuffer>);
+        fn create_shader(&self, shader_type: u32) -> Option<WebGlShader>;
+        fn shader_source(&self, shader: &WebGlShader, source: &str);
+        fn compile_shader(&self, shader: &WebGlShader);
+        fn get_shader_parameter(&self, shader: &WebGlShader, pname: u32) -> JsValue;
+        fn get_shader_info_log(&self, shader: &WebGlShader) -> Option<String>;
+        fn delete_shader(&self, shader: Option<&WebGlShader>);
+        fn create_program(&self) -> Option<WebGlProgram>;
+        fn delete_program(&self, program: Option<&WebGlProgram>);
+        fn attach_shader(&self, program: &WebGlProgram, shader: &WebGlShader);
+        fn bind_attrib_location(&self, program: &WebGlProgram, index: u32, name: &str);
+        fn link_program(&self, program: &WebGlProgram);
+        fn get_program_parameter(&self, program: &WebGlProgram, pname: u32) -> JsValue;
+        fn get_program_info_log(&self, program: &WebGlProgram) -> Option<String>;
+        fn use_program(&self, program: Option<&WebGlProgram>);
+        fn get_uniform_location(
+            &self,
+            program: &WebGlProgram,
+            name: &str
+        ) -> Option<WebGlUniformLocation>;
+        fn get_attrib_location(&self, program: &WebGlProgram, name: &str) -> i32;
+        fn vertex_attrib_pointer_with_i32(
+            &self,
+            index: u32,
+            size: i32,
+            kind: u32,
+            normalized: bool,
+            stride: i32,
+            offset: i32
+        );
+        fn enable_vertex_attrib_array(&self, index: u32);
+        fn disable_vertex_attrib_array(&self, index: u32);
+        fn uniform1i(&self, location: Option<&WebGlUniformLocation>, x: i32);
+        fn uniform1f(&self, location: Option<&WebGlUniformLocation>, x: f32);
+        fn uniform2f(&self, location: Option<&WebGlUniformLocation>, x: f32, y: f32);
+        fn uniform3f(&self, location: Option<&WebGlUniformLocation>, x: f32, y: f32, z: f32);
+        fn uniform4f(
+            &self,
+            location: Option<&WebGlUniformLocation>,
+            x: f32,
+            y: f32,
+            z: f32,
+            w: f32
+        );
+        fn uniform4fv_with_f32_array(&self, location: Option<&WebGlUniformLocation>, data: &[f32]);
+        fn uniform_matrix3fv_with_f32_array(
+            &self,
+            location: Option<&WebGlUniformLocation>,
+            transpose: bool,
+            data: &[f32]
+        );
+        fn uniform_matrix4fv_with_f32_array(
+            &self,
+            location: Option<&WebGlUniformLocation>,
+            transpose: bool,
+            data: &[f32]
+        );
+        fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+        fn clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+        fn clear(&self, mask: u32);
+        fn enable(&self, cap: u32);
+        fn disable(&self, cap: u32);
+        fn is_enabled(&self, cap: u32) -> bool;
+        fn scissor(&self, x: i32, y: i32, width: i32, height: i32);
+        fn blend_func(&self, sfactor: u32, dfactor: u32);
+        fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32);
+        fn create_texture(&self) -> Option<WebGlTexture>;
+        fn delete_texture(&self, texture: Option<&WebGlTexture>);
+        fn bind_texture(&self, target: u32, texture: Option<&WebGlTexture>);
+        fn active_texture(&self, unit: u32);
+        fn tex_parameteri(&self, target: u32, pname: u32, param: i32);
+        ///
+        /// # Errors
+        ///
+        /// Returns the exception the context throws
+        fn tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            &self,
+            target: u32,
+            level: i32,
+            internal_format: i32,
+            width: i32,
+            height: i32,
+            border: i32,
+            format: u32,
+            kind: u32,
+            pixels: Option<&[u8]>
+        ) -> Result<(), JsValue>;
+        ///
+        /// # Errors
+        ///
+        /// Returns the exception the context throws
+        fn tex_image_2d_with_u32_and_u32_and_image_bitmap(
+            &self,
+            target: u32,
+            level: i32,
+            internal_format: i32,
+            format: u32,
+            kind: u32,
+            source: &ImageBitmap
+        ) -> Result<(), JsValue>;
+        fn pixel_storei(&self, pname: u32, param: i32);
+        fn generate_mipmap(&self, target: u32);
+        fn create_framebuffer(&self) -> Option<WebGlFramebuffer>;
+        fn bind_framebuffer(&self, target: u32, framebuffer: Option<&WebGlFramebuffer>);
+        fn delete_framebuffer(&self, framebuffer: Option<&WebGlFramebuffer>);
+        fn framebuffer_texture_2d(
+            &self,
+            target: u32,
+            attachment: u32,
+            textarget: u32,
+            texture: Option<&WebGlTexture>,
+            level: i32
+        );
+        fn check_framebuffer_status(&self, target: u32) -> u32;
+        fn create_renderbuffer(&self) -> Option<WebGlRenderbuffer>;
+        fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&WebGlRenderbuffer>);
+        fn delete_renderbuffer(&self, renderbuffer: Option<&WebGlRenderbuffer>);
+        fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32);
+        fn framebuffer_renderbuffer(
+            &self,
+            target: u32,
+            attachment: u32,
+            renderbuffer_target: u32,
+            renderbuffer: Option<&WebGlRenderbuffer>
+        );
+        fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
+        fn stencil_func(&self, func: u32, reference: i32, mask: u32);
+        fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32);
+        fn stencil_mask(&self, mask: u32);
+    }
+}
+
+fn extension<T: JsCast>(gl: &WebGlRenderingContext, name: &str) -> Option<T> {
+    gl.get_extension(name)
+        .ok()
+        .flatten()
+        .map(JsCast::unchecked_into)
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+use wmc_core::sdf::distance_field;
+
+use crate::{error::RenderError, gl::Gl, log, texture::GpuTexture};
+
+/// Font size glyphs are rasterized at, in pixels
+pub const GLYPH_SIZE: f32 = 24.0;
+
+/// Distance in atlas pixels the signed distance field spans from the edge
+pub const SDF_RADIUS: f32 = 8.0;
+
+/// Share of [`SDF_RADIUS`] the field reaches into a glyph; the edge sits at
+/// `1 - SDF_CUTOFF` in the normalized texture values
+pub const SDF_CUTOFF: f32 = 0.25;
+
+/// Empty pixels around every glyph, so the field can fall off beyond the
+/// edge
+const BUFFER: u32 = 3;
+
+/// Atlas width and height in pixels
+const ATLAS_SIZE: u32 = 1024;
+
+/// Side of the fully inside patch reserved for solid boxes, in pixels
+const SOLID_SIZE: u32 = 4;
+
+/// Scratch canvas width and height in pixels, enough for any glyph at
+/// [`GLYPH_SIZE`]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const CANVAS_SIZE: u32 = GLYPH_SIZE as u32 * 3 + 2 * BUFFER;
+
+/// Placement of a glyph in the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    /// Atlas rectangle as `[x, y, width, height]` in pixels, buffer
+    /// included; empty for whitespace
+    pub rect: [u32; 4],
+    /// Offset of the rectangle's top left corner from the pen position on
+    /// the baseline, in atlas pixels
+    pub offset: [f32; 2],
+    /// Horizontal pen advance in atlas pixels
+    pub advance: f32,
+}
+
+/// Row of glyphs of similar height
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Start of the free space
+    x: u32,
+}
+
+/// Signed distance field atlas of glyphs rasterized on demand
+///
+/// Glyphs are drawn with the browser's text rendering on an
+/// `OffscreenCanvas` at [`GLYPH_SIZE`], converted to distance fields and
+/// packed into shelves of a single texture, which works in workers too. The
+/// field keeps edges sharp at any scale and leaves room for halos. Glyphs
+/// that no longer fit are skipped.
+pub struct GlyphAtlas {
+    context: OffscreenCanvasRenderingContext2d,
+    texture: GpuTexture,
+    /// RGBA pixels mirroring the texture
+    pixels: Vec<u8>,
+    glyphs: HashMap<char, Option<Glyph>>,
+    shelves: Vec<Shelf>,
+    /// Center of the solid patch in atlas pixels
+    solid: [u32; 2],
+    dirty: bool,
+}
+
+impl GlyphAtlas {
+    /// Creates an empty atlas for the CSS font family `font`, e.g.
+    /// `"sans-serif"`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::GlyphRasterizationFailed`] if the scratch canvas
+    /// can't be created and [`RenderError`] if texture creation fails
+    pub fn new(gl: &Gl, font: &str) -> Result<Self, RenderError> {
+        let canvas = OffscreenCanvas::new(CANVAS_SIZE, CANVAS_SIZE).map_err(raster_error)?;
+        let context = canvas
+            .get_context("2d")
+            .map_err(raster_error)?
+            .ok_or_else(|| RenderError::GlyphRasterizationFailed {
+                reason: "2D canvas context unavailable".to_string(),
+            })?
+            .unchecked_into::<OffscreenCanvasRenderingContext2d>();
+        context.set_font(&format!("{GLYPH_SIZE}px {font}"));
+        context.set_text_baseline("alphabetic");
+        context.set_text_align("left");
+        context.set_fill_style_str("black");
+
+        let mut atlas = Self {
+            context,
+            texture: GpuTexture::new(gl)?,
+            pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize],
+            glyphs: HashMap::new(),
+            shelves: Vec::new(),
+            solid: [0; 2],
+            dirty: false,
+        };
+
+        // The empty atlas always has room for the patch
+        if let Some((x, y)) = atlas.pack(SOLID_SIZE, SOLID_SIZE) {
+            for row in y..y + SOLID_SIZE {
+                let start = ((row * ATLAS_SIZE + x) * 4) as usize;
+                atlas.pixels[start..start + (SOLID_SIZE * 4) as usize].fill(u8::MAX);
+            }
+            atlas.solid = [x + SOLID_SIZE / 2, y + SOLID_SIZE / 2];
+        }
+        atlas
+            .texture
+            .upload_pixels(gl, ATLAS_SIZE, ATLAS_SIZE, Some(&atlas.pixels))?;
+
+        Ok(atlas)
+    }
+
+    /// Frees the atlas texture; the atlas must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.texture.delete(gl);
+    }
+
+    /// Returns the atlas texture
+    #[must_use]
+    pub const fn texture(&self) -> &GpuTexture {
+        &self.texture
+    }
+
+    /// Returns the atlas width and height in pixels
+    #[must_use]
+    pub const fn size(&self) -> u32 {
+        ATLAS_SIZE
+    }
+
+    /// Returns the center of a patch that lies fully inside every edge, in
+    /// atlas pixels
+    ///
+    /// Quads sampling only this point draw solid boxes with the text shader.
+    #[must_use]
+    pub const fn solid(&self) -> [u32; 2] {
+        self.solid
+    }
+
+    /// Returns the glyph for `ch`, rasterizing it on first use
+    ///
+    /// Returns `None` if the browser can't draw it or the atlas is full.
+    pub fn glyph(&mut self, ch: char) -> Option<Glyph> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return *glyph;
+        }
+
+        let glyph = self.rasterize(ch).unwrap_or_else(|e| {
+            log::warn(
+                "glyph",
+                "Glyph skipped",
+                &[
+                    ("char", ch.to_string().into()),
+                    ("reason", e.to_string().into()),
+                ],
+            );
+            None
+        });
+        self.glyphs.insert(ch, glyph);
+        glyph
+    }
+
+    /// Uploads glyphs added since the last call
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if the upload fails
+    pub fn flush(&mut self, gl: &Gl) -> Result<(), RenderError> {
+        if std::mem::take(&mut self.dirty) {
+            self.texture
+                .upload_pixels(gl, ATLAS_SIZE, ATLAS_SIZE, Some(&self.pixels))?;
+        }
+        Ok(())
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        clippy::cast_possible_wrap
+    )]
+    fn rasterize(&mut self, ch: char) -> Result<Option<Glyph>, RenderError> {
+        let mut utf8 = [0; 4];
+        let text = ch.encode_utf8(&mut utf8);
+        let metrics = self.context.measure_text(text).map_err(raster_error)?;
+
+        let advance = metrics.width() as f32;
+        let left = metrics.actual_bounding_box_left();
+        let ascent = metrics.actual_bounding_box_ascent();
+        let glyph_width = (left + metrics.actual_bounding_box_right()).ceil().max(0.0) as u32;
+        let glyph_height = (ascent + metrics.actual_bounding_box_descent())
+            .ceil()
+            .max(0.0) as u32;
+        if glyph_width == 0 || glyph_height == 0 {
+            return Ok(Some(Glyph {
+                rect: [0; 4],
+                offset: [0.0; 2],
+                advance,
+            }));
+        }
+
+        let width = (glyph_width + 2 * BUFFER).min(CANVAS_SIZE);
+        let height = (glyph_height + 2 * BUFFER).min(CANVAS_SIZE);
+        let Some((x, y)) = self.pack(width, height) else {
+            return Ok(None);
+        };
+
+        let (width_f, height_f) = (f64::from(width), f64::from(height));
+        let buffer = f64::from(BUFFER);
+        self.context.clear_rect(0.0, 0.0, width_f, height_f);
+        self.context
+            .fill_text(text, buffer + left, buffer + ascent)
+            .map_err(raster_error)?;
+        let image = self
+            .context
+            .get_image_data(0, 0, width as i32, height as i32)
+            .map_err(raster_error)?;
+        let alpha: Vec<u8> = image.data().iter().skip(3).step_by(4).copied().collect();
+        let field = distance_field(
+            &alpha,
+            width as usize,
+            height as usize,
+            f64::from(SDF_RADIUS),
+            f64::from(SDF_CUTOFF),
+        );
+
+        for (row, values) in field.chunks_exact(width as usize).enumerate() {
+            let start = ((y as usize + row) * ATLAS_SIZE as usize + x as usize) * 4;
+            for (pixel, &value) in self.pixels[start..start + values.len() * 4]
+                .as_chunks_mut::<4>()
+                .0
+                .iter_mut()
+                .zip(values)
+            {
+                pixel.fill(value);
+            }
+        }
+        self.dirty = true;
+
+        Ok(Some(Glyph {
+            rect: [x, y, width, height],
+            offset: [
+                -(left as f32) - BUFFER as f32,
+                -(ascent as f32) - BUFFER as f32,
+            ],
+            advance,
+        }))
+    }
+
+    /// Reserves a `width` × `height` rectangle on the lowest shelf it fits,
+    /// opening a new shelf if none does
+    fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let fitting = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| height <= shelf.height && shelf.x + width <= ATLAS_SIZE)
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = fitting {
+            let x = shelf.x;
+            shelf.x += width;
+            return Some((x, shelf.y));
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if y + height > ATLAS_SIZE || width > ATLAS_SIZE {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+        Some((0, y))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn raster_error(error: JsValue) -> RenderError {
+    RenderError::GlyphRasterizationFailed {
+        reason: error.as_string().unwrap_or_else(|| format!("{error:?}")),
+    }
+}
@@ -0,0 +1,189 @@
+use std::{cell::RefCell, collections::VecDeque};
+
+use web_sys::{WebGl2RenderingContext, WebGlQuery};
+
+use crate::gl::Gl;
+
+/// `TIME_ELAPSED_EXT` of `EXT_disjoint_timer_query_webgl2`
+const TIME_ELAPSED: u32 = 0x88BF;
+/// `GPU_DISJOINT_EXT` of `EXT_disjoint_timer_query_webgl2`
+const GPU_DISJOINT: u32 = 0x8FBB;
+/// Frames whose results may be outstanding at once; frames beyond this are
+/// not measured
+const MAX_PENDING: usize = 4;
+
+/// GPU time one pass of a frame took
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    /// Pass name given to [`GpuTimer::begin`]
+    pub name: String,
+    /// GPU time in milliseconds
+    pub ms: f64,
+}
+
+type Passes = Vec<(String, WebGlQuery)>;
+
+#[derive(Default)]
+struct Queries {
+    /// Whether the frame being drawn is measured
+    measuring: bool,
+    /// Whether the last pass of `current` is still being timed
+    active: bool,
+    /// Passes of the frame being drawn
+    current: Passes,
+    /// Drawn frames waiting for their results, oldest first
+    pending: VecDeque<Passes>,
+    /// Query objects ready for reuse
+    free: Vec<WebGlQuery>,
+    /// Timings of the newest frame whose results came in
+    last: Vec<PassTiming>,
+}
+
+/// Per-pass GPU timing through `EXT_disjoint_timer_query_webgl2`
+///
+/// Only one pass is timed at a time, so passes follow each other rather than
+/// nest. Results arrive a few frames after the frame was drawn; frames the
+/// GPU reports as disjoint, e.g. after a power state change, are discarded.
+pub struct GpuTimer {
+    queries: RefCell<Queries>,
+}
+
+impl GpuTimer {
+    /// Enables the timer query extension, returning `None` on WebGL1 or if
+    /// the browser doesn't expose it
+    #[must_use]
+    pub fn new(gl: &Gl) -> Option<Self> {
+        gl.as_webgl2()?
+            .get_extension("EXT_disjoint_timer_query_webgl2")
+            .ok()
+            .flatten()?;
+
+        Some(Self {
+            queries: RefCell::default(),
+        })
+    }
+
+    /// Collects the results that became available and starts measuring a
+    /// frame
+    pub fn begin_frame(&self, gl: &Gl) {
+        let Some(gl) = gl.as_webgl2() else {
+            return;
+        };
+        let mut guard = self.queries.borrow_mut();
+        let queries = &mut *guard;
+
+        let disjoint = gl
+            .get_parameter(GPU_DISJOINT)
+            .ok()
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        if disjoint {
+            queries
+                .free
+                .extend(queries.pending.drain(..).flatten().map(|(_, query)| query));
+        }
+
+        while let Some(passes) = queries.pending.front() {
+            // Queries finish in order, so the last pass decides
+            let available = passes.last().is_none_or(|(_, query)| {
+                gl.get_query_parameter(query, WebGl2RenderingContext::QUERY_RESULT_AVAILABLE)
+                    .as_bool()
+                    .unwrap_or(false)
+            });
+            if !available {
+                break;
+            }
+
+            let Some(passes) = queries.pending.pop_front() else {
+                break;
+            };
+            let mut timings = Vec::with_capacity(passes.len());
+            for (name, query) in passes {
+                let ns = gl
+                    .get_query_parameter(&query, WebGl2RenderingContext::QUERY_RESULT)
+                    .as_f64()
+                    .unwrap_or(0.0);
+                timings.push(PassTiming {
+                    name,
+                    ms: ns / 1_000_000.0,
+                });
+                queries.free.push(query);
+            }
+            queries.last = timings;
+        }
+
+        queries.measuring = queries.pending.len() < MAX_PENDING;
+    }
+
+    /// Starts timing a pass, ending the previous one
+    pub fn begin(&self, gl: &Gl, name: &str) {
+        self.end(gl);
+
+        let Some(gl) = gl.as_webgl2() else {
+            return;
+        };
+        let mut queries = self.queries.borrow_mut();
+        if !queries.measuring {
+            return;
+        }
+
+        let Some(query) = queries.free.pop().or_else(|| gl.create_query()) else {
+            return;
+        };
+        gl.begin_query(TIME_ELAPSED, &query);
+        queries.current.push((name.to_string(), query));
+        queries.active = true;
+    }
+
+    /// Ends the pass being timed, if any
+    pub fn end(&self, gl: &Gl) {
+        let mut queries = self.queries.borrow_mut();
+        if !queries.active {
+            return;
+        }
+
+        if let Some(gl) = gl.as_webgl2() {
+            gl.end_query(TIME_ELAPSED);
+        }
+        queries.active = false;
+    }
+
+    /// Ends the frame's last pass and queues the frame for its results
+    pub fn end_frame(&self, gl: &Gl) {
+        self.end(gl);
+
+        let mut queries = self.queries.borrow_mut();
+        if !queries.current.is_empty() {
+            let passes = std::mem::take(&mut queries.current);
+            queries.pending.push_back(passes);
+        }
+    }
+
+    /// Returns the pass timings of the newest measured frame, in drawing
+    /// order
+    #[must_use]
+    pub fn timings(&self) -> Vec<PassTiming> {
+        self.queries.borrow().last.clone()
+    }
+
+    /// Deletes every query object
+    pub fn delete(&self, gl: &Gl) {
+        self.end(gl);
+
+        let Some(gl) = gl.as_webgl2() else {
+            return;
+        };
+        let mut guard = self.queries.borrow_mut();
+        let queries = &mut *guard;
+        for (_, query) in queries
+            .current
+            .drain(..)
+            .chain(queries.pending.drain(..).flatten())
+        {
+            gl.delete_query(Some(&query));
+        }
+        for query in queries.free.drain(..) {
+            gl.delete_query(Some(&query));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use web_sys::WebGlUniformLocation;
+
+use crate::{
+    error::RenderError,
+    gl::Gl,
+    post_process::{PostEffect, PostFrame, effect_program},
+    program::ShaderProgram,
+    shaders,
+};
+
+/// Animated film grain post effect
+///
+/// Disabled while the intensity is zero. The noise follows the frame time
+/// and [`PostFrame::seed`], so it repeats for a fixed time and seed.
+pub struct GrainEffect {
+    program: ShaderProgram,
+    u_intensity: WebGlUniformLocation,
+    u_seed: WebGlUniformLocation,
+    intensity: f32,
+}
+
+impl GrainEffect {
+    /// Creates a grain effect with the given noise amplitude
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation fails
+    pub fn new(gl: &Gl, intensity: f32) -> Result<Self, RenderError> {
+        let program = effect_program(gl, shaders::GRAIN_FRAGMENT, shaders::GRAIN_FRAGMENT_ES100)?;
+
+        let u_intensity = program.get_uniform_location(gl, "u_intensity")?;
+        let u_seed = program.get_uniform_location(gl, "u_seed")?;
+
+        Ok(Self {
+            program,
+            u_intensity,
+            u_seed,
+            intensity,
+        })
+    }
+
+    /// Returns the noise amplitude
+    #[must_use]
+    pub const fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Sets the noise amplitude; zero disables the effect
+    pub const fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity;
+    }
+}
+
+impl PostEffect for GrainEffect {
+    fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    fn apply_uniforms(&self, gl: &Gl, frame: &PostFrame) {
+        gl.uniform1f(Some(&self.u_intensity), self.intensity);
+        gl.uniform1f(Some(&self.u_seed), frame.seed);
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.intensity > 0.0
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+}
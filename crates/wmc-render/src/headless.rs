@@ -0,0 +1,244 @@
+use std::cell::{Ref, RefCell};
+
+use wmc_core::marker_buffer::MarkerBuffer;
+
+use crate::{
+    backend::{BackendKind, RenderBackend, ShaderSources},
+    error::RenderError,
+    line_mesh::{LINE_VERTEX_FLOATS, extruded_position},
+    marker_renderer::marker_pulse,
+};
+
+/// Backend rasterizing on the CPU into an RGBA8 pixel buffer, so render
+/// logic can be exercised natively in `cargo test` without a browser
+///
+/// Shader programs aren't run: [`HeadlessContext::draw_lines`] and
+/// [`HeadlessContext::draw_markers`] stand in for the line and marker
+/// renderers, filling the same meshes and instances with flat colors,
+/// without anti-aliasing, dashes or glow. They place geometry through
+/// [`extruded_position`] and [`marker_pulse`], the math the shaders share. Pixels are stored row by row from
+/// the top left, like `readPixels` flipped, and colors are blended over
+/// what is drawn already.
+///
+/// ```
+/// use wmc_render::{HeadlessContext, RenderBackend, line_mesh::extrude_polyline};
+///
+/// let ctx = HeadlessContext::new(16, 16);
+/// ctx.clear([0.0, 0.0, 0.0, 1.0]);
+///
+/// let mut vertices = Vec::new();
+/// extrude_polyline(&mut vertices, &[[0.0, 8.0], [16.0, 8.0]]);
+/// ctx.draw_lines(&vertices, [1.0, 0.0, 0.0, 1.0], 4.0);
+///
+/// assert_eq!(ctx.pixel(5, 8), [255, 0, 0, 255]);
+/// assert_eq!(ctx.pixel(5, 2), [0, 0, 0, 255]);
+/// ```
+#[derive(Debug)]
+pub struct HeadlessContext {
+    width: u32,
+    height: u32,
+    pixels: RefCell<Vec<u8>>,
+}
+
+/// Vertex data held by a [`HeadlessContext`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeadlessBuffer {
+    /// Uploaded bytes
+    pub data: Vec<u8>,
+}
+
+/// Program of a [`HeadlessContext`], which keeps the attribute names only
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeadlessProgram {
+    /// Vertex attribute names in location order
+    pub attributes: Vec<String>,
+}
+
+impl HeadlessContext {
+    /// Creates a transparent `width` x `height` pixel buffer
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: RefCell::new(vec![0; pixel_bytes(width, height)]),
+        }
+    }
+
+    /// Returns the pixels as RGBA8 rows from the top left
+    #[must_use]
+    pub fn pixels(&self) -> Ref<'_, [u8]> {
+        Ref::map(self.pixels.borrow(), Vec::as_slice)
+    }
+
+    /// Returns the RGBA8 color of a pixel, transparent outside the buffer
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            return [0; 4];
+        }
+
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let pixels = self.pixels.borrow();
+        [
+            pixels[start],
+            pixels[start + 1],
+            pixels[start + 2],
+            pixels[start + 3],
+        ]
+    }
+
+    /// Fills line mesh triangles of [`LINE_VERTEX_FLOATS`] floats per vertex,
+    /// as built by [`crate::line_mesh::extrude_polyline`], `line_width`
+    /// device pixels wide
+    pub fn draw_lines(&self, vertices: &[f32], color: [f32; 4], line_width: f32) {
+        let vertices = vertices.as_chunks::<LINE_VERTEX_FLOATS>().0;
+        for triangle in vertices.as_chunks::<3>().0 {
+            let corners = triangle
+                .each_ref()
+                .map(|vertex| extruded_position(vertex, line_width));
+            self.fill_triangle(&corners, color);
+        }
+    }
+
+    /// Fills a disc per marker instance, `size` device pixels in radius
+    /// scaled by the pulse at `time` seconds, as the marker shader does
+    pub fn draw_markers(&self, markers: &MarkerBuffer, size: f32, time: f32) {
+        for instance in (0..markers.len()).filter_map(|index| markers.get(index)) {
+            let radius = size * marker_pulse(time, instance.phase);
+            let color = [instance.r, instance.g, instance.b, instance.a];
+            self.fill_where(
+                [
+                    instance.x - radius,
+                    instance.y - radius,
+                    instance.x + radius,
+                    instance.y + radius,
+                ],
+                color,
+                |x, y| (x - instance.x).hypot(y - instance.y) <= radius,
+            );
+        }
+    }
+
+    fn fill_triangle(&self, [a, b, c]: &[[f32; 2]; 3], color: [f32; 4]) {
+        let area = edge(*a, *b, *c);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        let bounds = [
+            a[0].min(b[0]).min(c[0]),
+            a[1].min(b[1]).min(c[1]),
+            a[0].max(b[0]).max(c[0]),
+            a[1].max(b[1]).max(c[1]),
+        ];
+        self.fill_where(bounds, color, |x, y| {
+            let point = [x, y];
+            // Inside when all edges agree with the winding of the triangle
+            [
+                edge(*a, *b, point),
+                edge(*b, *c, point),
+                edge(*c, *a, point),
+            ]
+            .iter()
+            .all(|&side| side * area >= 0.0)
+        });
+    }
+
+    /// Blends `color` over the pixels within `[min_x, min_y, max_x, max_y]`
+    /// whose centers pass `inside`
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn fill_where(
+        &self,
+        [min_x, min_y, max_x, max_y]: [f32; 4],
+        color: [f32; 4],
+        inside: impl Fn(f32, f32) -> bool,
+    ) {
+        let clamp_x = |x: f32| x.clamp(0.0, self.width as f32) as u32;
+        let clamp_y = |y: f32| y.clamp(0.0, self.height as f32) as u32;
+        let mut pixels = self.pixels.borrow_mut();
+
+        for y in clamp_y(min_y.floor())..clamp_y(max_y.ceil()) {
+            for x in clamp_x(min_x.floor())..clamp_x(max_x.ceil()) {
+                if inside(x as f32 + 0.5, y as f32 + 0.5) {
+                    let start = (y as usize * self.width as usize + x as usize) * 4;
+                    blend(&mut pixels[start..start + 4], color);
+                }
+            }
+        }
+    }
+}
+
+impl RenderBackend for HeadlessContext {
+    type Buffer = HeadlessBuffer;
+    type Program = HeadlessProgram;
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::Headless
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Resizes and clears the buffer; CSS pixels are device pixels here
+    fn resize(&mut self, css_width: u32, css_height: u32) {
+        self.width = css_width;
+        self.height = css_height;
+        *self.pixels.get_mut() = vec![0; pixel_bytes(css_width, css_height)];
+    }
+
+    fn clear(&self, color: [f32; 4]) {
+        let color = color.map(to_byte);
+        for pixel in self.pixels.borrow_mut().as_chunks_mut::<4>().0 {
+            *pixel = color;
+        }
+    }
+
+    fn create_vertex_buffer(&self, data: &[u8]) -> Result<Self::Buffer, RenderError> {
+        Ok(HeadlessBuffer {
+            data: data.to_vec(),
+        })
+    }
+
+    fn upload(&self, buffer: &mut Self::Buffer, data: &[u8]) -> Result<(), RenderError> {
+        buffer.data.clear();
+        buffer.data.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn create_program(&self, sources: &ShaderSources<'_>) -> Result<Self::Program, RenderError> {
+        Ok(HeadlessProgram {
+            attributes: sources.attributes.iter().map(ToString::to_string).collect(),
+        })
+    }
+}
+
+const fn pixel_bytes(width: u32, height: u32) -> usize {
+    width as usize * height as usize * 4
+}
+
+/// Returns twice the signed area of the triangle `a`, `b`, `point`
+fn edge(a: [f32; 2], b: [f32; 2], point: [f32; 2]) -> f32 {
+    (b[0] - a[0]).mul_add(point[1] - a[1], -((b[1] - a[1]) * (point[0] - a[0])))
+}
+
+/// Blends a straight alpha `color` over an RGBA8 pixel
+fn blend(pixel: &mut [u8], color: [f32; 4]) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    for (channel, &value) in pixel.iter_mut().zip(&color[..3]) {
+        let below = f32::from(*channel) / 255.0;
+        *channel = to_byte((value - below).mul_add(alpha, below));
+    }
+    let below = f32::from(pixel[3]) / 255.0;
+    pixel[3] = to_byte((1.0 - alpha).mul_add(below, alpha));
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,295 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::{
+    heatmap::{Gradient, HeatPoint},
+    projection::Projection,
+};
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    post_process::{PostProcessor, effect_program, fullscreen_triangle},
+    program::ShaderProgram,
+    render_target::RenderTarget,
+    shaders,
+    texture::GpuTexture,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Corners of the quad every point is splatted on, as a triangle strip
+const UNIT_QUAD: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+/// Bytes per point instance: projected position and weight
+const POINT_BYTES: usize = 3 * 4;
+
+/// Width of the gradient lookup texture
+const GRADIENT_WIDTH: usize = 256;
+
+/// Heatmap settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatmapSettings {
+    /// Kernel radius in CSS pixels
+    pub radius: f32,
+    /// Density a point of weight 1 adds at its center; density 1 takes the
+    /// last gradient color
+    pub intensity: f32,
+    /// Layer opacity in `0.0..=1.0`
+    pub opacity: f32,
+}
+
+impl Default for HeatmapSettings {
+    fn default() -> Self {
+        Self {
+            radius: 20.0,
+            intensity: 0.2,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Density heatmap of weighted points
+///
+/// Every point adds a Gaussian kernel of its weight to an offscreen density
+/// target in one instanced call; a fullscreen pass then maps the density
+/// through a [`Gradient`] onto the scene. The density target uses half
+/// floats where the browser can render into them, so thousands of faint
+/// points still add up; otherwise it has 8 bits and saturates at 1.
+pub struct HeatmapRenderer {
+    splat: ShaderProgram,
+    u_radius: WebGlUniformLocation,
+    u_intensity: WebGlUniformLocation,
+    points: VertexArray,
+    point_count: i32,
+    colorize: ShaderProgram,
+    u_opacity: WebGlUniformLocation,
+    triangle: VertexArray,
+    gradient: GpuTexture,
+    density: Option<RenderTarget>,
+}
+
+impl HeatmapRenderer {
+    /// Creates a heatmap renderer with no points
+    ///
+    /// The density target is allocated on the first [`HeatmapRenderer::draw`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InstancingUnsupported`] on WebGL1 without
+    /// `ANGLE_instanced_arrays`, and [`RenderError`] if shader compilation,
+    /// buffer or texture creation fails
+    pub fn new(ctx: &RenderContext, gradient: &Gradient) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::HEATMAP_VERTEX, shaders::HEATMAP_FRAGMENT)
+        } else {
+            (
+                shaders::HEATMAP_VERTEX_ES100,
+                shaders::HEATMAP_FRAGMENT_ES100,
+            )
+        };
+        let splat = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::HEATMAP_ATTRIBUTES,
+        )?;
+        let u_radius = splat.get_uniform_location(gl, "u_radius")?;
+        let u_intensity = splat.get_uniform_location(gl, "u_intensity")?;
+
+        let colorize = effect_program(
+            gl,
+            shaders::HEATMAP_COLORIZE_FRAGMENT,
+            shaders::HEATMAP_COLORIZE_FRAGMENT_ES100,
+        )?;
+        let u_opacity = colorize.get_uniform_location(gl, "u_opacity")?;
+        let u_gradient = colorize.get_uniform_location(gl, "u_gradient")?;
+        colorize.use_program(gl);
+        gl.uniform1i(Some(&u_gradient), 1);
+
+        let quad_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+
+        #[allow(unsafe_code)]
+        let quad_bytes = unsafe {
+            std::slice::from_raw_parts(
+                UNIT_QUAD.as_ptr().cast::<u8>(),
+                std::mem::size_of_val(&UNIT_QUAD),
+            )
+        };
+        quad_buffer.upload_data(gl, quad_bytes);
+
+        let instance_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let instance_stride = POINT_BYTES as i32;
+        let points = VertexArray::instanced(
+            gl,
+            quad_buffer,
+            0,
+            &[VertexAttribute::float(0, 2, 0)],
+            instance_buffer,
+            instance_stride,
+            &[
+                VertexAttribute::float(1, 2, 0),
+                VertexAttribute::float(2, 1, 8),
+            ],
+        )?;
+
+        let mut renderer = Self {
+            splat,
+            u_radius,
+            u_intensity,
+            points,
+            point_count: 0,
+            colorize,
+            u_opacity,
+            triangle: fullscreen_triangle(gl)?,
+            gradient: GpuTexture::new(gl)?,
+            density: None,
+        };
+        renderer.set_gradient(ctx, gradient)?;
+        Ok(renderer)
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.splat.delete(gl);
+        self.colorize.delete(gl);
+        self.points.delete(gl);
+        self.triangle.delete(gl);
+        self.gradient.delete(gl);
+        if let Some(density) = &self.density {
+            density.delete(gl);
+        }
+    }
+
+    /// Replaces the color ramp
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if the upload fails
+    pub fn set_gradient(
+        &mut self,
+        ctx: &RenderContext,
+        gradient: &Gradient,
+    ) -> Result<(), RenderError> {
+        #[allow(clippy::cast_possible_truncation)]
+        self.gradient.upload_pixels(
+            ctx.gl(),
+            GRADIENT_WIDTH as u32,
+            1,
+            Some(&gradient.ramp(GRADIENT_WIDTH)),
+        )
+    }
+
+    /// Projects `points` and replaces the instance data with them
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn upload(
+        &mut self,
+        ctx: &RenderContext,
+        points: &[HeatPoint],
+        projection: &dyn Projection,
+    ) {
+        let gl = ctx.gl();
+
+        let mut bytes = Vec::with_capacity(points.len() * POINT_BYTES);
+        for point in points {
+            let projected = projection.project(point.coord);
+            for value in [projected.x as f32, projected.y as f32, point.weight] {
+                bytes.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        if let Some(buffer) = self.points.instance_buffer() {
+            buffer.upload_data(gl, &bytes);
+            buffer.unbind(gl);
+        }
+
+        #[allow(clippy::cast_possible_wrap)]
+        {
+            self.point_count = points.len() as i32;
+        }
+    }
+
+    /// Accumulates the points' density and composites it, colored, onto the
+    /// scene destination of `post`
+    ///
+    /// The composite uses the context's blend mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the density target can't be allocated;
+    /// nothing is drawn then
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn draw(
+        &mut self,
+        ctx: &RenderContext,
+        post: &PostProcessor,
+        settings: HeatmapSettings,
+    ) -> Result<(), RenderError> {
+        if self.point_count == 0 {
+            return Ok(());
+        }
+
+        let gl = ctx.gl();
+        let (width, height) = (ctx.width(), ctx.height());
+        if let Some(density) = &mut self.density {
+            density.resize(gl, width, height)?;
+        } else {
+            let density = match RenderTarget::half_float(gl, width, height)? {
+                Some(density) => density,
+                None => RenderTarget::new(gl, width, height)?,
+            };
+            self.density = Some(density);
+        }
+        let Some(density) = &self.density else {
+            return Ok(());
+        };
+
+        density.bind(gl);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        gl.blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ONE);
+
+        self.splat.use_with_camera(gl, &ctx.camera());
+        gl.uniform1f(
+            Some(&self.u_radius),
+            settings.radius * ctx.pixel_ratio() as f32,
+        );
+        gl.uniform1f(Some(&self.u_intensity), settings.intensity);
+        self.points.bind(gl);
+        gl.draw_arrays_instanced(
+            WebGl2RenderingContext::TRIANGLE_STRIP,
+            0,
+            4,
+            self.point_count,
+        );
+        self.points.unbind(gl);
+
+        post.bind_scene(ctx);
+        ctx.apply_blend(true);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(density.texture()));
+        self.gradient.bind(gl, 1);
+        self.colorize.use_program(gl);
+        gl.uniform1f(Some(&self.u_opacity), settings.opacity);
+        self.triangle.bind(gl);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        self.triangle.unbind(gl);
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        ctx.apply_blend(false);
+        Ok(())
+    }
+}
@@ -2,21 +2,111 @@
 //!
 //! This crate provides WebGL-based rendering for world maps and markers.
 
+/// Great-circle arc renderer
+pub mod arc_renderer;
+/// Graphics API abstraction
+pub mod backend;
+/// Layer blend modes
+pub mod blend;
+/// Bloom pass for glowing layers
+pub mod bloom;
 /// GPU buffer management
 pub mod buffer;
+/// Per-frame camera uniforms
+pub mod camera;
+/// Color grading post effect
+pub mod color_grade;
 /// WebGL rendering context
 pub mod context;
 /// Render error types
 pub mod error;
+/// Filled polygon renderer
+pub mod fill_renderer;
+/// WebGL2/WebGL1 context wrapper
+pub mod gl;
+/// Signed distance field glyph atlas
+pub mod glyph_atlas;
+/// GPU timer queries
+pub mod gpu_timer;
+/// Film grain post effect
+pub mod grain;
+/// CPU rasterizing backend for native tests
+pub mod headless;
+/// Weighted point density renderer
+pub mod heatmap_renderer;
+/// Line meshes drawn together in one call
+pub mod line_batch;
+/// Extruded line mesh generation
+pub mod line_mesh;
+/// Leveled diagnostic logging
+pub mod log;
+/// Instanced marker renderer
+pub mod marker_renderer;
+/// Georeferenced image overlay renderer
+pub mod overlay_renderer;
+/// Fullscreen post-processing passes
+pub mod post_process;
 /// Shader program utilities
 pub mod program;
+/// Offscreen render targets
+pub mod render_target;
+/// Instanced marker ripple renderer
+pub mod ripple_renderer;
+/// Partial redraw regions
+pub mod scissor;
 /// GLSL shader sources
 pub mod shaders;
+/// PNG encoding and perceptual comparison of golden images
+pub mod snapshot;
+/// Per-frame render statistics
+pub mod stats;
+/// SDF text label renderer
+pub mod text_renderer;
+/// GPU texture management
+pub mod texture;
+/// Textured rectangle renderer for map tiles
+pub mod tile_renderer;
+/// Vertex buffer layouts
+pub mod vertex_array;
+/// Vignette post effect
+pub mod vignette;
+#[cfg(feature = "webgpu")]
+pub mod webgpu;
 /// World map renderer
 pub mod world_renderer;
 
+pub use arc_renderer::ArcRenderer;
+pub use backend::{BackendKind, RenderBackend, ShaderSources};
+pub use blend::BlendMode;
+pub use bloom::{Bloom, BloomSettings};
 pub use buffer::GpuBuffer;
-pub use context::RenderContext;
+pub use camera::{CameraUniforms, View};
+pub use color_grade::ColorGradeEffect;
+pub use context::{ContextOptions, PowerPreference, RenderContext, Surface};
 pub use error::RenderError;
-pub use program::ShaderProgram;
-pub use world_renderer::WorldRenderer;
+pub use fill_renderer::{FillMode, FillRenderer};
+pub use gl::Gl;
+pub use glyph_atlas::{Glyph, GlyphAtlas};
+pub use gpu_timer::{GpuTimer, PassTiming};
+pub use grain::GrainEffect;
+pub use headless::{HeadlessBuffer, HeadlessContext, HeadlessProgram};
+pub use heatmap_renderer::{HeatmapRenderer, HeatmapSettings};
+pub use line_batch::{BatchStyle, LineBatch};
+pub use log::LogLevel;
+pub use marker_renderer::MarkerRenderer;
+pub use overlay_renderer::OverlayRenderer;
+pub use post_process::{PostEffect, PostFrame, PostProcessor};
+pub use program::{BUILTIN_PROGRAMS, ShaderProgram};
+pub use render_target::RenderTarget;
+pub use ripple_renderer::RippleRenderer;
+pub use scissor::ScissorRect;
+pub use snapshot::SnapshotDiff;
+pub use stats::{CountingAllocator, RenderStats};
+pub use text_renderer::{TextAnchor, TextRenderer, TextStyle};
+pub use texture::GpuTexture;
+pub use tile_renderer::TileRenderer;
+pub use vertex_array::{VertexArray, VertexAttribute};
+pub use vignette::VignetteEffect;
+#[cfg(feature = "webgpu")]
+pub use webgpu::WebGpuContext;
+pub use world_renderer::{LineMesh, WorldRenderer};
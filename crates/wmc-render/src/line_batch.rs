@@ -0,0 +1,236 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::theme::LineStyle;
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    line_mesh::{LINE_VERTEX_FLOATS, QUANTIZED_LINE_VERTEX_BYTES, quantize, stretch},
+    program::ShaderProgram,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+    world_renderer::{FeatureIndex, LineMesh, dash_pattern, stretch_transform},
+};
+
+/// Most line meshes one [`LineBatch`] draws, the length of the paint arrays
+/// of [`shaders::LINE_BATCH_VERTEX`]
+pub const MAX_BATCH_MEMBERS: usize = 8;
+
+/// Byte offset of the mesh index in a quantized vertex, the spare `u16`
+/// after the side
+const MEMBER_OFFSET: usize = 10;
+
+/// Paint of one mesh of a [`LineBatch`], lengths in drawing buffer pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchStyle {
+    /// Line color
+    pub color: [f32; 4],
+    /// Line width
+    pub width: f32,
+    /// Dash pattern
+    pub style: LineStyle,
+}
+
+/// Several line meshes in one vertex buffer, drawn in one call
+///
+/// Each vertex carries the index of its mesh, which picks the mesh's paint
+/// from uniform arrays, so meshes drawn one after another with the built-in
+/// line shaders and the same blend mode can share a draw call. Meshes are
+/// drawn in order, each over the ones before it, as separate draws would.
+pub struct LineBatch {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    vertex_count: i32,
+    features: FeatureIndex,
+    /// Maps quantized positions back to the pixels of the projection
+    dequantize: [f32; 9],
+    /// Drawing buffer size the meshes were stretched to on upload
+    size: [f32; 2],
+    /// Current drawing buffer size over `size`
+    stretch: [f32; 2],
+    u_dequantize: WebGlUniformLocation,
+    u_stretch: WebGlUniformLocation,
+    u_colors: WebGlUniformLocation,
+    u_strokes: WebGlUniformLocation,
+    u_aa_width: WebGlUniformLocation,
+}
+
+impl LineBatch {
+    /// Uploads `meshes`, each with the drawing buffer size it was projected
+    /// for, stretched to the context's size
+    ///
+    /// Meshes past [`MAX_BATCH_MEMBERS`] are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(
+        ctx: &RenderContext,
+        meshes: &[(&LineMesh, [u32; 2])],
+    ) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::LINE_BATCH_VERTEX, shaders::LINE_BATCH_FRAGMENT)
+        } else {
+            (
+                shaders::LINE_BATCH_VERTEX_ES100,
+                shaders::LINE_BATCH_FRAGMENT_ES100,
+            )
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::LINE_BATCH_ATTRIBUTES,
+        )?;
+
+        let u_dequantize = program.get_uniform_location(gl, "u_dequantize")?;
+        let u_stretch = program.get_uniform_location(gl, "u_stretch")?;
+        let u_colors = program.get_uniform_location(gl, "u_colors")?;
+        let u_strokes = program.get_uniform_location(gl, "u_strokes")?;
+        let u_aa_width = program.get_uniform_location(gl, "u_aa_width")?;
+
+        #[allow(clippy::cast_precision_loss)]
+        let size = [ctx.width().max(1) as f32, ctx.height().max(1) as f32];
+        let mut vertices = Vec::new();
+        let mut members = Vec::new();
+        let mut spans = Vec::new();
+        for &(mesh, projected) in meshes.iter().take(MAX_BATCH_MEMBERS) {
+            #[allow(clippy::cast_precision_loss)]
+            let scale = [
+                size[0] / projected[0].max(1) as f32,
+                size[1] / projected[1].max(1) as f32,
+            ];
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let first = (vertices.len() / LINE_VERTEX_FLOATS) as i32;
+            let start = vertices.len();
+            vertices.extend_from_slice(&mesh.vertices);
+            stretch(&mut vertices[start..], scale);
+
+            members.push(mesh.vertices.len() / LINE_VERTEX_FLOATS);
+            spans.extend(
+                mesh.ranges
+                    .iter()
+                    .zip(&mesh.bounds)
+                    .map(|(&[start, count], bounds)| {
+                        let bounds = [
+                            bounds[0] * scale[0],
+                            bounds[1] * scale[1],
+                            bounds[2] * scale[0],
+                            bounds[3] * scale[1],
+                        ];
+                        ([first + start, count], bounds)
+                    }),
+            );
+        }
+
+        let mut quantized = quantize(&vertices);
+        let mut chunks = quantized
+            .bytes
+            .as_chunks_mut::<QUANTIZED_LINE_VERTEX_BYTES>()
+            .0
+            .iter_mut();
+        for (member, count) in (0u16..).zip(members) {
+            for vertex in chunks.by_ref().take(count) {
+                vertex[MEMBER_OFFSET..MEMBER_OFFSET + 2].copy_from_slice(&member.to_le_bytes());
+            }
+        }
+
+        let vertex_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+        vertex_buffer.upload_data(gl, &quantized.bytes);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let stride = QUANTIZED_LINE_VERTEX_BYTES as i32;
+        let vertex_array = VertexArray::new(
+            gl,
+            vertex_buffer,
+            stride,
+            &[
+                VertexAttribute::unorm16(0, 2, 0),
+                VertexAttribute::snorm16(1, 2, 4),
+                VertexAttribute::snorm16(2, 1, 8),
+                VertexAttribute::float(3, 1, 12),
+                VertexAttribute::uint16(4, 1, 10),
+            ],
+        )?;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let vertex_count = (vertices.len() / LINE_VERTEX_FLOATS) as i32;
+
+        Ok(Self {
+            program,
+            vertices: vertex_array,
+            vertex_count,
+            features: FeatureIndex::new(spans),
+            dequantize: quantized.dequantize,
+            size,
+            stretch: [1.0, 1.0],
+            u_dequantize,
+            u_stretch,
+            u_colors,
+            u_strokes,
+            u_aa_width,
+        })
+    }
+
+    /// Stretches the meshes over a `width` x `height` drawing buffer, see
+    /// [`crate::WorldRenderer::resize`]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.stretch = [width as f32 / self.size[0], height as f32 / self.size[1]];
+    }
+
+    /// Frees the batch's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+    }
+
+    /// Draws the meshes, each with the paint at its position in `styles`
+    ///
+    /// While the context is scissored, only features whose bounds reach
+    /// into the scissor box are submitted.
+    pub fn draw(&self, ctx: &RenderContext, styles: &[BatchStyle]) {
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+
+        let mut colors = [0.0; MAX_BATCH_MEMBERS * 4];
+        let mut strokes = [0.0; MAX_BATCH_MEMBERS * 4];
+        let mut reach: f32 = 0.0;
+        for ((style, color), stroke) in styles
+            .iter()
+            .zip(colors.as_chunks_mut::<4>().0)
+            .zip(strokes.as_chunks_mut::<4>().0)
+        {
+            let [dash, gap, round] = dash_pattern(style.style);
+            *color = style.color;
+            *stroke = [style.width, dash, gap, round];
+            reach = reach.max(style.width);
+        }
+
+        gl.uniform_matrix3fv_with_f32_array(
+            Some(&self.u_dequantize),
+            false,
+            &stretch_transform(self.dequantize, self.stretch),
+        );
+        gl.uniform2f(Some(&self.u_stretch), self.stretch[0], self.stretch[1]);
+        gl.uniform4fv_with_f32_array(Some(&self.u_colors), &colors);
+        gl.uniform4fv_with_f32_array(Some(&self.u_strokes), &strokes);
+        #[allow(clippy::cast_possible_truncation)]
+        gl.uniform1f(Some(&self.u_aa_width), ctx.pixel_ratio() as f32);
+
+        self.vertices.bind(gl);
+        match ctx.scissor() {
+            Some(region) => self.features.draw_region(ctx, region, reach, self.stretch),
+            None => gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count),
+        }
+        self.vertices.unbind(gl);
+    }
+}
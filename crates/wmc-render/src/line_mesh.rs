@@ -0,0 +1,298 @@
+/// Number of `f32` values per line mesh vertex: position (2) + extrusion (2) + side (1) +
+/// distance along the line (1)
+pub const LINE_VERTEX_FLOATS: usize = 6;
+
+/// Miter length (in half-widths) above which a join falls back to a bevel
+///
+/// It bounds the extrusion vectors, which [`quantize`] stores relative to
+/// it.
+pub const MITER_LIMIT: f32 = 4.0;
+
+/// Number of bytes per quantized line mesh vertex: position (2 × `u16`) +
+/// extrusion (2 × `i16`) + side (`i16`) + spare (`u16`) + distance along
+/// the line (`f32`)
+///
+/// [`crate::LineBatch`] fills the spare bytes with the mesh of the vertex.
+pub const QUANTIZED_LINE_VERTEX_BYTES: usize = 16;
+
+/// Line mesh vertices quantized for upload, see [`quantize`]
+#[derive(Debug, Clone, Default)]
+pub struct QuantizedMesh {
+    /// Interleaved vertices of [`QUANTIZED_LINE_VERTEX_BYTES`] bytes each
+    pub bytes: Vec<u8>,
+    /// Column-major 3x3 matrix mapping normalized positions in `0.0..=1.0`
+    /// back to pixels
+    pub dequantize: [f32; 9],
+}
+
+/// Packs vertices of [`LINE_VERTEX_FLOATS`] floats into
+/// [`QUANTIZED_LINE_VERTEX_BYTES`] bytes each
+///
+/// Positions become `u16` steps across the bounds of the mesh, a
+/// sixteenth of a pixel on a 4096 pixel wide buffer, and extrusions `i16`
+/// steps across `±MITER_LIMIT`. Distances along the line stay `f32`, as dash
+/// patterns on long lines need their precision.
+///
+/// ```
+/// use wmc_render::line_mesh::{QUANTIZED_LINE_VERTEX_BYTES, extrude_polyline, quantize};
+///
+/// let mut vertices = Vec::new();
+/// extrude_polyline(&mut vertices, &[[10.0, 20.0], [110.0, 20.0]]);
+/// let mesh = quantize(&vertices);
+///
+/// assert_eq!(mesh.bytes.len(), 6 * QUANTIZED_LINE_VERTEX_BYTES);
+/// // Normalized x 1.0 maps back to the right end of the line
+/// let [sx, _, _, _, _, _, tx, _, _] = mesh.dequantize;
+/// assert_eq!(sx + tx, 110.0);
+/// ```
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn quantize(vertices: &[f32]) -> QuantizedMesh {
+    let vertices = vertices.as_chunks::<LINE_VERTEX_FLOATS>().0;
+    let [min_x, min_y, max_x, max_y] = vertices.iter().fold(
+        [
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ],
+        |[min_x, min_y, max_x, max_y], vertex| {
+            [
+                min_x.min(vertex[0]),
+                min_y.min(vertex[1]),
+                max_x.max(vertex[0]),
+                max_y.max(vertex[1]),
+            ]
+        },
+    );
+    if vertices.is_empty() {
+        return QuantizedMesh::default();
+    }
+
+    let (width, height) = (max_x - min_x, max_y - min_y);
+    let unorm = |value: f32, min: f32, extent: f32| {
+        if extent > 0.0 {
+            ((value - min) / extent * f32::from(u16::MAX)).round() as u16
+        } else {
+            0
+        }
+    };
+    let snorm = |value: f32| (value.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16;
+
+    let mut bytes = Vec::with_capacity(vertices.len() * QUANTIZED_LINE_VERTEX_BYTES);
+    for &[x, y, extrusion_x, extrusion_y, side, along] in vertices {
+        bytes.extend_from_slice(&unorm(x, min_x, width).to_le_bytes());
+        bytes.extend_from_slice(&unorm(y, min_y, height).to_le_bytes());
+        bytes.extend_from_slice(&snorm(extrusion_x / MITER_LIMIT).to_le_bytes());
+        bytes.extend_from_slice(&snorm(extrusion_y / MITER_LIMIT).to_le_bytes());
+        bytes.extend_from_slice(&snorm(side).to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(&along.to_le_bytes());
+    }
+
+    QuantizedMesh {
+        bytes,
+        dequantize: [width, 0.0, 0.0, 0.0, height, 0.0, min_x, min_y, 1.0],
+    }
+}
+
+/// Stretches vertices of [`LINE_VERTEX_FLOATS`] floats by `scale`, as for
+/// a buffer `scale` times the size the mesh was projected for
+///
+/// Extrusions are turned to stay normal to the stretched segments, keeping
+/// their length, as the line vertex shaders do. Distances along the line
+/// are kept, so dash patterns don't stretch.
+pub fn stretch(vertices: &mut [f32], [scale_x, scale_y]: [f32; 2]) {
+    for vertex in vertices.as_chunks_mut::<LINE_VERTEX_FLOATS>().0 {
+        vertex[0] *= scale_x;
+        vertex[1] *= scale_y;
+
+        let turned = [vertex[2] / scale_x, vertex[3] / scale_y];
+        let length = turned[0].hypot(turned[1]);
+        if length > 0.0 {
+            let scale = vertex[2].hypot(vertex[3]) / length;
+            vertex[2] = turned[0] * scale;
+            vertex[3] = turned[1] * scale;
+        }
+    }
+}
+
+/// Returns where a mesh vertex lands on a line `line_width` pixels wide
+///
+/// The point moves by `extrusion * line_width / 2`, as in the line vertex
+/// shaders before their anti-aliasing margin.
+///
+/// ```
+/// use wmc_render::line_mesh::{LINE_VERTEX_FLOATS, extrude_polyline, extruded_position};
+///
+/// let mut vertices = Vec::new();
+/// extrude_polyline(&mut vertices, &[[0.0, 10.0], [20.0, 10.0]]);
+/// let edges = vertices.as_chunks::<LINE_VERTEX_FLOATS>().0;
+///
+/// let [_, y] = extruded_position(&edges[0], 4.0);
+/// assert_eq!((y - 10.0).abs(), 2.0);
+/// ```
+#[must_use]
+pub fn extruded_position(vertex: &[f32; LINE_VERTEX_FLOATS], line_width: f32) -> [f32; 2] {
+    let half = line_width / 2.0;
+    [
+        half.mul_add(vertex[2], vertex[0]),
+        half.mul_add(vertex[3], vertex[1]),
+    ]
+}
+
+/// Appends a triangulated, extrudable mesh for a polyline to `vertices`
+///
+/// Every vertex stores the polyline point, a unit-width extrusion vector and
+/// a side value (`±1` on the line edges, `0` on its core). The vertex shader
+/// offsets the point by `extrusion * width / 2`, so the same mesh serves every
+/// line width, and the interpolated side gives the fragment shader its signed
+/// distance to the line core for edge anti-aliasing. The distance from the start of the
+/// polyline to the vertex's point lets it lay out dash patterns. Interior joins are
+/// mitered, falling back to a bevel for sharp angles. A polyline whose last point equals
+/// its first is treated as a closed ring and joined at the seam as well.
+///
+/// Non-finite and consecutive duplicate points are skipped.
+pub fn extrude_polyline(vertices: &mut Vec<f32>, points: &[[f32; 2]]) {
+    let mut path: Vec<[f32; 2]> = Vec::with_capacity(points.len());
+    for &point in points {
+        if point[0].is_finite() && point[1].is_finite() && path.last() != Some(&point) {
+            path.push(point);
+        }
+    }
+
+    let closed = path.len() > 2 && path.first() == path.last();
+    if closed {
+        path.pop();
+    }
+
+    let point_count = path.len();
+    if point_count < 2 {
+        return;
+    }
+
+    let segment_count = if closed { point_count } else { point_count - 1 };
+    let mut normals = Vec::with_capacity(segment_count);
+    // Distance along the line to the start of each segment, and to the end
+    let mut along = Vec::with_capacity(segment_count + 1);
+    along.push(0.0);
+    for i in 0..segment_count {
+        let a = path[i];
+        let b = path[(i + 1) % point_count];
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = dx.hypot(dy);
+        normals.push([-dy / len, dx / len]);
+        along.push(along[i] + len);
+    }
+
+    // Offset of each point for its outgoing (start) and incoming (end) segment
+    let mut joins = Vec::with_capacity(point_count);
+    for j in 0..point_count {
+        let incoming = if closed || j > 0 {
+            Some(normals[(j + segment_count - 1) % segment_count])
+        } else {
+            None
+        };
+        let outgoing = if closed || j < segment_count {
+            Some(normals[j % segment_count])
+        } else {
+            None
+        };
+
+        joins.push(match (incoming, outgoing) {
+            (Some(n_in), Some(n_out)) => Join::new(n_in, n_out),
+            (Some(n), None) | (None, Some(n)) => Join::Miter(n),
+            (None, None) => Join::Miter([0.0, 0.0]),
+        });
+    }
+
+    for i in 0..segment_count {
+        let a = (path[i], along[i]);
+        let b = (path[(i + 1) % point_count], along[i + 1]);
+        let n = normals[i];
+        let start = joins[i].offset(n);
+        let end = joins[(i + 1) % point_count].offset(n);
+
+        push_vertex(vertices, a, start, 1.0);
+        push_vertex(vertices, a, neg(start), -1.0);
+        push_vertex(vertices, b, end, 1.0);
+
+        push_vertex(vertices, a, neg(start), -1.0);
+        push_vertex(vertices, b, neg(end), -1.0);
+        push_vertex(vertices, b, end, 1.0);
+    }
+
+    for (j, join) in joins.iter().enumerate() {
+        if let Join::Bevel { from, to } = *join {
+            let point = (path[j], along[j]);
+            push_vertex(vertices, point, [0.0, 0.0], 0.0);
+            push_vertex(vertices, point, from, 1.0);
+            push_vertex(vertices, point, to, 1.0);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Join {
+    /// Both segments share a single mitered offset
+    Miter([f32; 2]),
+    /// Each segment keeps its own normal; a triangle fills the outer gap
+    Bevel {
+        /// Outer offset of the incoming segment
+        from: [f32; 2],
+        /// Outer offset of the outgoing segment
+        to: [f32; 2],
+    },
+}
+
+impl Join {
+    fn new(n_in: [f32; 2], n_out: [f32; 2]) -> Self {
+        let sum = [n_in[0] + n_out[0], n_in[1] + n_out[1]];
+        let sum_len = sum[0].hypot(sum[1]);
+
+        if sum_len > f32::EPSILON {
+            let miter = [sum[0] / sum_len, sum[1] / sum_len];
+            let cos_half = miter[0].mul_add(n_out[0], miter[1] * n_out[1]);
+            let length = 1.0 / cos_half;
+
+            if length <= MITER_LIMIT {
+                return Self::Miter([miter[0] * length, miter[1] * length]);
+            }
+        }
+
+        // The outer side is where the two normals diverge, opposite the turn
+        let turn = n_in[0].mul_add(n_out[1], -(n_in[1] * n_out[0]));
+        if turn > 0.0 {
+            Self::Bevel {
+                from: neg(n_in),
+                to: neg(n_out),
+            }
+        } else {
+            Self::Bevel {
+                from: n_in,
+                to: n_out,
+            }
+        }
+    }
+
+    /// Returns the offset a segment with the given normal uses at this join
+    const fn offset(self, normal: [f32; 2]) -> [f32; 2] {
+        match self {
+            Self::Miter(offset) => offset,
+            Self::Bevel { .. } => normal,
+        }
+    }
+}
+
+const fn neg(v: [f32; 2]) -> [f32; 2] {
+    [-v[0], -v[1]]
+}
+
+fn push_vertex(
+    vertices: &mut Vec<f32>,
+    (point, along): ([f32; 2], f32),
+    extrusion: [f32; 2],
+    side: f32,
+) {
+    vertices.extend_from_slice(&[point[0], point[1], extrusion[0], extrusion[1], side, along]);
+}
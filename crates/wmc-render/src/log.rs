@@ -0,0 +1,131 @@
+use std::cell::{Cell, RefCell};
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::JsValue;
+
+/// Severity of a diagnostic message, from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Something failed and the map is missing content
+    Error,
+    /// Something was skipped or degraded but the map still draws
+    Warn,
+    /// Notable events such as slow frames
+    Info,
+    /// Detail for tracking down a problem
+    Debug,
+}
+
+impl LogLevel {
+    /// Parses a level name: `error`, `warn`, `info` or `debug`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+
+    /// Returns the level name as accepted by [`LogLevel::from_name`]
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+        }
+    }
+}
+
+thread_local! {
+    /// Least severe level emitted, `None` silencing every message
+    static MAX_LEVEL: Cell<Option<LogLevel>> = const { Cell::new(Some(LogLevel::Warn)) };
+    /// Host logger replacing the console
+    static LOGGER: RefCell<Option<Function>> = const { RefCell::new(None) };
+}
+
+/// Sets the least severe level emitted, or silences logging with `None`
+///
+/// Defaults to [`LogLevel::Warn`].
+pub fn set_max_level(level: Option<LogLevel>) {
+    MAX_LEVEL.with(|max| max.set(level));
+}
+
+/// Returns true if messages at `level` are emitted
+///
+/// Lets callers skip building fields for messages that would be dropped.
+#[must_use]
+pub fn enabled(level: LogLevel) -> bool {
+    MAX_LEVEL.with(Cell::get).is_some_and(|max| level <= max)
+}
+
+/// Sends messages to `logger` instead of the console, or back to the
+/// console with `None`
+///
+/// The logger is called with a `{level, target, message, fields}` record;
+/// errors it throws are reported to the console.
+pub fn set_logger(logger: Option<Function>) {
+    LOGGER.with(|current| *current.borrow_mut() = logger);
+}
+
+/// Emits a structured message if `level` is enabled
+///
+/// `target` names the subsystem, e.g. `frame` or `shader`, and `fields`
+/// carry the message's data.
+pub fn log(level: LogLevel, target: &str, message: &str, fields: &[(&str, JsValue)]) {
+    if !enabled(level) {
+        return;
+    }
+
+    let data = Object::new();
+    for (key, value) in fields {
+        // Setting a property on a fresh object can't fail
+        let _ = Reflect::set(&data, &JsValue::from_str(key), value);
+    }
+
+    // Clone the logger out so it may call back into logging
+    let logger = LOGGER.with(|logger| logger.borrow().clone());
+    let Some(logger) = logger else {
+        let text = JsValue::from_str(&format!("[wmc:{target}] {message}"));
+        match level {
+            LogLevel::Error => web_sys::console::error_2(&text, &data),
+            LogLevel::Warn => web_sys::console::warn_2(&text, &data),
+            LogLevel::Info => web_sys::console::info_2(&text, &data),
+            LogLevel::Debug => web_sys::console::debug_2(&text, &data),
+        }
+        return;
+    };
+
+    let record = Object::new();
+    let _ = Reflect::set(&record, &"level".into(), &level.name().into());
+    let _ = Reflect::set(&record, &"target".into(), &target.into());
+    let _ = Reflect::set(&record, &"message".into(), &message.into());
+    let _ = Reflect::set(&record, &"fields".into(), &data);
+    if let Err(e) = logger.call1(&JsValue::NULL, &record) {
+        web_sys::console::error_1(&e);
+    }
+}
+
+/// Emits a message at [`LogLevel::Error`]
+pub fn error(target: &str, message: &str, fields: &[(&str, JsValue)]) {
+    log(LogLevel::Error, target, message, fields);
+}
+
+/// Emits a message at [`LogLevel::Warn`]
+pub fn warn(target: &str, message: &str, fields: &[(&str, JsValue)]) {
+    log(LogLevel::Warn, target, message, fields);
+}
+
+/// Emits a message at [`LogLevel::Info`]
+pub fn info(target: &str, message: &str, fields: &[(&str, JsValue)]) {
+    log(LogLevel::Info, target, message, fields);
+}
+
+/// Emits a message at [`LogLevel::Debug`]
+pub fn debug(target: &str, message: &str, fields: &[(&str, JsValue)]) {
+    log(LogLevel::Debug, target, message, fields);
+}
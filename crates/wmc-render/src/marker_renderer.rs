@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::marker_buffer::{MarkerBuffer, MarkerInstance};
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Corners of the quad every marker is drawn on, as a triangle strip
+const UNIT_QUAD: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+/// Marker size factor at the middle of the pulse
+pub const PULSE_BASE: f32 = 0.8;
+
+/// How far the pulse swings the size factor either way from [`PULSE_BASE`]
+pub const PULSE_AMPLITUDE: f32 = 0.2;
+
+/// Returns the factor the marker radius is scaled by at `time` seconds for
+/// a marker of pulse `phase`
+///
+/// The marker vertex shaders compute the same through
+/// [`shaders::PULSE_CHUNK`], and [`crate::HeadlessContext::draw_markers`]
+/// calls this, so both draw markers the same size.
+///
+/// ```
+/// use std::f32::consts::FRAC_PI_2;
+///
+/// use wmc_render::marker_renderer::marker_pulse;
+///
+/// assert_eq!(marker_pulse(0.0, 0.0), 0.8);
+/// assert_eq!(marker_pulse(FRAC_PI_2, 0.0), 1.0);
+/// ```
+#[must_use]
+pub fn marker_pulse(time: f32, phase: f32) -> f32 {
+    PULSE_AMPLITUDE.mul_add((time + phase).sin(), PULSE_BASE)
+}
+
+/// Instanced WebGL renderer for markers
+///
+/// A single unit quad is shared by all markers; position, intensity, phase
+/// and color come from a [`MarkerBuffer`] uploaded as per-instance attributes,
+/// so any number of markers is one draw call.
+pub struct MarkerRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    instance_count: i32,
+    u_marker_size: WebGlUniformLocation,
+}
+
+impl MarkerRenderer {
+    /// Creates a marker renderer with no instances
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InstancingUnsupported`] on WebGL1 without
+    /// `ANGLE_instanced_arrays`, and [`RenderError`] if shader compilation or
+    /// buffer creation fails
+    pub fn new(ctx: &RenderContext) -> Result<Self, RenderError> {
+        Self::with_fragment(ctx, None)
+    }
+
+    /// Creates a marker renderer drawing rings instead of discs, e.g. to
+    /// outline the selected marker
+    ///
+    /// Rings cover the outer fifth of the marker radius, see
+    /// [`shaders::MARKER_RING_FRAGMENT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InstancingUnsupported`] on WebGL1 without
+    /// `ANGLE_instanced_arrays`, and [`RenderError`] if shader compilation or
+    /// buffer creation fails
+    pub fn ring(ctx: &RenderContext) -> Result<Self, RenderError> {
+        let fragment = if ctx.gl().is_webgl2() {
+            shaders::MARKER_RING_FRAGMENT
+        } else {
+            shaders::MARKER_RING_FRAGMENT_ES100
+        };
+        Self::with_fragment(ctx, Some(fragment))
+    }
+
+    /// Creates a marker renderer drawing with a custom fragment shader, or
+    /// the built-in one when `fragment` is `None`
+    ///
+    /// The shader must match the context: GLSL ES 3.00 on WebGL2, GLSL ES
+    /// 1.00 on WebGL1. It receives the `v_color`, `v_uv` and `v_intensity`
+    /// varyings of [`shaders::MARKER_FRAGMENT`], `v_uv` spanning the marker
+    /// quad from -1 to 1.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::ShaderCompilationFailed`] if `fragment` doesn't
+    /// compile, [`RenderError::InstancingUnsupported`] on WebGL1 without
+    /// `ANGLE_instanced_arrays`, and [`RenderError`] if linking or buffer
+    /// creation fails
+    pub fn with_fragment(
+        ctx: &RenderContext,
+        fragment: Option<&str>,
+    ) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::MARKER_VERTEX, shaders::MARKER_FRAGMENT)
+        } else {
+            (shaders::MARKER_VERTEX_ES100, shaders::MARKER_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment.unwrap_or(fragment_source),
+            shaders::MARKER_ATTRIBUTES,
+        )?;
+
+        let u_marker_size = program.get_uniform_location(gl, "u_marker_size")?;
+
+        let vertices = marker_vertices(gl)?;
+
+        Ok(Self {
+            program,
+            vertices,
+            instance_count: 0,
+            u_marker_size,
+        })
+    }
+
+    /// Returns the number of uploaded markers
+    #[must_use]
+    pub const fn instance_count(&self) -> i32 {
+        self.instance_count
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+    }
+
+    /// Replaces the instance data with the contents of `markers`
+    pub fn upload(&mut self, ctx: &RenderContext, markers: &MarkerBuffer) {
+        let gl = ctx.gl();
+
+        if let Some(buffer) = self.vertices.instance_buffer() {
+            buffer.upload_data(gl, markers.as_bytes());
+            buffer.unbind(gl);
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        {
+            self.instance_count = markers.len() as i32;
+        }
+    }
+
+    /// Re-uploads the instances in `range` of `markers`, which must hold as
+    /// many markers as the last full upload
+    pub fn update(&self, ctx: &RenderContext, markers: &MarkerBuffer, range: Range<usize>) {
+        let gl = ctx.gl();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let offset = (range.start * std::mem::size_of::<MarkerInstance>()) as i32;
+        if let Some(buffer) = self.vertices.instance_buffer() {
+            buffer.update_data(gl, offset, markers.range_bytes(range));
+            buffer.unbind(gl);
+        }
+    }
+
+    /// Draws all markers in a single instanced call
+    ///
+    /// `size` is the marker radius in device pixels; the camera time drives
+    /// the pulse animation.
+    pub fn draw(&self, ctx: &RenderContext, size: f32) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+        gl.uniform1f(Some(&self.u_marker_size), size);
+
+        self.vertices.bind(gl);
+        gl.draw_arrays_instanced(
+            WebGl2RenderingContext::TRIANGLE_STRIP,
+            0,
+            4,
+            self.instance_count,
+        );
+        self.vertices.unbind(gl);
+    }
+}
+
+/// Creates the vertex array of the unit quad with an empty buffer of
+/// [`MarkerInstance`] attributes, laid out as [`shaders::MARKER_ATTRIBUTES`]
+///
+/// # Errors
+///
+/// Returns [`RenderError`] if buffer or vertex array creation fails
+pub(crate) fn marker_vertices(gl: &Gl) -> Result<VertexArray, RenderError> {
+    let quad_buffer = GpuBuffer::new(
+        gl,
+        WebGl2RenderingContext::ARRAY_BUFFER,
+        WebGl2RenderingContext::STATIC_DRAW,
+    )?;
+
+    #[allow(unsafe_code)]
+    let quad_bytes = unsafe {
+        std::slice::from_raw_parts(
+            UNIT_QUAD.as_ptr().cast::<u8>(),
+            std::mem::size_of_val(&UNIT_QUAD),
+        )
+    };
+    quad_buffer.upload_data(gl, quad_bytes);
+
+    let instance_buffer = GpuBuffer::new(
+        gl,
+        WebGl2RenderingContext::ARRAY_BUFFER,
+        WebGl2RenderingContext::DYNAMIC_DRAW,
+    )?;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let instance_stride = std::mem::size_of::<MarkerInstance>() as i32;
+    VertexArray::instanced(
+        gl,
+        quad_buffer,
+        0,
+        &[VertexAttribute::float(0, 2, 0)],
+        instance_buffer,
+        instance_stride,
+        &[
+            VertexAttribute::float(1, 2, 0),
+            VertexAttribute::float(2, 1, 8),
+            VertexAttribute::float(3, 1, 12),
+            VertexAttribute::float(4, 4, 16),
+        ],
+    )
+}
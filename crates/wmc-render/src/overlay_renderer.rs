@@ -0,0 +1,141 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::projection::GeoBounds;
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    shaders,
+    texture::GpuTexture,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Latitude bands the overlay is split into
+///
+/// Vertices are projected exactly; in between, Mercator's latitude stretch is
+/// approximated linearly, so taller overlays need more bands.
+const OVERLAY_BANDS: usize = 64;
+
+/// WebGL renderer draping an image over a geographic bounding box
+///
+/// The image is taken to span its bounds linearly in longitude and latitude,
+/// as georeferenced rasters usually do, and is reprojected to Web Mercator in
+/// the vertex shader.
+pub struct OverlayRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    vertex_count: i32,
+    texture: GpuTexture,
+    u_opacity: WebGlUniformLocation,
+}
+
+impl OverlayRenderer {
+    /// Creates an overlay of `texture` over `bounds`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(
+        ctx: &RenderContext,
+        bounds: GeoBounds,
+        texture: GpuTexture,
+    ) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::OVERLAY_VERTEX, shaders::TILE_FRAGMENT)
+        } else {
+            (shaders::OVERLAY_VERTEX_ES100, shaders::TILE_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::OVERLAY_ATTRIBUTES,
+        )?;
+
+        let u_bounds = program.get_uniform_location(gl, "u_bounds")?;
+        let u_opacity = program.get_uniform_location(gl, "u_opacity")?;
+        let u_texture = program.get_uniform_location(gl, "u_texture")?;
+        program.use_program(gl);
+        #[allow(clippy::cast_possible_truncation)]
+        gl.uniform4f(
+            Some(&u_bounds),
+            bounds.west as f32,
+            bounds.south as f32,
+            bounds.east as f32,
+            bounds.north as f32,
+        );
+        gl.uniform1i(Some(&u_texture), 0);
+
+        let vertices = Self::build_vertices(bounds);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let vertex_count = (vertices.len() / 2) as i32;
+
+        let vertex_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+
+        #[allow(unsafe_code)]
+        let vertices_bytes = unsafe {
+            std::slice::from_raw_parts(
+                vertices.as_ptr().cast::<u8>(),
+                vertices.len() * std::mem::size_of::<f32>(),
+            )
+        };
+        vertex_buffer.upload_data(gl, vertices_bytes);
+
+        let vertices = VertexArray::new(gl, vertex_buffer, 0, &[VertexAttribute::float(0, 2, 0)])?;
+
+        Ok(Self {
+            program,
+            vertices,
+            vertex_count,
+            texture,
+            u_opacity,
+        })
+    }
+
+    /// Frees the renderer's GPU resources, including the image texture; it
+    /// must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+        self.texture.delete(gl);
+    }
+
+    /// Draws the overlay at `opacity` in `0.0..=1.0`
+    pub fn draw(&self, ctx: &RenderContext, opacity: f32) {
+        let gl = ctx.gl();
+
+        self.program.use_program(gl);
+        gl.uniform1f(Some(&self.u_opacity), opacity);
+        self.texture.bind(gl, 0);
+        ctx.apply_blend(true);
+
+        self.vertices.bind(gl);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, self.vertex_count);
+        self.vertices.unbind(gl);
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        ctx.apply_blend(false);
+    }
+
+    /// Builds a triangle strip of longitude/latitude pairs from north to south
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn build_vertices(bounds: GeoBounds) -> Vec<f32> {
+        let mut vertices = Vec::with_capacity((OVERLAY_BANDS + 1) * 4);
+
+        for band in 0..=OVERLAY_BANDS {
+            let t = band as f64 / OVERLAY_BANDS as f64;
+            let lat = (bounds.south - bounds.north).mul_add(t, bounds.north) as f32;
+            vertices.extend_from_slice(&[bounds.west as f32, lat, bounds.east as f32, lat]);
+        }
+
+        vertices
+    }
+}
@@ -0,0 +1,318 @@
+use web_sys::WebGl2RenderingContext;
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    render_target::RenderTarget,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Clip-space corners of a triangle covering the whole viewport
+const FULLSCREEN_TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+/// Per-frame values passed to [`PostEffect::apply_uniforms`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostFrame {
+    /// Animation time in seconds
+    pub time: f32,
+    /// Drawing buffer width in device pixels
+    pub width: u32,
+    /// Drawing buffer height in device pixels
+    pub height: u32,
+    /// Device pixel ratio of the drawing buffer
+    pub pixel_ratio: f32,
+    /// Seed of noise effects, see [`PostProcessor::set_seed`]
+    pub seed: f32,
+}
+
+/// Fullscreen pass in a [`PostProcessor`] chain
+///
+/// The program is drawn over the whole viewport with the previous pass'
+/// output bound to texture unit 0; fragment shaders read it through a
+/// `sampler2D u_scene` at the `v_uv` coordinates provided by
+/// [`shaders::POST_VERTEX`]. Build programs with [`effect_program`].
+pub trait PostEffect {
+    /// Returns the program drawing this pass
+    fn program(&self) -> &ShaderProgram;
+
+    /// Sets the program's uniforms; the program is already in use
+    fn apply_uniforms(&self, gl: &Gl, frame: &PostFrame);
+
+    /// Returns false to skip this pass
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// Returns true if the pass changes over time and needs continuous frames
+    fn is_animated(&self) -> bool {
+        false
+    }
+}
+
+/// Compiles a post effect program from its fragment shader variants
+///
+/// # Errors
+///
+/// Returns [`RenderError`] if shader compilation or program linking fails
+pub fn effect_program(
+    gl: &Gl,
+    fragment: &str,
+    fragment_es100: &str,
+) -> Result<ShaderProgram, RenderError> {
+    let (vertex_source, fragment_source) = if gl.is_webgl2() {
+        (shaders::POST_VERTEX, fragment)
+    } else {
+        (shaders::POST_VERTEX_ES100, fragment_es100)
+    };
+
+    ShaderProgram::with_attributes(gl, vertex_source, fragment_source, shaders::POST_ATTRIBUTES)
+}
+
+/// Creates a vertex array holding [`FULLSCREEN_TRIANGLE`] at location 0
+pub(crate) fn fullscreen_triangle(gl: &Gl) -> Result<VertexArray, RenderError> {
+    let buffer = GpuBuffer::new(
+        gl,
+        WebGl2RenderingContext::ARRAY_BUFFER,
+        WebGl2RenderingContext::STATIC_DRAW,
+    )?;
+
+    #[allow(unsafe_code)]
+    let bytes = unsafe {
+        std::slice::from_raw_parts(
+            FULLSCREEN_TRIANGLE.as_ptr().cast::<u8>(),
+            std::mem::size_of_val(&FULLSCREEN_TRIANGLE),
+        )
+    };
+    buffer.upload_data(gl, bytes);
+
+    VertexArray::new(gl, buffer, 0, &[VertexAttribute::float(0, 2, 0)])
+}
+
+/// Framebuffer-based post-processing stage
+///
+/// When any effect is enabled, [`PostProcessor::begin`] redirects scene
+/// rendering into an offscreen texture and [`PostProcessor::finish`] runs the
+/// effects in order, ping-ponging between two render targets and drawing the
+/// last pass to the canvas. Without enabled effects the scene goes straight to
+/// the canvas.
+pub struct PostProcessor {
+    triangle: VertexArray,
+    targets: Vec<RenderTarget>,
+    effects: Vec<Box<dyn PostEffect>>,
+    capturing: bool,
+    seed: f32,
+}
+
+impl PostProcessor {
+    /// Creates a post processor with an empty effect chain
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if buffer creation fails
+    pub fn new(gl: &Gl) -> Result<Self, RenderError> {
+        let triangle = fullscreen_triangle(gl)?;
+
+        Ok(Self {
+            triangle,
+            targets: Vec::new(),
+            effects: Vec::new(),
+            capturing: false,
+            seed: 0.0,
+        })
+    }
+
+    /// Frees the GPU resources of the processor and its effects; neither may
+    /// be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.triangle.delete(gl);
+        for target in &self.targets {
+            target.delete(gl);
+        }
+        for effect in &self.effects {
+            effect.program().delete(gl);
+        }
+    }
+
+    /// Appends an effect to the end of the chain
+    pub fn push(&mut self, effect: Box<dyn PostEffect>) {
+        self.effects.push(effect);
+    }
+
+    /// Inserts an effect at `index`, clamped to the chain length
+    pub fn insert(&mut self, index: usize, effect: Box<dyn PostEffect>) {
+        self.effects.insert(index.min(self.effects.len()), effect);
+    }
+
+    /// Removes and returns the effect at `index`
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn PostEffect>> {
+        (index < self.effects.len()).then(|| self.effects.remove(index))
+    }
+
+    /// Returns the seed of noise effects
+    #[must_use]
+    pub const fn seed(&self) -> f32 {
+        self.seed
+    }
+
+    /// Sets the seed of noise effects such as grain
+    ///
+    /// Noise depends on the seed and the frame time only, so frames drawn
+    /// at the same time with the same seed are identical.
+    pub const fn set_seed(&mut self, seed: f32) {
+        self.seed = seed;
+    }
+
+    /// Removes all effects
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Returns the effect chain in draw order
+    #[must_use]
+    pub fn effects(&self) -> &[Box<dyn PostEffect>] {
+        &self.effects
+    }
+
+    /// Returns true if any effect is enabled
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.effects.iter().any(|effect| effect.is_enabled())
+    }
+
+    /// Returns true if any enabled effect is animated
+    #[must_use]
+    pub fn is_animated(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.is_enabled() && effect.is_animated())
+    }
+
+    /// Prepares for scene rendering
+    ///
+    /// Binds the offscreen scene target if any effect is enabled, and the
+    /// canvas otherwise. Targets are (re)allocated to the drawing buffer size.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if a render target can't be allocated; the
+    /// canvas is bound in that case so the scene still renders unprocessed
+    pub fn begin(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let enabled = self
+            .effects
+            .iter()
+            .filter(|effect| effect.is_enabled())
+            .count();
+
+        self.capturing = false;
+        ctx.bind_canvas();
+        if enabled == 0 {
+            return Ok(());
+        }
+
+        let gl = ctx.gl();
+        let needed = enabled.min(2);
+        for target in &mut self.targets {
+            target.resize(gl, ctx.width(), ctx.height())?;
+        }
+        while self.targets.len() < needed {
+            // The scene target mirrors the canvas stencil buffer for stencil
+            // fills
+            let target = if self.targets.is_empty() && ctx.has_stencil() {
+                RenderTarget::with_stencil(gl, ctx.width(), ctx.height())?
+            } else {
+                RenderTarget::new(gl, ctx.width(), ctx.height())?
+            };
+            self.targets.push(target);
+        }
+
+        self.targets[0].bind(gl);
+        self.capturing = true;
+        Ok(())
+    }
+
+    /// Returns true if [`PostProcessor::begin`] bound the offscreen scene
+    /// target
+    ///
+    /// The scene target keeps its contents between frames, so it can be
+    /// partially redrawn.
+    #[must_use]
+    pub const fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Rebinds the scene destination chosen by [`PostProcessor::begin`]
+    ///
+    /// Passes that render into their own targets mid-frame call this to return
+    /// to the scene.
+    pub fn bind_scene(&self, ctx: &RenderContext) {
+        match self.targets.first().filter(|_| self.capturing) {
+            Some(target) => target.bind(ctx.gl()),
+            None => ctx.bind_canvas(),
+        }
+    }
+
+    /// Runs the enabled effects over the scene and draws the result to the
+    /// canvas
+    ///
+    /// Does nothing if [`PostProcessor::begin`] did not bind the scene target.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn finish(&mut self, ctx: &RenderContext, time: f32) {
+        if !std::mem::take(&mut self.capturing) {
+            return;
+        }
+
+        let enabled = self
+            .effects
+            .iter()
+            .filter(|effect| effect.is_enabled())
+            .count();
+
+        if enabled == 0 || self.targets.len() < enabled.min(2) {
+            ctx.bind_canvas();
+            return;
+        }
+
+        let gl = ctx.gl();
+        let frame = PostFrame {
+            time,
+            width: ctx.width(),
+            height: ctx.height(),
+            pixel_ratio: ctx.pixel_ratio() as f32,
+            seed: self.seed,
+        };
+
+        // Each pass replaces its destination rather than blending into it
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        self.triangle.bind(gl);
+
+        for (index, effect) in self
+            .effects
+            .iter()
+            .filter(|effect| effect.is_enabled())
+            .enumerate()
+        {
+            if index + 1 == enabled {
+                ctx.bind_canvas();
+            } else {
+                self.targets[(index + 1) % 2].bind(gl);
+            }
+
+            gl.bind_texture(
+                WebGl2RenderingContext::TEXTURE_2D,
+                Some(self.targets[index % 2].texture()),
+            );
+            effect.program().use_with_camera(gl, &ctx.camera());
+            effect.apply_uniforms(gl, &frame);
+            gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        }
+
+        self.triangle.unbind(gl);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        gl.enable(WebGl2RenderingContext::BLEND);
+    }
+}
@@ -1,10 +1,144 @@
 use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlUniformLocation};
 
-use crate::error::RenderError;
+use crate::{
+    backend::ShaderSources,
+    camera::{CameraLocations, CameraUniforms},
+    error::RenderError,
+    gl::Gl,
+    log, shaders,
+};
+
+/// `COMPLETION_STATUS_KHR` of `KHR_parallel_shader_compile`, true once a
+/// program's compile and link status can be read without blocking
+const COMPLETION_STATUS_KHR: u32 = 0x91B1;
+
+/// Programs of the built-in renderers and post effects, for
+/// [`ShaderProgram::precompile`]
+///
+/// Custom fragment shaders aren't known up front and compile at first use.
+pub const BUILTIN_PROGRAMS: &[ShaderSources<'static>] = &[
+    builtin(
+        (shaders::LINE_VERTEX, shaders::LINE_FRAGMENT),
+        (shaders::LINE_VERTEX_ES100, shaders::LINE_FRAGMENT_ES100),
+        shaders::LINE_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::LINE_BATCH_VERTEX, shaders::LINE_BATCH_FRAGMENT),
+        (
+            shaders::LINE_BATCH_VERTEX_ES100,
+            shaders::LINE_BATCH_FRAGMENT_ES100,
+        ),
+        shaders::LINE_BATCH_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::WORLD_VERTEX, shaders::WORLD_FRAGMENT),
+        (shaders::WORLD_VERTEX_ES100, shaders::WORLD_FRAGMENT_ES100),
+        shaders::WORLD_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::MARKER_VERTEX, shaders::MARKER_FRAGMENT),
+        (shaders::MARKER_VERTEX_ES100, shaders::MARKER_FRAGMENT_ES100),
+        shaders::MARKER_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::MARKER_VERTEX, shaders::MARKER_RING_FRAGMENT),
+        (
+            shaders::MARKER_VERTEX_ES100,
+            shaders::MARKER_RING_FRAGMENT_ES100,
+        ),
+        shaders::MARKER_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::RIPPLE_VERTEX, shaders::RIPPLE_FRAGMENT),
+        (shaders::RIPPLE_VERTEX_ES100, shaders::RIPPLE_FRAGMENT_ES100),
+        shaders::MARKER_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::TEXT_VERTEX, shaders::TEXT_FRAGMENT),
+        (shaders::TEXT_VERTEX_ES100, shaders::TEXT_FRAGMENT_ES100),
+        shaders::TEXT_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::ARC_VERTEX, shaders::ARC_FRAGMENT),
+        (shaders::ARC_VERTEX_ES100, shaders::ARC_FRAGMENT_ES100),
+        shaders::ARC_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::TILE_VERTEX, shaders::TILE_FRAGMENT),
+        (shaders::TILE_VERTEX_ES100, shaders::TILE_FRAGMENT_ES100),
+        shaders::TILE_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::OVERLAY_VERTEX, shaders::TILE_FRAGMENT),
+        (shaders::OVERLAY_VERTEX_ES100, shaders::TILE_FRAGMENT_ES100),
+        shaders::OVERLAY_ATTRIBUTES,
+    ),
+    builtin(
+        (shaders::HEATMAP_VERTEX, shaders::HEATMAP_FRAGMENT),
+        (
+            shaders::HEATMAP_VERTEX_ES100,
+            shaders::HEATMAP_FRAGMENT_ES100,
+        ),
+        shaders::HEATMAP_ATTRIBUTES,
+    ),
+    effect(
+        shaders::HEATMAP_COLORIZE_FRAGMENT,
+        shaders::HEATMAP_COLORIZE_FRAGMENT_ES100,
+    ),
+    effect(
+        shaders::BLOOM_BRIGHT_FRAGMENT,
+        shaders::BLOOM_BRIGHT_FRAGMENT_ES100,
+    ),
+    effect(
+        shaders::BLOOM_BLUR_FRAGMENT,
+        shaders::BLOOM_BLUR_FRAGMENT_ES100,
+    ),
+    effect(
+        shaders::BLOOM_COMPOSITE_FRAGMENT,
+        shaders::BLOOM_COMPOSITE_FRAGMENT_ES100,
+    ),
+    effect(
+        shaders::COLOR_GRADE_FRAGMENT,
+        shaders::COLOR_GRADE_FRAGMENT_ES100,
+    ),
+    effect(shaders::VIGNETTE_FRAGMENT, shaders::VIGNETTE_FRAGMENT_ES100),
+    effect(shaders::GRAIN_FRAGMENT, shaders::GRAIN_FRAGMENT_ES100),
+];
+
+/// Returns the GLSL sources of a built-in program
+const fn builtin(
+    (glsl_vertex, glsl_fragment): (&'static str, &'static str),
+    glsl100: (&'static str, &'static str),
+    attributes: &'static [&'static str],
+) -> ShaderSources<'static> {
+    ShaderSources {
+        glsl_vertex,
+        glsl_fragment,
+        glsl100: Some(glsl100),
+        attributes,
+        wgsl: None,
+    }
+}
+
+/// Returns the GLSL sources of a post effect, see
+/// [`crate::post_process::effect_program`]
+const fn effect(fragment: &'static str, fragment_es100: &'static str) -> ShaderSources<'static> {
+    builtin(
+        (shaders::POST_VERTEX, fragment),
+        (shaders::POST_VERTEX_ES100, fragment_es100),
+        shaders::POST_ATTRIBUTES,
+    )
+}
 
 /// Compiled and linked GLSL shader program
+///
+/// Programs may read the per-frame [`CameraUniforms`]: WebGL2 shaders
+/// through the [`crate::camera::CAMERA_BLOCK`] uniform block, which is bound
+/// on link, WebGL1 shaders through plain uniforms of the same names, which
+/// [`ShaderProgram::use_with_camera`] sets.
 pub struct ShaderProgram {
     program: WebGlProgram,
+    camera: CameraLocations,
 }
 
 impl ShaderProgram {
@@ -13,30 +147,178 @@ impl ShaderProgram {
     /// # Errors
     ///
     /// Returns [`RenderError`] if shader compilation or program linking fails
-    pub fn new(
-        gl: &WebGl2RenderingContext,
+    pub fn new(gl: &Gl, vertex_source: &str, fragment_source: &str) -> Result<Self, RenderError> {
+        Self::with_attributes(gl, vertex_source, fragment_source, &[])
+    }
+
+    /// Creates a new shader program, binding each of `attributes` to the
+    /// location of its index before linking
+    ///
+    /// GLSL ES 1.00 has no `layout(location)` qualifier, so WebGL1 shader
+    /// variants rely on this to keep the locations vertex arrays expect.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or program linking fails
+    pub fn with_attributes(
+        gl: &Gl,
         vertex_source: &str,
         fragment_source: &str,
+        attributes: &[&str],
     ) -> Result<Self, RenderError> {
-        let vertex_shader =
-            Self::compile_shader(gl, WebGl2RenderingContext::VERTEX_SHADER, vertex_source)?;
+        Self::with_defines(gl, vertex_source, fragment_source, attributes, &[])
+    }
 
-        let fragment_shader =
-            Self::compile_shader(gl, WebGl2RenderingContext::FRAGMENT_SHADER, fragment_source)?;
+    /// Creates a new shader program like [`ShaderProgram::with_attributes`],
+    /// defining each of `defines` in both shaders
+    ///
+    /// Sources go through [`shaders::preprocess`], so they may include shared
+    /// chunks; every constructor does this. A program started by
+    /// [`ShaderProgram::precompile`] with the same inputs is taken over
+    /// instead of compiling again, blocking only until it's done.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if an include is unknown, or shader compilation
+    /// or program linking fails
+    pub fn with_defines(
+        gl: &Gl,
+        vertex_source: &str,
+        fragment_source: &str,
+        attributes: &[&str],
+        defines: &[(&str, &str)],
+    ) -> Result<Self, RenderError> {
+        let precompiled = {
+            let mut precompiled = gl.precompiled().borrow_mut();
+            precompiled
+                .iter()
+                .position(|pending| {
+                    pending
+                        .key
+                        .matches(vertex_source, fragment_source, attributes, defines)
+                })
+                .map(|position| precompiled.swap_remove(position))
+        };
+        let pending = match precompiled {
+            Some(pending) => pending,
+            None => Self::compile(gl, vertex_source, fragment_source, attributes, defines)?,
+        };
 
-        let program = Self::link_program(gl, &vertex_shader, &fragment_shader)?;
+        pending.finish(gl)
+    }
+
+    /// Starts compiling `programs` without waiting for them, when the
+    /// browser exposes `KHR_parallel_shader_compile`
+    ///
+    /// The driver compiles them in the background; constructors given the
+    /// same sources take them over, see [`ShaderProgram::with_defines`], and
+    /// [`ShaderProgram::compiling`] tells how many are still busy. Without
+    /// the extension, checking a program blocks until it's compiled, so
+    /// nothing is started and programs compile at first use as before.
+    /// Programs without sources for the context's GLSL version are skipped.
+    pub fn precompile(gl: &Gl, programs: &[ShaderSources<'_>]) {
+        if !gl.supports_parallel_compile() {
+            return;
+        }
+
+        for sources in programs {
+            let (vertex, fragment) = if gl.is_webgl2() {
+                (sources.glsl_vertex, sources.glsl_fragment)
+            } else if let Some(sources) = sources.glsl100 {
+                sources
+            } else {
+                continue;
+            };
+            match Self::compile(gl, vertex, fragment, sources.attributes, &[]) {
+                Ok(pending) => gl.precompiled().borrow_mut().push(pending),
+                Err(e) => log::warn(
+                    "shader",
+                    "Program not precompiled",
+                    &[("error", e.to_string().into())],
+                ),
+            }
+        }
+    }
+
+    /// Returns how many programs started by [`ShaderProgram::precompile`]
+    /// are still compiling, without blocking
+    #[must_use]
+    pub fn compiling(gl: &Gl) -> usize {
+        gl.precompiled()
+            .borrow()
+            .iter()
+            .filter(|pending| !pending.is_ready(gl))
+            .count()
+    }
+
+    /// Deletes the programs started by [`ShaderProgram::precompile`] that
+    /// nothing took over
+    pub fn delete_precompiled(gl: &Gl) {
+        for pending in gl.precompiled().take() {
+            pending.delete(gl);
+        }
+    }
+
+    /// Compiles and links a program without checking the result, which
+    /// would wait for the driver
+    fn compile(
+        gl: &Gl,
+        vertex_source: &str,
+        fragment_source: &str,
+        attributes: &[&str],
+        defines: &[(&str, &str)],
+    ) -> Result<PendingProgram, RenderError> {
+        let key = ProgramKey::new(vertex_source, fragment_source, attributes, defines);
+        let vertex_source = shaders::preprocess(vertex_source, defines)?;
+        let fragment_source = shaders::preprocess(fragment_source, defines)?;
+
+        let vertex_shader =
+            Self::compile_shader(gl, WebGl2RenderingContext::VERTEX_SHADER, &vertex_source)?;
+        let fragment_shader = Self::compile_shader(
+            gl,
+            WebGl2RenderingContext::FRAGMENT_SHADER,
+            &fragment_source,
+        )?;
+
+        let program = gl
+            .create_program()
+            .ok_or_else(|| RenderError::ProgramLinkingFailed {
+                log: "Failed to create program".to_string(),
+            })?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        for (location, name) in (0..).zip(attributes) {
+            gl.bind_attrib_location(&program, location, name);
+        }
+        gl.link_program(&program);
 
-        gl.delete_shader(Some(&vertex_shader));
-        gl.delete_shader(Some(&fragment_shader));
+        Ok(PendingProgram {
+            program,
+            vertex_shader,
+            fragment_shader,
+            key,
+        })
+    }
 
-        Ok(Self { program })
+    /// Deletes the program; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        gl.delete_program(Some(&self.program));
     }
 
     /// Activates this shader program for rendering
-    pub fn use_program(&self, gl: &WebGl2RenderingContext) {
+    pub fn use_program(&self, gl: &Gl) {
         gl.use_program(Some(&self.program));
     }
 
+    /// Activates this shader program and provides it with `camera`
+    ///
+    /// On WebGL2 the camera comes from the uniform buffer bound for the
+    /// frame, so this is the same as [`ShaderProgram::use_program`].
+    pub fn use_with_camera(&self, gl: &Gl, camera: &CameraUniforms) {
+        self.use_program(gl);
+        self.camera.apply(gl, camera);
+    }
+
     /// Gets a uniform location by name
     ///
     /// # Errors
@@ -44,7 +326,7 @@ impl ShaderProgram {
     /// Returns [`RenderError::UniformLocationNotFound`] if the uniform doesn't exist
     pub fn get_uniform_location(
         &self,
-        gl: &WebGl2RenderingContext,
+        gl: &Gl,
         name: &str,
     ) -> Result<WebGlUniformLocation, RenderError> {
         gl.get_uniform_location(&self.program, name).ok_or_else(|| {
@@ -56,12 +338,12 @@ impl ShaderProgram {
 
     /// Gets an attribute location by name
     #[must_use]
-    pub fn get_attrib_location(&self, gl: &WebGl2RenderingContext, name: &str) -> i32 {
+    pub fn get_attrib_location(&self, gl: &Gl, name: &str) -> i32 {
         gl.get_attrib_location(&self.program, name)
     }
 
     fn compile_shader(
-        gl: &WebGl2RenderingContext,
+        gl: &Gl,
         shader_type: u32,
         source: &str,
     ) -> Result<WebGlShader, RenderError> {
@@ -74,51 +356,162 @@ impl ShaderProgram {
 
         gl.shader_source(&shader, source);
         gl.compile_shader(&shader);
+        Ok(shader)
+    }
+}
 
-        if gl
-            .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            Ok(shader)
-        } else {
-            let log = gl
-                .get_shader_info_log(&shader)
-                .unwrap_or_else(|| "Unknown error".to_string());
-            Err(RenderError::ShaderCompilationFailed {
-                shader_type: shader_type_name(shader_type),
-                log,
-            })
+/// Inputs a program was compiled from, matching precompiled programs to
+/// the constructors that need them
+struct ProgramKey {
+    vertex_source: String,
+    fragment_source: String,
+    attributes: Vec<String>,
+    defines: Vec<(String, String)>,
+}
+
+impl ProgramKey {
+    fn new(
+        vertex_source: &str,
+        fragment_source: &str,
+        attributes: &[&str],
+        defines: &[(&str, &str)],
+    ) -> Self {
+        Self {
+            vertex_source: vertex_source.to_string(),
+            fragment_source: fragment_source.to_string(),
+            attributes: attributes.iter().map(ToString::to_string).collect(),
+            defines: defines
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
         }
     }
 
-    fn link_program(
-        gl: &WebGl2RenderingContext,
-        vertex_shader: &WebGlShader,
-        fragment_shader: &WebGlShader,
-    ) -> Result<WebGlProgram, RenderError> {
-        let program = gl
-            .create_program()
-            .ok_or_else(|| RenderError::ProgramLinkingFailed {
-                log: "Failed to create program".to_string(),
-            })?;
+    fn matches(
+        &self,
+        vertex_source: &str,
+        fragment_source: &str,
+        attributes: &[&str],
+        defines: &[(&str, &str)],
+    ) -> bool {
+        self.vertex_source == vertex_source
+            && self.fragment_source == fragment_source
+            && self.attributes.iter().eq(attributes)
+            && self
+                .defines
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .eq(defines.iter().copied())
+    }
+}
 
-        gl.attach_shader(&program, vertex_shader);
-        gl.attach_shader(&program, fragment_shader);
-        gl.link_program(&program);
+/// Program handed to the driver whose compile and link status hasn't been
+/// checked yet
+///
+/// Checking blocks until the driver is done; with
+/// `KHR_parallel_shader_compile`, [`PendingProgram::is_ready`] tells without
+/// blocking whether it would.
+pub(crate) struct PendingProgram {
+    program: WebGlProgram,
+    vertex_shader: WebGlShader,
+    fragment_shader: WebGlShader,
+    key: ProgramKey,
+}
+
+impl PendingProgram {
+    /// Returns true if checking the program won't block, always without
+    /// `KHR_parallel_shader_compile`, which can't tell
+    fn is_ready(&self, gl: &Gl) -> bool {
+        !gl.supports_parallel_compile()
+            || gl
+                .get_program_parameter(&self.program, COMPLETION_STATUS_KHR)
+                .as_bool()
+                .unwrap_or(true)
+    }
+
+    /// Checks the compile and link status, waiting for the driver if it's
+    /// still busy
+    fn finish(self, gl: &Gl) -> Result<ShaderProgram, RenderError> {
+        let linked = check_shader(
+            gl,
+            &self.vertex_shader,
+            WebGl2RenderingContext::VERTEX_SHADER,
+        )
+        .and_then(|()| {
+            check_shader(
+                gl,
+                &self.fragment_shader,
+                WebGl2RenderingContext::FRAGMENT_SHADER,
+            )
+        })
+        .and_then(|()| check_program(gl, &self.program));
+        gl.delete_shader(Some(&self.vertex_shader));
+        gl.delete_shader(Some(&self.fragment_shader));
+        if let Err(e) = linked {
+            gl.delete_program(Some(&self.program));
+            return Err(e);
+        }
+
+        let camera = CameraLocations::new(gl, &self.program);
+        Ok(ShaderProgram {
+            program: self.program,
+            camera,
+        })
+    }
 
-        if gl
-            .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-            .as_bool()
-            .unwrap_or(false)
+    /// Frees the program and its shaders
+    pub(crate) fn delete(self, gl: &Gl) {
+        gl.delete_shader(Some(&self.vertex_shader));
+        gl.delete_shader(Some(&self.fragment_shader));
+        gl.delete_program(Some(&self.program));
+    }
+}
+
+/// Returns the compile error of `shader`, logging its warnings
+fn check_shader(gl: &Gl, shader: &WebGlShader, shader_type: u32) -> Result<(), RenderError> {
+    if gl
+        .get_shader_parameter(shader, WebGl2RenderingContext::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        if let Some(warnings) = gl
+            .get_shader_info_log(shader)
+            .filter(|log| !log.trim().is_empty())
         {
-            Ok(program)
-        } else {
-            let log = gl
-                .get_program_info_log(&program)
-                .unwrap_or_else(|| "Unknown error".to_string());
-            Err(RenderError::ProgramLinkingFailed { log })
+            log::warn(
+                "shader",
+                "Shader compiled with warnings",
+                &[
+                    ("shaderType", shader_type_name(shader_type).into()),
+                    ("log", warnings.into()),
+                ],
+            );
         }
+        Ok(())
+    } else {
+        let log = gl
+            .get_shader_info_log(shader)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        Err(RenderError::ShaderCompilationFailed {
+            shader_type: shader_type_name(shader_type),
+            log,
+        })
+    }
+}
+
+/// Returns the link error of `program`
+fn check_program(gl: &Gl, program: &WebGlProgram) -> Result<(), RenderError> {
+    if gl
+        .get_program_parameter(program, WebGl2RenderingContext::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(())
+    } else {
+        let log = gl
+            .get_program_info_log(program)
+            .unwrap_or_else(|| "Unknown error".to_string());
+        Err(RenderError::ProgramLinkingFailed { log })
     }
 }
 
@@ -0,0 +1,200 @@
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer, WebGlRenderbuffer, WebGlTexture};
+
+use crate::{error::RenderError, gl::Gl, texture::GpuTexture};
+
+/// Offscreen framebuffer with an RGBA color texture and an optional stencil
+/// buffer
+///
+/// The texture uses linear filtering and edge clamping without mipmaps, so any
+/// size works on WebGL1 as well.
+pub struct RenderTarget {
+    framebuffer: WebGlFramebuffer,
+    texture: GpuTexture,
+    stencil: Option<WebGlRenderbuffer>,
+    half_float: bool,
+}
+
+impl RenderTarget {
+    /// Creates a render target of the given size in pixels
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the texture or framebuffer can't be created,
+    /// and [`RenderError::FramebufferIncomplete`] if the browser can't render
+    /// into it
+    pub fn new(gl: &Gl, width: u32, height: u32) -> Result<Self, RenderError> {
+        Self::create(gl, width, height, false, false)
+    }
+
+    /// Creates a render target with an 8-bit stencil buffer, for passes that
+    /// use the stencil test
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the texture, stencil buffer or framebuffer
+    /// can't be created, and [`RenderError::FramebufferIncomplete`] if the
+    /// browser can't render into it
+    pub fn with_stencil(gl: &Gl, width: u32, height: u32) -> Result<Self, RenderError> {
+        Self::create(gl, width, height, true, false)
+    }
+
+    /// Creates a render target with a 16-bit float color texture, for passes
+    /// accumulating values finer than 8 bits allow or beyond 1
+    ///
+    /// The texture uses nearest filtering, which WebGL1 supports for half
+    /// floats without a further extension. Returns `None` if the browser
+    /// can't render into half float textures.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if the texture or framebuffer can't be created
+    pub fn half_float(gl: &Gl, width: u32, height: u32) -> Result<Option<Self>, RenderError> {
+        let supported = if gl.is_webgl2() {
+            gl.enable_extension("EXT_color_buffer_float")
+        } else {
+            // Some browsers render to half floats without advertising
+            // `EXT_color_buffer_half_float`; completeness decides
+            gl.enable_extension("EXT_color_buffer_half_float");
+            gl.enable_extension("OES_texture_half_float")
+        };
+        if !supported {
+            return Ok(None);
+        }
+
+        match Self::create(gl, width, height, false, true) {
+            Err(RenderError::FramebufferIncomplete { .. }) => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    fn create(
+        gl: &Gl,
+        width: u32,
+        height: u32,
+        stencil: bool,
+        half_float: bool,
+    ) -> Result<Self, RenderError> {
+        let mut texture = GpuTexture::new(gl)?;
+        if half_float {
+            texture.set_filter(
+                gl,
+                WebGl2RenderingContext::NEAREST,
+                WebGl2RenderingContext::NEAREST,
+            );
+            texture.allocate_half_float(gl, width, height)?;
+        } else {
+            texture.upload_pixels(gl, width, height, None)?;
+        }
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or(RenderError::TextureCreationFailed)?;
+
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(texture.raw()),
+            0,
+        );
+        let stencil = if stencil {
+            let renderbuffer = gl
+                .create_renderbuffer()
+                .ok_or(RenderError::TextureCreationFailed)?;
+            allocate_stencil(gl, &renderbuffer, width, height);
+            gl.framebuffer_renderbuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                WebGl2RenderingContext::STENCIL_ATTACHMENT,
+                WebGl2RenderingContext::RENDERBUFFER,
+                Some(&renderbuffer),
+            );
+            Some(renderbuffer)
+        } else {
+            None
+        };
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            gl.delete_framebuffer(Some(&framebuffer));
+            texture.delete(gl);
+            gl.delete_renderbuffer(stencil.as_ref());
+            return Err(RenderError::FramebufferIncomplete { status });
+        }
+
+        Ok(Self {
+            framebuffer,
+            texture,
+            stencil,
+            half_float,
+        })
+    }
+
+    /// Deletes the framebuffer and frees its texture and stencil buffer; the
+    /// target must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        gl.delete_framebuffer(Some(&self.framebuffer));
+        self.texture.delete(gl);
+        gl.delete_renderbuffer(self.stencil.as_ref());
+    }
+
+    /// Reallocates the color texture and stencil buffer if the size changed
+    ///
+    /// The contents are undefined afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if allocation fails
+    pub fn resize(&mut self, gl: &Gl, width: u32, height: u32) -> Result<(), RenderError> {
+        if width == self.width() && height == self.height() {
+            return Ok(());
+        }
+
+        if self.half_float {
+            self.texture.allocate_half_float(gl, width, height)?;
+        } else {
+            self.texture.upload_pixels(gl, width, height, None)?;
+        }
+        if let Some(stencil) = &self.stencil {
+            allocate_stencil(gl, stencil, width, height);
+        }
+        Ok(())
+    }
+
+    /// Directs rendering into this target and sets the viewport to its size
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn bind(&self, gl: &Gl) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width() as i32, self.height() as i32);
+    }
+
+    /// Returns the color texture
+    #[must_use]
+    pub const fn texture(&self) -> &WebGlTexture {
+        self.texture.raw()
+    }
+
+    /// Returns the width in pixels
+    #[must_use]
+    pub const fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    /// Returns the height in pixels
+    #[must_use]
+    pub const fn height(&self) -> u32 {
+        self.texture.height()
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn allocate_stencil(gl: &Gl, renderbuffer: &WebGlRenderbuffer, width: u32, height: u32) {
+    gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(renderbuffer));
+    gl.renderbuffer_storage(
+        WebGl2RenderingContext::RENDERBUFFER,
+        WebGl2RenderingContext::STENCIL_INDEX8,
+        width as i32,
+        height as i32,
+    );
+    gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+}
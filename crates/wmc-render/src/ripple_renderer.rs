@@ -0,0 +1,109 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+use wmc_core::marker_buffer::MarkerBuffer;
+
+use crate::{
+    context::RenderContext, error::RenderError, gl::Gl, marker_renderer::marker_vertices,
+    program::ShaderProgram, shaders, vertex_array::VertexArray,
+};
+
+/// Instanced WebGL renderer for rings spreading from markers
+///
+/// Ripples share the marker instance layout: each instance's `phase` holds
+/// the camera time its ripple started at, and its `intensity` scales the
+/// ring's opacity. Rings spread and fade out over a shared duration, see
+/// [`shaders::RIPPLE_VERTEX`], so a running ripple costs no uploads.
+pub struct RippleRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    instance_count: i32,
+    u_marker_size: WebGlUniformLocation,
+    u_duration: WebGlUniformLocation,
+    u_scale: WebGlUniformLocation,
+}
+
+impl RippleRenderer {
+    /// Creates a ripple renderer with no instances
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InstancingUnsupported`] on WebGL1 without
+    /// `ANGLE_instanced_arrays`, and [`RenderError`] if shader compilation or
+    /// buffer creation fails
+    pub fn new(ctx: &RenderContext) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::RIPPLE_VERTEX, shaders::RIPPLE_FRAGMENT)
+        } else {
+            (shaders::RIPPLE_VERTEX_ES100, shaders::RIPPLE_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::MARKER_ATTRIBUTES,
+        )?;
+
+        Ok(Self {
+            u_marker_size: program.get_uniform_location(gl, "u_marker_size")?,
+            u_duration: program.get_uniform_location(gl, "u_duration")?,
+            u_scale: program.get_uniform_location(gl, "u_scale")?,
+            program,
+            vertices: marker_vertices(gl)?,
+            instance_count: 0,
+        })
+    }
+
+    /// Returns the number of uploaded ripples
+    #[must_use]
+    pub const fn instance_count(&self) -> i32 {
+        self.instance_count
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+    }
+
+    /// Replaces the instance data with the contents of `ripples`
+    pub fn upload(&mut self, ctx: &RenderContext, ripples: &MarkerBuffer) {
+        let gl = ctx.gl();
+
+        if let Some(buffer) = self.vertices.instance_buffer() {
+            buffer.upload_data(gl, ripples.as_bytes());
+            buffer.unbind(gl);
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        {
+            self.instance_count = ripples.len() as i32;
+        }
+    }
+
+    /// Draws all ripples in a single instanced call
+    ///
+    /// Rings start at `size`, the marker radius in device pixels, and spread
+    /// to `scale` times it over `duration` seconds of camera time.
+    pub fn draw(&self, ctx: &RenderContext, size: f32, duration: f32, scale: f32) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+        gl.uniform1f(Some(&self.u_marker_size), size);
+        gl.uniform1f(Some(&self.u_duration), duration);
+        gl.uniform1f(Some(&self.u_scale), scale);
+
+        self.vertices.bind(gl);
+        gl.draw_arrays_instanced(
+            WebGl2RenderingContext::TRIANGLE_STRIP,
+            0,
+            4,
+            self.instance_count,
+        );
+        self.vertices.unbind(gl);
+    }
+}
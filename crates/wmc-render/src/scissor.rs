@@ -0,0 +1,101 @@
+/// Rectangle of the drawing buffer that rendering is restricted to
+///
+/// Coordinates are device pixels with the origin at the top left, matching
+/// projected map coordinates; [`crate::RenderContext::set_scissor`] flips them
+/// for WebGL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    /// Left edge
+    pub x: u32,
+    /// Top edge
+    pub y: u32,
+    /// Width
+    pub width: u32,
+    /// Height
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// Returns the pixels covering the bounds `[min_x, min_y, max_x, max_y]`,
+    /// clipped to a `width` x `height` buffer
+    ///
+    /// Returns `None` if nothing of the bounds is inside the buffer.
+    ///
+    /// ```
+    /// use wmc_render::ScissorRect;
+    ///
+    /// let rect = ScissorRect::from_bounds([-4.0, 10.5, 20.0, 30.0], 100, 100);
+    /// assert_eq!(
+    ///     rect,
+    ///     Some(ScissorRect {
+    ///         x: 0,
+    ///         y: 10,
+    ///         width: 20,
+    ///         height: 20
+    ///     })
+    /// );
+    /// assert_eq!(ScissorRect::from_bounds([150.0, 0.0, 160.0, 10.0], 100, 100), None);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_bounds(bounds: [f32; 4], width: u32, height: u32) -> Option<Self> {
+        let [min_x, min_y, max_x, max_y] = bounds;
+        if !(min_x < max_x && min_y < max_y) {
+            return None;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let clamp = |value: f32, limit: u32| value.clamp(0.0, limit as f32);
+        let left = clamp(min_x.floor(), width) as u32;
+        let top = clamp(min_y.floor(), height) as u32;
+        let right = clamp(max_x.ceil(), width) as u32;
+        let bottom = clamp(max_y.ceil(), height) as u32;
+
+        (left < right && top < bottom).then_some(Self {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    /// Returns the smallest rectangle containing both rectangles
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Self {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+
+    /// Returns true if the rectangle covers a whole `width` x `height` buffer
+    #[must_use]
+    pub const fn covers(self, width: u32, height: u32) -> bool {
+        self.x == 0 && self.y == 0 && self.width >= width && self.height >= height
+    }
+
+    /// Returns true if the bounds `[min_x, min_y, max_x, max_y]` overlap the
+    /// rectangle
+    ///
+    /// ```
+    /// use wmc_render::ScissorRect;
+    ///
+    /// let rect = ScissorRect { x: 10, y: 10, width: 20, height: 20 };
+    /// assert!(rect.intersects([0.0, 0.0, 12.0, 12.0]));
+    /// assert!(!rect.intersects([31.0, 0.0, 40.0, 40.0]));
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn intersects(self, [min_x, min_y, max_x, max_y]: [f32; 4]) -> bool {
+        let (left, top) = (self.x as f32, self.y as f32);
+        let (right, bottom) = (left + self.width as f32, top + self.height as f32);
+        min_x < right && max_x > left && min_y < bottom && max_y > top
+    }
+}
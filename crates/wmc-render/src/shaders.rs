@@ -1,17 +1,251 @@
+use std::fmt::Write;
+
+use crate::error::RenderError;
+
+/// Declarations of the [`crate::camera::CameraUniforms`]
+///
+/// A `Camera` uniform block under GLSL ES 3.00, plain uniforms of the same
+/// names under GLSL ES 1.00.
+pub const CAMERA_CHUNK: &str = r"#if __VERSION__ >= 300
+layout(std140) uniform Camera {
+    mat4 u_view_projection;
+    vec2 u_resolution;
+    float u_time;
+    float u_pixel_ratio;
+};
+#else
+uniform mat4 u_view_projection;
+uniform vec2 u_resolution;
+uniform float u_time;
+uniform float u_pixel_ratio;
+#endif
+";
+
+/// Conversions to clip space, y down
+///
+/// `pixel_to_clip` maps map pixels through the view, `screen_to_clip`
+/// places screen pixels as they are. Both read the camera, so include
+/// [`CAMERA_CHUNK`] first.
+pub const CLIP_CHUNK: &str = r"vec4 unit_to_clip(vec2 unit) {
+    vec2 clip = unit * 2.0 - 1.0;
+    return vec4(clip.x, -clip.y, 0.0, 1.0);
+}
+
+vec4 pixel_to_clip(vec2 pixel) {
+    return u_view_projection * vec4(pixel, 0.0, 1.0);
+}
+
+vec4 screen_to_clip(vec2 pixel) {
+    return unit_to_clip(pixel / u_resolution);
+}
+";
+
+/// Web Mercator projection of longitude/latitude degrees to the unit square,
+/// north up
+pub const MERCATOR_CHUNK: &str = r"const float PI = 3.141592653589793;
+const float MAX_LAT = 85.05113;
+
+vec2 mercator(vec2 geo) {
+    float lat = radians(clamp(geo.y, -MAX_LAT, MAX_LAT));
+    return vec2(
+        (geo.x + 180.0) / 360.0,
+        (1.0 - log(tan(lat) + 1.0 / cos(lat)) / PI) / 2.0
+    );
+}
+";
+
+/// Distance from a line fragment to the nearest dash
+///
+/// Declares `u_dash`: the dash length, the gap, and 1.0 for round caps, in
+/// pixels. Round caps add half the line width to each dash end; an all-zero
+/// pattern is a solid line. Compare the distance against half the line
+/// width as for solid lines. `dash_pattern_distance` takes the pattern as
+/// an argument instead, for lines whose pattern varies.
+pub const DASH_CHUNK: &str = r"uniform vec3 u_dash;
+
+float dash_pattern_distance(vec3 dash, float across, float along, float half_width) {
+    float extent = dash.x + dash.z * half_width * 2.0;
+    float period = extent + dash.y;
+    if (period <= 0.0) {
+        return abs(across);
+    }
+
+    // Offset from the center of the nearest dash
+    float t = mod(along - extent * 0.5 + period * 0.5, period) - period * 0.5;
+    float outside = max(abs(t) - dash.x * 0.5, 0.0);
+    if (dash.z > 0.5) {
+        return length(vec2(outside, across));
+    }
+    return max(abs(across), outside + half_width);
+}
+
+float dash_distance(float across, float along, float half_width) {
+    return dash_pattern_distance(u_dash, across, along, half_width);
+}
+";
+
+/// Marker size factor over the pulse animation, as
+/// [`crate::marker_renderer::marker_pulse`] computes it on the CPU
+///
+/// ```
+/// use wmc_render::{
+///     marker_renderer::{PULSE_AMPLITUDE, PULSE_BASE},
+///     shaders::PULSE_CHUNK,
+/// };
+///
+/// let formula = format!("{PULSE_BASE:?} + {PULSE_AMPLITUDE:?} * sin(time + phase)");
+/// assert!(PULSE_CHUNK.contains(&formula));
+/// ```
+pub const PULSE_CHUNK: &str = r"float marker_pulse(float time, float phase) {
+    return 0.8 + 0.2 * sin(time + phase);
+}
+";
+
+/// Chunks available to `#include "name"` in shader sources
+pub const CHUNKS: &[(&str, &str)] = &[
+    ("camera", CAMERA_CHUNK),
+    ("clip", CLIP_CHUNK),
+    ("dash", DASH_CHUNK),
+    ("mercator", MERCATOR_CHUNK),
+    ("pulse", PULSE_CHUNK),
+];
+
+/// Expands `#include "name"` lines with [`CHUNKS`] and adds a `#define` for
+/// each of `defines`
+///
+/// Defines go right after the `#version` line, if any, so one source can
+/// serve several variants through `#ifdef`. Chunks are not expanded again,
+/// so they can't include other chunks.
+///
+/// ```
+/// use wmc_render::shaders::preprocess;
+///
+/// let source = "#version 300 es\n#include \"clip\"\nvoid main() {}\n";
+/// let expanded = preprocess(source, &[("SMOOTH", "1")]).unwrap();
+///
+/// assert!(expanded.starts_with("#version 300 es\n#define SMOOTH 1\n"));
+/// assert!(expanded.contains("vec4 unit_to_clip(vec2 unit)"));
+/// assert!(preprocess("#include \"missing\"", &[]).is_err());
+/// ```
+///
+/// # Errors
+///
+/// Returns [`RenderError::UnknownShaderChunk`] if an include names no chunk
+pub fn preprocess(source: &str, defines: &[(&str, &str)]) -> Result<String, RenderError> {
+    let mut out = String::with_capacity(source.len());
+    let mut lines = source.lines().peekable();
+
+    if let Some(version) = lines.next_if(|line| line.trim_start().starts_with("#version")) {
+        out.push_str(version);
+        out.push('\n');
+    }
+    for (name, value) in defines {
+        // Writing to a String cannot fail
+        let _ = writeln!(out, "#define {name} {value}");
+    }
+
+    for line in lines {
+        let Some(name) = line.trim_start().strip_prefix("#include") else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+
+        let name = name.trim().trim_matches('"');
+        let chunk = CHUNKS
+            .iter()
+            .find_map(|&(chunk, source)| (chunk == name).then_some(source))
+            .ok_or_else(|| RenderError::UnknownShaderChunk {
+                name: name.to_string(),
+            })?;
+        out.push_str(chunk);
+    }
+
+    Ok(out)
+}
+
 /// Vertex shader for world map rendering
-pub const WORLD_VERTEX: &str = r"#version 300 es
+pub const WORLD_VERTEX: &str = r#"#version 300 es
 precision highp float;
 
 layout(location = 0) in vec2 a_position;
 
-uniform vec2 u_resolution;
+#include "camera"
+#include "clip"
 
 void main() {
-    vec2 normalized = a_position / u_resolution;
-    vec2 clip = normalized * 2.0 - 1.0;
-    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+    gl_Position = pixel_to_clip(a_position);
 }
-";
+"#;
+
+/// Vertex shader for extruded line rendering
+///
+/// The mesh is widened by `u_aa_width` so the fragment shader has room to
+/// feather the edges without thinning the line. Vertices are quantized as
+/// by [`crate::line_mesh::quantize`]: `u_dequantize` maps positions back to
+/// pixels and extrusions are scaled back up by the miter limit. A mesh
+/// projected for another buffer size is stretched by `u_stretch`, its
+/// extrusions turned to stay normal to the stretched segments.
+pub const LINE_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_extrusion;
+layout(location = 2) in float a_side;
+layout(location = 3) in float a_along;
+
+#include "camera"
+#include "clip"
+
+uniform mat3 u_dequantize;
+uniform vec2 u_stretch;
+uniform float u_line_width;
+uniform float u_aa_width;
+
+out float v_distance;
+out float v_along;
+
+const float MITER_LIMIT = 4.0;
+
+void main() {
+    float outer = u_line_width * 0.5 + u_aa_width;
+    vec2 position = (u_dequantize * vec3(a_position, 1.0)).xy;
+    vec2 extrusion = a_extrusion * MITER_LIMIT;
+    vec2 turned = extrusion / u_stretch;
+    extrusion = turned * (length(extrusion) / max(length(turned), 1e-6));
+    gl_Position = pixel_to_clip(position + extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+}
+"#;
+
+/// Fragment shader for anti-aliased line rendering
+///
+/// Fades alpha over `u_aa_width` pixels around the line edge using the
+/// signed distance to the line core, and around dash ends likewise.
+pub const LINE_FRAGMENT: &str = r#"#version 300 es
+precision highp float;
+
+uniform vec4 u_color;
+uniform float u_line_width;
+uniform float u_aa_width;
+
+#include "dash"
+
+in float v_distance;
+in float v_along;
+
+out vec4 fragColor;
+
+void main() {
+    float half_width = u_line_width * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_distance(v_distance, v_along, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    fragColor = vec4(u_color.rgb, u_color.a * coverage);
+}
+"#;
 
 /// Fragment shader for world map rendering
 pub const WORLD_FRAGMENT: &str = r"#version 300 es
@@ -27,7 +261,7 @@ void main() {
 ";
 
 /// Vertex shader for marker rendering with instancing
-pub const MARKER_VERTEX: &str = r"#version 300 es
+pub const MARKER_VERTEX: &str = r#"#version 300 es
 precision highp float;
 
 layout(location = 0) in vec2 a_position;
@@ -36,8 +270,9 @@ layout(location = 2) in float a_intensity;
 layout(location = 3) in float a_phase;
 layout(location = 4) in vec4 a_color;
 
-uniform mat4 u_projection;
-uniform float u_time;
+#include "camera"
+#include "pulse"
+
 uniform float u_marker_size;
 
 out vec4 v_color;
@@ -45,17 +280,16 @@ out vec2 v_uv;
 out float v_intensity;
 
 void main() {
-    float pulse = 0.8 + 0.2 * sin(u_time + a_phase);
-    float size = u_marker_size * pulse;
+    float size = u_marker_size * marker_pulse(u_time, a_phase);
 
     vec2 pos = a_instance_pos + a_position * size;
-    gl_Position = u_projection * vec4(pos, 0.0, 1.0);
+    gl_Position = u_view_projection * vec4(pos, 0.0, 1.0);
 
     v_color = a_color;
     v_uv = a_position;
     v_intensity = a_intensity;
 }
-";
+"#;
 
 /// Fragment shader for marker rendering with glow effect
 pub const MARKER_FRAGMENT: &str = r"#version 300 es
@@ -78,3 +312,1276 @@ void main() {
     fragColor = vec4(v_color.rgb, v_color.a * alpha);
 }
 ";
+
+/// Attribute names of [`WORLD_VERTEX_ES100`] in location order
+pub const WORLD_ATTRIBUTES: &[&str] = &["a_position"];
+
+/// GLSL ES 1.00 variant of [`WORLD_VERTEX`] for WebGL1
+pub const WORLD_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+
+#include "camera"
+#include "clip"
+
+void main() {
+    gl_Position = pixel_to_clip(a_position);
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`WORLD_FRAGMENT`] for WebGL1
+pub const WORLD_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform vec4 u_color;
+
+void main() {
+    gl_FragColor = u_color;
+}
+";
+
+/// Attribute names of [`LINE_VERTEX_ES100`] in location order
+pub const LINE_ATTRIBUTES: &[&str] = &["a_position", "a_extrusion", "a_side", "a_along"];
+
+/// GLSL ES 1.00 variant of [`LINE_VERTEX`] for WebGL1
+pub const LINE_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_extrusion;
+attribute float a_side;
+attribute float a_along;
+
+#include "camera"
+#include "clip"
+
+uniform mat3 u_dequantize;
+uniform vec2 u_stretch;
+uniform float u_line_width;
+uniform float u_aa_width;
+
+varying float v_distance;
+varying float v_along;
+
+const float MITER_LIMIT = 4.0;
+
+void main() {
+    float outer = u_line_width * 0.5 + u_aa_width;
+    vec2 position = (u_dequantize * vec3(a_position, 1.0)).xy;
+    vec2 extrusion = a_extrusion * MITER_LIMIT;
+    vec2 turned = extrusion / u_stretch;
+    extrusion = turned * (length(extrusion) / max(length(turned), 1e-6));
+    gl_Position = pixel_to_clip(position + extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`LINE_FRAGMENT`] for WebGL1
+///
+/// Uses high precision where available, as distances along long lines lose
+/// the dash pattern at medium precision.
+pub const LINE_FRAGMENT_ES100: &str = r#"#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform vec4 u_color;
+uniform float u_line_width;
+uniform float u_aa_width;
+
+#include "dash"
+
+varying float v_distance;
+varying float v_along;
+
+void main() {
+    float half_width = u_line_width * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_distance(v_distance, v_along, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    gl_FragColor = vec4(u_color.rgb, u_color.a * coverage);
+}
+"#;
+
+/// Attribute names of [`LINE_BATCH_VERTEX_ES100`] in location order
+pub const LINE_BATCH_ATTRIBUTES: &[&str] =
+    &["a_position", "a_extrusion", "a_side", "a_along", "a_member"];
+
+/// Vertex shader for several line meshes drawn in one call
+///
+/// [`LINE_VERTEX`] reading the paint of each vertex's mesh from uniform
+/// arrays indexed by `a_member`: `u_colors` holds the colors and
+/// `u_strokes` the line width, dash length, gap, and 1.0 for round caps.
+/// Arrays hold [`crate::line_batch::MAX_BATCH_MEMBERS`] entries.
+pub const LINE_BATCH_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_extrusion;
+layout(location = 2) in float a_side;
+layout(location = 3) in float a_along;
+layout(location = 4) in float a_member;
+
+#include "camera"
+#include "clip"
+
+uniform mat3 u_dequantize;
+uniform vec2 u_stretch;
+uniform vec4 u_colors[8];
+uniform vec4 u_strokes[8];
+uniform float u_aa_width;
+
+out float v_distance;
+out float v_along;
+out vec4 v_color;
+out vec4 v_stroke;
+
+const float MITER_LIMIT = 4.0;
+
+void main() {
+    int member = int(a_member + 0.5);
+    v_color = u_colors[member];
+    v_stroke = u_strokes[member];
+
+    float outer = v_stroke.x * 0.5 + u_aa_width;
+    vec2 position = (u_dequantize * vec3(a_position, 1.0)).xy;
+    vec2 extrusion = a_extrusion * MITER_LIMIT;
+    vec2 turned = extrusion / u_stretch;
+    extrusion = turned * (length(extrusion) / max(length(turned), 1e-6));
+    gl_Position = pixel_to_clip(position + extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+}
+"#;
+
+/// Fragment shader for [`LINE_BATCH_VERTEX`], feathering like
+/// [`LINE_FRAGMENT`]
+pub const LINE_BATCH_FRAGMENT: &str = r#"#version 300 es
+precision highp float;
+
+uniform float u_aa_width;
+
+#include "dash"
+
+in float v_distance;
+in float v_along;
+in vec4 v_color;
+in vec4 v_stroke;
+
+out vec4 fragColor;
+
+void main() {
+    float half_width = v_stroke.x * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_pattern_distance(v_stroke.yzw, v_distance, v_along, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    fragColor = vec4(v_color.rgb, v_color.a * coverage);
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`LINE_BATCH_VERTEX`] for WebGL1
+pub const LINE_BATCH_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_extrusion;
+attribute float a_side;
+attribute float a_along;
+attribute float a_member;
+
+#include "camera"
+#include "clip"
+
+uniform mat3 u_dequantize;
+uniform vec2 u_stretch;
+uniform vec4 u_colors[8];
+uniform vec4 u_strokes[8];
+uniform float u_aa_width;
+
+varying float v_distance;
+varying float v_along;
+varying vec4 v_color;
+varying vec4 v_stroke;
+
+const float MITER_LIMIT = 4.0;
+
+void main() {
+    int member = int(a_member + 0.5);
+    v_color = u_colors[member];
+    v_stroke = u_strokes[member];
+
+    float outer = v_stroke.x * 0.5 + u_aa_width;
+    vec2 position = (u_dequantize * vec3(a_position, 1.0)).xy;
+    vec2 extrusion = a_extrusion * MITER_LIMIT;
+    vec2 turned = extrusion / u_stretch;
+    extrusion = turned * (length(extrusion) / max(length(turned), 1e-6));
+    gl_Position = pixel_to_clip(position + extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`LINE_BATCH_FRAGMENT`] for WebGL1
+pub const LINE_BATCH_FRAGMENT_ES100: &str = r#"#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform float u_aa_width;
+
+#include "dash"
+
+varying float v_distance;
+varying float v_along;
+varying vec4 v_color;
+varying vec4 v_stroke;
+
+void main() {
+    float half_width = v_stroke.x * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_pattern_distance(v_stroke.yzw, v_distance, v_along, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    gl_FragColor = vec4(v_color.rgb, v_color.a * coverage);
+}
+"#;
+
+/// Attribute names of [`MARKER_VERTEX_ES100`] in location order
+pub const MARKER_ATTRIBUTES: &[&str] = &[
+    "a_position",
+    "a_instance_pos",
+    "a_intensity",
+    "a_phase",
+    "a_color",
+];
+
+/// GLSL ES 1.00 variant of [`MARKER_VERTEX`] for WebGL1
+///
+/// Instanced through `ANGLE_instanced_arrays`.
+pub const MARKER_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_instance_pos;
+attribute float a_intensity;
+attribute float a_phase;
+attribute vec4 a_color;
+
+#include "camera"
+#include "pulse"
+
+uniform float u_marker_size;
+
+varying vec4 v_color;
+varying vec2 v_uv;
+varying float v_intensity;
+
+void main() {
+    float size = u_marker_size * marker_pulse(u_time, a_phase);
+
+    vec2 pos = a_instance_pos + a_position * size;
+    gl_Position = u_view_projection * vec4(pos, 0.0, 1.0);
+
+    v_color = a_color;
+    v_uv = a_position;
+    v_intensity = a_intensity;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`MARKER_FRAGMENT`] for WebGL1
+pub const MARKER_FRAGMENT_ES100: &str = r"precision mediump float;
+
+varying vec4 v_color;
+varying vec2 v_uv;
+varying float v_intensity;
+
+void main() {
+    float dist = length(v_uv);
+
+    if (dist > 1.0) {
+        discard;
+    }
+
+    float alpha = smoothstep(1.0, 0.0, dist) * v_intensity;
+    gl_FragColor = vec4(v_color.rgb, v_color.a * alpha);
+}
+";
+
+/// Fragment shader drawing markers as rings over the outer fifth of their
+/// quad, for the selected marker
+pub const MARKER_RING_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+in vec4 v_color;
+in vec2 v_uv;
+in float v_intensity;
+
+out vec4 fragColor;
+
+void main() {
+    float dist = length(v_uv);
+    float width = fwidth(dist);
+    float alpha = smoothstep(0.8 - width, 0.8, dist) * (1.0 - smoothstep(1.0 - width, 1.0, dist));
+
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    fragColor = vec4(v_color.rgb, v_color.a * alpha * v_intensity);
+}
+";
+
+/// GLSL ES 1.00 variant of [`MARKER_RING_FRAGMENT`] for WebGL1
+///
+/// Edges are smoothed over a fixed share of the quad, as `fwidth` needs
+/// `OES_standard_derivatives` there.
+pub const MARKER_RING_FRAGMENT_ES100: &str = r"precision mediump float;
+
+varying vec4 v_color;
+varying vec2 v_uv;
+varying float v_intensity;
+
+void main() {
+    float dist = length(v_uv);
+    float alpha = smoothstep(0.76, 0.8, dist) * (1.0 - smoothstep(0.96, 1.0, dist));
+
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    gl_FragColor = vec4(v_color.rgb, v_color.a * alpha * v_intensity);
+}
+";
+
+/// Vertex shader spreading a ring from each marker instance, for ripples
+///
+/// Takes the instance attributes of [`MARKER_VERTEX`], `a_phase` holding
+/// the camera time the ripple started at. Each ring grows from
+/// `u_marker_size` to `u_scale` times it over `u_duration` seconds, easing
+/// out, and passes its progress in `0.0..=1.0` as `v_age`.
+pub const RIPPLE_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_instance_pos;
+layout(location = 2) in float a_intensity;
+layout(location = 3) in float a_phase;
+layout(location = 4) in vec4 a_color;
+
+#include "camera"
+
+uniform float u_marker_size;
+uniform float u_duration;
+uniform float u_scale;
+
+out vec4 v_color;
+out vec2 v_uv;
+out float v_age;
+
+void main() {
+    float age = clamp((u_time - a_phase) / u_duration, 0.0, 1.0);
+    float spread = 1.0 - (1.0 - age) * (1.0 - age);
+    float size = u_marker_size * mix(1.0, u_scale, spread);
+
+    vec2 pos = a_instance_pos + a_position * size;
+    gl_Position = u_view_projection * vec4(pos, 0.0, 1.0);
+
+    v_color = vec4(a_color.rgb, a_color.a * a_intensity);
+    v_uv = a_position;
+    v_age = age;
+}
+"#;
+
+/// Fragment shader drawing ripple rings over the outer sixth of their
+/// quad, fading out as they spread
+pub const RIPPLE_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+in vec4 v_color;
+in vec2 v_uv;
+in float v_age;
+
+out vec4 fragColor;
+
+void main() {
+    float dist = length(v_uv);
+    float width = fwidth(dist);
+    float ring = smoothstep(0.85 - width, 0.85, dist) * (1.0 - smoothstep(1.0 - width, 1.0, dist));
+    float fade = (1.0 - v_age) * (1.0 - v_age);
+
+    if (ring * fade <= 0.0) {
+        discard;
+    }
+
+    fragColor = vec4(v_color.rgb, v_color.a * ring * fade);
+}
+";
+
+/// GLSL ES 1.00 variant of [`RIPPLE_VERTEX`] for WebGL1
+///
+/// Instanced through `ANGLE_instanced_arrays`.
+pub const RIPPLE_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_instance_pos;
+attribute float a_intensity;
+attribute float a_phase;
+attribute vec4 a_color;
+
+#include "camera"
+
+uniform float u_marker_size;
+uniform float u_duration;
+uniform float u_scale;
+
+varying vec4 v_color;
+varying vec2 v_uv;
+varying float v_age;
+
+void main() {
+    float age = clamp((u_time - a_phase) / u_duration, 0.0, 1.0);
+    float spread = 1.0 - (1.0 - age) * (1.0 - age);
+    float size = u_marker_size * mix(1.0, u_scale, spread);
+
+    vec2 pos = a_instance_pos + a_position * size;
+    gl_Position = u_view_projection * vec4(pos, 0.0, 1.0);
+
+    v_color = vec4(a_color.rgb, a_color.a * a_intensity);
+    v_uv = a_position;
+    v_age = age;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`RIPPLE_FRAGMENT`] for WebGL1
+///
+/// Edges are smoothed over a fixed share of the quad, as `fwidth` needs
+/// `OES_standard_derivatives` there.
+pub const RIPPLE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+varying vec4 v_color;
+varying vec2 v_uv;
+varying float v_age;
+
+void main() {
+    float dist = length(v_uv);
+    float ring = smoothstep(0.81, 0.85, dist) * (1.0 - smoothstep(0.96, 1.0, dist));
+    float fade = (1.0 - v_age) * (1.0 - v_age);
+
+    if (ring * fade <= 0.0) {
+        discard;
+    }
+
+    gl_FragColor = vec4(v_color.rgb, v_color.a * ring * fade);
+}
+";
+
+/// Attribute names of [`POST_VERTEX`] in location order
+pub const POST_ATTRIBUTES: &[&str] = &["a_position"];
+
+/// Vertex shader for fullscreen post-processing passes
+///
+/// Draws a single triangle covering the viewport and passes texture
+/// coordinates of the scene as `v_uv`.
+pub const POST_VERTEX: &str = r"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+
+out vec2 v_uv;
+
+void main() {
+    v_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+";
+
+/// GLSL ES 1.00 variant of [`POST_VERTEX`] for WebGL1
+pub const POST_VERTEX_ES100: &str = r"precision highp float;
+
+attribute vec2 a_position;
+
+varying vec2 v_uv;
+
+void main() {
+    v_uv = a_position * 0.5 + 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+";
+
+/// Fragment shader for animated film grain
+///
+/// Adds per-cell noise of amplitude `u_intensity`, with cells one CSS pixel
+/// wide, re-rolled 24 times per second. `u_seed` offsets the noise pattern.
+pub const GRAIN_FRAGMENT: &str = r#"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform float u_intensity;
+uniform float u_seed;
+
+#include "camera"
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+float hash(vec2 p) {
+    vec3 p3 = fract(vec3(p.xyx) * 0.1031);
+    p3 += dot(p3, p3.yzx + 33.33);
+    return fract((p3.x + p3.y) * p3.z);
+}
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    vec2 cell = floor(gl_FragCoord.xy / u_pixel_ratio);
+    float frame = mod(floor(u_time * 24.0), 256.0);
+    float noise = (hash(cell + frame * 17.0 + u_seed) - 0.5) * 2.0 * u_intensity;
+    fragColor = vec4(clamp(scene.rgb + noise, 0.0, 1.0), scene.a);
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`GRAIN_FRAGMENT`] for WebGL1
+pub const GRAIN_FRAGMENT_ES100: &str = r#"#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform sampler2D u_scene;
+uniform float u_intensity;
+uniform float u_seed;
+
+#include "camera"
+
+varying vec2 v_uv;
+
+float hash(vec2 p) {
+    vec3 p3 = fract(vec3(p.xyx) * 0.1031);
+    p3 += dot(p3, p3.yzx + 33.33);
+    return fract((p3.x + p3.y) * p3.z);
+}
+
+void main() {
+    vec4 scene = texture2D(u_scene, v_uv);
+    vec2 cell = floor(gl_FragCoord.xy / u_pixel_ratio);
+    float frame = mod(floor(u_time * 24.0), 256.0);
+    float noise = (hash(cell + frame * 17.0 + u_seed) - 0.5) * 2.0 * u_intensity;
+    gl_FragColor = vec4(clamp(scene.rgb + noise, 0.0, 1.0), scene.a);
+}
+"#;
+
+/// Fragment shader extracting the bright parts of a layer for bloom
+///
+/// Keeps colors whose brightest channel exceeds `u_threshold`, fading in over
+/// the range up to full brightness.
+pub const BLOOM_BRIGHT_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform float u_threshold;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    vec4 color = texture(u_scene, v_uv);
+    float brightness = max(color.r, max(color.g, color.b));
+    float weight = smoothstep(u_threshold, 1.0, brightness);
+    fragColor = color * weight;
+}
+";
+
+/// GLSL ES 1.00 variant of [`BLOOM_BRIGHT_FRAGMENT`] for WebGL1
+pub const BLOOM_BRIGHT_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_scene;
+uniform float u_threshold;
+
+varying vec2 v_uv;
+
+void main() {
+    vec4 color = texture2D(u_scene, v_uv);
+    float brightness = max(color.r, max(color.g, color.b));
+    float weight = smoothstep(u_threshold, 1.0, brightness);
+    gl_FragColor = color * weight;
+}
+";
+
+/// Fragment shader for one direction of a separable 9-tap Gaussian blur
+///
+/// `u_step` is the offset between taps in texture coordinates.
+pub const BLOOM_BLUR_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform vec2 u_step;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    vec4 sum = texture(u_scene, v_uv) * 0.2270270270;
+    sum += texture(u_scene, v_uv + u_step) * 0.1945945946;
+    sum += texture(u_scene, v_uv - u_step) * 0.1945945946;
+    sum += texture(u_scene, v_uv + u_step * 2.0) * 0.1216216216;
+    sum += texture(u_scene, v_uv - u_step * 2.0) * 0.1216216216;
+    sum += texture(u_scene, v_uv + u_step * 3.0) * 0.0540540541;
+    sum += texture(u_scene, v_uv - u_step * 3.0) * 0.0540540541;
+    sum += texture(u_scene, v_uv + u_step * 4.0) * 0.0162162162;
+    sum += texture(u_scene, v_uv - u_step * 4.0) * 0.0162162162;
+    fragColor = sum;
+}
+";
+
+/// GLSL ES 1.00 variant of [`BLOOM_BLUR_FRAGMENT`] for WebGL1
+pub const BLOOM_BLUR_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_scene;
+uniform vec2 u_step;
+
+varying vec2 v_uv;
+
+void main() {
+    vec4 sum = texture2D(u_scene, v_uv) * 0.2270270270;
+    sum += texture2D(u_scene, v_uv + u_step) * 0.1945945946;
+    sum += texture2D(u_scene, v_uv - u_step) * 0.1945945946;
+    sum += texture2D(u_scene, v_uv + u_step * 2.0) * 0.1216216216;
+    sum += texture2D(u_scene, v_uv - u_step * 2.0) * 0.1216216216;
+    sum += texture2D(u_scene, v_uv + u_step * 3.0) * 0.0540540541;
+    sum += texture2D(u_scene, v_uv - u_step * 3.0) * 0.0540540541;
+    sum += texture2D(u_scene, v_uv + u_step * 4.0) * 0.0162162162;
+    sum += texture2D(u_scene, v_uv - u_step * 4.0) * 0.0162162162;
+    gl_FragColor = sum;
+}
+";
+
+/// Fragment shader adding a blurred glow to a layer
+///
+/// Both textures hold premultiplied colors; the result is meant to be blended
+/// with `ONE, ONE_MINUS_SRC_ALPHA`.
+pub const BLOOM_COMPOSITE_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform sampler2D u_glow;
+uniform float u_strength;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    vec4 layer = texture(u_scene, v_uv);
+    vec4 glow = texture(u_glow, v_uv) * u_strength;
+    fragColor = vec4(layer.rgb + glow.rgb, clamp(layer.a + glow.a, 0.0, 1.0));
+}
+";
+
+/// GLSL ES 1.00 variant of [`BLOOM_COMPOSITE_FRAGMENT`] for WebGL1
+pub const BLOOM_COMPOSITE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_scene;
+uniform sampler2D u_glow;
+uniform float u_strength;
+
+varying vec2 v_uv;
+
+void main() {
+    vec4 layer = texture2D(u_scene, v_uv);
+    vec4 glow = texture2D(u_glow, v_uv) * u_strength;
+    gl_FragColor = vec4(layer.rgb + glow.rgb, clamp(layer.a + glow.a, 0.0, 1.0));
+}
+";
+
+/// Fragment shader darkening the frame toward its corners
+///
+/// `u_strength` is the darkening at the corners; the center stays untouched.
+pub const VIGNETTE_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform float u_strength;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    float dist = distance(v_uv, vec2(0.5)) * 1.4142136;
+    float shade = 1.0 - u_strength * smoothstep(0.4, 1.0, dist);
+    fragColor = vec4(scene.rgb * shade, scene.a);
+}
+";
+
+/// GLSL ES 1.00 variant of [`VIGNETTE_FRAGMENT`] for WebGL1
+pub const VIGNETTE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_scene;
+uniform float u_strength;
+
+varying vec2 v_uv;
+
+void main() {
+    vec4 scene = texture2D(u_scene, v_uv);
+    float dist = distance(v_uv, vec2(0.5)) * 1.4142136;
+    float shade = 1.0 - u_strength * smoothstep(0.4, 1.0, dist);
+    gl_FragColor = vec4(scene.rgb * shade, scene.a);
+}
+";
+
+/// Fragment shader for exposure, saturation and tint grading
+pub const COLOR_GRADE_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_scene;
+uniform float u_exposure;
+uniform float u_saturation;
+uniform vec4 u_tint;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    vec4 scene = texture(u_scene, v_uv);
+    vec3 color = scene.rgb * exp2(u_exposure);
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luma), color, u_saturation);
+    color = mix(color, color * u_tint.rgb, u_tint.a);
+    fragColor = vec4(clamp(color, 0.0, 1.0), scene.a);
+}
+";
+
+/// GLSL ES 1.00 variant of [`COLOR_GRADE_FRAGMENT`] for WebGL1
+pub const COLOR_GRADE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_scene;
+uniform float u_exposure;
+uniform float u_saturation;
+uniform vec4 u_tint;
+
+varying vec2 v_uv;
+
+void main() {
+    vec4 scene = texture2D(u_scene, v_uv);
+    vec3 color = scene.rgb * exp2(u_exposure);
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luma), color, u_saturation);
+    color = mix(color, color * u_tint.rgb, u_tint.a);
+    gl_FragColor = vec4(clamp(color, 0.0, 1.0), scene.a);
+}
+";
+
+/// Attribute names of [`TILE_VERTEX`] in location order
+pub const TILE_ATTRIBUTES: &[&str] = &["a_position"];
+
+/// Vertex shader stretching a unit quad over a pixel rectangle
+///
+/// `u_rect` holds the left, top, right and bottom edges in pixels.
+pub const TILE_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+
+uniform vec4 u_rect;
+
+#include "camera"
+#include "clip"
+
+out vec2 v_uv;
+
+void main() {
+    gl_Position = pixel_to_clip(mix(u_rect.xy, u_rect.zw, a_position));
+    v_uv = a_position;
+}
+"#;
+
+/// Fragment shader drawing a premultiplied texture at `u_opacity`
+///
+/// The result is meant to be blended with `ONE, ONE_MINUS_SRC_ALPHA`.
+pub const TILE_FRAGMENT: &str = r"#version 300 es
+precision mediump float;
+
+uniform sampler2D u_texture;
+uniform float u_opacity;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    fragColor = texture(u_texture, v_uv) * u_opacity;
+}
+";
+
+/// GLSL ES 1.00 variant of [`TILE_VERTEX`] for WebGL1
+pub const TILE_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+
+uniform vec4 u_rect;
+
+#include "camera"
+#include "clip"
+
+varying vec2 v_uv;
+
+void main() {
+    gl_Position = pixel_to_clip(mix(u_rect.xy, u_rect.zw, a_position));
+    v_uv = a_position;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`TILE_FRAGMENT`] for WebGL1
+pub const TILE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_texture;
+uniform float u_opacity;
+
+varying vec2 v_uv;
+
+void main() {
+    gl_FragColor = texture2D(u_texture, v_uv) * u_opacity;
+}
+";
+
+/// Attribute names of [`OVERLAY_VERTEX`] in location order
+pub const OVERLAY_ATTRIBUTES: &[&str] = &["a_geo"];
+
+/// Vertex shader projecting longitude/latitude vertices with Web Mercator
+///
+/// The world fills the viewport, as with [`wmc_core::projection::MercatorProjection`].
+/// Pair with [`TILE_FRAGMENT`]. `u_bounds` holds the west, south, east and north edges of the image in
+/// degrees; texture coordinates follow longitude and latitude linearly, with
+/// the top of the image at the north edge.
+pub const OVERLAY_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_geo;
+
+uniform vec4 u_bounds;
+
+#include "camera"
+
+out vec2 v_uv;
+
+#include "mercator"
+#include "clip"
+
+void main() {
+    gl_Position = pixel_to_clip(mercator(a_geo) * u_resolution);
+
+    v_uv = vec2(
+        (a_geo.x - u_bounds.x) / (u_bounds.z - u_bounds.x),
+        (u_bounds.w - a_geo.y) / (u_bounds.w - u_bounds.y)
+    );
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`OVERLAY_VERTEX`] for WebGL1
+pub const OVERLAY_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_geo;
+
+uniform vec4 u_bounds;
+
+#include "camera"
+
+varying vec2 v_uv;
+
+#include "mercator"
+#include "clip"
+
+void main() {
+    gl_Position = pixel_to_clip(mercator(a_geo) * u_resolution);
+
+    v_uv = vec2(
+        (a_geo.x - u_bounds.x) / (u_bounds.z - u_bounds.x),
+        (u_bounds.w - a_geo.y) / (u_bounds.w - u_bounds.y)
+    );
+}
+"#;
+
+/// Attribute names of [`ARC_VERTEX_ES100`] in location order
+pub const ARC_ATTRIBUTES: &[&str] = &["a_position", "a_extrusion", "a_side", "a_along", "a_color"];
+
+/// Vertex shader for great-circle arcs
+///
+/// [`LINE_VERTEX`] with a per-vertex color.
+pub const ARC_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_extrusion;
+layout(location = 2) in float a_side;
+layout(location = 3) in float a_along;
+layout(location = 4) in vec4 a_color;
+
+#include "camera"
+#include "clip"
+
+uniform float u_line_width;
+uniform float u_aa_width;
+
+out float v_distance;
+out float v_along;
+out vec4 v_color;
+
+void main() {
+    float outer = u_line_width * 0.5 + u_aa_width;
+    gl_Position = pixel_to_clip(a_position + a_extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+    v_color = a_color;
+}
+"#;
+
+/// Fragment shader for great-circle arcs
+///
+/// [`LINE_FRAGMENT`] with the dash pattern shifted `u_flow` pixels towards
+/// the arc's end.
+pub const ARC_FRAGMENT: &str = r#"#version 300 es
+precision highp float;
+
+uniform float u_line_width;
+uniform float u_aa_width;
+uniform float u_flow;
+
+#include "dash"
+
+in float v_distance;
+in float v_along;
+in vec4 v_color;
+
+out vec4 fragColor;
+
+void main() {
+    float half_width = u_line_width * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_distance(v_distance, v_along - u_flow, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    fragColor = vec4(v_color.rgb, v_color.a * coverage);
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`ARC_VERTEX`] for WebGL1
+pub const ARC_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_extrusion;
+attribute float a_side;
+attribute float a_along;
+attribute vec4 a_color;
+
+#include "camera"
+#include "clip"
+
+uniform float u_line_width;
+uniform float u_aa_width;
+
+varying float v_distance;
+varying float v_along;
+varying vec4 v_color;
+
+void main() {
+    float outer = u_line_width * 0.5 + u_aa_width;
+    gl_Position = pixel_to_clip(a_position + a_extrusion * outer);
+
+    v_distance = a_side * outer;
+    v_along = a_along;
+    v_color = a_color;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`ARC_FRAGMENT`] for WebGL1
+pub const ARC_FRAGMENT_ES100: &str = r#"#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform float u_line_width;
+uniform float u_aa_width;
+uniform float u_flow;
+
+#include "dash"
+
+varying float v_distance;
+varying float v_along;
+varying vec4 v_color;
+
+void main() {
+    float half_width = u_line_width * 0.5;
+    float feather = max(u_aa_width, 0.0001) * 0.5;
+    float dist = dash_distance(v_distance, v_along - u_flow, half_width);
+    float coverage = 1.0 - smoothstep(half_width - feather, half_width + feather, dist);
+    gl_FragColor = vec4(v_color.rgb, v_color.a * coverage);
+}
+"#;
+
+/// Attribute names of [`HEATMAP_VERTEX_ES100`] in location order
+pub const HEATMAP_ATTRIBUTES: &[&str] = &["a_position", "a_point", "a_weight"];
+
+/// Vertex shader splatting heatmap points with instancing
+///
+/// Each instance covers a quad `u_radius` pixels around its point.
+pub const HEATMAP_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_point;
+layout(location = 2) in float a_weight;
+
+#include "camera"
+#include "clip"
+
+uniform float u_radius;
+
+out vec2 v_uv;
+out float v_weight;
+
+void main() {
+    gl_Position = pixel_to_clip(a_point + a_position * u_radius);
+
+    v_uv = a_position;
+    v_weight = a_weight;
+}
+"#;
+
+/// Fragment shader adding a Gaussian kernel of a point's weight
+///
+/// The kernel falls to about 1% at the quad edge, three standard deviations
+/// out; `u_intensity` scales every point. Meant to be blended with `ONE,
+/// ONE` into a density target.
+pub const HEATMAP_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform float u_intensity;
+
+in vec2 v_uv;
+in float v_weight;
+
+out vec4 fragColor;
+
+void main() {
+    float d2 = dot(v_uv, v_uv);
+    if (d2 > 1.0) {
+        discard;
+    }
+
+    fragColor = vec4(exp(-4.5 * d2) * v_weight * u_intensity);
+}
+";
+
+/// GLSL ES 1.00 variant of [`HEATMAP_VERTEX`] for WebGL1
+///
+/// Instanced through `ANGLE_instanced_arrays`.
+pub const HEATMAP_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_point;
+attribute float a_weight;
+
+#include "camera"
+#include "clip"
+
+uniform float u_radius;
+
+varying vec2 v_uv;
+varying float v_weight;
+
+void main() {
+    gl_Position = pixel_to_clip(a_point + a_position * u_radius);
+
+    v_uv = a_position;
+    v_weight = a_weight;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`HEATMAP_FRAGMENT`] for WebGL1
+pub const HEATMAP_FRAGMENT_ES100: &str = r"#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform float u_intensity;
+
+varying vec2 v_uv;
+varying float v_weight;
+
+void main() {
+    float d2 = dot(v_uv, v_uv);
+    if (d2 > 1.0) {
+        discard;
+    }
+
+    gl_FragColor = vec4(exp(-4.5 * d2) * v_weight * u_intensity);
+}
+";
+
+/// Fragment shader coloring accumulated heatmap density
+///
+/// Densities from 0 to 1 index the `u_gradient` lookup texture, a row of
+/// 256 straight-alpha colors. The result is premultiplied and scaled by
+/// `u_opacity`.
+pub const HEATMAP_COLORIZE_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+uniform sampler2D u_density;
+uniform sampler2D u_gradient;
+uniform float u_opacity;
+
+in vec2 v_uv;
+
+out vec4 fragColor;
+
+void main() {
+    float density = clamp(texture(u_density, v_uv).r, 0.0, 1.0);
+    vec4 color = texture(u_gradient, vec2((density * 255.0 + 0.5) / 256.0, 0.5));
+    fragColor = vec4(color.rgb * color.a, color.a) * u_opacity;
+}
+";
+
+/// GLSL ES 1.00 variant of [`HEATMAP_COLORIZE_FRAGMENT`] for WebGL1
+pub const HEATMAP_COLORIZE_FRAGMENT_ES100: &str = r"precision mediump float;
+
+uniform sampler2D u_density;
+uniform sampler2D u_gradient;
+uniform float u_opacity;
+
+varying vec2 v_uv;
+
+void main() {
+    float density = clamp(texture2D(u_density, v_uv).r, 0.0, 1.0);
+    vec4 color = texture2D(u_gradient, vec2((density * 255.0 + 0.5) / 256.0, 0.5));
+    gl_FragColor = vec4(color.rgb * color.a, color.a) * u_opacity;
+}
+";
+
+/// Attribute names of [`TEXT_VERTEX_ES100`] in location order
+pub const TEXT_ATTRIBUTES: &[&str] =
+    &["a_position", "a_uv", "a_color", "a_halo_color", "a_params"];
+
+/// Vertex shader for glyph quads
+///
+/// Glyphs are laid out in screen pixels and don't follow the view.
+/// `a_params` holds the halo width and the anti-aliasing half width, both in
+/// normalized distance field units.
+pub const TEXT_VERTEX: &str = r#"#version 300 es
+precision highp float;
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_uv;
+layout(location = 2) in vec4 a_color;
+layout(location = 3) in vec4 a_halo_color;
+layout(location = 4) in vec2 a_params;
+
+#include "camera"
+#include "clip"
+
+out vec2 v_uv;
+out vec4 v_color;
+out vec4 v_halo_color;
+out vec2 v_params;
+
+void main() {
+    gl_Position = screen_to_clip(a_position);
+
+    v_uv = a_uv;
+    v_color = a_color;
+    v_halo_color = a_halo_color;
+    v_params = a_params;
+}
+"#;
+
+/// Fragment shader drawing glyphs from a signed distance field atlas
+///
+/// The glyph edge sits at 0.75, matching
+/// [`crate::glyph_atlas::SDF_CUTOFF`]; the halo extends the edge outwards.
+/// Output is premultiplied, the fill over its halo.
+pub const TEXT_FRAGMENT: &str = r"#version 300 es
+precision highp float;
+
+const float EDGE = 0.75;
+
+uniform sampler2D u_atlas;
+
+in vec2 v_uv;
+in vec4 v_color;
+in vec4 v_halo_color;
+in vec2 v_params;
+
+out vec4 fragColor;
+
+void main() {
+    float dist = texture(u_atlas, v_uv).a;
+    float gamma = v_params.y;
+
+    float fill = smoothstep(EDGE - gamma, EDGE + gamma, dist);
+    float halo_edge = EDGE - v_params.x;
+    float halo = v_params.x > 0.0 ? smoothstep(halo_edge - gamma, halo_edge + gamma, dist) : 0.0;
+
+    vec4 fill_color = vec4(v_color.rgb * v_color.a, v_color.a) * fill;
+    vec4 halo_color = vec4(v_halo_color.rgb * v_halo_color.a, v_halo_color.a) * halo;
+    fragColor = fill_color + halo_color * (1.0 - fill_color.a);
+}
+";
+
+/// GLSL ES 1.00 variant of [`TEXT_VERTEX`] for WebGL1
+pub const TEXT_VERTEX_ES100: &str = r#"precision highp float;
+
+attribute vec2 a_position;
+attribute vec2 a_uv;
+attribute vec4 a_color;
+attribute vec4 a_halo_color;
+attribute vec2 a_params;
+
+#include "camera"
+#include "clip"
+
+varying vec2 v_uv;
+varying vec4 v_color;
+varying vec4 v_halo_color;
+varying vec2 v_params;
+
+void main() {
+    gl_Position = screen_to_clip(a_position);
+
+    v_uv = a_uv;
+    v_color = a_color;
+    v_halo_color = a_halo_color;
+    v_params = a_params;
+}
+"#;
+
+/// GLSL ES 1.00 variant of [`TEXT_FRAGMENT`] for WebGL1
+pub const TEXT_FRAGMENT_ES100: &str = r"precision mediump float;
+
+const float EDGE = 0.75;
+
+uniform sampler2D u_atlas;
+
+varying vec2 v_uv;
+varying vec4 v_color;
+varying vec4 v_halo_color;
+varying vec2 v_params;
+
+void main() {
+    float dist = texture2D(u_atlas, v_uv).a;
+    float gamma = v_params.y;
+
+    float fill = smoothstep(EDGE - gamma, EDGE + gamma, dist);
+    float halo_edge = EDGE - v_params.x;
+    float halo = v_params.x > 0.0 ? smoothstep(halo_edge - gamma, halo_edge + gamma, dist) : 0.0;
+
+    vec4 fill_color = vec4(v_color.rgb * v_color.a, v_color.a) * fill;
+    vec4 halo_color = vec4(v_halo_color.rgb * v_halo_color.a, v_halo_color.a) * halo;
+    gl_FragColor = fill_color + halo_color * (1.0 - fill_color.a);
+}
+";
@@ -0,0 +1,284 @@
+use crate::error::RenderError;
+
+/// PNG file signature
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest payload of a stored deflate block
+const STORED_BLOCK: usize = 65_535;
+
+/// Largest YIQ color distance, between black and white
+const MAX_DELTA: f32 = 35_215.0;
+
+/// Pixels of a rendered scene compared against a golden image
+///
+/// ```
+/// use wmc_render::snapshot::{SnapshotDiff, decode_png, encode_png};
+///
+/// let pixels = [255, 0, 0, 255, 0, 0, 255, 255];
+/// let png = encode_png(2, 1, &pixels);
+/// assert_eq!(decode_png(&png).unwrap(), (2, 1, pixels.to_vec()));
+///
+/// // Slightly off colors pass, swapped ones don't
+/// let tinted = [250, 4, 0, 255, 0, 0, 255, 255];
+/// assert_eq!(SnapshotDiff::compare(&pixels, &tinted, 0.1).mismatched, 0);
+/// let swapped = [0, 0, 255, 255, 255, 0, 0, 255];
+/// assert_eq!(SnapshotDiff::compare(&pixels, &swapped, 0.1).mismatched, 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    /// Pixels differing by more than the threshold
+    pub mismatched: usize,
+    /// Pixels compared
+    pub total: usize,
+}
+
+impl SnapshotDiff {
+    /// Compares two RGBA8 images of the same size pixel by pixel
+    ///
+    /// Pixels are blended over white and compared by their distance in YIQ
+    /// space, which weighs brightness over hue as the eye does, like
+    /// pixelmatch. `threshold` in `0.0..=1.0` is the share of the largest
+    /// distance a pixel may be off by. Pixels past the end of the shorter
+    /// image all mismatch.
+    #[must_use]
+    pub fn compare(actual: &[u8], golden: &[u8], threshold: f32) -> Self {
+        let limit = threshold * threshold * MAX_DELTA;
+        let (actual, golden) = (actual.as_chunks::<4>().0, golden.as_chunks::<4>().0);
+        let matched = actual
+            .iter()
+            .zip(golden)
+            .filter(|&(a, b)| color_delta(*a, *b) <= limit)
+            .count();
+        let total = actual.len().max(golden.len());
+
+        Self {
+            mismatched: total - matched,
+            total,
+        }
+    }
+
+    /// Returns the share of mismatched pixels in `0.0..=1.0`
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.mismatched as f64 / self.total as f64
+        }
+    }
+}
+
+/// Encodes RGBA8 pixel rows from the top left as a PNG file
+///
+/// The image data is stored uncompressed, which keeps the encoder small
+/// and goldens exact; [`decode_png`] reads it back.
+///
+/// # Panics
+///
+/// Panics if `pixels` holds fewer than `width * height * 4` bytes
+#[must_use]
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let row = width as usize * 4;
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in pixels[..row * height as usize].chunks_exact(row.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    // zlib stream of stored deflate blocks
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        #[allow(clippy::cast_possible_truncation)]
+        let length = block.len() as u16;
+        zlib.push(u8::from(blocks.peek().is_none()));
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8-bit RGBA, deflate, adaptive filtering, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    push_chunk(&mut png, *b"IHDR", &header);
+    push_chunk(&mut png, *b"IDAT", &zlib);
+    push_chunk(&mut png, *b"IEND", &[]);
+    png
+}
+
+/// Decodes a PNG file written by [`encode_png`] into its size and RGBA8
+/// pixel rows from the top left
+///
+/// Any row filters are undone, but only 8-bit RGBA images without
+/// interlacing and with stored deflate blocks are read.
+///
+/// # Errors
+///
+/// Returns [`RenderError::SnapshotDecodeFailed`] if the file is malformed
+/// or uses an unsupported format
+pub fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), RenderError> {
+    let fail = |reason: &str| RenderError::SnapshotDecodeFailed {
+        reason: reason.to_string(),
+    };
+    let rest = bytes
+        .strip_prefix(&SIGNATURE)
+        .ok_or_else(|| fail("not a PNG file"))?;
+
+    let (mut size, mut zlib) = (None, Vec::new());
+    let mut rest = rest;
+    while rest.len() >= 12 {
+        let length = read_u32(rest) as usize;
+        let data = rest
+            .get(8..8 + length)
+            .ok_or_else(|| fail("truncated chunk"))?;
+        match &rest[4..8] {
+            b"IHDR" => {
+                if data.len() != 13 || data[8..] != [8, 6, 0, 0, 0] {
+                    return Err(fail("only 8-bit RGBA without interlacing is supported"));
+                }
+                size = Some((read_u32(data), read_u32(&data[4..])));
+            },
+            b"IDAT" => zlib.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {},
+        }
+        rest = rest.get(12 + length..).unwrap_or_default();
+    }
+    let (width, height) = size.ok_or_else(|| fail("missing IHDR chunk"))?;
+
+    let raw =
+        inflate_stored(&zlib).ok_or_else(|| fail("only stored deflate blocks are supported"))?;
+    let row = width as usize * 4;
+    if raw.len() != (row + 1) * height as usize {
+        return Err(fail("image data doesn't match the size"));
+    }
+
+    let mut pixels = vec![0; row * height as usize];
+    for (y, line) in raw.chunks_exact(row + 1).enumerate() {
+        let (filter, line) = (line[0], &line[1..]);
+        let (above, current) = pixels.split_at_mut(y * row);
+        let above = above
+            .get(above.len().saturating_sub(row)..)
+            .filter(|_| y > 0);
+        let current = &mut current[..row];
+
+        for x in 0..row {
+            let left = if x >= 4 { current[x - 4] } else { 0 };
+            let up = above.map_or(0, |above| above[x]);
+            let up_left = above.filter(|_| x >= 4).map_or(0, |above| above[x - 4]);
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                #[allow(clippy::cast_possible_truncation)]
+                3 => u16::midpoint(u16::from(left), u16::from(up)) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(fail("unknown row filter")),
+            };
+            current[x] = line[x].wrapping_add(predicted);
+        }
+    }
+
+    Ok((width, height, pixels))
+}
+
+/// Returns the squared YIQ distance between two RGBA8 pixels blended over
+/// white
+fn color_delta(actual: [u8; 4], golden: [u8; 4]) -> f32 {
+    let yiq = |pixel: [u8; 4]| {
+        let alpha = f32::from(pixel[3]) / 255.0;
+        let [red, green, blue] =
+            [pixel[0], pixel[1], pixel[2]].map(|c| (f32::from(c) - 255.0).mul_add(alpha, 255.0));
+        [
+            red.mul_add(0.298_895_3, green.mul_add(0.586_622_5, blue * 0.114_482_23)),
+            red.mul_add(0.595_978, green.mul_add(-0.274_176_1, blue * -0.321_801_9)),
+            red.mul_add(
+                0.211_470_17,
+                green.mul_add(-0.522_617_1, blue * 0.311_146_94),
+            ),
+        ]
+    };
+    let [luma, in_phase, quadrature] = {
+        let (actual, golden) = (yiq(actual), yiq(golden));
+        [0, 1, 2].map(|i| actual[i] - golden[i])
+    };
+
+    (0.5053 * luma).mul_add(
+        luma,
+        (0.299 * in_phase).mul_add(in_phase, 0.1957 * quadrature * quadrature),
+    )
+}
+
+/// Concatenates the payloads of a zlib stream of stored deflate blocks
+fn inflate_stored(zlib: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = zlib.get(2..)?;
+    let mut raw = Vec::new();
+    loop {
+        let (&header, after) = rest.split_first()?;
+        if header & 0b110 != 0 {
+            return None;
+        }
+        let length = u16::from_le_bytes([*after.first()?, *after.get(1)?]) as usize;
+        raw.extend_from_slice(after.get(4..4 + length)?);
+        rest = &after[4 + length..];
+        if header & 1 == 1 {
+            return Some(raw);
+        }
+    }
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: [u8; 4], data: &[u8]) {
+    #[allow(clippy::cast_possible_truncation)]
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(&kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+const fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1_u32, 0_u32), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % 65_521;
+        (a, (b + a) % 65_521)
+    });
+    (b << 16) | a
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let (to_left, to_up, to_up_left) = (
+        (estimate - i16::from(left)).abs(),
+        (estimate - i16::from(up)).abs(),
+        (estimate - i16::from(up_left)).abs(),
+    );
+
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Work counted over one frame
+///
+/// Draw calls, vertices and uploads are counted by [`crate::Gl`] as they are
+/// issued; the frame time is filled in by whoever drives the frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    /// Draw calls issued
+    pub draw_calls: u32,
+    /// Vertices processed, counting every instance of instanced draws
+    pub vertices: u64,
+    /// Buffer uploads, whole or partial
+    pub buffer_uploads: u32,
+    /// Bytes uploaded to buffers
+    pub upload_bytes: u64,
+    /// CPU time spent building the frame in milliseconds
+    pub frame_ms: f64,
+    /// Heap allocations made while building the frame, filled in like
+    /// `frame_ms` from [`allocation_count`]; `None` without a
+    /// [`CountingAllocator`]
+    ///
+    /// Drawing an unchanged scene allocates nothing, so anything above zero
+    /// in steady frames is a regression.
+    pub allocations: Option<u64>,
+}
+
+impl RenderStats {
+    /// Counts a draw call of `vertices` vertices
+    #[allow(clippy::cast_sign_loss)]
+    pub(crate) const fn record_draw(&mut self, vertices: i32) {
+        self.draw_calls += 1;
+        if vertices > 0 {
+            self.vertices += vertices as u64;
+        }
+    }
+
+    /// Counts a buffer upload of `bytes` bytes
+    pub(crate) const fn record_upload(&mut self, bytes: usize) {
+        self.buffer_uploads += 1;
+        self.upload_bytes += bytes as u64;
+    }
+}
+
+/// Heap allocations counted by [`CountingAllocator`] since startup
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator wrapper counting allocations and reallocations, read
+/// with [`allocation_count`]
+///
+/// Install it in the final binary, which alone may pick the global
+/// allocator, to fill in [`RenderStats::allocations`]:
+///
+/// ```
+/// use std::alloc::System;
+///
+/// use wmc_render::stats::{CountingAllocator, allocation_count};
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator<System> = CountingAllocator(System);
+///
+/// let before = allocation_count().unwrap();
+/// let boxed = Box::new(1);
+/// assert!(allocation_count().unwrap() > before);
+/// # drop(boxed);
+/// ```
+pub struct CountingAllocator<A>(pub A);
+
+#[allow(unsafe_code)]
+// SAFETY: every call is forwarded unchanged to the wrapped allocator
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { self.0.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { self.0.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { self.0.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) }
+    }
+}
+
+/// Returns the allocations counted so far, `None` unless
+/// [`CountingAllocator`] is the global allocator
+///
+/// Any program allocates before it gets here, so a count of zero means
+/// nothing is counting.
+#[must_use]
+pub fn allocation_count() -> Option<u64> {
+    Some(ALLOCATIONS.load(Ordering::Relaxed)).filter(|&count| count > 0)
+}
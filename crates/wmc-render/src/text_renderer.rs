@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use web_sys::WebGl2RenderingContext;
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    glyph_atlas::{GLYPH_SIZE, GlyphAtlas, SDF_CUTOFF, SDF_RADIUS},
+    program::ShaderProgram,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Bytes per glyph vertex: position, atlas coordinates, fill and halo colors
+/// and halo parameters
+const TEXT_VERTEX_BYTES: usize = 32;
+
+/// Height of the text box above the baseline, in ems
+const ASCENT: f32 = 0.8;
+
+/// Depth of the text box below the baseline, in ems
+const DESCENT: f32 = 0.2;
+
+/// Edge softness of solid boxes in distance field units, small enough that
+/// the fully inside patch draws at full opacity
+const SOLID_GAMMA: f32 = 0.01;
+
+/// Side of a label's box placed at its position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TextAnchor {
+    /// Box centered on the position
+    #[default]
+    Center,
+    /// Left edge at the position, vertically centered
+    Left,
+    /// Right edge at the position, vertically centered
+    Right,
+    /// Top edge at the position, horizontally centered
+    Top,
+    /// Bottom edge at the position, horizontally centered
+    Bottom,
+}
+
+/// Look of a text label
+///
+/// Lengths are in drawing buffer pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// Font size
+    pub size: f32,
+    /// Fill color, straight alpha
+    pub color: [f32; 4],
+    /// Halo color, straight alpha
+    pub halo_color: [f32; 4],
+    /// Halo width around the glyph outlines; 0 draws no halo
+    pub halo_width: f32,
+    /// Side of the box placed at the label position
+    pub anchor: TextAnchor,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 12.0,
+            color: [0.0, 0.0, 0.0, 1.0],
+            halo_color: [1.0, 1.0, 1.0, 1.0],
+            halo_width: 0.0,
+            anchor: TextAnchor::Center,
+        }
+    }
+}
+
+/// Batched single-line text labels drawn from a [`GlyphAtlas`]
+///
+/// Labels are laid out into quads with [`TextRenderer::add`], uploaded on the
+/// next [`TextRenderer::draw`] and drawn in one call until
+/// [`TextRenderer::clear`]. Every label can have its own size, colors and
+/// halo.
+pub struct TextRenderer {
+    program: ShaderProgram,
+    vertices: VertexArray,
+    vertex_count: i32,
+    atlas: GlyphAtlas,
+    batch: Vec<u8>,
+    dirty: bool,
+}
+
+impl TextRenderer {
+    /// Creates a text renderer with an empty batch, rasterizing glyphs in the
+    /// CSS font family `font`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation, buffer or atlas
+    /// creation fails
+    pub fn new(ctx: &RenderContext, font: &str) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::TEXT_VERTEX, shaders::TEXT_FRAGMENT)
+        } else {
+            (shaders::TEXT_VERTEX_ES100, shaders::TEXT_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::TEXT_ATTRIBUTES,
+        )?;
+
+        let vertex_buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::DYNAMIC_DRAW,
+        )?;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let stride = TEXT_VERTEX_BYTES as i32;
+        let vertices = VertexArray::new(
+            gl,
+            vertex_buffer,
+            stride,
+            &[
+                VertexAttribute::float(0, 2, 0),
+                VertexAttribute::float(1, 2, 8),
+                VertexAttribute::unorm8(2, 4, 16),
+                VertexAttribute::unorm8(3, 4, 20),
+                VertexAttribute::float(4, 2, 24),
+            ],
+        )?;
+
+        Ok(Self {
+            program,
+            vertices,
+            vertex_count: 0,
+            atlas: GlyphAtlas::new(gl, font)?,
+            batch: Vec::new(),
+            dirty: false,
+        })
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
+        self.atlas.delete(gl);
+    }
+
+    /// Removes every label
+    pub fn clear(&mut self) {
+        self.batch.clear();
+        self.dirty = true;
+    }
+
+    /// Returns true if no label has been added since the last clear
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+
+    /// Returns the box `text` would cover at `position` as
+    /// `[min_x, min_y, max_x, max_y]`, halo included, without adding it
+    ///
+    /// Useful for collision checks before placing a label.
+    pub fn bounds(&mut self, text: &str, position: [f32; 2], style: &TextStyle) -> [f32; 4] {
+        let width = self.width(text, style);
+        box_bounds(position, width, style)
+    }
+
+    /// Lays out `text` at `position` and adds it to the batch
+    ///
+    /// Returns the box it covers, as [`TextRenderer::bounds`] does.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn add(&mut self, text: &str, position: [f32; 2], style: &TextStyle) -> [f32; 4] {
+        let width = self.width(text, style);
+        let scale = style.size / GLYPH_SIZE;
+        let [mut pen, baseline] = origin(position, width, style);
+
+        // Distance field units per drawing buffer pixel
+        let unit = 1.0 / (SDF_RADIUS * scale);
+        let gamma = 0.5 * unit;
+        let halo = (style.halo_width.max(0.0) * unit).min(1.0 - SDF_CUTOFF - gamma);
+        let color = to_rgba8(style.color);
+        let halo_color = to_rgba8(style.halo_color);
+        let atlas_size = self.atlas.size() as f32;
+
+        for ch in text.chars() {
+            let Some(glyph) = self.atlas.glyph(ch) else {
+                continue;
+            };
+            let [u, v, glyph_width, glyph_height] = glyph.rect.map(|value| value as f32);
+            if glyph_width > 0.0 && glyph_height > 0.0 {
+                let x0 = glyph.offset[0].mul_add(scale, pen);
+                let y0 = glyph.offset[1].mul_add(scale, baseline);
+                let x1 = glyph_width.mul_add(scale, x0);
+                let y1 = glyph_height.mul_add(scale, y0);
+                let (u0, v0) = (u / atlas_size, v / atlas_size);
+                let (u1, v1) = (
+                    (u + glyph_width) / atlas_size,
+                    (v + glyph_height) / atlas_size,
+                );
+
+                for corner in [
+                    [x0, y0, u0, v0],
+                    [x1, y0, u1, v0],
+                    [x0, y1, u0, v1],
+                    [x0, y1, u0, v1],
+                    [x1, y0, u1, v0],
+                    [x1, y1, u1, v1],
+                ] {
+                    for value in corner {
+                        self.batch.extend_from_slice(&value.to_ne_bytes());
+                    }
+                    self.batch.extend_from_slice(&color);
+                    self.batch.extend_from_slice(&halo_color);
+                    for value in [halo, gamma] {
+                        self.batch.extend_from_slice(&value.to_ne_bytes());
+                    }
+                }
+            }
+            pen = glyph.advance.mul_add(scale, pen);
+        }
+
+        self.dirty = true;
+        box_bounds(position, width, style)
+    }
+
+    /// Adds a solid box `[min_x, min_y, max_x, max_y]` in `color`, straight
+    /// alpha, e.g. for a scale bar drawn along with its label
+    ///
+    /// Boxes and labels draw in the order they were added.
+    pub fn add_box(&mut self, [x0, y0, x1, y1]: [f32; 4], color: [f32; 4]) {
+        self.add_solid(
+            &[[x0, y0], [x1, y0], [x0, y1], [x0, y1], [x1, y0], [x1, y1]],
+            color,
+        );
+    }
+
+    /// Adds a solid triangle with `corners` in `color`, straight alpha, e.g.
+    /// for a compass needle
+    ///
+    /// Triangles draw in the order they were added, like boxes.
+    pub fn add_triangle(&mut self, corners: [[f32; 2]; 3], color: [f32; 4]) {
+        self.add_solid(&corners, color);
+    }
+
+    /// Adds triangles with `corners`, three each, covering the atlas' solid
+    /// patch
+    #[allow(clippy::cast_precision_loss)]
+    fn add_solid(&mut self, corners: &[[f32; 2]], color: [f32; 4]) {
+        let atlas_size = self.atlas.size() as f32;
+        let [u, v] = self.atlas.solid().map(|value| value as f32 / atlas_size);
+        let color = to_rgba8(color);
+
+        for [x, y] in corners {
+            for value in [*x, *y, u, v] {
+                self.batch.extend_from_slice(&value.to_ne_bytes());
+            }
+            self.batch.extend_from_slice(&color);
+            self.batch.extend_from_slice(&[0; 4]);
+            for value in [0.0, SOLID_GAMMA] {
+                self.batch.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    /// Returns the advance width of `text` in drawing buffer pixels
+    fn width(&mut self, text: &str, style: &TextStyle) -> f32 {
+        let scale = style.size / GLYPH_SIZE;
+        text.chars()
+            .filter_map(|ch| self.atlas.glyph(ch))
+            .map(|glyph| glyph.advance * scale)
+            .sum()
+    }
+
+    /// Draws every label in one call
+    ///
+    /// Uploads new glyphs and the batch first if they changed. Blends
+    /// premultiplied with the context's blend mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if new glyphs can't be
+    /// uploaded; nothing is drawn then
+    pub fn draw(&mut self, ctx: &RenderContext) -> Result<(), RenderError> {
+        let gl = ctx.gl();
+
+        self.atlas.flush(gl)?;
+        if std::mem::take(&mut self.dirty) {
+            self.vertices.buffer().upload_data(gl, &self.batch);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            {
+                self.vertex_count = (self.batch.len() / TEXT_VERTEX_BYTES) as i32;
+            }
+        }
+        if self.vertex_count == 0 {
+            return Ok(());
+        }
+
+        self.program.use_with_camera(gl, &ctx.camera());
+        self.atlas.texture().bind(gl, 0);
+        ctx.apply_blend(true);
+
+        self.vertices.bind(gl);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count);
+        self.vertices.unbind(gl);
+
+        ctx.apply_blend(false);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        Ok(())
+    }
+}
+
+/// Returns the pen position on the baseline where a line `width` pixels wide
+/// starts
+fn origin([x, y]: [f32; 2], width: f32, style: &TextStyle) -> [f32; 2] {
+    let size = style.size;
+    let middle = size.mul_add(ASCENT - 0.5, y);
+
+    match style.anchor {
+        TextAnchor::Center => [width.mul_add(-0.5, x), middle],
+        TextAnchor::Left => [x, middle],
+        TextAnchor::Right => [x - width, middle],
+        TextAnchor::Top => [width.mul_add(-0.5, x), size.mul_add(ASCENT, y)],
+        TextAnchor::Bottom => [width.mul_add(-0.5, x), size.mul_add(-DESCENT, y)],
+    }
+}
+
+/// Returns the box of a line `width` pixels wide, halo included
+fn box_bounds(position: [f32; 2], width: f32, style: &TextStyle) -> [f32; 4] {
+    let [x, baseline] = origin(position, width, style);
+    let halo = style.halo_width.max(0.0);
+
+    [
+        x - halo,
+        style.size.mul_add(-ASCENT, baseline) - halo,
+        x + width + halo,
+        style.size.mul_add(DESCENT, baseline) + halo,
+    ]
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
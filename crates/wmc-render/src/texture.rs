@@ -0,0 +1,277 @@
+use wasm_bindgen::JsValue;
+use web_sys::{HtmlImageElement, ImageBitmap, WebGl2RenderingContext, WebGlTexture};
+
+use crate::{error::RenderError, gl::Gl};
+
+/// `HALF_FLOAT_OES` of `OES_texture_half_float`, which differs from the
+/// WebGL2 `HALF_FLOAT`
+const HALF_FLOAT_OES: u32 = 0x8D61;
+
+/// WebGL 2D texture wrapper
+///
+/// New textures use linear filtering and edge clamping without mipmaps, which
+/// works for any size on WebGL1 as well. Images are uploaded as RGBA with
+/// premultiplied alpha, so filtering doesn't bleed the color of transparent
+/// pixels into their neighbors.
+pub struct GpuTexture {
+    texture: WebGlTexture,
+    width: u32,
+    height: u32,
+}
+
+impl GpuTexture {
+    /// Creates an empty texture
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureCreationFailed`] if texture creation fails
+    pub fn new(gl: &Gl) -> Result<Self, RenderError> {
+        let texture = gl
+            .create_texture()
+            .ok_or(RenderError::TextureCreationFailed)?;
+
+        let texture = Self {
+            texture,
+            width: 0,
+            height: 0,
+        };
+        texture.set_filter(
+            gl,
+            WebGl2RenderingContext::LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        texture.set_wrap(
+            gl,
+            WebGl2RenderingContext::CLAMP_TO_EDGE,
+            WebGl2RenderingContext::CLAMP_TO_EDGE,
+        );
+
+        Ok(texture)
+    }
+
+    /// Frees the texture's GPU memory; the texture must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        gl.delete_texture(Some(&self.texture));
+    }
+
+    /// Returns the underlying WebGL texture
+    #[must_use]
+    pub const fn raw(&self) -> &WebGlTexture {
+        &self.texture
+    }
+
+    /// Returns the width in pixels
+    #[must_use]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height in pixels
+    #[must_use]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Reallocates the texture storage, uploading `pixels` if given
+    ///
+    /// `pixels` holds tightly packed RGBA bytes, row by row.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if allocation fails
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn upload_pixels(
+        &mut self,
+        gl: &Gl,
+        width: u32,
+        height: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), RenderError> {
+        self.bind_to_target(gl);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            width as i32,
+            height as i32,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            pixels,
+        )
+        .map_err(upload_error)?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Reallocates the texture storage as uninitialized 16-bit float RGBA
+    ///
+    /// Needs `EXT_color_buffer_float` on WebGL2 or `OES_texture_half_float`
+    /// on WebGL1 to be enabled first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if allocation fails
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn allocate_half_float(
+        &mut self,
+        gl: &Gl,
+        width: u32,
+        height: u32,
+    ) -> Result<(), RenderError> {
+        let (internal_format, kind) = if gl.is_webgl2() {
+            (
+                WebGl2RenderingContext::RGBA16F,
+                WebGl2RenderingContext::HALF_FLOAT,
+            )
+        } else {
+            (WebGl2RenderingContext::RGBA, HALF_FLOAT_OES)
+        };
+
+        self.bind_to_target(gl);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            internal_format as i32,
+            width as i32,
+            height as i32,
+            0,
+            WebGl2RenderingContext::RGBA,
+            kind,
+            None,
+        )
+        .map_err(upload_error)?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Uploads a decoded `ImageBitmap`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if the browser rejects the
+    /// image
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn upload_image_bitmap(
+        &mut self,
+        gl: &Gl,
+        bitmap: &ImageBitmap,
+    ) -> Result<(), RenderError> {
+        self.bind_to_target(gl);
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 1);
+        let result = gl.tex_image_2d_with_u32_and_u32_and_image_bitmap(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            bitmap,
+        );
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        result.map_err(upload_error)?;
+
+        self.width = bitmap.width();
+        self.height = bitmap.height();
+        Ok(())
+    }
+
+    /// Uploads a loaded `<img>` element
+    ///
+    /// Cross-origin images need CORS approval, or the upload fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::TextureUploadFailed`] if the browser rejects the
+    /// image
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn upload_image(&mut self, gl: &Gl, image: &HtmlImageElement) -> Result<(), RenderError> {
+        self.bind_to_target(gl);
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 1);
+        let result = gl.tex_image_2d_with_u32_and_u32_and_html_image_element(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            image,
+        );
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        result.map_err(upload_error)?;
+
+        self.width = image.natural_width();
+        self.height = image.natural_height();
+        Ok(())
+    }
+
+    /// Sets the minification and magnification filters
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn set_filter(&self, gl: &Gl, min: u32, mag: u32) {
+        self.bind_to_target(gl);
+        gl.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+            min as i32,
+        );
+        gl.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+            mag as i32,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+    }
+
+    /// Sets the horizontal and vertical wrap modes
+    ///
+    /// WebGL1 only supports `CLAMP_TO_EDGE` for non-power-of-two sizes.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn set_wrap(&self, gl: &Gl, s: u32, t: u32) {
+        self.bind_to_target(gl);
+        gl.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::TEXTURE_WRAP_S,
+            s as i32,
+        );
+        gl.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::TEXTURE_WRAP_T,
+            t as i32,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+    }
+
+    /// Generates mipmaps from the uploaded image
+    ///
+    /// WebGL1 requires power-of-two sizes for mipmaps.
+    pub fn generate_mipmaps(&self, gl: &Gl) {
+        self.bind_to_target(gl);
+        gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+    }
+
+    /// Binds the texture to texture unit `unit`
+    ///
+    /// Leaves `unit` as the active texture unit.
+    pub fn bind(&self, gl: &Gl, unit: u32) {
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+        self.bind_to_target(gl);
+    }
+
+    fn bind_to_target(&self, gl: &Gl) {
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn upload_error(error: JsValue) -> RenderError {
+    RenderError::TextureUploadFailed {
+        reason: error.as_string().unwrap_or_else(|| format!("{error:?}")),
+    }
+}
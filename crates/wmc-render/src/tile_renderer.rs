@@ -0,0 +1,112 @@
+use web_sys::{WebGl2RenderingContext, WebGlUniformLocation};
+
+use crate::{
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    program::ShaderProgram,
+    shaders,
+    texture::GpuTexture,
+    vertex_array::{VertexArray, VertexAttribute},
+};
+
+/// Corners of the unit quad as a triangle strip
+const UNIT_QUAD: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+
+/// WebGL renderer for axis-aligned textured rectangles such as map tiles
+///
+/// Textures are expected to hold premultiplied colors, as uploaded by
+/// [`GpuTexture`].
+pub struct TileRenderer {
+    program: ShaderProgram,
+    quad: VertexArray,
+    u_rect: WebGlUniformLocation,
+    u_opacity: WebGlUniformLocation,
+}
+
+impl TileRenderer {
+    /// Creates a tile renderer
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation or buffer creation fails
+    pub fn new(ctx: &RenderContext) -> Result<Self, RenderError> {
+        let gl = ctx.gl();
+
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::TILE_VERTEX, shaders::TILE_FRAGMENT)
+        } else {
+            (shaders::TILE_VERTEX_ES100, shaders::TILE_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
+            gl,
+            vertex_source,
+            fragment_source,
+            shaders::TILE_ATTRIBUTES,
+        )?;
+
+        let u_rect = program.get_uniform_location(gl, "u_rect")?;
+        let u_opacity = program.get_uniform_location(gl, "u_opacity")?;
+        let u_texture = program.get_uniform_location(gl, "u_texture")?;
+        program.use_program(gl);
+        gl.uniform1i(Some(&u_texture), 0);
+
+        let buffer = GpuBuffer::new(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            WebGl2RenderingContext::STATIC_DRAW,
+        )?;
+
+        #[allow(unsafe_code)]
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                UNIT_QUAD.as_ptr().cast::<u8>(),
+                std::mem::size_of_val(&UNIT_QUAD),
+            )
+        };
+        buffer.upload_data(gl, bytes);
+
+        let quad = VertexArray::new(gl, buffer, 0, &[VertexAttribute::float(0, 2, 0)])?;
+
+        Ok(Self {
+            program,
+            quad,
+            u_rect,
+            u_opacity,
+        })
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.quad.delete(gl);
+    }
+
+    /// Draws textured rectangles
+    ///
+    /// Each item is a texture with its `[left, top, right, bottom]` rectangle
+    /// in drawing buffer pixels and an opacity in `0.0..=1.0`.
+    pub fn draw<'a>(
+        &self,
+        ctx: &RenderContext,
+        tiles: impl IntoIterator<Item = (&'a GpuTexture, [f32; 4], f32)>,
+    ) {
+        let gl = ctx.gl();
+
+        self.program.use_with_camera(gl, &ctx.camera());
+        ctx.apply_blend(true);
+        self.quad.bind(gl);
+
+        for (texture, [left, top, right, bottom], opacity) in tiles {
+            texture.bind(gl, 0);
+            gl.uniform4f(Some(&self.u_rect), left, top, right, bottom);
+            gl.uniform1f(Some(&self.u_opacity), opacity);
+            gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4);
+        }
+
+        self.quad.unbind(gl);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        ctx.apply_blend(false);
+    }
+}
@@ -0,0 +1,272 @@
+use web_sys::{WebGl2RenderingContext, WebGlVertexArrayObject};
+
+use crate::{buffer::GpuBuffer, error::RenderError, gl::Gl};
+
+/// Vertex attribute read from an interleaved buffer
+///
+/// The stride is shared by all attributes of a buffer and the divisor by all
+/// attributes of a stream, so both are given to [`VertexArray`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    /// Attribute location
+    pub location: u32,
+    /// Number of components
+    pub size: i32,
+    /// Component type, e.g. `FLOAT` or `UNSIGNED_BYTE`
+    pub kind: u32,
+    /// Map integer components to `0.0..=1.0` (or `-1.0..=1.0` if signed)
+    pub normalized: bool,
+    /// Byte offset within a vertex
+    pub offset: i32,
+}
+
+impl VertexAttribute {
+    /// Creates an attribute of `size` float components at byte `offset`
+    #[must_use]
+    pub const fn float(location: u32, size: i32, offset: i32) -> Self {
+        Self {
+            location,
+            size,
+            kind: WebGl2RenderingContext::FLOAT,
+            normalized: false,
+            offset,
+        }
+    }
+
+    /// Creates an attribute of `size` unsigned shorts at byte `offset`,
+    /// read as floats in `0.0..=1.0`, e.g. quantized positions
+    #[must_use]
+    pub const fn unorm16(location: u32, size: i32, offset: i32) -> Self {
+        Self {
+            location,
+            size,
+            kind: WebGl2RenderingContext::UNSIGNED_SHORT,
+            normalized: true,
+            offset,
+        }
+    }
+
+    /// Creates an attribute of `size` signed shorts at byte `offset`, read
+    /// as floats in `-1.0..=1.0`
+    #[must_use]
+    pub const fn snorm16(location: u32, size: i32, offset: i32) -> Self {
+        Self {
+            location,
+            size,
+            kind: WebGl2RenderingContext::SHORT,
+            normalized: true,
+            offset,
+        }
+    }
+
+    /// Creates an attribute of `size` unsigned shorts at byte `offset`,
+    /// read as floats of the same values, e.g. indices
+    #[must_use]
+    pub const fn uint16(location: u32, size: i32, offset: i32) -> Self {
+        Self {
+            location,
+            size,
+            kind: WebGl2RenderingContext::UNSIGNED_SHORT,
+            normalized: false,
+            offset,
+        }
+    }
+
+    /// Creates an attribute of `size` unsigned bytes at byte `offset`, read
+    /// as floats in `0.0..=1.0`, e.g. packed RGBA colors
+    #[must_use]
+    pub const fn unorm8(location: u32, size: i32, offset: i32) -> Self {
+        Self {
+            location,
+            size,
+            kind: WebGl2RenderingContext::UNSIGNED_BYTE,
+            normalized: true,
+            offset,
+        }
+    }
+}
+
+/// Buffer whose attributes advance once per instance
+struct InstanceStream {
+    buffer: GpuBuffer,
+    stride: i32,
+    attributes: Vec<VertexAttribute>,
+}
+
+/// Vertex buffer with its attribute layout
+///
+/// Uses a vertex array object where available. Without one (WebGL1 lacking
+/// `OES_vertex_array_object`) the attribute pointers are set up again on
+/// every [`VertexArray::bind`].
+///
+/// An optional second buffer holds per-instance attributes with a divisor of
+/// 1, for drawing with [`Gl::draw_arrays_instanced`].
+pub struct VertexArray {
+    vao: Option<WebGlVertexArrayObject>,
+    buffer: GpuBuffer,
+    stride: i32,
+    attributes: Vec<VertexAttribute>,
+    instances: Option<InstanceStream>,
+}
+
+impl VertexArray {
+    /// Creates a vertex array reading `attributes` from `buffer`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::VaoCreationFailed`] if vertex arrays are
+    /// supported but creating one fails
+    pub fn new(
+        gl: &Gl,
+        buffer: GpuBuffer,
+        stride: i32,
+        attributes: &[VertexAttribute],
+    ) -> Result<Self, RenderError> {
+        Self::build(gl, buffer, stride, attributes, None)
+    }
+
+    /// Creates a vertex array reading per-vertex `attributes` from `buffer`
+    /// and per-instance `instance_attributes` from `instances`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::InstancingUnsupported`] without instanced
+    /// drawing, and [`RenderError::VaoCreationFailed`] if vertex arrays are
+    /// supported but creating one fails
+    pub fn instanced(
+        gl: &Gl,
+        buffer: GpuBuffer,
+        stride: i32,
+        attributes: &[VertexAttribute],
+        instances: GpuBuffer,
+        instance_stride: i32,
+        instance_attributes: &[VertexAttribute],
+    ) -> Result<Self, RenderError> {
+        if !gl.supports_instancing() {
+            return Err(RenderError::InstancingUnsupported);
+        }
+
+        let instances = InstanceStream {
+            buffer: instances,
+            stride: instance_stride,
+            attributes: instance_attributes.to_vec(),
+        };
+        Self::build(gl, buffer, stride, attributes, Some(instances))
+    }
+
+    fn build(
+        gl: &Gl,
+        buffer: GpuBuffer,
+        stride: i32,
+        attributes: &[VertexAttribute],
+        instances: Option<InstanceStream>,
+    ) -> Result<Self, RenderError> {
+        let mut array = Self {
+            vao: None,
+            buffer,
+            stride,
+            attributes: attributes.to_vec(),
+            instances,
+        };
+
+        if gl.supports_vertex_arrays() {
+            let vao = gl
+                .create_vertex_array()
+                .ok_or(RenderError::VaoCreationFailed)?;
+
+            gl.bind_vertex_array(Some(&vao));
+            array.set_pointers(gl);
+            gl.bind_vertex_array(None);
+
+            array.vao = Some(vao);
+        }
+
+        Ok(array)
+    }
+
+    /// Returns the vertex buffer
+    #[must_use]
+    pub const fn buffer(&self) -> &GpuBuffer {
+        &self.buffer
+    }
+
+    /// Returns the per-instance buffer, if any
+    #[must_use]
+    pub fn instance_buffer(&self) -> Option<&GpuBuffer> {
+        self.instances.as_ref().map(|instances| &instances.buffer)
+    }
+
+    /// Deletes the vertex array object and its buffers; the vertex array must
+    /// not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        gl.delete_vertex_array(self.vao.as_ref());
+        self.buffer.delete(gl);
+        if let Some(instances) = &self.instances {
+            instances.buffer.delete(gl);
+        }
+    }
+
+    /// Binds the vertex array for drawing
+    pub fn bind(&self, gl: &Gl) {
+        match &self.vao {
+            Some(vao) => gl.bind_vertex_array(Some(vao)),
+            None => self.set_pointers(gl),
+        }
+    }
+
+    /// Unbinds the vertex array
+    pub fn unbind(&self, gl: &Gl) {
+        if self.vao.is_some() {
+            gl.bind_vertex_array(None);
+            return;
+        }
+
+        for attribute in &self.attributes {
+            gl.disable_vertex_attrib_array(attribute.location);
+        }
+        // Without a vertex array object divisors are global state
+        if let Some(instances) = &self.instances {
+            for attribute in &instances.attributes {
+                gl.vertex_attrib_divisor(attribute.location, 0);
+                gl.disable_vertex_attrib_array(attribute.location);
+            }
+        }
+    }
+
+    fn set_pointers(&self, gl: &Gl) {
+        set_pointers(gl, &self.buffer, self.stride, &self.attributes, 0);
+        if let Some(instances) = &self.instances {
+            set_pointers(
+                gl,
+                &instances.buffer,
+                instances.stride,
+                &instances.attributes,
+                1,
+            );
+        }
+    }
+}
+
+fn set_pointers(
+    gl: &Gl,
+    buffer: &GpuBuffer,
+    stride: i32,
+    attributes: &[VertexAttribute],
+    divisor: u32,
+) {
+    buffer.bind(gl);
+    for attribute in attributes {
+        gl.vertex_attrib_pointer_with_i32(
+            attribute.location,
+            attribute.size,
+            attribute.kind,
+            attribute.normalized,
+            stride,
+            attribute.offset,
+        );
+        gl.enable_vertex_attrib_array(attribute.location);
+        if divisor > 0 {
+            gl.vertex_attrib_divisor(attribute.location, divisor);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use web_sys::WebGlUniformLocation;
+
+use crate::{
+    error::RenderError,
+    gl::Gl,
+    post_process::{PostEffect, PostFrame, effect_program},
+    program::ShaderProgram,
+    shaders,
+};
+
+/// Vignette post effect darkening the frame toward its corners
+///
+/// Disabled while the strength is zero.
+pub struct VignetteEffect {
+    program: ShaderProgram,
+    u_strength: WebGlUniformLocation,
+    strength: f32,
+}
+
+impl VignetteEffect {
+    /// Creates a vignette effect with the given corner darkening
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError`] if shader compilation fails
+    pub fn new(gl: &Gl, strength: f32) -> Result<Self, RenderError> {
+        let program = effect_program(
+            gl,
+            shaders::VIGNETTE_FRAGMENT,
+            shaders::VIGNETTE_FRAGMENT_ES100,
+        )?;
+        let u_strength = program.get_uniform_location(gl, "u_strength")?;
+
+        Ok(Self {
+            program,
+            u_strength,
+            strength,
+        })
+    }
+
+    /// Returns the corner darkening
+    #[must_use]
+    pub const fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// Sets the corner darkening; zero disables the effect
+    pub const fn set_strength(&mut self, strength: f32) {
+        self.strength = strength;
+    }
+}
+
+impl PostEffect for VignetteEffect {
+    fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    fn apply_uniforms(&self, gl: &Gl, _frame: &PostFrame) {
+        gl.uniform1f(Some(&self.u_strength), self.strength);
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.strength > 0.0
+    }
+}
@@ -0,0 +1,310 @@
+//! WebGPU backend
+//!
+//! Requires building with `--cfg=web_sys_unstable_apis`, which the workspace
+//! `.cargo/config.toml` sets. Pipelines use WGSL modules with `vs_main` and
+//! `fs_main` entry points and a single vertex buffer of `vec2<f32>` positions.
+
+use js_sys::{JsNullable, Number, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    GpuAdapter, GpuBufferDescriptor, GpuCanvasAlphaMode, GpuCanvasConfiguration, GpuCanvasContext,
+    GpuColorTargetState, GpuDevice, GpuFragmentState, GpuLoadOp, GpuRenderPassColorAttachment,
+    GpuRenderPassDescriptor, GpuRenderPipeline, GpuRenderPipelineDescriptor,
+    GpuShaderModuleDescriptor, GpuStoreOp, GpuTextureFormat, GpuVertexAttribute,
+    GpuVertexBufferLayout, GpuVertexFormat, GpuVertexState, HtmlCanvasElement, gpu_buffer_usage,
+};
+
+use crate::{
+    backend::{BackendKind, RenderBackend, ShaderSources},
+    context::Surface,
+    error::RenderError,
+};
+
+/// Checks whether the browser exposes `navigator.gpu`
+///
+/// An available API can still fail to provide an adapter, so callers should
+/// fall back to WebGL2 when [`WebGpuContext::new`] fails as well.
+#[must_use]
+pub fn is_available() -> bool {
+    let global = js_sys::global();
+    Reflect::get(&global, &JsValue::from_str("navigator"))
+        .and_then(|navigator| Reflect::get(&navigator, &JsValue::from_str("gpu")))
+        .is_ok_and(|gpu| !gpu.is_undefined() && !gpu.is_null())
+}
+
+/// WebGPU vertex buffer
+pub struct WebGpuBuffer {
+    buffer: web_sys::GpuBuffer,
+    size: usize,
+}
+
+impl WebGpuBuffer {
+    /// Returns the underlying WebGPU buffer
+    #[must_use]
+    pub const fn buffer(&self) -> &web_sys::GpuBuffer {
+        &self.buffer
+    }
+
+    /// Returns the buffer size in bytes
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// WebGPU render pipeline drawing triangle lists
+pub struct WebGpuPipeline {
+    pipeline: GpuRenderPipeline,
+}
+
+impl WebGpuPipeline {
+    /// Returns the underlying render pipeline
+    #[must_use]
+    pub const fn pipeline(&self) -> &GpuRenderPipeline {
+        &self.pipeline
+    }
+}
+
+/// WebGPU rendering context with canvas state
+///
+/// Mirrors [`crate::RenderContext`]: the canvas is sized at its CSS size
+/// multiplied by the device pixel ratio.
+pub struct WebGpuContext {
+    device: GpuDevice,
+    context: GpuCanvasContext,
+    format: GpuTextureFormat,
+    surface: Surface,
+    css_width: u32,
+    css_height: u32,
+    width: u32,
+    height: u32,
+    pixel_ratio: f64,
+}
+
+impl WebGpuContext {
+    /// Requests an adapter and device and configures the canvas for WebGPU
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::WebGpuUnavailable`] if the browser has no
+    /// WebGPU support or no suitable adapter, and
+    /// [`RenderError::WebGpuDeviceFailed`] if device or canvas setup fails
+    #[allow(clippy::future_not_send)]
+    pub async fn new(canvas: &HtmlCanvasElement) -> Result<Self, RenderError> {
+        if !is_available() {
+            return Err(RenderError::WebGpuUnavailable);
+        }
+
+        let window = web_sys::window().ok_or(RenderError::WebGpuUnavailable)?;
+        let gpu = window.navigator().gpu();
+
+        let adapter = JsFuture::from(gpu.request_adapter())
+            .await
+            .map_err(device_failed)?;
+        if adapter.is_null() || adapter.is_undefined() {
+            return Err(RenderError::WebGpuUnavailable);
+        }
+        let adapter: GpuAdapter = adapter.unchecked_into();
+
+        let device: GpuDevice = JsFuture::from(adapter.request_device())
+            .await
+            .map_err(device_failed)?
+            .unchecked_into();
+
+        let context = canvas
+            .get_context("webgpu")
+            .map_err(device_failed)?
+            .ok_or(RenderError::WebGpuUnavailable)?
+            .dyn_into::<GpuCanvasContext>()
+            .map_err(|_| RenderError::WebGpuUnavailable)?;
+
+        let format = gpu.get_preferred_canvas_format();
+        let configuration = GpuCanvasConfiguration::new(&device, format);
+        configuration.set_alpha_mode(GpuCanvasAlphaMode::Premultiplied);
+        context.configure(&configuration).map_err(device_failed)?;
+
+        let (css_width, css_height) = match (
+            u32::try_from(canvas.client_width()),
+            u32::try_from(canvas.client_height()),
+        ) {
+            (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+            _ => (canvas.width(), canvas.height()),
+        };
+
+        let mut ctx = Self {
+            device,
+            context,
+            format,
+            surface: Surface::Element(canvas.clone()),
+            css_width,
+            css_height,
+            width: 0,
+            height: 0,
+            pixel_ratio: window.device_pixel_ratio(),
+        };
+        ctx.resize(css_width, css_height);
+
+        Ok(ctx)
+    }
+
+    /// Returns the WebGPU device
+    #[must_use]
+    pub const fn device(&self) -> &GpuDevice {
+        &self.device
+    }
+
+    /// Returns the device pixel ratio the canvas is sized for
+    #[must_use]
+    pub const fn pixel_ratio(&self) -> f64 {
+        self.pixel_ratio
+    }
+
+    /// Records and submits a render pass that clears the current frame and
+    /// draws each `(pipeline, buffer, vertex_count)` triangle list in order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::WebGpuDeviceFailed`] if the frame texture or
+    /// render pass cannot be acquired
+    pub fn draw(
+        &self,
+        clear: [f32; 4],
+        draws: &[(&WebGpuPipeline, &WebGpuBuffer, u32)],
+    ) -> Result<(), RenderError> {
+        let view = self
+            .context
+            .get_current_texture()
+            .and_then(|texture| texture.create_view())
+            .map_err(device_failed)?;
+
+        let attachment = GpuRenderPassColorAttachment::new_with_gpu_texture_view(
+            GpuLoadOp::Clear,
+            GpuStoreOp::Store,
+            &view,
+        );
+        let clear_value: Vec<Number> = clear.iter().map(|&c| Number::from(c)).collect();
+        attachment.set_clear_value(&clear_value);
+        let descriptor = GpuRenderPassDescriptor::new(&[JsNullable::wrap(attachment)]);
+
+        let encoder = self.device.create_command_encoder();
+        let pass = encoder
+            .begin_render_pass(&descriptor)
+            .map_err(device_failed)?;
+        for (pipeline, buffer, vertex_count) in draws {
+            pass.set_pipeline(&pipeline.pipeline);
+            pass.set_vertex_buffer(0, Some(&buffer.buffer));
+            pass.draw(*vertex_count);
+        }
+        pass.end();
+
+        self.device.queue().submit(&[encoder.finish()]);
+        Ok(())
+    }
+}
+
+impl RenderBackend for WebGpuContext {
+    type Buffer = WebGpuBuffer;
+    type Program = WebGpuPipeline;
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::WebGpu
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn resize(&mut self, css_width: u32, css_height: u32) {
+        self.css_width = css_width;
+        self.css_height = css_height;
+        self.width = (f64::from(css_width) * self.pixel_ratio).round() as u32;
+        self.height = (f64::from(css_height) * self.pixel_ratio).round() as u32;
+
+        self.surface.set_size(self.width, self.height);
+    }
+
+    fn clear(&self, color: [f32; 4]) {
+        // A failed clear only drops one frame; the next draw reports errors
+        let _ = self.draw(color, &[]);
+    }
+
+    fn create_vertex_buffer(&self, data: &[u8]) -> Result<Self::Buffer, RenderError> {
+        // WebGPU requires buffer sizes and writes to be multiples of four
+        let size = data.len().next_multiple_of(4).max(4);
+        let size_u32 =
+            u32::try_from(size).map_err(|_| RenderError::BufferAllocationFailed { size })?;
+
+        let descriptor = GpuBufferDescriptor::new(
+            size_u32,
+            gpu_buffer_usage::VERTEX | gpu_buffer_usage::COPY_DST,
+        );
+        let buffer = self
+            .device
+            .create_buffer(&descriptor)
+            .map_err(|_| RenderError::BufferAllocationFailed { size })?;
+
+        let mut buffer = WebGpuBuffer { buffer, size };
+        self.upload(&mut buffer, data)?;
+        Ok(buffer)
+    }
+
+    fn upload(&self, buffer: &mut Self::Buffer, data: &[u8]) -> Result<(), RenderError> {
+        if data.len() > buffer.size {
+            *buffer = self.create_vertex_buffer(data)?;
+            return Ok(());
+        }
+
+        let mut padded = data.to_vec();
+        padded.resize(data.len().next_multiple_of(4), 0);
+        self.device
+            .queue()
+            .write_buffer_with_u32_and_u8_slice(&buffer.buffer, 0, &padded)
+            .map_err(|_| RenderError::BufferAllocationFailed { size: padded.len() })
+    }
+
+    fn create_program(&self, sources: &ShaderSources<'_>) -> Result<Self::Program, RenderError> {
+        let code = sources
+            .wgsl
+            .ok_or_else(|| RenderError::ShaderCompilationFailed {
+                shader_type: "wgsl".to_string(),
+                log: "no WGSL source for the WebGPU backend".to_string(),
+            })?;
+        let module = self
+            .device
+            .create_shader_module(&GpuShaderModuleDescriptor::new(code));
+
+        let attribute = GpuVertexAttribute::new(GpuVertexFormat::Float32x2, 0, 0);
+        let layout = GpuVertexBufferLayout::new(8, &[attribute]);
+        let vertex = GpuVertexState::new(&module);
+        vertex.set_entry_point("vs_main");
+        vertex.set_buffers(&[JsNullable::wrap(layout)]);
+
+        let target = GpuColorTargetState::new(self.format);
+        let fragment = GpuFragmentState::new(&module, &[JsNullable::wrap(target)]);
+        fragment.set_entry_point("fs_main");
+
+        let descriptor = GpuRenderPipelineDescriptor::new_with_gpu_auto_layout_mode(
+            web_sys::GpuAutoLayoutMode::Auto,
+            &vertex,
+        );
+        descriptor.set_fragment(&fragment);
+
+        let pipeline = self
+            .device
+            .create_render_pipeline(&descriptor)
+            .map_err(|err| RenderError::ProgramLinkingFailed {
+                log: format!("{err:?}"),
+            })?;
+
+        Ok(WebGpuPipeline { pipeline })
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn device_failed(err: JsValue) -> RenderError {
+    RenderError::WebGpuDeviceFailed {
+        reason: format!("{err:?}"),
+    }
+}
@@ -1,21 +1,226 @@
+use std::cell::RefCell;
+
 use web_sys::WebGl2RenderingContext;
 use wmc_core::{
+    index::SpatialIndex,
     projection::{GeoCoord, Projection},
-    topology::{Geometry, WorldTopology},
+    simplify::LineDetail,
+    theme::LineStyle,
+    topology::WorldTopology,
 };
 
 use crate::{
-    buffer::GpuBuffer, context::RenderContext, error::RenderError, program::ShaderProgram,
+    buffer::GpuBuffer,
+    context::RenderContext,
+    error::RenderError,
+    gl::Gl,
+    line_mesh::{LINE_VERTEX_FLOATS, QUANTIZED_LINE_VERTEX_BYTES, extrude_polyline, quantize},
+    program::ShaderProgram,
+    scissor::ScissorRect,
+    shaders,
+    vertex_array::{VertexArray, VertexAttribute},
 };
 
+/// Vertices of one feature in the line mesh
+#[derive(Debug, Clone, Copy)]
+struct FeatureSpan {
+    first: i32,
+    count: i32,
+}
+
+/// Vertex ranges of the features of a line mesh by their projected bounds,
+/// for drawing the features reaching into a scissor box
+pub(crate) struct FeatureIndex {
+    /// Vertex range of every feature, in mesh order
+    features: Vec<FeatureSpan>,
+    /// Positions in `features` by projected bounds
+    index: SpatialIndex<usize>,
+    /// Positions of the features drawn into the last region, kept to reuse
+    /// its allocation
+    visible: RefCell<Vec<usize>>,
+}
+
+impl FeatureIndex {
+    /// Indexes features given as their first vertex and vertex count, in
+    /// mesh order, with their projected bounds
+    pub(crate) fn new(spans: impl IntoIterator<Item = ([i32; 2], [f32; 4])>) -> Self {
+        let mut features = Vec::new();
+        let mut index = SpatialIndex::new();
+        for ([first, count], bounds) in spans {
+            index.insert(bounds.map(f64::from), features.len());
+            features.push(FeatureSpan { first, count });
+        }
+
+        Self {
+            features,
+            index,
+            visible: RefCell::default(),
+        }
+    }
+
+    /// Draws the features that may reach into the screen `region` when
+    /// lines reach `reach` pixels past their centers, for a mesh stretched
+    /// by `stretch`
+    ///
+    /// Features are drawn in mesh order, one call per run of adjacent ones.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub(crate) fn draw_region(
+        &self,
+        ctx: &RenderContext,
+        region: ScissorRect,
+        reach: f32,
+        stretch: [f32; 2],
+    ) {
+        let reach = reach + ctx.pixel_ratio() as f32;
+        let (x0, y0) = (region.x as f32 - reach, region.y as f32 - reach);
+        let (x1, y1) = (
+            region.x as f32 + region.width as f32 + reach,
+            region.y as f32 + region.height as f32 + reach,
+        );
+        let (width, height) = (ctx.width() as f32, ctx.height() as f32);
+        let view = ctx.view();
+
+        let bounds = [[x0, y0], [x1, y0], [x0, y1], [x1, y1]]
+            .map(|corner| {
+                let [x, y] = view.to_map(width, height, corner);
+                [x / stretch[0], y / stretch[1]]
+            })
+            .iter()
+            .fold(
+                [
+                    f64::INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::NEG_INFINITY,
+                ],
+                |[min_x, min_y, max_x, max_y], &[x, y]| {
+                    let (x, y) = (f64::from(x), f64::from(y));
+                    [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                },
+            );
+
+        let mut visible = self.visible.borrow_mut();
+        visible.clear();
+        self.index
+            .query_each(bounds, |&position| visible.push(position));
+        visible.sort_unstable();
+
+        let gl = ctx.gl();
+        let mut run: Option<(i32, i32)> = None;
+        for span in visible
+            .iter()
+            .filter_map(|&position| self.features.get(position))
+        {
+            match &mut run {
+                Some((first, count)) if *first + *count == span.first => *count += span.count,
+                _ => {
+                    if let Some((first, count)) = run.replace((span.first, span.count)) {
+                        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, first, count);
+                    }
+                },
+            }
+        }
+        if let Some((first, count)) = run {
+            gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, first, count);
+        }
+    }
+}
+
+/// Projected line mesh of a topology, built apart from the GPU upload so
+/// it can be built off the main thread
+///
+/// Features without vertices have no range.
+#[derive(Debug, Clone, Default)]
+pub struct LineMesh {
+    /// Extruded vertices of [`LINE_VERTEX_FLOATS`] floats each
+    pub vertices: Vec<f32>,
+    /// First vertex and vertex count of every feature, in mesh order
+    pub ranges: Vec<[i32; 2]>,
+    /// Projected line centers of every feature in `ranges` as `[min_x,
+    /// min_y, max_x, max_y]`
+    pub bounds: Vec<[f32; 4]>,
+}
+
+impl LineMesh {
+    /// Projects and extrudes the lines of `topology`, dropping the vertices
+    /// less important than the tolerance paired with its [`LineDetail`]
+    ///
+    /// The tolerance is in Web Mercator pixels at zoom 0, see
+    /// [`LineDetail::tolerance`]. Without detail every vertex is kept.
+    #[must_use]
+    pub fn build(
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+        detail: Option<(&LineDetail, f64)>,
+    ) -> Self {
+        let estimated_size = topology.point_count() * 6 * LINE_VERTEX_FLOATS;
+        let mut mesh = Self {
+            vertices: Vec::with_capacity(estimated_size),
+            ranges: Vec::with_capacity(topology.features.len()),
+            bounds: Vec::with_capacity(topology.features.len()),
+        };
+        let mut scratch = Vec::new();
+        let mut index = 0;
+
+        for feature in &topology.features {
+            let start = mesh.vertices.len();
+            let mut bounds = [
+                f32::INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::NEG_INFINITY,
+            ];
+            for line in feature.geometry.lines() {
+                scratch.clear();
+                let project = |point| project(projection, point);
+                match detail {
+                    Some((detail, tolerance)) => {
+                        scratch.extend(detail.simplify(index, line, tolerance).map(project));
+                    },
+                    None => scratch.extend(line.iter().copied().map(project)),
+                }
+                add_line(&mut mesh.vertices, &scratch, &mut bounds);
+                index += 1;
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let (first, count) = (
+                (start / LINE_VERTEX_FLOATS) as i32,
+                ((mesh.vertices.len() - start) / LINE_VERTEX_FLOATS) as i32,
+            );
+            if count > 0 {
+                mesh.ranges.push([first, count]);
+                mesh.bounds.push(bounds);
+            }
+        }
+
+        mesh
+    }
+}
+
 /// WebGL renderer for world map topology
+///
+/// Lines are drawn as extruded triangles rather than `GL_LINES`, so the line
+/// width is honored on every WebGL implementation, and their edges are
+/// feathered in the fragment shader so they stay smooth without MSAA. Dash
+/// patterns are laid out along each line from its start.
 pub struct WorldRenderer {
     program: ShaderProgram,
-    vao: web_sys::WebGlVertexArrayObject,
-    _vertex_buffer: GpuBuffer,
+    vertices: VertexArray,
     vertex_count: i32,
-    u_color: web_sys::WebGlUniformLocation,
-    u_resolution: web_sys::WebGlUniformLocation,
+    features: FeatureIndex,
+    /// Maps quantized positions back to the pixels of the projection
+    dequantize: [f32; 9],
+    /// Drawing buffer size the mesh was projected for
+    size: [f32; 2],
+    /// Current drawing buffer size over `size`
+    stretch: [f32; 2],
+    u_dequantize: web_sys::WebGlUniformLocation,
+    u_stretch: web_sys::WebGlUniformLocation,
+    u_color: Option<web_sys::WebGlUniformLocation>,
+    u_line_width: web_sys::WebGlUniformLocation,
+    u_aa_width: web_sys::WebGlUniformLocation,
+    u_dash: Option<web_sys::WebGlUniformLocation>,
 }
 
 impl WorldRenderer {
@@ -28,21 +233,102 @@ impl WorldRenderer {
         ctx: &RenderContext,
         topology: &WorldTopology,
         projection: &dyn Projection,
+    ) -> Result<Self, RenderError> {
+        Self::with_fragment(ctx, topology, projection, None)
+    }
+
+    /// Creates a world renderer drawing with a custom fragment shader, or
+    /// the built-in one when `fragment` is `None`
+    ///
+    /// The shader must match the context: GLSL ES 3.00 on WebGL2, GLSL ES
+    /// 1.00 on WebGL1. It receives `v_distance`, the signed distance from
+    /// the line center in pixels, and `v_along`, the distance along the line
+    /// in pixels, and may read the `u_color`, `u_line_width`, `u_aa_width`
+    /// and `u_dash` uniforms of [`shaders::LINE_FRAGMENT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::ShaderCompilationFailed`] if `fragment` doesn't
+    /// compile, and [`RenderError`] if linking or buffer creation fails
+    pub fn with_fragment(
+        ctx: &RenderContext,
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+        fragment: Option<&str>,
+    ) -> Result<Self, RenderError> {
+        Self::with_detail(ctx, topology, projection, fragment, None)
+    }
+
+    /// Creates a world renderer like [`Self::with_fragment`], dropping the
+    /// vertices of `topology` less important than the tolerance paired with
+    /// its [`LineDetail`]
+    ///
+    /// The tolerance is in Web Mercator pixels at zoom 0, see
+    /// [`LineDetail::tolerance`]. Without detail every vertex is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::ShaderCompilationFailed`] if `fragment` doesn't
+    /// compile, and [`RenderError`] if linking or buffer creation fails
+    pub fn with_detail(
+        ctx: &RenderContext,
+        topology: &WorldTopology,
+        projection: &dyn Projection,
+        fragment: Option<&str>,
+        detail: Option<(&LineDetail, f64)>,
+    ) -> Result<Self, RenderError> {
+        Self::from_mesh(
+            ctx,
+            &LineMesh::build(topology, projection, detail),
+            [ctx.width(), ctx.height()],
+            fragment,
+        )
+    }
+
+    /// Creates a world renderer uploading a mesh built beforehand, drawn
+    /// with a custom fragment shader unless `fragment` is `None`
+    ///
+    /// `size` is the drawing buffer size the mesh was projected for; it's
+    /// stretched to the context's size as by [`Self::resize`]. The mesh is
+    /// quantized to 16 bytes per vertex on upload, see [`quantize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RenderError::ShaderCompilationFailed`] if `fragment` doesn't
+    /// compile, and [`RenderError`] if linking or buffer creation fails
+    pub fn from_mesh(
+        ctx: &RenderContext,
+        mesh: &LineMesh,
+        size: [u32; 2],
+        fragment: Option<&str>,
     ) -> Result<Self, RenderError> {
         let gl = ctx.gl();
 
-        let program = ShaderProgram::new(
+        let (vertex_source, fragment_source) = if gl.is_webgl2() {
+            (shaders::LINE_VERTEX, shaders::LINE_FRAGMENT)
+        } else {
+            (shaders::LINE_VERTEX_ES100, shaders::LINE_FRAGMENT_ES100)
+        };
+        let program = ShaderProgram::with_attributes(
             gl,
-            crate::shaders::WORLD_VERTEX,
-            crate::shaders::WORLD_FRAGMENT,
+            vertex_source,
+            fragment.unwrap_or(fragment_source),
+            shaders::LINE_ATTRIBUTES,
         )?;
 
-        let u_color = program.get_uniform_location(gl, "u_color")?;
-        let u_resolution = program.get_uniform_location(gl, "u_resolution")?;
+        // Custom fragment shaders need not use the color or dashes
+        let u_color = program.get_uniform_location(gl, "u_color").ok();
+        let u_dash = program.get_uniform_location(gl, "u_dash").ok();
+        let u_dequantize = program.get_uniform_location(gl, "u_dequantize")?;
+        let u_stretch = program.get_uniform_location(gl, "u_stretch")?;
+        let u_line_width = program.get_uniform_location(gl, "u_line_width")?;
+        let u_aa_width = program.get_uniform_location(gl, "u_aa_width")?;
 
-        let vertices = Self::build_vertices(topology, projection);
+        let vertices = &mesh.vertices;
+        let features =
+            FeatureIndex::new(mesh.ranges.iter().copied().zip(mesh.bounds.iter().copied()));
         #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-        let vertex_count = (vertices.len() / 2) as i32;
+        let vertex_count = (vertices.len() / LINE_VERTEX_FLOATS) as i32;
 
         let vertex_buffer = GpuBuffer::new(
             gl,
@@ -50,91 +336,138 @@ impl WorldRenderer {
             WebGl2RenderingContext::STATIC_DRAW,
         )?;
 
-        #[allow(unsafe_code)]
-        let vertices_bytes = unsafe {
-            std::slice::from_raw_parts(
-                vertices.as_ptr().cast::<u8>(),
-                vertices.len() * std::mem::size_of::<f32>(),
-            )
-        };
-
-        vertex_buffer.upload_data(gl, vertices_bytes);
-
-        let vao = gl
-            .create_vertex_array()
-            .ok_or(RenderError::VaoCreationFailed)?;
+        let quantized = quantize(vertices);
+        vertex_buffer.upload_data(gl, &quantized.bytes);
 
-        gl.bind_vertex_array(Some(&vao));
-
-        vertex_buffer.bind(gl);
-        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(0);
-
-        gl.bind_vertex_array(None);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let stride = QUANTIZED_LINE_VERTEX_BYTES as i32;
+        let vertices = VertexArray::new(
+            gl,
+            vertex_buffer,
+            stride,
+            &[
+                VertexAttribute::unorm16(0, 2, 0),
+                VertexAttribute::snorm16(1, 2, 4),
+                VertexAttribute::snorm16(2, 1, 8),
+                VertexAttribute::float(3, 1, 12),
+            ],
+        )?;
 
-        Ok(Self {
+        #[allow(clippy::cast_precision_loss)]
+        let size = size.map(|side| side.max(1) as f32);
+        let mut renderer = Self {
             program,
-            vao,
-            _vertex_buffer: vertex_buffer,
+            vertices,
             vertex_count,
+            features,
+            dequantize: quantized.dequantize,
+            size,
+            stretch: [1.0, 1.0],
+            u_dequantize,
+            u_stretch,
             u_color,
-            u_resolution,
-        })
+            u_line_width,
+            u_aa_width,
+            u_dash,
+        };
+        renderer.resize(ctx.width(), ctx.height());
+        Ok(renderer)
+    }
+
+    /// Stretches the mesh over a `width` x `height` drawing buffer
+    ///
+    /// Only the transform uniforms change; the mesh isn't reprojected or
+    /// uploaded again. Positions follow the projection exactly, since the
+    /// projection scales with the buffer. Extrusions are turned to stay
+    /// normal to their segments, so lines keep their width, while miter
+    /// joins of a mesh stretched to another aspect ratio are approximate.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.stretch = [width as f32 / self.size[0], height as f32 / self.size[1]];
+    }
+
+    /// Frees the renderer's GPU resources; it must not be used afterwards
+    pub fn delete(&self, gl: &Gl) {
+        self.program.delete(gl);
+        self.vertices.delete(gl);
     }
 
     /// Draws the world map
-    pub fn draw(&self, ctx: &RenderContext, color: [f32; 4], line_width: f32) {
+    ///
+    /// `line_width` and the lengths of `style` are in drawing buffer pixels.
+    /// While the context is scissored, only features whose bounds reach
+    /// into the scissor box are submitted.
+    pub fn draw(&self, ctx: &RenderContext, color: [f32; 4], line_width: f32, style: LineStyle) {
         let gl = ctx.gl();
 
-        self.program.use_program(gl);
+        self.program.use_with_camera(gl, &ctx.camera());
 
-        gl.uniform4f(Some(&self.u_color), color[0], color[1], color[2], color[3]);
-        #[allow(clippy::cast_precision_loss)]
-        {
-            gl.uniform2f(
-                Some(&self.u_resolution),
-                ctx.width() as f32,
-                ctx.height() as f32,
-            );
-        }
+        gl.uniform_matrix3fv_with_f32_array(
+            Some(&self.u_dequantize),
+            false,
+            &stretch_transform(self.dequantize, self.stretch),
+        );
+        gl.uniform2f(Some(&self.u_stretch), self.stretch[0], self.stretch[1]);
+        gl.uniform4f(
+            self.u_color.as_ref(),
+            color[0],
+            color[1],
+            color[2],
+            color[3],
+        );
+        gl.uniform1f(Some(&self.u_line_width), line_width);
+        #[allow(clippy::cast_possible_truncation)]
+        gl.uniform1f(Some(&self.u_aa_width), ctx.pixel_ratio() as f32);
+        let [dash, gap, round] = dash_pattern(style);
+        gl.uniform3f(self.u_dash.as_ref(), dash, gap, round);
 
-        gl.line_width(line_width);
-
-        gl.bind_vertex_array(Some(&self.vao));
-        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, self.vertex_count);
-        gl.bind_vertex_array(None);
+        self.vertices.bind(gl);
+        match ctx.scissor() {
+            Some(region) => self
+                .features
+                .draw_region(ctx, region, line_width, self.stretch),
+            None => gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, self.vertex_count),
+        }
+        self.vertices.unbind(gl);
     }
+}
 
-    fn build_vertices(topology: &WorldTopology, projection: &dyn Projection) -> Vec<f32> {
-        let estimated_size = topology.point_count() * 2 * 2;
-        let mut vertices = Vec::with_capacity(estimated_size);
-
-        for feature in &topology.features {
-            match &feature.geometry {
-                Geometry::LineString(points) => {
-                    Self::add_line(&mut vertices, points, projection);
-                },
-                Geometry::MultiLineString(lines) => {
-                    for line in lines {
-                        Self::add_line(&mut vertices, line, projection);
-                    }
-                },
-            }
-        }
+/// Returns the columns of a dequantization matrix scaled from projected
+/// pixels to a drawing buffer `stretch` times larger
+pub(crate) fn stretch_transform(dequantize: [f32; 9], stretch: [f32; 2]) -> [f32; 9] {
+    let mut transform = dequantize;
+    for column in transform.as_chunks_mut::<3>().0 {
+        column[0] *= stretch[0];
+        column[1] *= stretch[1];
+    }
+    transform
+}
 
-        vertices
+/// Returns the dash length, the gap, and 1.0 for round caps of `style`, as
+/// read by [`shaders::DASH_CHUNK`]
+pub(crate) const fn dash_pattern(style: LineStyle) -> [f32; 3] {
+    match style {
+        LineStyle::Solid => [0.0, 0.0, 0.0],
+        LineStyle::Dashed { dash, gap } => [dash, gap, 0.0],
+        LineStyle::Dotted { gap } => [0.0, gap, 1.0],
     }
+}
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn add_line(vertices: &mut Vec<f32>, points: &[GeoCoord], projection: &dyn Projection) {
-        for i in 0..points.len().saturating_sub(1) {
-            let p1 = projection.project(points[i]);
-            let p2 = projection.project(points[i + 1]);
+#[allow(clippy::cast_possible_truncation)]
+fn project(projection: &dyn Projection, point: GeoCoord) -> [f32; 2] {
+    let p = projection.project(point);
+    [p.x as f32, p.y as f32]
+}
 
-            vertices.push(p1.x as f32);
-            vertices.push(p1.y as f32);
-            vertices.push(p2.x as f32);
-            vertices.push(p2.y as f32);
-        }
+fn add_line(vertices: &mut Vec<f32>, points: &[[f32; 2]], bounds: &mut [f32; 4]) {
+    for &[x, y] in points {
+        *bounds = [
+            bounds[0].min(x),
+            bounds[1].min(y),
+            bounds[2].max(x),
+            bounds[3].max(y),
+        ];
     }
+
+    extrude_polyline(vertices, points);
 }
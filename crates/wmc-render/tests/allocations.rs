@@ -0,0 +1,130 @@
+//! Allocation regression check for the per-frame paths
+//!
+//! Installs [`CountingAllocator`] and runs steady frames on the CPU side of
+//! the frame path: rebuilding marker instances into reused buffers, culling
+//! them to the view, querying the feature index for a damaged region,
+//! updating the camera and drawing on the headless backend. Any allocation
+//! in those frames fails the test. Work done only in the browser, such as
+//! the GL calls themselves, isn't covered.
+
+use std::{alloc::System, error::Error};
+
+use wmc_core::{
+    index::SpatialIndex,
+    marker::{Marker, MarkerId},
+    marker_buffer::{InstanceBuilder, MarkerBuffer},
+    projection::{MercatorProjection, Projection},
+    theme::Theme,
+};
+use wmc_render::{
+    CameraUniforms, CountingAllocator, HeadlessContext, View, stats::allocation_count,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator<System> = CountingAllocator(System);
+
+/// Side of the drawing buffer in pixels
+const SIZE: u32 = 64;
+
+/// Frames counted after the warm-up frame
+const FRAMES: u32 = 10;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+/// Buffers a marker layer keeps between frames
+struct Scratch {
+    instances: MarkerBuffer,
+    uploaded: MarkerBuffer,
+    visible: Vec<usize>,
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn frame(
+    ctx: &HeadlessContext,
+    markers: &[Marker],
+    builder: &InstanceBuilder,
+    index: &SpatialIndex<usize>,
+    scratch: &mut Scratch,
+    time: f32,
+) -> TestResult {
+    let projection = MercatorProjection::new(f64::from(SIZE), f64::from(SIZE));
+    let view = View {
+        zoom: 0.5,
+        rotation: time,
+        ..View::default()
+    };
+    let size = SIZE as f32;
+
+    scratch.instances.reset(markers.len());
+    for marker in markers {
+        let point = projection.project(marker.coord);
+        scratch
+            .instances
+            .push(builder.build(marker, point.x as f32, point.y as f32))?;
+    }
+
+    let [min_x, min_y, max_x, max_y] = view.to_map_bounds(size, size, [0.0, 0.0, size, size]);
+    scratch.uploaded.reset(scratch.instances.len());
+    for instance in (0..scratch.instances.len()).filter_map(|i| scratch.instances.get(i)) {
+        if (min_x..=max_x).contains(&instance.x) && (min_y..=max_y).contains(&instance.y) {
+            scratch.uploaded.push(*instance)?;
+        }
+    }
+
+    scratch.visible.clear();
+    index.query_each([0.0, 0.0, 32.0, 32.0], |&position| {
+        scratch.visible.push(position);
+    });
+    scratch.visible.sort_unstable();
+
+    let camera = CameraUniforms::with_view(SIZE, SIZE, time, 1.0, &view);
+    ctx.draw_markers(&scratch.uploaded, 3.0 * camera.pixel_ratio, camera.time);
+    Ok(())
+}
+
+#[test]
+fn steady_frames_allocate_nothing() -> TestResult {
+    let theme = Theme::dark_minimal();
+    let builder = InstanceBuilder::new(theme.markers);
+    let markers = (0..100u32)
+        .map(|i| {
+            let lat = f64::from(i % 10).mul_add(12.0, -60.0);
+            let lon = f64::from(i / 10).mul_add(30.0, -150.0);
+            Marker::new(MarkerId::Number(u64::from(i)), lat, lon)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut index = SpatialIndex::new();
+    for position in 0..50 {
+        let corner = f64::from(u32::try_from(position)?);
+        index.insert([corner, corner, corner + 4.0, corner + 4.0], position);
+    }
+    let ctx = HeadlessContext::new(SIZE, SIZE);
+    let mut scratch = Scratch {
+        instances: MarkerBuffer::new(0),
+        uploaded: MarkerBuffer::new(0),
+        visible: Vec::new(),
+    };
+
+    // The first frame sizes the scratch buffers
+    frame(&ctx, &markers, &builder, &index, &mut scratch, 0.0)?;
+
+    let before = allocation_count().ok_or("allocator isn't counting")?;
+    for step in 1..=FRAMES {
+        #[allow(clippy::cast_precision_loss)]
+        frame(
+            &ctx,
+            &markers,
+            &builder,
+            &index,
+            &mut scratch,
+            step as f32 * 0.1,
+        )?;
+    }
+    let allocations = allocation_count().ok_or("allocator isn't counting")? - before;
+
+    assert_eq!(
+        allocations, 0,
+        "{FRAMES} steady frames allocated {allocations} times"
+    );
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Golden-image tests rendering known scenes on the headless backend
+//!
+//! Each scene is compared against `tests/goldens/<name>.png` with a
+//! perceptual threshold. Scenes are filled by the CPU rasterizer of
+//! [`HeadlessContext`]: no shader runs, so only geometry, styling and the
+//! math shared with the shaders are covered, and shader regressions can't
+//! be caught here. Set `WMC_UPDATE_SNAPSHOTS=1` to write the goldens after
+//! an intended change or for a new scene; a missing golden fails otherwise.
+
+use std::{error::Error, path::PathBuf};
+
+use wmc_core::{
+    marker::{Color, Marker, MarkerId},
+    marker_buffer::{InstanceBuilder, MarkerBuffer},
+    projection::{MercatorProjection, Projection},
+    theme::Theme,
+    topology::WorldTopology,
+};
+use wmc_render::{
+    HeadlessContext, RenderBackend, SnapshotDiff,
+    snapshot::{decode_png, encode_png},
+    world_renderer::LineMesh,
+};
+
+/// Side of the rendered scenes in pixels
+const SIZE: u32 = 128;
+
+/// Share of the largest color distance a pixel may be off by
+const THRESHOLD: f32 = 0.1;
+
+/// Share of pixels allowed past the threshold
+const MAX_MISMATCH: f64 = 0.001;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const fn rgba(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+fn projection() -> MercatorProjection {
+    MercatorProjection::new(f64::from(SIZE), f64::from(SIZE))
+}
+
+fn draw_world(ctx: &HeadlessContext, theme: &Theme) -> TestResult {
+    let topology =
+        WorldTopology::from_geojson(include_str!("../../../assets/world-110m.geojson"))?;
+    let mesh = LineMesh::build(&topology, &projection(), None);
+    ctx.draw_lines(
+        &mesh.vertices,
+        rgba(theme.coastlines.effective_color()),
+        theme.coastlines.width,
+    );
+    Ok(())
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn draw_markers(ctx: &HeadlessContext, theme: &Theme) -> TestResult {
+    let places = [
+        ("berlin", 52.52, 13.405, 1.0),
+        ("rio", -22.9068, -43.1729, 0.6),
+        ("tokyo", 35.6762, 139.6503, 0.3),
+        ("cape-town", -33.9249, 18.4241, 0.8),
+    ];
+    let builder = InstanceBuilder::new(theme.markers);
+    let mut buffer = MarkerBuffer::new(places.len());
+    for (id, lat, lon, intensity) in places {
+        let mut marker = Marker::new(MarkerId::String(id.to_string()), lat, lon)?;
+        marker.intensity = intensity;
+        let point = projection().project(marker.coord);
+        buffer.push(builder.build(&marker, point.x as f32, point.y as f32))?;
+    }
+
+    ctx.draw_markers(&buffer, 4.0, 0.0);
+    Ok(())
+}
+
+/// Compares the scene against its golden, writing the golden instead when
+/// it's missing or updates are asked for
+fn assert_snapshot(name: &str, ctx: &HeadlessContext) -> TestResult {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/goldens")
+        .join(format!("{name}.png"));
+    let (width, height) = ctx.size();
+
+    if std::env::var_os("WMC_UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, encode_png(width, height, &ctx.pixels()))?;
+        return Ok(());
+    }
+
+    let golden = std::fs::read(&path).map_err(|e| {
+        format!(
+            "{name}: no golden at {} ({e}), run with WMC_UPDATE_SNAPSHOTS=1 to write it",
+            path.display()
+        )
+    })?;
+    let (golden_width, golden_height, golden) = decode_png(&golden)?;
+    assert_eq!(
+        (golden_width, golden_height),
+        (width, height),
+        "{name}: size differs from the golden"
+    );
+    let diff = SnapshotDiff::compare(&ctx.pixels(), &golden, THRESHOLD);
+    assert!(
+        diff.ratio() <= MAX_MISMATCH,
+        "{name}: {} of {} pixels differ from {}",
+        diff.mismatched,
+        diff.total,
+        path.display()
+    );
+    Ok(())
+}
+
+#[test]
+fn world_dark() -> TestResult {
+    let theme = Theme::dark_minimal();
+    let ctx = HeadlessContext::new(SIZE, SIZE);
+    ctx.clear(rgba(theme.background));
+    draw_world(&ctx, &theme)?;
+
+    assert_snapshot("world_dark", &ctx)
+}
+
+#[test]
+fn world_markers_light() -> TestResult {
+    let theme = Theme::light_minimal();
+    let ctx = HeadlessContext::new(SIZE, SIZE);
+    ctx.clear(rgba(theme.background));
+    draw_world(&ctx, &theme)?;
+    draw_markers(&ctx, &theme)?;
+
+    assert_snapshot("world_markers_light", &ctx)
+}
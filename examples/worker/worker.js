@@ -0,0 +1,23 @@
+import init, { WorldMap } from './pkg/wmc_component.js';
+
+let map = null;
+const pending = [];
+
+self.onmessage = async (event) => {
+    if (typeof event.data === 'string') {
+        // Protocol messages may arrive before initialization finishes
+        if (map) {
+            map.handle_message(event.data);
+        } else {
+            pending.push(event.data);
+        }
+        return;
+    }
+
+    const { canvas, pixelRatio } = event.data;
+    await init();
+
+    map = WorldMap.from_offscreen(canvas, pixelRatio);
+    pending.splice(0).forEach((message) => map.handle_message(message));
+    map.start();
+};